@@ -0,0 +1,125 @@
+//! Latency benchmarks of screenshots and actions.
+//!
+//! `screenshot_encoding` times the CPU work every state response does on a
+//! synthetic page screenshot: PNG encoding, base64 encoding and the
+//! perceptual hash used for change detection. `round_trip` times tool calls
+//! through the MCP interface, screenshot included, against the fixture site of
+//! the integration tests, once per backend listed in `MCP_BENCH_BACKENDS`
+//! (e.g. `cdp,webdriver`); it needs a browser and is skipped when the
+//! variable is unset.
+//!
+//! To time the configured browser itself, without criterion, run the server
+//! with `--bench-mode`.
+
+#[path = "../tests/common/mod.rs"]
+#[allow(dead_code)]
+mod common;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use common::{FixtureSite, TestServer};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use image::imageops::FilterType;
+use serde_json::json;
+use std::hint::black_box;
+use std::io::Cursor;
+use std::time::Duration;
+
+/// Viewport sizes of the synthetic screenshots.
+const SIZES: &[(u32, u32)] = &[(1280, 720), (1920, 1080), (2560, 1440)];
+
+/// A PNG resembling a page screenshot: flat background, text-like stripes
+/// and a photo-like gradient block.
+fn synthetic_screenshot(width: u32, height: u32) -> Vec<u8> {
+    let image = image::RgbImage::from_fn(width, height, |x, y| {
+        if x > width / 2 && y > height / 3 && y < height * 2 / 3 {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8])
+        } else if y % 24 < 12 && (x / 7 + y / 24) % 5 != 0 && x > 40 && x < width / 2 {
+            image::Rgb([30, 30, 30])
+        } else {
+            image::Rgb([255, 255, 255])
+        }
+    });
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    png
+}
+
+fn screenshot_encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("screenshot_encoding");
+    for &(width, height) in SIZES {
+        let size = format!("{}x{}", width, height);
+        let png = synthetic_screenshot(width, height);
+        let pixels = image::load_from_memory(&png).unwrap();
+
+        group.throughput(Throughput::Bytes(png.len() as u64));
+        group.bench_with_input(BenchmarkId::new("png", &size), &pixels, |b, pixels| {
+            b.iter(|| {
+                let mut out = Vec::new();
+                pixels
+                    .write_to(&mut Cursor::new(&mut out), image::ImageFormat::Png)
+                    .unwrap();
+                out
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("base64", &size), &png, |b, png| {
+            b.iter(|| BASE64.encode(black_box(png)))
+        });
+        // The server's change detection decodes the screenshot and shrinks it
+        // to a 17x16 grayscale grid
+        group.bench_with_input(BenchmarkId::new("fingerprint", &size), &png, |b, png| {
+            b.iter(|| {
+                image::load_from_memory(black_box(png))
+                    .unwrap()
+                    .grayscale()
+                    .resize_exact(17, 16, FilterType::Triangle)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn round_trip(c: &mut Criterion) {
+    let Ok(backends) = std::env::var("MCP_BENCH_BACKENDS") else {
+        eprintln!("Skipping round_trip: set MCP_BENCH_BACKENDS, e.g. to cdp,webdriver");
+        return;
+    };
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let site = runtime.block_on(FixtureSite::start());
+
+    let mut group = c.benchmark_group("round_trip");
+    group
+        .sample_size(20)
+        .measurement_time(Duration::from_secs(20));
+    for backend in backends.split(',').map(str::trim).filter(|b| !b.is_empty()) {
+        let server = runtime.block_on(async {
+            let server = TestServer::start(&site, &[("MCP_CONNECTION_MODE", backend)]).await;
+            server
+                .call_ok("navigate", json!({ "url": site.url("/pointer") }))
+                .await;
+            server
+        });
+
+        group.bench_function(BenchmarkId::new("current_state", backend), |b| {
+            b.iter(|| runtime.block_on(server.call_raw("current_state", json!({}))))
+        });
+        group.bench_function(BenchmarkId::new("hover_at", backend), |b| {
+            b.iter(|| runtime.block_on(server.call_raw("hover_at", json!({ "x": 100, "y": 40 }))))
+        });
+        group.bench_function(BenchmarkId::new("scroll_at", backend), |b| {
+            b.iter(|| {
+                runtime.block_on(server.call_raw(
+                    "scroll_at",
+                    json!({ "x": 650, "y": 120, "direction": "down", "magnitude": 100 }),
+                ))
+            })
+        });
+
+        runtime.block_on(server.stop());
+    }
+    group.finish();
+}
+
+criterion_group!(benches, screenshot_encoding, round_trip);
+criterion_main!(benches);
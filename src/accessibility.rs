@@ -0,0 +1,189 @@
+//! Accessibility audits with axe-core.
+//!
+//! `audit_accessibility` runs the axe-core rule engine in the page and returns
+//! its violations: the rule, its impact, the offending elements and a link to
+//! the rule's documentation. axe-core is evaluated in the page the first time a
+//! document is audited, outside the page's Content Security Policy.
+//!
+//! The script is read from `MCP_AXE_CORE_PATH` when set. Otherwise builds
+//! with the `bundled-axe-core` feature use the pinned release vendored in
+//! `vendor/axe-core/axe.min.js`. axe-core is never fetched at runtime: the
+//! script is injected into pages, so it only comes from files the operator
+//! controls.
+
+use anyhow::Context;
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::sync::OnceCell;
+
+/// axe-core release vendored for the `bundled-axe-core` feature.
+pub const AXE_CORE_VERSION: &str = "4.10.2";
+
+/// The vendored axe-core, compiled into the binary.
+#[cfg(feature = "bundled-axe-core")]
+const BUNDLED_AXE_CORE: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/vendor/axe-core/axe.min.js"
+));
+
+/// Maximum number of offending elements reported per rule.
+const MAX_NODES_PER_RULE: usize = 10;
+
+/// Source of axe-core, loaded once per process.
+static AXE_SOURCE: OnceCell<String> = OnceCell::const_new();
+
+/// JavaScript expression that is true when axe-core is loaded in the document.
+pub const PRESENT_SCRIPT: &str =
+    "typeof window.axe === 'object' && typeof window.axe.run === 'function'";
+
+/// A rule the page violates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Violation {
+    /// axe-core rule ID, e.g. "color-contrast".
+    pub rule: String,
+    /// Severity: "minor", "moderate", "serious" or "critical".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impact: Option<String>,
+    /// What the rule checks.
+    pub description: String,
+    /// Documentation of the rule and how to fix violations.
+    pub help_url: String,
+    /// Offending elements, at most 10.
+    pub nodes: Vec<ViolationNode>,
+    /// Number of offending elements, including those not listed.
+    pub node_count: usize,
+}
+
+/// An element violating a rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ViolationNode {
+    /// CSS selector of the element.
+    pub selector: String,
+    /// Start of the element's HTML.
+    pub html: String,
+    /// What to fix on this element.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_summary: Option<String>,
+}
+
+/// Result of an audit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct AccessibilityAudit {
+    /// Rules the page violates.
+    pub violations: Vec<Violation>,
+    /// Number of rules the page passes.
+    pub passes: usize,
+    /// Number of rules that need a human to review.
+    pub incomplete: usize,
+}
+
+/// JavaScript expression evaluating to a promise of the audit results of the
+/// document, or of the first element matching `selector`.
+pub fn run_script(selector: Option<&str>) -> String {
+    let selector = selector
+        .map(|s| serde_json::to_string(s).unwrap_or_default())
+        .unwrap_or_else(|| "null".to_string());
+    format!(
+        r#"(function(selector, maxNodes) {{
+    var context = selector ? document.querySelector(selector) : document;
+    if (!context) {{
+        return Promise.resolve({{ error: 'No element matches ' + selector }});
+    }}
+    return window.axe.run(context, {{ resultTypes: ['violations'] }}).then(function(results) {{
+        return {{
+            violations: results.violations.map(function(rule) {{
+                return {{
+                    rule: rule.id,
+                    impact: rule.impact || null,
+                    description: rule.help,
+                    help_url: rule.helpUrl,
+                    nodes: rule.nodes.slice(0, maxNodes).map(function(node) {{
+                        return {{
+                            selector: [].concat(node.target).join(' '),
+                            html: String(node.html).slice(0, 300),
+                            failure_summary: node.failureSummary || null
+                        }};
+                    }}),
+                    node_count: rule.nodes.length
+                }};
+            }}),
+            passes: results.passes.length,
+            incomplete: results.incomplete.length
+        }};
+    }}, function(e) {{
+        return {{ error: String(e && e.message || e) }};
+    }});
+}})({}, {})"#,
+        selector, MAX_NODES_PER_RULE
+    )
+}
+
+/// Read the value the [`run_script`] promise resolved to.
+pub fn parse_results(value: serde_json::Value) -> anyhow::Result<AccessibilityAudit> {
+    if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+        return Err(anyhow::anyhow!("{}", error));
+    }
+    serde_json::from_value(value).context("Unexpected axe-core results")
+}
+
+/// The axe-core source: from `path` when given, else the bundled copy.
+pub async fn axe_source(path: Option<&Path>) -> anyhow::Result<&'static str> {
+    AXE_SOURCE
+        .get_or_try_init(|| async {
+            match path {
+                Some(path) => tokio::fs::read_to_string(path)
+                    .await
+                    .with_context(|| format!("Failed to read axe-core from {:?}", path)),
+                None => bundled(),
+            }
+        })
+        .await
+        .map(String::as_str)
+}
+
+#[cfg(feature = "bundled-axe-core")]
+fn bundled() -> anyhow::Result<String> {
+    tracing::debug!("Using bundled axe-core {}", AXE_CORE_VERSION);
+    Ok(BUNDLED_AXE_CORE.to_string())
+}
+
+#[cfg(not(feature = "bundled-axe-core"))]
+fn bundled() -> anyhow::Result<String> {
+    Err(anyhow::anyhow!(
+        "axe-core {} is not bundled with this build; set MCP_AXE_CORE_PATH to a local axe.min.js or build with --features bundled-axe-core",
+        AXE_CORE_VERSION
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_results() {
+        let value = serde_json::json!({
+            "violations": [{
+                "rule": "image-alt",
+                "impact": "critical",
+                "description": "Images must have alternate text",
+                "help_url": "https://dequeuniversity.com/rules/axe/4.10/image-alt",
+                "nodes": [{
+                    "selector": "img.logo",
+                    "html": "<img class=\"logo\" src=\"logo.png\">",
+                    "failure_summary": null
+                }],
+                "node_count": 1
+            }],
+            "passes": 20,
+            "incomplete": 2
+        });
+        let audit = parse_results(value).unwrap();
+        assert_eq!(audit.violations[0].rule, "image-alt");
+        assert_eq!(audit.violations[0].nodes[0].selector, "img.logo");
+        assert_eq!(audit.violations[0].nodes[0].failure_summary, None);
+
+        let error = parse_results(serde_json::json!({ "error": "No element matches #main" }));
+        assert!(error.unwrap_err().to_string().contains("#main"));
+    }
+}
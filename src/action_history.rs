@@ -0,0 +1,199 @@
+//! History of recent tool calls.
+//!
+//! The server remembers the last few actions it performed so the model can look
+//! up what it already tried without the client replaying the conversation.
+
+use rmcp::model::{CallToolResult, JsonObject, RawContent};
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Longest string argument kept in a parameter summary, in characters.
+const MAX_ARGUMENT_CHARS: usize = 100;
+
+/// A tool call recorded in the history.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ActionRecord {
+    /// Position of the action since the server started, starting at 1.
+    pub sequence: u64,
+    /// Time the action finished (seconds since UNIX epoch).
+    pub timestamp: u64,
+    /// Name of the tool.
+    pub tool: String,
+    /// Arguments of the call, with long strings shortened.
+    pub params: serde_json::Value,
+    /// Whether the action succeeded.
+    pub success: bool,
+    /// URL of the page after the action, when the tool reports one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Error message of a failed action.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Bounded, most-recent-last list of actions.
+#[derive(Debug)]
+pub struct ActionHistory {
+    capacity: usize,
+    inner: Mutex<HistoryState>,
+}
+
+#[derive(Debug)]
+struct HistoryState {
+    records: VecDeque<ActionRecord>,
+    next_sequence: u64,
+}
+
+impl ActionHistory {
+    /// Create a history keeping the last `capacity` actions; 0 disables recording.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(HistoryState {
+                records: VecDeque::with_capacity(capacity),
+                next_sequence: 1,
+            }),
+        }
+    }
+
+    /// Record a finished tool call.
+    pub fn record(&self, tool: &str, arguments: Option<&JsonObject>, result: &CallToolResult) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let success = result.is_error != Some(true);
+        let response = response_json(result);
+        let url = response
+            .as_ref()
+            .and_then(|r| r.get("url"))
+            .and_then(|url| url.as_str())
+            .filter(|url| !url.is_empty())
+            .map(String::from);
+        let error = (!success)
+            .then(|| {
+                response
+                    .as_ref()
+                    .and_then(|r| r.get("message"))
+                    .and_then(|message| message.as_str())
+                    .map(String::from)
+            })
+            .flatten();
+        let params = arguments
+            .map(|args| summarize(&serde_json::Value::Object(args.clone())))
+            .unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+
+        let mut state = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let sequence = state.next_sequence;
+        state.next_sequence += 1;
+        if state.records.len() == self.capacity {
+            state.records.pop_front();
+        }
+        state.records.push_back(ActionRecord {
+            sequence,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            tool: tool.to_string(),
+            params,
+            success,
+            url,
+            error,
+        });
+    }
+
+    /// The most recent actions, oldest first, at most `limit` when given.
+    pub fn recent(&self, limit: Option<usize>) -> Vec<ActionRecord> {
+        let state = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let skip = limit.map_or(0, |limit| state.records.len().saturating_sub(limit));
+        state.records.iter().skip(skip).cloned().collect()
+    }
+}
+
+/// Parse the JSON text block that tools return alongside any screenshot.
+fn response_json(result: &CallToolResult) -> Option<serde_json::Value> {
+    result
+        .content
+        .iter()
+        .find_map(|content| match &content.raw {
+            RawContent::Text(text) => serde_json::from_str(&text.text).ok(),
+            _ => None,
+        })
+}
+
+/// Shorten long strings (typed text, data URLs) so the history stays compact.
+fn summarize(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(s) if s.chars().count() > MAX_ARGUMENT_CHARS => {
+            let truncated: String = s.chars().take(MAX_ARGUMENT_CHARS).collect();
+            serde_json::Value::String(format!("{}...", truncated))
+        }
+        serde_json::Value::Array(items) => items.iter().map(summarize).collect(),
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), summarize(value)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::Content;
+    use serde_json::json;
+
+    fn arguments(value: serde_json::Value) -> JsonObject {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_history_keeps_most_recent() {
+        let history = ActionHistory::new(2);
+        let ok = CallToolResult::success(vec![Content::text(
+            r#"{"url":"https://example.com/","success":true}"#,
+        )]);
+        let failed = CallToolResult::error(vec![Content::text(
+            r#"{"url":"","success":false,"message":"Element not found"}"#,
+        )]);
+
+        history.record("navigate", Some(&arguments(json!({"url": "a"}))), &ok);
+        history.record("click_at", Some(&arguments(json!({"x": 1, "y": 2}))), &ok);
+        history.record("click_at", None, &failed);
+
+        let records = history.recent(None);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].sequence, 2);
+        assert_eq!(records[0].url.as_deref(), Some("https://example.com/"));
+        assert_eq!(records[0].params, json!({"x": 1, "y": 2}));
+        assert!(!records[1].success);
+        assert_eq!(records[1].url, None);
+        assert_eq!(records[1].error.as_deref(), Some("Element not found"));
+
+        let last = history.recent(Some(1));
+        assert_eq!(last.len(), 1);
+        assert_eq!(last[0].sequence, 3);
+    }
+
+    #[test]
+    fn test_summarize_truncates_long_strings() {
+        let summary = summarize(&json!({"text": "x".repeat(500), "x": 10}));
+        assert_eq!(
+            summary["text"].as_str().unwrap().len(),
+            MAX_ARGUMENT_CHARS + 3
+        );
+        assert_eq!(summary["x"], json!(10));
+    }
+
+    #[test]
+    fn test_disabled_history_records_nothing() {
+        let history = ActionHistory::new(0);
+        history.record("navigate", None, &CallToolResult::success(vec![]));
+        assert!(history.recent(None).is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+//! Scheduling of browser actions.
+//!
+//! Actions that change browser state (clicks, typing, navigation) run one at a
+//! time per lane, in arrival order, so concurrent clients cannot interleave them.
+//! A lane is a tab in CDP mode, where tabs can be driven independently, and the
+//! whole browser in WebDriver mode. Actions that change the set of tabs run
+//! exclusively. Read-only queries bypass the queue and run immediately, so a
+//! metadata lookup is not stuck behind a slow navigation.
+
+use crate::config::tool_names;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{Mutex, OwnedMutexGuard, OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock};
+use tracing::debug;
+
+/// Scheduling priority of an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    /// Read-only query; runs immediately, concurrently with other actions.
+    Query,
+    /// State-changing action; serialized with other mutations in its lane in FIFO order.
+    Mutation,
+    /// Action affecting every tab (opening the browser, creating or switching tabs);
+    /// waits for running mutations and blocks new ones until it completes.
+    Exclusive,
+}
+
+/// Classify a tool by how it affects browser state.
+/// Unknown tools are treated as mutations.
+pub fn action_kind(tool: &str) -> ActionKind {
+    match tool {
+        tool_names::CURRENT_STATE
+        | tool_names::WAIT_5_SECONDS
+        | tool_names::LIST_FRAMES
+        | tool_names::DOM_SNAPSHOT
+        | tool_names::GROUNDING_SNAPSHOT
+        | tool_names::REMEMBER_ELEMENT
+        | tool_names::GET_PAGE_METADATA
+        | tool_names::GET_NOTIFICATIONS
+        | tool_names::GET_MEDIA
+        | tool_names::CAPTURE_VIDEO_FRAME
+        | tool_names::LIST_TARGETS
+        | tool_names::GET_BROWSER_STATS
+        | tool_names::GET_ACTION_HISTORY
+        | tool_names::AUDIT_ACCESSIBILITY
+        | tool_names::CHECK_LINKS
+        | tool_names::COMPARE_SCREENSHOT
+        | tool_names::GET_SCREENSHOT
+        | tool_names::OCR_SCREENSHOT
+        | tool_names::LIST_WORKSPACE_FILES
+        | tool_names::POLL_EVENTS
+        // Act on browsers of their own, which have their own queues
+        | tool_names::CREATE_BROWSER
+        | tool_names::DESTROY_BROWSER
+        // Waits its turn in the queue of the watch's browser itself
+        | tool_names::UNWATCH_PAGE => ActionKind::Query,
+        // list_tabs switches through every window in WebDriver mode to read titles,
+        // so it must not run while another action is using a window
+        tool_names::OPEN_WEB_BROWSER
+        | tool_names::NEW_TAB
+        | tool_names::CLOSE_TAB
+        | tool_names::SWITCH_TAB
+        | tool_names::LIST_TABS
+        | tool_names::ATTACH_TO_TAB
+        // Visit or open every tab of a workspace
+        | tool_names::SAVE_WORKSPACE
+        | tool_names::LOAD_WORKSPACE
+        | tool_names::WATCH_PAGE
+        // Its new_tab and close_tab presets open and close tabs
+        | tool_names::SHORTCUT
+        // Starts listening in every tab
+        | tool_names::SUBSCRIBE_EVENTS => ActionKind::Exclusive,
+        _ => ActionKind::Mutation,
+    }
+}
+
+/// Per-browser action queue.
+#[derive(Debug, Default)]
+pub struct ActionQueue {
+    /// Shared by mutations, held exclusively by exclusive actions. Tokio's lock
+    /// is fair, which keeps actions in arrival order.
+    browser_lock: Arc<RwLock<()>>,
+    /// One lock per lane, held for the duration of a mutation.
+    lanes: std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    /// Number of mutations running or waiting, for diagnostics.
+    pending_mutations: Arc<AtomicUsize>,
+}
+
+/// Permission to run an action; the queue is released when dropped.
+#[derive(Default)]
+pub struct ActionPermit {
+    _shared: Option<OwnedRwLockReadGuard<()>>,
+    _lane: Option<OwnedMutexGuard<()>>,
+    _exclusive: Option<OwnedRwLockWriteGuard<()>>,
+    _pending: Option<PendingMutation>,
+}
+
+/// Counts a mutation as pending until dropped, including while it is still waiting.
+struct PendingMutation(Arc<AtomicUsize>);
+
+impl Drop for PendingMutation {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl ActionQueue {
+    /// Create an empty queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait until an action of the given kind may run in `lane`.
+    pub async fn admit(&self, kind: ActionKind, lane: &str) -> ActionPermit {
+        if kind == ActionKind::Query {
+            return ActionPermit::default();
+        }
+
+        let ahead = self.pending_mutations.fetch_add(1, Ordering::AcqRel);
+        let pending = Some(PendingMutation(Arc::clone(&self.pending_mutations)));
+        if ahead > 0 {
+            debug!("Action queued behind {} other action(s)", ahead);
+        }
+
+        if kind == ActionKind::Exclusive {
+            return ActionPermit {
+                _exclusive: Some(Arc::clone(&self.browser_lock).write_owned().await),
+                _pending: pending,
+                ..ActionPermit::default()
+            };
+        }
+
+        let shared = Arc::clone(&self.browser_lock).read_owned().await;
+        let lane = self.lane(lane).lock_owned().await;
+        ActionPermit {
+            _shared: Some(shared),
+            _lane: Some(lane),
+            _pending: pending,
+            ..ActionPermit::default()
+        }
+    }
+
+    /// Get the lock for a lane, dropping locks of lanes nobody is using.
+    fn lane(&self, lane: &str) -> Arc<Mutex<()>> {
+        let mut lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+        lanes.retain(|name, lock| name == lane || Arc::strong_count(lock) > 1);
+        Arc::clone(lanes.entry(lane.to_string()).or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_action_kind() {
+        assert_eq!(
+            action_kind(tool_names::GET_PAGE_METADATA),
+            ActionKind::Query
+        );
+        assert_eq!(action_kind(tool_names::CURRENT_STATE), ActionKind::Query);
+        assert_eq!(action_kind(tool_names::CLICK_AT), ActionKind::Mutation);
+        assert_eq!(action_kind(tool_names::LIST_TABS), ActionKind::Exclusive);
+        assert_eq!(action_kind("unknown_tool"), ActionKind::Mutation);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_queries_bypass_running_mutation() {
+        let queue = ActionQueue::new();
+        let _mutation = queue.admit(ActionKind::Mutation, "tab-1").await;
+
+        // A query is admitted while the mutation is still running
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            queue.admit(ActionKind::Query, "tab-1"),
+        )
+        .await
+        .expect("query should not wait for the mutation");
+
+        // A second mutation in the same lane has to wait
+        let blocked = tokio::time::timeout(
+            Duration::from_millis(50),
+            queue.admit(ActionKind::Mutation, "tab-1"),
+        )
+        .await;
+        assert!(blocked.is_err());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_lanes_run_concurrently() {
+        let queue = ActionQueue::new();
+        let _first = queue.admit(ActionKind::Mutation, "tab-1").await;
+
+        // A mutation in another lane is admitted immediately
+        let second = tokio::time::timeout(
+            Duration::from_secs(1),
+            queue.admit(ActionKind::Mutation, "tab-2"),
+        )
+        .await
+        .expect("mutation in another lane should not wait");
+        drop(second);
+
+        // An exclusive action waits for every lane
+        let blocked = tokio::time::timeout(
+            Duration::from_millis(50),
+            queue.admit(ActionKind::Exclusive, ""),
+        )
+        .await;
+        assert!(blocked.is_err());
+    }
+}
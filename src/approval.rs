@@ -0,0 +1,225 @@
+//! Operator approval of sensitive actions.
+//!
+//! Operators can require a human to approve some tool calls, such as clicks on
+//! a payment site or anything that sends messages, outside the MCP client.
+//! Rules in `MCP_APPROVAL_RULES` name the tools, optionally only on some
+//! domains: `click_at@pay.example.com,type_text_at@mail.example.com,save_*`.
+//! Before a matching call runs, the server POSTs it as JSON to
+//! `MCP_APPROVAL_WEBHOOK_URL` and waits for the answer `{"approved": true}` or
+//! `{"approved": false, "reason": "..."}`. When the operator does not answer
+//! within `MCP_APPROVAL_TIMEOUT`, `MCP_APPROVAL_ON_TIMEOUT` decides; any other
+//! failure to get an answer denies the call.
+
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+
+/// What to do when the operator does not answer in time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeoutPolicy {
+    /// Refuse the call.
+    #[default]
+    Deny,
+    /// Run the call.
+    Allow,
+}
+
+/// Tool calls requiring approval: a tool pattern, optionally limited to a domain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalRule {
+    /// Tool name, where `*` matches any characters.
+    pub tool: String,
+    /// Domain the page must be on, including its subdomains.
+    pub domain: Option<String>,
+}
+
+impl ApprovalRule {
+    fn matches_tool(&self, tool: &str) -> bool {
+        glob_match(&self.tool, tool)
+    }
+
+    fn matches_host(&self, host: Option<&str>) -> bool {
+        match (&self.domain, host) {
+            (None, _) => true,
+            (Some(domain), Some(host)) => host == domain || host.ends_with(&format!(".{}", domain)),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Parse comma-separated `tool` or `tool@domain` rules.
+pub fn parse_rules(rules: &str) -> Vec<ApprovalRule> {
+    rules
+        .split(',')
+        .map(str::trim)
+        .filter(|rule| !rule.is_empty())
+        .map(|rule| match rule.split_once('@') {
+            Some((tool, domain)) => ApprovalRule {
+                tool: tool.trim().to_string(),
+                domain: Some(domain.trim().to_ascii_lowercase()),
+            },
+            None => ApprovalRule {
+                tool: rule.to_string(),
+                domain: None,
+            },
+        })
+        .collect()
+}
+
+/// Match a pattern where `*` stands for any characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// A tool call waiting for approval, as sent to the webhook.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest<'a> {
+    /// ID of the tool call, as in the server's logs.
+    pub request_id: &'a str,
+    /// MCP session of the call, in HTTP mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<&'a str>,
+    pub tool: &'a str,
+    /// Arguments of the call.
+    pub arguments: Option<&'a serde_json::Map<String, serde_json::Value>>,
+    /// URL of the page the call acts on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<&'a str>,
+}
+
+/// The operator's answer.
+#[derive(Debug, Deserialize)]
+struct ApprovalDecision {
+    approved: bool,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Sends tool calls matching the rules to the operator's webhook.
+pub struct ApprovalGate {
+    client: reqwest::Client,
+    webhook_url: String,
+    token: Option<String>,
+    rules: Vec<ApprovalRule>,
+    on_timeout: TimeoutPolicy,
+}
+
+impl ApprovalGate {
+    /// The configured gate, or `None` without a webhook or rules.
+    pub fn new(config: &Config) -> Option<Self> {
+        let webhook_url = config.approval_webhook_url.clone()?;
+        if config.approval_rules.is_empty() {
+            tracing::warn!("MCP_APPROVAL_WEBHOOK_URL is set but MCP_APPROVAL_RULES is empty");
+            return None;
+        }
+        Some(Self {
+            client: reqwest::Client::builder()
+                .timeout(config.approval_timeout)
+                .build()
+                .unwrap_or_default(),
+            webhook_url,
+            token: config.approval_webhook_token.clone(),
+            rules: config.approval_rules.clone(),
+            on_timeout: config.approval_on_timeout,
+        })
+    }
+
+    /// Whether any rule names the tool, so the page's host needs checking.
+    pub fn may_require(&self, tool: &str) -> bool {
+        self.rules.iter().any(|rule| rule.matches_tool(tool))
+    }
+
+    /// Whether a call of the tool on a page of the host requires approval.
+    pub fn requires(&self, tool: &str, host: Option<&str>) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| rule.matches_tool(tool) && rule.matches_host(host))
+    }
+
+    /// Ask the operator. Returns why the call may not run, if it may not.
+    pub async fn ask(&self, request: &ApprovalRequest<'_>) -> Option<String> {
+        let mut post = self.client.post(&self.webhook_url).json(request);
+        if let Some(ref token) = self.token {
+            post = post.bearer_auth(token);
+        }
+        let response = match post.send().await {
+            Ok(response) => response,
+            Err(e) if e.is_timeout() => return self.timed_out(request.tool),
+            Err(e) => return Some(format!("Approval request failed: {}", e)),
+        };
+        if !response.status().is_success() {
+            return Some(format!(
+                "Approval webhook answered with HTTP {}",
+                response.status()
+            ));
+        }
+        match response.json::<ApprovalDecision>().await {
+            Ok(decision) if decision.approved => None,
+            Ok(decision) => Some(match decision.reason {
+                Some(reason) => format!("The operator denied '{}': {}", request.tool, reason),
+                None => format!("The operator denied '{}'", request.tool),
+            }),
+            Err(e) if e.is_timeout() => self.timed_out(request.tool),
+            Err(e) => Some(format!("Invalid answer from the approval webhook: {}", e)),
+        }
+    }
+
+    fn timed_out(&self, tool: &str) -> Option<String> {
+        match self.on_timeout {
+            TimeoutPolicy::Allow => {
+                tracing::warn!("No approval decision for '{}' in time, allowing it", tool);
+                None
+            }
+            TimeoutPolicy::Deny => Some(format!("The operator did not approve '{}' in time", tool)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let rules = parse_rules("click_at@Pay.example.com, save_*, *_text_at@mail.test,,");
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].domain.as_deref(), Some("pay.example.com"));
+
+        let gate = ApprovalGate {
+            client: reqwest::Client::new(),
+            webhook_url: "http://localhost/approve".to_string(),
+            token: None,
+            rules,
+            on_timeout: TimeoutPolicy::Deny,
+        };
+        assert!(gate.requires("click_at", Some("pay.example.com")));
+        assert!(gate.requires("click_at", Some("eu.pay.example.com")));
+        assert!(!gate.requires("click_at", Some("example.com")));
+        assert!(!gate.requires("click_at", None));
+        assert!(gate.requires("save_workspace", None));
+        assert!(gate.requires("type_text_at", Some("mail.test")));
+        assert!(!gate.requires("type_text_at", Some("other.test")));
+        assert!(gate.may_require("type_text_at"));
+        assert!(!gate.may_require("navigate"));
+
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("a*b*c", "aXbYc"));
+        assert!(!glob_match("a*bc", "abc_"));
+        assert!(!glob_match("ab*ba", "aba"));
+    }
+}
@@ -0,0 +1,136 @@
+//! Latency benchmark of the configured browser, run with `--bench-mode`.
+//!
+//! Instead of serving MCP, the server opens its browser as configured, with
+//! the backend, settle delays and screenshot handling the tools use, and times
+//! screenshot capture, screenshot encoding and action round trips on the
+//! initial page. The criterion benchmarks in `benches/` measure the same paths
+//! through the MCP interface against a fixture page; this mode measures the
+//! setup of whoever runs it.
+
+use crate::browser::ScrollAmount;
+use crate::config::Config;
+use crate::screenshot_diff;
+use crate::tools::BrowserBackend;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Command line flag running the benchmark.
+pub const FLAG: &str = "--bench-mode";
+
+/// Number of timed runs of each stage.
+const ITERATIONS: usize = 20;
+
+/// Timings of one stage of the benchmark.
+struct Stage {
+    name: &'static str,
+    samples: Vec<Duration>,
+}
+
+impl Stage {
+    /// One line of the report: the stage's minimum, median, 95th percentile and
+    /// maximum, in milliseconds.
+    fn summary(&self) -> String {
+        let mut samples = self.samples.clone();
+        samples.sort();
+        let at = |q: f64| {
+            let index = ((samples.len() - 1) as f64 * q).round() as usize;
+            samples[index].as_secs_f64() * 1000.0
+        };
+        format!(
+            "{:<16} {:>9.1} {:>9.1} {:>9.1} {:>9.1}",
+            self.name,
+            at(0.0),
+            at(0.5),
+            at(0.95),
+            at(1.0)
+        )
+    }
+}
+
+/// Time `ITERATIONS` runs of an async stage.
+async fn time_async<T, F>(name: &'static str, mut run: impl FnMut() -> F) -> anyhow::Result<Stage>
+where
+    F: Future<Output = anyhow::Result<T>>,
+{
+    let mut samples = Vec::with_capacity(ITERATIONS);
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        run().await?;
+        samples.push(start.elapsed());
+    }
+    Ok(Stage { name, samples })
+}
+
+/// Time `ITERATIONS` runs of a CPU-bound stage.
+fn time<T>(name: &'static str, mut run: impl FnMut() -> T) -> Stage {
+    let samples = (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            std::hint::black_box(run());
+            start.elapsed()
+        })
+        .collect();
+    Stage { name, samples }
+}
+
+/// Run the benchmark and print its report to stdout.
+pub async fn run(config: Config) -> anyhow::Result<()> {
+    let (x, y) = (
+        i64::from(config.screen_width / 2),
+        i64::from(config.screen_height / 2),
+    );
+    let browser = BrowserBackend::new(config);
+    let result = async {
+        let state = browser.open().await?;
+        let png = BASE64.decode(&state.screenshot)?;
+        println!(
+            "Benchmarking {} ({} KB screenshots, {} runs per stage)",
+            state.url,
+            png.len() / 1024,
+            ITERATIONS
+        );
+
+        let stages = vec![
+            time_async("capture", || browser.current_state()).await?,
+            time("base64 encode", || BASE64.encode(&png)),
+            time("fingerprint", || screenshot_diff::perceptual_hash(&png)),
+            time_async("hover_at", || browser.hover_at(x, y, None)).await?,
+            time_async("scroll_document", || {
+                browser.scroll_document("down", ScrollAmount::Pixels(100), false)
+            })
+            .await?,
+        ];
+
+        println!(
+            "{:<16} {:>9} {:>9} {:>9} {:>9}",
+            "stage (ms)", "min", "median", "p95", "max"
+        );
+        for stage in &stages {
+            println!("{}", stage.summary());
+        }
+        Ok(())
+    }
+    .await;
+    browser.close().await?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let stage = Stage {
+            name: "capture",
+            samples: (1..=20).rev().map(Duration::from_millis).collect(),
+        };
+        let columns: Vec<String> = stage
+            .summary()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        assert_eq!(columns, ["capture", "1.0", "11.0", "19.0", "20.0"]);
+    }
+}
@@ -2,13 +2,46 @@
 //!
 //! This module provides browser automation capabilities using WebDriver.
 
-use crate::config::{BrowserType, Config, ConnectionMode};
+use crate::accessibility::{self, AccessibilityAudit};
+use crate::browser_stats::{self, PageMetrics};
+use crate::checkpoint::{self, PageCheckpoint};
+use crate::config::{BrowserType, Config, ConnectionMode, CoordinatePolicy};
+use crate::context_strips::{self, ContextStrip, ContextStrips};
+use crate::credentials::{self, CredentialFill, SiteCredentials};
+use crate::dom_snapshot::{self, DomSnapshot};
+use crate::draw_path::{self, PathPoint, PointerType};
+use crate::element_aliases::{self, ElementFingerprint, Located};
+use crate::element_locator::{self, LocatedElement, Locator};
+use crate::emulation::{self, MediaEmulation, MediaEmulations, VisionDeficiency};
+use crate::evaluate::{self, Evaluation};
+use crate::input_controls::{self, ControlValue};
+use crate::keyboard_focus::{self, Activation, TabFocus};
+use crate::keys::{self, KeyMapping};
+use crate::link_check::{self, PageLinks};
+use crate::login_wall::{self, LoginSignals};
+use crate::media::{self, MediaCommand, MediaElement, VideoFrame};
+use crate::media_devices;
+use crate::metrics;
+use crate::navigation::NavigationTracker;
+use crate::notifications::{self, CapturedNotification};
+use crate::oauth::{self, OAuthStep, Provider, StepAction};
+use crate::page_watch::{self, PageText};
+use crate::recipes;
+use crate::screenshot_diff;
+use crate::screenshot_scale;
+use crate::scroll_memory::{self, ScrollMemory, ScrollPosition};
+use crate::search_engine::SearchEngine;
+use crate::shortcuts;
+use crate::state_delta::{self, PageActivity, StateDelta, StateDeltaTracker};
+use crate::web_search::{self, SearchResult};
+use crate::workspace::{self, SavedCookie};
+use crate::zoom_to_fit;
 use anyhow::Result;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use thirtyfour::common::capabilities::chromium::ChromiumLikeCapabilities;
 use thirtyfour::extensions::cdp::ChromeDevTools;
 use thirtyfour::prelude::*;
@@ -19,25 +52,51 @@ use tracing::{debug, info, warn};
 /// Delay in milliseconds to wait for page to settle after actions.
 const PAGE_SETTLE_DELAY_MS: u64 = 500;
 
+/// Delay after a sign-in step, for the provider's next page to load (in milliseconds).
+pub(crate) const OAUTH_STEP_DELAY_MS: u64 = 1500;
+
+/// Delay in milliseconds before capturing a blank screenshot again.
+const BLANK_SCREENSHOT_RETRY_DELAY_MS: u64 = 300;
+
 /// Delay in milliseconds after typing actions.
 const TYPING_DELAY_MS: u64 = 100;
 
+/// Delay in milliseconds between keystrokes when simulating typing.
+const KEYSTROKE_DELAY_MS: u64 = 30;
+
 /// Maximum number of retries for transient failures.
 const MAX_RETRIES: u32 = 3;
 
 /// Delay between retries in milliseconds.
 const RETRY_DELAY_MS: u64 = 200;
 
-/// Maximum safe integer value for JavaScript (2^53 - 1).
-/// Coordinates beyond this could lose precision in JavaScript.
-const MAX_SAFE_JS_INTEGER: i64 = 9007199254740991;
-
 /// Maximum allowed scroll magnitude in pixels.
 const MAX_SCROLL_MAGNITUDE: i64 = 10000;
 
 /// Minimum allowed scroll magnitude in pixels.
 const MIN_SCROLL_MAGNITUDE: i64 = 0;
 
+/// Maximum number of viewports `scroll_document` may move in one call.
+const MAX_SCROLL_PAGES: f64 = 100.0;
+
+/// Maximum number of steps in one `press_keys_sequence` call.
+const MAX_KEY_SEQUENCE_STEPS: usize = 50;
+
+/// Maximum delay after a single step of a key sequence, in milliseconds.
+const MAX_KEY_STEP_DELAY_MS: u64 = 5000;
+
+/// Maximum time `hover_at` may keep hovering, in milliseconds.
+const MAX_HOVER_DURATION_MS: u64 = 10000;
+
+/// Interval between the mouse moves that keep a hover alive, in milliseconds.
+pub(crate) const HOVER_REFRESH_INTERVAL_MS: u64 = 250;
+
+/// Interval between checks of the element under a click target, in milliseconds.
+pub(crate) const STABILITY_POLL_INTERVAL_MS: u64 = 100;
+
+/// Consecutive unchanged checks after which a click target counts as stable.
+pub(crate) const STABLE_CHECKS: u32 = 3;
+
 /// Default user agent for undetected mode (realistic Chrome user agent).
 const UNDETECTED_USER_AGENT: &str =
     "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
@@ -153,19 +212,134 @@ async fn wait_for_page_ready(driver: &WebDriver) -> Result<()> {
     Ok(())
 }
 
+/// Prefix a URL with "https://" if it has no http(s) scheme.
+pub(crate) fn normalize_url(url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    }
+}
+
+/// Base64-encode a PNG screenshot on the blocking thread pool, so encoding a
+/// multi-megabyte screenshot does not stall the runtime worker it runs on.
+/// Screenshots larger than `max_dimension` are scaled down first.
+pub(crate) async fn encode_screenshot(png: Vec<u8>, max_dimension: Option<u32>) -> Result<String> {
+    tokio::task::spawn_blocking(move || {
+        let png = match max_dimension {
+            Some(max) => screenshot_scale::downscale(png, max)?,
+            None => png,
+        };
+        Ok(BASE64.encode(png))
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Screenshot encoding failed: {}", e))?
+}
+
+/// Whether a PNG screenshot is blank, decoded on the blocking thread pool.
+/// Returns the screenshot along with the answer.
+pub(crate) async fn check_blank_screenshot(png: Vec<u8>) -> Result<(Vec<u8>, bool)> {
+    tokio::task::spawn_blocking(move || {
+        let blank = screenshot_diff::is_blank(&png).unwrap_or(false);
+        (png, blank)
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Blank screenshot check failed: {}", e))
+}
+
 /// Environment state returned by browser actions.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnvState {
     /// Screenshot in PNG format, base64 encoded.
     pub screenshot: String,
     /// Current URL of the page.
     pub url: String,
+    /// Notifications shown since the previous action, when they are reported
+    /// in responses.
+    pub notifications: Vec<CapturedNotification>,
+    /// Warnings about the agent's navigation, such as a loop between pages.
+    pub warnings: Vec<String>,
+    /// Changes since the previous state of the tab, when they are reported.
+    pub delta: Option<StateDelta>,
+    /// Set to `false` by clicks that had no observable effect.
+    pub effect_detected: Option<bool>,
+    /// Time spent settling the page and capturing its screenshot.
+    #[serde(default)]
+    pub timings: StateTimings,
+    /// Whether the page asks the user to sign in.
+    #[serde(default)]
+    pub login_required: bool,
+}
+
+/// Where the time of capturing a state went, in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct StateTimings {
+    /// Waiting for the page to be ready and settle.
+    pub settle_ms: u64,
+    /// Capturing and encoding the screenshot, blank retries included.
+    pub screenshot_ms: u64,
+}
+
+/// Error code reported to the model when a tab crashed.
+pub const PAGE_CRASHED_CODE: &str = "page_crashed";
+
+/// Start of the [`PageCrashed`] message, used to recognize the error once it
+/// has been formatted into a tool response.
+pub(crate) const PAGE_CRASHED_MESSAGE: &str = "Page crashed";
+
+/// The renderer process of a tab crashed ("Aw, Snap!"), usually because the page
+/// ran out of memory. The controller tries to reload the tab before reporting it.
+#[derive(Debug)]
+pub struct PageCrashed {
+    /// Whether the tab was reloaded after the crash.
+    pub reloaded: bool,
+}
+
+impl std::fmt::Display for PageCrashed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: the tab's renderer process was terminated, often because the page ran out of memory. ",
+            PAGE_CRASHED_MESSAGE
+        )?;
+        if self.reloaded {
+            write!(
+                f,
+                "The tab was reloaded; anything not saved on the page is lost."
+            )
+        } else {
+            write!(
+                f,
+                "Reloading the tab failed; open a new tab or restart the browser."
+            )
+        }
+    }
+}
+
+impl std::error::Error for PageCrashed {}
+
+/// Whether a WebDriver error reports a crashed tab. ChromeDriver answers with
+/// "tab crashed", or "session deleted because of page crash" when the crash
+/// took the session with it.
+fn is_crash_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("tab crashed") || message.contains("page crash")
+}
+
+/// Whether a WebDriver error reports that the session no longer exists, e.g.
+/// because the server expired it or the browser went away.
+fn is_session_gone_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("invalid session id")
+        || message.contains("session not found")
+        || message.contains("no such session")
+        || (message.contains("session") && message.contains("timed out"))
 }
 
 /// Information about a browser tab.
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct TabInfo {
-    /// The window handle identifier.
+    /// The tab handle: the WebDriver window handle, or the CDP target ID.
     pub handle: String,
     /// The URL of the tab.
     pub url: String,
@@ -178,41 +352,209 @@ pub struct TabInfo {
     pub navigation_error: Option<String>,
 }
 
-/// Validate coordinates are within reasonable screen bounds and safe for JavaScript.
-///
-/// Coordinates are validated to ensure:
-/// 1. They are not negative
-/// 2. They are not too far outside screen bounds (allows some tolerance for edge cases)
-/// 3. They are within JavaScript's safe integer range to prevent precision loss
-fn validate_coordinates(x: i64, y: i64, width: u32, height: u32) -> Result<()> {
-    if x < 0 || y < 0 {
-        return Err(anyhow::anyhow!(
-            "Coordinates cannot be negative: ({}, {})",
-            x,
-            y
-        ));
+/// A page target of a browser connected over CDP, as reported by the browser
+/// itself. Unlike [`TabInfo`], this includes tabs the server has not attached to yet.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PageTarget {
+    /// The CDP target ID, usable as a tab handle once attached.
+    pub handle: String,
+    /// The URL of the page.
+    pub url: String,
+    /// The title of the page.
+    pub title: String,
+    /// Whether this is the tab actions currently target.
+    pub active: bool,
+}
+
+/// One step of a key sequence: a key or chord, then an optional pause.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct KeyStep {
+    /// Keys to press together. Example: ["Tab"] or ["Shift", "Tab"].
+    pub keys: Vec<String>,
+    /// Milliseconds to wait after this step before the next one (max 5000).
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// Check a key sequence before any key is pressed, so an invalid step does not
+/// leave the page half-way through the sequence.
+pub(crate) fn validate_key_steps(steps: &[KeyStep]) -> Result<()> {
+    if steps.is_empty() {
+        return Err(anyhow::anyhow!("No steps provided"));
     }
-    if x > MAX_SAFE_JS_INTEGER || y > MAX_SAFE_JS_INTEGER {
+    if steps.len() > MAX_KEY_SEQUENCE_STEPS {
         return Err(anyhow::anyhow!(
-            "Coordinates ({}, {}) exceed JavaScript safe integer limit",
-            x,
-            y
+            "Too many steps: {} (max {})",
+            steps.len(),
+            MAX_KEY_SEQUENCE_STEPS
         ));
     }
-    if x as u32 > width * 2 || y as u32 > height * 2 {
-        return Err(anyhow::anyhow!(
-            "Coordinates ({}, {}) are too far outside screen bounds ({}x{})",
+    for (i, step) in steps.iter().enumerate() {
+        if step.keys.is_empty() {
+            return Err(anyhow::anyhow!("Step {} has no keys", i + 1));
+        }
+        if step.delay_ms > MAX_KEY_STEP_DELAY_MS {
+            return Err(anyhow::anyhow!(
+                "Step {} delay {}ms exceeds the maximum of {}ms",
+                i + 1,
+                step.delay_ms,
+                MAX_KEY_STEP_DELAY_MS
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Information about a frame (`<iframe>` or `<frame>`) in the current document.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct FrameInfo {
+    /// Index of the frame within the current document (used by switch_frame).
+    pub index: usize,
+    /// The frame element's `name` attribute.
+    pub name: String,
+    /// The frame element's `id` attribute.
+    pub id: String,
+    /// The frame's source URL.
+    pub src: String,
+    /// X coordinate of the frame's content area on the screenshot.
+    pub x: i64,
+    /// Y coordinate of the frame's content area on the screenshot.
+    pub y: i64,
+    /// Width of the frame in pixels.
+    pub width: i64,
+    /// Height of the frame in pixels.
+    pub height: i64,
+    /// Whether the frame's document is cross-origin to the current document.
+    pub cross_origin: bool,
+}
+
+/// Metadata describing the current page.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PageMetadata {
+    /// URL of the document.
+    pub url: String,
+    /// The document title.
+    pub title: String,
+    /// Content of the `description` meta tag.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Canonical URL from `<link rel="canonical">`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<String>,
+    /// OpenGraph tags keyed by property name without the `og:` prefix.
+    pub open_graph: std::collections::BTreeMap<String, String>,
+    /// Absolute URL of the page's favicon.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
+}
+
+/// JavaScript expression that extracts [`PageMetadata`] from the current document.
+pub(crate) const PAGE_METADATA_SCRIPT: &str = r#"
+(function() {
+    function meta(selector) {
+        var el = document.querySelector(selector);
+        var content = el && el.getAttribute('content');
+        return content ? content.trim() : null;
+    }
+    var canonical = document.querySelector('link[rel~="canonical" i]');
+    var icon = document.querySelector(
+        'link[rel~="icon" i], link[rel="shortcut icon" i], link[rel~="apple-touch-icon" i]'
+    );
+    var openGraph = {};
+    document.querySelectorAll('meta[property^="og:"]').forEach(function(el) {
+        var key = el.getAttribute('property').slice(3);
+        var content = el.getAttribute('content');
+        if (key && content && !(key in openGraph)) {
+            openGraph[key] = content.trim();
+        }
+    });
+    var favicon = null;
+    if (icon && icon.href) {
+        favicon = icon.href;
+    } else if (location.protocol === 'http:' || location.protocol === 'https:') {
+        favicon = location.origin + '/favicon.ico';
+    }
+    return {
+        url: location.href,
+        title: document.title || '',
+        description: meta('meta[name="description" i]') || meta('meta[property="og:description"]'),
+        canonical_url: canonical && canonical.href ? canonical.href : null,
+        open_graph: openGraph,
+        favicon: favicon
+    };
+})()
+"#;
+
+/// JavaScript expression that describes every frame element in the current document.
+///
+/// Positions are relative to the current document's viewport and point at the frame's
+/// content box, which is the origin for coordinates inside the frame.
+pub(crate) const LIST_FRAMES_SCRIPT: &str = r#"
+(function() {
+    var frames = document.querySelectorAll('iframe, frame');
+    var result = [];
+    for (var i = 0; i < frames.length; i++) {
+        var el = frames[i];
+        var rect = el.getBoundingClientRect();
+        var style = window.getComputedStyle(el);
+        var crossOrigin = false;
+        try {
+            crossOrigin = !el.contentDocument;
+        } catch (e) {
+            crossOrigin = true;
+        }
+        result.push({
+            index: i,
+            name: el.getAttribute('name') || '',
+            id: el.id || '',
+            src: el.src || '',
+            x: Math.round(rect.left + el.clientLeft + (parseFloat(style.paddingLeft) || 0)),
+            y: Math.round(rect.top + el.clientTop + (parseFloat(style.paddingTop) || 0)),
+            width: Math.round(rect.width),
+            height: Math.round(rect.height),
+            cross_origin: crossOrigin
+        });
+    }
+    return result;
+})()
+"#;
+
+/// Check that coordinates fall inside the viewport.
+///
+/// Returns the coordinates to act on: unchanged when inside the viewport, or moved
+/// to the nearest edge pixel under [`CoordinatePolicy::Clamp`]. Under
+/// [`CoordinatePolicy::Reject`] out-of-viewport coordinates are an error that
+/// names the viewport size, so the model can correct itself.
+pub(crate) fn validate_coordinates(x: i64, y: i64, config: &Config) -> Result<(i64, i64)> {
+    let width = i64::from(config.screen_width.max(1));
+    let height = i64::from(config.screen_height.max(1));
+    if (0..width).contains(&x) && (0..height).contains(&y) {
+        return Ok((x, y));
+    }
+
+    match config.coordinate_policy {
+        CoordinatePolicy::Clamp => {
+            let clamped = (x.clamp(0, width - 1), y.clamp(0, height - 1));
+            debug!(
+                "Clamped coordinates ({}, {}) to ({}, {})",
+                x, y, clamped.0, clamped.1
+            );
+            Ok(clamped)
+        }
+        CoordinatePolicy::Reject => Err(anyhow::anyhow!(
+            "Coordinates ({}, {}) are outside the {}x{} viewport: x must be 0-{} and y must be 0-{}",
             x,
             y,
             width,
-            height
-        ));
+            height,
+            width - 1,
+            height - 1
+        )),
     }
-    Ok(())
 }
 
 /// Validate scroll magnitude is within reasonable bounds.
-fn validate_magnitude(magnitude: i64) -> Result<()> {
+pub(crate) fn validate_magnitude(magnitude: i64) -> Result<()> {
     if magnitude < MIN_SCROLL_MAGNITUDE {
         return Err(anyhow::anyhow!(
             "Scroll magnitude {} is below minimum allowed value {}",
@@ -230,6 +572,532 @@ fn validate_magnitude(magnitude: i64) -> Result<()> {
     Ok(())
 }
 
+/// JavaScript function dispatching hover events at a point. The first call
+/// sends `mouseenter`, `mouseover` and `mousemove`; later calls (`initial` false)
+/// only repeat `mousemove` to keep hover menus open. The hovered element is
+/// remembered so [`MOVE_MOUSE_AWAY_SCRIPT`] can end the hover.
+const HOVER_FUNCTION: &str = r#"
+(function(x, y, initial) {
+    var element = document.elementFromPoint(x, y);
+    if (!element) {
+        return false;
+    }
+    window.__mcpHoverTarget = element;
+    var events = initial ? ['mouseenter', 'mouseover', 'mousemove'] : ['mousemove'];
+    events.forEach(function(eventType) {
+        var event = new MouseEvent(eventType, {
+            view: window,
+            bubbles: true,
+            cancelable: true,
+            clientX: x,
+            clientY: y
+        });
+        element.dispatchEvent(event);
+    });
+    return true;
+})"#;
+
+/// JavaScript expression hovering over the element at (`x`, `y`).
+pub(crate) fn hover_script(x: i64, y: i64, initial: bool) -> String {
+    format!("{}({}, {}, {})", HOVER_FUNCTION, x, y, initial)
+}
+
+/// JavaScript expression ending a hover started by [`hover_script`]: sends
+/// `mouseout` and `mouseleave` to the hovered element and its ancestors, then a
+/// `mousemove` over the top-left corner of the page. Evaluates to whether an
+/// element was hovered.
+pub(crate) const MOVE_MOUSE_AWAY_SCRIPT: &str = r#"
+(function() {
+    var target = window.__mcpHoverTarget;
+    window.__mcpHoverTarget = null;
+    function send(element, eventType, bubbles) {
+        element.dispatchEvent(new MouseEvent(eventType, {
+            view: window,
+            bubbles: bubbles,
+            cancelable: true,
+            clientX: 0,
+            clientY: 0
+        }));
+    }
+    if (target && target.isConnected) {
+        send(target, 'mouseout', true);
+        for (var el = target; el; el = el.parentElement) {
+            send(el, 'mouseleave', false);
+        }
+    }
+    send(document.documentElement, 'mouseover', true);
+    send(document.documentElement, 'mousemove', true);
+    return !!target;
+})()"#;
+
+/// JavaScript expression starting to watch the document for effects of a
+/// click: DOM mutations, focus changes and URL changes.
+pub(crate) const CLICK_EFFECT_WATCH_SCRIPT: &str = r#"
+(function() {
+    if (window.__mcpClickEffect) {
+        window.__mcpClickEffect.observer.disconnect();
+    }
+    var watch = { mutations: 0, focus: document.activeElement, url: location.href };
+    watch.observer = new MutationObserver(function(records) {
+        watch.mutations += records.length;
+    });
+    watch.observer.observe(document.documentElement, {
+        childList: true, subtree: true, attributes: true, characterData: true
+    });
+    window.__mcpClickEffect = watch;
+    return true;
+})()"#;
+
+/// JavaScript expression ending the watch started by
+/// [`CLICK_EFFECT_WATCH_SCRIPT`]. Evaluates to whether anything changed, which
+/// includes the watch being gone after a navigation.
+pub(crate) const CLICK_EFFECT_READ_SCRIPT: &str = r#"
+(function() {
+    var watch = window.__mcpClickEffect;
+    if (!watch) {
+        return true;
+    }
+    watch.observer.disconnect();
+    delete window.__mcpClickEffect;
+    return watch.mutations > 0 || document.activeElement !== watch.focus ||
+        location.href !== watch.url;
+})()"#;
+
+/// JavaScript function reporting whether the element at a point, and its
+/// bounding box, are the same as at the previous call. A `reset` call forgets
+/// the previous one and reports false.
+const STABILITY_FUNCTION: &str = r#"
+(function(x, y, reset) {
+    var element = document.elementFromPoint(x, y);
+    var rect = element ? element.getBoundingClientRect() : null;
+    var box = rect ? [rect.left, rect.top, rect.width, rect.height].join(',') : '';
+    var previous = reset ? null : window.__mcpStabilityTarget;
+    window.__mcpStabilityTarget = { element: element, box: box };
+    return !!previous && previous.element === element && previous.box === box;
+})"#;
+
+/// JavaScript expression checking whether the element at (`x`, `y`) stayed in
+/// place since the previous check.
+pub(crate) fn stability_script(x: i64, y: i64, reset: bool) -> String {
+    format!("{}({}, {}, {})", STABILITY_FUNCTION, x, y, reset)
+}
+
+/// Check a hover duration in milliseconds.
+pub(crate) fn validate_hover_duration(duration_ms: u64) -> Result<()> {
+    if duration_ms > MAX_HOVER_DURATION_MS {
+        return Err(anyhow::anyhow!(
+            "Hover duration {}ms exceeds the maximum of {}ms",
+            duration_ms,
+            MAX_HOVER_DURATION_MS
+        ));
+    }
+    Ok(())
+}
+
+/// Where `type_text_at` puts the typed text.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum InsertionMode {
+    /// After the existing content.
+    Append,
+    /// Before the existing content.
+    Prepend,
+    /// In place of the current selection, or at the caret when nothing is
+    /// selected (see select_text).
+    ReplaceSelection,
+    /// In place of all existing content.
+    #[default]
+    ReplaceAll,
+}
+
+/// JavaScript function moving the caret of the focused element to the start
+/// (`'prepend'`) or end (`'append'`) of its content, or selecting all of it
+/// (`'replace_all'`).
+const PREPARE_INSERTION_FUNCTION: &str = r#"
+(function(mode) {
+    var active = document.activeElement;
+    if (!active) {
+        return;
+    }
+    if (active.tagName === 'INPUT' || active.tagName === 'TEXTAREA') {
+        var length = (active.value || '').length;
+        try {
+            if (mode === 'replace_all') {
+                active.select();
+            } else {
+                var position = mode === 'prepend' ? 0 : length;
+                active.setSelectionRange(position, position);
+            }
+        } catch (e) {
+            // Inputs such as email and number do not expose a selection
+        }
+    } else if (active.isContentEditable) {
+        var range = document.createRange();
+        range.selectNodeContents(active);
+        if (mode !== 'replace_all') {
+            range.collapse(mode === 'prepend');
+        }
+        var selection = window.getSelection();
+        selection.removeAllRanges();
+        selection.addRange(range);
+    }
+})"#;
+
+/// JavaScript expression preparing the focused element for typing in `mode`,
+/// or `None` when the current selection is used as it is.
+pub(crate) fn prepare_insertion_script(mode: InsertionMode) -> Option<String> {
+    let mode = match mode {
+        InsertionMode::Append => "append",
+        InsertionMode::Prepend => "prepend",
+        InsertionMode::ReplaceAll => "replace_all",
+        InsertionMode::ReplaceSelection => return None,
+    };
+    Some(format!("{}('{}')", PREPARE_INSERTION_FUNCTION, mode))
+}
+
+/// Distance moved by `scroll_document`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ScrollAmount {
+    /// 0.8 of the viewport height vertically, half the viewport width horizontally.
+    #[default]
+    Default,
+    /// Multiples of the viewport height (vertical) or width (horizontal).
+    Pages(f64),
+    /// Exact distance in CSS pixels.
+    Pixels(i64),
+}
+
+impl ScrollAmount {
+    /// Build from the optional `amount_pages` and `pixels` tool parameters,
+    /// at most one of which may be given.
+    pub fn from_params(amount_pages: Option<f64>, pixels: Option<i64>) -> Result<Self> {
+        match (amount_pages, pixels) {
+            (Some(_), Some(_)) => Err(anyhow::anyhow!(
+                "Specify either amount_pages or pixels, not both"
+            )),
+            (Some(pages), None) => {
+                if !(pages > 0.0 && pages <= MAX_SCROLL_PAGES) {
+                    return Err(anyhow::anyhow!(
+                        "amount_pages {} must be greater than 0 and at most {}",
+                        pages,
+                        MAX_SCROLL_PAGES
+                    ));
+                }
+                Ok(ScrollAmount::Pages(pages))
+            }
+            (None, Some(pixels)) => {
+                validate_magnitude(pixels)?;
+                Ok(ScrollAmount::Pixels(pixels))
+            }
+            (None, None) => Ok(ScrollAmount::Default),
+        }
+    }
+}
+
+impl std::fmt::Display for ScrollAmount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScrollAmount::Default => write!(f, "one step"),
+            ScrollAmount::Pages(pages) => write!(f, "{} page(s)", pages),
+            ScrollAmount::Pixels(pixels) => write!(f, "{}px", pixels),
+        }
+    }
+}
+
+/// JavaScript statement scrolling the document in `direction` by `amount`,
+/// animated when `smooth` is set.
+pub(crate) fn scroll_document_script(
+    direction: &str,
+    amount: ScrollAmount,
+    smooth: bool,
+) -> Result<String> {
+    let (horizontal, sign, viewport, default_pages) = match direction.to_lowercase().as_str() {
+        "up" => (false, "-", "window.innerHeight", 0.8),
+        "down" => (false, "", "window.innerHeight", 0.8),
+        "left" => (true, "-", "window.innerWidth", 0.5),
+        "right" => (true, "", "window.innerWidth", 0.5),
+        _ => return Err(anyhow::anyhow!("Invalid scroll direction: {}", direction)),
+    };
+    let distance = match amount {
+        ScrollAmount::Default => format!("{} * {}", viewport, default_pages),
+        ScrollAmount::Pages(pages) => format!("{} * {}", viewport, pages),
+        ScrollAmount::Pixels(pixels) => pixels.to_string(),
+    };
+    let delta = format!("{}({})", sign, distance);
+    let (dx, dy) = if horizontal {
+        (delta.as_str(), "0")
+    } else {
+        ("0", delta.as_str())
+    };
+    Ok(if smooth {
+        format!(
+            "window.scrollBy({{ left: {}, top: {}, behavior: 'smooth' }});",
+            dx, dy
+        )
+    } else {
+        format!("window.scrollBy({}, {});", dx, dy)
+    })
+}
+
+/// JavaScript promise that resolves once the document has stopped scrolling and
+/// the images in the viewport have loaded, so the screenshot is not taken
+/// mid-animation or before lazy-loaded content appears. Resolves to `false` if
+/// the page did not settle within 3 seconds.
+pub(crate) const SCROLL_SETTLE_SCRIPT: &str = r#"
+new Promise(function(resolve) {
+    var deadline = Date.now() + 3000;
+    var last = null;
+    var stableChecks = 0;
+    function pendingImages() {
+        var pending = 0;
+        document.querySelectorAll('img').forEach(function(img) {
+            if (img.complete) {
+                return;
+            }
+            var rect = img.getBoundingClientRect();
+            if (rect.bottom >= 0 && rect.top <= window.innerHeight &&
+                rect.right >= 0 && rect.left <= window.innerWidth) {
+                pending++;
+            }
+        });
+        return pending;
+    }
+    function check() {
+        var position = window.scrollX + ',' + window.scrollY;
+        stableChecks = position === last ? stableChecks + 1 : 0;
+        last = position;
+        if (stableChecks >= 3 && pendingImages() === 0) {
+            resolve(true);
+        } else if (Date.now() > deadline) {
+            resolve(false);
+        } else {
+            setTimeout(check, 50);
+        }
+    }
+    check();
+})
+"#;
+
+/// JavaScript function that scrolls the element at a point to the top of the
+/// viewport, just below any fixed or sticky header. Called with the point and an
+/// optional explicit offset in pixels; returns the offset used, or `null` when no
+/// element is at the point.
+///
+/// The header is detected after a first scroll, since many sites only pin their
+/// navigation bar once the page is scrolled. The offset is applied through
+/// `scroll-margin-top`, so nested scroll containers are handled by the browser.
+const SCROLL_TO_ELEMENT_FUNCTION: &str = r#"
+(function(x, y, offset) {
+    var element = document.elementFromPoint(x, y);
+    if (!element) {
+        return null;
+    }
+    function pinnedHeaderBottom() {
+        var bottom = 0;
+        [0.1, 0.5, 0.9].forEach(function(fraction) {
+            var stack = document.elementsFromPoint(window.innerWidth * fraction, 1);
+            stack.forEach(function(el) {
+                for (var node = el; node && node !== document.body; node = node.parentElement) {
+                    var position = window.getComputedStyle(node).position;
+                    if (position !== 'fixed' && position !== 'sticky') {
+                        continue;
+                    }
+                    var rect = node.getBoundingClientRect();
+                    if (rect.top <= 1 && rect.height < window.innerHeight * 0.5) {
+                        bottom = Math.max(bottom, rect.bottom);
+                    }
+                }
+            });
+        });
+        return Math.ceil(bottom);
+    }
+    element.scrollIntoView({ block: 'start', inline: 'nearest', behavior: 'instant' });
+    var margin = offset !== null ? offset : pinnedHeaderBottom() + 8;
+    if (margin > 0) {
+        var previous = element.style.scrollMarginTop;
+        element.style.scrollMarginTop = margin + 'px';
+        element.scrollIntoView({ block: 'start', inline: 'nearest', behavior: 'instant' });
+        element.style.scrollMarginTop = previous;
+    }
+    return margin;
+})"#;
+
+/// JavaScript expression scrolling the element at (`x`, `y`) below any pinned header.
+pub(crate) fn scroll_to_element_script(x: i64, y: i64, offset: Option<i64>) -> String {
+    let offset = offset.map_or_else(|| "null".to_string(), |o| o.to_string());
+    format!("{}({}, {}, {})", SCROLL_TO_ELEMENT_FUNCTION, x, y, offset)
+}
+
+/// Element an action applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ElementTarget {
+    /// Element at a point, in the coordinates of the current frame.
+    Point(i64, i64),
+    /// First element matching a CSS selector in the current frame.
+    Selector(String),
+}
+
+impl ElementTarget {
+    /// The target as the `{ x, y }` or `{ selector }` object that element
+    /// scripts receive.
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        match self {
+            ElementTarget::Point(x, y) => serde_json::json!({ "x": x, "y": y }),
+            ElementTarget::Selector(selector) => serde_json::json!({ "selector": selector }),
+        }
+    }
+}
+
+/// JavaScript function selecting a range of text in an element. Inputs and
+/// textareas use their own selection, other elements the document selection
+/// over their text. `start` and `end` are character offsets, `null` for the
+/// start and end of the text. Returns `{ text }` with the selected text, or
+/// `{ error }`.
+const SELECT_TEXT_FUNCTION: &str = r#"
+(function(target, start, end) {
+    var el = target.selector !== undefined
+        ? document.querySelector(target.selector)
+        : document.elementFromPoint(target.x, target.y);
+    if (!el) {
+        return { error: target.selector !== undefined
+            ? 'No element matches selector ' + target.selector
+            : 'No element at (' + target.x + ', ' + target.y + ')' };
+    }
+    function bounds(length) {
+        var from = start === null ? 0 : Math.min(start, length);
+        var to = end === null ? length : Math.min(Math.max(end, from), length);
+        return [from, to];
+    }
+    if (el.tagName === 'INPUT' || el.tagName === 'TEXTAREA') {
+        var value = el.value || '';
+        var range = bounds(value.length);
+        el.focus();
+        try {
+            el.setSelectionRange(range[0], range[1]);
+        } catch (e) {
+            return { error: 'Input of type ' + el.type + ' does not support text selection' };
+        }
+        return { text: value.substring(range[0], range[1]) };
+    }
+    var offsets = bounds(el.textContent.length);
+    var selected = document.createRange();
+    selected.selectNodeContents(el);
+    var walker = document.createTreeWalker(el, NodeFilter.SHOW_TEXT);
+    var offset = 0;
+    var startSet = false;
+    for (var node = walker.nextNode(); node; node = walker.nextNode()) {
+        var length = node.data.length;
+        if (!startSet && offsets[0] <= offset + length) {
+            selected.setStart(node, offsets[0] - offset);
+            startSet = true;
+        }
+        if (offsets[1] <= offset + length) {
+            selected.setEnd(node, offsets[1] - offset);
+            break;
+        }
+        offset += length;
+    }
+    if (el.isContentEditable) {
+        el.focus();
+    }
+    var selection = window.getSelection();
+    selection.removeAllRanges();
+    selection.addRange(selected);
+    return { text: selection.toString() };
+})"#;
+
+/// JavaScript expression selecting text in the target element.
+pub(crate) fn select_text_script(
+    target: &ElementTarget,
+    start: Option<u32>,
+    end: Option<u32>,
+) -> String {
+    let offset = |o: Option<u32>| o.map_or_else(|| "null".to_string(), |o| o.to_string());
+    format!(
+        "{}({}, {}, {})",
+        SELECT_TEXT_FUNCTION,
+        target.to_json(),
+        offset(start),
+        offset(end)
+    )
+}
+
+/// Text from the result of an element script such as [`select_text_script`],
+/// which evaluates to `{ text }` or `{ error }`.
+pub(crate) fn parse_element_result(value: &serde_json::Value) -> Result<String> {
+    if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+        return Err(anyhow::anyhow!("{}", error));
+    }
+    value
+        .get("text")
+        .and_then(|t| t.as_str())
+        .map(String::from)
+        .ok_or_else(|| anyhow::anyhow!("Unexpected script result: {}", value))
+}
+
+/// Default time a highlight stays on the page, in milliseconds.
+pub(crate) const DEFAULT_HIGHLIGHT_DURATION_MS: u64 = 3000;
+
+/// Maximum time a highlight stays on the page, in milliseconds.
+const MAX_HIGHLIGHT_DURATION_MS: u64 = 60000;
+
+/// JavaScript function drawing an outlined box over an element, removed after
+/// `durationMs`. The box ignores the pointer so it does not intercept clicks.
+/// Returns `{ text }` describing the element, or `{ error }`.
+const HIGHLIGHT_FUNCTION: &str = r#"
+(function(target, durationMs) {
+    var el = target.selector !== undefined
+        ? document.querySelector(target.selector)
+        : document.elementFromPoint(target.x, target.y);
+    if (!el) {
+        return { error: target.selector !== undefined
+            ? 'No element matches selector ' + target.selector
+            : 'No element at (' + target.x + ', ' + target.y + ')' };
+    }
+    var previous = document.getElementById('__mcp_highlight');
+    if (previous) {
+        previous.remove();
+    }
+    var rect = el.getBoundingClientRect();
+    var box = document.createElement('div');
+    box.id = '__mcp_highlight';
+    box.style.cssText = 'position: fixed; pointer-events: none; z-index: 2147483647;'
+        + 'box-sizing: border-box; border: 3px solid #ff2d55; border-radius: 3px;'
+        + 'background: rgba(255, 45, 85, 0.12); box-shadow: 0 0 0 2px #fff;'
+        + 'left: ' + (rect.left - 3) + 'px; top: ' + (rect.top - 3) + 'px;'
+        + 'width: ' + (rect.width + 6) + 'px; height: ' + (rect.height + 6) + 'px;';
+    document.documentElement.appendChild(box);
+    setTimeout(function() { box.remove(); }, durationMs);
+    var description = el.tagName.toLowerCase();
+    if (el.id) {
+        description += '#' + el.id;
+    }
+    var label = (el.getAttribute('aria-label') || el.innerText || el.value || '').trim();
+    if (label) {
+        description += ' "' + label.substring(0, 60) + '"';
+    }
+    return { text: description };
+})"#;
+
+/// JavaScript expression highlighting the target element for `duration_ms`.
+pub(crate) fn highlight_script(target: &ElementTarget, duration_ms: u64) -> Result<String> {
+    if duration_ms > MAX_HIGHLIGHT_DURATION_MS {
+        return Err(anyhow::anyhow!(
+            "Highlight duration {}ms exceeds the maximum of {}ms",
+            duration_ms,
+            MAX_HIGHLIGHT_DURATION_MS
+        ));
+    }
+    Ok(format!(
+        "{}({}, {})",
+        HIGHLIGHT_FUNCTION,
+        target.to_json(),
+        duration_ms
+    ))
+}
+
 /// Valid key names for keyboard input (case-insensitive).
 static VALID_KEY_NAMES: &[&str] = &[
     "backspace",
@@ -279,7 +1147,7 @@ static SAFE_SINGLE_CHAR_KEYS: &[char] = &[
     // Letters and numbers are handled separately via is_ascii_alphanumeric()
     // Safe punctuation that won't break JavaScript strings
     '!', '@', '#', '$', '%', '^', '&', '*', '(', ')', '-', '_', '=', '+', '[', ']', '{', '}', ';',
-    ':', ',', '.', '<', '>', '/', '?', '|', '~',
+    ':', ',', '.', '<', '>', '/', '?', '|', '~', ' ',
 ];
 
 /// Validate that a key name is safe for use in JavaScript.
@@ -306,24 +1174,136 @@ fn validate_key_name(key: &str) -> Result<()> {
     ))
 }
 
+/// A run of text that is typed the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TextRun<'a> {
+    /// Characters typed as ordinary key input.
+    Keys(&'a str),
+    /// Characters normally entered through an input method (Chinese, Japanese,
+    /// Korean). Typed as a composition so sites listening for composition
+    /// events see the same sequence as with a real IME.
+    Composed(&'a str),
+    /// Characters outside the Basic Multilingual Plane, such as emoji, which
+    /// ChromeDriver cannot send as keys. Inserted as text instead.
+    Inserted(&'a str),
+}
+
+impl<'a> TextRun<'a> {
+    /// The characters of the run.
+    pub(crate) fn text(&self) -> &'a str {
+        match *self {
+            TextRun::Keys(text) | TextRun::Composed(text) | TextRun::Inserted(text) => text,
+        }
+    }
+}
+
+/// Whether a character belongs to a script that is typed through an IME.
+fn is_ime_char(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x11FF // Hangul Jamo
+        | 0x3000..=0x303F // CJK symbols and punctuation
+        | 0x3040..=0x30FF // Hiragana and Katakana
+        | 0x3130..=0x318F // Hangul compatibility Jamo
+        | 0x3400..=0x4DBF // CJK unified ideographs extension A
+        | 0x4E00..=0x9FFF // CJK unified ideographs
+        | 0xAC00..=0xD7AF // Hangul syllables
+        | 0xF900..=0xFAFF // CJK compatibility ideographs
+        | 0xFF00..=0xFFEF // Halfwidth and fullwidth forms
+        | 0x20000..=0x3134F // CJK unified ideographs extensions B to G
+    )
+}
+
+/// Split text into runs of characters that are typed the same way.
+pub(crate) fn text_runs(text: &str) -> Vec<TextRun<'_>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Kind {
+        Keys,
+        Composed,
+        Inserted,
+    }
+    let run = |kind, text| match kind {
+        Kind::Keys => TextRun::Keys(text),
+        Kind::Composed => TextRun::Composed(text),
+        Kind::Inserted => TextRun::Inserted(text),
+    };
+
+    let mut runs = Vec::new();
+    let mut current: Option<(Kind, usize)> = None;
+    for (i, c) in text.char_indices() {
+        let kind = match (c as u32, current) {
+            // Zero-width joiners and variation selectors stay with the emoji
+            // or character they modify
+            (0x200D | 0xFE00..=0xFE0F, Some((kind, _))) => kind,
+            _ if is_ime_char(c) => Kind::Composed,
+            (code, _) if code > 0xFFFF => Kind::Inserted,
+            _ => Kind::Keys,
+        };
+        match current {
+            Some((current_kind, _)) if current_kind == kind => {}
+            Some((current_kind, start)) => {
+                runs.push(run(current_kind, &text[start..i]));
+                current = Some((kind, i));
+            }
+            None => current = Some((kind, i)),
+        }
+    }
+    if let Some((kind, start)) = current {
+        runs.push(run(kind, &text[start..]));
+    }
+    runs
+}
+
+/// JavaScript inserting text at the caret of the focused element, firing the
+/// same `beforeinput` and `input` events as typing.
+pub(crate) fn insert_text_script(text: &str) -> String {
+    let escaped = serde_json::to_string(text).unwrap_or_else(|_| "\"\"".to_string());
+    format!("document.execCommand('insertText', false, {})", escaped)
+}
+
 /// Browser controller that wraps WebDriver operations.
 pub struct BrowserController {
     driver: Arc<Mutex<Option<WebDriver>>>,
     config: Config,
+    /// Screen offset of the frame that actions currently target.
+    /// `(0, 0)` when the top-level document is selected.
+    frame_offset: Mutex<(i64, i64)>,
+    /// Visited URLs, for the navigation budget and loop detection.
+    navigation: NavigationTracker,
+    /// Previous state of each tab, for change summaries.
+    state_delta: StateDeltaTracker,
+    /// Scroll position of recently shown pages, restored on history navigation.
+    scroll_memory: ScrollMemory,
+    /// Emulated media settings of each tab.
+    media_emulation: MediaEmulations,
     /// Tracks whether the browser was opened (and thus needs cleanup)
     was_opened: AtomicBool,
     /// Tracks whether close() was called
     was_closed: AtomicBool,
+    /// Task keeping the WebDriver session alive while idle.
+    keepalive: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+    /// Why the keep-alive found the session gone, reported by the next action.
+    session_lost: Arc<std::sync::Mutex<Option<String>>>,
 }
 
 impl BrowserController {
     /// Create a new browser controller with the given configuration.
     pub fn new(config: Config) -> Self {
+        let navigation = NavigationTracker::new(
+            config.max_navigations_per_domain,
+            config.detect_navigation_loops,
+        );
         Self {
             driver: Arc::new(Mutex::new(None)),
             config,
+            frame_offset: Mutex::new((0, 0)),
+            navigation,
+            state_delta: StateDeltaTracker::default(),
+            scroll_memory: ScrollMemory::default(),
+            media_emulation: MediaEmulations::default(),
             was_opened: AtomicBool::new(false),
             was_closed: AtomicBool::new(false),
+            keepalive: std::sync::Mutex::new(None),
+            session_lost: Arc::new(std::sync::Mutex::new(None)),
         }
     }
 
@@ -350,6 +1330,10 @@ impl BrowserController {
             BrowserType::Safari => self.create_safari_driver(&webdriver_url).await?,
         };
 
+        self.apply_session_timeouts(&driver).await;
+        self.install_notification_capture(&driver).await;
+        self.install_activity_tracking(&driver).await;
+
         // Set window size
         if self.config.connection_mode != ConnectionMode::Cdp {
             driver
@@ -357,17 +1341,101 @@ impl BrowserController {
                 .await?;
 
             // Navigate to initial URL
-            driver.goto(&self.config.initial_url).await?;
+            driver.goto(self.config.start_url()).await?;
+
+            if !self.config.initial_tabs.is_empty() {
+                self.open_initial_tabs(&driver).await?;
+            }
         }
 
         *driver_guard = Some(driver);
-        self.was_opened.store(true, Ordering::SeqCst);
+        if self.was_opened.swap(true, Ordering::SeqCst) {
+            metrics::record_browser_restart();
+        }
         drop(driver_guard);
+        self.session_lost.lock().unwrap().take();
+        self.start_keepalive();
 
         info!("Browser opened successfully");
         self.current_state().await
     }
 
+    /// Set the page load and script timeouts of a new session.
+    async fn apply_session_timeouts(&self, driver: &WebDriver) {
+        if let Err(e) = driver
+            .set_page_load_timeout(self.config.webdriver_page_load_timeout)
+            .await
+        {
+            warn!("Failed to set the page load timeout: {}", e);
+        }
+        if let Err(e) = driver
+            .set_script_timeout(self.config.webdriver_script_timeout)
+            .await
+        {
+            warn!("Failed to set the script timeout: {}", e);
+        }
+    }
+
+    /// Send a cheap command to the session every keep-alive interval, so a
+    /// server that expires idle sessions keeps it. A session found gone is
+    /// dropped, and the next action reports why instead of failing with the
+    /// server's error.
+    fn start_keepalive(&self) {
+        let interval = self.config.webdriver_keepalive_interval;
+        if interval.is_zero() {
+            return;
+        }
+        let driver = Arc::clone(&self.driver);
+        let session_lost = Arc::clone(&self.session_lost);
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(session) = driver.lock().await.clone() else {
+                    break;
+                };
+                match session.current_url().await {
+                    Ok(_) => debug!("WebDriver keep-alive succeeded"),
+                    Err(e) if is_session_gone_error(&e.to_string()) => {
+                        warn!("The WebDriver session is gone: {}", e);
+                        *session_lost.lock().unwrap() = Some(e.to_string());
+                        driver.lock().await.take();
+                        break;
+                    }
+                    Err(e) => debug!("WebDriver keep-alive failed: {}", e),
+                }
+            }
+        });
+        if let Some(previous) = self.keepalive.lock().unwrap().replace(task) {
+            previous.abort();
+        }
+    }
+
+    /// Stop the keep-alive task, if running.
+    fn stop_keepalive(&self) {
+        if let Some(task) = self.keepalive.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+
+    /// Open each configured initial tab, then return focus to the first tab.
+    async fn open_initial_tabs(&self, driver: &WebDriver) -> Result<()> {
+        let first_tab = driver.window().await?;
+
+        for url in &self.config.initial_tabs {
+            let handle = driver.new_tab().await?;
+            driver.switch_to_window(handle).await?;
+            self.apply_stealth_scripts(driver).await;
+            if let Err(e) = driver.goto(&normalize_url(url)).await {
+                // Keep going - one unreachable site shouldn't block startup
+                warn!("Failed to open initial tab {}: {}", url, e);
+            }
+        }
+
+        driver.switch_to_window(first_tab).await?;
+        info!("Opened {} initial tab(s)", self.config.initial_tabs.len());
+        Ok(())
+    }
+
     /// Create a Chrome WebDriver.
     async fn create_chrome_driver(&self, webdriver_url: &str) -> Result<WebDriver> {
         let mut caps = DesiredCapabilities::chrome();
@@ -387,11 +1455,7 @@ impl BrowserController {
         }
 
         let driver = WebDriver::new(webdriver_url, caps).await?;
-
-        // Apply stealth scripts for Chrome if undetected mode is enabled
-        if self.config.undetected && self.config.connection_mode != ConnectionMode::Cdp {
-            self.apply_chromium_stealth_scripts(&driver).await;
-        }
+        self.apply_stealth_scripts(&driver).await;
 
         Ok(driver)
     }
@@ -415,11 +1479,7 @@ impl BrowserController {
         }
 
         let driver = WebDriver::new(webdriver_url, caps).await?;
-
-        // Apply stealth scripts for Edge if undetected mode is enabled
-        if self.config.undetected && self.config.connection_mode != ConnectionMode::Cdp {
-            self.apply_chromium_stealth_scripts(&driver).await;
-        }
+        self.apply_stealth_scripts(&driver).await;
 
         Ok(driver)
     }
@@ -443,18 +1503,7 @@ impl BrowserController {
         }
 
         let driver = WebDriver::new(webdriver_url, caps).await?;
-
-        // Apply Firefox-specific stealth if undetected mode is enabled
-        if self.config.undetected {
-            let stealth_script = r#"
-                Object.defineProperty(navigator, 'webdriver', {
-                    get: () => undefined
-                });
-            "#;
-            if let Err(e) = driver.execute(stealth_script, vec![]).await {
-                warn!("Failed to apply Firefox stealth script: {}", e);
-            }
-        }
+        self.apply_stealth_scripts(&driver).await;
 
         Ok(driver)
     }
@@ -470,7 +1519,7 @@ impl BrowserController {
     /// Configure Chromium-based browser capabilities (Chrome/Edge).
     fn configure_chromium_caps<C: ChromiumLikeCapabilities>(&self, caps: &mut C) -> Result<()> {
         if self.config.headless {
-            caps.add_arg("--headless=new")?;
+            caps.add_arg(self.config.headless_mode.chromium_arg())?;
         }
         caps.add_arg("--disable-extensions")?;
         caps.add_arg("--disable-plugins")?;
@@ -483,12 +1532,31 @@ impl BrowserController {
             "--window-size={},{}",
             self.config.screen_width, self.config.screen_height
         ))?;
+        if let Some(app) = self.config.app_mode_arg() {
+            caps.add_arg(&app)?;
+        }
+        if self.config.mute_audio {
+            caps.add_arg("--mute-audio")?;
+        }
+        for arg in self.config.media_device_args() {
+            caps.add_arg(arg)?;
+        }
+        if let Some(ref dir) = self.config.download_dir {
+            caps.add_experimental_option(
+                "prefs",
+                serde_json::json!({
+                    "download.default_directory": dir,
+                    "download.prompt_for_download": false,
+                }),
+            )?;
+        }
 
         // Undetected mode settings (inspired by patchright/undetected-chromedriver)
         if self.config.undetected {
             info!("Enabling undetected mode");
             caps.add_exclude_switch("enable-automation")?;
             caps.add_experimental_option("useAutomationExtension", false)?;
+            caps.add_arg("--disable-blink-features=AutomationControlled")?;
             caps.add_arg("--disable-infobars")?;
             caps.add_arg("--disable-popup-blocking")?;
             caps.add_arg("--disable-notifications")?;
@@ -502,9 +1570,35 @@ impl BrowserController {
         Ok(())
     }
 
+    /// Apply the undetected-mode scripts to the current tab, when enabled.
+    /// Scripts only reach the tab they are installed in, so every tab the
+    /// controller opens goes through here.
+    async fn apply_stealth_scripts(&self, driver: &WebDriver) {
+        // A browser attached over CDP keeps its own settings
+        if !self.config.undetected || self.config.connection_mode == ConnectionMode::Cdp {
+            return;
+        }
+        match self.config.browser_type {
+            BrowserType::Chrome | BrowserType::Edge => {
+                self.apply_chromium_stealth_scripts(driver).await
+            }
+            BrowserType::Firefox => {
+                let stealth_script = r#"
+                    Object.defineProperty(navigator, 'webdriver', {
+                        get: () => undefined
+                    });
+                "#;
+                if let Err(e) = driver.execute(stealth_script, vec![]).await {
+                    warn!("Failed to apply Firefox stealth script: {}", e);
+                }
+            }
+            BrowserType::Safari => {}
+        }
+    }
+
     /// Apply stealth scripts for Chromium-based browsers.
     async fn apply_chromium_stealth_scripts(&self, driver: &WebDriver) {
-        let stealth_script = r#"
+        let navigator_script = r#"
             Object.defineProperty(navigator, 'webdriver', {
                 get: () => undefined
             });
@@ -540,6 +1634,11 @@ impl BrowserController {
                 get: () => ['en-US', 'en']
             });
         "#;
+        let stealth_script = format!(
+            "{}\n{}",
+            navigator_script,
+            media_devices::DEVICE_STUB_SCRIPT
+        );
 
         // Use CDP to add script that runs on every new document
         let dev_tools = ChromeDevTools::new(driver.handle.clone());
@@ -557,53 +1656,353 @@ impl BrowserController {
         }
 
         // Also execute immediately for the current page
-        if let Err(e) = driver.execute(stealth_script, vec![]).await {
+        if let Err(e) = driver.execute(&stealth_script, vec![]).await {
             warn!("Failed to execute stealth script: {}", e);
         }
     }
 
-    /// Close the browser.
-    #[allow(dead_code)]
-    pub async fn close(&self) -> Result<()> {
-        let mut driver_guard = self.driver.lock().await;
-        if let Some(driver) = driver_guard.take() {
-            driver.quit().await?;
-            self.was_closed.store(true, Ordering::SeqCst);
-            info!("Browser closed");
-        }
-        Ok(())
-    }
+    /// Reload a crashed tab, returning the error to report.
+    async fn recover_from_crash(&self, driver: &WebDriver) -> PageCrashed {
+        warn!("Tab crashed, reloading it");
+        metrics::record_page_crash();
+        self.leave_frame(driver).await;
+        let reloaded = match driver.refresh().await {
+            Ok(()) => {
+                let _ = wait_for_page_ready(driver).await;
+                self.install_notification_capture(driver).await;
+                true
+            }
+            Err(e) => {
+                warn!("Failed to reload crashed tab: {}", e);
+                false
+            }
+        };
+        PageCrashed { reloaded }
+    }
+
+    /// Record web notifications in the current tab, now and on every document
+    /// it loads later (Chromium only; other browsers rely on
+    /// [`Self::get_notifications`] reinstalling capture after navigation).
+    async fn install_notification_capture(&self, driver: &WebDriver) {
+        if !self.config.capture_notifications {
+            return;
+        }
+
+        if matches!(
+            self.config.browser_type,
+            BrowserType::Chrome | BrowserType::Edge
+        ) {
+            let dev_tools = ChromeDevTools::new(driver.handle.clone());
+            let cdp_cmd = serde_json::json!({
+                "source": notifications::CAPTURE_SCRIPT
+            });
+            if let Err(e) = dev_tools
+                .execute_cdp_with_params("Page.addScriptToEvaluateOnNewDocument", cdp_cmd)
+                .await
+            {
+                warn!("Failed to add notification capture script via CDP: {}", e);
+            }
+        }
+
+        if let Err(e) = driver.execute(notifications::CAPTURE_SCRIPT, vec![]).await {
+            warn!("Failed to install notification capture: {}", e);
+        }
+    }
+
+    /// Track DOM mutations and console errors in the current tab, on every
+    /// document it loads (Chromium only; other browsers start tracking when a
+    /// state is first read on the page).
+    async fn install_activity_tracking(&self, driver: &WebDriver) {
+        if !self.config.state_delta
+            || !matches!(
+                self.config.browser_type,
+                BrowserType::Chrome | BrowserType::Edge
+            )
+        {
+            return;
+        }
+        let dev_tools = ChromeDevTools::new(driver.handle.clone());
+        let cdp_cmd = serde_json::json!({ "source": state_delta::track_script() });
+        if let Err(e) = dev_tools
+            .execute_cdp_with_params("Page.addScriptToEvaluateOnNewDocument", cdp_cmd)
+            .await
+        {
+            warn!("Failed to add activity tracking script via CDP: {}", e);
+        }
+    }
+
+    /// Whether the page asks the user to sign in, when login walls are
+    /// detected. Without page signals, only the URL is checked.
+    async fn login_required(&self, driver: &WebDriver, url: &str) -> bool {
+        if !self.config.detect_login || url.starts_with("about:") {
+            return false;
+        }
+        let signals = driver
+            .execute(format!("return {}", login_wall::SIGNALS_SCRIPT), vec![])
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|value| Ok(serde_json::from_value(value.json().clone())?))
+            .unwrap_or_else(|e| {
+                debug!("Failed to read login signals: {}", e);
+                LoginSignals::default()
+            });
+        login_wall::login_required(url, &signals)
+    }
+
+    /// Zoom the page out to fit its content width, when enabled. Pages with a
+    /// frame selected are left alone, as zooming would move the frame.
+    async fn zoom_to_fit(&self, driver: &WebDriver) {
+        if !self.config.zoom_to_fit || *self.frame_offset.lock().await != (0, 0) {
+            return;
+        }
+        let script = format!(
+            "return {}",
+            zoom_to_fit::fit_script(self.config.zoom_to_fit_min)
+        );
+        if let Err(e) = driver.execute(script, vec![]).await {
+            debug!("Failed to zoom page to fit: {}", e);
+        }
+    }
+
+    /// Changes since the tab's previous state, when they are reported.
+    /// Failures are ignored so a response is never lost over them.
+    async fn state_delta(&self, driver: &WebDriver, tab: &str, url: &str) -> Option<StateDelta> {
+        if !self.config.state_delta {
+            return None;
+        }
+        let result = driver
+            .execute(format!("return {}", state_delta::read_script()), vec![])
+            .await;
+        match result.map(|value| serde_json::from_value::<PageActivity>(value.json().clone())) {
+            Ok(Ok(activity)) => Some(self.state_delta.diff(tab, url, activity)),
+            Ok(Err(e)) => {
+                debug!("Failed to parse page activity: {}", e);
+                None
+            }
+            Err(e) => {
+                debug!("Failed to read page activity: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Remember the scroll position of the page a tab shows.
+    async fn record_scroll(&self, driver: &WebDriver, tab: &str, url: &str) {
+        if !self.config.restore_scroll || url.starts_with("about:") {
+            return;
+        }
+        let result = driver
+            .execute(format!("return {}", scroll_memory::POSITION_SCRIPT), vec![])
+            .await;
+        match result.map(|value| serde_json::from_value::<ScrollPosition>(value.json().clone())) {
+            Ok(Ok(position)) => self.scroll_memory.record(tab, url, position),
+            Ok(Err(e)) => debug!("Failed to parse scroll position: {}", e),
+            Err(e) => debug!("Failed to read scroll position: {}", e),
+        }
+    }
+
+    /// Scroll a page reached through history back to where it was last seen.
+    /// Returns the restored offset, if the page had been scrolled and is not
+    /// the page at `from_url` the navigation started on.
+    async fn restore_scroll(&self, driver: &WebDriver, from_url: &str) -> Option<ScrollPosition> {
+        if !self.config.restore_scroll {
+            return None;
+        }
+        let tab = driver.window().await.ok()?.to_string();
+        let url = driver.current_url().await.ok()?.to_string();
+        if url == from_url {
+            return None;
+        }
+        let saved = self.scroll_memory.saved(&tab, &url)?;
+        if saved.is_origin() {
+            return None;
+        }
+        let result = driver
+            .execute(
+                format!("return {}", scroll_memory::restore_script(saved)),
+                vec![],
+            )
+            .await;
+        match result.map(|value| serde_json::from_value::<ScrollPosition>(value.json().clone())) {
+            Ok(Ok(position)) => Some(position),
+            Ok(Err(e)) => {
+                debug!("Failed to parse scroll position: {}", e);
+                None
+            }
+            Err(e) => {
+                debug!("Failed to restore scroll position: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Notifications not yet reported, when they are included in responses.
+    /// Failures are ignored so a response is never lost over them.
+    async fn unreported_notifications(&self, driver: &WebDriver) -> Vec<CapturedNotification> {
+        if !self.config.capture_notifications || !self.config.notifications_in_response {
+            return Vec::new();
+        }
+        match driver
+            .execute(
+                format!("return {}", notifications::UNREPORTED_SCRIPT),
+                vec![],
+            )
+            .await
+        {
+            Ok(result) => notifications::parse_notifications(result.json())
+                .ok()
+                .flatten()
+                .unwrap_or_default(),
+            Err(e) => {
+                debug!("Failed to read notifications: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Close the browser.
+    #[allow(dead_code)]
+    pub async fn close(&self) -> Result<()> {
+        self.stop_keepalive();
+        let mut driver_guard = self.driver.lock().await;
+        if let Some(driver) = driver_guard.take() {
+            driver.quit().await?;
+            self.was_closed.store(true, Ordering::SeqCst);
+            info!("Browser closed");
+        }
+        Ok(())
+    }
+
+    /// Get a handle to the WebDriver session.
+    /// The lock is only held while cloning, so long-running actions do not block
+    /// read-only queries; mutations are serialized by the server's action queue.
+    async fn session(&self) -> Result<WebDriver> {
+        self.driver.lock().await.clone().ok_or_else(|| {
+            match self.session_lost.lock().unwrap().as_deref() {
+                Some(reason) => anyhow::anyhow!(
+                    "The WebDriver session expired ({}); call open_web_browser to start a new one",
+                    reason
+                ),
+                None => anyhow::anyhow!("Browser not opened"),
+            }
+        })
+    }
 
     /// Get the current state (screenshot and URL).
     pub async fn current_state(&self) -> Result<EnvState> {
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let driver = &self.session().await?;
 
         // Wait for page to be ready
+        let settle_started = Instant::now();
         let _ = wait_for_page_ready(driver).await;
 
         // Additional settle time for dynamic content
         tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        let settle_ms = settle_started.elapsed().as_millis() as u64;
 
-        // Use retry for screenshot in case of transient failures
-        let screenshot_bytes =
-            retry_async("screenshot", || async { driver.screenshot_as_png().await }).await?;
-        let screenshot = BASE64.encode(&screenshot_bytes);
         let url = driver.current_url().await?.to_string();
+        self.zoom_to_fit(driver).await;
+        let screenshot_started = Instant::now();
+        let mut screenshot_bytes = self.capture_screenshot(driver).await?;
+        // Pages sometimes paint nothing right after a navigation
+        if !url.starts_with("about:") {
+            for attempt in 1..=self.config.blank_screenshot_retries {
+                let (bytes, blank) = check_blank_screenshot(screenshot_bytes).await?;
+                if !blank {
+                    screenshot_bytes = bytes;
+                    break;
+                }
+                debug!(
+                    "Screenshot is blank, capturing again ({}/{})",
+                    attempt, self.config.blank_screenshot_retries
+                );
+                tokio::time::sleep(Duration::from_millis(BLANK_SCREENSHOT_RETRY_DELAY_MS)).await;
+                screenshot_bytes = self.capture_screenshot(driver).await?;
+            }
+        }
+        let screenshot =
+            encode_screenshot(screenshot_bytes, self.config.max_screenshot_dimension).await?;
+        let timings = StateTimings {
+            settle_ms,
+            screenshot_ms: screenshot_started.elapsed().as_millis() as u64,
+        };
+        let notifications = self.unreported_notifications(driver).await;
+        let tab = if self.navigation.is_enabled()
+            || self.config.state_delta
+            || self.config.restore_scroll
+        {
+            let window = driver.window().await.map(|handle| handle.to_string());
+            window.unwrap_or_default()
+        } else {
+            String::new()
+        };
+        let warnings = self.navigation.record(&tab, &url);
+        let delta = self.state_delta(driver, &tab, &url).await;
+        self.record_scroll(driver, &tab, &url).await;
+        let login_required = self.login_required(driver, &url).await;
+
+        Ok(EnvState {
+            screenshot,
+            url,
+            notifications,
+            warnings,
+            delta,
+            effect_detected: None,
+            timings,
+            login_required,
+        })
+    }
+
+    /// Take a screenshot, retrying transient failures and recovering a crashed tab.
+    async fn capture_screenshot(&self, driver: &WebDriver) -> Result<Vec<u8>> {
+        match retry_async("screenshot", || async { driver.screenshot_as_png().await }).await {
+            Ok(bytes) => Ok(bytes),
+            Err(e) if is_crash_error(&e.to_string()) => {
+                Err(self.recover_from_crash(driver).await.into())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
 
-        Ok(EnvState { screenshot, url })
+    /// Wait until the element at (`x`, `y`) stops moving, up to the configured
+    /// click stability timeout. Returns whether it settled in time.
+    async fn wait_for_stable_target(&self, driver: &WebDriver, x: i64, y: i64) -> bool {
+        let timeout = self.config.click_stability_timeout;
+        let start = std::time::Instant::now();
+        let mut unchanged = 0;
+        let mut reset = true;
+        while start.elapsed() < timeout {
+            let same = driver
+                .execute(format!("return {}", stability_script(x, y, reset)), vec![])
+                .await
+                .map(|value| value.json().as_bool().unwrap_or(false))
+                .unwrap_or(false);
+            reset = false;
+            unchanged = if same { unchanged + 1 } else { 0 };
+            if unchanged >= STABLE_CHECKS {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(STABILITY_POLL_INTERVAL_MS)).await;
+        }
+        false
     }
 
     /// Click at specific coordinates.
     pub async fn click_at(&self, x: i64, y: i64) -> Result<EnvState> {
-        validate_coordinates(x, y, self.config.screen_width, self.config.screen_height)?;
+        let (x, y) = validate_coordinates(x, y, &self.config)?;
         debug!("Clicking at ({}, {})", x, y);
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let (x, y) = self.to_frame_coordinates(x, y).await;
+        let driver = &self.session().await?;
+        if !self.config.click_stability_timeout.is_zero()
+            && !self.wait_for_stable_target(driver, x, y).await
+        {
+            debug!("Element at ({}, {}) still moving, clicking anyway", x, y);
+        }
+        let start_url = driver.current_url().await?.to_string();
+        let watching = driver
+            .execute(format!("return {}", CLICK_EFFECT_WATCH_SCRIPT), vec![])
+            .await
+            .is_ok();
 
         // Try to find element at coordinates and click it with proper event dispatch
         // Note: x and y are i64, so format! only produces numeric values (no injection risk)
@@ -692,50 +2091,66 @@ impl BrowserController {
         // Wait for potential navigation or page changes
         let _ = wait_for_page_ready(driver).await;
 
-        drop(driver_guard);
-        self.current_state().await
+        let mut state = self.current_state().await?;
+        if watching && state.url == start_url {
+            let changed = driver
+                .execute(format!("return {}", CLICK_EFFECT_READ_SCRIPT), vec![])
+                .await
+                .map(|value| value.json() != &serde_json::Value::Bool(false))
+                .unwrap_or(true);
+            if !changed {
+                state.effect_detected = Some(false);
+            }
+        }
+        Ok(state)
     }
 
-    /// Hover at specific coordinates.
-    pub async fn hover_at(&self, x: i64, y: i64) -> Result<EnvState> {
-        validate_coordinates(x, y, self.config.screen_width, self.config.screen_height)?;
-        debug!("Hovering at ({}, {})", x, y);
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+    /// Hover at specific coordinates, keeping the hover alive for `duration_ms`
+    /// when given.
+    pub async fn hover_at(&self, x: i64, y: i64, duration_ms: Option<u64>) -> Result<EnvState> {
+        let (x, y) = validate_coordinates(x, y, &self.config)?;
+        if let Some(duration_ms) = duration_ms {
+            validate_hover_duration(duration_ms)?;
+        }
+        debug!("Hovering at ({}, {}) for {:?}ms", x, y, duration_ms);
+        let (x, y) = self.to_frame_coordinates(x, y).await;
+        let driver = &self.session().await?;
 
         // Use JavaScript to simulate hover with full mouse event sequence
-        let script = format!(
-            r#"
-            (function() {{
-                var element = document.elementFromPoint({}, {});
-                if (element) {{
-                    // Dispatch mouseenter and mouseover events for proper hover behavior
-                    var events = ['mouseenter', 'mouseover', 'mousemove'];
-                    events.forEach(function(eventType) {{
-                        var event = new MouseEvent(eventType, {{
-                            view: window,
-                            bubbles: true,
-                            cancelable: true,
-                            clientX: {},
-                            clientY: {}
-                        }});
-                        element.dispatchEvent(event);
-                    }});
-                    return true;
-                }}
-                return false;
-            }})();
-            "#,
-            x, y, x, y
-        );
-        driver.execute(&script, vec![]).await?;
+        driver
+            .execute(format!("return {}", hover_script(x, y, true)), vec![])
+            .await?;
+
+        match duration_ms {
+            Some(duration_ms) => {
+                let deadline = Instant::now() + Duration::from_millis(duration_ms);
+                while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+                    tokio::time::sleep(
+                        remaining.min(Duration::from_millis(HOVER_REFRESH_INTERVAL_MS)),
+                    )
+                    .await;
+                    driver
+                        .execute(format!("return {}", hover_script(x, y, false)), vec![])
+                        .await?;
+                }
+            }
+            // Give time for hover menus/effects to appear
+            None => tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await,
+        }
 
-        // Give time for hover menus/effects to appear
+        self.current_state().await
+    }
+
+    /// End a hover by moving the mouse off the hovered element.
+    pub async fn move_mouse_away(&self) -> Result<EnvState> {
+        debug!("Moving mouse away");
+        let driver = &self.session().await?;
+
+        driver
+            .execute(format!("return {}", MOVE_MOUSE_AWAY_SCRIPT), vec![])
+            .await?;
         tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
 
-        drop(driver_guard);
         self.current_state().await
     }
 
@@ -746,14 +2161,13 @@ impl BrowserController {
         y: i64,
         text: &str,
         press_enter: bool,
-        clear_before_typing: bool,
+        mode: InsertionMode,
+        simulate_keystrokes: bool,
     ) -> Result<EnvState> {
-        validate_coordinates(x, y, self.config.screen_width, self.config.screen_height)?;
-        debug!("Typing at ({}, {}): {}", x, y, text);
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let (x, y) = validate_coordinates(x, y, &self.config)?;
+        debug!("Typing at ({}, {}) with {:?}: {}", x, y, mode, text);
+        let (x, y) = self.to_frame_coordinates(x, y).await;
+        let driver = &self.session().await?;
 
         // Click at the position first
         // Note: x and y are i64, so format! only produces numeric values (no injection risk)
@@ -773,14 +2187,33 @@ impl BrowserController {
         // Find active element and interact with it
         let active_element = driver.active_element().await?;
 
-        if clear_before_typing {
-            // Select all and delete
-            active_element.send_keys(Key::Control + "a").await?;
+        if let Some(script) = prepare_insertion_script(mode) {
+            driver.execute(script, vec![]).await?;
+        }
+        if mode == InsertionMode::ReplaceAll {
+            // Delete the selected content
             active_element.send_keys(Key::Delete).await?;
         }
 
-        // Type the text
-        active_element.send_keys(text).await?;
+        // Type the text. ChromeDriver rejects characters outside the BMP, so
+        // those are inserted with an editing command instead.
+        for run in text_runs(text) {
+            match run {
+                TextRun::Keys(run) if simulate_keystrokes => {
+                    // Pause between characters so debounced listeners see each one
+                    for c in run.chars() {
+                        active_element.send_keys(c.to_string()).await?;
+                        tokio::time::sleep(Duration::from_millis(KEYSTROKE_DELAY_MS)).await;
+                    }
+                }
+                TextRun::Keys(run) | TextRun::Composed(run) => {
+                    active_element.send_keys(run).await?;
+                }
+                TextRun::Inserted(run) => {
+                    driver.execute(insert_text_script(run), vec![]).await?;
+                }
+            }
+        }
 
         if press_enter {
             active_element.send_keys(Key::Enter).await?;
@@ -788,29 +2221,33 @@ impl BrowserController {
 
         tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
 
-        drop(driver_guard);
         self.current_state().await
     }
 
     /// Scroll the entire document.
-    pub async fn scroll_document(&self, direction: &str) -> Result<EnvState> {
-        debug!("Scrolling document: {}", direction);
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
-
-        let script = match direction.to_lowercase().as_str() {
-            "up" => "window.scrollBy(0, -window.innerHeight * 0.8);",
-            "down" => "window.scrollBy(0, window.innerHeight * 0.8);",
-            "left" => "window.scrollBy(-window.innerWidth * 0.5, 0);",
-            "right" => "window.scrollBy(window.innerWidth * 0.5, 0);",
-            _ => return Err(anyhow::anyhow!("Invalid scroll direction: {}", direction)),
-        };
+    pub async fn scroll_document(
+        &self,
+        direction: &str,
+        amount: ScrollAmount,
+        smooth: bool,
+    ) -> Result<EnvState> {
+        debug!("Scrolling document: {} by {}", direction, amount);
+        let script = scroll_document_script(direction, amount, smooth)?;
+        let driver = &self.session().await?;
 
         driver.execute(script, vec![]).await?;
 
-        drop(driver_guard);
+        match driver
+            .execute(format!("return {}", SCROLL_SETTLE_SCRIPT), vec![])
+            .await
+        {
+            Ok(settled) if settled.json().as_bool() == Some(false) => {
+                debug!("Page did not settle after scrolling")
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to wait for scrolling to finish: {}", e),
+        }
+
         self.current_state().await
     }
 
@@ -822,16 +2259,14 @@ impl BrowserController {
         direction: &str,
         magnitude: i64,
     ) -> Result<EnvState> {
-        validate_coordinates(x, y, self.config.screen_width, self.config.screen_height)?;
+        let (x, y) = validate_coordinates(x, y, &self.config)?;
         validate_magnitude(magnitude)?;
         debug!(
             "Scrolling at ({}, {}) direction: {} magnitude: {}",
             x, y, direction, magnitude
         );
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let (x, y) = self.to_frame_coordinates(x, y).await;
+        let driver = &self.session().await?;
 
         let (dx, dy) = match direction.to_lowercase().as_str() {
             "up" => (0, -magnitude),
@@ -855,10 +2290,234 @@ impl BrowserController {
 
         driver.execute(&script, vec![]).await?;
 
-        drop(driver_guard);
         self.current_state().await
     }
 
+    /// Scroll the element at the given coordinates to the top of the viewport,
+    /// below any fixed or sticky header. Returns the top offset that was left free.
+    pub async fn scroll_to_element(
+        &self,
+        x: i64,
+        y: i64,
+        offset: Option<i64>,
+    ) -> Result<(i64, EnvState)> {
+        let (x, y) = validate_coordinates(x, y, &self.config)?;
+        debug!("Scrolling element at ({}, {}) into view", x, y);
+        let (x, y) = self.to_frame_coordinates(x, y).await;
+        let driver = &self.session().await?;
+
+        let script = format!("return {}", scroll_to_element_script(x, y, offset));
+        let used_offset = driver
+            .execute(&script, vec![])
+            .await?
+            .json()
+            .as_f64()
+            .ok_or_else(|| anyhow::anyhow!("No element found at ({}, {})", x, y))?;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+
+        Ok((used_offset as i64, self.current_state().await?))
+    }
+
+    /// Select text in an element, or place the caret when `start` equals `end`.
+    /// Returns the selected text.
+    pub async fn select_text(
+        &self,
+        target: ElementTarget,
+        start: Option<u32>,
+        end: Option<u32>,
+    ) -> Result<(String, EnvState)> {
+        debug!(
+            "Selecting text in {:?} from {:?} to {:?}",
+            target, start, end
+        );
+        let target = self.resolve_target(target).await?;
+        let driver = &self.session().await?;
+
+        let script = format!("return {}", select_text_script(&target, start, end));
+        let result = driver.execute(&script, vec![]).await?;
+        let text = parse_element_result(result.json())?;
+
+        Ok((text, self.current_state().await?))
+    }
+
+    /// Set a color or range input and return the value it took.
+    pub async fn set_control_value(
+        &self,
+        target: ElementTarget,
+        value: ControlValue,
+    ) -> Result<(String, EnvState)> {
+        debug!("Setting {:?} to {:?}", target, value);
+        let target = self.resolve_target(target).await?;
+        let driver = &self.session().await?;
+
+        let script = format!(
+            "return {}",
+            input_controls::set_value_script(&target, &value)
+        );
+        let result = driver.execute(&script, vec![]).await?;
+        let value = parse_element_result(result.json())?;
+
+        Ok((value, self.current_state().await?))
+    }
+
+    /// Draw a temporary box around an element and return a description of it.
+    pub async fn highlight_element(
+        &self,
+        target: ElementTarget,
+        duration_ms: u64,
+    ) -> Result<(String, EnvState)> {
+        debug!("Highlighting {:?} for {}ms", target, duration_ms);
+        let target = self.resolve_target(target).await?;
+        let script = format!("return {}", highlight_script(&target, duration_ms)?);
+        let driver = &self.session().await?;
+
+        let result = driver.execute(&script, vec![]).await?;
+        let description = parse_element_result(result.json())?;
+
+        Ok((description, self.current_state().await?))
+    }
+
+    /// Fingerprint an element so `click_named` can find it again.
+    pub async fn remember_element(&self, target: ElementTarget) -> Result<ElementFingerprint> {
+        debug!("Remembering {:?}", target);
+        let target = self.resolve_target(target).await?;
+        let script = format!("return {}", element_aliases::remember_script(&target));
+        let driver = &self.session().await?;
+
+        let result = driver.execute(&script, vec![]).await?;
+        element_aliases::parse_result(result.json())
+    }
+
+    /// Find a remembered element again and click its center. Returns the
+    /// point clicked, in screenshot coordinates.
+    pub async fn click_named(
+        &self,
+        fingerprint: &ElementFingerprint,
+    ) -> Result<((i64, i64), EnvState)> {
+        debug!("Clicking remembered {}", fingerprint.describe());
+        let script = format!("return {}", element_aliases::locate_script(fingerprint));
+        let driver = &self.session().await?;
+
+        let result = driver.execute(&script, vec![]).await?;
+        let located: Located = element_aliases::parse_result(result.json())?;
+        let (offset_x, offset_y) = *self.frame_offset.lock().await;
+        let (x, y) = (located.x + offset_x, located.y + offset_y);
+        Ok(((x, y), self.click_at(x, y).await?))
+    }
+
+    /// Find the first visible element matching a locator and scroll it into
+    /// view. Its center is returned in screenshot coordinates.
+    pub async fn locate_element(&self, locator: &Locator) -> Result<LocatedElement> {
+        debug!("Locating element by {}", locator);
+        let script = format!("return {}", element_locator::locate_script(locator));
+        let driver = &self.session().await?;
+
+        let result = driver.execute(&script, vec![]).await?;
+        let mut element = element_locator::parse_located(result.json())?;
+        let (offset_x, offset_y) = *self.frame_offset.lock().await;
+        element.x += offset_x;
+        element.y += offset_y;
+        Ok(element)
+    }
+
+    /// Step the zoom of the current document by `step` levels, or reset it
+    /// with 0. Returns the new zoom factor.
+    pub async fn zoom_page(&self, step: i32) -> Result<(f64, EnvState)> {
+        debug!("Zooming page by {} steps", step);
+        let script = format!("return {}", shortcuts::zoom_script(step));
+        let driver = &self.session().await?;
+
+        let result = driver.execute(&script, vec![]).await?;
+        let zoom = result
+            .json()
+            .as_f64()
+            .ok_or_else(|| anyhow::anyhow!("Zoom script returned {}", result.json()))?;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        Ok((zoom, self.current_state().await?))
+    }
+
+    /// Press at the first point of a path, move through the others and
+    /// release at the last, with native mouse or touch events.
+    pub async fn draw_path(
+        &self,
+        points: &[PathPoint],
+        duration_ms: u64,
+        pointer: PointerType,
+    ) -> Result<EnvState> {
+        if !matches!(
+            self.config.browser_type,
+            BrowserType::Chrome | BrowserType::Edge
+        ) {
+            return Err(anyhow::anyhow!(
+                "Drawing paths requires a Chromium-based browser (Chrome or Edge)"
+            ));
+        }
+        let points = points
+            .iter()
+            .map(|point| {
+                validate_coordinates(point.x, point.y, &self.config)
+                    .map(|(x, y)| PathPoint { x, y })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let events =
+            draw_path::plan(&points, duration_ms, pointer).map_err(|e| anyhow::anyhow!(e))?;
+        debug!("Drawing a path of {} events", events.len());
+        let driver = &self.session().await?;
+
+        let dev_tools = ChromeDevTools::new(driver.handle.clone());
+        for event in events {
+            tokio::time::sleep(event.delay).await;
+            dev_tools
+                .execute_cdp_with_params(event.method, event.params)
+                .await?;
+        }
+        self.current_state().await
+    }
+
+    /// Capture downscaled strips of the content just above and below the
+    /// viewport.
+    pub async fn context_strips(&self) -> Result<ContextStrips> {
+        if !matches!(
+            self.config.browser_type,
+            BrowserType::Chrome | BrowserType::Edge
+        ) {
+            return Err(anyhow::anyhow!(
+                "Context strips require a Chromium-based browser (Chrome or Edge)"
+            ));
+        }
+        let driver = &self.session().await?;
+
+        let dev_tools = ChromeDevTools::new(driver.handle.clone());
+        let metrics = dev_tools
+            .execute_cdp(context_strips::LAYOUT_METRICS_METHOD)
+            .await?;
+        let plan = context_strips::plan(&metrics)?;
+        let mut strips = ContextStrips::default();
+        for (clip, hidden, strip) in [
+            (plan.above, plan.hidden_above, &mut strips.above),
+            (plan.below, plan.hidden_below, &mut strips.below),
+        ] {
+            let Some(clip) = clip else {
+                continue;
+            };
+            let reply = dev_tools
+                .execute_cdp_with_params(
+                    context_strips::CAPTURE_SCREENSHOT_METHOD,
+                    context_strips::capture_params(&clip),
+                )
+                .await?;
+            let screenshot = reply["data"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Strip capture returned no image"))?;
+            *strip = Some(ContextStrip {
+                screenshot: screenshot.to_string(),
+                covers: clip.height.round() as u32,
+                hidden,
+            });
+        }
+        Ok(strips)
+    }
+
     /// Wait for 5 seconds.
     pub async fn wait_5_seconds(&self) -> Result<EnvState> {
         debug!("Waiting 5 seconds");
@@ -866,68 +2525,193 @@ impl BrowserController {
         self.current_state().await
     }
 
-    /// Navigate back.
-    pub async fn go_back(&self) -> Result<EnvState> {
+    /// Navigate back, restoring the page's scroll position. Returns the
+    /// restored offset along with the state.
+    pub async fn go_back(&self) -> Result<(EnvState, Option<ScrollPosition>)> {
         debug!("Going back");
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let driver = &self.session().await?;
 
+        let from_url = driver.current_url().await?.to_string();
         driver.back().await?;
+        self.leave_frame(driver).await;
         tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        let restored = self.restore_scroll(driver, &from_url).await;
 
-        drop(driver_guard);
-        self.current_state().await
+        Ok((self.current_state().await?, restored))
     }
 
-    /// Navigate forward.
-    pub async fn go_forward(&self) -> Result<EnvState> {
+    /// Navigate forward, restoring the page's scroll position. Returns the
+    /// restored offset along with the state.
+    pub async fn go_forward(&self) -> Result<(EnvState, Option<ScrollPosition>)> {
         debug!("Going forward");
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let driver = &self.session().await?;
 
+        let from_url = driver.current_url().await?.to_string();
         driver.forward().await?;
+        self.leave_frame(driver).await;
         tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        let restored = self.restore_scroll(driver, &from_url).await;
 
-        drop(driver_guard);
-        self.current_state().await
+        Ok((self.current_state().await?, restored))
     }
 
-    /// Navigate to search engine.
-    pub async fn search(&self) -> Result<EnvState> {
-        debug!("Navigating to search engine");
-        self.navigate(&self.config.search_engine_url.clone()).await
+    /// Navigate to a search engine's home page, or to its results for a query.
+    pub async fn search(&self, query: Option<&str>, engine: Option<&str>) -> Result<EnvState> {
+        let engine = self.config.search_engine(engine)?;
+        let Some(query) = query else {
+            debug!("Navigating to search engine: {}", engine.name);
+            return self.navigate(&engine.home_url()).await;
+        };
+        let driver = &self.session().await?;
+        self.open_search(driver, engine, query).await?;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        let _ = wait_for_page_ready(driver).await;
+        self.current_state().await
     }
 
     /// Navigate to a specific URL.
     pub async fn navigate(&self, url: &str) -> Result<EnvState> {
         debug!("Navigating to: {}", url);
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let driver = &self.session().await?;
 
-        let normalized_url = if url.starts_with("http://") || url.starts_with("https://") {
-            url.to_string()
-        } else {
-            format!("https://{}", url)
-        };
+        let normalized_url = normalize_url(url);
 
         driver.goto(&normalized_url).await?;
+        self.leave_frame(driver).await;
 
         // Wait for page to be fully loaded
         let _ = wait_for_page_ready(driver).await;
+        self.apply_recipe(driver).await;
 
-        drop(driver_guard);
         self.current_state().await
     }
 
+    /// Apply the recipe of the loaded page's site, if any: wait for its ready
+    /// selector, then accept its cookie banner. Failures only delay the page.
+    async fn apply_recipe(&self, driver: &WebDriver) {
+        let Ok(url) = driver.current_url().await else {
+            return;
+        };
+        let host = credentials::host_of(url.as_str()).unwrap_or_default();
+        let Some(recipe) = self.config.recipes.for_host(&host) else {
+            return;
+        };
+        debug!("Applying recipe '{}'", recipe.name);
+
+        if let Some(ref wait) = recipe.wait {
+            if let Some(ref selector) = wait.selector {
+                let Ok(script) = recipes::exists_script(selector) else {
+                    return;
+                };
+                let deadline = Instant::now() + Duration::from_millis(wait.timeout_ms());
+                loop {
+                    let found = driver
+                        .execute(format!("return {}", script), vec![])
+                        .await
+                        .map(|value| value.json() == &serde_json::Value::Bool(true))
+                        .unwrap_or(false);
+                    if found || Instant::now() >= deadline {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_millis(recipes::WAIT_POLL_INTERVAL_MS)).await;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(wait.delay_ms)).await;
+        }
+
+        if let Some(ref selector) = recipe.selectors.cookie_accept {
+            let Ok(script) = recipes::click_script(selector) else {
+                return;
+            };
+            match driver.execute(format!("return {}", script), vec![]).await {
+                Ok(value) if value.json() == &serde_json::Value::Bool(true) => {
+                    debug!("Accepted the cookie banner");
+                    tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+                }
+                Ok(_) => {}
+                Err(e) => debug!("Failed to accept the cookie banner: {}", e),
+            }
+        }
+    }
+
+    /// Get the URL of the current page.
+    pub async fn current_url(&self) -> Result<String> {
+        let driver = &self.session().await?;
+        Ok(driver.current_url().await?.to_string())
+    }
+
+    /// Search the web and return the results.
+    pub async fn web_search(
+        &self,
+        query: &str,
+        engine: Option<&str>,
+        num_results: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let engine = self.config.search_engine(engine)?;
+        let driver = &self.session().await?;
+        self.open_search(driver, engine, query).await?;
+
+        // Read the result page until it lists results; reads during the
+        // navigation fail and are retried
+        let script = format!("return {}", web_search::results_script(num_results));
+        let mut results = Vec::new();
+        for _ in 0..web_search::RESULT_ATTEMPTS {
+            tokio::time::sleep(Duration::from_millis(web_search::RESULT_POLL_INTERVAL_MS)).await;
+            match driver.execute(&script, vec![]).await {
+                Ok(value) => results = web_search::parse_results(value.json())?,
+                Err(e) => debug!("Failed to read search results: {}", e),
+            }
+            if !results.is_empty() {
+                break;
+            }
+        }
+        Ok(results)
+    }
+
+    /// Open the engine's results for a query: through its URL template, or by
+    /// typing the query into the search box of its home page.
+    async fn open_search(
+        &self,
+        driver: &WebDriver,
+        engine: &SearchEngine,
+        query: &str,
+    ) -> Result<()> {
+        debug!("Searching {} for: {}", engine.name, query);
+        if let Some(url) = engine.query_url(query) {
+            driver.goto(&normalize_url(&url)).await?;
+            self.leave_frame(driver).await;
+            return Ok(());
+        }
+
+        driver.goto(&normalize_url(&engine.home_url())).await?;
+        self.leave_frame(driver).await;
+        let _ = wait_for_page_ready(driver).await;
+        let host = credentials::host_of(&engine.home_url()).unwrap_or_default();
+        let search_box = self
+            .config
+            .recipes
+            .selectors_for(&host)
+            .search_box
+            .as_deref();
+        let submitted = driver
+            .execute(
+                format!("return {}", web_search::submit_script(query, search_box)?),
+                vec![],
+            )
+            .await?;
+        if submitted.json() != &serde_json::Value::Bool(true) {
+            return Err(anyhow::anyhow!(
+                "No search box found on {}",
+                engine.home_url()
+            ));
+        }
+        Ok(())
+    }
+
     /// Press key combination.
     pub async fn key_combination(&self, keys: Vec<String>) -> Result<EnvState> {
         debug!("Pressing key combination: {:?}", keys);
+        let keys = keys::normalize_keys(&keys, &KeyMapping::from_config(&self.config))?;
 
         if keys.is_empty() {
             return Err(anyhow::anyhow!("No keys provided"));
@@ -938,14 +2722,105 @@ impl BrowserController {
             validate_key_name(key)?;
         }
 
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let driver = &self.session().await?;
+        self.press_chord(driver, &keys).await?;
+
+        self.current_state().await
+    }
+
+    /// Press a series of keys or chords in order, pausing after each step as
+    /// requested.
+    pub async fn press_keys_sequence(&self, mut steps: Vec<KeyStep>) -> Result<EnvState> {
+        debug!("Pressing key sequence of {} steps", steps.len());
+        let mapping = KeyMapping::from_config(&self.config);
+        for step in &mut steps {
+            step.keys = keys::normalize_keys(&step.keys, &mapping)?;
+        }
+        validate_key_steps(&steps)?;
+        for key in steps.iter().flat_map(|step| &step.keys) {
+            validate_key_name(key)?;
+        }
+
+        let driver = &self.session().await?;
+        for step in &steps {
+            self.press_chord(driver, &step.keys).await?;
+            if step.delay_ms > 0 {
+                tokio::time::sleep(Duration::from_millis(step.delay_ms)).await;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(TYPING_DELAY_MS)).await;
+
+        self.current_state().await
+    }
+
+    /// Press Tab, or Shift+Tab when `backwards`, until the element a locator
+    /// describes has focus, then press the activation key on it.
+    pub async fn tab_to_element(
+        &self,
+        locator: &Locator,
+        backwards: bool,
+        max_presses: u32,
+        activation: Activation,
+    ) -> Result<(TabFocus, EnvState)> {
+        debug!("Tabbing to the element with {}", locator);
+        let driver = &self.session().await?;
+        // Modifiers stay pressed until the null key
+        let tab = if backwards {
+            format!(
+                "{}{}\u{E000}",
+                get_key_mapping("shift"),
+                get_key_mapping("tab")
+            )
+        } else {
+            get_key_mapping("tab").to_string()
+        };
+
+        let mut presses = 0;
+        let check = loop {
+            let script = format!(
+                "return {}",
+                keyboard_focus::focus_script(locator, presses == 0)
+            );
+            let check = keyboard_focus::parse_focus(driver.execute(&script, vec![]).await?.json())?;
+            if check.focused {
+                break check;
+            }
+            if check.wrapped || presses >= max_presses {
+                return Err(keyboard_focus::not_reached(locator, presses, check.wrapped));
+            }
+            driver.active_element().await?.send_keys(&tab).await?;
+            presses += 1;
+        };
+
+        let activated = keyboard_focus::activation_key(activation, &check);
+        if let Some(key) = activated {
+            let key = if key == Activation::Space {
+                "space"
+            } else {
+                "enter"
+            };
+            driver
+                .active_element()
+                .await?
+                .send_keys(get_key_mapping(key))
+                .await?;
+            tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        }
+        let focus = TabFocus {
+            tag: check.tag,
+            text: check.text,
+            presses,
+            activated,
+        };
+        Ok((focus, self.current_state().await?))
+    }
 
+    /// Press a single key or chord on the focused element. Keys must already be
+    /// validated.
+    async fn press_chord(&self, driver: &WebDriver, keys: &[String]) -> Result<()> {
         // Build the key sequence using JavaScript
         let mut key_codes = Vec::new();
-        for key in &keys {
+        for key in keys {
             let mapped = get_key_mapping(key);
             key_codes.push(mapped.to_string());
         }
@@ -995,8 +2870,7 @@ impl BrowserController {
             }
         }
 
-        drop(driver_guard);
-        self.current_state().await
+        Ok(())
     }
 
     /// Drag and drop from one position to another.
@@ -1007,21 +2881,18 @@ impl BrowserController {
         destination_x: i64,
         destination_y: i64,
     ) -> Result<EnvState> {
-        validate_coordinates(x, y, self.config.screen_width, self.config.screen_height)?;
-        validate_coordinates(
-            destination_x,
-            destination_y,
-            self.config.screen_width,
-            self.config.screen_height,
-        )?;
+        let (x, y) = validate_coordinates(x, y, &self.config)?;
+        let (destination_x, destination_y) =
+            validate_coordinates(destination_x, destination_y, &self.config)?;
         debug!(
             "Drag and drop from ({}, {}) to ({}, {})",
             x, y, destination_x, destination_y
         );
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let (x, y) = self.to_frame_coordinates(x, y).await;
+        let (destination_x, destination_y) = self
+            .to_frame_coordinates(destination_x, destination_y)
+            .await;
+        let driver = &self.session().await?;
 
         // Use JavaScript to simulate drag and drop
         // Note: All coordinates are i64, so format! only produces numeric values (no injection risk)
@@ -1079,7 +2950,6 @@ impl BrowserController {
 
         driver.execute(&script, vec![]).await?;
 
-        drop(driver_guard);
         self.current_state().await
     }
 
@@ -1090,25 +2960,22 @@ impl BrowserController {
     /// Returns both tab info and the current environment state.
     pub async fn new_tab(&self, url: Option<&str>) -> Result<(TabInfo, EnvState)> {
         debug!("Creating new tab");
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let driver = &self.session().await?;
 
         // Create new tab
         let new_handle = driver.new_tab().await?;
 
         // Switch to the new tab
         driver.switch_to_window(new_handle.clone()).await?;
+        self.leave_frame(driver).await;
+        self.install_notification_capture(driver).await;
+        self.install_activity_tracking(driver).await;
+        self.apply_stealth_scripts(driver).await;
 
         // Navigate to URL if provided, handling failures gracefully
         let mut navigation_error: Option<String> = None;
         if let Some(url) = url {
-            let normalized_url = if url.starts_with("http://") || url.starts_with("https://") {
-                url.to_string()
-            } else {
-                format!("https://{}", url)
-            };
+            let normalized_url = normalize_url(url);
             if let Err(e) = driver.goto(&normalized_url).await {
                 // Log the error but don't fail - tab is still created
                 warn!("Navigation failed in new tab: {}. Tab remains open.", e);
@@ -1131,11 +2998,18 @@ impl BrowserController {
 
         // Get screenshot for the state
         let screenshot_bytes = driver.screenshot_as_png().await?;
-        let screenshot = BASE64.encode(&screenshot_bytes);
+        let screenshot =
+            encode_screenshot(screenshot_bytes, self.config.max_screenshot_dimension).await?;
 
         let state = EnvState {
             screenshot,
             url: current_url,
+            notifications: Vec::new(),
+            warnings: Vec::new(),
+            delta: None,
+            effect_detected: None,
+            timings: StateTimings::default(),
+            login_required: false,
         };
 
         Ok((tab_info, state))
@@ -1144,10 +3018,7 @@ impl BrowserController {
     /// Close a browser tab by handle.
     pub async fn close_tab(&self, handle: Option<&str>) -> Result<EnvState> {
         debug!("Closing tab: {:?}", handle);
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let driver = &self.session().await?;
 
         if let Some(handle) = handle {
             // Switch to the specified tab first
@@ -1167,8 +3038,8 @@ impl BrowserController {
         if let Some(other) = next_window {
             driver.switch_to_window(other).await?;
         }
+        self.leave_frame(driver).await;
 
-        drop(driver_guard);
         self.current_state().await
     }
 
@@ -1190,10 +3061,7 @@ impl BrowserController {
             _ => {}
         }
 
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let driver = &self.session().await?;
 
         if let Some(handle) = handle {
             let window_handle = WindowHandle::from(handle.to_string());
@@ -1206,20 +3074,105 @@ impl BrowserController {
                 .ok_or_else(|| anyhow::anyhow!("Tab index {} out of range", index))?;
             driver.switch_to_window(window).await?;
         }
+        self.leave_frame(driver).await;
 
         tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
 
-        drop(driver_guard);
         self.current_state().await
     }
 
+    /// Handle of the session's current window, if the browser is open.
+    pub async fn active_tab(&self) -> Option<String> {
+        let driver = self.session().await.ok()?;
+        driver.window().await.ok().map(|handle| handle.to_string())
+    }
+
+    /// Make the tab with the given handle the current window, if it is not already.
+    pub async fn focus_tab(&self, handle: &str) -> Result<()> {
+        let driver = &self.session().await?;
+        let window_handle = WindowHandle::from(handle.to_string());
+        if driver.window().await.ok().as_ref() == Some(&window_handle) {
+            return Ok(());
+        }
+
+        debug!("Focusing tab: {}", handle);
+        driver
+            .switch_to_window(window_handle)
+            .await
+            .map_err(|e| anyhow::anyhow!("Tab not found: {} ({})", handle, e))?;
+        self.leave_frame(driver).await;
+        Ok(())
+    }
+
+    /// Open a URL in a new tab and return to the current one.
+    pub async fn open_background_tab(&self, url: &str) -> Result<String> {
+        debug!("Opening background tab: {}", url);
+        let driver = &self.session().await?;
+        let current = driver.window().await?;
+        let handle = driver.new_tab().await?;
+        driver.switch_to_window(handle.clone()).await?;
+        let loaded = driver.goto(&normalize_url(url)).await;
+        if loaded.is_err() {
+            driver.close_window().await?;
+        }
+        driver.switch_to_window(current).await?;
+        // Switching windows always lands in the top-level document
+        self.leave_frame(driver).await;
+        loaded.map_err(|e| anyhow::anyhow!("Navigation failed: {}", e))?;
+        Ok(handle.to_string())
+    }
+
+    /// Read the text of a background tab, reloading it first, and return to the
+    /// current tab.
+    pub async fn read_background_tab(
+        &self,
+        handle: &str,
+        selector: Option<&str>,
+        reload: bool,
+    ) -> Result<PageText> {
+        let driver = &self.session().await?;
+        let current = driver.window().await?;
+        driver
+            .switch_to_window(WindowHandle::from(handle.to_string()))
+            .await
+            .map_err(|e| anyhow::anyhow!("Tab not found: {} ({})", handle, e))?;
+        let read = async {
+            if reload {
+                driver.refresh().await?;
+                wait_for_page_ready(driver).await?;
+            }
+            let result = driver
+                .execute(
+                    format!("return {}", page_watch::text_script(selector)),
+                    vec![],
+                )
+                .await?;
+            Ok::<_, anyhow::Error>(serde_json::from_value(result.json().clone())?)
+        }
+        .await;
+        driver.switch_to_window(current).await?;
+        self.leave_frame(driver).await;
+        read
+    }
+
+    /// Close a background tab, staying on the current one.
+    pub async fn close_background_tab(&self, handle: &str) -> Result<()> {
+        let driver = &self.session().await?;
+        let current = driver.window().await?;
+        driver
+            .switch_to_window(WindowHandle::from(handle.to_string()))
+            .await
+            .map_err(|e| anyhow::anyhow!("Tab not found: {} ({})", handle, e))?;
+        driver.close_window().await?;
+        driver.switch_to_window(current).await?;
+        self.leave_frame(driver).await;
+        Ok(())
+    }
+
     /// List all open tabs and return current state.
     pub async fn list_tabs(&self) -> Result<(Vec<TabInfo>, EnvState)> {
         debug!("Listing all tabs");
-        let driver_guard = self.driver.lock().await;
-        let driver = driver_guard
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let driver = &self.session().await?;
 
         let current_handle = driver.window().await?;
         let windows = driver.windows().await?;
@@ -1257,14 +3210,533 @@ impl BrowserController {
 
         // Get current state (screenshot and URL)
         let screenshot_bytes = driver.screenshot_as_png().await?;
-        let screenshot = BASE64.encode(&screenshot_bytes);
+        let screenshot =
+            encode_screenshot(screenshot_bytes, self.config.max_screenshot_dimension).await?;
         let url = driver.current_url().await?.to_string();
 
-        let state = EnvState { screenshot, url };
+        let state = EnvState {
+            screenshot,
+            url,
+            notifications: Vec::new(),
+            warnings: Vec::new(),
+            delta: None,
+            effect_detected: None,
+            timings: StateTimings::default(),
+            login_required: false,
+        };
 
         Ok((tabs, state))
     }
 
+    // ========== Frame Methods ==========
+
+    /// Translate screenshot coordinates into the coordinate space of the selected frame.
+    async fn to_frame_coordinates(&self, x: i64, y: i64) -> (i64, i64) {
+        let (offset_x, offset_y) = *self.frame_offset.lock().await;
+        (x - offset_x, y - offset_y)
+    }
+
+    /// Validate a point target and translate it into the selected frame.
+    async fn resolve_target(&self, target: ElementTarget) -> Result<ElementTarget> {
+        match target {
+            ElementTarget::Point(x, y) => {
+                let (x, y) = validate_coordinates(x, y, &self.config)?;
+                let (x, y) = self.to_frame_coordinates(x, y).await;
+                Ok(ElementTarget::Point(x, y))
+            }
+            target => Ok(target),
+        }
+    }
+
+    /// Return to the top-level document after navigation or a tab change.
+    async fn leave_frame(&self, driver: &WebDriver) {
+        let mut offset = self.frame_offset.lock().await;
+        if *offset != (0, 0) {
+            if let Err(e) = driver.enter_default_frame().await {
+                warn!("Failed to return to top-level document: {}", e);
+            }
+            *offset = (0, 0);
+        }
+    }
+
+    /// Describe the frames of the currently selected document.
+    async fn frames_in_current_document(&self, driver: &WebDriver) -> Result<Vec<FrameInfo>> {
+        let result = driver
+            .execute(format!("return {}", LIST_FRAMES_SCRIPT), vec![])
+            .await?;
+        let mut frames: Vec<FrameInfo> = serde_json::from_value(result.json().clone())?;
+
+        // Report positions relative to the screenshot rather than the selected frame
+        let (offset_x, offset_y) = *self.frame_offset.lock().await;
+        for frame in &mut frames {
+            frame.x += offset_x;
+            frame.y += offset_y;
+        }
+        Ok(frames)
+    }
+
+    /// List the frames of the currently selected document and return current state.
+    pub async fn list_frames(&self) -> Result<(Vec<FrameInfo>, EnvState)> {
+        debug!("Listing frames");
+        let driver = &self.session().await?;
+
+        let frames = self.frames_in_current_document(driver).await?;
+
+        let state = self.current_state().await?;
+        Ok((frames, state))
+    }
+
+    /// Switch into a child frame of the currently selected document by index,
+    /// or back to the top-level document when no index is given.
+    ///
+    /// Coordinate-based actions keep using screenshot coordinates; they are
+    /// translated into the selected frame before being dispatched.
+    pub async fn switch_frame(&self, index: Option<usize>) -> Result<EnvState> {
+        debug!("Switching to frame: {:?}", index);
+        let driver = &self.session().await?;
+
+        match index {
+            Some(index) => {
+                let frames = self.frames_in_current_document(driver).await?;
+                let frame = frames
+                    .get(index)
+                    .ok_or_else(|| anyhow::anyhow!("Frame index {} out of range", index))?;
+                let frame_number = u16::try_from(index)
+                    .map_err(|_| anyhow::anyhow!("Frame index {} out of range", index))?;
+
+                driver.enter_frame(frame_number).await?;
+                *self.frame_offset.lock().await = (frame.x, frame.y);
+            }
+            None => {
+                driver.enter_default_frame().await?;
+                *self.frame_offset.lock().await = (0, 0);
+            }
+        }
+
+        self.current_state().await
+    }
+
+    /// Extract title, description, canonical URL, OpenGraph tags and favicon.
+    pub async fn page_metadata(&self) -> Result<PageMetadata> {
+        debug!("Extracting page metadata");
+        let driver = &self.session().await?;
+
+        let result = driver
+            .execute(format!("return {}", PAGE_METADATA_SCRIPT), vec![])
+            .await?;
+        Ok(serde_json::from_value(result.json().clone())?)
+    }
+
+    /// Fill saved credentials into the sign-in form of the current document,
+    /// provided the tab is on one of the site's domains.
+    pub async fn fill_credentials(
+        &self,
+        credentials: &SiteCredentials,
+        username: Option<&str>,
+        password: &str,
+        submit: bool,
+    ) -> Result<(CredentialFill, EnvState)> {
+        debug!("Filling saved credentials");
+        let driver = &self.session().await?;
+
+        let url = driver.current_url().await?.to_string();
+        // The form is in the selected frame, if any. `location` cannot be
+        // redefined by the frame's scripts, unlike the builtins the fill
+        // script checks the origin with, so the password never reaches
+        // another origin.
+        let frame_url = driver
+            .execute("return location.href", vec![])
+            .await?
+            .json()
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let host = credentials::host_of(&frame_url).unwrap_or_default();
+        if !credentials.allows_url(&url) || !credentials.allows_url(&frame_url) {
+            let fill = CredentialFill {
+                host,
+                ..Default::default()
+            };
+            return Ok((fill, self.current_state().await?));
+        }
+
+        let selectors = self.config.recipes.selectors_for(&host);
+        let script = credentials::fill_script(credentials, username, password, submit, selectors)?;
+        let result = driver.execute(format!("return {}", script), vec![]).await?;
+        let fill: CredentialFill = serde_json::from_value(result.json().clone())?;
+        if fill.submitted {
+            self.leave_frame(driver).await;
+            tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS * 2)).await;
+        }
+
+        Ok((fill, self.current_state().await?))
+    }
+
+    /// Run one step of a sign-in on an identity provider page. The saved
+    /// credentials are only offered to the page when its host is one of the
+    /// site's domains.
+    pub async fn oauth_step(
+        &self,
+        credentials: &SiteCredentials,
+        username: Option<&str>,
+        password: &str,
+        approve_consent: bool,
+    ) -> Result<OAuthStep> {
+        debug!("Running sign-in step");
+        let driver = &self.session().await?;
+
+        let url = driver.current_url().await?.to_string();
+        let host = credentials::host_of(&url).unwrap_or_default();
+        let Some(provider) = Provider::detect(&host) else {
+            return Ok(OAuthStep {
+                host,
+                provider: None,
+                action: StepAction::None,
+            });
+        };
+        let (username, password) = match credentials.allows_url(&url) {
+            true => (username, Some(password)),
+            false => (None, None),
+        };
+
+        self.leave_frame(driver).await;
+        let script = oauth::step_script(
+            provider,
+            username,
+            password,
+            approve_consent,
+            &credentials.domains,
+        )?;
+        let result = driver.execute(format!("return {}", script), vec![]).await?;
+        let action: StepAction = serde_json::from_value(result.json().clone())?;
+        if action.advanced() {
+            tokio::time::sleep(Duration::from_millis(OAUTH_STEP_DELAY_MS)).await;
+        }
+
+        Ok(OAuthStep {
+            host,
+            provider: Some(provider),
+            action,
+        })
+    }
+
+    /// Notifications and permission prompts recorded in the current document,
+    /// optionally clearing the record. Capture is installed again when a
+    /// navigation has replaced the document since it was last set up.
+    pub async fn get_notifications(&self, clear: bool) -> Result<Vec<CapturedNotification>> {
+        debug!("Reading notifications");
+        if !self.config.capture_notifications {
+            return Err(anyhow::anyhow!(
+                "Notification capture is disabled (MCP_CAPTURE_NOTIFICATIONS=false)"
+            ));
+        }
+        let driver = &self.session().await?;
+
+        let result = driver
+            .execute(
+                format!("return {}", notifications::read_script(clear)),
+                vec![],
+            )
+            .await?;
+        match notifications::parse_notifications(result.json())? {
+            Some(notifications) => Ok(notifications),
+            None => {
+                driver
+                    .execute(notifications::CAPTURE_SCRIPT, vec![])
+                    .await?;
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Evaluate a JavaScript expression in the current document.
+    pub async fn evaluate_javascript(&self, expression: &str) -> Result<Evaluation> {
+        debug!("Evaluating JavaScript");
+        let driver = &self.session().await?;
+
+        let script = format!(
+            "var done = arguments[arguments.length - 1]; {}.then(done);",
+            evaluate::evaluate_script(expression)
+        );
+        let result = driver.execute_async(script, vec![]).await?;
+        evaluate::parse_evaluation(result.json())
+    }
+
+    /// List the audio and video elements of the current document.
+    pub async fn get_media(&self) -> Result<Vec<MediaElement>> {
+        debug!("Listing media elements");
+        let driver = &self.session().await?;
+
+        let result = driver
+            .execute(format!("return {}", media::state_script()), vec![])
+            .await?;
+        media::parse_media(result.json())
+    }
+
+    /// Play, pause, mute or unmute the media element at `index`, or all of
+    /// them, and list the media elements once playback had time to change.
+    pub async fn control_media(
+        &self,
+        command: MediaCommand,
+        index: Option<u32>,
+    ) -> Result<Vec<MediaElement>> {
+        debug!("Media command {:?} on {:?}", command, index);
+        let driver = &self.session().await?;
+
+        let result = driver
+            .execute(
+                format!("return {}", media::control_script(command, index)),
+                vec![],
+            )
+            .await?;
+        media::parse_media(result.json())?;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        self.get_media().await
+    }
+
+    /// Capture the current frame of the video matching `selector`, or of the
+    /// first playing video.
+    pub async fn capture_video_frame(&self, selector: Option<&str>) -> Result<VideoFrame> {
+        debug!("Capturing video frame");
+        let driver = &self.session().await?;
+
+        let result = driver
+            .execute(format!("return {}", media::frame_script(selector)), vec![])
+            .await?;
+        media::parse_frame(result.json())
+    }
+
+    /// Capture URL, scroll position and form values of the top-level document,
+    /// leaving any selected frame.
+    pub async fn capture_checkpoint(&self, include_storage: bool) -> Result<PageCheckpoint> {
+        debug!("Capturing checkpoint");
+        let driver = &self.session().await?;
+        self.leave_frame(driver).await;
+
+        let result = driver
+            .execute(
+                format!("return {}", checkpoint::capture_script(include_storage)),
+                vec![],
+            )
+            .await?;
+        Ok(serde_json::from_value(result.json().clone())?)
+    }
+
+    /// Return to a checkpoint: load its URL, restore storage (reloading so the page
+    /// sees it), then form values and scroll position.
+    /// Returns the number of restored fields and the resulting state.
+    pub async fn restore_checkpoint(
+        &self,
+        checkpoint: &PageCheckpoint,
+    ) -> Result<(usize, EnvState)> {
+        debug!("Restoring checkpoint at {}", checkpoint.url);
+        let driver = &self.session().await?;
+
+        driver.goto(&checkpoint.url).await?;
+        self.leave_frame(driver).await;
+        let _ = wait_for_page_ready(driver).await;
+
+        if checkpoint.storage.is_some() {
+            driver
+                .execute(checkpoint::restore_storage_script(checkpoint)?, vec![])
+                .await?;
+            driver.refresh().await?;
+            let _ = wait_for_page_ready(driver).await;
+        }
+
+        let restored = driver
+            .execute(
+                format!("return {}", checkpoint::restore_page_script(checkpoint)?),
+                vec![],
+            )
+            .await?
+            .json()
+            .as_u64()
+            .unwrap_or(0) as usize;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+
+        Ok((restored, self.current_state().await?))
+    }
+
+    /// Get all cookies of the browser via CDP.
+    /// Returns `None` for browsers that do not support CDP.
+    pub async fn get_cookies(&self) -> Result<Option<Vec<SavedCookie>>> {
+        if !matches!(
+            self.config.browser_type,
+            BrowserType::Chrome | BrowserType::Edge
+        ) {
+            return Ok(None);
+        }
+
+        let driver = &self.session().await?;
+
+        let dev_tools = ChromeDevTools::new(driver.handle.clone());
+        let raw = dev_tools.execute_cdp("Network.getAllCookies").await?;
+        Ok(Some(workspace::parse_cookies(&raw)?))
+    }
+
+    /// Set cookies via CDP. Returns false for browsers that do not support CDP.
+    pub async fn set_cookies(&self, cookies: &[SavedCookie]) -> Result<bool> {
+        if !matches!(
+            self.config.browser_type,
+            BrowserType::Chrome | BrowserType::Edge
+        ) {
+            return Ok(false);
+        }
+
+        let driver = &self.session().await?;
+
+        let dev_tools = ChromeDevTools::new(driver.handle.clone());
+        dev_tools
+            .execute_cdp_with_params(
+                "Network.setCookies",
+                serde_json::json!({ "cookies": cookies }),
+            )
+            .await?;
+        Ok(true)
+    }
+
+    /// Get page performance metrics via CDP.
+    /// Returns `None` for browsers that do not support CDP.
+    pub async fn page_metrics(&self) -> Result<Option<PageMetrics>> {
+        if !matches!(
+            self.config.browser_type,
+            BrowserType::Chrome | BrowserType::Edge
+        ) {
+            return Ok(None);
+        }
+
+        let driver = &self.session().await?;
+
+        let dev_tools = ChromeDevTools::new(driver.handle.clone());
+        dev_tools
+            .execute_cdp(browser_stats::ENABLE_METRICS_METHOD)
+            .await?;
+        let raw = dev_tools
+            .execute_cdp(browser_stats::GET_METRICS_METHOD)
+            .await?;
+
+        Ok(Some(PageMetrics::from_json(&raw)))
+    }
+
+    /// Capture a compact snapshot of the visible DOM with layout information.
+    /// Requires a Chromium-based browser, since it relies on CDP.
+    pub async fn dom_snapshot(&self) -> Result<DomSnapshot> {
+        debug!("Capturing DOM snapshot");
+        if !matches!(
+            self.config.browser_type,
+            BrowserType::Chrome | BrowserType::Edge
+        ) {
+            return Err(anyhow::anyhow!(
+                "DOM snapshots require a Chromium-based browser (Chrome or Edge)"
+            ));
+        }
+
+        let driver = &self.session().await?;
+
+        let dev_tools = ChromeDevTools::new(driver.handle.clone());
+        let raw = dev_tools
+            .execute_cdp_with_params(
+                dom_snapshot::CAPTURE_SNAPSHOT_METHOD,
+                dom_snapshot::capture_snapshot_params(),
+            )
+            .await?;
+
+        dom_snapshot::build_snapshot(&raw, (self.config.screen_width, self.config.screen_height))
+    }
+
+    /// Audit the accessibility of the current document with axe-core, loading it
+    /// into the document first if needed.
+    pub async fn audit_accessibility(
+        &self,
+        axe_source: &str,
+        selector: Option<&str>,
+    ) -> Result<AccessibilityAudit> {
+        debug!("Auditing accessibility");
+        let driver = &self.session().await?;
+
+        let present = driver
+            .execute(format!("return {}", accessibility::PRESENT_SCRIPT), vec![])
+            .await?;
+        if !present.json().as_bool().unwrap_or(false) {
+            driver.execute(axe_source, vec![]).await?;
+        }
+
+        let script = format!(
+            "var done = arguments[arguments.length - 1]; {}.then(done);",
+            accessibility::run_script(selector)
+        );
+        let result = driver.execute_async(script, vec![]).await?;
+        accessibility::parse_results(result.json().clone())
+    }
+
+    /// Collect the links of the current document, or of the first element
+    /// matching `selector`.
+    pub async fn collect_links(&self, selector: Option<&str>) -> Result<PageLinks> {
+        debug!("Collecting links");
+        let driver = &self.session().await?;
+        let result = driver
+            .execute(
+                format!("return {}", link_check::links_script(selector)),
+                vec![],
+            )
+            .await?;
+        Ok(serde_json::from_value(result.json().clone())?)
+    }
+
+    /// Change the emulated media settings of the current tab via CDP. Returns the
+    /// tab's resulting settings.
+    pub async fn set_emulated_media(
+        &self,
+        changes: MediaEmulation,
+    ) -> Result<(EnvState, MediaEmulation)> {
+        debug!("Emulating media: {:?}", changes);
+        if !matches!(
+            self.config.browser_type,
+            BrowserType::Chrome | BrowserType::Edge
+        ) {
+            return Err(anyhow::anyhow!(
+                "Media emulation requires a Chromium-based browser (Chrome or Edge)"
+            ));
+        }
+
+        let driver = &self.session().await?;
+        let tab = driver.window().await?.to_string();
+        let settings = self.media_emulation.update(&tab, changes);
+
+        let dev_tools = ChromeDevTools::new(driver.handle.clone());
+        dev_tools
+            .execute_cdp_with_params(emulation::SET_EMULATED_MEDIA_METHOD, settings.cdp_params())
+            .await?;
+
+        Ok((self.current_state().await?, settings))
+    }
+
+    /// Render the current tab as seen with a vision deficiency via CDP.
+    pub async fn set_vision_deficiency(&self, deficiency: VisionDeficiency) -> Result<EnvState> {
+        debug!("Emulating vision deficiency: {:?}", deficiency);
+        if !matches!(
+            self.config.browser_type,
+            BrowserType::Chrome | BrowserType::Edge
+        ) {
+            return Err(anyhow::anyhow!(
+                "Vision deficiency emulation requires a Chromium-based browser (Chrome or Edge)"
+            ));
+        }
+
+        let driver = &self.session().await?;
+
+        let dev_tools = ChromeDevTools::new(driver.handle.clone());
+        dev_tools
+            .execute_cdp_with_params(
+                emulation::SET_VISION_DEFICIENCY_METHOD,
+                deficiency.cdp_params(),
+            )
+            .await?;
+
+        self.current_state().await
+    }
+
     /// Get the screen size.
     #[allow(dead_code)]
     pub fn screen_size(&self) -> (u32, u32) {
@@ -1274,6 +3746,7 @@ impl BrowserController {
 
 impl Drop for BrowserController {
     fn drop(&mut self) {
+        self.stop_keepalive();
         // Use atomic flags to reliably detect if cleanup is needed
         // This is more reliable than try_lock() which may fail silently
         let was_opened = self.was_opened.load(Ordering::SeqCst);
@@ -1288,3 +3761,199 @@ impl Drop for BrowserController {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_coordinates() {
+        let config = Config {
+            screen_width: 1280,
+            screen_height: 720,
+            ..Config::default()
+        };
+        assert_eq!(validate_coordinates(0, 719, &config).unwrap(), (0, 719));
+        assert!(validate_coordinates(1280, 10, &config).is_err());
+        assert!(validate_coordinates(-1, 10, &config).is_err());
+
+        let config = Config {
+            coordinate_policy: CoordinatePolicy::Clamp,
+            ..config
+        };
+        assert_eq!(validate_coordinates(1500, -20, &config).unwrap(), (1279, 0));
+        assert_eq!(validate_coordinates(640, 360, &config).unwrap(), (640, 360));
+    }
+
+    #[test]
+    fn test_scroll_document_script() {
+        assert_eq!(
+            scroll_document_script("Down", ScrollAmount::Default, false).unwrap(),
+            "window.scrollBy(0, (window.innerHeight * 0.8));"
+        );
+        assert_eq!(
+            scroll_document_script("up", ScrollAmount::Pages(3.0), false).unwrap(),
+            "window.scrollBy(0, -(window.innerHeight * 3));"
+        );
+        assert_eq!(
+            scroll_document_script("left", ScrollAmount::Pixels(250), true).unwrap(),
+            "window.scrollBy({ left: -(250), top: 0, behavior: 'smooth' });"
+        );
+        assert!(scroll_document_script("sideways", ScrollAmount::Default, false).is_err());
+
+        assert_eq!(
+            ScrollAmount::from_params(Some(0.5), None).unwrap(),
+            ScrollAmount::Pages(0.5)
+        );
+        assert!(ScrollAmount::from_params(Some(1.0), Some(100)).is_err());
+        assert!(ScrollAmount::from_params(Some(0.0), None).is_err());
+        assert!(ScrollAmount::from_params(None, Some(-5)).is_err());
+    }
+
+    #[test]
+    fn test_text_runs_cjk() {
+        assert_eq!(text_runs("hello"), vec![TextRun::Keys("hello")]);
+        assert_eq!(
+            text_runs("搜索 東京タワー"),
+            vec![
+                TextRun::Composed("搜索"),
+                TextRun::Keys(" "),
+                TextRun::Composed("東京タワー"),
+            ]
+        );
+        assert_eq!(
+            text_runs("안녕하세요, world"),
+            vec![TextRun::Composed("안녕하세요"), TextRun::Keys(", world")]
+        );
+        assert!(text_runs("").is_empty());
+    }
+
+    #[test]
+    fn test_text_runs_emoji() {
+        assert_eq!(
+            text_runs("nice 👍🎉!"),
+            vec![
+                TextRun::Keys("nice "),
+                TextRun::Inserted("👍🎉"),
+                TextRun::Keys("!"),
+            ]
+        );
+        // Accented letters and BMP symbols are ordinary keys
+        assert_eq!(text_runs("café ✓"), vec![TextRun::Keys("café ✓")]);
+        assert_eq!(
+            text_runs("family 👨\u{200D}👩\u{200D}👧"),
+            vec![
+                TextRun::Keys("family "),
+                TextRun::Inserted("👨\u{200D}👩\u{200D}👧"),
+            ]
+        );
+        assert_eq!(
+            text_runs("好👍"),
+            vec![TextRun::Composed("好"), TextRun::Inserted("👍")]
+        );
+    }
+
+    #[test]
+    fn test_highlight_script() {
+        let target = ElementTarget::Selector("#submit".to_string());
+        let script = highlight_script(&target, DEFAULT_HIGHLIGHT_DURATION_MS).unwrap();
+        assert!(script.ends_with(r##"({"selector":"#submit"}, 3000)"##));
+        assert!(highlight_script(&target, MAX_HIGHLIGHT_DURATION_MS + 1).is_err());
+    }
+
+    #[test]
+    fn test_hover_script() {
+        assert!(hover_script(5, 6, true).ends_with("(5, 6, true)"));
+        assert!(validate_hover_duration(MAX_HOVER_DURATION_MS).is_ok());
+        assert!(validate_hover_duration(MAX_HOVER_DURATION_MS + 1).is_err());
+    }
+
+    #[test]
+    fn test_prepare_insertion_script() {
+        assert!(prepare_insertion_script(InsertionMode::Append)
+            .unwrap()
+            .ends_with("('append')"));
+        assert!(prepare_insertion_script(InsertionMode::ReplaceAll)
+            .unwrap()
+            .ends_with("('replace_all')"));
+        assert_eq!(
+            prepare_insertion_script(InsertionMode::ReplaceSelection),
+            None
+        );
+        let mode: InsertionMode = serde_json::from_str(r#""replace_selection""#).unwrap();
+        assert_eq!(mode, InsertionMode::ReplaceSelection);
+    }
+
+    #[test]
+    fn test_select_text_script() {
+        let script = select_text_script(&ElementTarget::Point(10, 20), Some(2), None);
+        assert!(script.ends_with(r#"({"x":10,"y":20}, 2, null)"#));
+        let script = select_text_script(
+            &ElementTarget::Selector("input[name=\"q\"]".to_string()),
+            None,
+            None,
+        );
+        assert!(script.ends_with(r#"({"selector":"input[name=\"q\"]"}, null, null)"#));
+
+        let selected = serde_json::json!({ "text": "world" });
+        assert_eq!(parse_element_result(&selected).unwrap(), "world");
+        let failed = serde_json::json!({ "error": "No element matches selector #q" });
+        assert!(parse_element_result(&failed).is_err());
+    }
+
+    #[test]
+    fn test_validate_key_steps() {
+        let step = |keys: &[&str], delay_ms| KeyStep {
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            delay_ms,
+        };
+        assert!(validate_key_steps(&[step(&["Tab"], 0), step(&["Shift", "Tab"], 200)]).is_ok());
+        assert!(validate_key_steps(&[]).is_err());
+        assert!(validate_key_steps(&[step(&[], 0)]).is_err());
+        assert!(validate_key_steps(&[step(&["Enter"], MAX_KEY_STEP_DELAY_MS + 1)]).is_err());
+        assert!(validate_key_steps(&vec![step(&["Tab"], 0); MAX_KEY_SEQUENCE_STEPS + 1]).is_err());
+    }
+
+    #[test]
+    fn test_scroll_to_element_script() {
+        assert!(scroll_to_element_script(10, 20, None).ends_with("(10, 20, null)"));
+        assert!(scroll_to_element_script(10, 20, Some(64)).ends_with("(10, 20, 64)"));
+    }
+
+    #[test]
+    fn test_crash_errors() {
+        assert!(is_crash_error(
+            "unknown error: session deleted because of page crash"
+        ));
+        assert!(is_crash_error("Tab crashed"));
+        assert!(!is_crash_error("no such element"));
+        assert!(is_session_gone_error(
+            "invalid session id: session deleted as the browser has closed the connection"
+        ));
+        assert!(!is_session_gone_error("Page crashed"));
+
+        let message = anyhow::Error::new(PageCrashed { reloaded: true }).to_string();
+        assert!(message.starts_with(PAGE_CRASHED_MESSAGE));
+        assert!(message.contains("reloaded"));
+    }
+
+    #[tokio::test]
+    async fn test_screenshot_processing() {
+        let mut png = Vec::new();
+        image::RgbImage::from_pixel(64, 36, image::Rgb([255, 255, 255]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let (returned, blank) = check_blank_screenshot(png.clone()).await.unwrap();
+        assert!(blank);
+        assert_eq!(returned, png);
+        assert_eq!(
+            encode_screenshot(png.clone(), None).await.unwrap(),
+            BASE64.encode(&png)
+        );
+        let scaled = BASE64
+            .decode(encode_screenshot(png.clone(), Some(32)).await.unwrap())
+            .unwrap();
+        assert_eq!(image::load_from_memory(&scaled).unwrap().width(), 32);
+    }
+}
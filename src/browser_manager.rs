@@ -4,12 +4,13 @@
 //! launching browsers with CDP (Chrome DevTools Protocol) support,
 //! and managing browser processes.
 
+use crate::browser::normalize_url;
 use crate::config::{BrowserType, Config};
 use anyhow::{Context, Result};
 use std::net::TcpStream;
 use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
 /// Maximum time to wait for browser to become ready (in seconds).
@@ -18,6 +19,9 @@ const BROWSER_READY_TIMEOUT_SECS: u64 = 30;
 /// Interval between health checks (in milliseconds).
 const HEALTH_CHECK_INTERVAL_MS: u64 = 100;
 
+/// How long a launched browser gets to exit after being asked to, before it is killed.
+const BROWSER_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Common Chrome browser paths on different platforms.
 #[cfg(target_os = "windows")]
 const CHROME_PATHS: &[&str] = &[
@@ -429,6 +433,9 @@ impl BrowserManager {
         );
 
         let mut cmd = Command::new(&browser_path);
+        if let Some(ref display) = config.display {
+            cmd.env("DISPLAY", display);
+        }
 
         // Essential CDP arguments
         cmd.arg(format!("--remote-debugging-port={}", self.cdp_port));
@@ -455,18 +462,25 @@ impl BrowserManager {
         ));
 
         if config.headless {
-            cmd.arg("--headless=new");
+            cmd.arg(config.headless_mode.chromium_arg());
         }
 
         // Undetected mode
         if config.undetected {
             cmd.arg("--disable-infobars");
             cmd.arg("--disable-notifications");
+            cmd.arg("--disable-blink-features=AutomationControlled");
         }
 
-        // Open with initial URL - validate it looks like a URL
+        // Open with initial URL - validate it looks like a URL.
+        // An app window already shows its own URL.
         let url = &config.initial_url;
-        if url.starts_with("http://") || url.starts_with("https://") || url.starts_with("file://") {
+        if let Some(app) = config.app_mode_arg() {
+            cmd.arg(app);
+        } else if url.starts_with("http://")
+            || url.starts_with("https://")
+            || url.starts_with("file://")
+        {
             cmd.arg(url);
         } else {
             warn!(
@@ -475,6 +489,11 @@ impl BrowserManager {
             );
         }
 
+        // Additional initial tabs - Chrome opens each URL argument in its own tab
+        for tab_url in &config.initial_tabs {
+            cmd.arg(normalize_url(tab_url));
+        }
+
         // Suppress output
         cmd.stdout(Stdio::null());
         cmd.stderr(Stdio::null());
@@ -533,9 +552,14 @@ impl BrowserManager {
     }
 
     /// Stop the browser process if we launched it.
+    /// The browser is asked to exit first, so it can save its profile and take
+    /// its helper processes down with it; it is killed if it does not exit in time.
     pub fn stop(&mut self) {
         if let Some(mut child) = self.browser_process.take() {
             info!("Stopping browser process");
+            if request_exit(&child) && wait_for_exit(&mut child, BROWSER_STOP_TIMEOUT) {
+                return;
+            }
             if let Err(e) = child.kill() {
                 warn!("Failed to kill browser process: {}", e);
             }
@@ -544,6 +568,36 @@ impl BrowserManager {
     }
 }
 
+/// Ask a process to exit (SIGTERM). Returns whether the request was delivered.
+#[cfg(unix)]
+fn request_exit(child: &Child) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(child.id()) else {
+        return false;
+    };
+    // SAFETY: kill() has no memory-safety requirements; the pid belongs to our
+    // child, which has not been reaped yet, so it cannot have been reused.
+    unsafe { libc::kill(pid, libc::SIGTERM) == 0 }
+}
+
+/// Windows has no graceful termination signal for a console-less process.
+#[cfg(not(unix))]
+fn request_exit(_child: &Child) -> bool {
+    false
+}
+
+/// Wait until the process exits or the timeout passes. Returns whether it exited.
+fn wait_for_exit(child: &mut Child, timeout: Duration) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        match child.try_wait() {
+            Ok(Some(_)) => return true,
+            Ok(None) => std::thread::sleep(Duration::from_millis(HEALTH_CHECK_INTERVAL_MS)),
+            Err(_) => return false,
+        }
+    }
+    false
+}
+
 impl Default for BrowserManager {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,232 @@
+//! Browser resource usage reporting.
+//!
+//! Combines the resident memory of the browser process tree (via sysinfo) with
+//! the page metrics reported by CDP `Performance.getMetrics`, and flags values
+//! that exceed the configured warning thresholds.
+
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// CDP method returning page performance metrics.
+pub const GET_METRICS_METHOD: &str = "Performance.getMetrics";
+
+/// CDP method enabling performance metrics collection.
+pub const ENABLE_METRICS_METHOD: &str = "Performance.enable";
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Page-level metrics reported by CDP.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PageMetrics {
+    /// JavaScript heap currently in use, in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub js_heap_used_bytes: Option<u64>,
+    /// Total JavaScript heap allocated, in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub js_heap_total_bytes: Option<u64>,
+    /// Number of DOM nodes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dom_nodes: Option<u64>,
+    /// Number of documents (including frames).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documents: Option<u64>,
+    /// Number of JavaScript event listeners.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub js_event_listeners: Option<u64>,
+}
+
+impl PageMetrics {
+    /// Build page metrics from CDP `(name, value)` pairs.
+    pub fn from_metrics<'a>(metrics: impl IntoIterator<Item = (&'a str, f64)>) -> Self {
+        let mut result = Self::default();
+        for (name, value) in metrics {
+            let value = Some(value.max(0.0) as u64);
+            match name {
+                "JSHeapUsedSize" => result.js_heap_used_bytes = value,
+                "JSHeapTotalSize" => result.js_heap_total_bytes = value,
+                "Nodes" => result.dom_nodes = value,
+                "Documents" => result.documents = value,
+                "JSEventListeners" => result.js_event_listeners = value,
+                _ => {}
+            }
+        }
+        result
+    }
+
+    /// Build page metrics from a raw `Performance.getMetrics` response.
+    pub fn from_json(raw: &serde_json::Value) -> Self {
+        let metrics = raw["metrics"].as_array().cloned().unwrap_or_default();
+        Self::from_metrics(
+            metrics
+                .iter()
+                .filter_map(|m| Some((m["name"].as_str()?, m["value"].as_f64()?))),
+        )
+    }
+}
+
+/// Resource usage of the browser.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct BrowserStats {
+    /// Number of processes spawned by this server (driver, browser, renderers, ...).
+    pub process_count: usize,
+    /// Combined resident memory of those processes, in bytes.
+    /// Not available when the browser was started outside this server.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rss_bytes: Option<u64>,
+    /// Page metrics from CDP, when available.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<PageMetrics>,
+    /// Thresholds that are currently exceeded.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl BrowserStats {
+    /// Collect process memory and combine it with the given page metrics.
+    pub async fn collect(page: Option<PageMetrics>, config: &Config) -> Self {
+        // Refreshing the process table is blocking I/O on most platforms
+        let (process_count, rss_bytes) = tokio::task::spawn_blocking(process_tree_memory)
+            .await
+            .unwrap_or((0, 0));
+
+        let mut stats = Self {
+            process_count,
+            rss_bytes: (process_count > 0).then_some(rss_bytes),
+            page,
+            warnings: Vec::new(),
+        };
+        stats.warnings = threshold_warnings(&stats, config);
+        stats
+    }
+}
+
+/// Count the descendants of this process and sum their resident memory.
+fn process_tree_memory() -> (usize, u64) {
+    let Ok(root) = sysinfo::get_current_pid() else {
+        return (0, 0);
+    };
+
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_memory(),
+    );
+
+    let processes = system.processes();
+    let is_descendant = |mut pid: Pid| {
+        // Walk up the parent chain; bounded to guard against cycles from PID reuse
+        for _ in 0..64 {
+            match processes.get(&pid).and_then(|p| p.parent()) {
+                Some(parent) if parent == root => return true,
+                Some(parent) => pid = parent,
+                None => return false,
+            }
+        }
+        false
+    };
+
+    processes
+        .iter()
+        .filter(|(pid, _)| is_descendant(**pid))
+        .fold((0, 0), |(count, rss), (_, process)| {
+            (count + 1, rss + process.memory())
+        })
+}
+
+/// Describe every configured threshold that the stats exceed.
+pub fn threshold_warnings(stats: &BrowserStats, config: &Config) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if let Some(rss) = stats.rss_bytes {
+        let limit = config.memory_warning_mb;
+        if limit > 0 && rss > limit.saturating_mul(BYTES_PER_MB) {
+            warnings.push(format!(
+                "Browser memory usage {} MB exceeds MCP_MEMORY_WARNING_MB ({} MB)",
+                rss / BYTES_PER_MB,
+                limit
+            ));
+        }
+    }
+
+    if let Some(heap) = stats.page.as_ref().and_then(|p| p.js_heap_used_bytes) {
+        let limit = config.js_heap_warning_mb;
+        if limit > 0 && heap > limit.saturating_mul(BYTES_PER_MB) {
+            warnings.push(format!(
+                "JavaScript heap usage {} MB exceeds MCP_JS_HEAP_WARNING_MB ({} MB)",
+                heap / BYTES_PER_MB,
+                limit
+            ));
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_metrics_from_json() {
+        let raw = serde_json::json!({
+            "metrics": [
+                { "name": "JSHeapUsedSize", "value": 1048576.0 },
+                { "name": "JSHeapTotalSize", "value": 2097152.0 },
+                { "name": "Nodes", "value": 42.0 },
+                { "name": "Timestamp", "value": 123.4 }
+            ]
+        });
+        let metrics = PageMetrics::from_json(&raw);
+        assert_eq!(metrics.js_heap_used_bytes, Some(1048576));
+        assert_eq!(metrics.js_heap_total_bytes, Some(2097152));
+        assert_eq!(metrics.dom_nodes, Some(42));
+        assert_eq!(metrics.documents, None);
+    }
+
+    #[test]
+    fn test_threshold_warnings() {
+        let config = Config {
+            memory_warning_mb: 100,
+            js_heap_warning_mb: 10,
+            ..Config::default()
+        };
+        let mut stats = BrowserStats {
+            process_count: 3,
+            rss_bytes: Some(50 * BYTES_PER_MB),
+            page: Some(PageMetrics {
+                js_heap_used_bytes: Some(5 * BYTES_PER_MB),
+                ..PageMetrics::default()
+            }),
+            warnings: Vec::new(),
+        };
+        assert!(threshold_warnings(&stats, &config).is_empty());
+
+        stats.rss_bytes = Some(150 * BYTES_PER_MB);
+        stats.page.as_mut().unwrap().js_heap_used_bytes = Some(20 * BYTES_PER_MB);
+        let warnings = threshold_warnings(&stats, &config);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("150 MB"));
+        assert!(warnings[1].contains("20 MB"));
+    }
+
+    #[test]
+    fn test_threshold_warnings_disabled() {
+        let config = Config {
+            memory_warning_mb: 0,
+            js_heap_warning_mb: 0,
+            ..Config::default()
+        };
+        let stats = BrowserStats {
+            process_count: 1,
+            rss_bytes: Some(u64::MAX),
+            page: Some(PageMetrics {
+                js_heap_used_bytes: Some(u64::MAX),
+                ..PageMetrics::default()
+            }),
+            warnings: Vec::new(),
+        };
+        assert!(threshold_warnings(&stats, &config).is_empty());
+    }
+}
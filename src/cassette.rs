@@ -0,0 +1,197 @@
+//! Recording and replay of browser backend replies.
+//!
+//! With `MCP_RECORD_CASSETTE` set, every reply of the browser backend, states
+//! and screenshots included, is appended to a cassette file as a line of
+//! JSON. With `MCP_REPLAY_CASSETTE` set, no browser is started: the backend
+//! returns the recorded replies in order instead, so a maintainer can rerun a
+//! user's session against the tool layer without the site it ran on.
+//!
+//! Replies are matched by order and backend method, not by arguments. A call
+//! of another method than the one recorded next fails without consuming the
+//! recorded reply, which shows where a replay diverged from the recording.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// One backend reply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    /// Backend method that returned the reply.
+    method: String,
+    /// The value returned, for a successful call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+    /// The error message, for a failed call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug)]
+enum Mode {
+    Record(Mutex<std::fs::File>),
+    Replay(Mutex<VecDeque<Entry>>),
+}
+
+/// A cassette being recorded or replayed.
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+}
+
+impl Cassette {
+    /// Start recording to `path`, replacing any cassette there.
+    pub fn record(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::create(path).map_err(|e| {
+            anyhow::anyhow!("Failed to create the cassette {}: {}", path.display(), e)
+        })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            mode: Mode::Record(Mutex::new(file)),
+        })
+    }
+
+    /// Load the cassette at `path` for replay.
+    pub fn replay(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!("Failed to read the cassette {}: {}", path.display(), e)
+        })?;
+        let entries = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    anyhow::anyhow!("Invalid cassette {} line {}: {}", path.display(), i + 1, e)
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            mode: Mode::Replay(Mutex::new(entries)),
+        })
+    }
+
+    /// Whether the cassette is replayed rather than recorded.
+    pub fn is_replay(&self) -> bool {
+        matches!(self.mode, Mode::Replay(_))
+    }
+
+    /// Number of replies left to replay.
+    pub fn remaining(&self) -> usize {
+        match &self.mode {
+            Mode::Record(_) => 0,
+            Mode::Replay(entries) => entries.lock().unwrap_or_else(|e| e.into_inner()).len(),
+        }
+    }
+
+    /// Append a reply of `method` to a recorded cassette and pass it through.
+    /// Failing to write is logged, never reported to the caller.
+    pub fn record_reply<T: Serialize>(
+        &self,
+        method: &str,
+        reply: anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let Mode::Record(file) = &self.mode else {
+            return reply;
+        };
+        let entry = match &reply {
+            Ok(value) => Entry {
+                method: method.to_string(),
+                result: Some(serde_json::to_value(value).unwrap_or_default()),
+                error: None,
+            },
+            Err(e) => Entry {
+                method: method.to_string(),
+                result: None,
+                error: Some(e.to_string()),
+            },
+        };
+        let mut line = serde_json::to_string(&entry).unwrap_or_default();
+        line.push('\n');
+        if let Err(e) = file
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .write_all(line.as_bytes())
+        {
+            tracing::warn!(
+                "Failed to write to the cassette {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+        reply
+    }
+
+    /// Return the next recorded reply, which must be one of `method`.
+    pub fn play<T: DeserializeOwned>(&self, method: &str) -> anyhow::Result<T> {
+        let Mode::Replay(entries) = &self.mode else {
+            return Err(anyhow::anyhow!("The cassette is being recorded"));
+        };
+        let mut entries = entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = entries.front().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Replay ended: the cassette has no more replies ({} was called)",
+                method
+            )
+        })?;
+        if entry.method != method {
+            return Err(anyhow::anyhow!(
+                "Replay diverged: {} was called where the cassette recorded {}",
+                method,
+                entry.method
+            ));
+        }
+        let entry = entries.pop_front().unwrap();
+        if let Some(error) = entry.error {
+            return Err(anyhow::anyhow!(error));
+        }
+        serde_json::from_value(entry.result.unwrap_or_default())
+            .map_err(|e| anyhow::anyhow!("The recorded reply of {} does not match: {}", method, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = std::env::temp_dir().join(format!("cassette-{}.jsonl", std::process::id()));
+        let recording = Cassette::record(&path).unwrap();
+        assert_eq!(
+            recording
+                .record_reply("current_url", Ok("https://a.test/".to_string()))
+                .unwrap(),
+            "https://a.test/"
+        );
+        let failed =
+            recording.record_reply::<String>("navigate", Err(anyhow::anyhow!("Page crashed")));
+        assert!(failed.is_err());
+        recording
+            .record_reply("list_frames", Ok((Vec::<String>::new(), 3)))
+            .unwrap();
+        drop(recording);
+
+        let replay = Cassette::replay(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(replay.is_replay());
+        assert_eq!(replay.remaining(), 3);
+        assert_eq!(
+            replay.play::<String>("current_url").unwrap(),
+            "https://a.test/"
+        );
+        // A call of another method than the next recorded one leaves it in place
+        let diverged = replay.play::<()>("go_back").unwrap_err();
+        assert!(diverged.to_string().contains("recorded navigate"));
+        let crashed = replay.play::<String>("navigate").unwrap_err();
+        assert_eq!(crashed.to_string(), "Page crashed");
+        let frames: (Vec<String>, i64) = replay.play("list_frames").unwrap();
+        assert_eq!(frames.1, 3);
+        assert!(replay.play::<String>("current_url").is_err());
+    }
+}
@@ -4,51 +4,230 @@
 //! It uses the chromiumoxide library for native CDP communication.
 //! Supports Chrome and Edge browsers (both are Chromium-based).
 
-use crate::browser::EnvState;
+use crate::accessibility::{self, AccessibilityAudit};
+use crate::browser::{
+    check_blank_screenshot, encode_screenshot, highlight_script, hover_script, normalize_url,
+    parse_element_result, prepare_insertion_script, scroll_document_script,
+    scroll_to_element_script, select_text_script, stability_script, text_runs,
+    validate_coordinates, validate_hover_duration, validate_key_steps, validate_magnitude,
+    ElementTarget, EnvState, FrameInfo, InsertionMode, KeyStep, PageCrashed, PageMetadata,
+    PageTarget, ScrollAmount, StateTimings, TabInfo, TextRun, CLICK_EFFECT_READ_SCRIPT,
+    CLICK_EFFECT_WATCH_SCRIPT, HOVER_REFRESH_INTERVAL_MS, LIST_FRAMES_SCRIPT,
+    MOVE_MOUSE_AWAY_SCRIPT, OAUTH_STEP_DELAY_MS, PAGE_METADATA_SCRIPT, SCROLL_SETTLE_SCRIPT,
+    STABILITY_POLL_INTERVAL_MS, STABLE_CHECKS,
+};
+use crate::browser_stats::PageMetrics;
+use crate::checkpoint::{self, PageCheckpoint};
 use crate::config::Config;
+use crate::context_strips::{self, ContextStrip, ContextStrips};
+use crate::credentials::{self, CredentialFill, SiteCredentials};
+use crate::dom_snapshot::{self, DomSnapshot, SNAPSHOT_COMPUTED_STYLES};
+use crate::draw_path::{self, PathPoint, PointerType};
+use crate::element_aliases::{self, ElementFingerprint, Located};
+use crate::element_locator::{self, LocatedElement, Locator};
+use crate::emulation::{MediaEmulation, MediaEmulations, MediaType, VisionDeficiency};
+use crate::evaluate::{self, Evaluation};
+use crate::input_controls::{self, ControlValue};
+use crate::keyboard_focus::{self, Activation, TabFocus};
+use crate::keys::{self, KeyMapping};
+use crate::link_check::{self, PageLinks};
+use crate::login_wall::{self, LoginSignals};
+use crate::media::{self, MediaCommand, MediaElement, VideoFrame};
+use crate::media_devices;
+use crate::metrics;
+use crate::navigation::NavigationTracker;
+use crate::notifications::{self, CapturedNotification};
+use crate::oauth::{self, OAuthStep, Provider, StepAction};
+use crate::page_events::{EventDetails, EventType, PageEvents};
+use crate::page_watch::{self, PageText};
+use crate::recipes;
+use crate::scroll_memory::{self, ScrollMemory, ScrollPosition};
+use crate::search_engine::SearchEngine;
+use crate::shortcuts;
+use crate::state_delta::{self, PageActivity, StateDelta, StateDeltaTracker};
+use crate::web_search::{self, SearchResult};
+use crate::workspace::{self, SavedCookie};
+use crate::zoom_to_fit;
 use anyhow::Result;
-use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use chromiumoxide::browser::{Browser, BrowserConfig};
-use chromiumoxide::cdp::browser_protocol::input::{DispatchKeyEventParams, DispatchKeyEventType};
+use chromiumoxide::cdp::browser_protocol::browser::{
+    EventDownloadWillBegin, SetDownloadBehaviorBehavior, SetDownloadBehaviorParams,
+};
+use chromiumoxide::cdp::browser_protocol::dom::DescribeNodeParams;
+use chromiumoxide::cdp::browser_protocol::dom_snapshot::CaptureSnapshotParams;
+use chromiumoxide::cdp::browser_protocol::emulation::{
+    MediaFeature, SetEmulatedMediaParams, SetEmulatedVisionDeficiencyParams,
+    SetEmulatedVisionDeficiencyType,
+};
+use chromiumoxide::cdp::browser_protocol::input::{
+    DispatchKeyEventParams, DispatchKeyEventType, DispatchMouseEventParams, DispatchMouseEventType,
+    DispatchTouchEventParams, ImeSetCompositionParams, InsertTextParams, MouseButton,
+};
+use chromiumoxide::cdp::browser_protocol::inspector::{
+    EnableParams as InspectorEnableParams, EventTargetCrashed,
+};
+use chromiumoxide::cdp::browser_protocol::network::CookieParam;
 use chromiumoxide::cdp::browser_protocol::page::{
-    CaptureScreenshotFormat, GetNavigationHistoryParams, NavigateToHistoryEntryParams,
+    CaptureScreenshotFormat, CaptureScreenshotParams, EventFrameNavigated,
+    EventJavascriptDialogOpening, FrameId, GetNavigationHistoryParams,
+    NavigateToHistoryEntryParams, RemoveScriptToEvaluateOnNewDocumentParams, ScriptIdentifier,
+};
+use chromiumoxide::cdp::browser_protocol::target::{
+    EventTargetCreated, EventTargetDestroyed, GetTargetsParams, TargetId,
 };
+use chromiumoxide::cdp::js_protocol::runtime::{EvaluateParams, EventConsoleApiCalled};
+use chromiumoxide::cdp::IntoEventKind;
 use chromiumoxide::handler::viewport::Viewport;
+use chromiumoxide::js::EvaluationResult;
+use chromiumoxide::listeners::EventStream;
 use chromiumoxide::page::ScreenshotParams;
 use chromiumoxide::Page;
 use futures::StreamExt;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tracing::{debug, info, warn};
 
 /// Delay in milliseconds to wait for page to settle after actions.
 const PAGE_SETTLE_DELAY_MS: u64 = 500;
 
+/// Delay in milliseconds before capturing a blank screenshot again.
+const BLANK_SCREENSHOT_RETRY_DELAY_MS: u64 = 300;
+
 /// Delay in milliseconds after typing actions.
 const TYPING_DELAY_MS: u64 = 100;
 
+/// Delay in milliseconds between keystrokes when simulating typing.
+const KEYSTROKE_DELAY_MS: u64 = 30;
+
+/// How long to wait for a launched browser to exit after `Browser.close` before killing it.
+const BROWSER_EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long to wait for the tabs of a connected browser to be attached.
+const ATTACH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Attempts to reconnect to a `cdp_url` browser whose connection dropped, and
+/// the delay before the first; each further attempt waits one delay longer.
+const RECONNECT_ATTEMPTS: u32 = 5;
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// Script keeping navigation in the current tab: `window.open` navigates the
+/// tab itself, and links and forms targeting another window open in place.
+/// Installed in attached mode. Safe to run more than once.
+const SAME_TAB_SCRIPT: &str = r#"
+(function() {
+    if (window.__mcpSameTab) {
+        return;
+    }
+    Object.defineProperty(window, '__mcpSameTab', { value: true });
+    function inPlace(target) {
+        return target && !/^_(self|top|parent)$/i.test(target);
+    }
+    window.open = function(url) {
+        if (url) {
+            location.href = url;
+        }
+        return window;
+    };
+    document.addEventListener('click', function(event) {
+        var link = event.target && event.target.closest ? event.target.closest('a[target], area[target]') : null;
+        if (link && inPlace(link.target)) {
+            link.target = '_self';
+        }
+    }, true);
+    document.addEventListener('submit', function(event) {
+        if (event.target && inPlace(event.target.target)) {
+            event.target.target = '_self';
+        }
+    }, true);
+})()"#;
+
+tokio::task_local! {
+    /// Tab targeted by the action running in the current task, set by `in_tab`.
+    static TARGET_TAB: Option<String>;
+}
+
+/// A child frame selected via `switch_frame`.
+#[derive(Debug, Clone)]
+struct SelectedFrame {
+    /// Tab the frame belongs to; actions in other tabs ignore the selection.
+    tab: TargetId,
+    /// CDP frame identifier used to look up the frame's execution context.
+    id: FrameId,
+    /// Screen offset of the frame's content area.
+    offset: (i64, i64),
+}
+
 /// CDP browser controller that wraps chromiumoxide operations.
 pub struct CdpBrowserController {
     browser: Arc<Mutex<Option<Browser>>>,
     page: Arc<Mutex<Option<Page>>>,
     config: Config,
+    /// Frame that script-based actions currently target (`None` for the top-level document).
+    frame: Mutex<Option<SelectedFrame>>,
+    /// Tab handles in the order they were first seen, so tab indexes stay stable.
+    tab_order: Mutex<Vec<String>>,
+    /// Sessions of the tabs listed last, by handle, reused by actions that
+    /// target a tab.
+    sessions: std::sync::Mutex<HashMap<String, Page>>,
+    /// Tabs whose renderer crashed since their last action, filled by the
+    /// crash watchers started in `prepare_page`.
+    crashed_tabs: Arc<std::sync::Mutex<HashSet<TargetId>>>,
+    /// Scripts added to run in every new document, removed again when a
+    /// connected browser is released.
+    document_scripts: Mutex<Vec<(Page, ScriptIdentifier)>>,
+    /// Visited URLs, for the navigation budget and loop detection.
+    navigation: NavigationTracker,
+    /// Previous state of each tab, for change summaries.
+    state_delta: StateDeltaTracker,
+    /// Scroll position of recently shown pages, restored on history navigation.
+    scroll_memory: ScrollMemory,
+    /// Emulated media settings of each tab.
+    media_emulation: MediaEmulations,
     /// Tracks whether the browser was opened (and thus needs cleanup)
     was_opened: AtomicBool,
     /// Tracks whether close() was called
     was_closed: AtomicBool,
+    /// Set by the handler task of a browser connected via CDP URL when its
+    /// connection ends; a fresh flag is made for each connection.
+    connection_lost: std::sync::Mutex<Arc<AtomicBool>>,
+    /// Held while reconnecting, so concurrent actions reconnect only once.
+    reconnecting: Mutex<()>,
+    /// Where browser events go once `subscribe_events` was called.
+    event_sink: std::sync::Mutex<Option<Arc<PageEvents>>>,
+    /// Tabs whose events are being reported.
+    event_tabs: std::sync::Mutex<HashSet<TargetId>>,
 }
 
 impl CdpBrowserController {
     /// Create a new CDP browser controller with the given configuration.
     pub fn new(config: Config) -> Self {
+        let navigation = NavigationTracker::new(
+            config.max_navigations_per_domain,
+            config.detect_navigation_loops,
+        );
         Self {
             browser: Arc::new(Mutex::new(None)),
             page: Arc::new(Mutex::new(None)),
             config,
+            frame: Mutex::new(None),
+            tab_order: Mutex::new(Vec::new()),
+            sessions: std::sync::Mutex::new(HashMap::new()),
+            crashed_tabs: Arc::new(std::sync::Mutex::new(HashSet::new())),
+            document_scripts: Mutex::new(Vec::new()),
+            navigation,
+            state_delta: StateDeltaTracker::default(),
+            scroll_memory: ScrollMemory::default(),
+            media_emulation: MediaEmulations::default(),
             was_opened: AtomicBool::new(false),
             was_closed: AtomicBool::new(false),
+            connection_lost: std::sync::Mutex::new(Arc::new(AtomicBool::new(false))),
+            reconnecting: Mutex::new(()),
+            event_sink: std::sync::Mutex::new(None),
+            event_tabs: std::sync::Mutex::new(HashSet::new()),
         }
     }
 
@@ -73,6 +252,11 @@ impl CdpBrowserController {
             drop(page_guard);
             return self.connect(cdp_url).await;
         }
+        if self.config.attached_mode {
+            return Err(anyhow::anyhow!(
+                "Attached mode never launches a browser; set MCP_CDP_URL to the browser to attach to"
+            ));
+        }
 
         info!("Opening browser via CDP...");
 
@@ -86,6 +270,9 @@ impl CdpBrowserController {
                 is_landscape: false,
                 has_touch: false,
             })
+            .window_size(self.config.screen_width, self.config.screen_height)
+            // Headless switches are added below, so the builder must not add its own
+            .with_head()
             .disable_default_args()
             .arg("--disable-extensions")
             .arg("--disable-plugins")
@@ -96,8 +283,18 @@ impl CdpBrowserController {
             .arg("--no-first-run")
             .arg("--disable-popup-blocking");
 
+        if self.config.mute_audio {
+            builder = builder.arg("--mute-audio");
+        }
+        for arg in self.config.media_device_args() {
+            builder = builder.arg(arg);
+        }
+
         if self.config.headless {
-            builder = builder.arg("--headless=new").arg("--no-sandbox");
+            builder = builder
+                .arg(self.config.headless_mode.chromium_arg())
+                .arg("--hide-scrollbars")
+                .arg("--no-sandbox");
         }
 
         // Undetected mode settings
@@ -105,7 +302,8 @@ impl CdpBrowserController {
             info!("Enabling undetected mode");
             builder = builder
                 .arg("--disable-infobars")
-                .arg("--disable-notifications");
+                .arg("--disable-notifications")
+                .arg("--disable-blink-features=AutomationControlled");
         }
 
         // Set browser binary if specified
@@ -113,10 +311,22 @@ impl CdpBrowserController {
             builder = builder.chrome_executable(binary_path);
         }
 
+        if let Some(ref display) = self.config.display {
+            builder = builder.env("DISPLAY", display);
+        }
+
+        if let Some(ref dir) = self.config.profile_dir {
+            builder = builder.user_data_dir(dir);
+        }
+
+        if let Some(app) = self.config.app_mode_arg() {
+            builder = builder.arg(app);
+        }
+
         let config = builder.build().map_err(|e| anyhow::anyhow!("{}", e))?;
 
         // Launch browser
-        let (browser, mut handler) = Browser::launch(config)
+        let (mut browser, mut handler) = Browser::launch(config)
             .await
             .map_err(|e| anyhow::anyhow!("Failed to launch browser via CDP: {}", e))?;
 
@@ -129,11 +339,22 @@ impl CdpBrowserController {
             }
         });
 
-        // Create a new page and navigate to initial URL
-        let page = browser
-            .new_page(&self.config.initial_url)
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to create page: {}", e))?;
+        self.set_download_dir(&browser).await;
+        self.watch_browser_events(&browser).await;
+
+        // An app window opens with the browser; otherwise create a page and
+        // navigate to the initial URL
+        let app_page = match self.config.app_mode_url {
+            Some(_) => Self::existing_pages(&mut browser).await?.into_iter().next(),
+            None => None,
+        };
+        let page = match app_page {
+            Some(page) => page,
+            None => browser
+                .new_page(self.config.start_url())
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to create page: {}", e))?,
+        };
 
         // Apply stealth scripts if undetected mode is enabled
         if self.config.undetected {
@@ -161,11 +382,25 @@ impl CdpBrowserController {
             if let Err(e) = page.evaluate(stealth_script).await {
                 warn!("Failed to apply stealth script: {}", e);
             }
+            if let Err(e) = page.evaluate(media_devices::DEVICE_STUB_SCRIPT).await {
+                warn!("Failed to apply media device stub: {}", e);
+            }
+        }
+
+        self.prepare_page(&page).await;
+
+        // Open configured initial tabs; the first page stays the active one
+        for url in &self.config.initial_tabs {
+            if let Err(e) = browser.new_page(normalize_url(url)).await {
+                warn!("Failed to open initial tab {}: {}", url, e);
+            }
         }
 
         *browser_guard = Some(browser);
         *page_guard = Some(page);
-        self.was_opened.store(true, Ordering::SeqCst);
+        if self.was_opened.swap(true, Ordering::SeqCst) {
+            metrics::record_browser_restart();
+        }
 
         drop(browser_guard);
         drop(page_guard);
@@ -174,6 +409,20 @@ impl CdpBrowserController {
         self.current_state().await
     }
 
+    /// Save downloads to the session's scratch directory, when it has one.
+    async fn set_download_dir(&self, browser: &Browser) {
+        let Some(ref dir) = self.config.download_dir else {
+            return;
+        };
+        let mut params = SetDownloadBehaviorParams::new(SetDownloadBehaviorBehavior::Allow);
+        params.download_path = Some(dir.to_string_lossy().into_owned());
+        // Reported to event subscriptions
+        params.events_enabled = Some(true);
+        if let Err(e) = browser.execute(params).await {
+            warn!("Failed to set download directory {:?}: {}", dir, e);
+        }
+    }
+
     /// Connect to an existing browser via CDP.
     pub async fn connect(&self, cdp_url: &str) -> Result<EnvState> {
         let mut browser_guard = self.browser.lock().await;
@@ -187,27 +436,64 @@ impl CdpBrowserController {
 
         info!("Connecting to browser via CDP at: {}", cdp_url);
 
-        let (browser, mut handler) = Browser::connect(cdp_url)
+        let (browser, page) = self.attach(cdp_url, None).await?;
+
+        *browser_guard = Some(browser);
+        *page_guard = Some(page);
+        if self.was_opened.swap(true, Ordering::SeqCst) {
+            metrics::record_browser_restart();
+        }
+
+        drop(browser_guard);
+        drop(page_guard);
+
+        info!("Connected to browser via CDP");
+        self.current_state().await
+    }
+
+    /// Connect to the browser at `cdp_url` and pick the tab to act on: the tab
+    /// `previous` if it is still open, otherwise the first tab.
+    async fn attach(&self, cdp_url: &str, previous: Option<&str>) -> Result<(Browser, Page)> {
+        let (mut browser, mut handler) = Browser::connect(cdp_url)
             .await
             .map_err(|e| anyhow::anyhow!("Failed to connect to browser via CDP: {}", e))?;
 
-        // Spawn handler task
+        // Spawn handler task; it ends with the connection
+        let connection_lost = Arc::new(AtomicBool::new(false));
+        *self
+            .connection_lost
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Arc::clone(&connection_lost);
         tokio::spawn(async move {
             while let Some(h) = handler.next().await {
                 if h.is_err() {
                     break;
                 }
             }
+            connection_lost.store(true, Ordering::SeqCst);
         });
 
-        // Get existing pages or create a new one
-        let pages = browser
-            .pages()
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to get pages: {}", e))?;
+        // An attached browser belongs to the user and keeps its download settings
+        if !self.config.attached_mode {
+            self.set_download_dir(&browser).await;
+        }
+        self.watch_browser_events(&browser).await;
+
+        let mut pages = Self::existing_pages(&mut browser).await?;
+        if let Some(index) = previous.and_then(|previous| {
+            pages
+                .iter()
+                .position(|page| page.target_id().as_ref() == previous)
+        }) {
+            pages.swap(0, index);
+        }
 
         let page = if let Some(existing_page) = pages.into_iter().next() {
             existing_page
+        } else if self.config.attached_mode {
+            return Err(anyhow::anyhow!(
+                "The browser has no open tab to attach to; open one and try again"
+            ));
         } else {
             browser
                 .new_page(&self.config.initial_url)
@@ -215,25 +501,148 @@ impl CdpBrowserController {
                 .map_err(|e| anyhow::anyhow!("Failed to create page: {}", e))?
         };
 
-        *browser_guard = Some(browser);
-        *page_guard = Some(page);
-        self.was_opened.store(true, Ordering::SeqCst);
+        self.prepare_page(&page).await;
+        Ok((browser, page))
+    }
 
-        drop(browser_guard);
-        drop(page_guard);
+    /// Reconnect to a browser connected via CDP URL whose connection dropped,
+    /// e.g. because it was restarted, keeping the active tab when it survived.
+    /// The sessions of the old connection are discarded.
+    async fn reconnect_if_lost(&self) -> Result<()> {
+        let Some(ref cdp_url) = self.config.cdp_url else {
+            return Ok(());
+        };
+        if !self
+            .connection_lost
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .load(Ordering::SeqCst)
+        {
+            return Ok(());
+        }
+        let _reconnecting = self.reconnecting.lock().await;
+        // Another action may have reconnected meanwhile
+        if !self
+            .connection_lost
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .load(Ordering::SeqCst)
+        {
+            return Ok(());
+        }
+        let mut browser_guard = self.browser.lock().await;
+        let mut page_guard = self.page.lock().await;
+        if browser_guard.is_none() {
+            return Ok(());
+        }
 
-        info!("Connected to browser via CDP");
-        self.current_state().await
+        let previous = page_guard
+            .as_ref()
+            .map(|page| page.target_id().as_ref().to_string());
+        warn!("Lost the CDP connection to {}, reconnecting", cdp_url);
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.event_tabs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.document_scripts.lock().await.clear();
+        *self.frame.lock().await = None;
+
+        let mut last_error = None;
+        for attempt in 1..=RECONNECT_ATTEMPTS {
+            tokio::time::sleep(RECONNECT_DELAY * attempt).await;
+            match self.attach(cdp_url, previous.as_deref()).await {
+                Ok((browser, page)) => {
+                    *browser_guard = Some(browser);
+                    *page_guard = Some(page);
+                    info!("Reconnected to browser via CDP");
+                    return Ok(());
+                }
+                Err(e) => {
+                    debug!(
+                        "Reconnect attempt {}/{} failed: {}",
+                        attempt, RECONNECT_ATTEMPTS, e
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(anyhow::anyhow!(
+            "Lost the connection to the browser at {} and could not reconnect: {}",
+            cdp_url,
+            last_error.map(|e| e.to_string()).unwrap_or_default()
+        ))
+    }
+
+    /// Pages that were open before we connected. They are only visible once
+    /// attached, which happens in the background.
+    async fn existing_pages(browser: &mut Browser) -> Result<Vec<Page>> {
+        let existing = browser
+            .fetch_targets()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get targets: {}", e))?
+            .into_iter()
+            .filter(|target| target.r#type == "page")
+            .count();
+        let deadline = Instant::now() + ATTACH_TIMEOUT;
+        loop {
+            let pages = browser
+                .pages()
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to get pages: {}", e))?;
+            if pages.len() >= existing || Instant::now() >= deadline {
+                return Ok(pages);
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
     }
 
     /// Close the browser.
-    #[allow(dead_code)]
+    /// A browser launched by this controller is shut down and reaped; a browser
+    /// connected via CDP URL is left running, with the scripts added to its tabs removed.
     pub async fn close(&self) -> Result<()> {
         let mut browser_guard = self.browser.lock().await;
         let mut page_guard = self.page.lock().await;
 
         *page_guard = None;
-        if let Some(browser) = browser_guard.take() {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.event_tabs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        let document_scripts = std::mem::take(&mut *self.document_scripts.lock().await);
+        *self.frame.lock().await = None;
+        if let Some(mut browser) = browser_guard.take() {
+            // Only a browser we launched is shut down; a connected browser is released after
+            // removing the scripts added to its tabs, and in attached mode is never shut down
+            if browser.get_mut_child().is_none() || self.config.attached_mode {
+                for (page, identifier) in document_scripts {
+                    let params = RemoveScriptToEvaluateOnNewDocumentParams::new(identifier);
+                    if let Err(e) = page.execute(params).await {
+                        debug!("Failed to remove document script: {}", e);
+                    }
+                }
+            } else {
+                if let Err(e) = browser.close().await {
+                    warn!("Failed to close browser gracefully: {}", e);
+                }
+                match tokio::time::timeout(BROWSER_EXIT_TIMEOUT, browser.wait()).await {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => warn!("Failed to wait for browser exit: {}", e),
+                    Err(_) => {
+                        warn!("Browser did not exit in time, killing it");
+                        if let Some(Err(e)) = browser.kill().await {
+                            warn!("Failed to kill browser: {}", e);
+                        }
+                    }
+                }
+            }
             drop(browser);
             self.was_closed.store(true, Ordering::SeqCst);
             info!("Browser closed");
@@ -242,8 +651,35 @@ impl CdpBrowserController {
         Ok(())
     }
 
-    /// Get the current page reference.
+    /// Run an action against the given tab instead of the active one.
+    ///
+    /// The tab is not activated, so actions in different tabs can run concurrently.
+    pub async fn in_tab<F: Future>(tab: Option<&str>, action: F) -> F::Output {
+        TARGET_TAB.scope(tab.map(String::from), action).await
+    }
+
+    /// Get the page the current action targets: the tab selected with `in_tab`,
+    /// or the active tab.
     async fn get_page(&self) -> Result<Page> {
+        self.reconnect_if_lost().await?;
+        match TARGET_TAB.try_with(|tab| tab.clone()).ok().flatten() {
+            Some(handle) if self.config.attached_mode => {
+                let page = self.active_page().await?;
+                if page.target_id().as_ref() != handle {
+                    return Err(anyhow::anyhow!(
+                        "Attached mode only acts on the attached tab; use attach_to_tab to change it"
+                    ));
+                }
+                Ok(page)
+            }
+            Some(handle) => self.find_tab(&handle).await,
+            None => self.active_page().await,
+        }
+    }
+
+    /// Get the active page.
+    async fn active_page(&self) -> Result<Page> {
+        self.reconnect_if_lost().await?;
         let page_guard = self.page.lock().await;
         page_guard
             .clone()
@@ -252,93 +688,635 @@ impl CdpBrowserController {
 
     /// Get the current state (screenshot and URL).
     pub async fn current_state(&self) -> Result<EnvState> {
+        let started = Instant::now();
         let page = self.get_page().await?;
+        let session_ms = started.elapsed().as_millis() as u64;
 
         // Wait for page to be ready
+        let settle_started = Instant::now();
         tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        let settle_ms = settle_started.elapsed().as_millis() as u64;
+
+        // A crashed tab would only produce a blank screenshot
+        if self.take_crash(&page) {
+            return Err(self.recover_from_crash(&page).await.into());
+        }
+
+        self.zoom_to_fit(&page).await;
+        // The handler answers the URL from the frames it tracks without asking
+        // the browser, so it is read while the screenshot is captured
+        let capture_started = Instant::now();
+        let (url, mut screenshot_bytes) = tokio::try_join!(
+            async {
+                page.url()
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Failed to get URL: {}", e))
+            },
+            self.capture_screenshot(&page)
+        )?;
+        let url = url.unwrap_or_else(|| "about:blank".to_string());
+        // Pages sometimes paint nothing right after a navigation
+        if !url.starts_with("about:") {
+            for attempt in 1..=self.config.blank_screenshot_retries {
+                let (bytes, blank) = check_blank_screenshot(screenshot_bytes).await?;
+                if !blank {
+                    screenshot_bytes = bytes;
+                    break;
+                }
+                debug!(
+                    "Screenshot is blank, capturing again ({}/{})",
+                    attempt, self.config.blank_screenshot_retries
+                );
+                tokio::time::sleep(Duration::from_millis(BLANK_SCREENSHOT_RETRY_DELAY_MS)).await;
+                screenshot_bytes = self.capture_screenshot(&page).await?;
+            }
+        }
+
+        let capture_ms = capture_started.elapsed().as_millis() as u64;
+
+        let encode_started = Instant::now();
+        let screenshot =
+            encode_screenshot(screenshot_bytes, self.config.max_screenshot_dimension).await?;
+        let encode_ms = encode_started.elapsed().as_millis() as u64;
+        let timings = StateTimings {
+            settle_ms,
+            screenshot_ms: capture_ms + encode_ms,
+        };
+
+        let extras_started = Instant::now();
+        let notifications = self.unreported_notifications(&page).await;
+        let warnings = self.navigation.record(page.target_id().as_ref(), &url);
+        let delta = self.state_delta(&page, &url).await;
+        self.record_scroll(&page, &url).await;
+        let login_required = self.login_required(&page, &url).await;
+        debug!(
+            session_ms,
+            settle_ms,
+            capture_ms,
+            encode_ms,
+            extras_ms = extras_started.elapsed().as_millis() as u64,
+            total_ms = started.elapsed().as_millis() as u64,
+            "Captured page state"
+        );
+
+        Ok(EnvState {
+            screenshot,
+            url,
+            notifications,
+            warnings,
+            delta,
+            effect_detected: None,
+            timings,
+            login_required,
+        })
+    }
 
-        // Take screenshot
-        let screenshot_bytes = page
+    /// Take a screenshot of the tab, recovering it if it crashed meanwhile.
+    async fn capture_screenshot(&self, page: &Page) -> Result<Vec<u8>> {
+        match page
             .screenshot(
                 ScreenshotParams::builder()
                     .format(CaptureScreenshotFormat::Png)
                     .build(),
             )
             .await
-            .map_err(|e| anyhow::anyhow!("Failed to take screenshot: {}", e))?;
+        {
+            Ok(bytes) => Ok(bytes),
+            Err(_) if self.take_crash(page) => Err(self.recover_from_crash(page).await.into()),
+            Err(e) => Err(anyhow::anyhow!("Failed to take screenshot: {}", e)),
+        }
+    }
 
-        let screenshot = BASE64.encode(&screenshot_bytes);
-        let url = page
-            .url()
+    /// Set up a tab the controller acts on: notification capture, crash detection,
+    /// and in attached mode, keeping navigation in the tab.
+    async fn prepare_page(&self, page: &Page) {
+        self.install_notification_capture(page).await;
+        if self.config.state_delta {
+            self.add_document_script(page, &state_delta::track_script(), "activity tracking")
+                .await;
+        }
+        self.watch_for_crash(page).await;
+        if self.config.attached_mode {
+            self.add_document_script(page, SAME_TAB_SCRIPT, "same-tab navigation")
+                .await;
+        }
+        self.watch_page_events(page).await;
+    }
+
+    /// Report browser events to `events` from now on: the navigations,
+    /// dialogs and console messages of every open tab and of tabs opened by
+    /// the server later, and the tabs and downloads of the browser.
+    pub async fn watch_events(&self, events: Arc<PageEvents>) -> Result<()> {
+        let first = self
+            .event_sink
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace(events)
+            .is_none();
+        if !first {
+            return Ok(());
+        }
+        // Without a browser, listening starts when it is opened
+        match *self.browser.lock().await {
+            Some(ref browser) => self.watch_browser_events(browser).await,
+            None => return Ok(()),
+        }
+        for page in self.tabs().await? {
+            self.watch_page_events(&page).await;
+        }
+        Ok(())
+    }
+
+    /// Report the tabs opened and closed and the downloads started in a
+    /// browser, when events are subscribed to.
+    async fn watch_browser_events(&self, browser: &Browser) {
+        let Some(events) = self
+            .event_sink
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+        else {
+            return;
+        };
+        match browser.event_listener::<EventTargetCreated>().await {
+            Ok(created) => forward_events(created, &events, EventType::Target, |event| {
+                let target = &event.target_info;
+                (target.r#type == "page").then(|| EventDetails {
+                    tab: Some(target.target_id.as_ref().to_string()),
+                    url: Some(target.url.clone()),
+                    subtype: Some("opened".to_string()),
+                    text: None,
+                })
+            }),
+            Err(e) => warn!("Failed to listen for new tabs: {}", e),
+        }
+        match browser.event_listener::<EventTargetDestroyed>().await {
+            Ok(destroyed) => forward_events(destroyed, &events, EventType::Target, |event| {
+                Some(EventDetails {
+                    tab: Some(event.target_id.as_ref().to_string()),
+                    subtype: Some("closed".to_string()),
+                    ..EventDetails::default()
+                })
+            }),
+            Err(e) => warn!("Failed to listen for closed tabs: {}", e),
+        }
+        match browser.event_listener::<EventDownloadWillBegin>().await {
+            Ok(downloads) => forward_events(downloads, &events, EventType::Download, |event| {
+                Some(EventDetails {
+                    url: Some(event.url.clone()),
+                    text: Some(event.suggested_filename.clone()),
+                    ..EventDetails::default()
+                })
+            }),
+            Err(e) => warn!("Failed to listen for downloads: {}", e),
+        }
+    }
+
+    /// Report the navigations, dialogs and console messages of a tab, once
+    /// per tab, when events are subscribed to.
+    async fn watch_page_events(&self, page: &Page) {
+        let Some(events) = self
+            .event_sink
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+        else {
+            return;
+        };
+        if !self
+            .event_tabs
+            .lock()
+            .unwrap()
+            .insert(page.target_id().clone())
+        {
+            return;
+        }
+        let tab = page.target_id().as_ref().to_string();
+        match page.event_listener::<EventFrameNavigated>().await {
+            Ok(navigations) => {
+                let tab = tab.clone();
+                forward_events(navigations, &events, EventType::Navigation, move |event| {
+                    event.frame.parent_id.is_none().then(|| EventDetails {
+                        tab: Some(tab.clone()),
+                        url: Some(event.frame.url.clone()),
+                        ..EventDetails::default()
+                    })
+                })
+            }
+            Err(e) => warn!("Failed to listen for navigations: {}", e),
+        }
+        match page.event_listener::<EventJavascriptDialogOpening>().await {
+            Ok(dialogs) => {
+                let tab = tab.clone();
+                forward_events(dialogs, &events, EventType::Dialog, move |event| {
+                    Some(EventDetails {
+                        tab: Some(tab.clone()),
+                        url: Some(event.url.clone()),
+                        subtype: Some(event.r#type.as_ref().to_string()),
+                        text: Some(event.message.clone()),
+                    })
+                })
+            }
+            Err(e) => warn!("Failed to listen for dialogs: {}", e),
+        }
+        match page.event_listener::<EventConsoleApiCalled>().await {
+            Ok(messages) => forward_events(messages, &events, EventType::Console, move |event| {
+                let text = event
+                    .args
+                    .iter()
+                    .map(|arg| match arg.value {
+                        Some(serde_json::Value::String(ref text)) => text.clone(),
+                        Some(ref value) => value.to_string(),
+                        None => arg.description.clone().unwrap_or_default(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                Some(EventDetails {
+                    tab: Some(tab.clone()),
+                    url: None,
+                    subtype: Some(event.r#type.as_ref().to_string()),
+                    text: Some(text),
+                })
+            }),
+            Err(e) => warn!("Failed to listen for console messages: {}", e),
+        }
+    }
+
+    /// Run a script in a tab now and in every document it loads later.
+    async fn add_document_script(&self, page: &Page, script: &str, name: &str) {
+        match page.evaluate_on_new_document(script).await {
+            Ok(identifier) => self
+                .document_scripts
+                .lock()
+                .await
+                .push((page.clone(), identifier)),
+            Err(e) => warn!("Failed to add {} script: {}", name, e),
+        }
+        if let Err(e) = page.evaluate(script).await {
+            warn!("Failed to install {}: {}", name, e);
+        }
+    }
+
+    /// Listen for `Inspector.targetCrashed`, marking the tab as crashed so its
+    /// next action reports the crash instead of a blank screenshot.
+    async fn watch_for_crash(&self, page: &Page) {
+        if let Err(e) = page.execute(InspectorEnableParams::default()).await {
+            warn!("Failed to enable crash detection: {}", e);
+            return;
+        }
+        let mut crashes = match page.event_listener::<EventTargetCrashed>().await {
+            Ok(crashes) => crashes,
+            Err(e) => {
+                warn!("Failed to enable crash detection: {}", e);
+                return;
+            }
+        };
+        let crashed_tabs = self.crashed_tabs.clone();
+        let target = page.target_id().clone();
+        tokio::spawn(async move {
+            while crashes.next().await.is_some() {
+                warn!("Tab {} crashed", target.as_ref());
+                crashed_tabs
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(target.clone());
+            }
+        });
+    }
+
+    /// Whether the tab crashed since this was last checked.
+    fn take_crash(&self, page: &Page) -> bool {
+        self.crashed_tabs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(page.target_id())
+    }
+
+    /// Reload a crashed tab, returning the error to report.
+    async fn recover_from_crash(&self, page: &Page) -> PageCrashed {
+        warn!("Reloading crashed tab {}", page.target_id().as_ref());
+        metrics::record_page_crash();
+        self.leave_frame(page).await;
+        let reloaded = match page.reload().await {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("Failed to reload crashed tab: {}", e);
+                false
+            }
+        };
+        PageCrashed { reloaded }
+    }
+
+    /// Record web notifications in a tab, now and on every document it loads later.
+    async fn install_notification_capture(&self, page: &Page) {
+        if !self.config.capture_notifications {
+            return;
+        }
+        self.add_document_script(page, notifications::CAPTURE_SCRIPT, "notification capture")
+            .await;
+    }
+
+    /// Whether the page asks the user to sign in, when login walls are
+    /// detected. Without page signals, only the URL is checked.
+    async fn login_required(&self, page: &Page, url: &str) -> bool {
+        if !self.config.detect_login || url.starts_with("about:") {
+            return false;
+        }
+        let signals = page
+            .evaluate(login_wall::SIGNALS_SCRIPT)
             .await
-            .map_err(|e| anyhow::anyhow!("Failed to get URL: {}", e))?
-            .unwrap_or_else(|| "about:blank".to_string());
+            .map_err(anyhow::Error::from)
+            .and_then(|value| Ok(value.into_value::<LoginSignals>()?))
+            .unwrap_or_else(|e| {
+                debug!("Failed to read login signals: {}", e);
+                LoginSignals::default()
+            });
+        login_wall::login_required(url, &signals)
+    }
+
+    /// Zoom the page out to fit its content width, when enabled. Pages with a
+    /// frame selected are left alone, as zooming would move the frame.
+    async fn zoom_to_fit(&self, page: &Page) {
+        if !self.config.zoom_to_fit || self.selected_frame(page).await.is_some() {
+            return;
+        }
+        if let Err(e) = page
+            .evaluate(zoom_to_fit::fit_script(self.config.zoom_to_fit_min))
+            .await
+        {
+            debug!("Failed to zoom page to fit: {}", e);
+        }
+    }
+
+    /// Changes since the tab's previous state, when they are reported.
+    /// Failures are ignored so a response is never lost over them.
+    async fn state_delta(&self, page: &Page, url: &str) -> Option<StateDelta> {
+        if !self.config.state_delta {
+            return None;
+        }
+        let activity = match page.evaluate(state_delta::read_script()).await {
+            Ok(value) => value.into_value::<PageActivity>(),
+            Err(e) => {
+                debug!("Failed to read page activity: {}", e);
+                return None;
+            }
+        };
+        match activity {
+            Ok(activity) => Some(
+                self.state_delta
+                    .diff(page.target_id().as_ref(), url, activity),
+            ),
+            Err(e) => {
+                debug!("Failed to parse page activity: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Remember the scroll position of the page a tab shows.
+    async fn record_scroll(&self, page: &Page, url: &str) {
+        if !self.config.restore_scroll || url.starts_with("about:") {
+            return;
+        }
+        match page.evaluate(scroll_memory::POSITION_SCRIPT).await {
+            Ok(value) => match value.into_value::<ScrollPosition>() {
+                Ok(position) => self
+                    .scroll_memory
+                    .record(page.target_id().as_ref(), url, position),
+                Err(e) => debug!("Failed to parse scroll position: {}", e),
+            },
+            Err(e) => debug!("Failed to read scroll position: {}", e),
+        }
+    }
+
+    /// Scroll a page reached through history back to where it was last seen.
+    /// Returns the restored offset, if the page had been scrolled and is not
+    /// the page at `from_url` the navigation started on.
+    async fn restore_scroll(&self, page: &Page, from_url: &str) -> Option<ScrollPosition> {
+        if !self.config.restore_scroll {
+            return None;
+        }
+        let url = page.url().await.ok().flatten()?;
+        if url == from_url {
+            return None;
+        }
+        let saved = self.scroll_memory.saved(page.target_id().as_ref(), &url)?;
+        if saved.is_origin() {
+            return None;
+        }
+        match page.evaluate(scroll_memory::restore_script(saved)).await {
+            Ok(value) => value.into_value::<ScrollPosition>().ok(),
+            Err(e) => {
+                debug!("Failed to restore scroll position: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Notifications not yet reported, when they are included in responses.
+    /// Failures are ignored so a response is never lost over them.
+    async fn unreported_notifications(&self, page: &Page) -> Vec<CapturedNotification> {
+        if !self.config.capture_notifications || !self.config.notifications_in_response {
+            return Vec::new();
+        }
+        match page.evaluate(notifications::UNREPORTED_SCRIPT).await {
+            Ok(result) => result
+                .value()
+                .and_then(|value| notifications::parse_notifications(value).ok().flatten())
+                .unwrap_or_default(),
+            Err(e) => {
+                debug!("Failed to read notifications: {}", e);
+                Vec::new()
+            }
+        }
+    }
 
-        Ok(EnvState { screenshot, url })
+    /// Wait until the element at (`x`, `y`) stops moving, up to the configured
+    /// click stability timeout. Returns whether it settled in time.
+    async fn wait_for_stable_target(&self, page: &Page, x: i64, y: i64) -> bool {
+        let timeout = self.config.click_stability_timeout;
+        let start = std::time::Instant::now();
+        let mut unchanged = 0;
+        let mut reset = true;
+        while start.elapsed() < timeout {
+            let same = match self.evaluate(page, stability_script(x, y, reset)).await {
+                Ok(value) => value.into_value::<bool>().unwrap_or(false),
+                Err(_) => false,
+            };
+            reset = false;
+            unchanged = if same { unchanged + 1 } else { 0 };
+            if unchanged >= STABLE_CHECKS {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(STABILITY_POLL_INTERVAL_MS)).await;
+        }
+        false
     }
 
     /// Click at specific coordinates.
     pub async fn click_at(&self, x: i64, y: i64) -> Result<EnvState> {
+        let (x, y) = validate_coordinates(x, y, &self.config)?;
         debug!("Clicking at ({}, {})", x, y);
         let page = self.get_page().await?;
+        // Native mouse events take screenshot coordinates, whatever frame is selected
+        let (screen_x, screen_y) = (x, y);
+        let (x, y) = self.to_frame_coordinates(&page, x, y).await;
+        if !self.config.click_stability_timeout.is_zero()
+            && !self.wait_for_stable_target(&page, x, y).await
+        {
+            debug!("Element at ({}, {}) still moving, clicking anyway", x, y);
+        }
+        let start_url = page.url().await.ok().flatten().unwrap_or_default();
+        let watching = self
+            .evaluate(&page, CLICK_EFFECT_WATCH_SCRIPT)
+            .await
+            .is_ok();
+
+        if self.config.cdp_js_click {
+            let script = format!(
+                r#"
+                (function() {{
+                    var element = document.elementFromPoint({}, {});
+                    if (element) {{
+                        element.click();
+                        return true;
+                    }}
+                    return false;
+                }})();
+                "#,
+                x, y
+            );
 
-        // Use JavaScript to click at coordinates
-        let script = format!(
-            r#"
-            (function() {{
-                var element = document.elementFromPoint({}, {});
-                if (element) {{
-                    element.click();
-                    return true;
-                }}
-                return false;
-            }})();
-            "#,
-            x, y
-        );
+            self.evaluate(&page, script)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to click: {}", e))?;
+        } else {
+            self.dispatch_click(&page, screen_x, screen_y).await?;
+        }
+
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        let mut state = self.current_state().await?;
+        if watching && state.url == start_url {
+            let changed = match self.evaluate(&page, CLICK_EFFECT_READ_SCRIPT).await {
+                Ok(value) => value.into_value::<bool>().unwrap_or(true),
+                Err(_) => true,
+            };
+            if !changed {
+                state.effect_detected = Some(false);
+            }
+        }
+        Ok(state)
+    }
+
+    /// Hover at specific coordinates, keeping the hover alive for `duration_ms`
+    /// when given.
+    pub async fn hover_at(&self, x: i64, y: i64, duration_ms: Option<u64>) -> Result<EnvState> {
+        let (x, y) = validate_coordinates(x, y, &self.config)?;
+        if let Some(duration_ms) = duration_ms {
+            validate_hover_duration(duration_ms)?;
+        }
+        debug!("Hovering at ({}, {}) for {:?}ms", x, y, duration_ms);
+        let page = self.get_page().await?;
+        let (x, y) = self.to_frame_coordinates(&page, x, y).await;
 
-        page.evaluate(script)
+        self.evaluate(&page, hover_script(x, y, true))
             .await
-            .map_err(|e| anyhow::anyhow!("Failed to click: {}", e))?;
+            .map_err(|e| anyhow::anyhow!("Failed to hover: {}", e))?;
+
+        match duration_ms {
+            Some(duration_ms) => {
+                let deadline = Instant::now() + Duration::from_millis(duration_ms);
+                while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+                    tokio::time::sleep(
+                        remaining.min(Duration::from_millis(HOVER_REFRESH_INTERVAL_MS)),
+                    )
+                    .await;
+                    self.evaluate(&page, hover_script(x, y, false))
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Failed to hover: {}", e))?;
+                }
+            }
+            None => tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await,
+        }
 
-        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
         self.current_state().await
     }
 
-    /// Hover at specific coordinates.
-    pub async fn hover_at(&self, x: i64, y: i64) -> Result<EnvState> {
-        debug!("Hovering at ({}, {})", x, y);
+    /// Click with native mouse events at viewport coordinates: move there,
+    /// then press and release the left button. The page receives trusted
+    /// events, as from a real mouse.
+    async fn dispatch_click(&self, page: &Page, x: i64, y: i64) -> Result<()> {
+        for (event_type, buttons) in [
+            (DispatchMouseEventType::MouseMoved, 0),
+            (DispatchMouseEventType::MousePressed, 1),
+            (DispatchMouseEventType::MouseReleased, 0),
+        ] {
+            let mut params = DispatchMouseEventParams::builder()
+                .r#type(event_type.clone())
+                .x(x as f64)
+                .y(y as f64)
+                .buttons(buttons);
+            if event_type != DispatchMouseEventType::MouseMoved {
+                params = params.button(MouseButton::Left).click_count(1);
+            }
+            let params = params
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to build mouse event: {}", e))?;
+            page.execute(params)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to click: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Press at the first point of a path, move through the others and
+    /// release at the last, with native mouse or touch events.
+    pub async fn draw_path(
+        &self,
+        points: &[PathPoint],
+        duration_ms: u64,
+        pointer: PointerType,
+    ) -> Result<EnvState> {
+        // Native input events take screenshot coordinates, whatever frame is selected
+        let points = points
+            .iter()
+            .map(|point| {
+                validate_coordinates(point.x, point.y, &self.config)
+                    .map(|(x, y)| PathPoint { x, y })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let events =
+            draw_path::plan(&points, duration_ms, pointer).map_err(|e| anyhow::anyhow!(e))?;
+        debug!("Drawing a path of {} events", events.len());
         let page = self.get_page().await?;
 
-        let script = format!(
-            r#"
-            (function() {{
-                var element = document.elementFromPoint({}, {});
-                if (element) {{
-                    var events = ['mouseenter', 'mouseover', 'mousemove'];
-                    events.forEach(function(eventType) {{
-                        var event = new MouseEvent(eventType, {{
-                            view: window,
-                            bubbles: true,
-                            cancelable: true,
-                            clientX: {},
-                            clientY: {}
-                        }});
-                        element.dispatchEvent(event);
-                    }});
-                    return true;
-                }}
-                return false;
-            }})();
-            "#,
-            x, y, x, y
-        );
+        for event in events {
+            tokio::time::sleep(event.delay).await;
+            let sent = match pointer {
+                PointerType::Mouse => {
+                    let params: DispatchMouseEventParams = serde_json::from_value(event.params)?;
+                    page.execute(params).await.map(|_| ())
+                }
+                PointerType::Touch => {
+                    let params: DispatchTouchEventParams = serde_json::from_value(event.params)?;
+                    page.execute(params).await.map(|_| ())
+                }
+            };
+            sent.map_err(|e| anyhow::anyhow!("Failed to draw path: {}", e))?;
+        }
+        self.current_state().await
+    }
 
-        page.evaluate(script)
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to hover: {}", e))?;
+    /// End a hover by moving the mouse off the hovered element.
+    pub async fn move_mouse_away(&self) -> Result<EnvState> {
+        debug!("Moving mouse away");
+        let page = self.get_page().await?;
 
+        self.evaluate(&page, MOVE_MOUSE_AWAY_SCRIPT)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to move mouse away: {}", e))?;
         tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+
         self.current_state().await
     }
 
@@ -349,10 +1327,13 @@ impl CdpBrowserController {
         y: i64,
         text: &str,
         press_enter: bool,
-        clear_before_typing: bool,
+        mode: InsertionMode,
+        simulate_keystrokes: bool,
     ) -> Result<EnvState> {
-        debug!("Typing at ({}, {}): {}", x, y, text);
+        let (x, y) = validate_coordinates(x, y, &self.config)?;
+        debug!("Typing at ({}, {}) with {:?}: {}", x, y, mode, text);
         let page = self.get_page().await?;
+        let (x, y) = self.to_frame_coordinates(&page, x, y).await;
 
         // Click to focus element
         let click_script = format!(
@@ -365,13 +1346,13 @@ impl CdpBrowserController {
             "#,
             x, y
         );
-        page.evaluate(click_script)
+        self.evaluate(&page, click_script)
             .await
             .map_err(|e| anyhow::anyhow!("Failed to focus: {}", e))?;
 
         tokio::time::sleep(Duration::from_millis(TYPING_DELAY_MS)).await;
 
-        if clear_before_typing {
+        if mode == InsertionMode::ReplaceAll {
             // Clear content using appropriate method for each element type
             let clear_script = r#"
                 var active = document.activeElement;
@@ -388,56 +1369,33 @@ impl CdpBrowserController {
                     selection.deleteFromDocument();
                 }
             "#;
-            page.evaluate(clear_script)
+            self.evaluate(&page, clear_script)
                 .await
                 .map_err(|e| anyhow::anyhow!("Failed to clear: {}", e))?;
+        } else if let Some(script) = prepare_insertion_script(mode) {
+            self.evaluate(&page, script)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to place the caret: {}", e))?;
         }
 
-        // Type text using JavaScript
-        let escaped_text = serde_json::to_string(text)
-            .unwrap_or_else(|_| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"")));
-        let type_script = format!(
-            r#"
-            (function() {{
-                var text = {};
-                var active = document.activeElement;
-                if (active && (active.tagName === 'INPUT' || active.tagName === 'TEXTAREA')) {{
-                    active.value = (active.value || '') + text;
-                    active.dispatchEvent(new Event('input', {{ bubbles: true }}));
-                }} else if (active && active.isContentEditable) {{
-                    // Replace selection with text node in contentEditable elements
-                    var selection = window.getSelection();
-                    if (selection.rangeCount > 0) {{
-                        var range = selection.getRangeAt(0);
-                        range.deleteContents();
-                        var textNode = document.createTextNode(text);
-                        range.insertNode(textNode);
-                        // Move cursor to end of inserted text
-                        range.setStartAfter(textNode);
-                        range.setEndAfter(textNode);
-                        selection.removeAllRanges();
-                        selection.addRange(range);
-                    }} else {{
-                        active.textContent += text;
-                    }}
-                }} else {{
-                    // Fallback: dispatch key events
-                    for (var i = 0; i < text.length; i++) {{
-                        var event = new KeyboardEvent('keypress', {{
-                            key: text[i],
-                            charCode: text.charCodeAt(i),
-                            bubbles: true
-                        }});
-                        active.dispatchEvent(event);
-                    }}
-                }}
-            }})();
-            "#,
-            escaped_text
-        );
-        page.evaluate(type_script)
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to type: {}", e))?;
+        // Type with native input commands so the page gets trusted input events.
+        // CJK text is entered as an IME composition that is then committed,
+        // which fires the composition events some editors depend on.
+        for run in text_runs(text) {
+            if simulate_keystrokes && !matches!(run, TextRun::Composed(_)) {
+                self.type_keystrokes(&page, run.text()).await?;
+                continue;
+            }
+            if let TextRun::Composed(run) = run {
+                let length = run.encode_utf16().count() as i64;
+                page.execute(ImeSetCompositionParams::new(run, length, length))
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Failed to compose text: {}", e))?;
+            }
+            page.execute(InsertTextParams::new(run.text()))
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to type: {}", e))?;
+        }
 
         if press_enter {
             // Use CDP to send Enter key
@@ -466,23 +1424,61 @@ impl CdpBrowserController {
         self.current_state().await
     }
 
+    /// Type text one character at a time with key down, char and key up events,
+    /// for pages that react to individual keystrokes.
+    async fn type_keystrokes(&self, page: &Page, text: &str) -> Result<()> {
+        for c in text.chars() {
+            let (key, text) = match c {
+                '\n' | '\r' => ("Enter".to_string(), "\r".to_string()),
+                c => (c.to_string(), c.to_string()),
+            };
+            let key_down = DispatchKeyEventParams::builder()
+                .r#type(DispatchKeyEventType::KeyDown)
+                .key(key.as_str())
+                .text(text)
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to build key down params: {}", e))?;
+            page.execute(key_down)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to press key: {}", e))?;
+
+            let key_up = DispatchKeyEventParams::builder()
+                .r#type(DispatchKeyEventType::KeyUp)
+                .key(key.as_str())
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to build key up params: {}", e))?;
+            page.execute(key_up)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to release key: {}", e))?;
+
+            tokio::time::sleep(Duration::from_millis(KEYSTROKE_DELAY_MS)).await;
+        }
+        Ok(())
+    }
+
     /// Scroll the entire document.
-    pub async fn scroll_document(&self, direction: &str) -> Result<EnvState> {
-        debug!("Scrolling document: {}", direction);
+    pub async fn scroll_document(
+        &self,
+        direction: &str,
+        amount: ScrollAmount,
+        smooth: bool,
+    ) -> Result<EnvState> {
+        debug!("Scrolling document: {} by {}", direction, amount);
+        let script = scroll_document_script(direction, amount, smooth)?;
         let page = self.get_page().await?;
 
-        let script = match direction.to_lowercase().as_str() {
-            "up" => "window.scrollBy(0, -window.innerHeight * 0.8);",
-            "down" => "window.scrollBy(0, window.innerHeight * 0.8);",
-            "left" => "window.scrollBy(-window.innerWidth * 0.5, 0);",
-            "right" => "window.scrollBy(window.innerWidth * 0.5, 0);",
-            _ => return Err(anyhow::anyhow!("Invalid scroll direction: {}", direction)),
-        };
-
-        page.evaluate(script)
+        self.evaluate(&page, script)
             .await
             .map_err(|e| anyhow::anyhow!("Failed to scroll: {}", e))?;
 
+        match self.evaluate(&page, SCROLL_SETTLE_SCRIPT).await {
+            Ok(settled) if settled.value().and_then(|v| v.as_bool()) == Some(false) => {
+                debug!("Page did not settle after scrolling")
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to wait for scrolling to finish: {}", e),
+        }
+
         self.current_state().await
     }
 
@@ -494,11 +1490,14 @@ impl CdpBrowserController {
         direction: &str,
         magnitude: i64,
     ) -> Result<EnvState> {
+        let (x, y) = validate_coordinates(x, y, &self.config)?;
+        validate_magnitude(magnitude)?;
         debug!(
             "Scrolling at ({}, {}) direction: {} magnitude: {}",
             x, y, direction, magnitude
         );
         let page = self.get_page().await?;
+        let (x, y) = self.to_frame_coordinates(&page, x, y).await;
 
         let (dx, dy) = match direction.to_lowercase().as_str() {
             "up" => (0, -magnitude),
@@ -520,13 +1519,220 @@ impl CdpBrowserController {
             x, y, dx, dy, dx, dy
         );
 
-        page.evaluate(script)
+        self.evaluate(&page, script)
             .await
             .map_err(|e| anyhow::anyhow!("Failed to scroll: {}", e))?;
 
         self.current_state().await
     }
 
+    /// Scroll the element at the given coordinates to the top of the viewport,
+    /// below any fixed or sticky header. Returns the top offset that was left free.
+    pub async fn scroll_to_element(
+        &self,
+        x: i64,
+        y: i64,
+        offset: Option<i64>,
+    ) -> Result<(i64, EnvState)> {
+        let (x, y) = validate_coordinates(x, y, &self.config)?;
+        debug!("Scrolling element at ({}, {}) into view", x, y);
+        let page = self.get_page().await?;
+        let (x, y) = self.to_frame_coordinates(&page, x, y).await;
+
+        let used_offset: Option<f64> = self
+            .evaluate(&page, scroll_to_element_script(x, y, offset))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to scroll: {}", e))?
+            .into_value()?;
+        let used_offset =
+            used_offset.ok_or_else(|| anyhow::anyhow!("No element found at ({}, {})", x, y))?;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+
+        Ok((used_offset as i64, self.current_state().await?))
+    }
+
+    /// Select text in an element, or place the caret when `start` equals `end`.
+    /// Returns the selected text.
+    pub async fn select_text(
+        &self,
+        target: ElementTarget,
+        start: Option<u32>,
+        end: Option<u32>,
+    ) -> Result<(String, EnvState)> {
+        debug!(
+            "Selecting text in {:?} from {:?} to {:?}",
+            target, start, end
+        );
+        let page = self.get_page().await?;
+        let target = self.resolve_target(&page, target).await?;
+
+        let result = self
+            .evaluate(&page, select_text_script(&target, start, end))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to select text: {}", e))?;
+        let value = result
+            .value()
+            .ok_or_else(|| anyhow::anyhow!("Selection script returned no result"))?;
+        let text = parse_element_result(value)?;
+
+        Ok((text, self.current_state().await?))
+    }
+
+    /// Set a color or range input and return the value it took.
+    pub async fn set_control_value(
+        &self,
+        target: ElementTarget,
+        value: ControlValue,
+    ) -> Result<(String, EnvState)> {
+        debug!("Setting {:?} to {:?}", target, value);
+        let page = self.get_page().await?;
+        let target = self.resolve_target(&page, target).await?;
+
+        let result = self
+            .evaluate(&page, input_controls::set_value_script(&target, &value))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to set input value: {}", e))?;
+        let value = result
+            .value()
+            .ok_or_else(|| anyhow::anyhow!("Input script returned no result"))?;
+        let value = parse_element_result(value)?;
+
+        Ok((value, self.current_state().await?))
+    }
+
+    /// Draw a temporary box around an element and return a description of it.
+    pub async fn highlight_element(
+        &self,
+        target: ElementTarget,
+        duration_ms: u64,
+    ) -> Result<(String, EnvState)> {
+        debug!("Highlighting {:?} for {}ms", target, duration_ms);
+        let page = self.get_page().await?;
+        let target = self.resolve_target(&page, target).await?;
+        let script = highlight_script(&target, duration_ms)?;
+
+        let result = self
+            .evaluate(&page, script)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to highlight element: {}", e))?;
+        let value = result
+            .value()
+            .ok_or_else(|| anyhow::anyhow!("Highlight script returned no result"))?;
+        let description = parse_element_result(value)?;
+
+        Ok((description, self.current_state().await?))
+    }
+
+    /// Fingerprint an element so `click_named` can find it again.
+    pub async fn remember_element(&self, target: ElementTarget) -> Result<ElementFingerprint> {
+        debug!("Remembering {:?}", target);
+        let page = self.get_page().await?;
+        let target = self.resolve_target(&page, target).await?;
+
+        let result = self
+            .evaluate(&page, element_aliases::remember_script(&target))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to remember element: {}", e))?;
+        let value = result
+            .value()
+            .ok_or_else(|| anyhow::anyhow!("Remember script returned no result"))?;
+        element_aliases::parse_result(value)
+    }
+
+    /// Find a remembered element again and click its center. Returns the
+    /// point clicked, in screenshot coordinates.
+    pub async fn click_named(
+        &self,
+        fingerprint: &ElementFingerprint,
+    ) -> Result<((i64, i64), EnvState)> {
+        debug!("Clicking remembered {}", fingerprint.describe());
+        let page = self.get_page().await?;
+
+        let result = self
+            .evaluate(&page, element_aliases::locate_script(fingerprint))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to locate element: {}", e))?;
+        let value = result
+            .value()
+            .ok_or_else(|| anyhow::anyhow!("Locate script returned no result"))?;
+        let located: Located = element_aliases::parse_result(value)?;
+        let (offset_x, offset_y) = match self.selected_frame(&page).await {
+            Some(frame) => frame.offset,
+            None => (0, 0),
+        };
+        let (x, y) = (located.x + offset_x, located.y + offset_y);
+        Ok(((x, y), self.click_at(x, y).await?))
+    }
+
+    /// Find the first visible element matching a locator and scroll it into
+    /// view. Its center is returned in screenshot coordinates.
+    pub async fn locate_element(&self, locator: &Locator) -> Result<LocatedElement> {
+        debug!("Locating element by {}", locator);
+        let page = self.get_page().await?;
+
+        let result = self
+            .evaluate(&page, element_locator::locate_script(locator))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to locate element: {}", e))?;
+        let value = result
+            .value()
+            .ok_or_else(|| anyhow::anyhow!("Locate script returned no result"))?;
+        let mut element = element_locator::parse_located(value)?;
+        if let Some(frame) = self.selected_frame(&page).await {
+            element.x += frame.offset.0;
+            element.y += frame.offset.1;
+        }
+        Ok(element)
+    }
+
+    /// Step the zoom of the current document by `step` levels, or reset it
+    /// with 0. Returns the new zoom factor.
+    pub async fn zoom_page(&self, step: i32) -> Result<(f64, EnvState)> {
+        debug!("Zooming page by {} steps", step);
+        let page = self.get_page().await?;
+
+        let zoom: f64 = self
+            .evaluate(&page, shortcuts::zoom_script(step))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to zoom page: {}", e))?
+            .into_value()?;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        Ok((zoom, self.current_state().await?))
+    }
+
+    /// Capture downscaled strips of the content just above and below the
+    /// viewport.
+    pub async fn context_strips(&self) -> Result<ContextStrips> {
+        let page = self.get_page().await?;
+
+        let metrics = page
+            .layout_metrics()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get layout metrics: {}", e))?;
+        let plan = context_strips::plan(&serde_json::to_value(metrics)?)?;
+        let mut strips = ContextStrips::default();
+        for (clip, hidden, strip) in [
+            (plan.above, plan.hidden_above, &mut strips.above),
+            (plan.below, plan.hidden_below, &mut strips.below),
+        ] {
+            let Some(clip) = clip else {
+                continue;
+            };
+            let params: CaptureScreenshotParams =
+                serde_json::from_value(context_strips::capture_params(&clip))?;
+            let reply = page
+                .execute(params)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to capture strip: {}", e))?;
+            *strip = Some(ContextStrip {
+                screenshot: reply.result.data.into(),
+                covers: clip.height.round() as u32,
+                hidden,
+            });
+        }
+        Ok(strips)
+    }
+
     /// Wait for 5 seconds.
     pub async fn wait_5_seconds(&self) -> Result<EnvState> {
         debug!("Waiting 5 seconds");
@@ -534,10 +1740,12 @@ impl CdpBrowserController {
         self.current_state().await
     }
 
-    /// Navigate back using CDP.
-    pub async fn go_back(&self) -> Result<EnvState> {
+    /// Navigate back using CDP, restoring the page's scroll position. Returns
+    /// the restored offset along with the state.
+    pub async fn go_back(&self) -> Result<(EnvState, Option<ScrollPosition>)> {
         debug!("Going back");
         let page = self.get_page().await?;
+        let from_url = page.url().await.ok().flatten().unwrap_or_default();
 
         // Get navigation history
         let history = page
@@ -551,16 +1759,20 @@ impl CdpBrowserController {
             page.execute(NavigateToHistoryEntryParams::new(prev_entry.id))
                 .await
                 .map_err(|e| anyhow::anyhow!("Failed to navigate back: {}", e))?;
+            self.leave_frame(&page).await;
         }
 
         tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
-        self.current_state().await
+        let restored = self.restore_scroll(&page, &from_url).await;
+        Ok((self.current_state().await?, restored))
     }
 
-    /// Navigate forward using CDP.
-    pub async fn go_forward(&self) -> Result<EnvState> {
+    /// Navigate forward using CDP, restoring the page's scroll position. Returns
+    /// the restored offset along with the state.
+    pub async fn go_forward(&self) -> Result<(EnvState, Option<ScrollPosition>)> {
         debug!("Going forward");
         let page = self.get_page().await?;
+        let from_url = page.url().await.ok().flatten().unwrap_or_default();
 
         // Get navigation history
         let history = page
@@ -574,16 +1786,25 @@ impl CdpBrowserController {
             page.execute(NavigateToHistoryEntryParams::new(next_entry.id))
                 .await
                 .map_err(|e| anyhow::anyhow!("Failed to navigate forward: {}", e))?;
+            self.leave_frame(&page).await;
         }
 
         tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
-        self.current_state().await
+        let restored = self.restore_scroll(&page, &from_url).await;
+        Ok((self.current_state().await?, restored))
     }
 
-    /// Navigate to search engine.
-    pub async fn search(&self) -> Result<EnvState> {
-        debug!("Navigating to search engine");
-        self.navigate(&self.config.search_engine_url.clone()).await
+    /// Navigate to a search engine's home page, or to its results for a query.
+    pub async fn search(&self, query: Option<&str>, engine: Option<&str>) -> Result<EnvState> {
+        let engine = self.config.search_engine(engine)?;
+        let Some(query) = query else {
+            debug!("Navigating to search engine: {}", engine.name);
+            return self.navigate(&engine.home_url()).await;
+        };
+        let page = self.get_page().await?;
+        self.open_search(&page, engine, query).await?;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS * 2)).await;
+        self.current_state().await
     }
 
     /// Navigate to a specific URL.
@@ -591,28 +1812,268 @@ impl CdpBrowserController {
         debug!("Navigating to: {}", url);
         let page = self.get_page().await?;
 
-        let normalized_url = if url.starts_with("http://") || url.starts_with("https://") {
-            url.to_string()
-        } else {
-            format!("https://{}", url)
-        };
+        let normalized_url = normalize_url(url);
 
         page.goto(&normalized_url)
             .await
             .map_err(|e| anyhow::anyhow!("Failed to navigate: {}", e))?;
+        self.leave_frame(&page).await;
 
         // Wait for page to load
         tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS * 2)).await;
+        self.apply_recipe(&page).await;
         self.current_state().await
     }
 
+    /// Apply the recipe of the loaded page's site, if any: wait for its ready
+    /// selector, then accept its cookie banner. Failures only delay the page.
+    async fn apply_recipe(&self, page: &Page) {
+        let Ok(Some(url)) = page.url().await else {
+            return;
+        };
+        let host = credentials::host_of(&url).unwrap_or_default();
+        let Some(recipe) = self.config.recipes.for_host(&host) else {
+            return;
+        };
+        debug!("Applying recipe '{}'", recipe.name);
+
+        if let Some(ref wait) = recipe.wait {
+            if let Some(ref selector) = wait.selector {
+                let Ok(script) = recipes::exists_script(selector) else {
+                    return;
+                };
+                let deadline = Instant::now() + Duration::from_millis(wait.timeout_ms());
+                loop {
+                    let found = match page.evaluate(script.as_str()).await {
+                        Ok(value) => value.into_value::<bool>().unwrap_or(false),
+                        Err(_) => false,
+                    };
+                    if found || Instant::now() >= deadline {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_millis(recipes::WAIT_POLL_INTERVAL_MS)).await;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(wait.delay_ms)).await;
+        }
+
+        if let Some(ref selector) = recipe.selectors.cookie_accept {
+            let Ok(script) = recipes::click_script(selector) else {
+                return;
+            };
+            match page.evaluate(script).await {
+                Ok(value) => {
+                    if value.into_value::<bool>().unwrap_or(false) {
+                        debug!("Accepted the cookie banner");
+                        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+                    }
+                }
+                Err(e) => debug!("Failed to accept the cookie banner: {}", e),
+            }
+        }
+    }
+
+    /// Get the URL of the current page.
+    pub async fn current_url(&self) -> Result<String> {
+        let page = self.get_page().await?;
+        Ok(page
+            .url()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get URL: {}", e))?
+            .unwrap_or_default())
+    }
+
+    /// Search the web and return the results.
+    pub async fn web_search(
+        &self,
+        query: &str,
+        engine: Option<&str>,
+        num_results: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let engine = self.config.search_engine(engine)?;
+        let page = self.get_page().await?;
+        self.open_search(&page, engine, query).await?;
+
+        // Read the result page until it lists results; reads during the
+        // navigation fail and are retried
+        let script = web_search::results_script(num_results);
+        let mut results = Vec::new();
+        for _ in 0..web_search::RESULT_ATTEMPTS {
+            tokio::time::sleep(Duration::from_millis(web_search::RESULT_POLL_INTERVAL_MS)).await;
+            match page.evaluate(script.as_str()).await {
+                Ok(value) => {
+                    results = value
+                        .value()
+                        .map(web_search::parse_results)
+                        .transpose()?
+                        .unwrap_or_default()
+                }
+                Err(e) => debug!("Failed to read search results: {}", e),
+            }
+            if !results.is_empty() {
+                break;
+            }
+        }
+        Ok(results)
+    }
+
+    /// Open the engine's results for a query: through its URL template, or by
+    /// typing the query into the search box of its home page.
+    async fn open_search(&self, page: &Page, engine: &SearchEngine, query: &str) -> Result<()> {
+        debug!("Searching {} for: {}", engine.name, query);
+        if let Some(url) = engine.query_url(query) {
+            page.goto(normalize_url(&url))
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to navigate: {}", e))?;
+            self.leave_frame(page).await;
+            return Ok(());
+        }
+
+        page.goto(normalize_url(&engine.home_url()))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to navigate: {}", e))?;
+        self.leave_frame(page).await;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        let host = credentials::host_of(&engine.home_url()).unwrap_or_default();
+        let search_box = self
+            .config
+            .recipes
+            .selectors_for(&host)
+            .search_box
+            .as_deref();
+        let submitted: bool = page
+            .evaluate(web_search::submit_script(query, search_box)?)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to submit search: {}", e))?
+            .into_value()?;
+        if !submitted {
+            return Err(anyhow::anyhow!(
+                "No search box found on {}",
+                engine.home_url()
+            ));
+        }
+        Ok(())
+    }
+
     /// Press key combination using CDP.
     pub async fn key_combination(&self, keys: Vec<String>) -> Result<EnvState> {
         debug!("Pressing key combination: {:?}", keys);
+        let keys = keys::normalize_keys(&keys, &KeyMapping::from_config(&self.config))?;
         let page = self.get_page().await?;
+        self.press_chord(&page, &keys).await?;
 
+        self.current_state().await
+    }
+
+    /// Press a series of keys or chords in order, pausing after each step as
+    /// requested.
+    pub async fn press_keys_sequence(&self, mut steps: Vec<KeyStep>) -> Result<EnvState> {
+        debug!("Pressing key sequence of {} steps", steps.len());
+        let mapping = KeyMapping::from_config(&self.config);
+        for step in &mut steps {
+            step.keys = keys::normalize_keys(&step.keys, &mapping)?;
+        }
+        validate_key_steps(&steps)?;
+        let page = self.get_page().await?;
+
+        for step in &steps {
+            self.press_chord(&page, &step.keys).await?;
+            if step.delay_ms > 0 {
+                tokio::time::sleep(Duration::from_millis(step.delay_ms)).await;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+
+        self.current_state().await
+    }
+
+    /// Press Tab, or Shift+Tab when `backwards`, until the element a locator
+    /// describes has focus, then press the activation key on it.
+    pub async fn tab_to_element(
+        &self,
+        locator: &Locator,
+        backwards: bool,
+        max_presses: u32,
+        activation: Activation,
+    ) -> Result<(TabFocus, EnvState)> {
+        debug!("Tabbing to the element with {}", locator);
+        let page = self.get_page().await?;
+
+        let mut presses = 0;
+        let check = loop {
+            let result = self
+                .evaluate(&page, keyboard_focus::focus_script(locator, presses == 0))
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to read the focused element: {}", e))?;
+            let value = result
+                .value()
+                .ok_or_else(|| anyhow::anyhow!("Focus script returned no result"))?;
+            let check = keyboard_focus::parse_focus(value)?;
+            if check.focused {
+                break check;
+            }
+            if check.wrapped || presses >= max_presses {
+                return Err(keyboard_focus::not_reached(locator, presses, check.wrapped));
+            }
+            self.press_focus_key(&page, "Tab", backwards).await?;
+            presses += 1;
+        };
+
+        let activated = keyboard_focus::activation_key(activation, &check);
+        if let Some(key) = activated {
+            let key = if key == Activation::Space {
+                " "
+            } else {
+                "Enter"
+            };
+            self.press_focus_key(&page, key, false).await?;
+            tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        }
+        let focus = TabFocus {
+            tag: check.tag,
+            text: check.text,
+            presses,
+            activated,
+        };
+        Ok((focus, self.current_state().await?))
+    }
+
+    /// Press Tab, Enter or Space (`" "`) with the key codes and text that
+    /// Chromium's default actions, moving focus and activating the focused
+    /// element, need.
+    async fn press_focus_key(&self, page: &Page, key: &str, shift: bool) -> Result<()> {
+        let (code, key_code, text) = match key {
+            "Tab" => ("Tab", 9, None),
+            "Enter" => ("Enter", 13, Some("\r")),
+            _ => ("Space", 32, Some(" ")),
+        };
+        // Shift is bit 8 of the modifiers
+        let modifiers = if shift { 8 } else { 0 };
+        for event_type in [DispatchKeyEventType::KeyDown, DispatchKeyEventType::KeyUp] {
+            let mut builder = DispatchKeyEventParams::builder()
+                .key(key)
+                .code(code)
+                .windows_virtual_key_code(key_code)
+                .native_virtual_key_code(key_code)
+                .modifiers(modifiers);
+            if let (DispatchKeyEventType::KeyDown, Some(text)) = (&event_type, text) {
+                builder = builder.text(text);
+            }
+            let params = builder
+                .r#type(event_type)
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to build key event params: {}", e))?;
+            page.execute(params)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to press {}: {}", code, e))?;
+        }
+        Ok(())
+    }
+
+    /// Dispatch key down and key up events for each key.
+    async fn press_chord(&self, page: &Page, keys: &[String]) -> Result<()> {
         // Use CDP to dispatch key events
-        for key in &keys {
+        for key in keys {
             let key_down = DispatchKeyEventParams::builder()
                 .r#type(DispatchKeyEventType::KeyDown)
                 .key(key.as_str())
@@ -632,7 +2093,7 @@ impl CdpBrowserController {
                 .map_err(|e| anyhow::anyhow!("Failed to release key: {}", e))?;
         }
 
-        self.current_state().await
+        Ok(())
     }
 
     /// Drag and drop from one position to another.
@@ -643,11 +2104,18 @@ impl CdpBrowserController {
         destination_x: i64,
         destination_y: i64,
     ) -> Result<EnvState> {
+        let (x, y) = validate_coordinates(x, y, &self.config)?;
+        let (destination_x, destination_y) =
+            validate_coordinates(destination_x, destination_y, &self.config)?;
         debug!(
             "Drag and drop from ({}, {}) to ({}, {})",
             x, y, destination_x, destination_y
         );
         let page = self.get_page().await?;
+        let (x, y) = self.to_frame_coordinates(&page, x, y).await;
+        let (destination_x, destination_y) = self
+            .to_frame_coordinates(&page, destination_x, destination_y)
+            .await;
 
         let script = format!(
             r#"
@@ -692,13 +2160,935 @@ impl CdpBrowserController {
             x, y, destination_x, destination_y
         );
 
-        page.evaluate(script)
+        self.evaluate(&page, script)
             .await
             .map_err(|e| anyhow::anyhow!("Failed to drag and drop: {}", e))?;
 
         self.current_state().await
     }
 
+    // ========== Tab Methods ==========
+
+    /// Handle of the active tab, if the browser is open.
+    pub async fn active_tab(&self) -> Option<String> {
+        self.page
+            .lock()
+            .await
+            .as_ref()
+            .map(|page| page.target_id().as_ref().to_string())
+    }
+
+    /// All open tabs, ordered by when they were first seen.
+    async fn tabs(&self) -> Result<Vec<Page>> {
+        self.reconnect_if_lost().await?;
+        let mut pages = {
+            let browser_guard = self.browser.lock().await;
+            let browser = browser_guard
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+            browser
+                .pages()
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to get tabs: {}", e))?
+        };
+
+        let mut order = self.tab_order.lock().await;
+        pages.sort_by(|a, b| a.target_id().as_ref().cmp(b.target_id().as_ref()));
+        for page in &pages {
+            let handle = page.target_id().as_ref();
+            if !order.iter().any(|h| h == handle) {
+                order.push(handle.to_string());
+            }
+        }
+        order.retain(|h| pages.iter().any(|p| p.target_id().as_ref() == h));
+        *self.sessions.lock().unwrap_or_else(|e| e.into_inner()) = pages
+            .iter()
+            .map(|page| (page.target_id().as_ref().to_string(), page.clone()))
+            .collect();
+        pages.sort_by_key(|page| {
+            order
+                .iter()
+                .position(|h| h == page.target_id().as_ref())
+                .unwrap_or(usize::MAX)
+        });
+        Ok(pages)
+    }
+
+    /// Find an open tab by handle, reusing its session when the tab was seen
+    /// before rather than listing every tab again.
+    async fn find_tab(&self, handle: &str) -> Result<Page> {
+        let cached = self
+            .sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(handle)
+            .cloned();
+        if let Some(page) = cached {
+            // Tabs closed by the page or the user leave their session behind,
+            // but the handler drops its end of their channel, so even a query
+            // it answers locally fails for them
+            if page.url().await.is_ok() {
+                return Ok(page);
+            }
+            self.sessions
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(handle);
+        }
+        self.tabs()
+            .await?
+            .into_iter()
+            .find(|page| page.target_id().as_ref() == handle)
+            .ok_or_else(|| anyhow::anyhow!("Tab not found: {}", handle))
+    }
+
+    /// Fail when the action is not allowed on the user's own browser.
+    fn refuse_in_attached_mode(&self, action: &str) -> Result<()> {
+        if self.config.attached_mode {
+            return Err(anyhow::anyhow!(
+                "{} is not allowed in attached mode (MCP_ATTACHED_MODE=true); use attach_to_tab to pick a tab",
+                action
+            ));
+        }
+        Ok(())
+    }
+
+    /// Make the given page the active tab.
+    async fn activate_tab(&self, page: Page) {
+        if let Err(e) = page.bring_to_front().await {
+            warn!("Failed to bring tab to front: {}", e);
+        }
+        *self.page.lock().await = Some(page);
+    }
+
+    /// Describe a tab.
+    async fn tab_info(page: &Page, active: bool) -> TabInfo {
+        TabInfo {
+            handle: page.target_id().as_ref().to_string(),
+            url: page
+                .url()
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| "about:blank".to_string()),
+            title: page.get_title().await.ok().flatten().unwrap_or_default(),
+            active,
+            navigation_error: None,
+        }
+    }
+
+    /// Open a new tab, optionally navigating to a URL, and make it active.
+    pub async fn new_tab(&self, url: Option<&str>) -> Result<(TabInfo, EnvState)> {
+        debug!("Creating new tab");
+        self.refuse_in_attached_mode("Opening tabs")?;
+        let page = {
+            let browser_guard = self.browser.lock().await;
+            let browser = browser_guard
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+            browser
+                .new_page("about:blank")
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to create tab: {}", e))?
+        };
+        self.prepare_page(&page).await;
+
+        // Navigate to URL if provided, handling failures gracefully
+        let mut navigation_error: Option<String> = None;
+        if let Some(url) = url {
+            if let Err(e) = page.goto(normalize_url(url)).await {
+                // Log the error but don't fail - tab is still created
+                warn!("Navigation failed in new tab: {}. Tab remains open.", e);
+                navigation_error = Some(format!("Navigation failed: {}", e));
+            }
+        }
+
+        self.activate_tab(page.clone()).await;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+
+        let mut tab_info = Self::tab_info(&page, true).await;
+        tab_info.navigation_error = navigation_error;
+        let state = self.current_state().await?;
+        Ok((tab_info, state))
+    }
+
+    /// Close a tab by handle, or the active tab.
+    /// Closing the active tab activates another one; closing the last tab opens a blank one.
+    pub async fn close_tab(&self, handle: Option<&str>) -> Result<EnvState> {
+        debug!("Closing tab: {:?}", handle);
+        self.refuse_in_attached_mode("Closing tabs")?;
+        let active = self.active_page().await?;
+        let page = match handle {
+            Some(handle) => self.find_tab(handle).await?,
+            None => active.clone(),
+        };
+        let closed = page.target_id().clone();
+
+        self.leave_frame(&page).await;
+        page.close()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to close tab: {}", e))?;
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(closed.as_ref());
+
+        if &closed == active.target_id() {
+            let next = self
+                .tabs()
+                .await?
+                .into_iter()
+                .find(|p| p.target_id() != &closed);
+            let next = match next {
+                Some(next) => next,
+                None => {
+                    let browser_guard = self.browser.lock().await;
+                    let browser = browser_guard
+                        .as_ref()
+                        .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+                    browser
+                        .new_page("about:blank")
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Failed to create tab: {}", e))?
+                }
+            };
+            self.activate_tab(next).await;
+        }
+
+        self.current_state().await
+    }
+
+    /// Open a URL in a new tab without activating it.
+    pub async fn open_background_tab(&self, url: &str) -> Result<String> {
+        debug!("Opening background tab: {}", url);
+        self.refuse_in_attached_mode("Opening tabs")?;
+        let page = {
+            let browser_guard = self.browser.lock().await;
+            let browser = browser_guard
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+            browser
+                .new_page("about:blank")
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to create tab: {}", e))?
+        };
+        self.prepare_page(&page).await;
+        if let Err(e) = page.goto(normalize_url(url)).await {
+            let _ = page.close().await;
+            return Err(anyhow::anyhow!("Navigation failed: {}", e));
+        }
+        // new_page may have brought the tab to the front
+        if let Ok(active) = self.active_page().await {
+            let _ = active.bring_to_front().await;
+        }
+        Ok(page.target_id().as_ref().to_string())
+    }
+
+    /// Read the text of a background tab, reloading it first.
+    pub async fn read_background_tab(
+        &self,
+        handle: &str,
+        selector: Option<&str>,
+        reload: bool,
+    ) -> Result<PageText> {
+        let page = self.find_tab(handle).await?;
+        if reload {
+            page.reload()
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to reload: {}", e))?;
+            tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        }
+        page.evaluate(page_watch::text_script(selector))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to read page text: {}", e))?
+            .into_value()
+            .map_err(|e| anyhow::anyhow!("Failed to parse page text: {}", e))
+    }
+
+    /// Close a background tab.
+    pub async fn close_background_tab(&self, handle: &str) -> Result<()> {
+        let page = self.find_tab(handle).await?;
+        page.close()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to close tab: {}", e))?;
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(handle);
+        Ok(())
+    }
+
+    /// Switch to a tab by handle or index.
+    /// Exactly one of handle or index must be provided.
+    pub async fn switch_tab(&self, handle: Option<&str>, index: Option<usize>) -> Result<EnvState> {
+        debug!("Switching to tab: handle={:?}, index={:?}", handle, index);
+        self.refuse_in_attached_mode("Switching tabs")?;
+
+        let page = match (handle, index) {
+            (Some(_), Some(_)) => {
+                return Err(anyhow::anyhow!(
+                    "Provide exactly one of 'handle' or 'index', not both"
+                ));
+            }
+            (None, None) => {
+                return Err(anyhow::anyhow!("Either handle or index must be provided"));
+            }
+            (Some(handle), None) => self.find_tab(handle).await?,
+            (None, Some(index)) => self
+                .tabs()
+                .await?
+                .into_iter()
+                .nth(index)
+                .ok_or_else(|| anyhow::anyhow!("Tab index {} out of range", index))?,
+        };
+
+        self.activate_tab(page).await;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+
+        self.current_state().await
+    }
+
+    /// List all open tabs and return current state.
+    pub async fn list_tabs(&self) -> Result<(Vec<TabInfo>, EnvState)> {
+        debug!("Listing all tabs");
+        let active = self.active_tab().await;
+        let mut tabs = Vec::new();
+        for page in self.tabs().await? {
+            let is_active = active.as_deref() == Some(page.target_id().as_ref());
+            tabs.push(Self::tab_info(&page, is_active).await);
+        }
+
+        let state = self.current_state().await?;
+        Ok((tabs, state))
+    }
+
+    /// List the page targets of the browser, including tabs that were open before
+    /// the server connected.
+    pub async fn list_targets(&self) -> Result<Vec<PageTarget>> {
+        debug!("Listing page targets");
+        let active = self.active_tab().await;
+        let targets = {
+            let browser_guard = self.browser.lock().await;
+            let browser = browser_guard
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+            browser
+                .execute(GetTargetsParams::default())
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to get targets: {}", e))?
+                .result
+                .target_infos
+        };
+
+        Ok(targets
+            .into_iter()
+            .filter(|target| target.r#type == "page")
+            .map(|target| {
+                let handle = target.target_id.as_ref().to_string();
+                PageTarget {
+                    active: active.as_deref() == Some(handle.as_str()),
+                    handle,
+                    url: target.url,
+                    title: target.title,
+                }
+            })
+            .collect())
+    }
+
+    /// Make the page target with the given handle the active tab, attaching to it
+    /// first if it was opened outside the server.
+    pub async fn attach_to_tab(&self, handle: &str) -> Result<EnvState> {
+        debug!("Attaching to tab: {}", handle);
+        let page = match self.find_tab(handle).await {
+            Ok(page) => page,
+            Err(_) => {
+                let mut browser_guard = self.browser.lock().await;
+                let browser = browser_guard
+                    .as_mut()
+                    .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+                let found = browser
+                    .fetch_targets()
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Failed to get targets: {}", e))?
+                    .into_iter()
+                    .any(|target| target.r#type == "page" && target.target_id.as_ref() == handle);
+                if !found {
+                    return Err(anyhow::anyhow!("Tab not found: {}", handle));
+                }
+                let deadline = Instant::now() + ATTACH_TIMEOUT;
+                let page = loop {
+                    match browser.get_page(TargetId::from(handle.to_string())).await {
+                        Ok(page) => break page,
+                        Err(e) if Instant::now() >= deadline => {
+                            return Err(anyhow::anyhow!("Failed to attach to tab: {}", e));
+                        }
+                        Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+                    }
+                };
+                drop(browser_guard);
+                self.prepare_page(&page).await;
+                page
+            }
+        };
+
+        self.activate_tab(page).await;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+
+        info!("Attached to tab {}", handle);
+        self.current_state().await
+    }
+
+    // ========== Frame Methods ==========
+
+    /// The frame selected in the given page, if any.
+    async fn selected_frame(&self, page: &Page) -> Option<SelectedFrame> {
+        self.frame
+            .lock()
+            .await
+            .clone()
+            .filter(|frame| &frame.tab == page.target_id())
+    }
+
+    /// Return to the top-level document if a frame of the given page is selected.
+    async fn leave_frame(&self, page: &Page) {
+        let mut frame = self.frame.lock().await;
+        if frame.as_ref().is_some_and(|f| &f.tab == page.target_id()) {
+            *frame = None;
+        }
+    }
+
+    /// Translate screenshot coordinates into the coordinate space of the selected frame.
+    async fn to_frame_coordinates(&self, page: &Page, x: i64, y: i64) -> (i64, i64) {
+        match self.selected_frame(page).await {
+            Some(frame) => (x - frame.offset.0, y - frame.offset.1),
+            None => (x, y),
+        }
+    }
+
+    /// Validate a point target and translate it into the selected frame.
+    async fn resolve_target(&self, page: &Page, target: ElementTarget) -> Result<ElementTarget> {
+        match target {
+            ElementTarget::Point(x, y) => {
+                let (x, y) = validate_coordinates(x, y, &self.config)?;
+                let (x, y) = self.to_frame_coordinates(page, x, y).await;
+                Ok(ElementTarget::Point(x, y))
+            }
+            target => Ok(target),
+        }
+    }
+
+    /// Build evaluation parameters targeting the selected frame's execution context.
+    async fn frame_evaluate_params(
+        &self,
+        page: &Page,
+        expression: impl Into<String>,
+    ) -> Result<EvaluateParams> {
+        let mut params = EvaluateParams::new(expression);
+        if let Some(frame) = self.selected_frame(page).await {
+            let context_id = page
+                .frame_execution_context(frame.id)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to resolve frame context: {}", e))?
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Selected frame is no longer available. Use switch_frame to select a frame again."
+                    )
+                })?;
+            params.context_id = Some(context_id);
+        }
+        Ok(params)
+    }
+
+    /// Evaluate a script in the selected frame, or in the page when no frame is selected.
+    async fn evaluate(&self, page: &Page, script: impl Into<String>) -> Result<EvaluationResult> {
+        let params = self.frame_evaluate_params(page, script).await?;
+        Ok(page.evaluate(params).await?)
+    }
+
+    /// Describe the frames of the currently selected document.
+    async fn frames_in_current_document(&self, page: &Page) -> Result<Vec<FrameInfo>> {
+        let mut frames: Vec<FrameInfo> = self
+            .evaluate(page, LIST_FRAMES_SCRIPT)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to list frames: {}", e))?
+            .into_value()?;
+
+        // Report positions relative to the screenshot rather than the selected frame
+        let (offset_x, offset_y) = match self.selected_frame(page).await {
+            Some(frame) => frame.offset,
+            None => (0, 0),
+        };
+        for frame in &mut frames {
+            frame.x += offset_x;
+            frame.y += offset_y;
+        }
+        Ok(frames)
+    }
+
+    /// List the frames of the currently selected document and return current state.
+    pub async fn list_frames(&self) -> Result<(Vec<FrameInfo>, EnvState)> {
+        debug!("Listing frames");
+        let page = self.get_page().await?;
+        let frames = self.frames_in_current_document(&page).await?;
+        let state = self.current_state().await?;
+        Ok((frames, state))
+    }
+
+    /// Switch into a child frame of the currently selected document by index,
+    /// or back to the top-level document when no index is given.
+    ///
+    /// Only frames rendered in the page's process can be selected; cross-origin
+    /// frames isolated into their own process are reported as an error.
+    pub async fn switch_frame(&self, index: Option<usize>) -> Result<EnvState> {
+        debug!("Switching to frame: {:?}", index);
+        let page = self.get_page().await?;
+
+        let Some(index) = index else {
+            self.leave_frame(&page).await;
+            return self.current_state().await;
+        };
+
+        let frames = self.frames_in_current_document(&page).await?;
+        let frame = frames
+            .get(index)
+            .ok_or_else(|| anyhow::anyhow!("Frame index {} out of range", index))?;
+
+        // Resolve the frame element to its CDP frame id
+        let mut params = self
+            .frame_evaluate_params(
+                &page,
+                format!("document.querySelectorAll('iframe, frame')[{}]", index),
+            )
+            .await?;
+        params.return_by_value = Some(false);
+        let element = page
+            .evaluate_expression(params)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to locate frame element: {}", e))?;
+        let object_id = element
+            .object()
+            .object_id
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Frame element {} not found", index))?;
+        let node = page
+            .execute(DescribeNodeParams::builder().object_id(object_id).build())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to describe frame element: {}", e))?
+            .result
+            .node;
+        let frame_id = node
+            .frame_id
+            .ok_or_else(|| anyhow::anyhow!("Element {} is not a frame", index))?;
+
+        let has_context = page
+            .frame_execution_context(frame_id.clone())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to resolve frame context: {}", e))?
+            .is_some();
+        if !has_context {
+            return Err(anyhow::anyhow!(
+                "Frame {} runs in a separate process (cross-origin) and cannot be selected in CDP mode. Use WebDriver mode to interact with it.",
+                index
+            ));
+        }
+
+        *self.frame.lock().await = Some(SelectedFrame {
+            tab: page.target_id().clone(),
+            id: frame_id,
+            offset: (frame.x, frame.y),
+        });
+
+        self.current_state().await
+    }
+
+    /// Extract title, description, canonical URL, OpenGraph tags and favicon.
+    pub async fn page_metadata(&self) -> Result<PageMetadata> {
+        debug!("Extracting page metadata");
+        let page = self.get_page().await?;
+        let metadata = self
+            .evaluate(&page, PAGE_METADATA_SCRIPT)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to extract page metadata: {}", e))?
+            .into_value()?;
+        Ok(metadata)
+    }
+
+    /// Fill saved credentials into the sign-in form of the current document,
+    /// provided the tab is on one of the site's domains.
+    pub async fn fill_credentials(
+        &self,
+        credentials: &SiteCredentials,
+        username: Option<&str>,
+        password: &str,
+        submit: bool,
+    ) -> Result<(CredentialFill, EnvState)> {
+        debug!("Filling saved credentials");
+        let page = self.get_page().await?;
+
+        let url = page
+            .url()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get URL: {}", e))?
+            .unwrap_or_default();
+        // The form is in the selected frame, if any. Its URL comes from the
+        // frames the browser reported rather than from scripts the frame
+        // could tamper with, so the password never reaches another origin.
+        let frame_url = match self.selected_frame(&page).await {
+            Some(frame) => page
+                .frame_url(frame.id)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to get frame URL: {}", e))?
+                .unwrap_or_default(),
+            None => url.clone(),
+        };
+        let host = credentials::host_of(&frame_url).unwrap_or_default();
+        if !credentials.allows_url(&url) || !credentials.allows_url(&frame_url) {
+            let fill = CredentialFill {
+                host,
+                ..Default::default()
+            };
+            return Ok((fill, self.current_state().await?));
+        }
+
+        let selectors = self.config.recipes.selectors_for(&host);
+        let script = credentials::fill_script(credentials, username, password, submit, selectors)?;
+        let fill: CredentialFill = self
+            .evaluate(&page, script)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to fill credentials: {}", e))?
+            .into_value()?;
+        if fill.submitted {
+            self.leave_frame(&page).await;
+            tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS * 2)).await;
+        }
+
+        Ok((fill, self.current_state().await?))
+    }
+
+    /// Run one step of a sign-in on an identity provider page. The saved
+    /// credentials are only offered to the page when its host is one of the
+    /// site's domains.
+    pub async fn oauth_step(
+        &self,
+        credentials: &SiteCredentials,
+        username: Option<&str>,
+        password: &str,
+        approve_consent: bool,
+    ) -> Result<OAuthStep> {
+        debug!("Running sign-in step");
+        let page = self.get_page().await?;
+
+        let url = page
+            .url()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get URL: {}", e))?
+            .unwrap_or_default();
+        let host = credentials::host_of(&url).unwrap_or_default();
+        let Some(provider) = Provider::detect(&host) else {
+            return Ok(OAuthStep {
+                host,
+                provider: None,
+                action: StepAction::None,
+            });
+        };
+        let (username, password) = match credentials.allows_url(&url) {
+            true => (username, Some(password)),
+            false => (None, None),
+        };
+
+        self.leave_frame(&page).await;
+        let script = oauth::step_script(
+            provider,
+            username,
+            password,
+            approve_consent,
+            &credentials.domains,
+        )?;
+        let action: StepAction = page
+            .evaluate(script)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to run sign-in step: {}", e))?
+            .into_value()?;
+        if action.advanced() {
+            tokio::time::sleep(Duration::from_millis(OAUTH_STEP_DELAY_MS)).await;
+        }
+
+        Ok(OAuthStep {
+            host,
+            provider: Some(provider),
+            action,
+        })
+    }
+
+    /// Notifications and permission prompts recorded in the tab's document,
+    /// optionally clearing the record. Capture is installed again when it is
+    /// missing, for example in a tab the page opened itself.
+    pub async fn get_notifications(&self, clear: bool) -> Result<Vec<CapturedNotification>> {
+        debug!("Reading notifications");
+        if !self.config.capture_notifications {
+            return Err(anyhow::anyhow!(
+                "Notification capture is disabled (MCP_CAPTURE_NOTIFICATIONS=false)"
+            ));
+        }
+        let page = self.get_page().await?;
+
+        let result = page
+            .evaluate(notifications::read_script(clear))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to read notifications: {}", e))?;
+        let value = result.value().cloned().unwrap_or_default();
+        match notifications::parse_notifications(&value)? {
+            Some(notifications) => Ok(notifications),
+            None => {
+                self.install_notification_capture(&page).await;
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Evaluate a JavaScript expression in the current document.
+    pub async fn evaluate_javascript(&self, expression: &str) -> Result<Evaluation> {
+        debug!("Evaluating JavaScript");
+        let page = self.get_page().await?;
+
+        let result = self
+            .evaluate(&page, evaluate::evaluate_script(expression))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to evaluate JavaScript: {}", e))?;
+        evaluate::parse_evaluation(&result.value().cloned().unwrap_or_default())
+    }
+
+    /// List the audio and video elements of the current document.
+    pub async fn get_media(&self) -> Result<Vec<MediaElement>> {
+        debug!("Listing media elements");
+        let page = self.get_page().await?;
+
+        let result = self
+            .evaluate(&page, media::state_script())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to list media elements: {}", e))?;
+        media::parse_media(&result.value().cloned().unwrap_or_default())
+    }
+
+    /// Play, pause, mute or unmute the media element at `index`, or all of
+    /// them, and list the media elements once playback had time to change.
+    pub async fn control_media(
+        &self,
+        command: MediaCommand,
+        index: Option<u32>,
+    ) -> Result<Vec<MediaElement>> {
+        debug!("Media command {:?} on {:?}", command, index);
+        let page = self.get_page().await?;
+
+        let result = self
+            .evaluate(&page, media::control_script(command, index))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to control media: {}", e))?;
+        media::parse_media(&result.value().cloned().unwrap_or_default())?;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+        self.get_media().await
+    }
+
+    /// Capture the current frame of the video matching `selector`, or of the
+    /// first playing video.
+    pub async fn capture_video_frame(&self, selector: Option<&str>) -> Result<VideoFrame> {
+        debug!("Capturing video frame");
+        let page = self.get_page().await?;
+
+        let result = self
+            .evaluate(&page, media::frame_script(selector))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to capture video frame: {}", e))?;
+        media::parse_frame(&result.value().cloned().unwrap_or_default())
+    }
+
+    /// Capture URL, scroll position and form values of the top-level document.
+    pub async fn capture_checkpoint(&self, include_storage: bool) -> Result<PageCheckpoint> {
+        debug!("Capturing checkpoint");
+        let page = self.get_page().await?;
+        let checkpoint = page
+            .evaluate(checkpoint::capture_script(include_storage))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to capture checkpoint: {}", e))?
+            .into_value()?;
+        Ok(checkpoint)
+    }
+
+    /// Return to a checkpoint: load its URL, restore storage (reloading so the page
+    /// sees it), then form values and scroll position.
+    /// Returns the number of restored fields and the resulting state.
+    pub async fn restore_checkpoint(
+        &self,
+        checkpoint: &PageCheckpoint,
+    ) -> Result<(usize, EnvState)> {
+        debug!("Restoring checkpoint at {}", checkpoint.url);
+        let page = self.get_page().await?;
+
+        page.goto(&checkpoint.url)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to navigate: {}", e))?;
+        self.leave_frame(&page).await;
+
+        if checkpoint.storage.is_some() {
+            page.evaluate(checkpoint::restore_storage_script(checkpoint)?)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to restore storage: {}", e))?;
+            page.reload()
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to reload: {}", e))?;
+        }
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS * 2)).await;
+
+        let restored: usize = page
+            .evaluate(checkpoint::restore_page_script(checkpoint)?)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to restore checkpoint: {}", e))?
+            .into_value()?;
+        tokio::time::sleep(Duration::from_millis(PAGE_SETTLE_DELAY_MS)).await;
+
+        Ok((restored, self.current_state().await?))
+    }
+
+    /// Get all cookies of the browser.
+    pub async fn get_cookies(&self) -> Result<Option<Vec<SavedCookie>>> {
+        let browser_guard = self.browser.lock().await;
+        let browser = browser_guard
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        let cookies = browser
+            .get_cookies()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get cookies: {}", e))?;
+        Ok(Some(workspace::parse_cookies(&serde_json::to_value(
+            cookies,
+        )?)?))
+    }
+
+    /// Set cookies in the browser.
+    pub async fn set_cookies(&self, cookies: &[SavedCookie]) -> Result<bool> {
+        let params: Vec<CookieParam> = serde_json::from_value(serde_json::to_value(cookies)?)?;
+        let browser_guard = self.browser.lock().await;
+        let browser = browser_guard
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Browser not opened"))?;
+        browser
+            .set_cookies(params)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to set cookies: {}", e))?;
+        Ok(true)
+    }
+
+    /// Get page performance metrics.
+    pub async fn page_metrics(&self) -> Result<Option<PageMetrics>> {
+        let page = self.get_page().await?;
+        let metrics = page
+            .metrics()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get page metrics: {}", e))?;
+        Ok(Some(PageMetrics::from_metrics(
+            metrics.iter().map(|m| (m.name.as_str(), m.value)),
+        )))
+    }
+
+    /// Capture a compact snapshot of the visible DOM with layout information.
+    pub async fn dom_snapshot(&self) -> Result<DomSnapshot> {
+        debug!("Capturing DOM snapshot");
+        let page = self.get_page().await?;
+
+        let params = CaptureSnapshotParams::new(
+            SNAPSHOT_COMPUTED_STYLES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        );
+        let response = page
+            .execute(params)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to capture DOM snapshot: {}", e))?;
+        let raw = serde_json::to_value(&response.result)?;
+
+        dom_snapshot::build_snapshot(&raw, (self.config.screen_width, self.config.screen_height))
+    }
+
+    /// Audit the accessibility of the current document with axe-core, loading it
+    /// into the document first if needed.
+    pub async fn audit_accessibility(
+        &self,
+        axe_source: &str,
+        selector: Option<&str>,
+    ) -> Result<AccessibilityAudit> {
+        debug!("Auditing accessibility");
+        let page = self.get_page().await?;
+
+        let present: bool = self
+            .evaluate(&page, accessibility::PRESENT_SCRIPT)
+            .await?
+            .into_value()?;
+        if !present {
+            self.evaluate(&page, axe_source)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to load axe-core: {}", e))?;
+        }
+
+        let result: serde_json::Value = self
+            .evaluate(&page, accessibility::run_script(selector))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to run axe-core: {}", e))?
+            .into_value()?;
+        accessibility::parse_results(result)
+    }
+
+    /// Collect the links of the current document, or of the first element
+    /// matching `selector`.
+    pub async fn collect_links(&self, selector: Option<&str>) -> Result<PageLinks> {
+        debug!("Collecting links");
+        let page = self.get_page().await?;
+        self.evaluate(&page, link_check::links_script(selector))
+            .await?
+            .into_value()
+            .map_err(|e| anyhow::anyhow!("Failed to parse links: {}", e))
+    }
+
+    /// Change the emulated media settings of the current tab. Returns the tab's
+    /// resulting settings.
+    pub async fn set_emulated_media(
+        &self,
+        changes: MediaEmulation,
+    ) -> Result<(EnvState, MediaEmulation)> {
+        debug!("Emulating media: {:?}", changes);
+        let page = self.get_page().await?;
+        let settings = self
+            .media_emulation
+            .update(page.target_id().as_ref(), changes);
+        let params = SetEmulatedMediaParams::builder()
+            .media(settings.media.map_or("", MediaType::cdp_value))
+            .features(
+                settings
+                    .features()
+                    .into_iter()
+                    .map(|(name, value)| MediaFeature::new(name, value)),
+            )
+            .build();
+        page.execute(params)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to emulate media: {}", e))?;
+        Ok((self.current_state().await?, settings))
+    }
+
+    /// Render the current tab as seen with a vision deficiency.
+    pub async fn set_vision_deficiency(&self, deficiency: VisionDeficiency) -> Result<EnvState> {
+        debug!("Emulating vision deficiency: {:?}", deficiency);
+        let page = self.get_page().await?;
+        let kind: SetEmulatedVisionDeficiencyType = deficiency
+            .cdp_value()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Unsupported vision deficiency: {}", e))?;
+        page.execute(SetEmulatedVisionDeficiencyParams::new(kind))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to emulate vision deficiency: {}", e))?;
+        self.current_state().await
+    }
+
     /// Get the screen size.
     #[allow(dead_code)]
     pub fn screen_size(&self) -> (u32, u32) {
@@ -706,6 +3096,29 @@ impl CdpBrowserController {
     }
 }
 
+/// Push the events of a CDP event stream that `describe` reports to a
+/// subscription, until the stream ends with its tab or browser.
+fn forward_events<T>(
+    mut stream: EventStream<T>,
+    events: &Arc<PageEvents>,
+    kind: EventType,
+    describe: impl Fn(&T) -> Option<EventDetails> + Send + 'static,
+) where
+    T: IntoEventKind + Unpin + Send + Sync + 'static,
+{
+    let events = Arc::clone(events);
+    tokio::spawn(async move {
+        while let Some(event) = stream.next().await {
+            if !events.wants(kind) {
+                continue;
+            }
+            if let Some(details) = describe(&event) {
+                events.push(kind, details);
+            }
+        }
+    });
+}
+
 impl Drop for CdpBrowserController {
     fn drop(&mut self) {
         let was_opened = self.was_opened.load(Ordering::SeqCst);
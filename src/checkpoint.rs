@@ -0,0 +1,276 @@
+//! Page checkpoints.
+//!
+//! A checkpoint records where the agent was on a page (URL, scroll position and
+//! the values of form fields, optionally the page's web storage) so it can return
+//! to a known-good point after a misstep. Checkpoints are captured and restored
+//! with injected JavaScript, which works the same in WebDriver and CDP mode.
+//!
+//! Password and file inputs are never captured. Cookies are not part of a
+//! checkpoint.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Snapshot of the top-level document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageCheckpoint {
+    /// URL of the page.
+    pub url: String,
+    /// Horizontal scroll offset in CSS pixels.
+    pub scroll_x: f64,
+    /// Vertical scroll offset in CSS pixels.
+    pub scroll_y: f64,
+    /// Values of the form fields on the page.
+    pub fields: Vec<FieldValue>,
+    /// Web storage of the page's origin, when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage: Option<StorageState>,
+}
+
+/// Value of a single `input`, `textarea` or `select` element.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldValue {
+    /// Position among the form fields of the document, used when the element
+    /// has no id or name.
+    pub index: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Text value, or the value attribute of a checkbox or radio button.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Checked state of a checkbox or radio button.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checked: Option<bool>,
+    /// Selected option values of a multi-select.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected: Option<Vec<String>>,
+}
+
+/// `localStorage` and `sessionStorage` contents. A side is `None` when the page
+/// denied access to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageState {
+    pub local: Option<BTreeMap<String, String>>,
+    pub session: Option<BTreeMap<String, String>>,
+}
+
+/// Summary of a saved checkpoint returned to the model. Field values are not
+/// echoed back.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CheckpointSummary {
+    /// Name of the checkpoint.
+    pub name: String,
+    /// URL of the page.
+    pub url: String,
+    /// Horizontal scroll offset in CSS pixels.
+    pub scroll_x: f64,
+    /// Vertical scroll offset in CSS pixels.
+    pub scroll_y: f64,
+    /// Number of form fields captured.
+    pub field_count: usize,
+    /// Whether web storage was captured.
+    pub includes_storage: bool,
+}
+
+impl PageCheckpoint {
+    /// Describe the checkpoint under the given name.
+    pub fn summary(&self, name: &str) -> CheckpointSummary {
+        CheckpointSummary {
+            name: name.to_string(),
+            url: self.url.clone(),
+            scroll_x: self.scroll_x,
+            scroll_y: self.scroll_y,
+            field_count: self.fields.len(),
+            includes_storage: self.storage.is_some(),
+        }
+    }
+}
+
+/// JavaScript expression capturing a [`PageCheckpoint`] of the current document.
+pub fn capture_script(include_storage: bool) -> String {
+    format!("{}({})", CAPTURE_CHECKPOINT_FUNCTION, include_storage)
+}
+
+/// JavaScript expression writing the checkpoint's storage back into the page.
+/// Returns nothing; a no-op when the checkpoint has no storage.
+pub fn restore_storage_script(checkpoint: &PageCheckpoint) -> anyhow::Result<String> {
+    Ok(format!(
+        "{}({})",
+        RESTORE_STORAGE_FUNCTION,
+        serde_json::to_string(&checkpoint.storage)?
+    ))
+}
+
+/// JavaScript expression restoring form fields and scroll position. Evaluates to
+/// the number of fields that were restored.
+pub fn restore_page_script(checkpoint: &PageCheckpoint) -> anyhow::Result<String> {
+    Ok(format!(
+        "{}({})",
+        RESTORE_PAGE_FUNCTION,
+        serde_json::to_string(checkpoint)?
+    ))
+}
+
+const CAPTURE_CHECKPOINT_FUNCTION: &str = r#"
+(function(includeStorage) {
+    var skipped = ['password', 'file', 'hidden', 'submit', 'button', 'reset', 'image'];
+    var elements = document.querySelectorAll('input, textarea, select');
+    var fields = [];
+    for (var i = 0; i < elements.length; i++) {
+        var el = elements[i];
+        var type = (el.type || '').toLowerCase();
+        if (skipped.indexOf(type) !== -1) {
+            continue;
+        }
+        var field = { index: i, id: el.id || null, name: el.name || null };
+        if (type === 'checkbox' || type === 'radio') {
+            field.checked = el.checked;
+            field.value = el.value;
+        } else if (el.tagName === 'SELECT' && el.multiple) {
+            field.selected = [];
+            for (var j = 0; j < el.options.length; j++) {
+                if (el.options[j].selected) {
+                    field.selected.push(el.options[j].value);
+                }
+            }
+        } else {
+            field.value = el.value;
+        }
+        fields.push(field);
+    }
+    function dump(getStorage) {
+        try {
+            var storage = getStorage();
+            var out = {};
+            for (var i = 0; i < storage.length; i++) {
+                var key = storage.key(i);
+                out[key] = storage.getItem(key);
+            }
+            return out;
+        } catch (e) {
+            return null;
+        }
+    }
+    return {
+        url: location.href,
+        scroll_x: window.scrollX,
+        scroll_y: window.scrollY,
+        fields: fields,
+        storage: includeStorage ? {
+            local: dump(function() { return window.localStorage; }),
+            session: dump(function() { return window.sessionStorage; })
+        } : null
+    };
+})"#;
+
+const RESTORE_STORAGE_FUNCTION: &str = r#"
+(function(storage) {
+    function load(getStorage, values) {
+        if (!values) {
+            return;
+        }
+        try {
+            var target = getStorage();
+            target.clear();
+            Object.keys(values).forEach(function(key) {
+                target.setItem(key, values[key]);
+            });
+        } catch (e) {}
+    }
+    if (storage) {
+        load(function() { return window.localStorage; }, storage.local);
+        load(function() { return window.sessionStorage; }, storage.session);
+    }
+})"#;
+
+const RESTORE_PAGE_FUNCTION: &str = r#"
+(function(checkpoint) {
+    var elements = document.querySelectorAll('input, textarea, select');
+    function isField(el) {
+        return el && /^(INPUT|TEXTAREA|SELECT)$/.test(el.tagName);
+    }
+    function find(field) {
+        var el = field.id ? document.getElementById(field.id) : null;
+        if (isField(el)) {
+            return el;
+        }
+        if (field.name) {
+            var named = document.getElementsByName(field.name);
+            for (var i = 0; i < named.length; i++) {
+                var type = (named[i].type || '').toLowerCase();
+                var byValue = type === 'radio' || type === 'checkbox';
+                if (isField(named[i]) && (!byValue || named[i].value === field.value)) {
+                    return named[i];
+                }
+            }
+        }
+        return isField(elements[field.index]) ? elements[field.index] : null;
+    }
+    // Use the prototype setter so frameworks that track the value property see the change
+    function setValue(el, value) {
+        var descriptor = Object.getOwnPropertyDescriptor(Object.getPrototypeOf(el), 'value');
+        if (descriptor && descriptor.set) {
+            descriptor.set.call(el, value);
+        } else {
+            el.value = value;
+        }
+    }
+    var restored = 0;
+    checkpoint.fields.forEach(function(field) {
+        var el = find(field);
+        if (!el) {
+            return;
+        }
+        if (field.checked !== undefined && field.checked !== null) {
+            el.checked = field.checked;
+        } else if (field.selected) {
+            for (var i = 0; i < el.options.length; i++) {
+                el.options[i].selected = field.selected.indexOf(el.options[i].value) !== -1;
+            }
+        } else if (field.value !== undefined && field.value !== null) {
+            setValue(el, field.value);
+        } else {
+            return;
+        }
+        el.dispatchEvent(new Event('input', { bubbles: true }));
+        el.dispatchEvent(new Event('change', { bubbles: true }));
+        restored++;
+    });
+    window.scrollTo(checkpoint.scroll_x, checkpoint.scroll_y);
+    return restored;
+})"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        // Shape produced by the capture script
+        let captured = serde_json::json!({
+            "url": "https://example.com/form",
+            "scroll_x": 0,
+            "scroll_y": 420.5,
+            "fields": [
+                {"index": 0, "id": "email", "name": "email", "value": "a@example.com"},
+                {"index": 2, "id": null, "name": "plan", "value": "pro", "checked": true}
+            ],
+            "storage": null
+        });
+        let checkpoint: PageCheckpoint = serde_json::from_value(captured).unwrap();
+        assert_eq!(checkpoint.fields.len(), 2);
+        assert_eq!(checkpoint.fields[1].checked, Some(true));
+        assert!(checkpoint.storage.is_none());
+
+        let script = restore_page_script(&checkpoint).unwrap();
+        assert!(script.contains(r#""value":"a@example.com""#));
+        assert!(script.trim_end().ends_with(')'));
+
+        let summary = checkpoint.summary("before-submit");
+        assert_eq!(summary.field_count, 2);
+        assert!(!summary.includes_storage);
+    }
+}
@@ -2,9 +2,17 @@
 //!
 //! Supports configuration via environment variables and config files.
 
+use crate::approval::{self, ApprovalRule, TimeoutPolicy};
+use crate::cassette::Cassette;
+use crate::media_devices;
+use crate::recipes::RecipeRegistry;
+use crate::screenshot_scale;
+use crate::search_engine::{self, SearchEngine};
+use crate::zoom_to_fit;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// Default WebDriver port (ChromeDriver default).
@@ -16,6 +24,17 @@ pub const DEFAULT_CDP_PORT: u16 = 9222;
 /// Default HTTP server port.
 pub const DEFAULT_HTTP_PORT: u16 = 8080;
 
+/// Normalize an HTTP base path to `/segment/...` form without a trailing slash.
+/// Returns an empty string for the root path.
+fn normalize_base_path(s: &str) -> String {
+    let trimmed = s.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", trimmed)
+    }
+}
+
 /// Parse a duration string into a Duration.
 ///
 /// Accepts formats like:
@@ -81,8 +100,71 @@ pub enum ConnectionMode {
     Cdp,
 }
 
+/// Headless implementation used for Chromium-based browsers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HeadlessMode {
+    /// The full browser without a window (`--headless=new`); renders like a
+    /// headful browser
+    #[default]
+    New,
+    /// The legacy headless shell (`--headless=old`), which lacks some browser
+    /// features and renders some sites differently
+    Old,
+}
+
+impl HeadlessMode {
+    /// Command-line switch selecting this mode in Chrome and Edge.
+    pub fn chromium_arg(self) -> &'static str {
+        match self {
+            HeadlessMode::New => "--headless=new",
+            HeadlessMode::Old => "--headless=old",
+        }
+    }
+}
+
+/// How coordinates outside the viewport are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CoordinatePolicy {
+    /// Fail the action with an error naming the viewport size
+    #[default]
+    Reject,
+    /// Move the point to the nearest pixel inside the viewport
+    Clamp,
+}
+
+/// Modifier that shortcuts written with Control are pressed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PrimaryModifier {
+    /// Command on macOS hosts when the browser is visible, Control otherwise
+    #[default]
+    Auto,
+    /// Always press Control as requested
+    Control,
+    /// Always press Command (Meta) in place of Control
+    Meta,
+}
+
+/// When action tools attach a screenshot to their response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenshotPolicy {
+    /// Attach a screenshot to every response
+    #[default]
+    Always,
+    /// Attach a screenshot only when the page looks different from the
+    /// previous screenshot of the tab
+    OnChange,
+    /// Never attach a screenshot; the agent calls `current_state`
+    Never,
+    /// Attach a screenshot when the call asks for one with `include_screenshot`
+    OnDemand,
+}
+
 /// Main configuration for the MCP browser control server.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
     /// Path to the browser binary (e.g., Chrome, Chromium, Firefox).
@@ -94,6 +176,18 @@ pub struct Config {
     /// If auto_launch is true, this is automatically determined.
     pub webdriver_url: Option<String>,
 
+    /// How long the WebDriver session waits for a page to load before a
+    /// navigation fails.
+    pub webdriver_page_load_timeout: std::time::Duration,
+
+    /// How long the WebDriver session lets a script run before it fails.
+    pub webdriver_script_timeout: std::time::Duration,
+
+    /// Interval of the keep-alive command sent to an idle WebDriver session,
+    /// so a server-side session timeout (e.g. Selenium Grid's) does not expire
+    /// it between actions. Zero disables the keep-alive.
+    pub webdriver_keepalive_interval: std::time::Duration,
+
     /// Browser type to use.
     pub browser_type: BrowserType,
 
@@ -104,12 +198,34 @@ pub struct Config {
     /// Initial URL to navigate to when opening the browser.
     pub initial_url: String,
 
-    /// Search engine URL for the search action.
-    pub search_engine_url: String,
+    /// URL to open as a Chrome/Edge app window (`--app=URL`), without tabs or
+    /// omnibox, so the whole window is page content. Replaces `initial_url`.
+    pub app_mode_url: Option<String>,
+
+    /// Additional URLs to open in their own tabs when the browser starts.
+    /// The tab showing `initial_url` stays active.
+    pub initial_tabs: Vec<String>,
+
+    /// Search engines available to the search tools, by name.
+    pub search_engines: Vec<SearchEngine>,
+
+    /// Name of the engine used when a search names none.
+    pub default_search_engine: String,
 
     /// Whether to run the browser in headless mode.
     pub headless: bool,
 
+    /// Headless implementation used for Chrome and Edge when `headless` is set.
+    pub headless_mode: HeadlessMode,
+
+    /// Whether to start an Xvfb display for a headful browser when the host is
+    /// Linux without a display (e.g., a CI runner). Default is false.
+    pub virtual_display: bool,
+
+    /// X display that launched browsers and drivers are started on.
+    /// Set when a virtual display is started; None uses the server's environment.
+    pub display: Option<String>,
+
     /// Set of tool names to disable.
     pub disabled_tools: HashSet<String>,
 
@@ -126,6 +242,24 @@ pub struct Config {
     /// HTTP server host (only used when transport_mode is Http).
     pub http_host: String,
 
+    /// Bearer tokens accepted on the HTTP `/mcp` endpoint.
+    /// When empty, the endpoint does not require authentication.
+    pub http_auth_tokens: Vec<String>,
+
+    /// Origins allowed to make cross-origin requests to the HTTP server.
+    /// `*` allows any origin. When empty, CORS headers are not sent.
+    pub http_cors_origins: Vec<String>,
+
+    /// Path prefix under which the HTTP endpoints are served, for deployments
+    /// behind a reverse proxy (e.g. `/agents/browser`).
+    /// Normalized to start with `/` and have no trailing `/`; empty means root.
+    pub http_base_path: String,
+
+    /// Inactivity period after which an HTTP session expires and its browser is closed.
+    /// Set to 0 (or Duration::ZERO) to keep sessions until the client closes them.
+    /// Default is 30 minutes.
+    pub http_session_timeout: std::time::Duration,
+
     /// Path to the browser driver executable.
     /// If not set, will try to find the driver in PATH or common locations,
     /// or download it if auto_download_driver is enabled.
@@ -138,6 +272,14 @@ pub struct Config {
     /// Whether to use undetected/stealth mode.
     pub undetected: bool,
 
+    /// Whether WebRTC is kept on the proxy so it cannot reveal the real IP.
+    /// Always on in undetected mode.
+    pub webrtc_leak_protection: bool,
+
+    /// Whether Chromium provides fake cameras and microphones and grants
+    /// capture permission without a prompt.
+    pub fake_media_devices: bool,
+
     /// Browser connection mode: webdriver or cdp.
     pub connection_mode: ConnectionMode,
 
@@ -165,13 +307,233 @@ pub struct Config {
     /// CDP URL for connecting to an existing browser.
     /// Set automatically when auto_start launches a browser with CDP,
     /// or can be derived from cdp_port when connecting to a manually started browser.
+    /// Set explicitly to attach to a running desktop browser, possibly on another host.
     pub cdp_url: Option<String>,
 
+    /// Whether the CDP browser is the user's own browser (CDP mode only).
+    /// The server then never launches or closes a browser, never opens or closes
+    /// tabs, keeps navigation in the attached tab, and removes its injected
+    /// scripts when it disconnects.
+    pub attached_mode: bool,
+
     /// Idle timeout duration for automatically closing the browser when inactive.
     /// After this duration of no operations, the browser will be closed automatically.
     /// Set to 0 (or Duration::ZERO) to disable idle timeout.
     /// Default is 10 minutes.
     pub idle_timeout: std::time::Duration,
+
+    /// Browser process tree memory (RSS) above which a warning is logged, in megabytes.
+    /// Set to 0 to disable.
+    pub memory_warning_mb: u64,
+
+    /// JavaScript heap usage above which a warning is logged, in megabytes.
+    /// Set to 0 to disable.
+    pub js_heap_warning_mb: u64,
+
+    /// Interval between background memory checks.
+    /// Set to 0 (or Duration::ZERO) to disable periodic checks.
+    pub memory_check_interval: std::time::Duration,
+
+    /// Number of recent tool calls kept for `get_action_history`.
+    /// Set to 0 to disable the history.
+    pub action_history_size: usize,
+
+    /// Number of recent screenshots kept for `get_screenshot`.
+    /// Set to 0 to disable screenshot storage.
+    pub screenshot_retention: usize,
+
+    /// Maximum total size of the stored screenshots of a session, in megabytes.
+    pub screenshot_store_max_mb: u64,
+
+    /// Directory for stored screenshots; each session uses a subdirectory.
+    /// Defaults to `mcp-computer-use/screenshots` in the system temp directory.
+    pub screenshot_dir: Option<PathBuf>,
+
+    /// S3 bucket for stored screenshots, instead of the directory
+    /// (requires the `s3-screenshots` feature).
+    pub screenshot_s3_bucket: Option<String>,
+
+    /// Endpoint of the S3-compatible service.
+    /// Defaults to AWS S3 in `screenshot_s3_region`.
+    pub screenshot_s3_endpoint: Option<String>,
+
+    /// Region of the S3 bucket.
+    pub screenshot_s3_region: String,
+
+    /// Key prefix of stored screenshots in the S3 bucket.
+    pub screenshot_s3_prefix: String,
+
+    /// TOML file of saved site credentials for `login_with_saved_credentials`.
+    /// Read on every login, so edits apply without a restart.
+    pub credentials_file: Option<PathBuf>,
+
+    /// Whether navigations started by the tools consult the site's robots.txt
+    /// and are refused where it disallows them.
+    pub respect_robots_txt: bool,
+
+    /// Minimum delay between navigations to the same host.
+    /// Zero disables the delay; a robots.txt `Crawl-delay` can raise it.
+    pub politeness_delay: std::time::Duration,
+
+    /// Whether `check_links` may request loopback, link-local and private
+    /// addresses, e.g. to check a site under development.
+    pub check_links_allow_private: bool,
+
+    /// Navigations to one domain after which responses carry a warning.
+    /// Zero disables the budget.
+    pub max_navigations_per_domain: usize,
+
+    /// Whether responses warn when the agent returns to a page right after
+    /// leaving it (A → B → A).
+    pub detect_navigation_loops: bool,
+
+    /// Whether responses summarize what changed since the previous state:
+    /// URL and title changes, DOM mutations and new console errors.
+    pub state_delta: bool,
+
+    /// Whether responses flag pages that ask the user to sign in: login
+    /// URLs, 401/403 responses and visible password fields.
+    pub detect_login: bool,
+
+    /// Whether pages reached with `go_back` and `go_forward` are scrolled back
+    /// to where they were when last shown.
+    pub restore_scroll: bool,
+
+    /// Maximum time a click waits for the element under the target coordinates
+    /// to stop moving (layout shifts, animations). Zero disables the wait.
+    pub click_stability_timeout: std::time::Duration,
+
+    /// Whether `click_at` in CDP mode clicks with JavaScript `element.click()`
+    /// instead of native mouse events.
+    pub cdp_js_click: bool,
+
+    /// Directory of site recipes (TOML or JSON files), loaded at startup.
+    pub recipes_dir: Option<PathBuf>,
+
+    /// Directory where `save_workspace` stores workspaces.
+    /// Defaults to `mcp-computer-use/workspaces` in the user's data directory.
+    pub workspaces_dir: Option<PathBuf>,
+
+    /// Directory where `compare_screenshot` stores baselines.
+    /// Defaults to `mcp-computer-use/baselines` in the user's data directory.
+    pub baselines_dir: Option<PathBuf>,
+
+    /// Local copy of axe-core (`axe.min.js`) for `audit_accessibility`.
+    /// Without it, a pinned release is downloaded on first use and cached.
+    pub axe_core_path: Option<PathBuf>,
+
+    /// Content types downloads may have, exact or as `type/*`. Empty allows any.
+    pub download_allowed_types: Vec<String>,
+
+    /// Maximum size of a download in megabytes. Zero means no limit.
+    pub download_max_mb: u64,
+
+    /// Whether executables may be downloaded.
+    pub download_allow_executables: bool,
+
+    /// Cassette file the browser backend's replies are recorded to.
+    pub record_cassette: Option<PathBuf>,
+
+    /// Cassette file whose replies are replayed instead of driving a browser.
+    /// Takes precedence over `record_cassette`.
+    pub replay_cassette: Option<PathBuf>,
+
+    /// Operator endpoint asked to approve tool calls matching `approval_rules`.
+    pub approval_webhook_url: Option<String>,
+
+    /// Bearer token sent to the approval webhook.
+    pub approval_webhook_token: Option<String>,
+
+    /// Tool calls that require approval.
+    pub approval_rules: Vec<ApprovalRule>,
+
+    /// Time to wait for an approval decision.
+    pub approval_timeout: std::time::Duration,
+
+    /// What to do when no decision arrives in time.
+    pub approval_on_timeout: TimeoutPolicy,
+
+    /// Base directory of the per-session scratch directories.
+    /// Defaults to `mcp-computer-use/sessions` in the system temp directory.
+    pub scratch_dir: Option<PathBuf>,
+
+    /// Directories the files tools read and write must be in, besides the
+    /// session's scratch directory. Empty allows any directory.
+    pub allowed_paths: Vec<PathBuf>,
+
+    /// Maximum size of a file tools read or write, in megabytes.
+    /// Zero means no limit.
+    pub max_file_mb: u64,
+
+    /// Extensions the files tools read and write must have. Empty allows any.
+    pub allowed_file_extensions: Vec<String>,
+
+    /// Extensions the files tools read and write must not have.
+    pub blocked_file_extensions: Vec<String>,
+
+    /// Recipes loaded from `recipes_dir`; set at startup.
+    #[serde(skip)]
+    pub recipes: Arc<RecipeRegistry>,
+
+    /// Cassette opened from `record_cassette` or `replay_cassette`; set at startup.
+    #[serde(skip)]
+    pub cassette: Option<Arc<Cassette>>,
+
+    /// Profile directory of a browser launched in CDP mode. Set for browsers
+    /// created with `create_browser`, so they do not share a profile.
+    #[serde(skip)]
+    pub profile_dir: Option<PathBuf>,
+
+    /// Directory the browser saves downloads to. Set to the `downloads`
+    /// directory of each session's scratch directory.
+    #[serde(skip)]
+    pub download_dir: Option<PathBuf>,
+
+    /// Handling of action coordinates outside the viewport.
+    pub coordinate_policy: CoordinatePolicy,
+
+    /// Modifier used for shortcuts such as Control+L, so they keep working
+    /// on macOS where the same shortcuts use Command.
+    pub primary_modifier: PrimaryModifier,
+
+    /// Whether web notifications and permission prompts are recorded for
+    /// `get_notifications`.
+    pub capture_notifications: bool,
+
+    /// Whether notifications shown since the previous action are included in
+    /// every action response.
+    pub notifications_in_response: bool,
+
+    /// Whether the browser is started with `--mute-audio`, so pages play no
+    /// sound.
+    pub mute_audio: bool,
+
+    /// How many times a blank (single-color) screenshot is captured again
+    /// before it is returned. Set to 0 to disable.
+    pub blank_screenshot_retries: u32,
+
+    /// Whether pages wider than the viewport are zoomed out to fit it before
+    /// each capture.
+    pub zoom_to_fit: bool,
+
+    /// Smallest zoom, in percent, that zoom-to-fit uses.
+    pub zoom_to_fit_min: u32,
+
+    /// Largest width or height of returned screenshots; larger screenshots
+    /// are scaled down, and coordinates scaled to match. None keeps them at
+    /// full size.
+    pub max_screenshot_dimension: Option<u32>,
+
+    /// When action tools attach a screenshot to their response. Tools that
+    /// exist to return screenshots, such as `current_state`, always do.
+    pub screenshot_policy: ScreenshotPolicy,
+
+    /// Tesseract languages `ocr_screenshot` recognizes by default, such as
+    /// `eng` or `chi_sim`.
+    pub ocr_languages: Vec<String>,
+
+    /// Tesseract command run by `ocr_screenshot`.
+    pub tesseract_path: PathBuf,
 }
 
 impl Default for Config {
@@ -179,31 +541,240 @@ impl Default for Config {
         Self {
             browser_binary_path: None,
             webdriver_url: None, // Empty by default, determined at runtime
+            webdriver_page_load_timeout: std::time::Duration::from_secs(300),
+            webdriver_script_timeout: std::time::Duration::from_secs(30),
+            webdriver_keepalive_interval: std::time::Duration::from_secs(60),
             browser_type: BrowserType::Chrome,
             screen_width: 1280,
             screen_height: 720,
             initial_url: "https://www.google.com".to_string(),
-            search_engine_url: "https://www.google.com".to_string(),
+            app_mode_url: None,
+            initial_tabs: Vec::new(),
+            search_engines: search_engine::builtin(),
+            default_search_engine: search_engine::DEFAULT_ENGINE.to_string(),
             headless: true,
+            headless_mode: HeadlessMode::New,
+            virtual_display: false,
+            display: None,
             disabled_tools: HashSet::new(),
             highlight_mouse: false,
             transport_mode: TransportMode::Stdio,
             http_port: None, // Fallback to DEFAULT_HTTP_PORT when needed
             http_host: "127.0.0.1".to_string(),
+            http_auth_tokens: Vec::new(),
+            http_cors_origins: Vec::new(),
+            http_base_path: String::new(),
+            http_session_timeout: std::time::Duration::from_secs(1800), // 30 minutes default
             driver_path: None,
             driver_port: None, // Fallback to DEFAULT_DRIVER_PORT when needed
             undetected: false,
+            webrtc_leak_protection: false,
+            fake_media_devices: false,
             connection_mode: ConnectionMode::WebDriver,
             cdp_port: None, // Fallback to DEFAULT_CDP_PORT when needed
             auto_start: false,
             auto_download_driver: false,
             open_browser_on_start: false,
             cdp_url: None,
+            attached_mode: false,
             idle_timeout: std::time::Duration::from_secs(600), // 10 minutes default
+            memory_warning_mb: 2048,
+            js_heap_warning_mb: 512,
+            memory_check_interval: std::time::Duration::from_secs(60),
+            action_history_size: 50,
+            screenshot_retention: 50,
+            screenshot_store_max_mb: 100,
+            screenshot_dir: None,
+            screenshot_s3_bucket: None,
+            screenshot_s3_endpoint: None,
+            screenshot_s3_region: "us-east-1".to_string(),
+            screenshot_s3_prefix: "screenshots/".to_string(),
+            credentials_file: None,
+            respect_robots_txt: false,
+            politeness_delay: std::time::Duration::ZERO,
+            check_links_allow_private: false,
+            max_navigations_per_domain: 0,
+            detect_navigation_loops: true,
+            state_delta: false,
+            detect_login: true,
+            restore_scroll: true,
+            click_stability_timeout: std::time::Duration::ZERO,
+            cdp_js_click: false,
+            recipes_dir: None,
+            workspaces_dir: None,
+            baselines_dir: None,
+            axe_core_path: None,
+            download_allowed_types: Vec::new(),
+            download_max_mb: 100,
+            download_allow_executables: false,
+            record_cassette: None,
+            replay_cassette: None,
+            approval_webhook_url: None,
+            approval_webhook_token: None,
+            approval_rules: Vec::new(),
+            approval_timeout: std::time::Duration::from_secs(120),
+            approval_on_timeout: TimeoutPolicy::Deny,
+            scratch_dir: None,
+            allowed_paths: Vec::new(),
+            max_file_mb: 0,
+            allowed_file_extensions: Vec::new(),
+            blocked_file_extensions: Vec::new(),
+            recipes: Arc::default(),
+            cassette: None,
+            profile_dir: None,
+            download_dir: None,
+            coordinate_policy: CoordinatePolicy::Reject,
+            primary_modifier: PrimaryModifier::Auto,
+            capture_notifications: true,
+            notifications_in_response: false,
+            mute_audio: true,
+            blank_screenshot_retries: 2,
+            zoom_to_fit: false,
+            zoom_to_fit_min: 50,
+            max_screenshot_dimension: None,
+            screenshot_policy: ScreenshotPolicy::Always,
+            ocr_languages: vec!["eng".to_string()],
+            tesseract_path: PathBuf::from("tesseract"),
         }
     }
 }
 
+/// Debug stand-in for a secret, so logging the configuration does not leak it.
+struct Redacted;
+
+impl std::fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+// Written out rather than derived so secrets are redacted: the
+// configuration is logged at startup.
+impl std::fmt::Debug for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Config")
+            .field("browser_binary_path", &self.browser_binary_path)
+            .field("webdriver_url", &self.webdriver_url)
+            .field(
+                "webdriver_page_load_timeout",
+                &self.webdriver_page_load_timeout,
+            )
+            .field("webdriver_script_timeout", &self.webdriver_script_timeout)
+            .field(
+                "webdriver_keepalive_interval",
+                &self.webdriver_keepalive_interval,
+            )
+            .field("browser_type", &self.browser_type)
+            .field("screen_width", &self.screen_width)
+            .field("screen_height", &self.screen_height)
+            .field("initial_url", &self.initial_url)
+            .field("app_mode_url", &self.app_mode_url)
+            .field("initial_tabs", &self.initial_tabs)
+            .field("search_engines", &self.search_engines)
+            .field("default_search_engine", &self.default_search_engine)
+            .field("headless", &self.headless)
+            .field("headless_mode", &self.headless_mode)
+            .field("virtual_display", &self.virtual_display)
+            .field("display", &self.display)
+            .field("disabled_tools", &self.disabled_tools)
+            .field("highlight_mouse", &self.highlight_mouse)
+            .field("transport_mode", &self.transport_mode)
+            .field("http_port", &self.http_port)
+            .field("http_host", &self.http_host)
+            .field(
+                "http_auth_tokens",
+                &self
+                    .http_auth_tokens
+                    .iter()
+                    .map(|_| Redacted)
+                    .collect::<Vec<_>>(),
+            )
+            .field("http_cors_origins", &self.http_cors_origins)
+            .field("http_base_path", &self.http_base_path)
+            .field("http_session_timeout", &self.http_session_timeout)
+            .field("driver_path", &self.driver_path)
+            .field("driver_port", &self.driver_port)
+            .field("undetected", &self.undetected)
+            .field("webrtc_leak_protection", &self.webrtc_leak_protection)
+            .field("fake_media_devices", &self.fake_media_devices)
+            .field("connection_mode", &self.connection_mode)
+            .field("cdp_port", &self.cdp_port)
+            .field("auto_start", &self.auto_start)
+            .field("auto_download_driver", &self.auto_download_driver)
+            .field("open_browser_on_start", &self.open_browser_on_start)
+            .field("cdp_url", &self.cdp_url)
+            .field("attached_mode", &self.attached_mode)
+            .field("idle_timeout", &self.idle_timeout)
+            .field("memory_warning_mb", &self.memory_warning_mb)
+            .field("js_heap_warning_mb", &self.js_heap_warning_mb)
+            .field("memory_check_interval", &self.memory_check_interval)
+            .field("action_history_size", &self.action_history_size)
+            .field("screenshot_retention", &self.screenshot_retention)
+            .field("screenshot_store_max_mb", &self.screenshot_store_max_mb)
+            .field("screenshot_dir", &self.screenshot_dir)
+            .field("screenshot_s3_bucket", &self.screenshot_s3_bucket)
+            .field("screenshot_s3_endpoint", &self.screenshot_s3_endpoint)
+            .field("screenshot_s3_region", &self.screenshot_s3_region)
+            .field("screenshot_s3_prefix", &self.screenshot_s3_prefix)
+            .field("credentials_file", &self.credentials_file)
+            .field("respect_robots_txt", &self.respect_robots_txt)
+            .field("politeness_delay", &self.politeness_delay)
+            .field("check_links_allow_private", &self.check_links_allow_private)
+            .field(
+                "max_navigations_per_domain",
+                &self.max_navigations_per_domain,
+            )
+            .field("detect_navigation_loops", &self.detect_navigation_loops)
+            .field("state_delta", &self.state_delta)
+            .field("detect_login", &self.detect_login)
+            .field("restore_scroll", &self.restore_scroll)
+            .field("click_stability_timeout", &self.click_stability_timeout)
+            .field("cdp_js_click", &self.cdp_js_click)
+            .field("recipes_dir", &self.recipes_dir)
+            .field("workspaces_dir", &self.workspaces_dir)
+            .field("baselines_dir", &self.baselines_dir)
+            .field("axe_core_path", &self.axe_core_path)
+            .field("download_allowed_types", &self.download_allowed_types)
+            .field("download_max_mb", &self.download_max_mb)
+            .field(
+                "download_allow_executables",
+                &self.download_allow_executables,
+            )
+            .field("record_cassette", &self.record_cassette)
+            .field("replay_cassette", &self.replay_cassette)
+            .field("approval_webhook_url", &self.approval_webhook_url)
+            .field(
+                "approval_webhook_token",
+                &self.approval_webhook_token.as_ref().map(|_| Redacted),
+            )
+            .field("approval_rules", &self.approval_rules)
+            .field("approval_timeout", &self.approval_timeout)
+            .field("approval_on_timeout", &self.approval_on_timeout)
+            .field("scratch_dir", &self.scratch_dir)
+            .field("allowed_paths", &self.allowed_paths)
+            .field("max_file_mb", &self.max_file_mb)
+            .field("allowed_file_extensions", &self.allowed_file_extensions)
+            .field("blocked_file_extensions", &self.blocked_file_extensions)
+            .field("recipes", &self.recipes)
+            .field("cassette", &self.cassette)
+            .field("profile_dir", &self.profile_dir)
+            .field("download_dir", &self.download_dir)
+            .field("coordinate_policy", &self.coordinate_policy)
+            .field("primary_modifier", &self.primary_modifier)
+            .field("capture_notifications", &self.capture_notifications)
+            .field("notifications_in_response", &self.notifications_in_response)
+            .field("mute_audio", &self.mute_audio)
+            .field("blank_screenshot_retries", &self.blank_screenshot_retries)
+            .field("zoom_to_fit", &self.zoom_to_fit)
+            .field("zoom_to_fit_min", &self.zoom_to_fit_min)
+            .field("max_screenshot_dimension", &self.max_screenshot_dimension)
+            .field("screenshot_policy", &self.screenshot_policy)
+            .field("ocr_languages", &self.ocr_languages)
+            .field("tesseract_path", &self.tesseract_path)
+            .finish()
+    }
+}
+
 impl Config {
     /// Get the effective WebDriver URL.
     /// Returns the configured URL or falls back to default based on driver_port.
@@ -221,6 +792,81 @@ impl Config {
         self.driver_port.unwrap_or(DEFAULT_DRIVER_PORT)
     }
 
+    /// Get the effective workspaces directory.
+    /// Returns the configured directory or falls back to the user's data directory.
+    pub fn effective_workspaces_dir(&self) -> PathBuf {
+        self.workspaces_dir.clone().unwrap_or_else(|| {
+            dirs::data_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("mcp-computer-use")
+                .join("workspaces")
+        })
+    }
+
+    /// Get the effective baselines directory.
+    /// Returns the configured directory or falls back to the user's data directory.
+    pub fn effective_baselines_dir(&self) -> PathBuf {
+        self.baselines_dir.clone().unwrap_or_else(|| {
+            dirs::data_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("mcp-computer-use")
+                .join("baselines")
+        })
+    }
+
+    /// Get the URL the first tab opens: the app mode URL, or the initial URL.
+    pub fn start_url(&self) -> &str {
+        self.app_mode_url.as_deref().unwrap_or(&self.initial_url)
+    }
+
+    /// Get the Chromium switch opening the app mode URL, if app mode is configured.
+    pub fn app_mode_arg(&self) -> Option<String> {
+        self.app_mode_url
+            .as_ref()
+            .map(|url| format!("--app={}", url))
+    }
+
+    /// Get the Chromium switches for WebRTC leak protection and fake media
+    /// devices.
+    pub fn media_device_args(&self) -> Vec<&'static str> {
+        media_devices::chromium_switches(
+            self.webrtc_leak_protection || self.undetected,
+            self.fake_media_devices,
+        )
+    }
+
+    /// Get the factor screenshots are scaled down by to fit
+    /// `max_screenshot_dimension`.
+    pub fn screenshot_scale(&self) -> f64 {
+        screenshot_scale::scale_factor(
+            (self.screen_width, self.screen_height),
+            self.max_screenshot_dimension,
+        )
+    }
+
+    /// Get a search engine by name, or the default engine.
+    pub fn search_engine(&self, name: Option<&str>) -> anyhow::Result<&SearchEngine> {
+        let name = name
+            .unwrap_or(&self.default_search_engine)
+            .trim()
+            .to_lowercase();
+        self.search_engines
+            .iter()
+            .find(|engine| engine.name == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = self
+                    .search_engines
+                    .iter()
+                    .map(|e| e.name.as_str())
+                    .collect();
+                anyhow::anyhow!(
+                    "Unknown search engine '{}', available: {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+
     /// Get the effective CDP port.
     pub fn effective_cdp_port(&self) -> u16 {
         self.cdp_port.unwrap_or(DEFAULT_CDP_PORT)
@@ -257,6 +903,40 @@ impl Config {
             config.webdriver_url = Some(url);
         }
 
+        if let Ok(timeout_str) = std::env::var("MCP_WEBDRIVER_PAGE_LOAD_TIMEOUT") {
+            config.webdriver_page_load_timeout = parse_duration(&timeout_str).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Invalid MCP_WEBDRIVER_PAGE_LOAD_TIMEOUT '{}': {}, using default 5m",
+                    timeout_str,
+                    e
+                );
+                std::time::Duration::from_secs(300)
+            });
+        }
+
+        if let Ok(timeout_str) = std::env::var("MCP_WEBDRIVER_SCRIPT_TIMEOUT") {
+            config.webdriver_script_timeout = parse_duration(&timeout_str).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Invalid MCP_WEBDRIVER_SCRIPT_TIMEOUT '{}': {}, using default 30s",
+                    timeout_str,
+                    e
+                );
+                std::time::Duration::from_secs(30)
+            });
+        }
+
+        // "0" disables the keep-alive
+        if let Ok(interval) = std::env::var("MCP_WEBDRIVER_KEEPALIVE_INTERVAL") {
+            config.webdriver_keepalive_interval = parse_duration(&interval).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Invalid MCP_WEBDRIVER_KEEPALIVE_INTERVAL '{}': {}, using default 60s",
+                    interval,
+                    e
+                );
+                std::time::Duration::from_secs(60)
+            });
+        }
+
         if let Ok(browser_type) = std::env::var("MCP_BROWSER_TYPE") {
             config.browser_type = match browser_type.to_lowercase().as_str() {
                 "chrome" => BrowserType::Chrome,
@@ -299,8 +979,51 @@ impl Config {
             config.initial_url = url;
         }
 
+        if let Ok(url) = std::env::var("MCP_APP_MODE_URL") {
+            config.app_mode_url = Some(url).filter(|url| !url.trim().is_empty());
+        }
+
+        if let Ok(tabs) = std::env::var("MCP_INITIAL_TABS") {
+            config.initial_tabs = tabs
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+
+        if let Ok(engines) = std::env::var("MCP_SEARCH_ENGINES") {
+            match search_engine::parse_list(&engines) {
+                Ok(engines) => search_engine::merge(&mut config.search_engines, engines),
+                Err(e) => tracing::warn!("Invalid MCP_SEARCH_ENGINES ({}), ignoring it", e),
+            }
+        }
+
+        // Older single-URL setting, kept as an engine named "custom"
         if let Ok(url) = std::env::var("MCP_SEARCH_ENGINE_URL") {
-            config.search_engine_url = url;
+            if !url.trim().is_empty() {
+                search_engine::merge(
+                    &mut config.search_engines,
+                    vec![SearchEngine::new("custom", &url)],
+                );
+                config.default_search_engine = "custom".to_string();
+            }
+        }
+
+        if let Ok(name) = std::env::var("MCP_SEARCH_ENGINE") {
+            let name = name.trim().to_lowercase();
+            if config
+                .search_engines
+                .iter()
+                .any(|engine| engine.name == name)
+            {
+                config.default_search_engine = name;
+            } else {
+                tracing::warn!(
+                    "Unknown MCP_SEARCH_ENGINE '{}', using default {}",
+                    name,
+                    config.default_search_engine
+                );
+            }
         }
 
         if let Ok(headless) = std::env::var("MCP_HEADLESS") {
@@ -314,6 +1037,31 @@ impl Config {
             };
         }
 
+        if let Ok(mode) = std::env::var("MCP_HEADLESS_MODE") {
+            config.headless_mode = match mode.to_lowercase().as_str() {
+                "new" => HeadlessMode::New,
+                "old" | "legacy" => HeadlessMode::Old,
+                _ => {
+                    tracing::warn!("Invalid MCP_HEADLESS_MODE '{}', using default new", mode);
+                    HeadlessMode::New
+                }
+            };
+        }
+
+        if let Ok(virtual_display) = std::env::var("MCP_VIRTUAL_DISPLAY") {
+            config.virtual_display = match virtual_display.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_VIRTUAL_DISPLAY '{}', using default false",
+                        virtual_display
+                    );
+                    false
+                }
+            };
+        }
+
         if let Ok(disabled) = std::env::var("MCP_DISABLED_TOOLS") {
             config.disabled_tools = disabled
                 .split(',')
@@ -362,6 +1110,37 @@ impl Config {
             config.http_host = host;
         }
 
+        if let Ok(tokens) = std::env::var("MCP_HTTP_AUTH_TOKEN") {
+            config.http_auth_tokens = tokens
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+
+        if let Ok(origins) = std::env::var("MCP_HTTP_CORS_ORIGINS") {
+            config.http_cors_origins = origins
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+
+        if let Ok(path) = std::env::var("MCP_HTTP_BASE_PATH") {
+            config.http_base_path = normalize_base_path(&path);
+        }
+
+        if let Ok(timeout_str) = std::env::var("MCP_HTTP_SESSION_TIMEOUT") {
+            config.http_session_timeout = parse_duration(&timeout_str).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Invalid MCP_HTTP_SESSION_TIMEOUT '{}': {}, using default 30m",
+                    timeout_str,
+                    e
+                );
+                std::time::Duration::from_secs(1800)
+            });
+        }
+
         if let Ok(path) = std::env::var("MCP_DRIVER_PATH") {
             config.driver_path = Some(PathBuf::from(path));
         }
@@ -395,6 +1174,34 @@ impl Config {
             };
         }
 
+        if let Ok(protection) = std::env::var("MCP_WEBRTC_LEAK_PROTECTION") {
+            config.webrtc_leak_protection = match protection.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_WEBRTC_LEAK_PROTECTION '{}', using default false",
+                        protection
+                    );
+                    false
+                }
+            };
+        }
+
+        if let Ok(fake) = std::env::var("MCP_FAKE_MEDIA_DEVICES") {
+            config.fake_media_devices = match fake.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_FAKE_MEDIA_DEVICES '{}', using default false",
+                        fake
+                    );
+                    false
+                }
+            };
+        }
+
         // Connection mode configuration
         if let Ok(mode) = std::env::var("MCP_CONNECTION_MODE") {
             config.connection_mode = match mode.to_lowercase().as_str() {
@@ -421,6 +1228,25 @@ impl Config {
             };
         }
 
+        // CDP URL of an already-running browser
+        if let Ok(url) = std::env::var("MCP_CDP_URL") {
+            config.cdp_url = Some(url);
+        }
+
+        if let Ok(attached) = std::env::var("MCP_ATTACHED_MODE") {
+            config.attached_mode = match attached.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_ATTACHED_MODE '{}', using default false",
+                        attached
+                    );
+                    false
+                }
+            };
+        }
+
         // Auto-start configuration (unified flag for both driver and browser)
         if let Ok(auto_start) = std::env::var("MCP_AUTO_START") {
             config.auto_start = match auto_start.to_lowercase().as_str() {
@@ -479,29 +1305,588 @@ impl Config {
             });
         }
 
-        Ok(config)
-    }
+        // Memory pressure warning configuration
+        if let Ok(limit) = std::env::var("MCP_MEMORY_WARNING_MB") {
+            config.memory_warning_mb = match limit.parse() {
+                Ok(l) => l,
+                Err(e) => {
+                    tracing::warn!(
+                        "Invalid MCP_MEMORY_WARNING_MB '{}': {}, using default 2048",
+                        limit,
+                        e
+                    );
+                    2048
+                }
+            };
+        }
 
-    /// Check if a tool is disabled.
-    pub fn is_tool_disabled(&self, tool_name: &str) -> bool {
-        self.disabled_tools.contains(tool_name)
-    }
-}
+        if let Ok(limit) = std::env::var("MCP_JS_HEAP_WARNING_MB") {
+            config.js_heap_warning_mb = match limit.parse() {
+                Ok(l) => l,
+                Err(e) => {
+                    tracing::warn!(
+                        "Invalid MCP_JS_HEAP_WARNING_MB '{}': {}, using default 512",
+                        limit,
+                        e
+                    );
+                    512
+                }
+            };
+        }
 
-/// All available tool names for reference.
-pub mod tool_names {
-    pub const CLICK_AT: &str = "click_at";
+        if let Ok(interval_str) = std::env::var("MCP_MEMORY_CHECK_INTERVAL") {
+            config.memory_check_interval = parse_duration(&interval_str).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Invalid MCP_MEMORY_CHECK_INTERVAL '{}': {}, using default 60s",
+                    interval_str,
+                    e
+                );
+                std::time::Duration::from_secs(60)
+            });
+        }
+
+        if let Ok(size) = std::env::var("MCP_ACTION_HISTORY_SIZE") {
+            config.action_history_size = match size.parse() {
+                Ok(s) => s,
+                Err(e) => {
+                    tracing::warn!(
+                        "Invalid MCP_ACTION_HISTORY_SIZE '{}': {}, using default 50",
+                        size,
+                        e
+                    );
+                    50
+                }
+            };
+        }
+
+        if let Ok(count) = std::env::var("MCP_SCREENSHOT_RETENTION") {
+            config.screenshot_retention = match count.parse() {
+                Ok(c) => c,
+                Err(e) => {
+                    tracing::warn!(
+                        "Invalid MCP_SCREENSHOT_RETENTION '{}': {}, using default 50",
+                        count,
+                        e
+                    );
+                    50
+                }
+            };
+        }
+
+        if let Ok(mb) = std::env::var("MCP_SCREENSHOT_STORE_MAX_MB") {
+            config.screenshot_store_max_mb = match mb.parse() {
+                Ok(m) => m,
+                Err(e) => {
+                    tracing::warn!(
+                        "Invalid MCP_SCREENSHOT_STORE_MAX_MB '{}': {}, using default 100",
+                        mb,
+                        e
+                    );
+                    100
+                }
+            };
+        }
+
+        if let Ok(path) = std::env::var("MCP_SCREENSHOT_DIR") {
+            config.screenshot_dir = Some(PathBuf::from(path));
+        }
+
+        if let Ok(bucket) = std::env::var("MCP_SCREENSHOT_S3_BUCKET") {
+            if !bucket.is_empty() {
+                config.screenshot_s3_bucket = Some(bucket);
+            }
+        }
+
+        if let Ok(endpoint) = std::env::var("MCP_SCREENSHOT_S3_ENDPOINT") {
+            if !endpoint.is_empty() {
+                config.screenshot_s3_endpoint = Some(endpoint);
+            }
+        }
+
+        if let Ok(region) = std::env::var("MCP_SCREENSHOT_S3_REGION") {
+            if !region.is_empty() {
+                config.screenshot_s3_region = region;
+            }
+        }
+
+        if let Ok(prefix) = std::env::var("MCP_SCREENSHOT_S3_PREFIX") {
+            config.screenshot_s3_prefix = prefix;
+        }
+
+        if let Ok(path) = std::env::var("MCP_CREDENTIALS_FILE") {
+            config.credentials_file = Some(PathBuf::from(path));
+            // Page scripts could read the credentials out of the sign-in form;
+            // an explicit MCP_DISABLED_TOOLS, even an empty one, decides instead
+            if std::env::var_os("MCP_DISABLED_TOOLS").is_none() {
+                config
+                    .disabled_tools
+                    .insert(tool_names::EVALUATE_JAVASCRIPT.to_string());
+            }
+        }
+
+        if let Ok(respect) = std::env::var("MCP_RESPECT_ROBOTS_TXT") {
+            config.respect_robots_txt = match respect.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_RESPECT_ROBOTS_TXT '{}', using default false",
+                        respect
+                    );
+                    false
+                }
+            };
+        }
+
+        if let Ok(delay_str) = std::env::var("MCP_POLITENESS_DELAY") {
+            config.politeness_delay = parse_duration(&delay_str).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Invalid MCP_POLITENESS_DELAY '{}': {}, using default 0",
+                    delay_str,
+                    e
+                );
+                std::time::Duration::ZERO
+            });
+        }
+
+        if let Ok(allow) = std::env::var("MCP_CHECK_LINKS_ALLOW_PRIVATE") {
+            config.check_links_allow_private = match allow.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_CHECK_LINKS_ALLOW_PRIVATE '{}', using default false",
+                        allow
+                    );
+                    false
+                }
+            };
+        }
+
+        if let Ok(max) = std::env::var("MCP_MAX_NAVIGATIONS_PER_DOMAIN") {
+            config.max_navigations_per_domain = match max.parse() {
+                Ok(m) => m,
+                Err(e) => {
+                    tracing::warn!(
+                        "Invalid MCP_MAX_NAVIGATIONS_PER_DOMAIN '{}': {}, using default 0",
+                        max,
+                        e
+                    );
+                    0
+                }
+            };
+        }
+
+        if let Ok(detect) = std::env::var("MCP_DETECT_NAVIGATION_LOOPS") {
+            config.detect_navigation_loops = match detect.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_DETECT_NAVIGATION_LOOPS '{}', using default true",
+                        detect
+                    );
+                    true
+                }
+            };
+        }
+
+        if let Ok(delta) = std::env::var("MCP_STATE_DELTA") {
+            config.state_delta = match delta.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!("Invalid MCP_STATE_DELTA '{}', using default false", delta);
+                    false
+                }
+            };
+        }
+
+        if let Ok(detect) = std::env::var("MCP_DETECT_LOGIN") {
+            config.detect_login = match detect.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!("Invalid MCP_DETECT_LOGIN '{}', using default true", detect);
+                    true
+                }
+            };
+        }
+
+        if let Ok(restore) = std::env::var("MCP_RESTORE_SCROLL") {
+            config.restore_scroll = match restore.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_RESTORE_SCROLL '{}', using default true",
+                        restore
+                    );
+                    true
+                }
+            };
+        }
+
+        if let Ok(js_click) = std::env::var("MCP_CDP_JS_CLICK") {
+            config.cdp_js_click = match js_click.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_CDP_JS_CLICK '{}', using default false",
+                        js_click
+                    );
+                    false
+                }
+            };
+        }
+
+        if let Ok(timeout_str) = std::env::var("MCP_CLICK_STABILITY_TIMEOUT") {
+            config.click_stability_timeout = parse_duration(&timeout_str).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Invalid MCP_CLICK_STABILITY_TIMEOUT '{}': {}, using default 0",
+                    timeout_str,
+                    e
+                );
+                std::time::Duration::ZERO
+            });
+        }
+
+        if let Ok(path) = std::env::var("MCP_RECIPES_DIR") {
+            config.recipes_dir = Some(PathBuf::from(path));
+        }
+
+        if let Ok(path) = std::env::var("MCP_WORKSPACES_DIR") {
+            config.workspaces_dir = Some(PathBuf::from(path));
+        }
+
+        if let Ok(path) = std::env::var("MCP_BASELINES_DIR") {
+            config.baselines_dir = Some(PathBuf::from(path));
+        }
+
+        if let Ok(types) = std::env::var("MCP_DOWNLOAD_ALLOWED_TYPES") {
+            config.download_allowed_types = types
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+
+        if let Ok(mb) = std::env::var("MCP_DOWNLOAD_MAX_MB") {
+            config.download_max_mb = match mb.parse() {
+                Ok(m) => m,
+                Err(e) => {
+                    tracing::warn!(
+                        "Invalid MCP_DOWNLOAD_MAX_MB '{}': {}, using default 100",
+                        mb,
+                        e
+                    );
+                    100
+                }
+            };
+        }
+
+        if let Ok(allow) = std::env::var("MCP_DOWNLOAD_ALLOW_EXECUTABLES") {
+            config.download_allow_executables = match allow.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_DOWNLOAD_ALLOW_EXECUTABLES '{}', using default false",
+                        allow
+                    );
+                    false
+                }
+            };
+        }
+
+        if let Ok(path) = std::env::var("MCP_RECORD_CASSETTE") {
+            if !path.is_empty() {
+                config.record_cassette = Some(PathBuf::from(path));
+            }
+        }
+
+        if let Ok(path) = std::env::var("MCP_REPLAY_CASSETTE") {
+            if !path.is_empty() {
+                config.replay_cassette = Some(PathBuf::from(path));
+            }
+        }
+
+        if let Ok(url) = std::env::var("MCP_APPROVAL_WEBHOOK_URL") {
+            if !url.is_empty() {
+                config.approval_webhook_url = Some(url);
+            }
+        }
+
+        if let Ok(token) = std::env::var("MCP_APPROVAL_WEBHOOK_TOKEN") {
+            if !token.is_empty() {
+                config.approval_webhook_token = Some(token);
+            }
+        }
+
+        if let Ok(rules) = std::env::var("MCP_APPROVAL_RULES") {
+            config.approval_rules = approval::parse_rules(&rules);
+        }
+
+        if let Ok(timeout) = std::env::var("MCP_APPROVAL_TIMEOUT") {
+            config.approval_timeout = parse_duration(&timeout).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Invalid MCP_APPROVAL_TIMEOUT '{}': {}, using default 2m",
+                    timeout,
+                    e
+                );
+                std::time::Duration::from_secs(120)
+            });
+        }
+
+        if let Ok(policy) = std::env::var("MCP_APPROVAL_ON_TIMEOUT") {
+            config.approval_on_timeout = match policy.to_lowercase().as_str() {
+                "deny" => TimeoutPolicy::Deny,
+                "allow" => TimeoutPolicy::Allow,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_APPROVAL_ON_TIMEOUT '{}', using default deny",
+                        policy
+                    );
+                    TimeoutPolicy::Deny
+                }
+            };
+        }
+
+        if let Ok(path) = std::env::var("MCP_SCRATCH_DIR") {
+            config.scratch_dir = Some(PathBuf::from(path));
+        }
+
+        if let Ok(paths) = std::env::var("MCP_ALLOWED_PATHS") {
+            config.allowed_paths = std::env::split_paths(&paths)
+                .filter(|p| !p.as_os_str().is_empty())
+                .collect();
+        }
+
+        if let Ok(mb) = std::env::var("MCP_MAX_FILE_MB") {
+            config.max_file_mb = match mb.parse() {
+                Ok(m) => m,
+                Err(e) => {
+                    tracing::warn!("Invalid MCP_MAX_FILE_MB '{}': {}, using default 0", mb, e);
+                    0
+                }
+            };
+        }
+
+        if let Ok(extensions) = std::env::var("MCP_ALLOWED_FILE_EXTENSIONS") {
+            config.allowed_file_extensions = extensions
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+
+        if let Ok(extensions) = std::env::var("MCP_BLOCKED_FILE_EXTENSIONS") {
+            config.blocked_file_extensions = extensions
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+
+        if let Ok(path) = std::env::var("MCP_AXE_CORE_PATH") {
+            config.axe_core_path = Some(PathBuf::from(path));
+        }
+
+        if let Ok(policy) = std::env::var("MCP_COORDINATE_POLICY") {
+            config.coordinate_policy = match policy.to_lowercase().as_str() {
+                "reject" => CoordinatePolicy::Reject,
+                "clamp" => CoordinatePolicy::Clamp,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_COORDINATE_POLICY '{}', using default reject",
+                        policy
+                    );
+                    CoordinatePolicy::Reject
+                }
+            };
+        }
+
+        if let Ok(modifier) = std::env::var("MCP_PRIMARY_MODIFIER") {
+            config.primary_modifier = match modifier.to_lowercase().as_str() {
+                "auto" => PrimaryModifier::Auto,
+                "control" | "ctrl" => PrimaryModifier::Control,
+                "meta" | "command" | "cmd" => PrimaryModifier::Meta,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_PRIMARY_MODIFIER '{}', using default auto",
+                        modifier
+                    );
+                    PrimaryModifier::Auto
+                }
+            };
+        }
+
+        if let Ok(capture) = std::env::var("MCP_CAPTURE_NOTIFICATIONS") {
+            config.capture_notifications = match capture.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_CAPTURE_NOTIFICATIONS '{}', using default true",
+                        capture
+                    );
+                    true
+                }
+            };
+        }
+
+        if let Ok(in_response) = std::env::var("MCP_NOTIFICATIONS_IN_RESPONSE") {
+            config.notifications_in_response = match in_response.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_NOTIFICATIONS_IN_RESPONSE '{}', using default false",
+                        in_response
+                    );
+                    false
+                }
+            };
+        }
+
+        if let Ok(mute) = std::env::var("MCP_MUTE_AUDIO") {
+            config.mute_audio = match mute.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!("Invalid MCP_MUTE_AUDIO '{}', using default true", mute);
+                    true
+                }
+            };
+        }
+
+        if let Ok(retries) = std::env::var("MCP_BLANK_SCREENSHOT_RETRIES") {
+            config.blank_screenshot_retries = match retries.parse() {
+                Ok(r) => r,
+                Err(e) => {
+                    tracing::warn!(
+                        "Invalid MCP_BLANK_SCREENSHOT_RETRIES '{}': {}, using default 2",
+                        retries,
+                        e
+                    );
+                    2
+                }
+            };
+        }
+
+        if let Ok(fit) = std::env::var("MCP_ZOOM_TO_FIT") {
+            config.zoom_to_fit = match fit.to_lowercase().as_str() {
+                "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    tracing::warn!("Invalid MCP_ZOOM_TO_FIT '{}', using default false", fit);
+                    false
+                }
+            };
+        }
+
+        if let Ok(min) = std::env::var("MCP_ZOOM_TO_FIT_MIN") {
+            config.zoom_to_fit_min = match min.trim().trim_end_matches('%').parse::<u32>() {
+                Ok(percent) if (zoom_to_fit::MIN_ZOOM_PERCENT..=100).contains(&percent) => percent,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_ZOOM_TO_FIT_MIN '{}' (expected {} to 100), using default 50",
+                        min,
+                        zoom_to_fit::MIN_ZOOM_PERCENT
+                    );
+                    50
+                }
+            };
+        }
+
+        if let Ok(max) = std::env::var("MCP_MAX_SCREENSHOT_DIMENSION") {
+            config.max_screenshot_dimension = match max.trim().parse::<u32>() {
+                Ok(0) => None,
+                Ok(max) if max >= screenshot_scale::MIN_DIMENSION => Some(max),
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_MAX_SCREENSHOT_DIMENSION '{}' (expected 0 or at least {}), using full size",
+                        max,
+                        screenshot_scale::MIN_DIMENSION
+                    );
+                    None
+                }
+            };
+        }
+
+        if let Ok(policy) = std::env::var("MCP_SCREENSHOT_POLICY") {
+            config.screenshot_policy = match policy.to_lowercase().replace('-', "_").as_str() {
+                "always" => ScreenshotPolicy::Always,
+                "on_change" => ScreenshotPolicy::OnChange,
+                "never" => ScreenshotPolicy::Never,
+                "on_demand" => ScreenshotPolicy::OnDemand,
+                _ => {
+                    tracing::warn!(
+                        "Invalid MCP_SCREENSHOT_POLICY '{}', using default always",
+                        policy
+                    );
+                    ScreenshotPolicy::Always
+                }
+            };
+        }
+
+        if let Ok(languages) = std::env::var("MCP_OCR_LANGUAGES") {
+            let languages: Vec<String> = languages
+                .split([',', '+'])
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+            if !languages.is_empty() {
+                config.ocr_languages = languages;
+            }
+        }
+
+        if let Ok(path) = std::env::var("MCP_TESSERACT_PATH") {
+            config.tesseract_path = PathBuf::from(path);
+        }
+
+        Ok(config)
+    }
+
+    /// Check if a tool is disabled.
+    pub fn is_tool_disabled(&self, tool_name: &str) -> bool {
+        self.disabled_tools.contains(tool_name)
+    }
+}
+
+/// All available tool names for reference.
+pub mod tool_names {
+    pub const CLICK_AT: &str = "click_at";
     pub const HOVER_AT: &str = "hover_at";
+    pub const MOVE_MOUSE_AWAY: &str = "move_mouse_away";
     pub const TYPE_TEXT_AT: &str = "type_text_at";
+    pub const CLICK_ELEMENT: &str = "click_element";
+    pub const HOVER_ELEMENT: &str = "hover_element";
+    pub const TYPE_IN_ELEMENT: &str = "type_in_element";
+    pub const OPEN_MENU_PATH: &str = "open_menu_path";
     pub const SCROLL_DOCUMENT: &str = "scroll_document";
     pub const SCROLL_AT: &str = "scroll_at";
+    pub const SCROLL_TO_ELEMENT: &str = "scroll_to_element";
+    pub const SELECT_TEXT: &str = "select_text";
+    pub const SELECT_ALL_IN: &str = "select_all_in";
+    pub const SET_COLOR: &str = "set_color";
+    pub const SET_RANGE: &str = "set_range";
+    pub const HIGHLIGHT_ELEMENT: &str = "highlight_element";
+    pub const REMEMBER_ELEMENT: &str = "remember_element";
+    pub const CLICK_NAMED: &str = "click_named";
     pub const WAIT_5_SECONDS: &str = "wait_5_seconds";
     pub const GO_BACK: &str = "go_back";
     pub const GO_FORWARD: &str = "go_forward";
     pub const SEARCH: &str = "search";
     pub const NAVIGATE: &str = "navigate";
+    pub const WEB_SEARCH: &str = "web_search";
     pub const KEY_COMBINATION: &str = "key_combination";
+    pub const SHORTCUT: &str = "shortcut";
+    pub const PRESS_KEYS_SEQUENCE: &str = "press_keys_sequence";
+    pub const TAB_TO_ELEMENT: &str = "tab_to_element";
     pub const DRAG_AND_DROP: &str = "drag_and_drop";
+    pub const DRAW_PATH: &str = "draw_path";
     pub const CURRENT_STATE: &str = "current_state";
     pub const OPEN_WEB_BROWSER: &str = "open_web_browser";
     // Tab operations
@@ -509,12 +1894,65 @@ pub mod tool_names {
     pub const CLOSE_TAB: &str = "close_tab";
     pub const SWITCH_TAB: &str = "switch_tab";
     pub const LIST_TABS: &str = "list_tabs";
+    pub const LIST_TARGETS: &str = "list_targets";
+    pub const ATTACH_TO_TAB: &str = "attach_to_tab";
+    // Frame operations
+    pub const LIST_FRAMES: &str = "list_frames";
+    pub const SWITCH_FRAME: &str = "switch_frame";
+    // Page inspection
+    pub const DOM_SNAPSHOT: &str = "dom_snapshot";
+    pub const GROUNDING_SNAPSHOT: &str = "grounding_snapshot";
+    pub const GET_PAGE_METADATA: &str = "get_page_metadata";
+    pub const GET_NOTIFICATIONS: &str = "get_notifications";
+    pub const GET_MEDIA: &str = "get_media";
+    pub const EVALUATE_JAVASCRIPT: &str = "evaluate_javascript";
+    pub const CONTROL_MEDIA: &str = "control_media";
+    pub const CAPTURE_VIDEO_FRAME: &str = "capture_video_frame";
+    pub const GET_BROWSER_STATS: &str = "get_browser_stats";
+    pub const GET_ACTION_HISTORY: &str = "get_action_history";
+    pub const AUDIT_ACCESSIBILITY: &str = "audit_accessibility";
+    pub const CHECK_LINKS: &str = "check_links";
+    pub const COMPARE_SCREENSHOT: &str = "compare_screenshot";
+    pub const GET_SCREENSHOT: &str = "get_screenshot";
+    // Credentials
+    pub const LOGIN_WITH_SAVED_CREDENTIALS: &str = "login_with_saved_credentials";
+    pub const COMPLETE_OAUTH_LOGIN: &str = "complete_oauth_login";
+    // Checkpoints
+    pub const SAVE_CHECKPOINT: &str = "save_checkpoint";
+    pub const RESTORE_CHECKPOINT: &str = "restore_checkpoint";
+    // Workspaces
+    pub const SAVE_WORKSPACE: &str = "save_workspace";
+    pub const LOAD_WORKSPACE: &str = "load_workspace";
+    pub const LIST_WORKSPACE_FILES: &str = "list_workspace_files";
+    // Browser instances
+    pub const CREATE_BROWSER: &str = "create_browser";
+    pub const DESTROY_BROWSER: &str = "destroy_browser";
+    // Emulation
+    pub const SET_EMULATED_MEDIA: &str = "set_emulated_media";
+    pub const SET_VISION_DEFICIENCY: &str = "set_vision_deficiency";
+    // Page watches
+    pub const WATCH_PAGE: &str = "watch_page";
+    pub const UNWATCH_PAGE: &str = "unwatch_page";
+    // Page events
+    pub const SUBSCRIBE_EVENTS: &str = "subscribe_events";
+    pub const POLL_EVENTS: &str = "poll_events";
+    // Text recognition
+    pub const OCR_SCREENSHOT: &str = "ocr_screenshot";
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_normalize_base_path() {
+        assert_eq!(normalize_base_path(""), "");
+        assert_eq!(normalize_base_path("/"), "");
+        assert_eq!(normalize_base_path("agents/browser"), "/agents/browser");
+        assert_eq!(normalize_base_path("/agents/browser/"), "/agents/browser");
+        assert_eq!(normalize_base_path(" /mcp-proxy "), "/mcp-proxy");
+    }
+
     #[test]
     fn test_parse_duration_seconds() {
         assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
@@ -558,4 +1996,17 @@ mod tests {
         // Very large number should return an overflow error
         assert!(parse_duration("99999999999999999999999h").is_err());
     }
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let config = Config {
+            http_auth_tokens: vec!["s3cret-token".to_string()],
+            approval_webhook_token: Some("webhook-s3cret".to_string()),
+            ..Config::default()
+        };
+        let debug = format!("{:?}", config);
+        assert!(!debug.contains("s3cret-token"));
+        assert!(debug.contains("http_auth_tokens: [[redacted]]"));
+        assert!(!debug.contains("webhook-s3cret"));
+    }
 }
@@ -0,0 +1,164 @@
+//! Preview strips of the content just above and below the viewport.
+//!
+//! A screenshot shows nothing of what scrolling would reveal, so an agent
+//! looking for a button either scrolls blindly or gives up too early. With
+//! `context_strips`, `current_state` adds two thin, downscaled captures of
+//! the page right above and right below the viewport, and how much content
+//! lies beyond each edge. Both backends capture them with the DevTools
+//! protocol, so they need a Chromium-based browser.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+/// DevTools method reporting the scroll position and content size.
+pub const LAYOUT_METRICS_METHOD: &str = "Page.getLayoutMetrics";
+
+/// DevTools method capturing a screenshot.
+pub const CAPTURE_SCREENSHOT_METHOD: &str = "Page.captureScreenshot";
+
+/// Height of page content covered by a strip, in CSS pixels.
+pub const STRIP_HEIGHT: f64 = 160.0;
+
+/// Scale a strip is captured at.
+pub const STRIP_SCALE: f64 = 0.5;
+
+/// A region of the page, in CSS pixels from the top left of the document.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clip {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Where the strips of a page are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StripPlan {
+    pub above: Option<Clip>,
+    pub below: Option<Clip>,
+    /// Content above the viewport, in CSS pixels.
+    pub hidden_above: u32,
+    /// Content below the viewport, in CSS pixels.
+    pub hidden_below: u32,
+}
+
+/// A captured strip.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextStrip {
+    /// PNG image, base64 encoded.
+    pub screenshot: String,
+    /// Height of page content the strip covers, in CSS pixels.
+    pub covers: u32,
+    /// All content beyond the viewport on this side, in CSS pixels.
+    pub hidden: u32,
+}
+
+/// The strips above and below the viewport; a strip is missing when the
+/// viewport is at that end of the page.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContextStrips {
+    pub above: Option<ContextStrip>,
+    pub below: Option<ContextStrip>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LayoutMetrics {
+    css_layout_viewport: LayoutViewport,
+    css_content_size: ContentSize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LayoutViewport {
+    page_x: f64,
+    page_y: f64,
+    client_width: f64,
+    client_height: f64,
+}
+
+#[derive(Deserialize)]
+struct ContentSize {
+    height: f64,
+}
+
+/// Plan the strips from the reply of `Page.getLayoutMetrics`.
+pub fn plan(layout_metrics: &serde_json::Value) -> anyhow::Result<StripPlan> {
+    let metrics: LayoutMetrics =
+        serde_json::from_value(layout_metrics.clone()).context("Unexpected layout metrics")?;
+    let viewport = &metrics.css_layout_viewport;
+    let top = viewport.page_y.max(0.0);
+    let bottom = top + viewport.client_height;
+    let hidden_below = (metrics.css_content_size.height - bottom).max(0.0);
+    let strip = |y: f64, height: f64| {
+        (height >= 1.0).then_some(Clip {
+            x: viewport.page_x,
+            y,
+            width: viewport.client_width,
+            height,
+        })
+    };
+    Ok(StripPlan {
+        above: strip((top - STRIP_HEIGHT).max(0.0), top.min(STRIP_HEIGHT)),
+        below: strip(bottom, hidden_below.min(STRIP_HEIGHT)),
+        hidden_above: top.round() as u32,
+        hidden_below: hidden_below.round() as u32,
+    })
+}
+
+/// Parameters of `Page.captureScreenshot` for a strip.
+pub fn capture_params(clip: &Clip) -> serde_json::Value {
+    serde_json::json!({
+        "format": "png",
+        "clip": {
+            "x": clip.x,
+            "y": clip.y,
+            "width": clip.width,
+            "height": clip.height,
+            "scale": STRIP_SCALE,
+        },
+        "captureBeyondViewport": true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(scroll_y: f64, content_height: f64) -> serde_json::Value {
+        serde_json::json!({
+            "cssLayoutViewport": {
+                "pageX": 0, "pageY": scroll_y, "clientWidth": 1280, "clientHeight": 720
+            },
+            "cssContentSize": { "x": 0, "y": 0, "width": 1280, "height": content_height },
+            "cssVisualViewport": {}
+        })
+    }
+
+    #[test]
+    fn test_plan() {
+        let top = plan(&metrics(0.0, 3000.0)).unwrap();
+        assert_eq!(top.above, None);
+        assert_eq!(
+            top.below,
+            Some(Clip {
+                x: 0.0,
+                y: 720.0,
+                width: 1280.0,
+                height: STRIP_HEIGHT
+            })
+        );
+        assert_eq!(top.hidden_below, 2280);
+
+        let near_bottom = plan(&metrics(100.0, 900.0)).unwrap();
+        assert_eq!(
+            near_bottom.above.map(|clip| (clip.y, clip.height)),
+            Some((0.0, 100.0))
+        );
+        assert_eq!(near_bottom.below.map(|clip| clip.height), Some(80.0));
+        assert_eq!(near_bottom.hidden_above, 100);
+
+        assert_eq!(plan(&metrics(0.0, 720.0)).unwrap().below, None);
+        assert!(plan(&serde_json::json!({})).is_err());
+    }
+}
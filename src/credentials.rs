@@ -0,0 +1,465 @@
+//! Saved site credentials.
+//!
+//! Operators register credentials for sites in a TOML file (`MCP_CREDENTIALS_FILE`),
+//! and the `login_with_saved_credentials` tool fills them into the sign-in form by
+//! site name. The secrets are resolved only when a login runs and go straight into
+//! the page: they never appear in tool responses, logs or the action history.
+//!
+//! ```toml
+//! [sites.github]
+//! url = "https://github.com/login"
+//! username = "octocat"
+//! password_env = "GITHUB_PASSWORD"
+//!
+//! [sites.intranet]
+//! domains = ["intranet.example.com", "sso.example.com"]
+//! username_env = "INTRANET_USER"
+//! password_command = "secret-tool lookup service intranet"
+//! ```
+//!
+//! A password is read from `password`, the environment variable named by
+//! `password_env`, or the output of `password_command`, which reaches an OS
+//! keyring or password manager through its CLI.
+//!
+//! Credentials are bound to the origins of the site: they are only filled on
+//! pages whose host is one of the site's `domains` (default: the host of `url`),
+//! only over HTTPS unless the site sets `allow_http = true`, and only into forms that
+//! submit to one of those origins. A lookalike domain, a downgraded connection
+//! or a form injected to post elsewhere never receives them.
+
+use crate::recipes::RecipeSelectors;
+use anyhow::Context;
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Credentials registered for one site.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SiteCredentials {
+    /// Sign-in page, opened when a login is requested on another site.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Hosts the credentials may be filled on; subdomains are included.
+    #[serde(default)]
+    pub domains: Vec<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    username_env: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    password_env: Option<String>,
+    #[serde(default)]
+    password_command: Option<String>,
+    /// Whether the credentials may be filled on plain HTTP pages.
+    #[serde(default)]
+    allow_http: bool,
+}
+
+// Written by hand so that a password in the file never reaches a log line
+impl std::fmt::Debug for SiteCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SiteCredentials")
+            .field("url", &self.url)
+            .field("domains", &self.domains)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SiteCredentials {
+    /// Resolve the username, if one is registered.
+    pub fn username(&self) -> anyhow::Result<Option<String>> {
+        match (&self.username, &self.username_env) {
+            (Some(username), _) => Ok(Some(username.clone())),
+            (None, Some(var)) => std::env::var(var)
+                .map(Some)
+                .with_context(|| format!("Environment variable {} is not set", var)),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Resolve the password, running `password_command` when that is its source.
+    pub async fn password(&self) -> anyhow::Result<String> {
+        if let Some(ref password) = self.password {
+            return Ok(password.clone());
+        }
+        if let Some(ref var) = self.password_env {
+            return std::env::var(var)
+                .with_context(|| format!("Environment variable {} is not set", var));
+        }
+        let command = self
+            .password_command
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("No password source"))?;
+        let output = shell_command(command)
+            .stdin(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .output()
+            .await
+            .context("Failed to run password_command")?;
+        if !output.status.success() {
+            // The command's output may hold the secret, so only the status is reported
+            return Err(anyhow::anyhow!(
+                "password_command failed with {}",
+                output.status
+            ));
+        }
+        let stdout = String::from_utf8(output.stdout)
+            .map_err(|_| anyhow::anyhow!("password_command printed invalid UTF-8"))?;
+        Ok(stdout.lines().next().unwrap_or_default().to_string())
+    }
+
+    /// Whether the credentials may be filled on a page at this URL: one of the
+    /// site's hosts, over HTTPS unless the site allows HTTP.
+    pub fn allows_url(&self, url: &str) -> bool {
+        let secure = url.starts_with("https://");
+        (secure || self.allow_http) && host_of(url).is_some_and(|host| self.allows_host(&host))
+    }
+
+    /// Whether the credentials may be filled on a page with this host.
+    pub fn allows_host(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        self.domains.iter().any(|domain| {
+            let domain = domain.to_ascii_lowercase();
+            host == domain || host.ends_with(&format!(".{}", domain))
+        })
+    }
+
+    /// Check the entry and derive its domains from `url` when none are listed.
+    fn validate(&mut self, site: &str) -> anyhow::Result<()> {
+        if self.domains.is_empty() {
+            let host = self
+                .url
+                .as_deref()
+                .and_then(host_of)
+                .ok_or_else(|| anyhow::anyhow!("Site '{}' needs `domains` or a `url`", site))?;
+            self.domains.push(host);
+        }
+        let sources = [&self.password, &self.password_env, &self.password_command]
+            .iter()
+            .filter(|source| source.is_some())
+            .count();
+        if sources != 1 {
+            return Err(anyhow::anyhow!(
+                "Site '{}' needs exactly one of `password`, `password_env` or `password_command`",
+                site
+            ));
+        }
+        if !self.allow_http
+            && self
+                .url
+                .as_deref()
+                .is_some_and(|url| url.starts_with("http://"))
+        {
+            return Err(anyhow::anyhow!(
+                "Site '{}' has an http:// url; set `allow_http = true` to fill its credentials without HTTPS",
+                site
+            ));
+        }
+        if self.username.is_some() && self.username_env.is_some() {
+            return Err(anyhow::anyhow!(
+                "Site '{}' sets both `username` and `username_env`",
+                site
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Run a command line through the platform shell.
+fn shell_command(command: &str) -> tokio::process::Command {
+    if cfg!(windows) {
+        let mut cmd = tokio::process::Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
+
+/// Host of an absolute http(s) URL.
+pub fn host_of(url: &str) -> Option<String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// The registered credentials, by site name.
+#[derive(Debug, Default, Deserialize)]
+pub struct CredentialStore {
+    #[serde(default)]
+    sites: BTreeMap<String, SiteCredentials>,
+}
+
+impl CredentialStore {
+    /// Parse a credentials file.
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        // The parser's own message quotes the offending line, which may hold a password
+        let mut store: CredentialStore =
+            toml::from_str(content).map_err(|e| anyhow::anyhow!("{}", e.message()))?;
+        for (site, credentials) in &mut store.sites {
+            credentials.validate(site)?;
+        }
+        Ok(store)
+    }
+
+    /// Load a credentials file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read credentials file {:?}", path))?;
+        Self::parse(&content).with_context(|| format!("Invalid credentials file {:?}", path))
+    }
+
+    /// Credentials registered for a site.
+    pub fn get(&self, site: &str) -> anyhow::Result<&SiteCredentials> {
+        self.sites.get(site).ok_or_else(|| {
+            anyhow::anyhow!(
+                "No saved credentials for '{}'. Saved sites: {}",
+                site,
+                self.sites.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        })
+    }
+
+    /// Number of registered sites.
+    pub fn len(&self) -> usize {
+        self.sites.len()
+    }
+}
+
+/// What a fill script did. Never contains the secrets.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CredentialFill {
+    /// Host of the document the form was looked for in.
+    pub host: String,
+    /// Whether the credentials may be used on that host.
+    pub allowed: bool,
+    /// Why nothing was filled on an allowed host, such as a form submitting
+    /// to another origin.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refused: Option<String>,
+    /// Whether a username field was filled.
+    pub username: bool,
+    /// Whether a password field was filled.
+    pub password: bool,
+    /// Whether the form was submitted.
+    pub submitted: bool,
+}
+
+/// JavaScript expression filling the sign-in form of the current document and
+/// optionally submitting it, after checking the document's origin and the
+/// form's target against the site's domains. Evaluates to a [`CredentialFill`].
+///
+/// Pages can tamper with the builtins the script's origin check relies on, so
+/// callers check the URLs of the tab and of the selected frame before running
+/// it, and never send the script elsewhere. Selectors from the site's
+/// recipe take precedence over the script's own form detection.
+pub fn fill_script(
+    credentials: &SiteCredentials,
+    username: Option<&str>,
+    password: &str,
+    submit: bool,
+    selectors: &RecipeSelectors,
+) -> anyhow::Result<String> {
+    let args = serde_json::json!({
+        "domains": credentials.domains,
+        "https_only": !credentials.allow_http,
+        "username": username,
+        "password": password,
+        "submit": submit,
+        "selectors": {
+            "username": selectors.username,
+            "password": selectors.password,
+            "submit": selectors.submit,
+        },
+    });
+    Ok(format!(
+        "{}({})",
+        FILL_FUNCTION,
+        serde_json::to_string(&args)?
+    ))
+}
+
+const FILL_FUNCTION: &str = r#"
+(function(args) {
+    function allowedOrigin(loc) {
+        var host = loc.hostname.toLowerCase();
+        var secure = loc.protocol === 'https:' || (!args.https_only && loc.protocol === 'http:');
+        return secure && args.domains.some(function(domain) {
+            domain = domain.toLowerCase();
+            return host === domain || host.endsWith('.' + domain);
+        });
+    }
+    var host = location.hostname.toLowerCase();
+    var result = { host: host, allowed: allowedOrigin(location), username: false, password: false, submitted: false };
+    if (!result.allowed) {
+        return result;
+    }
+    function usable(el) {
+        var rect = el.getBoundingClientRect();
+        var style = getComputedStyle(el);
+        return rect.width > 0 && rect.height > 0 && style.visibility !== 'hidden' &&
+            !el.disabled && !el.readOnly;
+    }
+    function fill(el, value) {
+        var setter = Object.getOwnPropertyDescriptor(HTMLInputElement.prototype, 'value').set;
+        el.focus();
+        setter.call(el, value);
+        el.dispatchEvent(new Event('input', { bubbles: true }));
+        el.dispatchEvent(new Event('change', { bubbles: true }));
+    }
+    function pick(selector) {
+        var el = selector && document.querySelector(selector);
+        return el && usable(el) ? el : null;
+    }
+    var inputs = Array.prototype.filter.call(document.querySelectorAll('input'), usable);
+    var password = pick(args.selectors.password) || inputs.filter(function(el) {
+        return el.type === 'password' && el.autocomplete !== 'new-password';
+    })[0] || null;
+    var texts = inputs.filter(function(el) {
+        return ['text', 'email', 'tel'].indexOf(el.type) !== -1;
+    });
+    var user = pick(args.selectors.username) || texts.filter(function(el) {
+        return /username|email/.test(el.autocomplete || '');
+    })[0] || texts.filter(function(el) {
+        return /user|login|email|account|identifier/i.test(el.name + ' ' + el.id + ' ' + el.type);
+    })[0] || null;
+    if (!user && password) {
+        var before = texts.filter(function(el) {
+            return el.form === password.form &&
+                (el.compareDocumentPosition(password) & Node.DOCUMENT_POSITION_FOLLOWING);
+        });
+        user = before[before.length - 1] || null;
+    }
+    if (!user && !password && texts.length === 1) {
+        user = texts[0];
+    }
+    // Refuse forms that would send the credentials to another origin
+    var field = password || user;
+    var form = field && field.form;
+    var button = form ? (pick(args.selectors.submit) || form.querySelector(
+        'button[type=submit], input[type=submit], button:not([type])')) : pick(args.selectors.submit);
+    // Attributes rather than properties, which fields named "action" shadow
+    var targets = [form && form.getAttribute('action'),
+        button && button.form === form && button.getAttribute('formaction')];
+    for (var i = 0; i < targets.length; i++) {
+        var target = targets[i] ? new URL(targets[i], document.baseURI) : null;
+        if (target && target.protocol !== 'javascript:' && !allowedOrigin(target)) {
+            result.refused = 'the sign-in form submits to ' + target.origin;
+            return result;
+        }
+    }
+    if (user && args.username !== null) {
+        fill(user, args.username);
+        result.username = true;
+    }
+    if (password) {
+        fill(password, args.password);
+        result.password = true;
+    }
+    field = password || (result.username ? user : null);
+    if (args.submit && field) {
+        form = field.form;
+        // Submit after returning, so a navigation cannot swallow the result
+        setTimeout(function() {
+            if (button) {
+                button.click();
+            } else if (form && form.requestSubmit) {
+                form.requestSubmit();
+            } else {
+                ['keydown', 'keypress', 'keyup'].forEach(function(type) {
+                    field.dispatchEvent(new KeyboardEvent(type, {
+                        key: 'Enter', code: 'Enter', keyCode: 13, which: 13, bubbles: true
+                    }));
+                });
+            }
+        }, 0);
+        result.submitted = true;
+    }
+    return result;
+})"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_credentials() {
+        let store = CredentialStore::parse(
+            r#"
+            [sites.github]
+            url = "https://github.com/login"
+            username = "octocat"
+            password_env = "MCP_TEST_UNSET_PASSWORD"
+
+            [sites.intranet]
+            domains = ["example.com"]
+            password = "hunter2"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(store.len(), 2);
+
+        let github = store.get("github").unwrap();
+        assert_eq!(github.domains, vec!["github.com"]);
+        assert_eq!(github.username().unwrap().as_deref(), Some("octocat"));
+        assert!(github.allows_host("GitHub.com"));
+        assert!(!github.allows_host("github.com.evil.test"));
+        assert!(github.allows_url("https://github.com/session"));
+        assert!(!github.allows_url("http://github.com/login"));
+        assert!(!github.allows_url("https://github.com.evil.test/login"));
+        assert!(!github.allows_url("https://evil.test/?next=https://github.com/"));
+        assert!(!format!("{:?}", store).contains("octocat"));
+
+        let intranet = store.get("intranet").unwrap();
+        assert!(intranet.allows_host("sso.example.com"));
+        assert!(!intranet.allows_host("notexample.com"));
+        assert!(!format!("{:?}", intranet).contains("hunter2"));
+
+        let err = store.get("gitlab").unwrap_err().to_string();
+        assert!(err.contains("github, intranet"));
+    }
+
+    #[test]
+    fn test_invalid_credentials() {
+        // No domain to restrict the credentials to
+        assert!(CredentialStore::parse("[sites.a]\npassword = \"x\"").is_err());
+        // Two password sources
+        assert!(CredentialStore::parse(
+            "[sites.a]\ndomains = [\"a.test\"]\npassword = \"x\"\npassword_env = \"X\""
+        )
+        .is_err());
+        // Sign-in page without HTTPS
+        assert!(CredentialStore::parse(
+            "[sites.a]\nurl = \"http://a.test/login\"\npassword = \"x\""
+        )
+        .is_err());
+        let store = CredentialStore::parse(
+            "[sites.a]\nurl = \"http://a.test/login\"\npassword = \"x\"\nallow_http = true",
+        )
+        .unwrap();
+        assert!(store.get("a").unwrap().allows_url("http://a.test/login"));
+        // Misspelled field
+        assert!(CredentialStore::parse(
+            "[sites.a]\ndomains = [\"a.test\"]\npasword = \"x\"\npassword = \"x\""
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_host_of() {
+        assert_eq!(
+            host_of("https://user@Login.Example.com:8443/path?q").as_deref(),
+            Some("login.example.com")
+        );
+        assert_eq!(host_of("example.com"), None);
+    }
+}
@@ -0,0 +1,538 @@
+//! Compact DOM snapshots built from CDP `DOMSnapshot.captureSnapshot`.
+//!
+//! The raw snapshot is a set of flattened, string-interned arrays covering the
+//! whole document. This module turns it into a small tree containing only the
+//! rendered nodes that carry text or meaning, with bounding boxes in screenshot
+//! coordinates so they can be used directly with the coordinate-based tools.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// CDP method used to capture the snapshot.
+pub const CAPTURE_SNAPSHOT_METHOD: &str = "DOMSnapshot.captureSnapshot";
+
+/// Computed styles requested from the browser, in this order.
+pub const SNAPSHOT_COMPUTED_STYLES: [&str; 3] = ["display", "visibility", "opacity"];
+
+/// Maximum number of nodes included in a snapshot before it is truncated.
+const MAX_SNAPSHOT_NODES: usize = 2000;
+
+/// Maximum length of the text reported for a single node.
+const MAX_NODE_TEXT_LEN: usize = 200;
+
+/// DOM node types as reported by CDP.
+const ELEMENT_NODE: i64 = 1;
+const TEXT_NODE: i64 = 3;
+
+/// Elements whose content is never useful for grounding.
+const SKIPPED_TAGS: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "meta", "link", "title", "base",
+];
+
+/// Elements that are meaningful on their own even without text.
+const INTERACTIVE_TAGS: &[&str] = &[
+    "a", "button", "input", "select", "textarea", "img", "option", "summary", "label", "video",
+    "audio", "canvas", "iframe", "frame", "svg",
+];
+
+/// Attributes kept in the compact tree.
+const KEPT_ATTRIBUTES: &[&str] = &[
+    "id",
+    "name",
+    "type",
+    "href",
+    "src",
+    "alt",
+    "title",
+    "placeholder",
+    "role",
+    "aria-label",
+    "value",
+];
+
+/// A visible node in a compact DOM snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SnapshotNode {
+    /// Lowercase tag name of the element.
+    pub tag: String,
+    /// Bounding box as `[x, y, width, height]` in screenshot coordinates.
+    pub bounds: [i64; 4],
+    /// Text directly contained in this element.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Selected attributes (id, href, aria-label, ...).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+    /// Whether the browser considers the element clickable.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub clickable: bool,
+    /// Whether the element lies inside the current viewport.
+    pub in_viewport: bool,
+    /// Visible child nodes.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SnapshotNode>,
+}
+
+/// A compact DOM snapshot of the current page.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DomSnapshot {
+    /// URL of the main document.
+    pub url: String,
+    /// Top-level visible nodes.
+    pub nodes: Vec<SnapshotNode>,
+    /// Number of nodes included in the snapshot.
+    pub node_count: usize,
+    /// Whether nodes were omitted because the snapshot grew too large.
+    pub truncated: bool,
+}
+
+/// Build the parameters for `DOMSnapshot.captureSnapshot`.
+pub fn capture_snapshot_params() -> Value {
+    serde_json::json!({ "computedStyles": SNAPSHOT_COMPUTED_STYLES })
+}
+
+/// Flattened node and layout data for one document of the raw snapshot.
+struct Document<'a> {
+    strings: &'a [String],
+    parent: Vec<i64>,
+    node_type: Vec<i64>,
+    node_name: Vec<i64>,
+    node_value: Vec<i64>,
+    attributes: Vec<Vec<i64>>,
+    children: HashMap<usize, Vec<usize>>,
+    /// Node index -> (bounds, styles) for every rendered node.
+    layout: HashMap<usize, ([f64; 4], Vec<i64>)>,
+    content_document: HashMap<usize, usize>,
+    input_value: HashMap<usize, i64>,
+    clickable: Vec<usize>,
+    scroll_x: f64,
+    scroll_y: f64,
+}
+
+fn int_array(value: &Value) -> Vec<i64> {
+    value
+        .as_array()
+        .map(|a| a.iter().map(|v| v.as_i64().unwrap_or(-1)).collect())
+        .unwrap_or_default()
+}
+
+/// Parse CDP "rare data" (`{ index: [...], value: [...] }`) into a map.
+fn rare_data(value: &Value) -> HashMap<usize, i64> {
+    let index = int_array(&value["index"]);
+    let values = int_array(&value["value"]);
+    index
+        .into_iter()
+        .zip(values)
+        .filter_map(|(i, v)| usize::try_from(i).ok().map(|i| (i, v)))
+        .collect()
+}
+
+impl<'a> Document<'a> {
+    fn parse(raw: &'a Value, strings: &'a [String]) -> Self {
+        let nodes = &raw["nodes"];
+        let parent = int_array(&nodes["parentIndex"]);
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, p) in parent.iter().enumerate() {
+            if let Ok(p) = usize::try_from(*p) {
+                children.entry(p).or_default().push(i);
+            }
+        }
+
+        let attributes = nodes["attributes"]
+            .as_array()
+            .map(|a| a.iter().map(int_array).collect())
+            .unwrap_or_default();
+
+        let layout_nodes = int_array(&raw["layout"]["nodeIndex"]);
+        let bounds = raw["layout"]["bounds"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let styles = raw["layout"]["styles"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let mut layout = HashMap::new();
+        for (i, node) in layout_nodes.iter().enumerate() {
+            let Ok(node) = usize::try_from(*node) else {
+                continue;
+            };
+            let mut rect = [0.0; 4];
+            if let Some(b) = bounds.get(i).and_then(Value::as_array) {
+                for (slot, v) in rect.iter_mut().zip(b) {
+                    *slot = v.as_f64().unwrap_or(0.0);
+                }
+            }
+            let style = styles.get(i).map(int_array).unwrap_or_default();
+            // Only keep the first layout object of a node (its principal box)
+            layout.entry(node).or_insert((rect, style));
+        }
+
+        let content_document = rare_data(&nodes["contentDocumentIndex"])
+            .into_iter()
+            .filter_map(|(i, d)| usize::try_from(d).ok().map(|d| (i, d)))
+            .collect();
+
+        Self {
+            strings,
+            parent,
+            node_type: int_array(&nodes["nodeType"]),
+            node_name: int_array(&nodes["nodeName"]),
+            node_value: int_array(&nodes["nodeValue"]),
+            attributes,
+            children,
+            layout,
+            content_document,
+            input_value: rare_data(&nodes["inputValue"]),
+            clickable: int_array(&nodes["isClickable"]["index"])
+                .into_iter()
+                .filter_map(|i| usize::try_from(i).ok())
+                .collect(),
+            scroll_x: raw["scrollOffsetX"].as_f64().unwrap_or(0.0),
+            scroll_y: raw["scrollOffsetY"].as_f64().unwrap_or(0.0),
+        }
+    }
+
+    fn string(&self, index: i64) -> &str {
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| self.strings.get(i))
+            .map(String::as_str)
+            .unwrap_or("")
+    }
+
+    fn roots(&self) -> Vec<usize> {
+        (0..self.parent.len())
+            .filter(|i| self.parent[*i] < 0)
+            .collect()
+    }
+
+    /// Whether a rendered node is actually visible according to its computed styles.
+    fn is_visible(&self, node: usize) -> bool {
+        let Some((rect, styles)) = self.layout.get(&node) else {
+            return false;
+        };
+        if rect[2] <= 0.0 || rect[3] <= 0.0 {
+            return false;
+        }
+        let style = |name: &str| {
+            SNAPSHOT_COMPUTED_STYLES
+                .iter()
+                .position(|s| *s == name)
+                .and_then(|i| styles.get(i))
+                .map(|v| self.string(*v))
+                .unwrap_or("")
+        };
+        style("display") != "none"
+            && style("visibility") != "hidden"
+            && style("opacity").parse::<f64>().map_or(true, |o| o > 0.0)
+    }
+}
+
+/// Collapse whitespace and cap the length of node text.
+fn normalize_text(text: &str) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() > MAX_NODE_TEXT_LEN {
+        let truncated: String = collapsed.chars().take(MAX_NODE_TEXT_LEN).collect();
+        format!("{}…", truncated)
+    } else {
+        collapsed
+    }
+}
+
+struct Builder<'a> {
+    documents: Vec<Document<'a>>,
+    viewport: (f64, f64),
+    node_count: usize,
+    truncated: bool,
+}
+
+impl Builder<'_> {
+    /// Build the visible nodes for `node` and its subtree.
+    ///
+    /// Returns a list because invisible or purely structural elements are
+    /// replaced by their visible descendants.
+    fn build(&mut self, doc: usize, node: usize, offset: (f64, f64)) -> Vec<SnapshotNode> {
+        if self.node_count >= MAX_SNAPSHOT_NODES {
+            self.truncated = true;
+            return Vec::new();
+        }
+
+        let d = &self.documents[doc];
+        let node_type = d.node_type.get(node).copied().unwrap_or(0);
+        let tag = d
+            .string(d.node_name.get(node).copied().unwrap_or(-1))
+            .to_lowercase();
+
+        if node_type != ELEMENT_NODE {
+            // Documents and other containers just pass through to their children
+            return self.build_children(doc, node, offset);
+        }
+        if SKIPPED_TAGS.contains(&tag.as_str()) {
+            return Vec::new();
+        }
+
+        let visible = d.is_visible(node);
+
+        // Text directly inside this element comes from its rendered text node children
+        let mut text_parts = Vec::new();
+        if let Some(children) = d.children.get(&node) {
+            for child in children {
+                if d.node_type.get(*child).copied() == Some(TEXT_NODE)
+                    && d.layout.contains_key(child)
+                {
+                    let text = d.string(d.node_value.get(*child).copied().unwrap_or(-1));
+                    if !text.trim().is_empty() {
+                        text_parts.push(text.to_string());
+                    }
+                }
+            }
+        }
+
+        let mut attributes = BTreeMap::new();
+        if let Some(attrs) = d.attributes.get(node) {
+            for pair in attrs.chunks(2) {
+                if let [name, value] = pair {
+                    let name = d.string(*name).to_lowercase();
+                    let value = d.string(*value);
+                    if KEPT_ATTRIBUTES.contains(&name.as_str()) && !value.is_empty() {
+                        attributes.insert(name, normalize_text(value));
+                    }
+                }
+            }
+        }
+        // Password fields would put the password into the snapshot
+        let password = tag == "input"
+            && attributes
+                .get("type")
+                .is_some_and(|t| t.eq_ignore_ascii_case("password"));
+        if password {
+            attributes.remove("value");
+        } else if let Some(value) = d.input_value.get(&node) {
+            let value = d.string(*value);
+            if !value.is_empty() {
+                attributes.insert("value".to_string(), normalize_text(value));
+            }
+        }
+        let clickable = d.clickable.contains(&node);
+        let rect = d.layout.get(&node).map(|(r, _)| *r).unwrap_or_default();
+        let x = rect[0] - d.scroll_x + offset.0;
+        let y = rect[1] - d.scroll_y + offset.1;
+        let content_document = d.content_document.get(&node).copied();
+
+        let children = match content_document {
+            // Frame contents are laid out relative to the frame element
+            Some(child_doc) if visible => {
+                let roots = self.documents[child_doc].roots();
+                roots
+                    .into_iter()
+                    .flat_map(|root| self.build(child_doc, root, (x, y)))
+                    .collect::<Vec<_>>()
+            }
+            _ => self.build_children(doc, node, offset),
+        };
+
+        if !visible {
+            return children;
+        }
+
+        let text = if text_parts.is_empty() {
+            None
+        } else {
+            Some(normalize_text(&text_parts.join(" ")))
+        };
+        let meaningful = text.is_some()
+            || !attributes.is_empty()
+            || clickable
+            || INTERACTIVE_TAGS.contains(&tag.as_str());
+
+        if !meaningful {
+            // Structural wrappers are flattened away
+            if children.len() <= 1 {
+                return children;
+            }
+        }
+
+        let in_viewport =
+            x < self.viewport.0 && y < self.viewport.1 && x + rect[2] > 0.0 && y + rect[3] > 0.0;
+
+        self.node_count += 1;
+        vec![SnapshotNode {
+            tag,
+            bounds: [
+                x.round() as i64,
+                y.round() as i64,
+                rect[2].round() as i64,
+                rect[3].round() as i64,
+            ],
+            text,
+            attributes,
+            clickable,
+            in_viewport,
+            children,
+        }]
+    }
+
+    fn build_children(&mut self, doc: usize, node: usize, offset: (f64, f64)) -> Vec<SnapshotNode> {
+        let children = self.documents[doc]
+            .children
+            .get(&node)
+            .cloned()
+            .unwrap_or_default();
+        children
+            .into_iter()
+            .flat_map(|child| self.build(doc, child, offset))
+            .collect()
+    }
+}
+
+/// Convert a raw `DOMSnapshot.captureSnapshot` response into a compact tree.
+///
+/// `viewport` is the size of the visible area, used to flag nodes that are on screen.
+pub fn build_snapshot(raw: &Value, viewport: (u32, u32)) -> Result<DomSnapshot> {
+    let strings: Vec<String> = serde_json::from_value(raw["strings"].clone())
+        .map_err(|e| anyhow::anyhow!("Invalid snapshot strings table: {}", e))?;
+    let raw_documents = raw["documents"]
+        .as_array()
+        .filter(|d| !d.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("Snapshot contains no documents"))?;
+
+    let documents = raw_documents
+        .iter()
+        .map(|d| Document::parse(d, &strings))
+        .collect();
+    let mut builder = Builder {
+        documents,
+        viewport: (viewport.0 as f64, viewport.1 as f64),
+        node_count: 0,
+        truncated: false,
+    };
+
+    // The first document is the main frame; frame documents are reached through their owners
+    let roots = builder.documents[0].roots();
+    let nodes = roots
+        .into_iter()
+        .flat_map(|root| builder.build(0, root, (0.0, 0.0)))
+        .collect();
+
+    let url = builder.documents[0]
+        .string(raw_documents[0]["documentURL"].as_i64().unwrap_or(-1))
+        .to_string();
+
+    Ok(DomSnapshot {
+        url,
+        nodes,
+        node_count: builder.node_count,
+        truncated: builder.truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Snapshot of `<html><body><div><a href="/x">Link</a></div><p style="display:none">Hidden</p></body></html>`.
+    fn sample_snapshot() -> Value {
+        serde_json::json!({
+            "strings": [
+                "#document", "HTML", "BODY", "DIV", "A", "href", "/x", "#text", "Link", "P",
+                "Hidden", "block", "visible", "1", "inline", "none",
+                "https://example.com/"
+            ],
+            "documents": [{
+                "documentURL": 16,
+                "scrollOffsetX": 0,
+                "scrollOffsetY": 100,
+                "nodes": {
+                    "parentIndex": [-1, 0, 1, 2, 3, 4, 2, 6],
+                    "nodeType": [9, 1, 1, 1, 1, 3, 1, 3],
+                    "nodeName": [0, 1, 2, 3, 4, 7, 9, 7],
+                    "nodeValue": [-1, -1, -1, -1, -1, 8, -1, 10],
+                    "attributes": [[], [], [], [], [5, 6], [], [], []],
+                    "isClickable": { "index": [4] }
+                },
+                "layout": {
+                    "nodeIndex": [1, 2, 3, 4, 5],
+                    "bounds": [
+                        [0, 0, 800, 600], [0, 0, 800, 600], [0, 0, 800, 20],
+                        [10, 150, 40, 20], [10, 150, 40, 20]
+                    ],
+                    "styles": [
+                        [11, 12, 13], [11, 12, 13], [11, 12, 13], [14, 12, 13], [14, 12, 13]
+                    ],
+                    "text": []
+                }
+            }]
+        })
+    }
+
+    #[test]
+    fn test_build_snapshot_keeps_visible_meaningful_nodes() {
+        let snapshot = build_snapshot(&sample_snapshot(), (800, 600)).unwrap();
+        assert!(!snapshot.truncated);
+        assert_eq!(snapshot.url, "https://example.com/");
+
+        // html/body/div are structural wrappers and collapse into the link
+        assert_eq!(snapshot.nodes.len(), 1);
+        let link = &snapshot.nodes[0];
+        assert_eq!(link.tag, "a");
+        assert_eq!(link.text.as_deref(), Some("Link"));
+        assert_eq!(link.attributes.get("href").map(String::as_str), Some("/x"));
+        assert!(link.clickable);
+        // Bounds are adjusted by the scroll offset
+        assert_eq!(link.bounds, [10, 50, 40, 20]);
+        assert!(link.in_viewport);
+        assert_eq!(snapshot.node_count, 1);
+    }
+
+    #[test]
+    fn test_build_snapshot_omits_password_values() {
+        // <html><body><input type="password" value="attr-secret"></body></html>, typed into
+        let raw = serde_json::json!({
+            "strings": [
+                "#document", "HTML", "BODY", "INPUT", "type", "password", "value",
+                "attr-secret", "typed-secret", "block", "visible", "1"
+            ],
+            "documents": [{
+                "nodes": {
+                    "parentIndex": [-1, 0, 1, 2],
+                    "nodeType": [9, 1, 1, 1],
+                    "nodeName": [0, 1, 2, 3],
+                    "nodeValue": [-1, -1, -1, -1],
+                    "attributes": [[], [], [], [4, 5, 6, 7]],
+                    "inputValue": { "index": [3], "value": [8] }
+                },
+                "layout": {
+                    "nodeIndex": [1, 2, 3],
+                    "bounds": [[0, 0, 800, 600], [0, 0, 800, 600], [10, 10, 100, 20]],
+                    "styles": [[9, 10, 11], [9, 10, 11], [9, 10, 11]],
+                    "text": []
+                }
+            }]
+        });
+        let snapshot = build_snapshot(&raw, (800, 600)).unwrap();
+        assert_eq!(snapshot.nodes.len(), 1);
+        let input = &snapshot.nodes[0];
+        assert_eq!(input.tag, "input");
+        assert_eq!(
+            input.attributes.get("type").map(String::as_str),
+            Some("password")
+        );
+        assert!(!input.attributes.contains_key("value"));
+    }
+
+    #[test]
+    fn test_build_snapshot_rejects_empty_response() {
+        let raw = serde_json::json!({ "strings": [], "documents": [] });
+        assert!(build_snapshot(&raw, (800, 600)).is_err());
+    }
+
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(normalize_text("  hello \n  world "), "hello world");
+        let long = "a".repeat(MAX_NODE_TEXT_LEN + 10);
+        assert_eq!(normalize_text(&long).chars().count(), MAX_NODE_TEXT_LEN + 1);
+    }
+}
@@ -0,0 +1,206 @@
+//! Pointer paths for drawing on canvases.
+//!
+//! Signature pads, whiteboards and annotation tools draw from the stream of
+//! pointer moves between press and release, so a drag with only a start and
+//! an end draws a straight line at best. `draw_path` presses at the first
+//! point, moves through the others, filling in points a few pixels apart so
+//! curves stay smooth, and releases at the last, spread over the requested
+//! duration. The events are native DevTools input events, mouse or touch,
+//! so pages receive them as trusted pointer input.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// DevTools method dispatching a mouse event.
+pub const MOUSE_EVENT_METHOD: &str = "Input.dispatchMouseEvent";
+
+/// DevTools method dispatching a touch event.
+pub const TOUCH_EVENT_METHOD: &str = "Input.dispatchTouchEvent";
+
+/// Default time a path takes to draw, in milliseconds.
+pub const DEFAULT_DURATION_MS: u64 = 500;
+
+/// Maximum time a path takes to draw, in milliseconds.
+pub const MAX_DURATION_MS: u64 = 10_000;
+
+/// Maximum number of points of a path.
+pub const MAX_POINTS: usize = 1000;
+
+/// Largest distance between consecutive moves, in pixels, unless the
+/// duration leaves too little time for that many moves.
+const STEP_PX: f64 = 5.0;
+
+/// Shortest time between consecutive moves, in milliseconds.
+const MIN_MOVE_INTERVAL_MS: u64 = 8;
+
+/// A point of a path, in screenshot coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PathPoint {
+    pub x: i64,
+    pub y: i64,
+}
+
+/// The kind of pointer drawing the path.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PointerType {
+    #[default]
+    Mouse,
+    Touch,
+}
+
+/// A DevTools input event of a path, dispatched `delay` after the previous
+/// one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathEvent {
+    pub method: &'static str,
+    pub params: serde_json::Value,
+    pub delay: Duration,
+}
+
+/// Plan the events drawing a path through `points` in `duration_ms`. A
+/// single point is a tap.
+pub fn plan(
+    points: &[PathPoint],
+    duration_ms: u64,
+    pointer: PointerType,
+) -> Result<Vec<PathEvent>, String> {
+    if points.is_empty() || points.len() > MAX_POINTS {
+        return Err(format!("A path has 1 to {} points", MAX_POINTS));
+    }
+    if duration_ms > MAX_DURATION_MS {
+        return Err(format!(
+            "duration_ms must be at most {} (got {})",
+            MAX_DURATION_MS, duration_ms
+        ));
+    }
+
+    let first = (points[0].x as f64, points[0].y as f64);
+    let moves = interpolate(points, duration_ms);
+    let total: f64 = moves.iter().map(|(_, distance)| distance).sum();
+    let mut events = Vec::with_capacity(moves.len() + 3);
+    let event = |params: serde_json::Value, delay: f64| PathEvent {
+        method: match pointer {
+            PointerType::Mouse => MOUSE_EVENT_METHOD,
+            PointerType::Touch => TOUCH_EVENT_METHOD,
+        },
+        params,
+        delay: Duration::from_micros((delay * 1000.0).round() as u64),
+    };
+
+    match pointer {
+        PointerType::Mouse => {
+            events.push(event(mouse("mouseMoved", first, 0), 0.0));
+            events.push(event(mouse("mousePressed", first, 1), 0.0));
+        }
+        PointerType::Touch => events.push(event(touch("touchStart", Some(first)), 0.0)),
+    }
+    let mut last = first;
+    for &(point, distance) in &moves {
+        // Time is spread by distance, so the pointer moves at a steady speed
+        let delay = if total > 0.0 {
+            duration_ms as f64 * distance / total
+        } else {
+            duration_ms as f64 / moves.len() as f64
+        };
+        events.push(match pointer {
+            PointerType::Mouse => event(mouse("mouseMoved", point, 1), delay),
+            PointerType::Touch => event(touch("touchMove", Some(point)), delay),
+        });
+        last = point;
+    }
+    events.push(match pointer {
+        PointerType::Mouse => event(mouse("mouseReleased", last, 0), 0.0),
+        PointerType::Touch => event(touch("touchEnd", None), 0.0),
+    });
+    Ok(events)
+}
+
+/// The points the pointer moves through after the first, with the distance
+/// from the previous one. Every point of the path is kept, so corners stay
+/// sharp.
+fn interpolate(points: &[PathPoint], duration_ms: u64) -> Vec<((f64, f64), f64)> {
+    let segments: Vec<((f64, f64), (f64, f64))> = points
+        .windows(2)
+        .map(|pair| {
+            (
+                (pair[0].x as f64, pair[0].y as f64),
+                (pair[1].x as f64, pair[1].y as f64),
+            )
+        })
+        .collect();
+    let length = |(a, b): &((f64, f64), (f64, f64))| (b.0 - a.0).hypot(b.1 - a.1);
+    let total: f64 = segments.iter().map(length).sum();
+    let max_moves = ((duration_ms / MIN_MOVE_INTERVAL_MS) as usize).max(segments.len());
+    let step = STEP_PX.max(total / max_moves.max(1) as f64);
+
+    let mut moves = Vec::new();
+    for segment in &segments {
+        let (a, b) = *segment;
+        let len = length(segment);
+        let count = ((len / step).ceil() as usize).max(1);
+        for k in 1..=count {
+            let t = k as f64 / count as f64;
+            moves.push((
+                (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t),
+                len / count as f64,
+            ));
+        }
+    }
+    moves
+}
+
+fn mouse(event_type: &str, (x, y): (f64, f64), buttons: i64) -> serde_json::Value {
+    let mut params = serde_json::json!({ "type": event_type, "x": x, "y": y, "buttons": buttons });
+    if event_type != "mouseMoved" || buttons != 0 {
+        params["button"] = serde_json::json!("left");
+    }
+    if event_type != "mouseMoved" {
+        params["clickCount"] = serde_json::json!(1);
+    }
+    params
+}
+
+fn touch(event_type: &str, point: Option<(f64, f64)>) -> serde_json::Value {
+    let touch_points: Vec<serde_json::Value> = point
+        .map(|(x, y)| serde_json::json!({ "x": x, "y": y }))
+        .into_iter()
+        .collect();
+    serde_json::json!({ "type": event_type, "touchPoints": touch_points })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        let points = [PathPoint { x: 0, y: 0 }, PathPoint { x: 20, y: 0 }];
+        let events = plan(&points, 400, PointerType::Mouse).unwrap();
+        // Move, press, four moves 5px apart, release
+        assert_eq!(events.len(), 7);
+        assert_eq!(events[1].params["type"], "mousePressed");
+        assert_eq!(events[2].params["x"], 5.0);
+        assert_eq!(events[2].params["buttons"], 1);
+        assert_eq!(events[2].delay, Duration::from_millis(100));
+        assert_eq!(events[6].params["type"], "mouseReleased");
+        assert_eq!(events[6].params["x"], 20.0);
+
+        let tap = plan(&points[..1], 0, PointerType::Touch).unwrap();
+        assert_eq!(tap.len(), 2);
+        assert_eq!(tap[0].method, TOUCH_EVENT_METHOD);
+        assert_eq!(
+            tap[1].params,
+            serde_json::json!({ "type": "touchEnd", "touchPoints": [] })
+        );
+
+        // A short duration spaces the moves further apart
+        let fast = plan(&points, 16, PointerType::Mouse).unwrap();
+        assert_eq!(fast.len(), 5);
+        assert!(plan(&[], 100, PointerType::Mouse).is_err());
+        assert!(plan(&points, MAX_DURATION_MS + 1, PointerType::Mouse).is_err());
+    }
+}
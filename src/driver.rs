@@ -96,10 +96,16 @@ impl DriverManager {
             driver_path, self.port
         );
 
-        let child = Command::new(&driver_path)
+        let mut command = Command::new(&driver_path);
+        command
             .arg(format!("--port={}", self.port))
             .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::inherit()) // Inherit stderr for debugging startup issues
+            .stderr(std::process::Stdio::inherit()); // Inherit stderr for debugging startup issues
+                                                     // The driver starts the browser, which inherits its display
+        if let Some(ref display) = config.display {
+            command.env("DISPLAY", display);
+        }
+        let child = command
             .spawn()
             .with_context(|| format!("Failed to start driver from {:?}", driver_path))?;
 
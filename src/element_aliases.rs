@@ -0,0 +1,282 @@
+//! Named element aliases remembered within a session.
+//!
+//! `remember_element` records a fingerprint of an element under a name: a
+//! CSS selector built from its most stable attributes, with its tag, text,
+//! label and role. `click_named` locates the element again from the
+//! fingerprint before every click, so the agent can keep using a button it
+//! found once after the page re-rendered or scrolled, without grounding it in
+//! a screenshot again. The selector is tried first; when it no longer matches
+//! an element with the remembered text, an element with the same tag and text
+//! is looked for instead.
+
+use crate::browser::ElementTarget;
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Maximum number of aliases per session.
+pub const MAX_ALIASES: usize = 200;
+
+/// Maximum length of an alias name.
+const MAX_NAME_LEN: usize = 64;
+
+/// What identifies a remembered element.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ElementFingerprint {
+    /// CSS selector of the element when it was remembered.
+    pub selector: String,
+    /// Lowercase tag name.
+    pub tag: String,
+    /// Visible text, whitespace collapsed.
+    #[serde(default)]
+    pub text: String,
+    /// Accessible label: aria-label, alt, title or placeholder.
+    #[serde(default)]
+    pub label: String,
+    /// ARIA role attribute.
+    #[serde(default)]
+    pub role: String,
+}
+
+impl ElementFingerprint {
+    /// Short description of the element, e.g. `button "Save"`.
+    pub fn describe(&self) -> String {
+        let name = if self.label.is_empty() {
+            &self.text
+        } else {
+            &self.label
+        };
+        if name.is_empty() {
+            self.tag.clone()
+        } else {
+            format!("{} \"{}\"", self.tag, name)
+        }
+    }
+}
+
+/// Where a remembered element was found again.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Located {
+    /// Center of the element, in the coordinates of the current frame.
+    pub x: i64,
+    pub y: i64,
+}
+
+/// The aliases of a session.
+#[derive(Default)]
+pub struct ElementAliases {
+    aliases: Mutex<HashMap<String, ElementFingerprint>>,
+}
+
+impl ElementAliases {
+    /// Remember an element under `name`, replacing any element remembered
+    /// under it before.
+    pub fn remember(&self, name: &str, fingerprint: ElementFingerprint) -> Result<(), String> {
+        validate_name(name)?;
+        let mut aliases = self.aliases.lock().unwrap_or_else(|e| e.into_inner());
+        if aliases.len() >= MAX_ALIASES && !aliases.contains_key(name) {
+            return Err(format!(
+                "At most {} elements can be remembered per session",
+                MAX_ALIASES
+            ));
+        }
+        aliases.insert(name.to_string(), fingerprint);
+        Ok(())
+    }
+
+    /// The element remembered under `name`.
+    pub fn get(&self, name: &str) -> Result<ElementFingerprint, String> {
+        let aliases = self.aliases.lock().unwrap_or_else(|e| e.into_inner());
+        aliases.get(name).cloned().ok_or_else(|| {
+            let mut names: Vec<&str> = aliases.keys().map(String::as_str).collect();
+            names.sort();
+            format!(
+                "No element remembered as '{}'; remembered: [{}]",
+                name,
+                names.join(", ")
+            )
+        })
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(format!(
+            "Element names must have 1 to {} characters",
+            MAX_NAME_LEN
+        ));
+    }
+    Ok(())
+}
+
+/// JavaScript function fingerprinting the target element. A point resolves
+/// to the control containing it, so remembering the icon of a button
+/// remembers the button. Returns an [`ElementFingerprint`] or `{ error }`.
+const REMEMBER_FUNCTION: &str = r#"
+(function(target) {
+    var el = target.selector !== undefined
+        ? document.querySelector(target.selector)
+        : document.elementFromPoint(target.x, target.y);
+    if (!el) {
+        return { error: target.selector !== undefined
+            ? 'No element matches selector ' + target.selector
+            : 'No element at (' + target.x + ', ' + target.y + ')' };
+    }
+    if (target.selector === undefined) {
+        el = el.closest('a[href], button, input, select, textarea, summary, label, [role], [onclick], [tabindex]') || el;
+    }
+    function unique(selector) {
+        try {
+            return document.querySelectorAll(selector).length === 1;
+        } catch (e) {
+            return false;
+        }
+    }
+    function ownSelector(node) {
+        var tag = node.tagName.toLowerCase();
+        // Generated IDs change between renders
+        if (node.id && !/\d{3,}/.test(node.id) && unique('#' + CSS.escape(node.id))) {
+            return '#' + CSS.escape(node.id);
+        }
+        var attributes = ['data-testid', 'data-test', 'data-qa', 'name', 'aria-label', 'title', 'placeholder', 'href'];
+        for (var i = 0; i < attributes.length; i++) {
+            var value = node.getAttribute(attributes[i]);
+            if (value) {
+                var selector = tag + '[' + attributes[i] + '="' + CSS.escape(value) + '"]';
+                if (unique(selector)) {
+                    return selector;
+                }
+            }
+        }
+        return null;
+    }
+    function pathSelector(node) {
+        var parts = [];
+        for (; node && node.nodeType === 1 && node !== document.documentElement; node = node.parentElement) {
+            var own = ownSelector(node);
+            if (own) {
+                parts.unshift(own);
+                break;
+            }
+            var index = 1;
+            for (var sibling = node.previousElementSibling; sibling; sibling = sibling.previousElementSibling) {
+                if (sibling.tagName === node.tagName) {
+                    index++;
+                }
+            }
+            parts.unshift(node.tagName.toLowerCase() + ':nth-of-type(' + index + ')');
+        }
+        return parts.join(' > ');
+    }
+    return {
+        selector: target.selector !== undefined ? target.selector : pathSelector(el),
+        tag: el.tagName.toLowerCase(),
+        text: (el.innerText || '').trim().replace(/\s+/g, ' ').substring(0, 80),
+        label: (el.getAttribute('aria-label') || el.getAttribute('alt') || el.getAttribute('title')
+            || el.getAttribute('placeholder') || '').trim().substring(0, 80),
+        role: el.getAttribute('role') || ''
+    };
+})"#;
+
+/// JavaScript function finding a fingerprinted element again and scrolling it
+/// into view if needed. Returns a [`Located`] or `{ error }`.
+const LOCATE_FUNCTION: &str = r#"
+(function(fingerprint) {
+    function visible(el) {
+        var rect = el.getBoundingClientRect();
+        return rect.width > 0 && rect.height > 0 && getComputedStyle(el).visibility !== 'hidden';
+    }
+    function label(el) {
+        return (el.getAttribute('aria-label') || el.getAttribute('alt') || el.getAttribute('title')
+            || el.getAttribute('placeholder') || '').trim().substring(0, 80);
+    }
+    function same(el) {
+        return (el.innerText || '').trim().replace(/\s+/g, ' ').substring(0, 80) === fingerprint.text
+            && label(el) === fingerprint.label;
+    }
+    var bySelector = null;
+    try {
+        bySelector = document.querySelector(fingerprint.selector);
+    } catch (e) {}
+    var el = bySelector && visible(bySelector) && same(bySelector) ? bySelector : null;
+    if (!el && (fingerprint.text || fingerprint.label)) {
+        // The page re-rendered: look for the same control elsewhere
+        var candidates = document.querySelectorAll(fingerprint.tag);
+        for (var i = 0; i < candidates.length && !el; i++) {
+            if (visible(candidates[i]) && same(candidates[i])) {
+                el = candidates[i];
+            }
+        }
+    }
+    if (!el && bySelector && visible(bySelector)) {
+        // Its text changed, e.g. a counter on the button
+        el = bySelector;
+    }
+    if (!el) {
+        return { error: 'The element is no longer on the page' };
+    }
+    var rect = el.getBoundingClientRect();
+    if (rect.bottom < 0 || rect.right < 0 || rect.top > window.innerHeight || rect.left > window.innerWidth) {
+        el.scrollIntoView({ block: 'center', inline: 'center' });
+        rect = el.getBoundingClientRect();
+    }
+    return { x: Math.round(rect.left + rect.width / 2), y: Math.round(rect.top + rect.height / 2) };
+})"#;
+
+/// JavaScript expression fingerprinting the target element.
+pub(crate) fn remember_script(target: &ElementTarget) -> String {
+    format!("{}({})", REMEMBER_FUNCTION, target.to_json())
+}
+
+/// JavaScript expression locating a fingerprinted element.
+pub(crate) fn locate_script(fingerprint: &ElementFingerprint) -> String {
+    format!(
+        "{}({})",
+        LOCATE_FUNCTION,
+        serde_json::to_string(fingerprint).unwrap_or_default()
+    )
+}
+
+/// The result of an alias script, which evaluates to `T` or `{ error }`.
+pub(crate) fn parse_result<T: serde::de::DeserializeOwned>(
+    value: &serde_json::Value,
+) -> anyhow::Result<T> {
+    if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+        return Err(anyhow::anyhow!("{}", error));
+    }
+    serde_json::from_value(value.clone())
+        .map_err(|e| anyhow::anyhow!("Unexpected script result {}: {}", value, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_element_aliases() {
+        let aliases = ElementAliases::default();
+        let save: ElementFingerprint = parse_result(&serde_json::json!({
+            "selector": "#save",
+            "tag": "button",
+            "text": "Save",
+            "label": "",
+            "role": ""
+        }))
+        .unwrap();
+        assert_eq!(save.describe(), "button \"Save\"");
+        aliases.remember("save", save.clone()).unwrap();
+        assert_eq!(aliases.get("save").unwrap(), save);
+        assert!(aliases.remember(" ", save.clone()).is_err());
+        assert!(aliases
+            .get("cancel")
+            .unwrap_err()
+            .contains("remembered: [save]"));
+
+        let missing = parse_result::<Located>(
+            &serde_json::json!({ "error": "The element is no longer on the page" }),
+        );
+        assert!(missing.unwrap_err().to_string().contains("no longer"));
+        assert!(locate_script(&save).contains(r##""selector":"#save""##));
+    }
+}
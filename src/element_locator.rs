@@ -0,0 +1,189 @@
+//! Locating elements by CSS selector or XPath.
+//!
+//! `click_element`, `hover_element` and `type_in_element` take a selector
+//! instead of coordinates, for agents that read the DOM and for targets a
+//! model keeps missing by a few pixels. The first visible match is scrolled
+//! into view if needed, and the action is then performed at its center like
+//! the coordinate tools do, so pages see the same input either way.
+//! Elements can also be found by their visible text, as `open_menu_path`
+//! finds menu items.
+
+use crate::element_aliases;
+use serde::{Deserialize, Serialize};
+
+/// How an element is selected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locator {
+    Css(String),
+    Xpath(String),
+    /// Innermost element whose visible text or accessible label is the
+    /// text, ignoring case and surrounding whitespace.
+    Text(String),
+}
+
+impl Locator {
+    /// The locator given as a `selector` or an `xpath` parameter, but not both.
+    pub fn from_params(selector: Option<&str>, xpath: Option<&str>) -> Result<Self, String> {
+        match (selector, xpath) {
+            (Some(selector), None) if !selector.trim().is_empty() => {
+                Ok(Locator::Css(selector.to_string()))
+            }
+            (None, Some(xpath)) if !xpath.trim().is_empty() => {
+                Ok(Locator::Xpath(xpath.to_string()))
+            }
+            _ => Err("Provide either selector or xpath".to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for Locator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Locator::Css(selector) => write!(f, "selector {}", selector),
+            Locator::Xpath(xpath) => write!(f, "XPath {}", xpath),
+            Locator::Text(text) => write!(f, "text \"{}\"", text),
+        }
+    }
+}
+
+/// The element a locator found.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocatedElement {
+    /// Center of the element, in the coordinates of the current frame until
+    /// a controller moves it into screenshot coordinates.
+    pub x: i64,
+    pub y: i64,
+    /// Lowercase tag name.
+    pub tag: String,
+    /// Visible text or accessible label, shortened.
+    #[serde(default)]
+    pub text: String,
+    /// Number of elements the locator matched.
+    pub matches: u32,
+}
+
+impl LocatedElement {
+    /// Short description of the element, e.g. `button "Save"`.
+    pub fn describe(&self) -> String {
+        if self.text.is_empty() {
+            self.tag.clone()
+        } else {
+            format!("{} \"{}\"", self.tag, self.text)
+        }
+    }
+}
+
+/// JavaScript function finding the first visible element matching a
+/// locator and scrolling it into view if needed. XPath matches on text nodes
+/// resolve to their element. Returns a [`LocatedElement`] or `{ error }`.
+const LOCATE_FUNCTION: &str = r#"
+(function(locator) {
+    var described = locator.xpath !== undefined ? 'XPath ' + locator.xpath
+        : locator.text !== undefined ? 'text "' + locator.text + '"' : 'selector ' + locator.css;
+    function normalize(text) {
+        return (text || '').trim().replace(/\s+/g, ' ').toLowerCase();
+    }
+    var matches = [];
+    try {
+        if (locator.text !== undefined) {
+            var wanted = normalize(locator.text);
+            var all = document.body ? document.body.querySelectorAll('*') : [];
+            for (var j = 0; j < all.length; j++) {
+                var candidate = all[j];
+                var label = candidate.getAttribute('aria-label');
+                if (label !== null ? normalize(label) === wanted
+                        : normalize(candidate.textContent).indexOf(wanted) >= 0 && normalize(candidate.innerText) === wanted) {
+                    matches.push(candidate);
+                }
+            }
+            // A menu item's wrapper has the same text as the item
+            matches = matches.filter(function(el) {
+                return !matches.some(function(other) {
+                    return other !== el && el.contains(other) && visible(other);
+                });
+            });
+        } else if (locator.xpath !== undefined) {
+            var result = document.evaluate(locator.xpath, document, null, XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null);
+            for (var i = 0; i < result.snapshotLength; i++) {
+                var node = result.snapshotItem(i);
+                var element = node.nodeType === 1 ? node : node.parentElement;
+                if (element && matches.indexOf(element) < 0) {
+                    matches.push(element);
+                }
+            }
+        } else {
+            matches = Array.prototype.slice.call(document.querySelectorAll(locator.css));
+        }
+    } catch (e) {
+        return { error: 'Invalid ' + described + ': ' + e.message };
+    }
+    if (!matches.length) {
+        return { error: 'No element matches ' + described };
+    }
+    function visible(el) {
+        var rect = el.getBoundingClientRect();
+        return rect.width > 0 && rect.height > 0 && getComputedStyle(el).visibility !== 'hidden';
+    }
+    var el = matches.filter(visible)[0];
+    if (!el) {
+        return { error: matches.length + ' elements match ' + described + ', but none is visible' };
+    }
+    var rect = el.getBoundingClientRect();
+    if (rect.top < 0 || rect.left < 0 || rect.bottom > window.innerHeight || rect.right > window.innerWidth) {
+        el.scrollIntoView({ block: 'center', inline: 'center', behavior: 'instant' });
+        rect = el.getBoundingClientRect();
+    }
+    // What was typed into a field, passwords included, stays out of the
+    // description; only the value of an input button is its label
+    var button = el.tagName === 'INPUT' && /^(button|submit|reset)$/i.test(el.type);
+    var text = (el.getAttribute('aria-label') || el.innerText || (button ? el.value : '') || el.getAttribute('placeholder') || '')
+        .trim().replace(/\s+/g, ' ').substring(0, 80);
+    return {
+        x: Math.round(rect.left + rect.width / 2),
+        y: Math.round(rect.top + rect.height / 2),
+        tag: el.tagName.toLowerCase(),
+        text: text,
+        matches: matches.length
+    };
+})"#;
+
+/// JavaScript expression locating an element.
+pub(crate) fn locate_script(locator: &Locator) -> String {
+    format!(
+        "{}({})",
+        LOCATE_FUNCTION,
+        serde_json::to_string(locator).unwrap_or_default()
+    )
+}
+
+/// The result of the locate script.
+pub(crate) fn parse_located(value: &serde_json::Value) -> anyhow::Result<LocatedElement> {
+    element_aliases::parse_result(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locator() {
+        let css = Locator::from_params(Some("#save"), None).unwrap();
+        assert!(locate_script(&css).ends_with(r##"({"css":"#save"})"##));
+        let xpath = Locator::from_params(None, Some("//button[text()='Save']")).unwrap();
+        assert_eq!(xpath.to_string(), "XPath //button[text()='Save']");
+        let text = Locator::Text("Export".to_string());
+        assert!(locate_script(&text).ends_with(r#"({"text":"Export"})"#));
+        assert!(Locator::from_params(Some("a"), Some("//a")).is_err());
+        assert!(Locator::from_params(Some(" "), None).is_err());
+
+        let located = parse_located(&serde_json::json!({
+            "x": 70, "y": 95, "tag": "button", "text": "Send", "matches": 1
+        }))
+        .unwrap();
+        assert_eq!(located.describe(), "button \"Send\"");
+        let missing =
+            parse_located(&serde_json::json!({ "error": "No element matches selector #x" }));
+        assert!(missing.is_err());
+    }
+}
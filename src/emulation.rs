@@ -0,0 +1,241 @@
+//! Emulated rendering conditions.
+//!
+//! QA agents need to see a page the way it renders outside the default screen
+//! conditions. `set_emulated_media` switches a tab's CSS media type, so print
+//! stylesheets can be checked from ordinary screenshots before printing, and
+//! overrides the `prefers-reduced-motion` and `forced-colors` media features, so
+//! accessibility testers can verify that a page respects those settings.
+//! `set_vision_deficiency` renders a tab as seen with a color vision deficiency
+//! or blurred vision, so auditors can check contrast and color coding from
+//! screenshots.
+//!
+//! Emulation uses the CDP `Emulation` domain, so it needs a Chromium-based
+//! browser. It applies to one tab and lasts across navigations until changed.
+//! Each `set_emulated_media` call only changes the settings it names; the
+//! controllers remember the rest per tab, since the CDP command replaces all of
+//! them at once.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// CDP method setting the emulated media type and features.
+pub const SET_EMULATED_MEDIA_METHOD: &str = "Emulation.setEmulatedMedia";
+
+/// CDP method setting the emulated vision deficiency.
+pub const SET_VISION_DEFICIENCY_METHOD: &str = "Emulation.setEmulatedVisionDeficiency";
+
+/// CSS media type to render a page with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaType {
+    /// Render with print stylesheets, as for printing.
+    Print,
+    /// Render normally.
+    Screen,
+}
+
+impl MediaType {
+    /// Value of the `media` parameter of `Emulation.setEmulatedMedia`. Screen
+    /// clears the override rather than forcing it, which renders the same.
+    pub fn cdp_value(self) -> &'static str {
+        match self {
+            MediaType::Print => "print",
+            MediaType::Screen => "",
+        }
+    }
+}
+
+/// Emulated media settings of a tab. Unset features follow the system.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema,
+)]
+pub struct MediaEmulation {
+    /// Emulated media type; absent for screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<MediaType>,
+    /// Whether `prefers-reduced-motion: reduce` matches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reduced_motion: Option<bool>,
+    /// Whether `forced-colors: active` matches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forced_colors: Option<bool>,
+}
+
+impl MediaEmulation {
+    /// These settings with the ones set in `changes` replaced.
+    pub fn merge(self, changes: MediaEmulation) -> Self {
+        Self {
+            media: changes.media.or(self.media),
+            reduced_motion: changes.reduced_motion.or(self.reduced_motion),
+            forced_colors: changes.forced_colors.or(self.forced_colors),
+        }
+    }
+
+    /// `(name, value)` pairs of the emulated media features. An empty value
+    /// clears the override of a feature.
+    pub fn features(&self) -> Vec<(&'static str, &'static str)> {
+        let reduced_motion = match self.reduced_motion {
+            Some(true) => "reduce",
+            Some(false) => "no-preference",
+            None => "",
+        };
+        let forced_colors = match self.forced_colors {
+            Some(true) => "active",
+            Some(false) => "none",
+            None => "",
+        };
+        vec![
+            ("prefers-reduced-motion", reduced_motion),
+            ("forced-colors", forced_colors),
+        ]
+    }
+
+    /// Parameters of `Emulation.setEmulatedMedia` for these settings.
+    pub fn cdp_params(&self) -> serde_json::Value {
+        let features: Vec<serde_json::Value> = self
+            .features()
+            .into_iter()
+            .map(|(name, value)| serde_json::json!({ "name": name, "value": value }))
+            .collect();
+        serde_json::json!({
+            "media": self.media.map_or("", MediaType::cdp_value),
+            "features": features,
+        })
+    }
+
+    /// Short description for tool responses.
+    pub fn describe(&self) -> String {
+        let media = match self.media {
+            Some(MediaType::Print) => "print",
+            _ => "screen",
+        };
+        let mut parts = vec![format!("{} media", media)];
+        if let Some(reduced_motion) = self.reduced_motion {
+            parts.push(format!("reduced motion {}", on_off(reduced_motion)));
+        }
+        if let Some(forced_colors) = self.forced_colors {
+            parts.push(format!("forced colors {}", on_off(forced_colors)));
+        }
+        parts.join(", ")
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// Vision deficiency to render a page with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VisionDeficiency {
+    /// Normal vision.
+    None,
+    /// Red-green color blindness with missing green cones.
+    Deuteranopia,
+    /// Red-green color blindness with missing red cones.
+    Protanopia,
+    /// Blue-yellow color blindness.
+    Tritanopia,
+    /// No color vision at all.
+    Achromatopsia,
+    BlurredVision,
+    ReducedContrast,
+}
+
+impl VisionDeficiency {
+    /// Value of the `type` parameter of `Emulation.setEmulatedVisionDeficiency`.
+    pub fn cdp_value(self) -> &'static str {
+        match self {
+            VisionDeficiency::None => "none",
+            VisionDeficiency::Deuteranopia => "deuteranopia",
+            VisionDeficiency::Protanopia => "protanopia",
+            VisionDeficiency::Tritanopia => "tritanopia",
+            VisionDeficiency::Achromatopsia => "achromatopsia",
+            VisionDeficiency::BlurredVision => "blurredVision",
+            VisionDeficiency::ReducedContrast => "reducedContrast",
+        }
+    }
+
+    /// Parameters of `Emulation.setEmulatedVisionDeficiency`.
+    pub fn cdp_params(self) -> serde_json::Value {
+        serde_json::json!({ "type": self.cdp_value() })
+    }
+}
+
+/// Media settings per tab.
+#[derive(Default)]
+pub struct MediaEmulations {
+    tabs: Mutex<HashMap<String, MediaEmulation>>,
+}
+
+impl MediaEmulations {
+    /// Apply changes to a tab's settings and return the resulting settings.
+    pub fn update(&self, tab: &str, changes: MediaEmulation) -> MediaEmulation {
+        let mut tabs = self.tabs.lock().unwrap_or_else(|e| e.into_inner());
+        let settings = tabs.entry(tab.to_string()).or_default();
+        *settings = settings.merge(changes);
+        *settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_emulation() {
+        let media: MediaType = serde_json::from_str(r#""print""#).unwrap();
+        assert_eq!(media, MediaType::Print);
+
+        let emulations = MediaEmulations::default();
+        let settings = emulations.update(
+            "t1",
+            MediaEmulation {
+                media: Some(MediaType::Print),
+                ..MediaEmulation::default()
+            },
+        );
+        assert_eq!(
+            settings.cdp_params(),
+            serde_json::json!({
+                "media": "print",
+                "features": [
+                    { "name": "prefers-reduced-motion", "value": "" },
+                    { "name": "forced-colors", "value": "" }
+                ]
+            })
+        );
+
+        // Later calls keep the settings they do not name
+        let settings = emulations.update(
+            "t1",
+            MediaEmulation {
+                reduced_motion: Some(true),
+                ..MediaEmulation::default()
+            },
+        );
+        assert_eq!(settings.media, Some(MediaType::Print));
+        assert_eq!(settings.features()[0], ("prefers-reduced-motion", "reduce"));
+        assert_eq!(settings.describe(), "print media, reduced motion on");
+        assert_eq!(
+            emulations.update("t2", MediaEmulation::default()),
+            MediaEmulation::default()
+        );
+    }
+
+    #[test]
+    fn test_vision_deficiency() {
+        let deficiency: VisionDeficiency = serde_json::from_str(r#""blurred_vision""#).unwrap();
+        assert_eq!(
+            deficiency.cdp_params(),
+            serde_json::json!({ "type": "blurredVision" })
+        );
+        assert!(serde_json::from_str::<VisionDeficiency>(r#""blurredVision""#).is_err());
+    }
+}
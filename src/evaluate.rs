@@ -0,0 +1,161 @@
+//! Evaluating JavaScript expressions given by the agent.
+//!
+//! `evaluate_javascript` reads page state no other tool exposes, such as an
+//! application's store or a value computed from the DOM. The expression is
+//! embedded in a script rather than passed to `eval`, so pages whose Content
+//! Security Policy forbids `eval` still run it. Promises are awaited, and the
+//! result is serialized to JSON in the page, where DOM nodes, functions and
+//! cyclic references have no JSON form, so they are described instead.
+//! Disable the tool with `MCP_DISABLED_TOOLS` where an agent must not run
+//! arbitrary code in the pages it visits.
+
+use serde::{Deserialize, Serialize};
+
+/// Time a returned promise has to settle, in milliseconds.
+pub const SETTLE_TIMEOUT_MS: u64 = 10_000;
+
+/// Longest JSON result returned, in characters; longer results are cut off.
+pub const MAX_RESULT_CHARS: usize = 50_000;
+
+/// The value of an evaluated expression.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Evaluation {
+    /// The value as JSON, or the start of its JSON text when it was cut off.
+    pub value: serde_json::Value,
+    /// `typeof` of the value, or `null` and `array` for those.
+    pub value_type: String,
+    /// Whether the JSON text was longer than [`MAX_RESULT_CHARS`].
+    pub truncated: bool,
+}
+
+#[derive(Deserialize)]
+struct ScriptResult {
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    json: Option<String>,
+    #[serde(default, rename = "type")]
+    value_type: String,
+}
+
+/// JavaScript expression evaluating to a promise of `{ json, type }` for the
+/// value of `expression`, or of `{ error }` when it throws, rejects or does
+/// not settle in time. A trailing semicolon is ignored.
+pub(crate) fn evaluate_script(expression: &str) -> String {
+    let expression = expression.trim().trim_end_matches(';');
+    format!(
+        r#"(function(timeoutMs) {{
+    function describe(value) {{
+        if (value instanceof Element) {{
+            return '<' + value.tagName.toLowerCase() + (value.id ? '#' + value.id : '') + '>';
+        }}
+        if (value instanceof Node) {{
+            return value.nodeName;
+        }}
+        if (value === window) {{
+            return '[Window]';
+        }}
+        return null;
+    }}
+    function serialize(value) {{
+        var seen = [];
+        var json = JSON.stringify(value, function(key, item) {{
+            if (item === undefined || typeof item === 'function' || typeof item === 'symbol') {{
+                return item === undefined ? null : '[' + typeof item + ']';
+            }}
+            if (typeof item === 'bigint') {{
+                return item.toString();
+            }}
+            if (item && typeof item === 'object') {{
+                var described = describe(item);
+                if (described !== null) {{
+                    return described;
+                }}
+                if (seen.indexOf(item) >= 0) {{
+                    return '[Circular]';
+                }}
+                seen.push(item);
+            }}
+            return item;
+        }});
+        return {{
+            json: json === undefined ? 'null' : json,
+            type: value === null ? 'null' : Array.isArray(value) ? 'array' : typeof value
+        }};
+    }}
+    function failure(prefix, e) {{
+        return {{ error: prefix + (e && e.message ? e.name + ': ' + e.message : String(e)) }};
+    }}
+    var evaluated = new Promise(function(resolve) {{
+        resolve((
+{}
+        ));
+    }}).then(serialize, function(e) {{
+        return failure('Uncaught ', e);
+    }});
+    var timeout = new Promise(function(resolve) {{
+        setTimeout(function() {{
+            resolve({{ error: 'The promise did not settle within ' + timeoutMs + ' ms' }});
+        }}, timeoutMs);
+    }});
+    return Promise.race([evaluated, timeout]).catch(function(e) {{
+        return failure('Could not serialize the result: ', e);
+    }});
+}})({})"#,
+        expression, SETTLE_TIMEOUT_MS
+    )
+}
+
+/// Read the value the [`evaluate_script`] promise resolved to.
+pub(crate) fn parse_evaluation(value: &serde_json::Value) -> anyhow::Result<Evaluation> {
+    let result: ScriptResult = serde_json::from_value(value.clone())
+        .map_err(|e| anyhow::anyhow!("Unexpected script result {}: {}", value, e))?;
+    if let Some(error) = result.error {
+        return Err(anyhow::anyhow!("{}", error));
+    }
+    let json = result.json.unwrap_or_else(|| "null".to_string());
+    if json.chars().count() > MAX_RESULT_CHARS {
+        return Ok(Evaluation {
+            value: serde_json::Value::String(json.chars().take(MAX_RESULT_CHARS).collect()),
+            value_type: result.value_type,
+            truncated: true,
+        });
+    }
+    Ok(Evaluation {
+        value: serde_json::from_str(&json)?,
+        value_type: result.value_type,
+        truncated: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluation() {
+        assert!(evaluate_script(" document.title; ").contains("resolve((\ndocument.title\n"));
+
+        let evaluation = parse_evaluation(&serde_json::json!({
+            "json": r#"{"items":[1,2],"node":"<div#app>"}"#,
+            "type": "object"
+        }))
+        .unwrap();
+        assert_eq!(evaluation.value["node"], serde_json::json!("<div#app>"));
+        assert!(!evaluation.truncated);
+
+        let long = serde_json::json!({
+            "json": format!("\"{}\"", "x".repeat(MAX_RESULT_CHARS)),
+            "type": "string"
+        });
+        let evaluation = parse_evaluation(&long).unwrap();
+        assert!(evaluation.truncated);
+        assert_eq!(
+            evaluation.value.as_str().unwrap().chars().count(),
+            MAX_RESULT_CHARS
+        );
+
+        let thrown = serde_json::json!({ "error": "Uncaught ReferenceError: app is not defined" });
+        assert!(parse_evaluation(&thrown).is_err());
+    }
+}
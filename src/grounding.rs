@@ -0,0 +1,223 @@
+//! Grounding snapshots: a screenshot with boxes of its interactable elements.
+//!
+//! `grounding_snapshot` pairs a screenshot with a flat list of the buttons,
+//! links, fields and other controls visible in it, each with a role, a
+//! human-readable label and a pixel box. The format follows the grounding
+//! datasets used to train and evaluate computer-use models (a box as
+//! `[left, top, right, bottom]` plus its click point), so sessions can be
+//! exported as examples without post-processing. Elements come from the
+//! compact DOM snapshot, whose bounds are already in screenshot coordinates.
+
+use crate::dom_snapshot::{DomSnapshot, SnapshotNode};
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+/// Maximum length of an element label, in characters.
+const MAX_LABEL_CHARS: usize = 100;
+
+/// ARIA roles of interactable elements.
+const INTERACTIVE_ROLES: &[&str] = &[
+    "button",
+    "link",
+    "checkbox",
+    "radio",
+    "switch",
+    "tab",
+    "menuitem",
+    "menuitemcheckbox",
+    "menuitemradio",
+    "option",
+    "combobox",
+    "textbox",
+    "searchbox",
+    "slider",
+    "spinbutton",
+    "treeitem",
+];
+
+/// An interactable element visible in the screenshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GroundingElement {
+    /// Index of the element in the list.
+    pub id: usize,
+    /// ARIA role, explicit or implied by the tag, e.g. `button`, `link`,
+    /// `textbox`; `clickable` for other elements reacting to clicks.
+    pub role: String,
+    /// Accessible name or visible text of the element.
+    pub label: String,
+    /// Lowercase tag name.
+    pub tag: String,
+    /// Visible part of the element as `[left, top, right, bottom]` in
+    /// screenshot pixels.
+    pub bbox: [i64; 4],
+    /// Center of the visible part, where a click would land.
+    pub center: [i64; 2],
+}
+
+/// The interactable elements of a snapshot that are visible in a viewport of
+/// `viewport` pixels, in document order.
+pub fn elements(snapshot: &DomSnapshot, viewport: (u32, u32)) -> Vec<GroundingElement> {
+    let mut elements = Vec::new();
+    for node in &snapshot.nodes {
+        collect(node, viewport, &mut elements);
+    }
+    elements
+}
+
+/// Add the interactable elements of a subtree. A control is added as a
+/// whole; an element that is only clickable, such as a card with a click
+/// handler, is added when none of its descendants was.
+fn collect(node: &SnapshotNode, viewport: (u32, u32), elements: &mut Vec<GroundingElement>) {
+    let role = implied_role(node);
+    if role.is_none() || role == Some("clickable") {
+        let before = elements.len();
+        for child in &node.children {
+            collect(child, viewport, elements);
+        }
+        if role.is_none() || elements.len() > before {
+            return;
+        }
+    }
+    let Some(bbox) = clip(node.bounds, viewport) else {
+        return;
+    };
+    elements.push(GroundingElement {
+        id: elements.len(),
+        role: role.unwrap_or_default().to_string(),
+        label: label(node),
+        tag: node.tag.clone(),
+        bbox,
+        center: [(bbox[0] + bbox[2]) / 2, (bbox[1] + bbox[3]) / 2],
+    });
+}
+
+/// Role of an interactable element; `None` for other elements.
+fn implied_role(node: &SnapshotNode) -> Option<&str> {
+    if let Some(role) = node.attributes.get("role") {
+        if let Some(role) = INTERACTIVE_ROLES.iter().find(|r| **r == role.as_str()) {
+            return Some(*role);
+        }
+    }
+    let input_type = node.attributes.get("type").map(String::as_str);
+    let role = match node.tag.as_str() {
+        "a" if node.attributes.contains_key("href") => "link",
+        "button" | "summary" => "button",
+        "select" => "combobox",
+        "textarea" => "textbox",
+        "option" => "option",
+        "input" => match input_type.unwrap_or("text") {
+            "hidden" => return None,
+            "checkbox" => "checkbox",
+            "radio" => "radio",
+            "range" => "slider",
+            "number" => "spinbutton",
+            "search" => "searchbox",
+            "button" | "submit" | "reset" | "image" | "file" => "button",
+            _ => "textbox",
+        },
+        _ if node.clickable => "clickable",
+        _ => return None,
+    };
+    Some(role)
+}
+
+/// Accessible name of an element, falling back to its text and describing
+/// attributes.
+fn label(node: &SnapshotNode) -> String {
+    let attribute = |name: &str| node.attributes.get(name).filter(|v| !v.is_empty());
+    let label = attribute("aria-label").cloned().unwrap_or_else(|| {
+        let mut text = Vec::new();
+        subtree_text(node, &mut text);
+        let text = text.join(" ");
+        if text.is_empty() {
+            ["alt", "title", "placeholder", "value", "name"]
+                .iter()
+                .find_map(|name| attribute(name).cloned())
+                .unwrap_or_default()
+        } else {
+            text
+        }
+    });
+    label.chars().take(MAX_LABEL_CHARS).collect()
+}
+
+fn subtree_text<'a>(node: &'a SnapshotNode, text: &mut Vec<&'a str>) {
+    if let Some(own) = &node.text {
+        text.push(own);
+    }
+    for child in &node.children {
+        subtree_text(child, text);
+    }
+}
+
+/// Clip `[x, y, width, height]` bounds to the viewport, as `[left, top,
+/// right, bottom]`. `None` when nothing of them is visible.
+fn clip(bounds: [i64; 4], viewport: (u32, u32)) -> Option<[i64; 4]> {
+    let [x, y, width, height] = bounds;
+    let left = x.max(0);
+    let top = y.max(0);
+    let right = (x + width).min(i64::from(viewport.0));
+    let bottom = (y + height).min(i64::from(viewport.1));
+    (right > left && bottom > top).then_some([left, top, right, bottom])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn node(tag: &str, bounds: [i64; 4], attributes: &[(&str, &str)]) -> SnapshotNode {
+        SnapshotNode {
+            tag: tag.to_string(),
+            bounds,
+            text: None,
+            attributes: attributes
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+            clickable: false,
+            in_viewport: true,
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_elements() {
+        let mut button = node("button", [10, 10, 80, 30], &[]);
+        let mut span = node("span", [20, 15, 40, 20], &[]);
+        span.text = Some("Save".to_string());
+        button.children.push(span);
+        let search = node("input", [-20, 50, 100, 30], &[("placeholder", "Search")]);
+        let below = node("a", [0, 900, 50, 20], &[("href", "/more")]);
+        let mut card = node("div", [0, 100, 300, 200], &[]);
+        card.clickable = true;
+        let mut card_link = node("a", [10, 110, 60, 20], &[("href", "/item")]);
+        card_link.text = Some("Item".to_string());
+        card.children.push(card_link);
+        let mut banner = node("div", [0, 400, 300, 50], &[("aria-label", "Dismiss")]);
+        banner.clickable = true;
+
+        let snapshot = DomSnapshot {
+            url: "https://a.test/".to_string(),
+            nodes: vec![button, search, below, card, banner],
+            node_count: 7,
+            truncated: false,
+        };
+        let elements = elements(&snapshot, (1280, 720));
+        let summary: Vec<(&str, &str, [i64; 4])> = elements
+            .iter()
+            .map(|e| (e.role.as_str(), e.label.as_str(), e.bbox))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("button", "Save", [10, 10, 90, 40]),
+                ("textbox", "Search", [0, 50, 80, 80]),
+                ("link", "Item", [10, 110, 70, 130]),
+                ("clickable", "Dismiss", [0, 400, 300, 450]),
+            ]
+        );
+        assert_eq!(elements[3].id, 3);
+        assert_eq!(elements[0].center, [50, 25]);
+    }
+}
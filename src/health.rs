@@ -0,0 +1,154 @@
+//! Liveness and readiness checks for the HTTP transport.
+//!
+//! `/healthz` only reports that the process is serving requests. `/readyz`
+//! probes the dependencies a tool call needs: the WebDriver server in WebDriver
+//! mode, the DevTools endpoint in CDP mode, and the browser binary whenever this
+//! server is responsible for launching the browser.
+
+use crate::browser_manager::BrowserManager;
+use crate::config::{Config, ConnectionMode};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Timeout for each HTTP probe. Kept short so a hung driver fails the probe
+/// instead of stalling it.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A dependency that must be available before the server is ready.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Probe {
+    /// WebDriver server status endpoint.
+    Driver(String),
+    /// DevTools HTTP endpoint of an already running browser.
+    Cdp(String),
+    /// Browser binary that will be launched on demand.
+    Browser,
+}
+
+impl Probe {
+    fn name(&self) -> &'static str {
+        match self {
+            Probe::Driver(_) => "driver",
+            Probe::Cdp(_) => "cdp",
+            Probe::Browser => "browser",
+        }
+    }
+}
+
+/// Result of a single readiness check.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    /// Name of the dependency that was checked.
+    pub name: &'static str,
+    /// Whether the dependency is available.
+    pub ok: bool,
+    /// What was checked, or why the check failed.
+    pub detail: String,
+}
+
+/// Overall readiness of the server.
+#[derive(Debug, Clone, Serialize)]
+pub struct Readiness {
+    /// True when every check passed.
+    pub ready: bool,
+    /// Individual check results.
+    pub checks: Vec<CheckResult>,
+}
+
+/// Decide which dependencies to probe for the given configuration.
+fn plan_probes(config: &Config) -> Vec<Probe> {
+    match config.connection_mode {
+        ConnectionMode::WebDriver => {
+            let mut probes = vec![Probe::Driver(config.effective_webdriver_url())];
+            // A remote WebDriver server launches its own browser
+            if config.auto_start {
+                probes.push(Probe::Browser);
+            }
+            probes
+        }
+        ConnectionMode::Cdp => match config.cdp_url {
+            Some(ref url) => vec![Probe::Cdp(url.clone())],
+            // Browser is launched on demand by open_web_browser
+            None => vec![Probe::Browser],
+        },
+    }
+}
+
+/// Probe every dependency required by the configuration.
+pub async fn check_readiness(config: &Config) -> Readiness {
+    let client = reqwest::Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .build()
+        .unwrap_or_default();
+
+    let mut checks = Vec::new();
+    for probe in plan_probes(config) {
+        let outcome = match probe {
+            Probe::Driver(ref url) => probe_http(&client, &format!("{}/status", url)).await,
+            Probe::Cdp(ref url) => probe_http(&client, &format!("{}/json/version", url)).await,
+            Probe::Browser => BrowserManager::new()
+                .find_browser(config)
+                .map(|path| path.display().to_string())
+                .map_err(|e| e.to_string()),
+        };
+        checks.push(CheckResult {
+            name: probe.name(),
+            ok: outcome.is_ok(),
+            detail: outcome.unwrap_or_else(|e| e),
+        });
+    }
+
+    Readiness {
+        ready: checks.iter().all(|c| c.ok),
+        checks,
+    }
+}
+
+/// Issue a GET request and require a successful status code.
+async fn probe_http(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    match client.get(url).send().await {
+        Ok(response) if response.status().is_success() => Ok(url.to_string()),
+        Ok(response) => Err(format!("{} returned {}", url, response.status())),
+        Err(e) => Err(format!("{} unreachable: {}", url, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_probes() {
+        let config = Config {
+            webdriver_url: Some("http://localhost:4444".to_string()),
+            ..Config::default()
+        };
+        assert_eq!(
+            plan_probes(&config),
+            vec![Probe::Driver("http://localhost:4444".to_string())]
+        );
+
+        let config = Config {
+            auto_start: true,
+            ..config
+        };
+        assert_eq!(plan_probes(&config).last(), Some(&Probe::Browser));
+
+        let config = Config {
+            connection_mode: ConnectionMode::Cdp,
+            cdp_url: Some("http://127.0.0.1:9222".to_string()),
+            ..Config::default()
+        };
+        assert_eq!(
+            plan_probes(&config),
+            vec![Probe::Cdp("http://127.0.0.1:9222".to_string())]
+        );
+
+        let config = Config {
+            connection_mode: ConnectionMode::Cdp,
+            auto_start: true,
+            ..Config::default()
+        };
+        assert_eq!(plan_probes(&config), vec![Probe::Browser]);
+    }
+}
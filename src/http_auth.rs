@@ -0,0 +1,82 @@
+//! Bearer-token authentication for the HTTP transport.
+//!
+//! When `MCP_HTTP_AUTH_TOKEN` is set, every request to the MCP endpoint must
+//! carry `Authorization: Bearer <token>` matching one of the configured tokens.
+//! Tokens are compared in constant time so response timing does not leak how
+//! much of a guessed token was correct.
+
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tracing::warn;
+
+/// Axum middleware rejecting requests without a valid bearer token.
+pub async fn require_bearer_token(
+    State(tokens): State<Arc<Vec<String>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if is_authorized(request.headers(), &tokens) {
+        return next.run(request).await;
+    }
+
+    warn!(
+        "Rejected unauthenticated HTTP request to {}",
+        request.uri().path()
+    );
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+        "Unauthorized",
+    )
+        .into_response()
+}
+
+/// Check the `Authorization` header against the accepted tokens.
+fn is_authorized(headers: &HeaderMap, tokens: &[String]) -> bool {
+    let Some(presented) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            let (scheme, token) = value.split_once(' ')?;
+            scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+        })
+    else {
+        return false;
+    };
+
+    // Compare against every token without short-circuiting
+    tokens.iter().fold(false, |matched, token| {
+        matched | bool::from(presented.as_bytes().ct_eq(token.as_bytes()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(authorization: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(authorization).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_is_authorized() {
+        let tokens = vec!["first-secret".to_string(), "second-secret".to_string()];
+
+        assert!(is_authorized(&headers("Bearer first-secret"), &tokens));
+        assert!(is_authorized(&headers("bearer second-secret"), &tokens));
+        assert!(!is_authorized(&headers("Bearer first-secre"), &tokens));
+        assert!(!is_authorized(&headers("Basic first-secret"), &tokens));
+        assert!(!is_authorized(&headers("first-secret"), &tokens));
+        assert!(!is_authorized(&HeaderMap::new(), &tokens));
+    }
+}
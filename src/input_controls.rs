@@ -0,0 +1,123 @@
+//! Setting color and range inputs.
+//!
+//! Color inputs open a picker that belongs to the browser rather than the
+//! page, and range sliders land on whatever value is under the pointer, so
+//! neither can be set reliably with clicks. `set_color` and `set_range` set
+//! the value through the native value setter, which frameworks that track
+//! input values (React) notice, and fire `input` and `change` like a user
+//! would. The browser sanitizes the value, so a range value is clamped to
+//! the slider's bounds and snapped to its step.
+
+use crate::browser::ElementTarget;
+
+/// Value for an input that cannot be set by typing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlValue {
+    /// A `#rrggbb` color.
+    Color(String),
+    Range(f64),
+}
+
+impl ControlValue {
+    /// The color input value for `#rgb` or `#rrggbb`, with or without `#`.
+    pub fn color(color: &str) -> Result<Self, String> {
+        let hex = color.trim().trim_start_matches('#').to_ascii_lowercase();
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Invalid color '{}': use #rrggbb", color));
+        }
+        match hex.len() {
+            6 => Ok(ControlValue::Color(format!("#{}", hex))),
+            3 => Ok(ControlValue::Color(hex.chars().fold(
+                "#".to_string(),
+                |mut color, c| {
+                    color.push(c);
+                    color.push(c);
+                    color
+                },
+            ))),
+            _ => Err(format!("Invalid color '{}': use #rrggbb", color)),
+        }
+    }
+
+    /// The `type` of the input the value is for.
+    pub fn input_type(&self) -> &'static str {
+        match self {
+            ControlValue::Color(_) => "color",
+            ControlValue::Range(_) => "range",
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            ControlValue::Color(color) => serde_json::json!(color),
+            ControlValue::Range(value) => serde_json::json!(value),
+        }
+    }
+}
+
+/// JavaScript function setting the value of the target input, which must be
+/// of `type`. A point on a label targets its input. Returns `{ text }` with
+/// the value the input took, or `{ error }`.
+const SET_VALUE_FUNCTION: &str = r#"
+(function(target, type, value) {
+    var el = target.selector !== undefined
+        ? document.querySelector(target.selector)
+        : document.elementFromPoint(target.x, target.y);
+    if (!el) {
+        return { error: target.selector !== undefined
+            ? 'No element matches selector ' + target.selector
+            : 'No element at (' + target.x + ', ' + target.y + ')' };
+    }
+    if (el.tagName === 'LABEL' && el.control) {
+        el = el.control;
+    }
+    if (el.tagName !== 'INPUT' || el.type !== type) {
+        var found = el.tagName === 'INPUT' ? '<input type=' + el.type + '>' : '<' + el.tagName.toLowerCase() + '>';
+        return { error: 'The element is ' + found + ', not <input type=' + type + '>' };
+    }
+    if (el.disabled || el.readOnly) {
+        return { error: 'The input is disabled' };
+    }
+    var setter = Object.getOwnPropertyDescriptor(HTMLInputElement.prototype, 'value').set;
+    el.focus();
+    setter.call(el, String(value));
+    el.dispatchEvent(new Event('input', { bubbles: true }));
+    el.dispatchEvent(new Event('change', { bubbles: true }));
+    return { text: el.value };
+})"#;
+
+/// JavaScript expression setting the value of the target input.
+pub(crate) fn set_value_script(target: &ElementTarget, value: &ControlValue) -> String {
+    format!(
+        "{}({}, '{}', {})",
+        SET_VALUE_FUNCTION,
+        target.to_json(),
+        value.input_type(),
+        value.to_json()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control_values() {
+        assert_eq!(
+            ControlValue::color("#FF8800"),
+            Ok(ControlValue::Color("#ff8800".to_string()))
+        );
+        assert_eq!(
+            ControlValue::color("0af"),
+            Ok(ControlValue::Color("#00aaff".to_string()))
+        );
+        assert!(ControlValue::color("red").is_err());
+        assert!(ControlValue::color("#12345").is_err());
+
+        let script = set_value_script(
+            &ElementTarget::Selector("#volume".to_string()),
+            &ControlValue::Range(7.5),
+        );
+        assert!(script.ends_with(r##"({"selector":"#volume"}, 'range', 7.5)"##));
+    }
+}
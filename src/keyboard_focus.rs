@@ -0,0 +1,224 @@
+//! Reaching elements with the keyboard.
+//!
+//! Clicks miss when a target is covered by an overlay, moves while the page
+//! settles, or only reacts to keyboard users. `tab_to_element` presses Tab
+//! (or Shift+Tab) until the focused element, read back from
+//! `document.activeElement` after every press, is the one wanted, then
+//! activates it with Enter or Space like a keyboard user would. Focus that
+//! comes back around to where it started means the element cannot be
+//! reached by tabbing.
+
+use crate::element_locator::Locator;
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+/// Default number of Tab presses before giving up.
+pub const DEFAULT_MAX_PRESSES: u32 = 50;
+
+/// Maximum number of Tab presses a call may allow.
+pub const MAX_PRESSES: u32 = 200;
+
+/// Key pressing the element once it has focus.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Activation {
+    /// Space for checkboxes, radio buttons and switches, nothing for text
+    /// fields and selects, Enter for anything else.
+    #[default]
+    Auto,
+    Enter,
+    Space,
+    /// Only move focus to the element.
+    None,
+}
+
+/// What the focus check found.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FocusCheck {
+    /// Whether the focused element is the target.
+    pub focused: bool,
+    /// Whether focus is back on the element it started from.
+    pub wrapped: bool,
+    /// Lowercase tag name of the focused element.
+    pub tag: String,
+    /// Visible text or accessible label of the focused element, shortened.
+    #[serde(default)]
+    pub text: String,
+    /// Key [`Activation::Auto`] presses on the focused element.
+    #[serde(default)]
+    pub activation: Option<Activation>,
+}
+
+/// The element focus was moved to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TabFocus {
+    /// Lowercase tag name.
+    pub tag: String,
+    /// Visible text or accessible label, shortened.
+    pub text: String,
+    /// Number of Tab presses it took.
+    pub presses: u32,
+    /// Key pressed on the element after focusing it, if any.
+    pub activated: Option<Activation>,
+}
+
+impl TabFocus {
+    /// Short description of the element, e.g. `button "Save"`.
+    pub fn describe(&self) -> String {
+        if self.text.is_empty() {
+            self.tag.clone()
+        } else {
+            format!("{} \"{}\"", self.tag, self.text)
+        }
+    }
+}
+
+/// JavaScript function checking whether the focused element matches a
+/// locator: it or one of its ancestors matches a selector or XPath, or its
+/// text, button label, placeholder, label or aria-label is the text, ignoring
+/// case.
+/// With `reset`, the focused element is remembered as the start for
+/// `wrapped`. Returns a [`FocusCheck`] or `{ error }`.
+const FOCUS_FUNCTION: &str = r#"
+(function(locator, reset) {
+    function normalize(text) {
+        return (text || '').trim().replace(/\s+/g, ' ').toLowerCase();
+    }
+    var active = document.activeElement || document.body;
+    while (active && active.shadowRoot && active.shadowRoot.activeElement) {
+        active = active.shadowRoot.activeElement;
+    }
+    if (reset) {
+        window.__mcpTabStart = active;
+    }
+    // What was typed into a field, passwords included, is never matched or
+    // reported; only the value of an input button is its label
+    var button = active.tagName === 'INPUT' && /^(button|submit|reset)$/i.test(active.type);
+    var label = button ? active.value : '';
+    var focused = false;
+    try {
+        if (locator.text !== undefined) {
+            var wanted = normalize(locator.text);
+            var names = [active.getAttribute('aria-label'), active.innerText, label, active.placeholder];
+            (active.labels || []).forEach(function(label) { names.push(label.innerText); });
+            focused = active !== document.body && names.some(function(name) {
+                return typeof name === 'string' && normalize(name) === wanted;
+            });
+        } else {
+            var matches = [];
+            if (locator.xpath !== undefined) {
+                var result = document.evaluate(locator.xpath, document, null, XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null);
+                for (var i = 0; i < result.snapshotLength; i++) {
+                    matches.push(result.snapshotItem(i));
+                }
+            } else {
+                matches = Array.prototype.slice.call(document.querySelectorAll(locator.css));
+            }
+            if (reset && !matches.length) {
+                return { error: 'No element matches ' + (locator.xpath !== undefined ? 'XPath ' + locator.xpath : 'selector ' + locator.css) };
+            }
+            focused = matches.some(function(el) { return el === active || el.contains(active); });
+        }
+    } catch (e) {
+        return { error: e.message };
+    }
+    var type = (active.type || '').toLowerCase();
+    var role = active.getAttribute('role');
+    var activation = 'enter';
+    if (type === 'checkbox' || type === 'radio' || role === 'checkbox' || role === 'radio' || role === 'switch') {
+        activation = 'space';
+    } else if (active.tagName === 'TEXTAREA' || active.tagName === 'SELECT' || active.isContentEditable
+            || (active.tagName === 'INPUT' && !/^(button|submit|reset|image|file|color)$/.test(type))) {
+        activation = 'none';
+    }
+    var text = (active.getAttribute('aria-label') || (active.innerText || '').substring(0, 200) || label || active.placeholder || '')
+        .trim().replace(/\s+/g, ' ').substring(0, 80);
+    return {
+        focused: focused,
+        wrapped: !reset && active === window.__mcpTabStart,
+        tag: active.tagName.toLowerCase(),
+        text: text,
+        activation: activation
+    };
+})"#;
+
+/// JavaScript expression checking the focused element against a locator.
+pub(crate) fn focus_script(locator: &Locator, reset: bool) -> String {
+    format!(
+        "{}({}, {})",
+        FOCUS_FUNCTION,
+        serde_json::to_string(locator).unwrap_or_default(),
+        reset
+    )
+}
+
+/// The result of the focus script.
+pub(crate) fn parse_focus(value: &serde_json::Value) -> anyhow::Result<FocusCheck> {
+    crate::element_aliases::parse_result(value)
+}
+
+/// The key to press on the focused element, if any.
+pub fn activation_key(activation: Activation, check: &FocusCheck) -> Option<Activation> {
+    match activation {
+        Activation::Auto => match check.activation {
+            Some(Activation::None) => None,
+            Some(Activation::Space) => Some(Activation::Space),
+            _ => Some(Activation::Enter),
+        },
+        Activation::None => None,
+        key => Some(key),
+    }
+}
+
+/// Error for a target focus did not reach.
+pub fn not_reached(locator: &Locator, presses: u32, wrapped: bool) -> anyhow::Error {
+    if wrapped {
+        anyhow::anyhow!(
+            "Focus went around the page in {} presses without reaching the element with {}",
+            presses,
+            locator
+        )
+    } else {
+        anyhow::anyhow!(
+            "Focus did not reach the element with {} in {} presses",
+            locator,
+            presses
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus() {
+        let script = focus_script(&Locator::Text("Save".to_string()), true);
+        assert!(script.ends_with(r#"({"text":"Save"}, true)"#));
+
+        let check = parse_focus(&serde_json::json!({
+            "focused": true, "wrapped": false, "tag": "input", "text": "Subscribe",
+            "activation": "space"
+        }))
+        .unwrap();
+        assert_eq!(
+            activation_key(Activation::Auto, &check),
+            Some(Activation::Space)
+        );
+        assert_eq!(activation_key(Activation::None, &check), None);
+        let field = FocusCheck {
+            activation: Some(Activation::None),
+            ..check
+        };
+        assert_eq!(activation_key(Activation::Auto, &field), None);
+        assert_eq!(
+            activation_key(Activation::Enter, &field),
+            Some(Activation::Enter)
+        );
+        assert!(
+            parse_focus(&serde_json::json!({ "error": "No element matches selector #x" })).is_err()
+        );
+    }
+}
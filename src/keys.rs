@@ -0,0 +1,273 @@
+//! Key name normalization.
+//!
+//! Models name keys in many ways: DOM key names (`ArrowDown`), Gemini-style
+//! combination strings (`Control+C`), xdotool names (`Page_Down`, `super`) or
+//! macOS symbols. Everything is normalized to DOM key names before it reaches
+//! a browser controller, so the same prompt works against every backend.
+//!
+//! The `Primary` modifier (also `Mod` or `CmdOrCtrl`) resolves to Meta on macOS
+//! and to Control elsewhere, for shortcuts such as copy and paste that use a
+//! different modifier per platform. Models usually write `Control` regardless,
+//! so with [`KeyMapping::control_as_meta`] a Control shortcut on a character key
+//! (`Control+L`) is pressed with Command instead. Control combined only with
+//! named keys (`Control+Tab`) means the same on every platform and is kept.
+
+use crate::config::{Config, PrimaryModifier};
+use anyhow::Result;
+
+/// Operating system whose shortcut conventions apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    MacOs,
+    Other,
+}
+
+impl Platform {
+    /// Platform of the host running the server (and a local browser).
+    pub fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Other
+        }
+    }
+
+    /// DOM name of the modifier used for common shortcuts.
+    pub fn primary_modifier(self) -> &'static str {
+        match self {
+            Platform::MacOs => "Meta",
+            Platform::Other => "Control",
+        }
+    }
+}
+
+/// How modifiers are mapped for the platform the browser runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyMapping {
+    /// DOM name of the modifier `Primary` resolves to.
+    pub primary: &'static str,
+    /// Press Control shortcuts on character keys with Meta (Command).
+    pub control_as_meta: bool,
+}
+
+impl KeyMapping {
+    /// Mapping for a platform, without translating Control.
+    pub fn for_platform(platform: Platform) -> Self {
+        Self {
+            primary: platform.primary_modifier(),
+            control_as_meta: false,
+        }
+    }
+
+    /// Mapping selected by `MCP_PRIMARY_MODIFIER`. In auto mode Control is only
+    /// translated for a visible browser on macOS, where the page receives real
+    /// platform shortcuts.
+    pub fn from_config(config: &Config) -> Self {
+        match config.primary_modifier {
+            PrimaryModifier::Control => Self {
+                primary: "Control",
+                control_as_meta: false,
+            },
+            PrimaryModifier::Meta => Self {
+                primary: "Meta",
+                control_as_meta: true,
+            },
+            PrimaryModifier::Auto => {
+                let platform = Platform::current();
+                Self {
+                    control_as_meta: platform == Platform::MacOs && !config.headless,
+                    ..Self::for_platform(platform)
+                }
+            }
+        }
+    }
+}
+
+/// Normalize key names to DOM key names.
+///
+/// Entries containing `+` are split into separate keys, so `["ctrl+shift+t"]`
+/// and `["Control", "Shift", "t"]` are equivalent. Single characters are kept
+/// as they are, apart from modifier symbols such as `⌘`.
+pub fn normalize_keys(keys: &[String], mapping: &KeyMapping) -> Result<Vec<String>> {
+    let mut normalized = Vec::with_capacity(keys.len());
+    for entry in keys {
+        for key in split_combination(entry) {
+            normalized.push(normalize_key(key, mapping)?);
+        }
+    }
+
+    let on_character = normalized
+        .iter()
+        .any(|key| key.chars().count() == 1 && key != " ");
+    if mapping.control_as_meta && on_character && !normalized.iter().any(|key| key == "Meta") {
+        for key in normalized.iter_mut().filter(|key| *key == "Control") {
+            *key = "Meta".to_string();
+        }
+    }
+    Ok(normalized)
+}
+
+/// Split a combination string like `ctrl+c` into its keys. A `+` that follows
+/// another `+`, or stands alone, is the plus key itself.
+fn split_combination(entry: &str) -> Vec<&str> {
+    if entry.chars().count() <= 1 {
+        return vec![entry];
+    }
+    let mut keys = Vec::new();
+    let mut start = 0;
+    for (i, c) in entry.char_indices() {
+        if c == '+' && i > start {
+            keys.push(entry[start..i].trim());
+            start = i + 1;
+        }
+    }
+    keys.push(entry[start..].trim());
+    keys
+}
+
+/// Map a single key name to its DOM key name. Characters without a special
+/// meaning are kept as they are.
+fn normalize_key(key: &str, mapping: &KeyMapping) -> Result<String> {
+    let name: String = key
+        .chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .flat_map(char::to_lowercase)
+        .collect();
+    let dom_name = match name.as_str() {
+        "control" | "ctrl" | "ctl" | "⌃" => "Control",
+        "meta" | "command" | "cmd" | "super" | "win" | "windows" | "os" | "⌘" => "Meta",
+        "alt" | "option" | "opt" | "⌥" => "Alt",
+        "shift" | "⇧" => "Shift",
+        "primary" | "mod" | "cmdorctrl" | "commandorcontrol" | "ctrlorcmd" => mapping.primary,
+        "enter" | "return" | "kpenter" | "↵" => "Enter",
+        "tab" | "⇥" => "Tab",
+        "escape" | "esc" => "Escape",
+        "backspace" | "⌫" => "Backspace",
+        "delete" | "del" => "Delete",
+        "insert" | "ins" => "Insert",
+        "space" | "spacebar" => " ",
+        "home" => "Home",
+        "end" => "End",
+        "pageup" | "pgup" | "prior" => "PageUp",
+        "pagedown" | "pgdn" | "next" => "PageDown",
+        "left" | "arrowleft" => "ArrowLeft",
+        "up" | "arrowup" => "ArrowUp",
+        "right" | "arrowright" => "ArrowRight",
+        "down" | "arrowdown" => "ArrowDown",
+        _ if key.chars().count() == 1 => return Ok(key.to_string()),
+        _ => {
+            return function_key(&name).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown key name '{}'. Use DOM key names such as Control, Enter or ArrowDown, or single characters.",
+                    key
+                )
+            })
+        }
+    };
+    Ok(dom_name.to_string())
+}
+
+/// `F1` to `F12`.
+fn function_key(name: &str) -> Option<String> {
+    let number: u8 = name.strip_prefix('f')?.parse().ok()?;
+    (1..=12).contains(&number).then(|| format!("F{}", number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(keys: &[&str], platform: Platform) -> Vec<String> {
+        normalize_with(keys, &KeyMapping::for_platform(platform))
+    }
+
+    fn normalize_with(keys: &[&str], mapping: &KeyMapping) -> Vec<String> {
+        let keys: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+        normalize_keys(&keys, mapping).unwrap()
+    }
+
+    #[test]
+    fn test_normalize_combination_strings() {
+        assert_eq!(normalize(&["ctrl+c"], Platform::Other), ["Control", "c"]);
+        assert_eq!(
+            normalize(&["Control+Shift+T"], Platform::Other),
+            ["Control", "Shift", "T"]
+        );
+        assert_eq!(normalize(&["ctrl++"], Platform::Other), ["Control", "+"]);
+        assert_eq!(normalize(&["+"], Platform::Other), ["+"]);
+        assert_eq!(
+            normalize(&["Control", "c"], Platform::Other),
+            normalize(&["Control+c"], Platform::Other)
+        );
+    }
+
+    #[test]
+    fn test_normalize_key_aliases() {
+        assert_eq!(
+            normalize(
+                &[
+                    "Return",
+                    "esc",
+                    "Page_Down",
+                    "up",
+                    "space",
+                    "super",
+                    "option",
+                    "f5"
+                ],
+                Platform::Other
+            ),
+            ["Enter", "Escape", "PageDown", "ArrowUp", " ", "Meta", "Alt", "F5"]
+        );
+        assert_eq!(normalize(&["ArrowLeft"], Platform::Other), ["ArrowLeft"]);
+        assert_eq!(
+            normalize(&["⌘", "F", " "], Platform::Other),
+            ["Meta", "F", " "]
+        );
+
+        let mapping = KeyMapping::for_platform(Platform::Other);
+        assert!(normalize_keys(&["hyper".to_string()], &mapping).is_err());
+        assert!(normalize_keys(&["f13".to_string()], &mapping).is_err());
+    }
+
+    #[test]
+    fn test_primary_modifier_per_platform() {
+        assert_eq!(normalize(&["Mod+c"], Platform::MacOs), ["Meta", "c"]);
+        assert_eq!(
+            normalize(&["CmdOrCtrl+c"], Platform::Other),
+            ["Control", "c"]
+        );
+        // Explicit modifiers are not translated
+        assert_eq!(normalize(&["ctrl+c"], Platform::MacOs), ["Control", "c"]);
+    }
+
+    #[test]
+    fn test_control_as_meta() {
+        let mut config = Config {
+            primary_modifier: PrimaryModifier::Meta,
+            ..Config::default()
+        };
+        let mapping = KeyMapping::from_config(&config);
+        assert_eq!(normalize_with(&["Control+L"], &mapping), ["Meta", "L"]);
+        assert_eq!(
+            normalize_with(&["ctrl", "shift", "t"], &mapping),
+            ["Meta", "Shift", "t"]
+        );
+        assert_eq!(normalize_with(&["Primary+a"], &mapping), ["Meta", "a"]);
+        // Control on named keys keeps its meaning on macOS
+        assert_eq!(
+            normalize_with(&["Control+Tab"], &mapping),
+            ["Control", "Tab"]
+        );
+
+        config.primary_modifier = PrimaryModifier::Control;
+        let mapping = KeyMapping::from_config(&config);
+        assert_eq!(normalize_with(&["Control+L"], &mapping), ["Control", "L"]);
+        assert_eq!(normalize_with(&["Primary+a"], &mapping), ["Control", "a"]);
+
+        // Headless browsers are never translated automatically
+        config.primary_modifier = PrimaryModifier::Auto;
+        config.headless = true;
+        assert!(!KeyMapping::from_config(&config).control_as_meta);
+    }
+}
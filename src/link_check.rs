@@ -0,0 +1,384 @@
+//! Broken link checks.
+//!
+//! `check_links` collects the links of a page, or of one element, and sends a
+//! HEAD request to each, a few at a time, so a QA agent can find broken links
+//! without navigating to every one of them. Servers that do not support HEAD
+//! are asked again with GET. Requests come from the server, not the browser, so
+//! they carry no cookies: links behind a login report the status an anonymous
+//! visitor gets.
+//!
+//! The links come from the page, so they must not turn the server into a
+//! probe of its own network: loopback, link-local and private addresses are
+//! refused, whether a link names them, its host resolves to them or a
+//! redirect leads to them, unless `MCP_CHECK_LINKS_ALLOW_PRIVATE` is set.
+//! Redirects are followed one hop at a time, and every request goes through
+//! the same robots.txt and politeness checks as navigations.
+
+use crate::politeness::{Politeness, ROBOTS_USER_AGENT};
+use futures::StreamExt;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::Url;
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::error::Error;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+/// Maximum number of links checked per call.
+pub const MAX_LINKS: usize = 200;
+
+/// Number of requests in flight at once.
+const CONCURRENCY: usize = 8;
+
+/// Timeout of each request.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of characters of link text kept.
+const MAX_TEXT_CHARS: usize = 80;
+
+/// Maximum number of redirects followed per link.
+const MAX_REDIRECTS: usize = 10;
+
+/// Which links to check, relative to the page's origin.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkScope {
+    /// Every link.
+    #[default]
+    All,
+    /// Links to the page's own origin.
+    Internal,
+    /// Links to other origins.
+    External,
+}
+
+/// A link found on a page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageLink {
+    /// Absolute URL of the link.
+    pub url: String,
+    /// Visible text of the link.
+    #[serde(default)]
+    pub text: String,
+}
+
+/// Links of a page, read by [`links_script`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageLinks {
+    /// URL of the page.
+    pub url: String,
+    pub links: Vec<PageLink>,
+}
+
+/// JavaScript expression evaluating to the [`PageLinks`] of the document, or of
+/// the first element matching `selector`.
+pub fn links_script(selector: Option<&str>) -> String {
+    let selector = selector
+        .map(|s| serde_json::to_string(s).unwrap_or_default())
+        .unwrap_or_else(|| "null".to_string());
+    format!(
+        r#"(function(selector) {{
+    var root = selector ? document.querySelector(selector) : document;
+    var anchors = root ? root.querySelectorAll('a[href], area[href]') : [];
+    return {{
+        url: location.href,
+        links: Array.prototype.map.call(anchors, function(a) {{
+            return {{ url: a.href, text: (a.innerText || a.getAttribute('aria-label') || '').trim() }};
+        }})
+    }};
+}})({})"#,
+        selector
+    )
+}
+
+/// Result of checking one link.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct LinkStatus {
+    /// URL of the link, without its fragment.
+    pub url: String,
+    /// Text of the first link to the URL.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub text: String,
+    /// HTTP status code; absent when the request failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Whether the link works: it answered with a status below 400.
+    pub ok: bool,
+    /// Final URL when the link redirects.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirected_to: Option<String>,
+    /// Why the request failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Links to check: the HTTP(S) links of a page in `scope`, without fragments
+/// or duplicates, in page order. Returns them, at most [`MAX_LINKS`], together
+/// with how many there were in total.
+pub fn select_links(page: &PageLinks, scope: LinkScope) -> (Vec<PageLink>, usize) {
+    let origin = reqwest::Url::parse(&page.url).ok().map(|url| url.origin());
+    let mut seen = HashSet::new();
+    let mut selected = Vec::new();
+    for link in &page.links {
+        let Ok(mut url) = reqwest::Url::parse(&link.url) else {
+            continue;
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            continue;
+        }
+        url.set_fragment(None);
+        let internal = origin.as_ref() == Some(&url.origin());
+        let in_scope = match scope {
+            LinkScope::All => true,
+            LinkScope::Internal => internal,
+            LinkScope::External => !internal,
+        };
+        if in_scope && seen.insert(url.to_string()) {
+            selected.push(PageLink {
+                url: url.to_string(),
+                text: link.text.chars().take(MAX_TEXT_CHARS).collect(),
+            });
+        }
+    }
+    let total = selected.len();
+    selected.truncate(MAX_LINKS);
+    (selected, total)
+}
+
+/// Whether an address is reachable from the internet, rather than the
+/// server's own host or network: not loopback, link-local (where cloud
+/// metadata services live), private, shared or unspecified.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || first == 0
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (first == 100 && second & 0xC0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    // Unique local, fc00::/7, and link-local, fe80::/10
+                    || first & 0xFE00 == 0xFC00
+                    || first & 0xFFC0 == 0xFE80)
+            }
+        },
+    }
+}
+
+/// Refuse a URL whose host is an internal address. Host names are checked
+/// when they are resolved, by [`PublicResolver`].
+fn check_host(url: &Url) -> Result<(), String> {
+    let host = url.host_str().ok_or_else(|| "no host".to_string())?;
+    match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) if !is_public(ip) => Err(format!("refused: {} is an internal address", ip)),
+        _ => Ok(()),
+    }
+}
+
+/// Resolver refusing host names with an internal address, so that neither a
+/// name nor a DNS answer changed after the page was checked leads there.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+                return Err(format!(
+                    "refused: {} resolves to the internal address {}",
+                    host,
+                    addr.ip()
+                )
+                .into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Check links, a few at a time. Results are in the order of `links`.
+/// With `allow_private`, internal addresses are requested too.
+pub async fn check(
+    links: Vec<PageLink>,
+    politeness: &Politeness,
+    allow_private: bool,
+) -> Vec<LinkStatus> {
+    // Redirects are followed by hand so every hop is checked. Proxies would
+    // resolve hosts out of reach of the resolver, so requests go direct.
+    let mut builder = reqwest::Client::builder()
+        .timeout(CHECK_TIMEOUT)
+        .user_agent(ROBOTS_USER_AGENT)
+        .redirect(reqwest::redirect::Policy::none());
+    if !allow_private {
+        builder = builder.no_proxy().dns_resolver(PublicResolver);
+    }
+    let client = builder.build().unwrap_or_default();
+    futures::stream::iter(links)
+        .map(|link| check_one(&client, politeness, allow_private, link))
+        .buffered(CONCURRENCY)
+        .collect()
+        .await
+}
+
+async fn check_one(
+    client: &reqwest::Client,
+    politeness: &Politeness,
+    allow_private: bool,
+    link: PageLink,
+) -> LinkStatus {
+    let failed = |link: PageLink, error: String| LinkStatus {
+        url: link.url,
+        text: link.text,
+        status: None,
+        ok: false,
+        redirected_to: None,
+        error: Some(error),
+    };
+    let Ok(mut url) = Url::parse(&link.url) else {
+        return failed(link, "invalid URL".to_string());
+    };
+    for _ in 0..=MAX_REDIRECTS {
+        if !allow_private {
+            if let Err(e) = check_host(&url) {
+                return failed(link, e);
+            }
+        }
+        if let Err(e) = politeness.before_navigation(url.as_str()).await {
+            return failed(link, e.to_string());
+        }
+
+        let mut response = client.head(url.clone()).send().await;
+        // Some servers reject or do not implement HEAD
+        if let Ok(ref head) = response {
+            let status = head.status().as_u16();
+            if status == 405 || status == 501 {
+                response = client.get(url.clone()).send().await;
+            }
+        }
+        let response = match response {
+            Ok(response) => response,
+            Err(e) if e.is_timeout() => return failed(link, "timed out".to_string()),
+            Err(e) => {
+                // Refusals by the resolver are only in the error's sources
+                let mut error = e.to_string();
+                let mut source = e.source();
+                while let Some(cause) = source {
+                    error.push_str(&format!(": {}", cause));
+                    source = cause.source();
+                }
+                return failed(link, error);
+            }
+        };
+
+        let status = response.status();
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|location| url.join(location).ok());
+        match location {
+            Some(next) if status.is_redirection() => url = next,
+            _ => {
+                let final_url = url.to_string();
+                return LinkStatus {
+                    redirected_to: (final_url != link.url).then_some(final_url),
+                    url: link.url,
+                    text: link.text,
+                    status: Some(status.as_u16()),
+                    ok: status.as_u16() < 400,
+                    error: None,
+                };
+            }
+        }
+    }
+    failed(link, format!("more than {} redirects", MAX_REDIRECTS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_links() {
+        let link = |url: &str| PageLink {
+            url: url.to_string(),
+            text: "Docs".to_string(),
+        };
+        let page = PageLinks {
+            url: "https://a.test/docs/".to_string(),
+            links: vec![
+                link("https://a.test/docs/intro"),
+                link("https://a.test/docs/intro#setup"),
+                link("https://b.test/"),
+                link("mailto:team@a.test"),
+                link("javascript:void(0)"),
+                link("http://a.test/docs/intro"),
+            ],
+        };
+
+        let (all, total) = select_links(&page, LinkScope::All);
+        let urls: Vec<&str> = all.iter().map(|l| l.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://a.test/docs/intro",
+                "https://b.test/",
+                "http://a.test/docs/intro"
+            ]
+        );
+        assert_eq!(total, 3);
+
+        let (internal, _) = select_links(&page, LinkScope::Internal);
+        assert_eq!(internal.len(), 1);
+        let (external, _) = select_links(&page, LinkScope::External);
+        assert_eq!(external.len(), 2);
+
+        let page = PageLinks {
+            url: "https://a.test/".to_string(),
+            links: (0..MAX_LINKS + 5)
+                .map(|i| link(&format!("https://a.test/{}", i)))
+                .collect(),
+        };
+        let (links, total) = select_links(&page, LinkScope::All);
+        assert_eq!((links.len(), total), (MAX_LINKS, MAX_LINKS + 5));
+    }
+
+    #[tokio::test]
+    async fn test_internal_addresses_refused() {
+        for ip in ["127.0.0.1", "10.1.2.3", "169.254.169.254", "::1", "fd00::1"] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        assert!(!is_public("::ffff:192.168.0.1".parse().unwrap()));
+        assert!(is_public("93.184.216.34".parse().unwrap()));
+
+        let link = |url: &str| PageLink {
+            url: url.to_string(),
+            text: String::new(),
+        };
+        let politeness = Politeness::new(false, Duration::ZERO);
+        let links = vec![
+            link("http://169.254.169.254/latest/meta-data/"),
+            link("http://[::1]:8080/"),
+            link("http://localhost:1/"),
+        ];
+        for status in check(links, &politeness, false).await {
+            assert!(!status.ok);
+            assert!(status.error.unwrap().contains("refused"), "{}", status.url);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Logging setup.
+//!
+//! Logs never go to stdout, which carries the MCP protocol in stdio mode. They
+//! are written to stderr by default, or to a rotating file when `MCP_LOG_FILE`
+//! is set. `MCP_LOG_FORMAT=json` switches to one JSON object per line for log
+//! collectors.
+//!
+//! Logging is configured from the environment directly rather than through
+//! [`crate::config::Config`], since it must be running before the configuration
+//! is loaded to report problems with it.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::{fmt, prelude::*, EnvFilter, Layer, Registry};
+
+/// Number of rotated log files kept by default.
+const DEFAULT_MAX_LOG_FILES: usize = 7;
+
+/// Output format of log lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Pretty,
+    /// One JSON object per line.
+    Json,
+}
+
+/// How often the log file is rotated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    /// Always write to the same file.
+    Never,
+}
+
+/// Logging options read from the environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogSettings {
+    /// Line format (`MCP_LOG_FORMAT`).
+    pub format: LogFormat,
+    /// Log file path; logs go to stderr when unset (`MCP_LOG_FILE`).
+    /// With rotation, the current date and time are appended to the file name.
+    pub file: Option<PathBuf>,
+    /// Rotation period of the log file (`MCP_LOG_ROTATION`).
+    pub rotation: LogRotation,
+    /// Number of rotated files to keep, 0 to keep all (`MCP_LOG_MAX_FILES`).
+    pub max_files: usize,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::default(),
+            file: None,
+            rotation: LogRotation::default(),
+            max_files: DEFAULT_MAX_LOG_FILES,
+        }
+    }
+}
+
+impl LogSettings {
+    /// Read settings from environment variables.
+    ///
+    /// Invalid values fall back to defaults; the returned warnings should be
+    /// logged once logging is initialized.
+    pub fn from_env() -> (Self, Vec<String>) {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> (Self, Vec<String>) {
+        let mut settings = Self::default();
+        let mut warnings = Vec::new();
+
+        if let Some(format) = var("MCP_LOG_FORMAT") {
+            settings.format = match format.trim().to_lowercase().as_str() {
+                "json" => LogFormat::Json,
+                "pretty" => LogFormat::Pretty,
+                _ => {
+                    warnings.push(format!(
+                        "Invalid MCP_LOG_FORMAT '{}', using default pretty",
+                        format
+                    ));
+                    LogFormat::Pretty
+                }
+            };
+        }
+
+        if let Some(file) = var("MCP_LOG_FILE") {
+            let file = file.trim();
+            if !file.is_empty() {
+                settings.file = Some(PathBuf::from(file));
+            }
+        }
+
+        if let Some(rotation) = var("MCP_LOG_ROTATION") {
+            settings.rotation = match rotation.trim().to_lowercase().as_str() {
+                "minutely" => LogRotation::Minutely,
+                "hourly" => LogRotation::Hourly,
+                "daily" => LogRotation::Daily,
+                "never" => LogRotation::Never,
+                _ => {
+                    warnings.push(format!(
+                        "Invalid MCP_LOG_ROTATION '{}', using default daily",
+                        rotation
+                    ));
+                    LogRotation::Daily
+                }
+            };
+        }
+
+        if let Some(max_files) = var("MCP_LOG_MAX_FILES") {
+            match max_files.trim().parse() {
+                Ok(n) => settings.max_files = n,
+                Err(e) => warnings.push(format!(
+                    "Invalid MCP_LOG_MAX_FILES '{}': {}, using default {}",
+                    max_files, e, DEFAULT_MAX_LOG_FILES
+                )),
+            }
+        }
+
+        (settings, warnings)
+    }
+}
+
+/// Install the global tracing subscriber.
+///
+/// Returns a guard that flushes buffered file output when dropped; it must be
+/// held until the process exits.
+pub fn init() -> Result<Option<WorkerGuard>> {
+    let (settings, warnings) = LogSettings::from_env();
+
+    let (writer, guard) = match settings.file {
+        Some(ref path) => {
+            let appender = file_appender(path, settings.rotation, settings.max_files)?;
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (BoxMakeWriter::new(writer), Some(guard))
+        }
+        None => (BoxMakeWriter::new(std::io::stderr), None),
+    };
+    // Escape codes are only useful on a terminal
+    let ansi = settings.file.is_none();
+
+    let layer: Box<dyn Layer<Registry> + Send + Sync> = match settings.format {
+        LogFormat::Pretty => fmt::layer().with_writer(writer).with_ansi(ansi).boxed(),
+        LogFormat::Json => fmt::layer().json().with_writer(writer).boxed(),
+    };
+
+    tracing_subscriber::registry()
+        .with(layer)
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .init();
+
+    for warning in warnings {
+        tracing::warn!("{}", warning);
+    }
+    Ok(guard)
+}
+
+/// Create a rotating appender writing to `path`.
+fn file_appender(
+    path: &Path,
+    rotation: LogRotation,
+    max_files: usize,
+) -> Result<RollingFileAppender> {
+    let directory = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("MCP_LOG_FILE '{}' has no file name", path.display()))?;
+
+    let mut builder = RollingFileAppender::builder()
+        .rotation(match rotation {
+            LogRotation::Minutely => Rotation::MINUTELY,
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Never => Rotation::NEVER,
+        })
+        .filename_prefix(file_name.to_string_lossy());
+    if max_files > 0 {
+        builder = builder.max_log_files(max_files);
+    }
+    builder
+        .build(directory)
+        .with_context(|| format!("Failed to open log file '{}'", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn settings(vars: &[(&str, &str)]) -> (LogSettings, Vec<String>) {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        LogSettings::from_vars(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_log_settings_from_vars() {
+        let (defaults, warnings) = settings(&[]);
+        assert_eq!(defaults, LogSettings::default());
+        assert!(warnings.is_empty());
+
+        let (parsed, warnings) = settings(&[
+            ("MCP_LOG_FORMAT", "JSON"),
+            ("MCP_LOG_FILE", "/var/log/mcp/server.log"),
+            ("MCP_LOG_ROTATION", "hourly"),
+            ("MCP_LOG_MAX_FILES", "24"),
+        ]);
+        assert!(warnings.is_empty());
+        assert_eq!(
+            parsed,
+            LogSettings {
+                format: LogFormat::Json,
+                file: Some(PathBuf::from("/var/log/mcp/server.log")),
+                rotation: LogRotation::Hourly,
+                max_files: 24,
+            }
+        );
+
+        let (invalid, warnings) = settings(&[
+            ("MCP_LOG_FORMAT", "xml"),
+            ("MCP_LOG_ROTATION", "weekly"),
+            ("MCP_LOG_MAX_FILES", "many"),
+        ]);
+        assert_eq!(invalid, LogSettings::default());
+        assert_eq!(warnings.len(), 3);
+    }
+}
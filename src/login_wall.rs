@@ -0,0 +1,107 @@
+//! Detection of login walls and expired sessions.
+//!
+//! An agent that was logged out sees a sign-in form where it expected the
+//! page it asked for, and may misread the screenshot as a page that failed to
+//! load or a form to fill. With `MCP_DETECT_LOGIN` enabled, every state
+//! response carries `login_required: true` when the page looks like it wants
+//! the user to sign in: its URL is a known login path (where sites redirect
+//! visitors without a session), the main document was answered with 401 or
+//! 403, or a visible password field is on the page.
+
+use serde::Deserialize;
+
+/// Path segments of login pages.
+const LOGIN_SEGMENTS: &[&str] = &[
+    "login",
+    "log-in",
+    "log_in",
+    "logon",
+    "signin",
+    "sign-in",
+    "sign_in",
+    "sso",
+    "authenticate",
+    "session-expired",
+];
+
+/// Hosts serving only sign-in pages.
+const LOGIN_HOSTS: &[&str] = &[
+    "accounts.google.com",
+    "login.microsoftonline.com",
+    "login.live.com",
+    "appleid.apple.com",
+];
+
+/// JavaScript expression returning the page's [`LoginSignals`]. The status
+/// code of the main document comes from the Navigation Timing API and is
+/// missing in browsers that do not report it.
+pub const SIGNALS_SCRIPT: &str = r#"(function() {
+    var entry = performance.getEntriesByType('navigation')[0];
+    var status = entry && entry.responseStatus ? entry.responseStatus : null;
+    var passwordFields = Array.prototype.filter.call(
+        document.querySelectorAll('input[type=password]'),
+        function(input) {
+            var rect = input.getBoundingClientRect();
+            return rect.width > 0 && rect.height > 0 && getComputedStyle(input).visibility !== 'hidden';
+        }).length;
+    return { status: status, passwordFields: passwordFields };
+})()"#;
+
+/// What the page reveals about needing a login.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginSignals {
+    /// HTTP status code of the main document.
+    #[serde(default)]
+    pub status: Option<u16>,
+    /// Number of visible password fields.
+    #[serde(default)]
+    pub password_fields: u32,
+}
+
+/// Whether `url` is a login page by its host or path.
+pub fn is_login_url(url: &str) -> bool {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    if parsed
+        .host_str()
+        .is_some_and(|host| LOGIN_HOSTS.contains(&host) || host.ends_with(".okta.com"))
+    {
+        return true;
+    }
+    parsed.path_segments().is_some_and(|mut segments| {
+        segments.any(|segment| LOGIN_SEGMENTS.contains(&segment.to_ascii_lowercase().as_str()))
+    })
+}
+
+/// Whether the page at `url` asks the user to sign in.
+pub fn login_required(url: &str, signals: &LoginSignals) -> bool {
+    is_login_url(url) || matches!(signals.status, Some(401 | 403)) || signals.password_fields > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_login_required() {
+        let none = LoginSignals::default();
+        assert!(login_required(
+            "https://shop.test/users/sign_in?return_to=%2Fcart",
+            &none
+        ));
+        assert!(login_required("https://acme.okta.com/app/x", &none));
+        assert!(!login_required("https://shop.test/blog/login-tips", &none));
+        assert!(!login_required("https://shop.test/cart", &none));
+
+        let forbidden = LoginSignals {
+            status: Some(403),
+            password_fields: 0,
+        };
+        assert!(login_required("https://shop.test/cart", &forbidden));
+        let form: LoginSignals =
+            serde_json::from_str(r#"{"status": 200, "passwordFields": 1}"#).unwrap();
+        assert!(login_required("https://shop.test/cart", &form));
+    }
+}
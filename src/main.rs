@@ -10,25 +10,99 @@
 //!
 //! - `MCP_BROWSER_PATH`: Path to the browser binary (auto-detected if not set)
 //! - `MCP_WEBDRIVER_URL`: WebDriver server URL (auto-determined when MCP_AUTO_START=true)
+//! - `MCP_WEBDRIVER_PAGE_LOAD_TIMEOUT`: Page load timeout of the WebDriver session (default: 5m)
+//! - `MCP_WEBDRIVER_SCRIPT_TIMEOUT`: Script timeout of the WebDriver session (default: 30s)
+//! - `MCP_WEBDRIVER_KEEPALIVE_INTERVAL`: Interval of keep-alive commands to an idle WebDriver session, "0" to disable (default: 60s)
 //! - `MCP_BROWSER_TYPE`: Browser type: `chrome`, `edge`, `firefox`, or `safari`
 //! - `MCP_SCREEN_WIDTH`: Screen width in pixels (default: 1280)
 //! - `MCP_SCREEN_HEIGHT`: Screen height in pixels (default: 720)
 //! - `MCP_INITIAL_URL`: Initial URL to load (default: https://www.google.com)
-//! - `MCP_SEARCH_ENGINE_URL`: Search engine URL (default: https://www.google.com)
+//! - `MCP_APP_MODE_URL`: Open this URL as a Chrome/Edge app window without tabs or omnibox, instead of the initial URL
+//! - `MCP_INITIAL_TABS`: Comma-separated URLs to open in additional tabs at startup
+//! - `MCP_SEARCH_ENGINES`: Extra search engines as comma-separated `name=url` pairs, with `{query}` in the URL
+//! - `MCP_SEARCH_ENGINE`: Default search engine name (default: google)
+//! - `MCP_SEARCH_ENGINE_URL`: Single search engine URL, added as the default engine `custom`
 //! - `MCP_HEADLESS`: Run in headless mode (default: true)
+//! - `MCP_HEADLESS_MODE`: Headless implementation for Chrome and Edge: new or old (default: new)
+//! - `MCP_VIRTUAL_DISPLAY`: Start an Xvfb display for a headful browser on Linux hosts without a display (default: false)
 //! - `MCP_DISABLED_TOOLS`: Comma-separated list of tools to disable
 //! - `MCP_TRANSPORT`: Transport mode: stdio or http (default: stdio)
 //! - `MCP_HTTP_HOST`: HTTP server host (default: 127.0.0.1)
 //! - `MCP_HTTP_PORT`: HTTP server port (default: 8080)
+//! - `MCP_HTTP_AUTH_TOKEN`: Comma-separated bearer tokens required on the HTTP `/mcp` endpoint
+//! - `MCP_HTTP_CORS_ORIGINS`: Comma-separated origins allowed for CORS, or `*` for any
+//! - `MCP_HTTP_BASE_PATH`: Path prefix for HTTP endpoints when behind a reverse proxy
+//! - `MCP_HTTP_SESSION_TIMEOUT`: Inactivity period after which an HTTP session and its browser are closed, "0" to disable (default: 30m)
 //! - `MCP_AUTO_START`: Automatically manage browser/driver lifecycle (default: false)
 //! - `MCP_AUTO_DOWNLOAD_DRIVER`: Download driver if not found (default: false)
 //! - `MCP_DRIVER_PATH`: Path to browser driver executable (auto-detected if not set)
 //! - `MCP_DRIVER_PORT`: Port for driver (default: 9515)
 //! - `MCP_UNDETECTED`: Enable undetected/stealth mode (default: false)
+//! - `MCP_WEBRTC_LEAK_PROTECTION`: Keep WebRTC on the proxy so it cannot reveal the real IP; always on in undetected mode (default: false)
+//! - `MCP_FAKE_MEDIA_DEVICES`: Provide a fake camera and microphone and grant capture permission (default: false)
 //! - `MCP_CONNECTION_MODE`: Connection mode: webdriver or cdp (default: webdriver)
 //! - `MCP_CDP_PORT`: CDP port for browser connection (default: 9222)
+//! - `MCP_CDP_URL`: CDP URL of an already-running browser to connect to (e.g., http://127.0.0.1:9222)
+//! - `MCP_ATTACHED_MODE`: Treat the CDP browser as the user's own: no launching, closing, or new tabs (default: false)
 //! - `MCP_OPEN_BROWSER_ON_START`: Open browser on MCP server startup (default: false)
 //! - `MCP_IDLE_TIMEOUT`: Idle timeout duration (e.g., "10m", "30s", "0" to disable) (default: 10m)
+//! - `MCP_MEMORY_WARNING_MB`: Browser memory (RSS) warning threshold in MB, 0 to disable (default: 2048)
+//! - `MCP_JS_HEAP_WARNING_MB`: JavaScript heap warning threshold in MB, 0 to disable (default: 512)
+//! - `MCP_MEMORY_CHECK_INTERVAL`: Interval between background memory checks, "0" to disable (default: 60s)
+//! - `MCP_COORDINATE_POLICY`: Handling of coordinates outside the viewport: reject or clamp (default: reject)
+//! - `MCP_PRIMARY_MODIFIER`: Modifier for Control shortcuts: auto (Command on headful macOS), control, or meta (default: auto)
+//! - `MCP_CAPTURE_NOTIFICATIONS`: Record web notifications and permission prompts (default: true)
+//! - `MCP_NOTIFICATIONS_IN_RESPONSE`: Include new notifications in action responses (default: false)
+//! - `MCP_MUTE_AUDIO`: Start the browser with its audio muted (default: true)
+//! - `MCP_BLANK_SCREENSHOT_RETRIES`: Times a blank screenshot is captured again before it is returned, 0 to disable (default: 2)
+//! - `MCP_ZOOM_TO_FIT`: Zoom pages wider than the viewport out to fit it before each capture (default: false)
+//! - `MCP_ZOOM_TO_FIT_MIN`: Smallest zoom in percent used to fit a page, 25 to 100 (default: 50)
+//! - `MCP_MAX_SCREENSHOT_DIMENSION`: Largest screenshot width or height; larger screenshots are scaled down, 0 for full size (default: 0)
+//! - `MCP_SCREENSHOT_POLICY`: When action tools attach screenshots: always, on-change, never, or on-demand (default: always)
+//! - `MCP_CREDENTIALS_FILE`: TOML file of saved site credentials for login_with_saved_credentials; disables evaluate_javascript unless `MCP_DISABLED_TOOLS` is set
+//! - `MCP_RESPECT_ROBOTS_TXT`: Refuse navigations that the site's robots.txt disallows (default: false)
+//! - `MCP_POLITENESS_DELAY`: Minimum delay between navigations to the same host, e.g. 5s (default: 0, disabled)
+//! - `MCP_CHECK_LINKS_ALLOW_PRIVATE`: Let check_links request loopback, link-local and private addresses (default: false)
+//! - `MCP_MAX_NAVIGATIONS_PER_DOMAIN`: Navigations to one domain before responses carry a warning (default: 0, unlimited)
+//! - `MCP_DETECT_NAVIGATION_LOOPS`: Warn in responses when the agent returns to the page it just left (default: true)
+//! - `MCP_STATE_DELTA`: Summarize changes since the previous state in responses: URL, title, DOM mutations, console errors (default: false)
+//! - `MCP_DETECT_LOGIN`: Flag pages asking to sign in with login_required in responses (default: true)
+//! - `MCP_RESTORE_SCROLL`: Scroll pages reached with go_back/go_forward back to where they were last shown (default: true)
+//! - `MCP_CDP_JS_CLICK`: Click with JavaScript instead of native mouse events in CDP mode (default: false)
+//! - `MCP_CLICK_STABILITY_TIMEOUT`: Wait up to this long for the element under a click to stop moving, e.g. 2s (default: 0, disabled)
+//! - `MCP_RECIPES_DIR`: Directory of site recipes (TOML or JSON) with known selectors, wait rules and blocked tools
+//! - `MCP_WORKSPACES_DIR`: Directory where save_workspace stores workspaces (default: mcp-computer-use/workspaces in the user's data directory)
+//! - `MCP_BASELINES_DIR`: Directory where compare_screenshot stores baselines (default: mcp-computer-use/baselines in the user's data directory)
+//! - `MCP_OCR_LANGUAGES`: Tesseract languages ocr_screenshot recognizes by default, e.g. eng,deu (default: eng; needs the ocr feature)
+//! - `MCP_TESSERACT_PATH`: Tesseract command ocr_screenshot runs (default: tesseract; needs the ocr feature)
+//! - `MCP_AXE_CORE_PATH`: Local axe.min.js for audit_accessibility (default: the copy bundled with the bundled-axe-core feature)
+//! - `MCP_SCRATCH_DIR`: Base directory of the per-session scratch directories (default: mcp-computer-use/sessions in the temp directory)
+//! - `MCP_ALLOWED_PATHS`: Directories (separated like PATH) the files tools read and write must be in, besides the session's scratch directory (default: any)
+//! - `MCP_MAX_FILE_MB`: Maximum size of a file tools read or write (default: 0, no limit)
+//! - `MCP_ALLOWED_FILE_EXTENSIONS`: Comma-separated extensions the files tools read and write must have (default: any)
+//! - `MCP_BLOCKED_FILE_EXTENSIONS`: Comma-separated extensions the files tools read and write must not have
+//! - `MCP_DOWNLOAD_ALLOWED_TYPES`: Comma-separated content types downloads may have, e.g. `application/pdf,image/*` (default: any)
+//! - `MCP_DOWNLOAD_MAX_MB`: Maximum size of a download (default: 100, 0 for no limit)
+//! - `MCP_DOWNLOAD_ALLOW_EXECUTABLES`: Whether executables and scripts may be downloaded (default: false)
+//! - `MCP_RECORD_CASSETTE`: File the browser's replies are recorded to, for replay with MCP_REPLAY_CASSETTE
+//! - `MCP_REPLAY_CASSETTE`: Cassette whose recorded replies are returned instead of starting a browser
+//! - `MCP_APPROVAL_WEBHOOK_URL`: Operator endpoint asked to approve tool calls matching MCP_APPROVAL_RULES
+//! - `MCP_APPROVAL_WEBHOOK_TOKEN`: Bearer token sent to the approval webhook
+//! - `MCP_APPROVAL_RULES`: Comma-separated `tool` or `tool@domain` rules, `*` matching any characters in tool names
+//! - `MCP_APPROVAL_TIMEOUT`: Time to wait for an approval decision (default: 2m)
+//! - `MCP_APPROVAL_ON_TIMEOUT`: `deny` or `allow` calls without a decision in time (default: deny)
+//! - `MCP_ACTION_HISTORY_SIZE`: Number of recent tool calls kept for get_action_history, 0 to disable (default: 50)
+//! - `MCP_SCREENSHOT_RETENTION`: Number of recent screenshots kept for get_screenshot, 0 to disable (default: 50)
+//! - `MCP_SCREENSHOT_STORE_MAX_MB`: Maximum total size of a session's stored screenshots (default: 100)
+//! - `MCP_SCREENSHOT_DIR`: Directory for stored screenshots (default: mcp-computer-use/screenshots in the temp directory)
+//! - `MCP_SCREENSHOT_S3_BUCKET`: Store screenshots in this S3 bucket instead (requires the s3-screenshots feature)
+//! - `MCP_SCREENSHOT_S3_ENDPOINT`: S3-compatible endpoint (default: AWS S3 in the region)
+//! - `MCP_SCREENSHOT_S3_REGION`: Region of the bucket (default: us-east-1)
+//! - `MCP_SCREENSHOT_S3_PREFIX`: Key prefix of stored screenshots (default: screenshots/)
+//! - `MCP_LOG_FORMAT`: Log line format: pretty or json (default: pretty)
+//! - `MCP_LOG_FILE`: Write logs to this file instead of stderr
+//! - `MCP_LOG_ROTATION`: Log file rotation: minutely, hourly, daily, or never (default: daily)
+//! - `MCP_LOG_MAX_FILES`: Number of rotated log files to keep, 0 to keep all (default: 7)
 //!
 //! # Usage
 //!
@@ -37,34 +111,97 @@
 //! 3. For CDP mode: set MCP_CONNECTION_MODE=cdp with MCP_AUTO_START=true
 //! 4. Use MCP_OPEN_BROWSER_ON_START=true to pre-open browser on startup
 //! 5. Run this MCP server and connect an MCP client
+//! 6. Run with `--bench-mode` to time screenshots and actions in the configured browser instead of serving
 
+mod accessibility;
+mod action_history;
+mod action_queue;
+mod approval;
+mod bench;
 mod browser;
 mod browser_manager;
+mod browser_stats;
+mod cassette;
 mod cdp_browser;
+mod checkpoint;
 mod config;
+mod context_strips;
+mod credentials;
+mod dom_snapshot;
+mod draw_path;
 mod driver;
+mod element_aliases;
+mod element_locator;
+mod emulation;
+mod evaluate;
+mod grounding;
+#[cfg(feature = "http-server")]
+mod health;
+#[cfg(feature = "http-server")]
+mod http_auth;
+mod input_controls;
+mod keyboard_focus;
+mod keys;
+mod link_check;
+mod logging;
+mod login_wall;
+mod media;
+mod media_devices;
+mod metrics;
+#[cfg(test)]
+mod mock_backend;
+mod navigation;
+mod notifications;
+mod oauth;
+#[cfg(feature = "ocr")]
+mod ocr;
+mod page_events;
+mod page_watch;
+mod path_policy;
+mod politeness;
+mod quarantine;
+mod recipes;
+#[cfg(feature = "s3-screenshots")]
+mod s3;
+mod scratch;
+mod screenshot_diff;
+mod screenshot_scale;
+mod screenshot_store;
+mod scroll_memory;
+mod search_engine;
+mod shortcuts;
+mod state_delta;
 mod tools;
+mod virtual_display;
+mod visual_diff;
+mod web_search;
+mod workspace;
+mod zoom_to_fit;
 
+use crate::cassette::Cassette;
 use crate::config::{Config, ConnectionMode, TransportMode};
+use crate::credentials::CredentialStore;
 use crate::driver::DriverManager;
+use crate::recipes::RecipeRegistry;
 use crate::tools::BrowserMcpServer;
+use crate::virtual_display::VirtualDisplay;
 use rmcp::transport::stdio;
 use rmcp::ServiceExt;
 use tracing::{error, info, warn};
-use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 #[cfg(feature = "http-server")]
 use rmcp::transport::streamable_http_server::{
-    session::local::LocalSessionManager, StreamableHttpServerConfig, StreamableHttpService,
+    session::local::{LocalSessionManager, SessionConfig},
+    StreamableHttpServerConfig, StreamableHttpService,
 };
 
+/// Upper bound on closing the browser during shutdown, so a hung driver cannot block exit.
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize logging
-    tracing_subscriber::registry()
-        .with(fmt::layer().with_writer(std::io::stderr))
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
-        .init();
+    // Initialize logging; the guard flushes the log file on exit
+    let _log_guard = logging::init()?;
 
     info!(
         "Starting MCP Computer Use server v{}",
@@ -75,12 +212,53 @@ async fn main() -> anyhow::Result<()> {
     let mut config = Config::load()?;
     info!("Configuration loaded: {:?}", config);
 
+    // Catch a broken credentials file at startup rather than at the first login
+    if let Some(ref path) = config.credentials_file {
+        let store = CredentialStore::load(path)?;
+        info!("Saved credentials registered for {} sites", store.len());
+    }
+
+    if let Some(ref path) = config.recipes_dir {
+        let recipes = RecipeRegistry::load_dir(path)?;
+        info!("Loaded {} site recipes from {:?}", recipes.len(), path);
+        config.recipes = std::sync::Arc::new(recipes);
+    }
+
+    if let Some(ref path) = config.replay_cassette {
+        let cassette = Cassette::replay(path)?;
+        info!(
+            "Replaying {} recorded replies from {:?}; no browser is started",
+            cassette.remaining(),
+            path
+        );
+        config.cassette = Some(std::sync::Arc::new(cassette));
+    } else if let Some(ref path) = config.record_cassette {
+        config.cassette = Some(std::sync::Arc::new(Cassette::record(path)?));
+        info!("Recording browser replies to {:?}", path);
+    }
+    let replaying = config.cassette.as_ref().is_some_and(|c| c.is_replay());
+
+    // A headful browser on a display-less host runs on a virtual display,
+    // kept until the server exits
+    let _virtual_display = if virtual_display::is_needed(&config) {
+        let display = VirtualDisplay::start(&config)?;
+        config.display = Some(display.display().to_string());
+        Some(display)
+    } else {
+        None
+    };
+
     // Initialize driver manager (only for WebDriver mode)
     let mut driver_manager = DriverManager::new();
 
     // Setup based on connection mode
     match config.connection_mode {
+        // Replies come from the cassette
+        _ if replaying => {}
         ConnectionMode::WebDriver => {
+            if config.attached_mode {
+                warn!("MCP_ATTACHED_MODE only applies to CDP mode and is ignored");
+            }
             // Ensure driver is ready (finds/downloads/launches if auto_start is enabled)
             match driver_manager.ensure_driver_ready(&config) {
                 Ok(url) => {
@@ -99,15 +277,36 @@ async fn main() -> anyhow::Result<()> {
             // CDP mode uses direct CDP connection without WebDriver
             info!("Using CDP (Chrome DevTools Protocol) mode - no WebDriver required");
             let cdp_port = config.effective_cdp_port();
+            let browser_manager = driver_manager.browser_manager();
 
-            if config.auto_start && config.open_browser_on_start {
+            if let Some(ref url) = config.cdp_url {
+                // An explicit CDP URL points at a browser the user is running; connect to it
+                // when the browser is opened and leave it running when the server exits.
+                info!("Will connect to existing browser at CDP URL: {}", url);
+            } else if browser_manager.is_cdp_available(cdp_port) {
+                // A browser is already listening (started by the user or a previous run):
+                // reuse it instead of launching a second one on the same port.
+                // It is not ours, so it keeps running when the server exits.
+                info!(
+                    "CDP endpoint available at port {}, will connect to existing browser",
+                    cdp_port
+                );
+                config.cdp_url = Some(format!("http://127.0.0.1:{}", cdp_port));
+            } else if config.attached_mode {
+                return Err(anyhow::anyhow!(
+                    "Attached mode needs a running browser, but no CDP endpoint is available at port {}. \
+                     Start Chrome with --remote-debugging-port={} or set MCP_CDP_URL.",
+                    cdp_port,
+                    cdp_port
+                ));
+            } else if config.auto_start && config.open_browser_on_start {
                 // Auto-start with open_browser_on_start: launch browser with CDP enabled now
-                match driver_manager
-                    .browser_manager()
-                    .launch_browser_with_cdp(&config)
-                {
+                match browser_manager.launch_browser_with_cdp(&config) {
                     Ok(cdp_url) => {
-                        info!("Browser launched with CDP at: {}", cdp_url);
+                        info!(
+                            "Browser launched with CDP at: {} (closed when the server exits)",
+                            cdp_url
+                        );
                         // Store CDP URL for later use - browser will be controlled directly via CDP
                         config.cdp_url = Some(cdp_url);
                     }
@@ -123,27 +322,24 @@ async fn main() -> anyhow::Result<()> {
                     "CDP auto-start mode enabled, browser will be launched on-demand via open_web_browser tool"
                 );
             } else {
-                // Check if CDP endpoint is available (user started browser manually)
-                if !driver_manager.browser_manager().is_cdp_available(cdp_port) {
-                    return Err(anyhow::anyhow!(
-                        "CDP endpoint not available at port {}. \
-                         Please start Chrome with --remote-debugging-port={}, \
-                         or enable MCP_AUTO_START=true to launch browser automatically.",
-                        cdp_port,
-                        cdp_port
-                    ));
-                }
-                info!(
-                    "CDP endpoint available at port {}, will connect to existing browser",
+                return Err(anyhow::anyhow!(
+                    "CDP endpoint not available at port {}. \
+                     Please start Chrome with --remote-debugging-port={}, \
+                     or enable MCP_AUTO_START=true to launch browser automatically.",
+                    cdp_port,
                     cdp_port
-                );
-                // Store CDP URL for connecting to the existing browser
-                config.cdp_url = Some(format!("http://127.0.0.1:{}", cdp_port));
+                ));
             }
             // No ChromeDriver needed in CDP mode - we use chromiumoxide directly
         }
     }
 
+    if std::env::args().any(|arg| arg == bench::FLAG) {
+        bench::run(config).await?;
+        driver_manager.stop();
+        return Ok(());
+    }
+
     // Run server based on transport mode
     match config.transport_mode {
         TransportMode::Stdio => {
@@ -164,7 +360,8 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
-    // DriverManager is cleaned up automatically when it goes out of scope
+    // Stop the driver and any browser launched above (also done on drop, for early returns)
+    driver_manager.stop();
     info!("MCP server shutting down");
     Ok(())
 }
@@ -183,18 +380,63 @@ async fn run_stdio_server(config: Config) -> anyhow::Result<()> {
     // reference will properly close the browser.
     let service = server.clone().serve(stdio()).await?;
 
-    // Wait for the service to complete
+    // Stop serving on Ctrl+C/SIGTERM so the cleanup below still runs
+    let cancel = service.cancellation_token();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!("Received shutdown signal");
+        cancel.cancel();
+    });
+
+    // Wait for the service to complete (stdin EOF or shutdown signal)
     service.waiting().await?;
 
     // Always attempt to close the browser session gracefully on exit
     // This ensures the WebDriver/CDP session is properly closed
-    if let Err(e) = server.shutdown().await {
-        warn!("Error during browser shutdown: {}", e);
+    match tokio::time::timeout(SHUTDOWN_TIMEOUT, server.shutdown()).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => warn!("Error during browser shutdown: {}", e),
+        Err(_) => warn!(
+            "Browser shutdown did not finish within {:?}, exiting anyway",
+            SHUTDOWN_TIMEOUT
+        ),
     }
 
     Ok(())
 }
 
+/// Wait for Ctrl+C, or SIGTERM on Unix.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
 /// Run the MCP server using HTTP streamable transport.
 #[cfg(feature = "http-server")]
 async fn run_http_server(config: Config) -> anyhow::Result<()> {
@@ -206,11 +448,12 @@ async fn run_http_server(config: Config) -> anyhow::Result<()> {
     info!("Running MCP server on HTTP at {}...", bind_addr);
 
     // Security warning for non-localhost bindings
-    if config.http_host != "127.0.0.1" && config.http_host != "localhost" {
+    let is_localhost = config.http_host == "127.0.0.1" || config.http_host == "localhost";
+    if !is_localhost && config.http_auth_tokens.is_empty() {
         warn!(
             "⚠️  SECURITY WARNING: HTTP server is binding to '{}' which may expose the MCP endpoint \
-            to the network. The HTTP endpoint has NO authentication. Only bind to non-localhost \
-            addresses if you have proper security measures (TLS, authentication, firewall) in place.",
+            to the network. The HTTP endpoint has NO authentication. Set MCP_HTTP_AUTH_TOKEN, and only \
+            bind to non-localhost addresses if you have proper security measures (TLS, firewall) in place.",
             config.http_host
         );
     }
@@ -225,6 +468,23 @@ async fn run_http_server(config: Config) -> anyhow::Result<()> {
     }
 
     let config = Arc::new(config);
+    // Expired sessions drop their BrowserMcpServer, which closes the session's browser
+    let session_timeout = config.http_session_timeout;
+    if session_timeout.is_zero() {
+        info!("HTTP session expiry disabled (MCP_HTTP_SESSION_TIMEOUT=0)");
+    } else {
+        info!(
+            "HTTP sessions expire after {:?} of inactivity",
+            session_timeout
+        );
+    }
+    let session_manager = Arc::new(LocalSessionManager {
+        session_config: SessionConfig {
+            keep_alive: (!session_timeout.is_zero()).then_some(session_timeout),
+            ..SessionConfig::default()
+        },
+        ..LocalSessionManager::default()
+    });
 
     let service: StreamableHttpService<BrowserMcpServer, LocalSessionManager> =
         StreamableHttpService::new(
@@ -232,14 +492,50 @@ async fn run_http_server(config: Config) -> anyhow::Result<()> {
                 let config = Arc::clone(&config);
                 move || Ok(BrowserMcpServer::new_with_config(Arc::clone(&config)))
             },
-            Default::default(),
+            Arc::clone(&session_manager),
             StreamableHttpServerConfig {
                 stateful_mode: true,
                 sse_keep_alive: Some(std::time::Duration::from_secs(15)),
             },
         );
 
-    let router = axum::Router::new().nest_service("/mcp", service);
+    let mut mcp_router = axum::Router::new().nest_service("/mcp", service);
+    if config.http_auth_tokens.is_empty() {
+        info!("HTTP authentication disabled (MCP_HTTP_AUTH_TOKEN not set)");
+    } else {
+        info!(
+            "HTTP bearer-token authentication enabled ({} token(s))",
+            config.http_auth_tokens.len()
+        );
+        mcp_router = mcp_router.layer(axum::middleware::from_fn_with_state(
+            Arc::new(config.http_auth_tokens.clone()),
+            http_auth::require_bearer_token,
+        ));
+    }
+
+    let base_path = config.http_base_path.clone();
+    let cors = cors_layer(&config.http_cors_origins)?;
+
+    let mut router = mcp_router
+        .route(
+            "/metrics",
+            axum::routing::get(move || metrics_handler(Arc::clone(&session_manager))),
+        )
+        .route("/healthz", axum::routing::get(|| async { "ok" }))
+        .route(
+            "/readyz",
+            axum::routing::get(move || readyz_handler(Arc::clone(&config))),
+        );
+
+    if !base_path.is_empty() {
+        info!("Serving HTTP endpoints under base path {}", base_path);
+        router = axum::Router::new().nest(&base_path, router);
+    }
+
+    // Applied last so preflight requests are answered before authentication
+    if let Some(cors) = cors {
+        router = router.layer(cors);
+    }
 
     let tcp_listener = tokio::net::TcpListener::bind(&bind_addr).await?;
     info!("HTTP server listening on {}", bind_addr);
@@ -247,9 +543,9 @@ async fn run_http_server(config: Config) -> anyhow::Result<()> {
     let ct = CancellationToken::new();
     let ct_clone = ct.clone();
 
-    // Handle Ctrl+C gracefully
+    // Handle Ctrl+C/SIGTERM gracefully
     tokio::spawn(async move {
-        tokio::signal::ctrl_c().await.ok();
+        shutdown_signal().await;
         info!("Received shutdown signal");
         ct_clone.cancel();
     });
@@ -260,3 +556,90 @@ async fn run_http_server(config: Config) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Build the CORS layer for the configured origins, or `None` when CORS is disabled.
+#[cfg(feature = "http-server")]
+fn cors_layer(origins: &[String]) -> anyhow::Result<Option<tower_http::cors::CorsLayer>> {
+    use axum::http::{header, HeaderName, HeaderValue, Method};
+    use tower_http::cors::{AllowOrigin, CorsLayer};
+
+    if origins.is_empty() {
+        return Ok(None);
+    }
+
+    let allow_origin = if origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        let origins = origins
+            .iter()
+            .map(|o| {
+                HeaderValue::from_str(o)
+                    .map_err(|e| anyhow::anyhow!("Invalid CORS origin '{}': {}", o, e))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+
+    info!("CORS enabled for origins: {}", origins.join(", "));
+
+    // Headers used by the MCP streamable HTTP transport
+    let session_id = HeaderName::from_static("mcp-session-id");
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
+            .allow_headers([
+                header::ACCEPT,
+                header::AUTHORIZATION,
+                header::CONTENT_TYPE,
+                session_id.clone(),
+                HeaderName::from_static("mcp-protocol-version"),
+                HeaderName::from_static("last-event-id"),
+            ])
+            .expose_headers([session_id]),
+    ))
+}
+
+/// Serve Prometheus metrics in the text exposition format.
+#[cfg(feature = "http-server")]
+async fn metrics_handler(
+    session_manager: std::sync::Arc<LocalSessionManager>,
+) -> axum::response::Response {
+    use axum::http::{header, StatusCode};
+    use axum::response::IntoResponse;
+
+    // Sessions are owned by the session manager, so sample the count at scrape time
+    metrics::set_active_sessions(session_manager.sessions.read().await.len());
+
+    match metrics::render() {
+        Ok(body) => (
+            [(
+                header::CONTENT_TYPE,
+                "text/plain; version=0.0.4; charset=utf-8",
+            )],
+            body,
+        )
+            .into_response(),
+        Err(e) => {
+            error!("Failed to render metrics: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Report whether the browser dependencies are reachable.
+/// Responds with 503 when any check fails so orchestrators stop routing traffic.
+#[cfg(feature = "http-server")]
+async fn readyz_handler(config: std::sync::Arc<Config>) -> axum::response::Response {
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+
+    let readiness = health::check_readiness(&config).await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        warn!("Readiness check failed: {:?}", readiness.checks);
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, axum::Json(readiness)).into_response()
+}
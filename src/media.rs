@@ -0,0 +1,227 @@
+//! Audio and video elements of the page.
+//!
+//! The browser is started with `--mute-audio` unless `MCP_MUTE_AUDIO` is
+//! disabled, so a headful session does not play sound out loud. Muting the
+//! browser leaves pages unaware of it, so `get_media` still reports which
+//! `<audio>` and `<video>` elements are playing and whether they would be
+//! heard, and `control_media` plays, pauses, mutes or unmutes them, e.g. an
+//! embedded video that autoplays over the content the agent works on.
+//! `capture_video_frame` returns the frame a `<video>` element shows, drawn
+//! without the controls, captions and overlays a screenshot would include.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+/// What `control_media` does to the media elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaCommand {
+    Play,
+    Pause,
+    Mute,
+    Unmute,
+}
+
+impl std::fmt::Display for MediaCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            MediaCommand::Play => "Played",
+            MediaCommand::Pause => "Paused",
+            MediaCommand::Mute => "Muted",
+            MediaCommand::Unmute => "Unmuted",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An `<audio>` or `<video>` element.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct MediaElement {
+    /// Position among the media elements of the document, for `control_media`.
+    pub index: u32,
+    /// `audio` or `video`.
+    pub kind: String,
+    /// Source URL; data URLs and media streams are left out.
+    #[serde(default)]
+    pub src: String,
+    /// Whether playback is running.
+    pub playing: bool,
+    /// Whether the element is muted or at zero volume.
+    pub muted: bool,
+    /// Playback position, in seconds.
+    pub current_time: f64,
+    /// Length in seconds; missing for live streams and unloaded media.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+}
+
+impl MediaElement {
+    /// Whether the element plays sound the page can hear, ignoring whether
+    /// the browser itself is muted.
+    pub fn audible(&self) -> bool {
+        self.playing && !self.muted
+    }
+}
+
+/// Largest width or height of a captured video frame; larger frames are
+/// scaled down.
+pub const MAX_FRAME_SIZE: u32 = 1920;
+
+/// The current frame of a `<video>` element.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoFrame {
+    /// Position among the media elements of the document, as in `get_media`.
+    pub index: u32,
+    /// Size of the captured image.
+    pub width: u32,
+    pub height: u32,
+    /// Playback position of the frame, in seconds.
+    pub current_time: f64,
+    /// PNG image, base64 encoded.
+    pub image: String,
+}
+
+/// JavaScript function drawing the current frame of the video matching
+/// `selector`, or of the first playing video, onto a canvas. Returns a
+/// [`VideoFrame`] or `{ error }`.
+const FRAME_FUNCTION: &str = r#"
+(function(selector, maxSize) {
+    var media = Array.prototype.slice.call(document.querySelectorAll('audio, video'));
+    var video;
+    if (selector !== null) {
+        try {
+            video = document.querySelector(selector);
+        } catch (e) {
+            return { error: 'Invalid selector ' + selector + ': ' + e.message };
+        }
+        if (video && video.tagName !== 'VIDEO') {
+            video = video.querySelector('video');
+        }
+        if (!video) {
+            return { error: 'No video element matches selector ' + selector };
+        }
+    } else {
+        var videos = media.filter(function(el) { return el.tagName === 'VIDEO'; });
+        video = videos.filter(function(el) { return !el.paused && !el.ended; })[0] || videos[0];
+        if (!video) {
+            return { error: 'The page has no video element' };
+        }
+    }
+    if (video.readyState < 2 || !video.videoWidth) {
+        return { error: 'The video has not loaded a frame yet' };
+    }
+    var scale = Math.min(1, maxSize / Math.max(video.videoWidth, video.videoHeight));
+    var canvas = document.createElement('canvas');
+    canvas.width = Math.round(video.videoWidth * scale);
+    canvas.height = Math.round(video.videoHeight * scale);
+    canvas.getContext('2d').drawImage(video, 0, 0, canvas.width, canvas.height);
+    var url;
+    try {
+        url = canvas.toDataURL('image/png');
+    } catch (e) {
+        return { error: 'The video comes from another origin without CORS, so its frames cannot be read; use current_state to see it' };
+    }
+    return {
+        index: media.indexOf(video),
+        width: canvas.width,
+        height: canvas.height,
+        current_time: Math.round(video.currentTime * 10) / 10,
+        image: url.substring(url.indexOf(',') + 1)
+    };
+})"#;
+
+/// JavaScript function listing the media elements of the document, after
+/// applying `command` to the element at `index`, or to all of them when
+/// `index` is null. Returns `{ error }` when the index does not exist.
+const MEDIA_FUNCTION: &str = r#"
+(function(command, index) {
+    var elements = Array.prototype.slice.call(document.querySelectorAll('audio, video'));
+    if (command) {
+        if (index !== null && !elements[index]) {
+            return { error: 'No media element ' + index + '; the page has ' + elements.length };
+        }
+        (index === null ? elements : [elements[index]]).forEach(function(el) {
+            if (command === 'play') {
+                var played = el.play();
+                // Rejected when the autoplay policy blocks it, which the state shows
+                if (played) {
+                    played.catch(function() {});
+                }
+            } else if (command === 'pause') {
+                el.pause();
+            } else {
+                el.muted = command === 'mute';
+            }
+        });
+    }
+    return elements.map(function(el, i) {
+        var src = el.currentSrc || el.src || '';
+        return {
+            index: i,
+            kind: el.tagName.toLowerCase(),
+            src: /^(data|blob):/.test(src) ? '' : src.substring(0, 300),
+            playing: !el.paused && !el.ended,
+            muted: el.muted || el.volume === 0,
+            current_time: Math.round(el.currentTime * 10) / 10,
+            duration: isFinite(el.duration) ? Math.round(el.duration * 10) / 10 : null
+        };
+    });
+})"#;
+
+/// JavaScript expression listing the media elements.
+pub(crate) fn state_script() -> String {
+    format!("{}(null, null)", MEDIA_FUNCTION)
+}
+
+/// JavaScript expression applying a command to the media elements.
+pub(crate) fn control_script(command: MediaCommand, index: Option<u32>) -> String {
+    format!(
+        "{}({}, {})",
+        MEDIA_FUNCTION,
+        serde_json::to_string(&command).unwrap_or_default(),
+        serde_json::to_string(&index).unwrap_or_default()
+    )
+}
+
+/// JavaScript expression capturing the current frame of a video.
+pub(crate) fn frame_script(selector: Option<&str>) -> String {
+    format!(
+        "{}({}, {})",
+        FRAME_FUNCTION,
+        serde_json::to_string(&selector).unwrap_or_default(),
+        MAX_FRAME_SIZE
+    )
+}
+
+/// The result of a media script.
+pub(crate) fn parse_media(value: &serde_json::Value) -> anyhow::Result<Vec<MediaElement>> {
+    crate::element_aliases::parse_result(value)
+}
+
+/// The result of the frame script.
+pub(crate) fn parse_frame(value: &serde_json::Value) -> anyhow::Result<VideoFrame> {
+    crate::element_aliases::parse_result(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media() {
+        assert!(control_script(MediaCommand::Pause, Some(1)).ends_with(r#"("pause", 1)"#));
+        assert!(control_script(MediaCommand::Mute, None).ends_with(r#"("mute", null)"#));
+
+        let media = parse_media(&serde_json::json!([{
+            "index": 0, "kind": "video", "src": "https://example.com/a.mp4",
+            "playing": true, "muted": false, "current_time": 3.5, "duration": null
+        }]))
+        .unwrap();
+        assert!(media[0].audible());
+        assert_eq!(media[0].duration, None);
+        assert!(parse_media(&serde_json::json!({ "error": "No media element 2" })).is_err());
+
+        assert!(frame_script(Some("#player")).ends_with(r##"("#player", 1920)"##));
+        assert!(frame_script(None).ends_with("(null, 1920)"));
+    }
+}
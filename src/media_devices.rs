@@ -0,0 +1,80 @@
+//! WebRTC leak protection and camera and microphone stand-ins.
+//!
+//! WebRTC gathers ICE candidates over UDP straight from the network
+//! interfaces, past any HTTP or SOCKS proxy, so a page can learn the real
+//! public IP of a proxied browser. With `MCP_WEBRTC_LEAK_PROTECTION`, always
+//! on in undetected mode, Chromium only uses the proxy for WebRTC.
+//!
+//! Headless browsers and servers have no cameras or microphones. With
+//! `MCP_FAKE_MEDIA_DEVICES`, Chromium provides a test pattern camera and a
+//! beeping microphone and grants `getUserMedia` without a prompt, so video
+//! call apps can be tested. In undetected mode, an empty device list, which
+//! gives a headless browser away, is reported as the default devices a
+//! desktop browser lists before permission is granted.
+
+/// Chromium switch keeping WebRTC traffic on the proxy.
+pub const WEBRTC_POLICY_SWITCH: &str = "--force-webrtc-ip-handling-policy=disable_non_proxied_udp";
+
+/// Chromium switches providing fake capture devices and accepting capture
+/// permission prompts.
+pub const FAKE_DEVICE_SWITCHES: &[&str] = &[
+    "--use-fake-device-for-media-stream",
+    "--use-fake-ui-for-media-stream",
+];
+
+/// Chromium switches for the configured WebRTC and device settings.
+pub fn chromium_switches(leak_protection: bool, fake_devices: bool) -> Vec<&'static str> {
+    let mut switches = Vec::new();
+    if leak_protection {
+        switches.push(WEBRTC_POLICY_SWITCH);
+    }
+    if fake_devices {
+        switches.extend_from_slice(FAKE_DEVICE_SWITCHES);
+    }
+    switches
+}
+
+/// Script making `enumerateDevices` report a microphone, a camera and a
+/// speaker, without IDs or labels, when the browser has no devices.
+pub const DEVICE_STUB_SCRIPT: &str = r#"
+(function() {
+    var devices = navigator.mediaDevices;
+    if (!devices || !devices.enumerateDevices || !window.MediaDeviceInfo) {
+        return;
+    }
+    var enumerate = devices.enumerateDevices.bind(devices);
+    function stub(kind) {
+        var info = Object.create(MediaDeviceInfo.prototype);
+        var fields = { deviceId: '', kind: kind, label: '', groupId: '' };
+        Object.keys(fields).forEach(function(key) {
+            Object.defineProperty(info, key, { value: fields[key], enumerable: true });
+        });
+        Object.defineProperty(info, 'toJSON', { value: function() { return fields; } });
+        return info;
+    }
+    devices.enumerateDevices = function enumerateDevices() {
+        return enumerate().then(function(list) {
+            return list.length ? list : ['audioinput', 'videoinput', 'audiooutput'].map(stub);
+        });
+    };
+})();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chromium_switches() {
+        assert!(chromium_switches(false, false).is_empty());
+        assert_eq!(chromium_switches(true, false), vec![WEBRTC_POLICY_SWITCH]);
+        assert_eq!(
+            chromium_switches(true, true),
+            vec![
+                WEBRTC_POLICY_SWITCH,
+                "--use-fake-device-for-media-stream",
+                "--use-fake-ui-for-media-stream"
+            ]
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Prometheus metrics for the MCP server.
+//!
+//! Metrics are recorded in every transport mode but are only exported over
+//! HTTP, through the `/metrics` route of the `http-server` feature.
+
+use prometheus::{
+    HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Latency buckets for tool calls, in seconds.
+/// Browser actions include page settle delays, so the range extends to a minute.
+const TOOL_LATENCY_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0];
+
+/// All metrics exported by the server.
+struct Metrics {
+    registry: Registry,
+    tool_calls: IntCounterVec,
+    tool_duration: HistogramVec,
+    screenshot_bytes: IntCounter,
+    active_sessions: IntGauge,
+    browser_restarts: IntCounter,
+    page_crashes: IntCounter,
+}
+
+impl Metrics {
+    fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new();
+
+        let tool_calls = IntCounterVec::new(
+            Opts::new(
+                "mcp_tool_calls_total",
+                "Number of tool calls, by tool and outcome",
+            ),
+            &["tool", "status"],
+        )?;
+        let tool_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "mcp_tool_duration_seconds",
+                "Tool call latency in seconds, by tool",
+            )
+            .buckets(TOOL_LATENCY_BUCKETS.to_vec()),
+            &["tool"],
+        )?;
+        let screenshot_bytes = IntCounter::new(
+            "mcp_screenshot_bytes_total",
+            "Total size of screenshots returned to clients, in bytes",
+        )?;
+        let active_sessions = IntGauge::new(
+            "mcp_active_sessions",
+            "Number of active MCP sessions (HTTP transport)",
+        )?;
+        let browser_restarts = IntCounter::new(
+            "mcp_browser_restarts_total",
+            "Number of times the browser was launched again after being closed",
+        )?;
+        let page_crashes = IntCounter::new(
+            "mcp_page_crashes_total",
+            "Number of tab renderer crashes detected",
+        )?;
+
+        registry.register(Box::new(tool_calls.clone()))?;
+        registry.register(Box::new(tool_duration.clone()))?;
+        registry.register(Box::new(screenshot_bytes.clone()))?;
+        registry.register(Box::new(active_sessions.clone()))?;
+        registry.register(Box::new(browser_restarts.clone()))?;
+        registry.register(Box::new(page_crashes.clone()))?;
+
+        Ok(Self {
+            registry,
+            tool_calls,
+            tool_duration,
+            screenshot_bytes,
+            active_sessions,
+            browser_restarts,
+            page_crashes,
+        })
+    }
+}
+
+static METRICS: LazyLock<Metrics> =
+    LazyLock::new(|| Metrics::new().expect("metric definitions should be valid"));
+
+/// Record a completed tool call.
+pub fn record_tool_call(tool: &str, success: bool, duration: Duration) {
+    let status = if success { "success" } else { "error" };
+    METRICS.tool_calls.with_label_values(&[tool, status]).inc();
+    METRICS
+        .tool_duration
+        .with_label_values(&[tool])
+        .observe(duration.as_secs_f64());
+}
+
+/// Record the size of a screenshot returned to a client.
+pub fn record_screenshot_bytes(bytes: u64) {
+    METRICS.screenshot_bytes.inc_by(bytes);
+}
+
+/// Record that the browser was launched again after being closed.
+pub fn record_browser_restart() {
+    METRICS.browser_restarts.inc();
+}
+
+/// Record that a tab's renderer process crashed.
+pub fn record_page_crash() {
+    METRICS.page_crashes.inc();
+}
+
+/// Update the number of active MCP sessions.
+#[cfg_attr(not(feature = "http-server"), allow(dead_code))]
+pub fn set_active_sessions(count: usize) {
+    METRICS
+        .active_sessions
+        .set(i64::try_from(count).unwrap_or(i64::MAX));
+}
+
+/// Render all metrics in the Prometheus text exposition format.
+#[cfg_attr(not(feature = "http-server"), allow(dead_code))]
+pub fn render() -> anyhow::Result<String> {
+    Ok(TextEncoder::new().encode_to_string(&METRICS.registry.gather())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_includes_recorded_metrics() {
+        record_tool_call("click_at", true, Duration::from_millis(120));
+        record_tool_call("click_at", false, Duration::from_millis(30));
+        record_screenshot_bytes(1024);
+        set_active_sessions(2);
+
+        let output = render().unwrap();
+        assert!(output.contains(r#"mcp_tool_calls_total{status="success",tool="click_at"}"#));
+        assert!(output.contains(r#"mcp_tool_calls_total{status="error",tool="click_at"}"#));
+        assert!(output.contains("mcp_tool_duration_seconds_bucket"));
+        assert!(output.contains("mcp_screenshot_bytes_total"));
+        assert!(output.contains("mcp_active_sessions 2"));
+    }
+}
@@ -0,0 +1,109 @@
+//! Scripted browser backend for unit tests of the tool layer.
+//!
+//! [`MockBackend`] stands in for a browser behind [`BrowserBackend::Mock`]:
+//! each backend method returns the next reply a test scripted for it, and
+//! every call is recorded, so tests can check how the tools handle disabled
+//! tools, backend errors and the shape of their responses without launching
+//! a browser.
+//!
+//! [`BrowserBackend::Mock`]: crate::tools::BrowserBackend::Mock
+
+use crate::browser::EnvState;
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// A 1x1 white PNG, base64 encoded.
+const BLANK_PNG: &str =
+    "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAIAAACQd1PeAAAADElEQVR4nGP4//8/AAX+Av4N70a4AAAAAElFTkSuQmCC";
+
+type Reply = anyhow::Result<Box<dyn Any + Send>>;
+
+/// Browser backend returning scripted replies.
+#[derive(Default)]
+pub struct MockBackend {
+    /// Replies not yet returned, per backend method.
+    replies: Mutex<HashMap<&'static str, VecDeque<Reply>>>,
+    /// Backend methods called, in order.
+    calls: Mutex<Vec<&'static str>>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue the reply of the next call of a backend method. The reply must
+    /// have the method's return type.
+    pub fn script<T: Any + Send>(&self, method: &'static str, reply: anyhow::Result<T>) {
+        self.replies
+            .lock()
+            .unwrap()
+            .entry(method)
+            .or_default()
+            .push_back(reply.map(|value| Box::new(value) as Box<dyn Any + Send>));
+    }
+
+    /// Record a call and return its scripted reply. Calls without one fail.
+    pub fn reply<T: Any>(&self, method: &'static str) -> anyhow::Result<T> {
+        self.record(method);
+        let reply = self
+            .replies
+            .lock()
+            .unwrap()
+            .get_mut(method)
+            .and_then(VecDeque::pop_front)
+            .ok_or_else(|| anyhow::anyhow!("No reply scripted for {}", method))?;
+        reply.map(|value| {
+            *value
+                .downcast::<T>()
+                .unwrap_or_else(|_| panic!("The reply scripted for {} has the wrong type", method))
+        })
+    }
+
+    /// Record a call that needs no reply.
+    pub fn record(&self, method: &'static str) {
+        self.calls.lock().unwrap().push(method);
+    }
+
+    /// Backend methods called so far, in order.
+    pub fn calls(&self) -> Vec<&'static str> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+/// State of a page at `url` with a blank screenshot.
+pub fn state(url: &str) -> EnvState {
+    EnvState {
+        screenshot: BLANK_PNG.to_string(),
+        url: url.to_string(),
+        notifications: Vec::new(),
+        warnings: Vec::new(),
+        delta: None,
+        effect_detected: None,
+        timings: Default::default(),
+        login_required: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripted_replies() {
+        let mock = MockBackend::new();
+        mock.script("navigate", Ok(state("https://a.test/")));
+        mock.script::<EnvState>(
+            "navigate",
+            Err(anyhow::anyhow!("net::ERR_NAME_NOT_RESOLVED")),
+        );
+
+        let first: EnvState = mock.reply("navigate").unwrap();
+        assert_eq!(first.url, "https://a.test/");
+        let second = mock.reply::<EnvState>("navigate").unwrap_err();
+        assert!(second.to_string().contains("ERR_NAME_NOT_RESOLVED"));
+        assert!(mock.reply::<EnvState>("navigate").is_err());
+        assert_eq!(mock.calls(), vec!["navigate"; 3]);
+    }
+}
@@ -0,0 +1,118 @@
+//! Navigation budget and loop detection.
+//!
+//! Agents sometimes get stuck going back and forth between the same pages. The
+//! tracker records the URL of every state the controllers report, and when a
+//! tab moves to a new page it checks for two signs of a stuck agent: a page
+//! revisited right after leaving it (A → B → A), and more navigations to one
+//! domain than `MCP_MAX_NAVIGATIONS_PER_DOMAIN` allows. Either one only adds a
+//! warning to the response; the navigation itself is not refused.
+
+use crate::credentials;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Number of recent URLs remembered per tab.
+const RECENT_URLS: usize = 8;
+
+#[derive(Default)]
+struct TrackerState {
+    /// Recent distinct URLs per tab, oldest first.
+    recent: HashMap<String, VecDeque<String>>,
+    /// Navigations per host.
+    per_domain: HashMap<String, usize>,
+}
+
+/// Records navigations and warns about loops and exhausted budgets.
+pub struct NavigationTracker {
+    max_per_domain: usize,
+    detect_loops: bool,
+    state: Mutex<TrackerState>,
+}
+
+impl NavigationTracker {
+    /// `max_per_domain` of 0 disables the budget.
+    pub fn new(max_per_domain: usize, detect_loops: bool) -> Self {
+        Self {
+            max_per_domain,
+            detect_loops,
+            state: Mutex::new(TrackerState::default()),
+        }
+    }
+
+    /// Whether anything is tracked.
+    pub fn is_enabled(&self) -> bool {
+        self.detect_loops || self.max_per_domain > 0
+    }
+
+    /// Record the URL a tab is on. Returns warnings when this is a navigation
+    /// that closes a loop or goes over the domain budget.
+    pub fn record(&self, tab: &str, url: &str) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !self.is_enabled() {
+            return warnings;
+        }
+        // about:blank, data: and the like are not pages an agent navigates between
+        let Some(host) = credentials::host_of(url) else {
+            return warnings;
+        };
+        // Moving to an anchor on the same page is not a navigation
+        let page = url.split('#').next().unwrap_or(url).to_string();
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let recent = state.recent.entry(tab.to_string()).or_default();
+        if recent.back() == Some(&page) {
+            return warnings;
+        }
+        if self.detect_loops && recent.len() >= 2 && recent[recent.len() - 2] == page {
+            warnings.push(format!(
+                "Possible navigation loop: returned to {} right after leaving it for {}. \
+                 Try a different approach if the task is not progressing.",
+                page,
+                recent[recent.len() - 1]
+            ));
+        }
+        recent.push_back(page);
+        if recent.len() > RECENT_URLS {
+            recent.pop_front();
+        }
+
+        let count = state.per_domain.entry(host.clone()).or_default();
+        *count += 1;
+        if self.max_per_domain > 0 && *count > self.max_per_domain {
+            warnings.push(format!(
+                "Navigation budget for {} exceeded: {} navigations, limit {}.",
+                host, count, self.max_per_domain
+            ));
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loop_detection() {
+        let tracker = NavigationTracker::new(0, true);
+        assert!(tracker.record("t1", "https://a.test/").is_empty());
+        assert!(tracker.record("t1", "https://b.test/").is_empty());
+        // Another tab does not interleave with the first one's history
+        assert!(tracker.record("t2", "https://a.test/").is_empty());
+        // Screenshots of the same page are not navigations
+        assert!(tracker.record("t1", "https://b.test/#section").is_empty());
+        let warnings = tracker.record("t1", "https://a.test/");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("loop"));
+    }
+
+    #[test]
+    fn test_domain_budget() {
+        let tracker = NavigationTracker::new(2, false);
+        assert!(tracker.record("t", "https://a.test/1").is_empty());
+        assert!(tracker.record("t", "https://a.test/2").is_empty());
+        assert!(tracker.record("t", "https://b.test/").is_empty());
+        assert!(tracker.record("t", "https://a.test/1").len() == 1);
+        assert!(tracker.record("t", "about:blank").is_empty());
+    }
+}
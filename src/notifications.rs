@@ -0,0 +1,180 @@
+//! Web notification capture.
+//!
+//! Sites often confirm an action ("Message sent") only through a desktop
+//! notification, which never appears in a screenshot. A script installed in
+//! every document wraps `Notification`, `ServiceWorkerRegistration.showNotification`
+//! and the permission requests that would show a browser prompt, and records
+//! them in a per-document log that the server reads back.
+//!
+//! The log lives in the page, so it starts empty on every navigation.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+/// A notification or permission prompt recorded in the page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CapturedNotification {
+    /// `notification`, or `permission_request` for a permission prompt.
+    pub kind: String,
+    /// Notification title, or the requested permission (`notifications`,
+    /// `geolocation`, `camera`, `microphone`).
+    pub title: String,
+    /// Notification body text.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub body: String,
+    /// Notification tag, used by sites to replace earlier notifications.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub tag: String,
+    /// Time the notification was shown (milliseconds since UNIX epoch).
+    pub timestamp: u64,
+    /// URL of the page that showed it.
+    pub url: String,
+}
+
+/// Script wrapping the notification and permission APIs of a document. Safe to
+/// run more than once.
+pub const CAPTURE_SCRIPT: &str = r#"
+(function() {
+    if (window.__mcpNotifications) {
+        return;
+    }
+    var log = [];
+    Object.defineProperty(window, '__mcpNotifications', { value: log });
+    function record(kind, title, options) {
+        log.push({
+            kind: kind,
+            title: String(title || ''),
+            body: options && options.body ? String(options.body) : '',
+            tag: options && options.tag ? String(options.tag) : '',
+            timestamp: Date.now(),
+            url: location.href,
+            reported: false
+        });
+        if (log.length > 50) {
+            log.shift();
+        }
+    }
+    var Original = window.Notification;
+    if (typeof Original === 'function') {
+        var Wrapped = function Notification(title, options) {
+            record('notification', title, options);
+            return new Original(title, options);
+        };
+        Wrapped.prototype = Original.prototype;
+        Object.defineProperty(Wrapped, 'permission', {
+            get: function() { return Original.permission; }
+        });
+        Wrapped.requestPermission = function() {
+            record('permission_request', 'notifications');
+            return Original.requestPermission.apply(Original, arguments);
+        };
+        window.Notification = Wrapped;
+    }
+    if (window.ServiceWorkerRegistration && ServiceWorkerRegistration.prototype.showNotification) {
+        var show = ServiceWorkerRegistration.prototype.showNotification;
+        ServiceWorkerRegistration.prototype.showNotification = function(title, options) {
+            record('notification', title, options);
+            return show.apply(this, arguments);
+        };
+    }
+    if (navigator.geolocation) {
+        ['getCurrentPosition', 'watchPosition'].forEach(function(name) {
+            var original = navigator.geolocation[name];
+            navigator.geolocation[name] = function() {
+                record('permission_request', 'geolocation');
+                return original.apply(navigator.geolocation, arguments);
+            };
+        });
+    }
+    if (navigator.mediaDevices && navigator.mediaDevices.getUserMedia) {
+        var getUserMedia = navigator.mediaDevices.getUserMedia;
+        navigator.mediaDevices.getUserMedia = function(constraints) {
+            if (constraints && constraints.video) {
+                record('permission_request', 'camera');
+            }
+            if (constraints && constraints.audio) {
+                record('permission_request', 'microphone');
+            }
+            return getUserMedia.apply(navigator.mediaDevices, arguments);
+        };
+    }
+})()"#;
+
+/// JavaScript expression returning the recorded notifications, and emptying the
+/// log when `clear` is set. Evaluates to `null` when capture is not installed
+/// in the document.
+pub fn read_script(clear: bool) -> String {
+    format!(
+        r#"(function(clear) {{
+    var log = window.__mcpNotifications;
+    if (!log) {{
+        return null;
+    }}
+    var entries = log.slice();
+    log.forEach(function(entry) {{ entry.reported = true; }});
+    if (clear) {{
+        log.length = 0;
+    }}
+    return entries;
+}})({})"#,
+        clear
+    )
+}
+
+/// JavaScript expression returning the notifications not yet returned by an
+/// earlier read, marking them as reported.
+pub const UNREPORTED_SCRIPT: &str = r#"
+(function() {
+    var log = window.__mcpNotifications || [];
+    var fresh = log.filter(function(entry) { return !entry.reported; });
+    fresh.forEach(function(entry) { entry.reported = true; });
+    return fresh;
+})()"#;
+
+/// Parse the result of [`read_script`] or [`UNREPORTED_SCRIPT`]. Returns `None`
+/// when capture is not installed.
+pub fn parse_notifications(
+    value: &serde_json::Value,
+) -> anyhow::Result<Option<Vec<CapturedNotification>>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_value(value.clone())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_notifications() {
+        // Shape produced by the capture script
+        let value = serde_json::json!([
+            {
+                "kind": "notification",
+                "title": "Message sent",
+                "body": "Your message to Alex was delivered",
+                "tag": "",
+                "timestamp": 1760000000000u64,
+                "url": "https://mail.example.com/",
+                "reported": false
+            },
+            {
+                "kind": "permission_request",
+                "title": "notifications",
+                "body": "",
+                "tag": "",
+                "timestamp": 1760000000500u64,
+                "url": "https://mail.example.com/",
+                "reported": true
+            }
+        ]);
+        let notifications = parse_notifications(&value).unwrap().unwrap();
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(notifications[0].body, "Your message to Alex was delivered");
+        assert_eq!(notifications[1].kind, "permission_request");
+
+        assert_eq!(parse_notifications(&serde_json::Value::Null).unwrap(), None);
+        assert!(read_script(true).ends_with("})(true)"));
+    }
+}
@@ -0,0 +1,273 @@
+//! Guided sign-in on identity provider pages.
+//!
+//! Signing in through Google, Microsoft or Okta takes several pages: account
+//! chooser, username, password, "stay signed in" and the app's consent screen.
+//! `complete_oauth_login` walks through them one step at a time. Each step runs a
+//! script that recognizes the current page from the provider's selectors, fills
+//! saved credentials (see [`crate::credentials`]) or presses the button that moves
+//! on, and reports what it did. The loop ends when the browser leaves the
+//! provider, or on a page the script cannot handle, such as a second factor.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+/// Identity providers `complete_oauth_login` can sign in with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    Google,
+    Microsoft,
+    Okta,
+}
+
+impl Provider {
+    /// The provider serving sign-in pages on this host.
+    pub fn detect(host: &str) -> Option<Self> {
+        let host = host.to_ascii_lowercase();
+        let under = |domain: &str| host.ends_with(&format!(".{}", domain));
+        match host.as_str() {
+            "accounts.google.com" => Some(Provider::Google),
+            "login.microsoftonline.com" | "login.live.com" | "login.microsoft.com" => {
+                Some(Provider::Microsoft)
+            }
+            _ if under("okta.com") || under("oktapreview.com") || under("okta-emea.com") => {
+                Some(Provider::Okta)
+            }
+            _ => None,
+        }
+    }
+
+    /// Display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Provider::Google => "Google",
+            Provider::Microsoft => "Microsoft",
+            Provider::Okta => "Okta",
+        }
+    }
+
+    /// Selectors recognizing the provider's sign-in pages.
+    fn selectors(self) -> serde_json::Value {
+        match self {
+            Provider::Google => serde_json::json!({
+                "username": ["input#identifierId", "input[type=email]"],
+                "password": ["input[name=Passwd]", "input[type=password]"],
+                "submit": ["#passwordNext button", "#identifierNext button", "#passwordNext", "#identifierNext"],
+                "chooser": "data-identifier",
+                "consent": ["#submit_approve_access"],
+                "consent_text": ["Continue", "Allow"],
+            }),
+            Provider::Microsoft => serde_json::json!({
+                "username": ["input[name=loginfmt]"],
+                "password": ["input[name=passwd]"],
+                "submit": ["#idSIButton9", "input[type=submit]"],
+                "chooser": "data-test-id",
+                // "Stay signed in?" and the app consent page share the primary button
+                "consent": ["#idSIButton9"],
+                "consent_text": ["Accept", "Yes"],
+            }),
+            Provider::Okta => serde_json::json!({
+                "username": ["input[name=identifier]", "input[name=username]"],
+                "password": ["input[name='credentials.passcode']", "input[name=password]"],
+                "submit": ["input[type=submit]", "button[type=submit]"],
+                "chooser": "data-se-account",
+                "consent": ["button[data-type=save]", "input[value='Allow Access']"],
+                "consent_text": ["Allow Access", "Allow"],
+            }),
+        }
+    }
+}
+
+/// Outcome of one sign-in step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OAuthStep {
+    /// Host of the page the step ran on.
+    pub host: String,
+    /// Provider of that page; `None` once the browser has left the provider.
+    pub provider: Option<Provider>,
+    /// What the step did (see [`StepAction`]).
+    pub action: StepAction,
+}
+
+/// What a sign-in step did, as reported by the step script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepAction {
+    /// Filled the username and moved on.
+    Username,
+    /// Filled the password and moved on.
+    Password,
+    /// Picked the account in the account chooser.
+    Account,
+    /// Accepted a consent or "stay signed in" prompt.
+    Consent,
+    /// Stopped at a consent prompt because approving was not requested.
+    ConsentPending,
+    /// A field needed credentials that are not saved for this host.
+    NeedsCredentials,
+    /// Nothing on the page was recognized.
+    None,
+}
+
+impl StepAction {
+    /// Whether the sign-in moved to another page.
+    pub fn advanced(self) -> bool {
+        matches!(
+            self,
+            StepAction::Username | StepAction::Password | StepAction::Account | StepAction::Consent
+        )
+    }
+}
+
+/// JavaScript expression performing one sign-in step on a provider page.
+/// Evaluates to a [`StepAction`]. `username` and `password` are `None` when
+/// the saved credentials may not be used on the page's host; the script checks
+/// again that the document is served over HTTPS from one of `domains`, in case
+/// the page changed since.
+pub fn step_script(
+    provider: Provider,
+    username: Option<&str>,
+    password: Option<&str>,
+    approve_consent: bool,
+    domains: &[String],
+) -> anyhow::Result<String> {
+    let args = serde_json::json!({
+        "selectors": provider.selectors(),
+        "domains": domains,
+        "username": username,
+        "password": password,
+        "approve": approve_consent,
+    });
+    Ok(format!(
+        "{}({})",
+        STEP_FUNCTION,
+        serde_json::to_string(&args)?
+    ))
+}
+
+const STEP_FUNCTION: &str = r#"
+(function(args) {
+    var s = args.selectors;
+    var host = location.hostname.toLowerCase();
+    var allowed = location.protocol === 'https:' && args.domains.some(function(domain) {
+        domain = domain.toLowerCase();
+        return host === domain || host.endsWith('.' + domain);
+    });
+    if (!allowed) {
+        args.username = null;
+        args.password = null;
+    }
+    function usable(el) {
+        var rect = el.getBoundingClientRect();
+        var style = getComputedStyle(el);
+        return rect.width > 0 && rect.height > 0 && style.visibility !== 'hidden' && !el.disabled;
+    }
+    function find(selectors) {
+        for (var i = 0; i < selectors.length; i++) {
+            var found = Array.prototype.filter.call(document.querySelectorAll(selectors[i]), usable);
+            if (found.length) {
+                return found[0];
+            }
+        }
+        return null;
+    }
+    function byText(texts) {
+        var buttons = document.querySelectorAll('button, input[type=submit], [role=button]');
+        return Array.prototype.filter.call(buttons, function(el) {
+            var text = (el.innerText || el.value || '').trim();
+            return usable(el) && texts.indexOf(text) !== -1;
+        })[0] || null;
+    }
+    function fill(el, value) {
+        var setter = Object.getOwnPropertyDescriptor(HTMLInputElement.prototype, 'value').set;
+        el.focus();
+        setter.call(el, value);
+        el.dispatchEvent(new Event('input', { bubbles: true }));
+        el.dispatchEvent(new Event('change', { bubbles: true }));
+    }
+    // Press after returning, so a navigation cannot swallow the result
+    function press(el, field) {
+        setTimeout(function() {
+            if (el) {
+                el.click();
+            } else if (field && field.form && field.form.requestSubmit) {
+                field.form.requestSubmit();
+            }
+        }, 0);
+    }
+
+    var password = find(s.password);
+    var user = find(s.username);
+    if (password) {
+        if (args.password === null) {
+            return 'needs_credentials';
+        }
+        // Some providers ask for both on one page
+        if (user && !user.value && args.username !== null) {
+            fill(user, args.username);
+        }
+        fill(password, args.password);
+        press(find(s.submit), password);
+        return 'password';
+    }
+    if (user) {
+        if (args.username === null) {
+            return 'needs_credentials';
+        }
+        fill(user, args.username);
+        press(find(s.submit), user);
+        return 'username';
+    }
+    if (args.username !== null) {
+        var wanted = args.username.toLowerCase();
+        var tile = Array.prototype.filter.call(
+            document.querySelectorAll('[' + s.chooser + ']'), function(el) {
+                return usable(el) && (el.getAttribute(s.chooser) || '').toLowerCase() === wanted;
+            })[0];
+        if (tile) {
+            press(tile);
+            return 'account';
+        }
+    }
+    var consent = find(s.consent) || byText(s.consent_text);
+    if (consent) {
+        if (!args.approve) {
+            return 'consent_pending';
+        }
+        press(consent);
+        return 'consent';
+    }
+    return 'none';
+})"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_provider() {
+        assert_eq!(
+            Provider::detect("accounts.google.com"),
+            Some(Provider::Google)
+        );
+        assert_eq!(
+            Provider::detect("login.microsoftonline.com"),
+            Some(Provider::Microsoft)
+        );
+        assert_eq!(Provider::detect("acme.okta.com"), Some(Provider::Okta));
+        assert_eq!(Provider::detect("okta.com.evil.test"), None);
+        assert_eq!(Provider::detect("www.google.com"), None);
+    }
+
+    #[test]
+    fn test_step_action() {
+        let action: StepAction =
+            serde_json::from_value(serde_json::json!("consent_pending")).unwrap();
+        assert_eq!(action, StepAction::ConsentPending);
+        assert!(!action.advanced());
+        assert!(StepAction::Account.advanced());
+        assert!(step_script(Provider::Okta, None, None, true, &[])
+            .unwrap()
+            .contains(r#""username":null"#));
+    }
+}
@@ -0,0 +1,197 @@
+//! Text recognition in screenshots, for pages drawn in a canvas.
+//!
+//! Dashboards, games and some PDF viewers render their text into a canvas or
+//! WebGL surface, where the DOM tools find nothing to read. Built with
+//! `--features ocr`, the server offers `ocr_screenshot`, which runs Tesseract
+//! on a screenshot of the tab and returns the recognized lines of text with
+//! their bounding boxes in screenshot pixels, the coordinates `click_at`
+//! takes. Tesseract is run as a command, so it must be installed together
+//! with the language data of every language asked for.
+
+use anyhow::Context;
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// Longest time Tesseract may take on one screenshot.
+const OCR_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Tesseract level of a word in its TSV output.
+const WORD_LEVEL: &str = "5";
+
+/// A line of recognized text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct OcrLine {
+    pub text: String,
+    /// Left edge of the line, in screenshot pixels.
+    pub x: u32,
+    /// Top edge of the line, in screenshot pixels.
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Mean confidence of the line's words, from 0 to 100.
+    pub confidence: f32,
+}
+
+/// Whether `language` looks like a Tesseract language code, such as `eng`,
+/// `chi_sim` or `script/Latin`.
+pub fn is_valid_language(language: &str) -> bool {
+    !language.is_empty()
+        && !language.starts_with('-')
+        && language
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '/')
+}
+
+/// Recognize the text of a PNG screenshot with the Tesseract at `tesseract`,
+/// keeping words recognized with at least `min_confidence`.
+pub async fn recognize(
+    tesseract: &Path,
+    png: &[u8],
+    languages: &[String],
+    min_confidence: f32,
+) -> anyhow::Result<Vec<OcrLine>> {
+    if let Some(language) = languages.iter().find(|l| !is_valid_language(l)) {
+        anyhow::bail!("Invalid OCR language '{}'", language);
+    }
+    let mut child = tokio::process::Command::new(tesseract)
+        .args(["stdin", "stdout", "-l", &languages.join("+"), "tsv"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| {
+            format!(
+                "Failed to run {} (is Tesseract installed? set MCP_TESSERACT_PATH)",
+                tesseract.display()
+            )
+        })?;
+    let mut stdin = child.stdin.take().context("Tesseract has no stdin")?;
+    stdin.write_all(png).await?;
+    drop(stdin);
+    let output = tokio::time::timeout(OCR_TIMEOUT, child.wait_with_output())
+        .await
+        .context("Tesseract timed out")??;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(
+            "Tesseract failed ({}): {}",
+            output.status,
+            stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("")
+        );
+    }
+    Ok(parse_tsv(
+        &String::from_utf8_lossy(&output.stdout),
+        min_confidence,
+    ))
+}
+
+/// Page, block, paragraph and line number of a word in Tesseract's output.
+type LineKey<'a> = (&'a str, &'a str, &'a str, &'a str);
+
+/// Group the words of Tesseract's TSV output into lines.
+fn parse_tsv(tsv: &str, min_confidence: f32) -> Vec<OcrLine> {
+    let mut lines: Vec<(LineKey, OcrLine, usize)> = Vec::new();
+    for row in tsv.lines().skip(1) {
+        let columns: Vec<&str> = row.split('\t').collect();
+        let [level, page, block, paragraph, line, _word, left, top, width, height, conf, text] =
+            columns[..]
+        else {
+            continue;
+        };
+        let text = text.trim();
+        let confidence = conf.parse::<f32>().unwrap_or(-1.0);
+        if level != WORD_LEVEL || text.is_empty() || confidence < min_confidence {
+            continue;
+        }
+        let (Ok(x), Ok(y), Ok(w), Ok(h)) = (
+            left.parse::<u32>(),
+            top.parse::<u32>(),
+            width.parse::<u32>(),
+            height.parse::<u32>(),
+        ) else {
+            continue;
+        };
+        let key = (page, block, paragraph, line);
+        match lines.last_mut() {
+            Some((last, ocr_line, words)) if *last == key => {
+                let right = (ocr_line.x + ocr_line.width).max(x + w);
+                let bottom = (ocr_line.y + ocr_line.height).max(y + h);
+                ocr_line.x = ocr_line.x.min(x);
+                ocr_line.y = ocr_line.y.min(y);
+                ocr_line.width = right - ocr_line.x;
+                ocr_line.height = bottom - ocr_line.y;
+                ocr_line.text.push(' ');
+                ocr_line.text.push_str(text);
+                ocr_line.confidence += confidence;
+                *words += 1;
+            }
+            _ => lines.push((
+                key,
+                OcrLine {
+                    text: text.to_string(),
+                    x,
+                    y,
+                    width: w,
+                    height: h,
+                    confidence,
+                },
+                1,
+            )),
+        }
+    }
+    lines
+        .into_iter()
+        .map(|(_, mut line, words)| {
+            line.confidence = (line.confidence / words as f32 * 10.0).round() / 10.0;
+            line
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tsv() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+            1\t1\t0\t0\t0\t0\t0\t0\t1280\t720\t-1\t\n\
+            4\t1\t1\t1\t1\t0\t40\t30\t200\t20\t-1\t\n\
+            5\t1\t1\t1\t1\t1\t40\t32\t90\t18\t96.5\tRevenue\n\
+            5\t1\t1\t1\t1\t2\t140\t30\t100\t20\t91.5\t€1.2M\n\
+            5\t1\t1\t1\t1\t3\t250\t30\t10\t20\t12.0\t~\n\
+            5\t1\t2\t1\t1\t1\t600\t400\t80\t16\t88.0\tЗаказы\n";
+        let lines = parse_tsv(tsv, 40.0);
+        assert_eq!(
+            lines,
+            [
+                OcrLine {
+                    text: "Revenue €1.2M".to_string(),
+                    x: 40,
+                    y: 30,
+                    width: 200,
+                    height: 20,
+                    confidence: 94.0,
+                },
+                OcrLine {
+                    text: "Заказы".to_string(),
+                    x: 600,
+                    y: 400,
+                    width: 80,
+                    height: 16,
+                    confidence: 88.0,
+                },
+            ]
+        );
+
+        assert!(is_valid_language("chi_sim"));
+        assert!(is_valid_language("script/Latin"));
+        assert!(!is_valid_language("--psm"));
+        assert!(!is_valid_language("eng+deu"));
+    }
+}
@@ -0,0 +1,256 @@
+//! Page lifecycle events collected for the agent.
+//!
+//! `subscribe_events` picks the kinds of browser events to collect:
+//! navigations, JavaScript dialogs, downloads, console messages, and tabs
+//! being opened or closed. Events wait in a bounded buffer until `poll_events`
+//! takes them. The buffer is also an MCP resource (`events://page`); clients
+//! subscribed to it receive a resource-updated notification when events
+//! arrive, so an agent can react to what a page does on its own instead of
+//! polling. Only the CDP backend reports events.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+
+/// URI of the event buffer resource.
+pub const URI: &str = "events://page";
+
+/// Maximum number of events kept; older events are dropped first.
+pub const MAX_BUFFERED_EVENTS: usize = 500;
+
+/// Maximum number of characters kept of an event's text, such as a console
+/// message.
+const MAX_TEXT_CHARS: usize = 1_000;
+
+/// Kind of page event.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    /// A tab's top-level document navigated to a new URL.
+    Navigation,
+    /// A page opened an alert, confirm, prompt or beforeunload dialog.
+    Dialog,
+    /// A download started.
+    Download,
+    /// A page wrote to the console.
+    Console,
+    /// A tab was opened or closed.
+    Target,
+}
+
+/// One collected event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PageEvent {
+    /// Sequence number, increasing across the session.
+    pub seq: u64,
+    #[serde(rename = "type")]
+    pub kind: EventType,
+    /// Time of the event, in milliseconds since the UNIX epoch.
+    pub timestamp: u64,
+    /// Handle of the tab the event happened in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+    /// URL involved: the new page, the downloaded file, the opened tab.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Finer kind: the console level, the dialog type, or `opened`/`closed`
+    /// for tabs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtype: Option<String>,
+    /// Text of the event: the console message, the dialog message, or the
+    /// suggested file name of a download.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// An event as reported by a backend, before it is numbered and timed.
+#[derive(Debug, Clone, Default)]
+pub struct EventDetails {
+    pub tab: Option<String>,
+    pub url: Option<String>,
+    pub subtype: Option<String>,
+    pub text: Option<String>,
+}
+
+/// Events taken from or read in the buffer.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct EventBatch {
+    /// Event types being collected.
+    pub types: Vec<EventType>,
+    pub events: Vec<PageEvent>,
+    /// Events dropped because the buffer was full, since the last poll.
+    pub dropped: u64,
+    /// Events still buffered.
+    pub remaining: usize,
+}
+
+#[derive(Default)]
+struct Buffer {
+    types: BTreeSet<EventType>,
+    events: VecDeque<PageEvent>,
+    dropped: u64,
+    next_seq: u64,
+    resource_subscribed: bool,
+}
+
+/// The event subscription of a session and its buffered events.
+#[derive(Default)]
+pub struct PageEvents {
+    buffer: Mutex<Buffer>,
+    arrived: Notify,
+}
+
+impl PageEvents {
+    /// Collect events of `types` from now on, replacing the previous
+    /// subscription. Buffered events of other types are discarded. Returns
+    /// the types collected.
+    pub fn subscribe(&self, types: &[EventType]) -> Vec<EventType> {
+        let mut buffer = self.lock();
+        buffer.types = types.iter().copied().collect();
+        let Buffer { types, events, .. } = &mut *buffer;
+        events.retain(|event| types.contains(&event.kind));
+        buffer.types.iter().copied().collect()
+    }
+
+    /// Whether any events are collected.
+    pub fn is_active(&self) -> bool {
+        !self.lock().types.is_empty()
+    }
+
+    /// Whether events of a type are collected.
+    pub fn wants(&self, kind: EventType) -> bool {
+        self.lock().types.contains(&kind)
+    }
+
+    /// Buffer an event if its type is collected, dropping the oldest event
+    /// when the buffer is full.
+    pub fn push(&self, kind: EventType, details: EventDetails) {
+        let mut buffer = self.lock();
+        if !buffer.types.contains(&kind) {
+            return;
+        }
+        buffer.next_seq += 1;
+        let event = PageEvent {
+            seq: buffer.next_seq,
+            kind,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis() as u64)
+                .unwrap_or_default(),
+            tab: details.tab,
+            url: details.url,
+            subtype: details.subtype,
+            text: details
+                .text
+                .map(|text| text.chars().take(MAX_TEXT_CHARS).collect()),
+        };
+        if buffer.events.len() >= MAX_BUFFERED_EVENTS {
+            buffer.events.pop_front();
+            buffer.dropped += 1;
+        }
+        buffer.events.push_back(event);
+        drop(buffer);
+        self.arrived.notify_one();
+    }
+
+    /// Remove and return up to `limit` of the oldest buffered events.
+    pub fn take(&self, limit: usize) -> EventBatch {
+        let mut buffer = self.lock();
+        let count = limit.min(buffer.events.len());
+        let events = buffer.events.drain(..count).collect();
+        EventBatch {
+            types: buffer.types.iter().copied().collect(),
+            events,
+            dropped: std::mem::take(&mut buffer.dropped),
+            remaining: buffer.events.len(),
+        }
+    }
+
+    /// The buffered events, left in the buffer.
+    pub fn peek(&self) -> EventBatch {
+        let buffer = self.lock();
+        EventBatch {
+            types: buffer.types.iter().copied().collect(),
+            events: buffer.events.iter().cloned().collect(),
+            dropped: buffer.dropped,
+            remaining: buffer.events.len(),
+        }
+    }
+
+    /// Wait until an event is buffered. An event buffered while nobody was
+    /// waiting wakes the next wait immediately.
+    pub async fn arrived(&self) {
+        self.arrived.notified().await
+    }
+
+    /// Subscribe to or unsubscribe from the event resource.
+    pub fn set_resource_subscribed(&self, subscribed: bool) {
+        self.lock().resource_subscribed = subscribed;
+    }
+
+    /// Whether the client subscribed to the event resource.
+    pub fn is_resource_subscribed(&self) -> bool {
+        self.lock().resource_subscribed
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Buffer> {
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn console(text: &str) -> EventDetails {
+        EventDetails {
+            tab: Some("A1".to_string()),
+            subtype: Some("log".to_string()),
+            text: Some(text.to_string()),
+            ..EventDetails::default()
+        }
+    }
+
+    #[test]
+    fn test_page_events() {
+        let events = PageEvents::default();
+        events.push(EventType::Console, console("before subscribing"));
+        assert_eq!(events.peek().events.len(), 0);
+
+        let types = events.subscribe(&[EventType::Navigation, EventType::Console]);
+        assert_eq!(types, [EventType::Navigation, EventType::Console]);
+        for i in 0..MAX_BUFFERED_EVENTS + 2 {
+            events.push(EventType::Console, console(&i.to_string()));
+        }
+        events.push(EventType::Dialog, EventDetails::default());
+
+        let batch = events.take(10);
+        assert_eq!(batch.dropped, 2);
+        assert_eq!(batch.events[0].text.as_deref(), Some("2"));
+        assert_eq!(batch.events[0].seq, 3);
+        assert_eq!(batch.remaining, MAX_BUFFERED_EVENTS - 10);
+        assert_eq!(events.take(10).dropped, 0);
+
+        // Narrowing the subscription discards buffered events of other types
+        events.subscribe(&[EventType::Navigation]);
+        assert_eq!(events.peek().remaining, 0);
+        assert!(!events.wants(EventType::Console));
+        events.subscribe(&[]);
+        assert!(!events.is_active());
+    }
+}
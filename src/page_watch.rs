@@ -0,0 +1,284 @@
+//! Pages reloaded in the background and watched for changes.
+//!
+//! `watch_page` opens a URL in a background tab and reloads it on an interval.
+//! Every reload reads the page's visible text, or the text of one element, and
+//! compares it with the previous read. Each watch is an MCP resource
+//! (`watch://<id>`) holding its status and the latest text; when the text
+//! changes, clients subscribed to the resource receive a resource-updated
+//! notification, so a monitoring agent can wait for changes instead of polling
+//! the page itself.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// URI scheme of watch resources.
+pub const URI_PREFIX: &str = "watch://";
+
+/// Shortest allowed reload interval, in seconds.
+pub const MIN_INTERVAL_SECS: u64 = 10;
+
+/// Maximum number of watches per session.
+pub const MAX_WATCHES: usize = 10;
+
+/// Maximum number of characters of page text kept in a watch's status.
+const MAX_TEXT_CHARS: usize = 10_000;
+
+/// Text read from a watched page.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageText {
+    pub title: String,
+    /// Text of the page or the watched element; absent when the selector
+    /// matches nothing.
+    pub text: Option<String>,
+}
+
+/// JavaScript expression evaluating to the [`PageText`] of the document, or of
+/// the first element matching `selector`.
+pub fn text_script(selector: Option<&str>) -> String {
+    let selector = selector
+        .map(|s| serde_json::to_string(s).unwrap_or_default())
+        .unwrap_or_else(|| "null".to_string());
+    format!(
+        r#"(function(selector) {{
+    var element = selector ? document.querySelector(selector) : document.body;
+    return {{ title: document.title, text: element ? element.innerText : null }};
+}})({})"#,
+        selector
+    )
+}
+
+/// Status of a watch, returned by `watch_page` and read from its resource.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WatchStatus {
+    /// ID of the watch.
+    pub id: String,
+    /// Resource URI to read or subscribe to.
+    pub uri: String,
+    /// Watched URL.
+    pub url: String,
+    /// Handle of the background tab showing the page.
+    pub tab: String,
+    /// CSS selector of the watched element, if only part of the page is watched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Seconds between reloads.
+    pub interval_secs: u64,
+    /// Number of reloads so far.
+    pub checks: u64,
+    /// Number of reloads that found changed text.
+    pub changes: u64,
+    /// Time of the last reload, in seconds since the UNIX epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_checked: Option<u64>,
+    /// Time the text last changed, in seconds since the UNIX epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_changed: Option<u64>,
+    /// Document title at the last read.
+    pub title: String,
+    /// Watched text at the last read, truncated.
+    pub text: String,
+    /// Error of the last reload, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Resource URI of a watch.
+pub fn uri(id: &str) -> String {
+    format!("{}{}", URI_PREFIX, id)
+}
+
+struct Watch {
+    status: WatchStatus,
+    /// Hash of the full text of the last successful read.
+    fingerprint: Option<u64>,
+}
+
+/// Watches of a session and the resources its client subscribed to.
+#[derive(Default)]
+pub struct PageWatches {
+    watches: Mutex<HashMap<String, Watch>>,
+    subscriptions: Mutex<HashSet<String>>,
+    next_id: AtomicU64,
+}
+
+impl PageWatches {
+    /// Whether no more watches may be added.
+    pub fn is_full(&self) -> bool {
+        self.lock().len() >= MAX_WATCHES
+    }
+
+    /// Register a watch of a page already open in `tab`, with its first read
+    /// as the baseline.
+    pub fn add(
+        &self,
+        url: &str,
+        tab: &str,
+        selector: Option<&str>,
+        interval_secs: u64,
+        first: PageText,
+    ) -> WatchStatus {
+        let id = (self.next_id.fetch_add(1, Ordering::Relaxed) + 1).to_string();
+        let mut watch = Watch {
+            status: WatchStatus {
+                uri: uri(&id),
+                id: id.clone(),
+                url: url.to_string(),
+                tab: tab.to_string(),
+                selector: selector.map(String::from),
+                interval_secs,
+                checks: 0,
+                changes: 0,
+                last_checked: None,
+                last_changed: None,
+                title: String::new(),
+                text: String::new(),
+                error: None,
+            },
+            fingerprint: None,
+        };
+        watch.update(first);
+        let status = watch.status.clone();
+        self.lock().insert(id, watch);
+        status
+    }
+
+    /// Record the result of a reload. Returns whether the text changed, or
+    /// `None` when the watch no longer exists.
+    pub fn record(&self, id: &str, read: Result<PageText, String>, now: u64) -> Option<bool> {
+        let mut watches = self.lock();
+        let watch = watches.get_mut(id)?;
+        watch.status.checks += 1;
+        watch.status.last_checked = Some(now);
+        let changed = match read {
+            Ok(text) => watch.update(text),
+            Err(e) => {
+                watch.status.error = Some(e);
+                false
+            }
+        };
+        if changed {
+            watch.status.changes += 1;
+            watch.status.last_changed = Some(now);
+        }
+        Some(changed)
+    }
+
+    /// Status of a watch.
+    pub fn get(&self, id: &str) -> Option<WatchStatus> {
+        self.lock().get(id).map(|watch| watch.status.clone())
+    }
+
+    /// Status of every watch, oldest first.
+    pub fn list(&self) -> Vec<WatchStatus> {
+        let mut list: Vec<WatchStatus> = self
+            .lock()
+            .values()
+            .map(|watch| watch.status.clone())
+            .collect();
+        list.sort_by_key(|status| status.id.parse::<u64>().unwrap_or_default());
+        list
+    }
+
+    /// Remove a watch.
+    pub fn remove(&self, id: &str) -> Option<WatchStatus> {
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&uri(id));
+        self.lock().remove(id).map(|watch| watch.status)
+    }
+
+    /// Subscribe to a watch resource. Fails for unknown URIs.
+    pub fn subscribe(&self, uri: &str) -> bool {
+        let known = uri
+            .strip_prefix(URI_PREFIX)
+            .is_some_and(|id| self.lock().contains_key(id));
+        if known {
+            self.subscriptions
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(uri.to_string());
+        }
+        known
+    }
+
+    /// Cancel a subscription.
+    pub fn unsubscribe(&self, uri: &str) {
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(uri);
+    }
+
+    /// Whether the client subscribed to a resource.
+    pub fn is_subscribed(&self, uri: &str) -> bool {
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(uri)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Watch>> {
+        self.watches.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Watch {
+    /// Take a successful read. Returns whether the text differs from the
+    /// previous read.
+    fn update(&mut self, read: PageText) -> bool {
+        let text = read.text.unwrap_or_default();
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let fingerprint = hasher.finish();
+        let changed = self
+            .fingerprint
+            .is_some_and(|previous| previous != fingerprint);
+        self.fingerprint = Some(fingerprint);
+        self.status.title = read.title;
+        self.status.text = text.chars().take(MAX_TEXT_CHARS).collect();
+        self.status.error = None;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_watches() {
+        let watches = PageWatches::default();
+        let read = |text: &str| PageText {
+            title: "Status".to_string(),
+            text: Some(text.to_string()),
+        };
+        let status = watches.add("https://a.test/", "t1", None, 60, read("up"));
+        assert_eq!(status.uri, "watch://1");
+        assert_eq!(status.text, "up");
+
+        assert_eq!(watches.record("1", Ok(read("up")), 10), Some(false));
+        assert_eq!(
+            watches.record("1", Err("timeout".to_string()), 20),
+            Some(false)
+        );
+        assert_eq!(watches.get("1").unwrap().error.as_deref(), Some("timeout"));
+        assert_eq!(watches.record("1", Ok(read("down")), 30), Some(true));
+        let status = watches.get("1").unwrap();
+        assert_eq!((status.checks, status.changes), (3, 1));
+        assert_eq!(status.last_changed, Some(30));
+        assert_eq!(status.error, None);
+
+        assert!(watches.subscribe("watch://1"));
+        assert!(!watches.subscribe("watch://2"));
+        assert!(watches.is_subscribed("watch://1"));
+        watches.remove("1");
+        assert!(!watches.is_subscribed("watch://1"));
+        assert_eq!(watches.record("1", Ok(read("up")), 40), None);
+    }
+}
@@ -0,0 +1,224 @@
+//! Limits on the files the server reads and writes for tools.
+//!
+//! Every tool that touches the host's file system asks the session's
+//! [`PathPolicy`] first: saved workspaces, screenshot baselines, and files the
+//! browser downloads. Operators can confine these files to a list of
+//! directories (`MCP_ALLOWED_PATHS`), cap their size (`MCP_MAX_FILE_MB`), and
+//! allow or block file extensions. The session's scratch directory is always
+//! allowed, so downloads keep working with an allowlist.
+
+use crate::config::Config;
+use std::path::{Component, Path, PathBuf};
+
+/// Limits on the files tools read and write.
+#[derive(Debug, Clone, Default)]
+pub struct PathPolicy {
+    /// Directories files must be in; empty allows any directory.
+    roots: Vec<PathBuf>,
+    /// Maximum file size in bytes.
+    max_file_bytes: Option<u64>,
+    /// Lowercase extensions files must have; empty allows any.
+    allowed_extensions: Vec<String>,
+    /// Lowercase extensions files must not have.
+    blocked_extensions: Vec<String>,
+}
+
+impl PathPolicy {
+    /// The policy configured by the operator.
+    pub fn new(config: &Config) -> Self {
+        Self {
+            roots: config.allowed_paths.iter().map(|p| resolve(p)).collect(),
+            max_file_bytes: (config.max_file_mb > 0).then(|| config.max_file_mb * 1024 * 1024),
+            allowed_extensions: normalize_extensions(&config.allowed_file_extensions),
+            blocked_extensions: normalize_extensions(&config.blocked_file_extensions),
+        }
+    }
+
+    /// Also allow files in `dir`, when the policy restricts directories.
+    pub fn with_root(mut self, dir: &Path) -> Self {
+        if !self.roots.is_empty() {
+            self.roots.push(resolve(dir));
+        }
+        self
+    }
+
+    /// Check that a file may be read, including its current size.
+    pub fn check_read(&self, path: &Path) -> anyhow::Result<()> {
+        self.check_path(path)?;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            self.check_size(path, metadata.len())?;
+        }
+        Ok(())
+    }
+
+    /// Check that `bytes` bytes may be written to a file.
+    pub fn check_write(&self, path: &Path, bytes: u64) -> anyhow::Result<()> {
+        self.check_path(path)?;
+        self.check_size(path, bytes)
+    }
+
+    /// Check the directory and extension of a file.
+    pub fn check_path(&self, path: &Path) -> anyhow::Result<()> {
+        if !self.roots.is_empty() {
+            let resolved = resolve(path);
+            if !self.roots.iter().any(|root| resolved.starts_with(root)) {
+                return Err(anyhow::anyhow!(
+                    "{:?} is outside the allowed directories (MCP_ALLOWED_PATHS)",
+                    path
+                ));
+            }
+        }
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if self.blocked_extensions.contains(&extension) {
+            return Err(anyhow::anyhow!(
+                "Files with the extension '.{}' are blocked",
+                extension
+            ));
+        }
+        if !self.allowed_extensions.is_empty() && !self.allowed_extensions.contains(&extension) {
+            return Err(anyhow::anyhow!(
+                "{:?} does not have an allowed extension ({})",
+                path,
+                self.allowed_extensions.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check the size of a file.
+    pub fn check_size(&self, path: &Path, bytes: u64) -> anyhow::Result<()> {
+        match self.max_file_bytes {
+            Some(max) if bytes > max => Err(anyhow::anyhow!(
+                "{:?} is {} bytes, more than the limit of {} bytes (MCP_MAX_FILE_MB)",
+                path,
+                bytes,
+                max
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Extensions without leading dots, lowercased.
+fn normalize_extensions(extensions: &[String]) -> Vec<String> {
+    extensions
+        .iter()
+        .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+        .filter(|e| !e.is_empty())
+        .collect()
+}
+
+/// Absolute form of a path with `.` and `..` removed and symlinks of its
+/// existing part resolved, so neither can lead out of an allowed directory.
+fn resolve(path: &Path) -> PathBuf {
+    resolve_links(path, 0)
+}
+
+/// Symlinks followed before giving up, as the OS does with `ELOOP`.
+const MAX_SYMLINKS: usize = 40;
+
+fn resolve_links(path: &Path, depth: usize) -> PathBuf {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("/"))
+            .join(path)
+    };
+    // Components are resolved one at a time, so a symlink is followed before
+    // a later `..` applies to its target, as the OS does
+    let mut resolved = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            // `resolved` has no symlinks left, so its parent is what `..` opens
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => {
+                resolved.push(name);
+                if let Ok(canonical) = resolved.canonicalize() {
+                    resolved = canonical;
+                } else if let Ok(target) = std::fs::read_link(&resolved) {
+                    // A dangling symlink: writing creates its target
+                    if depth < MAX_SYMLINKS {
+                        resolved.pop();
+                        resolved = resolve_links(&resolved.join(target), depth + 1);
+                    }
+                }
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_policy() {
+        let base = std::env::temp_dir().join(format!("mcp-policy-test-{}", std::process::id()));
+        let allowed = base.join("allowed");
+        let scratch = base.join("scratch");
+        std::fs::create_dir_all(&allowed).unwrap();
+        let config = Config {
+            allowed_paths: vec![allowed.clone()],
+            max_file_mb: 1,
+            blocked_file_extensions: vec![".EXE".to_string()],
+            ..Config::default()
+        };
+        let policy = PathPolicy::new(&config).with_root(&scratch);
+
+        assert!(policy.check_write(&allowed.join("a.json"), 10).is_ok());
+        assert!(policy.check_write(&allowed.join("new/a.json"), 10).is_ok());
+        assert!(policy.check_write(&scratch.join("report.csv"), 10).is_ok());
+        assert!(policy.check_write(&base.join("a.json"), 10).is_err());
+        assert!(policy
+            .check_write(&allowed.join("../elsewhere/a.json"), 10)
+            .is_err());
+        assert!(policy.check_write(&allowed.join("setup.exe"), 10).is_err());
+        assert!(policy
+            .check_write(&allowed.join("a.json"), 2 * 1024 * 1024)
+            .is_err());
+
+        let only_pdf = PathPolicy {
+            allowed_extensions: vec!["pdf".to_string()],
+            ..PathPolicy::default()
+        };
+        assert!(only_pdf.check_path(Path::new("/tmp/a.PDF")).is_ok());
+        assert!(only_pdf.check_path(Path::new("/tmp/a.csv")).is_err());
+        assert!(only_pdf.check_path(Path::new("/tmp/README")).is_err());
+        assert!(PathPolicy::default()
+            .check_write(Path::new("/anywhere/a.bin"), u64::MAX)
+            .is_ok());
+
+        // Symlinks are followed before `..`, as the OS follows them
+        #[cfg(unix)]
+        {
+            let outside = base.join("outside");
+            std::fs::create_dir_all(outside.join("sub")).unwrap();
+            std::os::unix::fs::symlink(outside.join("sub"), allowed.join("link")).unwrap();
+            std::os::unix::fs::symlink(base.join("gone.json"), allowed.join("dangling.json"))
+                .unwrap();
+            assert!(policy
+                .check_write(&allowed.join("link/a.json"), 10)
+                .is_err());
+            assert!(policy
+                .check_write(&allowed.join("link/../a.json"), 10)
+                .is_err());
+            assert!(policy
+                .check_write(&allowed.join("dangling.json"), 10)
+                .is_err());
+            assert!(policy
+                .check_write(&allowed.join("new/../a.json"), 10)
+                .is_ok());
+        }
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,310 @@
+//! Robots.txt checks and per-domain politeness delays.
+//!
+//! For operators who need their agents to crawl compliantly, navigations can
+//! consult the site's robots.txt (`MCP_RESPECT_ROBOTS_TXT`) and keep a minimum
+//! delay between requests to the same host (`MCP_POLITENESS_DELAY`). A
+//! `Crawl-delay` in robots.txt raises the delay for its site. Only navigations
+//! the tools start are covered; links the page follows on its own are not.
+//!
+//! robots.txt is read as RFC 9309 describes: the group for our product token, or
+//! else the `*` group, with the longest matching rule deciding and `Allow`
+//! winning ties. A missing robots.txt (4xx) allows everything; an unreachable
+//! one (5xx or network error) disallows everything.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Product token matched against robots.txt user-agent lines.
+pub const ROBOTS_USER_AGENT: &str = "mcp-computer-use";
+
+/// How long a fetched robots.txt is used before fetching it again.
+const ROBOTS_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Timeout for fetching robots.txt.
+const ROBOTS_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Rules of one robots.txt for our user agent.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RobotsRules {
+    /// `(allow, pattern)` pairs.
+    rules: Vec<(bool, String)>,
+    /// `Crawl-delay` of the group.
+    pub crawl_delay: Option<Duration>,
+}
+
+impl RobotsRules {
+    /// Rules disallowing every path, used when robots.txt is unreachable.
+    fn disallow_all() -> Self {
+        Self {
+            rules: vec![(false, "/".to_string())],
+            crawl_delay: None,
+        }
+    }
+
+    /// Parse a robots.txt for the given product token.
+    pub fn parse(content: &str, user_agent: &str) -> Self {
+        let user_agent = user_agent.to_ascii_lowercase();
+        let mut specific = RobotsRules::default();
+        let mut wildcard = RobotsRules::default();
+        let mut has_specific = false;
+        // Agents of the group being read, and whether its rules have started
+        let mut agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim();
+            if key == "user-agent" {
+                if in_rules {
+                    agents.clear();
+                    in_rules = false;
+                }
+                agents.push(value.to_ascii_lowercase());
+                continue;
+            }
+            in_rules = true;
+            let matches_specific = agents.contains(&user_agent);
+            let matches_wildcard = agents.iter().any(|agent| agent == "*");
+            has_specific |= matches_specific;
+            let targets = [
+                (matches_specific, &mut specific),
+                (matches_wildcard, &mut wildcard),
+            ];
+            for (matches, rules) in targets {
+                if !matches {
+                    continue;
+                }
+                match key.as_str() {
+                    // An empty Disallow allows everything
+                    "allow" | "disallow" if !value.is_empty() => {
+                        rules.rules.push((key == "allow", value.to_string()))
+                    }
+                    "crawl-delay" => {
+                        if let Ok(seconds) = value.parse::<f64>() {
+                            if seconds.is_finite() && seconds >= 0.0 {
+                                rules.crawl_delay = Some(Duration::from_secs_f64(seconds));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if has_specific {
+            specific
+        } else {
+            wildcard
+        }
+    }
+
+    /// Whether a path (with its query string) may be fetched.
+    pub fn is_allowed(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| pattern_matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+}
+
+/// Match a robots.txt path pattern, with `*` wildcards and a `$` end anchor.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        let last = i + 1 == parts.len();
+        if last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// Split an http(s) URL into its origin and its path with query string.
+fn split_url(url: &str) -> Option<(String, String)> {
+    let url = reqwest::Url::parse(url).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let mut path = url.path().to_string();
+    if let Some(query) = url.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    Some((url.origin().ascii_serialization(), path))
+}
+
+/// Robots.txt cache and per-host request times.
+pub struct Politeness {
+    respect_robots: bool,
+    min_delay: Duration,
+    client: reqwest::Client,
+    robots: Mutex<HashMap<String, (Instant, RobotsRules)>>,
+    /// Time the next request to each host may start.
+    next_request: Mutex<HashMap<String, Instant>>,
+}
+
+impl Politeness {
+    pub fn new(respect_robots: bool, min_delay: Duration) -> Self {
+        Self {
+            respect_robots,
+            min_delay,
+            client: reqwest::Client::new(),
+            robots: Mutex::new(HashMap::new()),
+            next_request: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether navigations need checking at all.
+    pub fn is_enabled(&self) -> bool {
+        self.respect_robots || !self.min_delay.is_zero()
+    }
+
+    /// Check a URL before navigating to it: fail if robots.txt disallows it,
+    /// otherwise wait until the host's politeness delay has passed.
+    pub async fn before_navigation(&self, url: &str) -> anyhow::Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        // Other schemes (about:, data:, file:) have no robots.txt or host
+        let Some((origin, path)) = split_url(url) else {
+            return Ok(());
+        };
+
+        let mut delay = self.min_delay;
+        if self.respect_robots {
+            let rules = self.robots_for(&origin).await;
+            if !rules.is_allowed(&path) {
+                return Err(anyhow::anyhow!(
+                    "robots.txt of {} disallows {}",
+                    origin,
+                    path
+                ));
+            }
+            delay = delay.max(rules.crawl_delay.unwrap_or_default());
+        }
+        if delay.is_zero() {
+            return Ok(());
+        }
+
+        // Reserve the slot under the lock and wait outside it, so concurrent
+        // navigations to one host queue up behind each other
+        let start = {
+            let mut next_request = self.next_request.lock().await;
+            let now = Instant::now();
+            let start = next_request
+                .get(&origin)
+                .map_or(now, |next| (*next).max(now));
+            next_request.insert(origin.clone(), start + delay);
+            start
+        };
+        let wait = start.saturating_duration_since(Instant::now());
+        if !wait.is_zero() {
+            tracing::debug!("Waiting {:?} before requesting {}", wait, origin);
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// The robots.txt rules of an origin, from the cache or fetched.
+    async fn robots_for(&self, origin: &str) -> RobotsRules {
+        if let Some((fetched, rules)) = self.robots.lock().await.get(origin) {
+            if fetched.elapsed() < ROBOTS_CACHE_TTL {
+                return rules.clone();
+            }
+        }
+        let rules = match self.fetch_robots(origin).await {
+            Ok(rules) => rules,
+            Err(e) => {
+                tracing::warn!("Failed to fetch robots.txt of {}: {}", origin, e);
+                // Not cached, so the next navigation tries again
+                return RobotsRules::disallow_all();
+            }
+        };
+        self.robots
+            .lock()
+            .await
+            .insert(origin.to_string(), (Instant::now(), rules.clone()));
+        rules
+    }
+
+    async fn fetch_robots(&self, origin: &str) -> anyhow::Result<RobotsRules> {
+        let response = self
+            .client
+            .get(format!("{}/robots.txt", origin))
+            .header(reqwest::header::USER_AGENT, ROBOTS_USER_AGENT)
+            .timeout(ROBOTS_FETCH_TIMEOUT)
+            .send()
+            .await?;
+        let status = response.status();
+        if status.is_client_error() {
+            return Ok(RobotsRules::default());
+        }
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("HTTP {}", status));
+        }
+        Ok(RobotsRules::parse(
+            &response.text().await?,
+            ROBOTS_USER_AGENT,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_robots_groups() {
+        let content = "
+            User-agent: *
+            Disallow: /private
+            Crawl-delay: 2
+
+            User-agent: other-bot
+            User-agent: mcp-computer-use
+            Disallow: /search
+            Allow: /search/about
+            Disallow: /*.pdf$
+        ";
+        let rules = RobotsRules::parse(content, ROBOTS_USER_AGENT);
+        assert!(rules.is_allowed("/private"));
+        assert!(!rules.is_allowed("/search?q=rust"));
+        assert!(rules.is_allowed("/search/about"));
+        assert!(!rules.is_allowed("/docs/guide.pdf"));
+        assert!(rules.is_allowed("/docs/guide.pdf?page=2"));
+        assert_eq!(rules.crawl_delay, None);
+
+        let rules = RobotsRules::parse(content, "another-agent");
+        assert!(!rules.is_allowed("/private/notes"));
+        assert!(rules.is_allowed("/search"));
+        assert_eq!(rules.crawl_delay, Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_pattern_matches() {
+        assert!(pattern_matches("/", "/anything"));
+        assert!(pattern_matches("/a*/c", "/a/b/c/d"));
+        assert!(pattern_matches("/a$", "/a"));
+        assert!(!pattern_matches("/a$", "/ab"));
+        assert!(!pattern_matches("/b", "/a/b"));
+        assert!(RobotsRules::parse("User-agent: *\nDisallow:", "x").is_allowed("/"));
+    }
+}
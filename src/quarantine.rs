@@ -0,0 +1,223 @@
+//! Checks on files the browser downloads.
+//!
+//! A download the agent triggers lands in the `downloads` directory of the
+//! session's scratch directory, which serves as a quarantine: finished files
+//! are checked against the operator's download policy and either made
+//! non-executable or deleted. The content type is sniffed from the file's
+//! first bytes rather than trusted from the server or the file name, and
+//! executables are refused unless `MCP_DOWNLOAD_ALLOW_EXECUTABLES` is set.
+
+use crate::config::Config;
+use std::io::Read;
+use std::path::Path;
+
+/// Number of leading bytes read to sniff a file's type.
+const SNIFF_LEN: usize = 512;
+
+/// Content types of programs the host could run.
+const EXECUTABLE_TYPES: &[&str] = &[
+    "application/x-executable",
+    "application/x-msdownload",
+    "application/x-mach-binary",
+    "application/x-sh",
+];
+
+/// Extensions operating systems run or install when a file is opened.
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "app", "apk", "appimage", "bat", "cmd", "com", "deb", "dmg", "exe", "jar", "js", "lnk", "msi",
+    "pkg", "ps1", "rpm", "scr", "sh", "vbs",
+];
+
+/// Limits on downloaded files.
+#[derive(Debug, Clone, Default)]
+pub struct DownloadPolicy {
+    /// Allowed content types, exact or as `type/*`; empty allows any.
+    allowed_types: Vec<String>,
+    /// Maximum size of a download in bytes.
+    max_bytes: Option<u64>,
+    /// Whether executables may be downloaded.
+    allow_executables: bool,
+}
+
+impl DownloadPolicy {
+    /// The policy configured by the operator.
+    pub fn new(config: &Config) -> Self {
+        Self {
+            allowed_types: config
+                .download_allowed_types
+                .iter()
+                .map(|t| t.trim().to_ascii_lowercase())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            max_bytes: (config.download_max_mb > 0).then(|| config.download_max_mb * 1024 * 1024),
+            allow_executables: config.download_allow_executables,
+        }
+    }
+
+    /// Check the size of a download, finished or not.
+    pub fn check_size(&self, bytes: u64) -> Result<(), String> {
+        match self.max_bytes {
+            Some(max) if bytes > max => Err(format!(
+                "the download is larger than the limit of {} MB (MCP_DOWNLOAD_MAX_MB)",
+                max / 1024 / 1024
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check a finished download of the given sniffed content type.
+    pub fn check(&self, path: &Path, content_type: &str, bytes: u64) -> Result<(), String> {
+        self.check_size(bytes)?;
+        let listed = self.allowed_types.iter().any(|allowed| {
+            allowed == content_type
+                || allowed
+                    .strip_suffix("/*")
+                    .is_some_and(|prefix| content_type.split('/').next() == Some(prefix))
+        });
+        if is_executable(path, content_type) && !self.allow_executables && !listed {
+            return Err(format!(
+                "executable files ({}) may not be downloaded (MCP_DOWNLOAD_ALLOW_EXECUTABLES)",
+                content_type
+            ));
+        }
+        if !self.allowed_types.is_empty() && !listed {
+            return Err(format!(
+                "the content type {} is not allowed (MCP_DOWNLOAD_ALLOWED_TYPES)",
+                content_type
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Whether a file is a program, by content or by extension.
+fn is_executable(path: &Path, content_type: &str) -> bool {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    EXECUTABLE_TYPES.contains(&content_type) || EXECUTABLE_EXTENSIONS.contains(&extension.as_str())
+}
+
+/// Content type of a file, sniffed from its first bytes.
+pub fn sniff_file(path: &Path) -> std::io::Result<&'static str> {
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    std::fs::File::open(path)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)?;
+    Ok(sniff(&head))
+}
+
+/// Content type of data, from its magic bytes, or a guess at text.
+pub fn sniff(head: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"%PDF-", "application/pdf"),
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xFF\xD8\xFF", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1F\x8B", "application/gzip"),
+        (b"7z\xBC\xAF\x27\x1C", "application/x-7z-compressed"),
+        (b"Rar!\x1A\x07", "application/vnd.rar"),
+        (b"\x7FELF", "application/x-executable"),
+        (b"MZ", "application/x-msdownload"),
+        (b"\xFE\xED\xFA\xCE", "application/x-mach-binary"),
+        (b"\xFE\xED\xFA\xCF", "application/x-mach-binary"),
+        (b"\xCE\xFA\xED\xFE", "application/x-mach-binary"),
+        (b"\xCF\xFA\xED\xFE", "application/x-mach-binary"),
+        (b"\xCA\xFE\xBA\xBE", "application/x-mach-binary"),
+        (b"#!", "application/x-sh"),
+    ];
+    if let Some((_, content_type)) = SIGNATURES.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return content_type;
+    }
+    if head.len() >= 12 && &head[..4] == b"RIFF" && &head[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+    // Text: no NUL bytes, and valid UTF-8 up to a character cut off at the end
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&head[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return "application/octet-stream",
+    };
+    if text.contains('\0') {
+        return "application/octet-stream";
+    }
+    let start = text.trim_start().to_ascii_lowercase();
+    if start.starts_with("<!doctype html") || start.starts_with("<html") {
+        "text/html"
+    } else if start.starts_with("<?xml") || start.starts_with("<svg") {
+        "application/xml"
+    } else if start.starts_with('{') || start.starts_with('[') {
+        "application/json"
+    } else {
+        "text/plain"
+    }
+}
+
+/// Remove the execute permissions of a downloaded file.
+pub fn make_non_executable(path: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(sniff(b"%PDF-1.7\n..."), "application/pdf");
+        assert_eq!(sniff(b"\x7FELF\x02\x01"), "application/x-executable");
+        assert_eq!(sniff(b"MZ\x90\x00"), "application/x-msdownload");
+        assert_eq!(sniff(b"#!/bin/sh\nrm -rf ~"), "application/x-sh");
+        assert_eq!(sniff(b"RIFF\x00\x00\x00\x00WEBPVP8 "), "image/webp");
+        assert_eq!(sniff(b"  <!DOCTYPE html><html>"), "text/html");
+        assert_eq!(sniff(b"name,price\nwidget,3\n"), "text/plain");
+        assert_eq!(sniff("caf\u{e9}".as_bytes()), "text/plain");
+        assert_eq!(sniff(&"caf\u{e9}".as_bytes()[..4]), "text/plain");
+        assert_eq!(sniff(b"\x00\x01\x02"), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_download_policy() {
+        let config = Config {
+            download_allowed_types: vec!["application/pdf".to_string(), "image/*".to_string()],
+            download_max_mb: 1,
+            ..Config::default()
+        };
+        let policy = DownloadPolicy::new(&config);
+        let file = Path::new("report.pdf");
+        assert!(policy.check(file, "application/pdf", 10).is_ok());
+        assert!(policy.check(file, "image/png", 10).is_ok());
+        assert!(policy.check(file, "text/html", 10).is_err());
+        assert!(policy
+            .check(file, "application/pdf", 2 * 1024 * 1024)
+            .is_err());
+        assert!(policy.check_size(2 * 1024 * 1024).is_err());
+
+        let any = DownloadPolicy::default();
+        assert!(any.check(Path::new("data.csv"), "text/plain", 10).is_ok());
+        assert!(any
+            .check(Path::new("setup.bin"), "application/x-msdownload", 10)
+            .is_err());
+        // Scripts are refused by extension, whatever their content
+        assert!(any.check(Path::new("run.ps1"), "text/plain", 10).is_err());
+        let executables = DownloadPolicy {
+            allow_executables: true,
+            ..DownloadPolicy::default()
+        };
+        assert!(executables
+            .check(Path::new("tool"), "application/x-executable", 10)
+            .is_ok());
+    }
+}
@@ -0,0 +1,279 @@
+//! Site-specific automation recipes.
+//!
+//! A recipe tells the tools what is known about a frequently used site: the
+//! selectors of its login form, search box and cookie banner, what to wait for
+//! after a page loads, and which tools must not run on it. Recipes are TOML or
+//! JSON files in the directory named by `MCP_RECIPES_DIR`, one site per file:
+//!
+//! ```toml
+//! domains = ["github.com"]
+//! blocked_actions = ["login_with_saved_credentials"]
+//!
+//! [selectors]
+//! username = "#login_field"
+//! password = "#password"
+//! search_box = "input[name=query-builder-test]"
+//! cookie_accept = "button.js-cookie-consent-accept"
+//!
+//! [wait]
+//! selector = "main"
+//! timeout_ms = 5000
+//! ```
+//!
+//! A recipe applies to its domains and their subdomains; when several match a
+//! host, the one with the longest matching domain wins.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Default time to wait for a recipe's wait selector (in milliseconds).
+pub const DEFAULT_WAIT_TIMEOUT_MS: u64 = 5000;
+
+/// Interval between checks for a recipe's wait selector (in milliseconds).
+pub const WAIT_POLL_INTERVAL_MS: u64 = 200;
+
+/// Selectors used on sites without a recipe.
+static NO_SELECTORS: RecipeSelectors = RecipeSelectors {
+    username: None,
+    password: None,
+    submit: None,
+    search_box: None,
+    cookie_accept: None,
+};
+
+/// Hints for one site.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Recipe {
+    /// Name of the recipe, from its file name.
+    #[serde(skip_deserializing)]
+    pub name: String,
+    /// Hosts the recipe applies to; subdomains are included.
+    pub domains: Vec<String>,
+    /// Known selectors on the site.
+    #[serde(default)]
+    pub selectors: RecipeSelectors,
+    /// What to wait for after a page of the site loads.
+    #[serde(default)]
+    pub wait: Option<WaitRule>,
+    /// Tools refused while the active page is on the site.
+    #[serde(default)]
+    pub blocked_actions: Vec<String>,
+}
+
+/// CSS selectors of well-known elements of a site.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecipeSelectors {
+    /// Username field of the login form.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Password field of the login form.
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Submit button of the login form.
+    #[serde(default)]
+    pub submit: Option<String>,
+    /// Search box.
+    #[serde(default)]
+    pub search_box: Option<String>,
+    /// Button accepting or dismissing the cookie banner, clicked after
+    /// navigation.
+    #[serde(default)]
+    pub cookie_accept: Option<String>,
+}
+
+/// Readiness rule applied after navigating to a site.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaitRule {
+    /// Element that appears once the page is usable.
+    #[serde(default)]
+    pub selector: Option<String>,
+    /// Maximum time to wait for `selector` (default: 5000).
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Extra delay after the page is ready, for late client-side rendering.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+impl WaitRule {
+    /// Maximum time to wait for the selector.
+    pub fn timeout_ms(&self) -> u64 {
+        self.timeout_ms.unwrap_or(DEFAULT_WAIT_TIMEOUT_MS)
+    }
+}
+
+impl Recipe {
+    /// Length of the longest of the recipe's domains matching the host, if any.
+    fn match_len(&self, host: &str) -> Option<usize> {
+        let host = host.to_ascii_lowercase();
+        self.domains
+            .iter()
+            .map(|domain| domain.to_ascii_lowercase())
+            .filter(|domain| host == *domain || host.ends_with(&format!(".{}", domain)))
+            .map(|domain| domain.len())
+            .max()
+    }
+
+    /// Whether the recipe blocks a tool.
+    pub fn blocks(&self, tool: &str) -> bool {
+        self.blocked_actions.iter().any(|action| action == tool)
+    }
+}
+
+/// The loaded recipes.
+#[derive(Debug, Clone, Default)]
+pub struct RecipeRegistry {
+    recipes: Vec<Recipe>,
+}
+
+impl RecipeRegistry {
+    /// Parse one recipe file; `json` selects JSON instead of TOML.
+    pub fn parse(name: &str, content: &str, json: bool) -> anyhow::Result<Recipe> {
+        let mut recipe: Recipe = if json {
+            serde_json::from_str(content)?
+        } else {
+            toml::from_str(content)?
+        };
+        if recipe.domains.is_empty() {
+            return Err(anyhow::anyhow!("Recipe '{}' lists no domains", name));
+        }
+        recipe.name = name.to_string();
+        Ok(recipe)
+    }
+
+    /// Load every `.toml` and `.json` file of a directory.
+    pub fn load_dir(dir: &Path) -> anyhow::Result<Self> {
+        let entries = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read recipes directory {:?}", dir))?;
+        let mut paths = Vec::new();
+        for entry in entries {
+            paths.push(entry?.path());
+        }
+        paths.sort();
+
+        let mut recipes = Vec::new();
+        for path in paths {
+            let json = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("toml") => false,
+                Some("json") => true,
+                _ => continue,
+            };
+            let name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default();
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read recipe {:?}", path))?;
+            let recipe = Self::parse(name, &content, json)
+                .with_context(|| format!("Invalid recipe {:?}", path))?;
+            recipes.push(recipe);
+        }
+        Ok(Self { recipes })
+    }
+
+    /// The recipe for a host: the one with the longest matching domain.
+    pub fn for_host(&self, host: &str) -> Option<&Recipe> {
+        self.recipes
+            .iter()
+            .filter_map(|recipe| recipe.match_len(host).map(|len| (len, recipe)))
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, recipe)| recipe)
+    }
+
+    /// Known selectors of the site serving a host; empty without a recipe.
+    pub fn selectors_for(&self, host: &str) -> &RecipeSelectors {
+        self.for_host(host)
+            .map_or(&NO_SELECTORS, |recipe| &recipe.selectors)
+    }
+
+    /// Whether any recipe blocks a tool, so the page's host needs checking.
+    pub fn may_block(&self, tool: &str) -> bool {
+        self.recipes.iter().any(|recipe| recipe.blocks(tool))
+    }
+
+    /// Number of loaded recipes.
+    pub fn len(&self) -> usize {
+        self.recipes.len()
+    }
+}
+
+/// JavaScript expression telling whether an element matching the selector is
+/// in the current document.
+pub fn exists_script(selector: &str) -> anyhow::Result<String> {
+    Ok(format!(
+        "!!document.querySelector({})",
+        serde_json::to_string(selector)?
+    ))
+}
+
+/// JavaScript expression clicking the first element matching the selector.
+/// Evaluates to whether one was found.
+pub fn click_script(selector: &str) -> anyhow::Result<String> {
+    Ok(format!(
+        "(function(el) {{ if (el) {{ el.click(); }} return !!el; }})(document.querySelector({}))",
+        serde_json::to_string(selector)?
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recipe_for_host() {
+        let github = RecipeRegistry::parse(
+            "github",
+            r##"
+                domains = ["github.com"]
+                blocked_actions = ["login_with_saved_credentials"]
+                [selectors]
+                username = "#login_field"
+                [wait]
+                selector = "main"
+            "##,
+            false,
+        )
+        .unwrap();
+        let gist = RecipeRegistry::parse(
+            "gist",
+            r#"{ "domains": ["gist.github.com"], "wait": { "delay_ms": 500 } }"#,
+            true,
+        )
+        .unwrap();
+        let registry = RecipeRegistry {
+            recipes: vec![github, gist],
+        };
+
+        assert_eq!(registry.for_host("github.com").unwrap().name, "github");
+        assert_eq!(registry.for_host("api.github.com").unwrap().name, "github");
+        assert_eq!(registry.for_host("gist.github.com").unwrap().name, "gist");
+        assert!(registry.for_host("notgithub.com").is_none());
+        assert!(registry.may_block("login_with_saved_credentials"));
+        assert!(!registry
+            .for_host("gist.github.com")
+            .unwrap()
+            .blocks("login_with_saved_credentials"));
+        assert_eq!(
+            registry
+                .for_host("github.com")
+                .unwrap()
+                .wait
+                .as_ref()
+                .unwrap()
+                .timeout_ms(),
+            DEFAULT_WAIT_TIMEOUT_MS
+        );
+    }
+
+    #[test]
+    fn test_invalid_recipe() {
+        assert!(RecipeRegistry::parse("empty", "domains = []", false).is_err());
+        assert!(
+            RecipeRegistry::parse("typo", "domains = [\"a.com\"]\nblocked = []", false).is_err()
+        );
+    }
+}
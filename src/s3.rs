@@ -0,0 +1,280 @@
+//! Minimal client for S3-compatible object storage.
+//!
+//! Just enough of the S3 API to keep screenshots in a bucket: PUT, GET and
+//! DELETE of single objects with path-style URLs, signed with AWS Signature
+//! Version 4. Works with AWS S3 and compatible services such as MinIO, R2 and
+//! Ceph. Credentials come from the standard `AWS_ACCESS_KEY_ID`,
+//! `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN` variables.
+
+use anyhow::Context;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Timeout of each request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Bucket location and credentials.
+pub struct S3Bucket {
+    client: reqwest::Client,
+    /// Endpoint URL without a trailing slash, e.g. `https://s3.us-east-1.amazonaws.com`.
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+impl S3Bucket {
+    /// A bucket at an endpoint, with credentials from the environment.
+    pub fn from_env(endpoint: &str, bucket: &str, region: &str) -> anyhow::Result<Self> {
+        let access_key = std::env::var("AWS_ACCESS_KEY_ID")
+            .context("AWS_ACCESS_KEY_ID is required for S3 screenshot storage")?;
+        let secret_key = std::env::var("AWS_SECRET_ACCESS_KEY")
+            .context("AWS_SECRET_ACCESS_KEY is required for S3 screenshot storage")?;
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+            access_key,
+            secret_key,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+
+    /// Store an object.
+    pub async fn put(&self, key: &str, body: Vec<u8>) -> anyhow::Result<()> {
+        let request = self.request(reqwest::Method::PUT, key, &body)?;
+        check(request.body(body).send().await?).await?;
+        Ok(())
+    }
+
+    /// Read an object.
+    pub async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        let response = check(self.request(reqwest::Method::GET, key, &[])?.send().await?).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Delete an object.
+    pub async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        check(
+            self.request(reqwest::Method::DELETE, key, &[])?
+                .send()
+                .await?,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// A signed request for an object.
+    fn request(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        body: &[u8],
+    ) -> anyhow::Result<reqwest::RequestBuilder> {
+        let path = format!("/{}/{}", self.bucket, encode_path(key));
+        let url = reqwest::Url::parse(&format!("{}{}", self.endpoint, path))
+            .with_context(|| format!("Invalid S3 endpoint '{}'", self.endpoint))?;
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let signed = sign(&SigningInput {
+            method: method.as_str(),
+            host: &host,
+            path: &path,
+            payload_hash: &hex(&Sha256::digest(body)),
+            amz_date: &amz_date(now),
+            region: &self.region,
+            access_key: &self.access_key,
+            secret_key: &self.secret_key,
+            session_token: self.session_token.as_deref(),
+        });
+
+        let mut request = self.client.request(method, url);
+        for (name, value) in signed {
+            request = request.header(name, value);
+        }
+        Ok(request)
+    }
+}
+
+/// Fail on error statuses, with the start of the error body.
+async fn check(response: reqwest::Response) -> anyhow::Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(anyhow::anyhow!(
+        "S3 request failed with HTTP {}: {}",
+        status,
+        body.chars().take(200).collect::<String>()
+    ))
+}
+
+/// What a request signature covers.
+struct SigningInput<'a> {
+    method: &'a str,
+    host: &'a str,
+    /// Percent-encoded path.
+    path: &'a str,
+    payload_hash: &'a str,
+    amz_date: &'a str,
+    region: &'a str,
+    access_key: &'a str,
+    secret_key: &'a str,
+    session_token: Option<&'a str>,
+}
+
+/// Headers signing a request with AWS Signature Version 4.
+fn sign(input: &SigningInput) -> Vec<(&'static str, String)> {
+    let mut headers = vec![
+        ("x-amz-content-sha256", input.payload_hash.to_string()),
+        ("x-amz-date", input.amz_date.to_string()),
+    ];
+    if let Some(token) = input.session_token {
+        headers.push(("x-amz-security-token", token.to_string()));
+    }
+
+    // Canonical headers must be sorted by name; host sorts first
+    let mut canonical_headers = format!("host:{}\n", input.host);
+    let mut signed_headers = "host".to_string();
+    for (name, value) in &headers {
+        canonical_headers.push_str(&format!("{}:{}\n", name, value));
+        signed_headers.push(';');
+        signed_headers.push_str(name);
+    }
+    let canonical_request = format!(
+        "{}\n{}\n\n{}\n{}\n{}",
+        input.method, input.path, canonical_headers, signed_headers, input.payload_hash
+    );
+
+    let date = &input.amz_date[..8];
+    let scope = format!("{}/{}/s3/aws4_request", date, input.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        input.amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = signing_key(input.secret_key, date, input.region, "s3");
+    let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    headers.push((
+        "authorization",
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            input.access_key, scope, signed_headers, signature
+        ),
+    ));
+    headers
+}
+
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Percent-encode an object key for the request path, keeping `/`.
+fn encode_path(key: &str) -> String {
+    let mut encoded = String::with_capacity(key.len());
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Format a UNIX timestamp as `YYYYMMDDTHHMMSSZ`.
+fn amz_date(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let time = secs % 86_400;
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature() {
+        assert_eq!(amz_date(1_700_000_000), "20231114T221320Z");
+        assert_eq!(
+            hex(&signing_key(
+                "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+                "20150830",
+                "us-east-1",
+                "iam"
+            )),
+            "c4afb1cc5771d871763a393e44b703571b55cc28424d1a5e86da6ed3c154a4b9"
+        );
+        assert_eq!(encode_path("shots/a b.png"), "shots/a%20b.png");
+
+        let headers = sign(&SigningInput {
+            method: "GET",
+            host: "s3.example.test",
+            path: "/bucket/shots/1.png",
+            payload_hash: &hex(&Sha256::digest(b"")),
+            amz_date: "20231114T221320Z",
+            region: "us-east-1",
+            access_key: "AKIDEXAMPLE",
+            secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            session_token: None,
+        });
+        assert_eq!(
+            headers.last().unwrap(),
+            &(
+                "authorization",
+                "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20231114/us-east-1/s3/aws4_request, \
+                 SignedHeaders=host;x-amz-content-sha256;x-amz-date, \
+                 Signature=fe52762038ed7b24cc1251a7846b55784d4129775c2464c5c38d86c9919735b2"
+                    .to_string()
+            )
+        );
+    }
+}
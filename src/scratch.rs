@@ -0,0 +1,293 @@
+//! Scratch directory of a session.
+//!
+//! Every session gets its own temporary directory for the files its tools
+//! produce, so they land in one predictable place instead of the user's
+//! Downloads folder, and one session never sees another's files. Browser
+//! downloads are saved to its `downloads` subdirectory. `list_workspace_files`
+//! lists the contents; the directory is removed when the session ends.
+//!
+//! The directory is swept whenever it is listed, and after tool calls once
+//! files were added, renamed or removed in it or its `downloads`
+//! subdirectory: files that break the session's [`PathPolicy`] are deleted,
+//! and so are downloads that break the [`DownloadPolicy`] or whose content
+//! type cannot be read; the downloads that pass are made non-executable.
+
+use crate::path_policy::PathPolicy;
+use crate::quarantine::{self, DownloadPolicy};
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Distinguishes the scratch directories of sessions in one process.
+static NEXT_SESSION: AtomicU64 = AtomicU64::new(0);
+
+/// Suffixes browsers give files still being downloaded.
+const PARTIAL_SUFFIXES: &[&str] = &[".crdownload", ".part"];
+
+/// A file in the scratch directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ScratchFile {
+    /// Path relative to the scratch directory, with `/` separators.
+    pub path: String,
+    /// Size in bytes.
+    pub bytes: u64,
+    /// Last modification time in seconds since the UNIX epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+    /// Whether the file is a download still in progress.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
+    /// Content type sniffed from the file's first bytes; absent for downloads
+    /// in progress.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+/// A file deleted for breaking the path or download policy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct RemovedFile {
+    /// Path relative to the scratch directory, with `/` separators.
+    pub path: String,
+    /// The rule the file broke.
+    pub reason: String,
+}
+
+/// Modification times of the scratch directory and its `downloads`
+/// subdirectory, which change when files are added, renamed or removed.
+type DirStamp = (Option<SystemTime>, Option<SystemTime>);
+
+/// Scratch directory of a session, removed when dropped.
+pub struct SessionScratch {
+    dir: PathBuf,
+    /// The directories as the last sweep after a tool call found them.
+    swept: Mutex<Option<DirStamp>>,
+}
+
+impl SessionScratch {
+    /// Create the scratch directory of a new session under `base`, or under
+    /// `mcp-computer-use/sessions` in the temp directory.
+    pub fn new(base: Option<&Path>) -> Self {
+        let base = base.map(Path::to_path_buf).unwrap_or_else(|| {
+            std::env::temp_dir()
+                .join("mcp-computer-use")
+                .join("sessions")
+        });
+        let dir = base.join(format!(
+            "{}-{}",
+            std::process::id(),
+            NEXT_SESSION.fetch_add(1, Ordering::Relaxed)
+        ));
+        if let Err(e) = std::fs::create_dir_all(dir.join("downloads")) {
+            tracing::warn!("Failed to create scratch directory {:?}: {}", dir, e);
+        }
+        Self {
+            dir,
+            swept: Mutex::new(None),
+        }
+    }
+
+    /// The scratch directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Directory browser downloads are saved to.
+    pub fn downloads_dir(&self) -> PathBuf {
+        self.dir.join("downloads")
+    }
+
+    /// Files in the scratch directory and its subdirectories, sorted by path.
+    pub fn list(&self) -> anyhow::Result<Vec<ScratchFile>> {
+        let mut files = Vec::new();
+        let mut pending = vec![self.dir.clone()];
+        while let Some(dir) = pending.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                if metadata.is_dir() {
+                    pending.push(entry.path());
+                    continue;
+                }
+                let path = entry.path();
+                let relative = path
+                    .strip_prefix(&self.dir)
+                    .unwrap_or(&path)
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let partial = PARTIAL_SUFFIXES.iter().any(|s| relative.ends_with(s));
+                files.push(ScratchFile {
+                    content_type: (!partial)
+                        .then(|| quarantine::sniff_file(&path).ok())
+                        .flatten()
+                        .map(String::from),
+                    partial,
+                    path: relative,
+                    bytes: metadata.len(),
+                    modified: metadata
+                        .modified()
+                        .ok()
+                        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                        .map(|age| age.as_secs()),
+                });
+            }
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+
+    fn stamp(&self) -> DirStamp {
+        let modified = |dir: &Path| std::fs::metadata(dir).and_then(|m| m.modified()).ok();
+        (modified(&self.dir), modified(&self.downloads_dir()))
+    }
+
+    /// [`sweep`](Self::sweep) the directory if files were added, renamed or
+    /// removed in it since the last time, and return nothing otherwise.
+    pub fn sweep_if_changed(
+        &self,
+        paths: &PathPolicy,
+        downloads: &DownloadPolicy,
+    ) -> anyhow::Result<Vec<RemovedFile>> {
+        // Taken before sweeping, so files added meanwhile are swept next time
+        let stamp = self.stamp();
+        if *self.swept.lock().unwrap_or_else(|e| e.into_inner()) == Some(stamp) {
+            return Ok(Vec::new());
+        }
+        let removed = self.sweep(paths, downloads)?;
+        *self.swept.lock().unwrap_or_else(|e| e.into_inner()) = Some(stamp);
+        Ok(removed)
+    }
+
+    /// Delete the files that break the policies and make the remaining
+    /// downloads non-executable. Downloads in progress are only checked for
+    /// size; deleting one makes the browser fail the download. Finished
+    /// downloads whose content type cannot be read are deleted.
+    pub fn sweep(
+        &self,
+        paths: &PathPolicy,
+        downloads: &DownloadPolicy,
+    ) -> anyhow::Result<Vec<RemovedFile>> {
+        let mut removed = Vec::new();
+        for file in self.list()? {
+            let path = self.dir.join(&file.path);
+            let download = file.path.starts_with("downloads/");
+            let verdict = match (download, file.partial, file.content_type.as_deref()) {
+                (true, true, _) => downloads.check_size(file.bytes),
+                (true, false, Some(content_type)) => paths
+                    .check_read(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|()| downloads.check(&path, content_type, file.bytes)),
+                (true, false, None) => Err("its content type could not be read".to_string()),
+                (false, _, _) => paths.check_read(&path).map_err(|e| e.to_string()),
+            };
+            match verdict {
+                Err(reason) => {
+                    tracing::warn!("Deleting {}: {}", file.path, reason);
+                    // One file that cannot be deleted must not shield the rest
+                    if let Err(e) = std::fs::remove_file(&path) {
+                        tracing::warn!("Failed to delete {}: {}", file.path, e);
+                        continue;
+                    }
+                    removed.push(RemovedFile {
+                        path: file.path,
+                        reason,
+                    });
+                }
+                Ok(()) if download && !file.partial => {
+                    if let Err(e) = quarantine::make_non_executable(&path) {
+                        tracing::warn!("Failed to make {} non-executable: {}", file.path, e);
+                    }
+                }
+                Ok(()) => {}
+            }
+        }
+        Ok(removed)
+    }
+}
+
+impl Drop for SessionScratch {
+    fn drop(&mut self) {
+        if self.dir.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&self.dir) {
+                tracing::warn!("Failed to remove scratch directory {:?}: {}", self.dir, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_scratch() {
+        let base = std::env::temp_dir().join(format!("mcp-scratch-test-{}", std::process::id()));
+        let scratch = SessionScratch::new(Some(&base));
+        let other = SessionScratch::new(Some(&base));
+        assert_ne!(scratch.dir(), other.dir());
+        assert!(scratch.list().unwrap().is_empty());
+
+        std::fs::write(scratch.downloads_dir().join("report.csv"), "a,b").unwrap();
+        std::fs::write(scratch.downloads_dir().join("big.zip.crdownload"), "").unwrap();
+        std::fs::write(scratch.dir().join("notes.txt"), "hi").unwrap();
+        let files = scratch.list().unwrap();
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "downloads/big.zip.crdownload",
+                "downloads/report.csv",
+                "notes.txt"
+            ]
+        );
+        assert!(files[0].partial);
+        assert_eq!(files[0].content_type, None);
+        assert_eq!((files[1].bytes, files[1].partial), (3, false));
+        assert_eq!(files[1].content_type.as_deref(), Some("text/plain"));
+
+        std::fs::write(scratch.downloads_dir().join("tool"), b"\x7FELF\x02").unwrap();
+        std::fs::write(scratch.dir().join("notes.exe"), "hi").unwrap();
+        let config = crate::config::Config {
+            blocked_file_extensions: vec!["exe".to_string(), "crdownload".to_string()],
+            ..Default::default()
+        };
+        let removed = scratch
+            .sweep(&PathPolicy::new(&config), &DownloadPolicy::new(&config))
+            .unwrap();
+        let removed: Vec<&str> = removed.iter().map(|f| f.path.as_str()).collect();
+        // Downloads in progress are only checked for size
+        assert_eq!(removed, vec!["downloads/tool", "notes.exe"]);
+        assert_eq!(scratch.list().unwrap().len(), 3);
+
+        let (paths, downloads) = (PathPolicy::new(&config), DownloadPolicy::new(&config));
+        scratch.sweep_if_changed(&paths, &downloads).unwrap();
+        // Nothing was added since, so the directory is not listed again
+        assert!(scratch
+            .sweep_if_changed(&paths, &downloads)
+            .unwrap()
+            .is_empty());
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(scratch.downloads_dir().join("tool2"), b"\x7FELF\x02").unwrap();
+        let removed = scratch.sweep_if_changed(&paths, &downloads).unwrap();
+        assert_eq!(removed[0].path, "downloads/tool2");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(scratch.downloads_dir().join("report.csv"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let dir = scratch.dir().to_path_buf();
+        drop(scratch);
+        assert!(!dir.exists());
+        drop(other);
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
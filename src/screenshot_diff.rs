@@ -0,0 +1,138 @@
+//! Screenshot change detection.
+//!
+//! Screenshots are reduced to a 256-bit difference hash (dHash): the image is
+//! converted to grayscale, shrunk to a 17x16 grid, and each bit records whether
+//! a cell is brighter than its right neighbour. Rendering noise such as
+//! anti-aliasing or compression artifacts does not change the hash, while
+//! layout and content changes do.
+//!
+//! Blank captures, which browsers occasionally return right after a navigation,
+//! are recognized by an almost uniform thumbnail.
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use image::imageops::FilterType;
+
+/// Width and height of the hash grid; one extra column is sampled for comparisons.
+const HASH_SIZE: u32 = 16;
+
+/// Size of the thumbnail inspected for blank screenshots.
+const BLANK_THUMBNAIL_SIZE: u32 = 32;
+
+/// Largest brightness spread (0-255) across the thumbnail of a blank screenshot.
+const BLANK_TOLERANCE: u8 = 4;
+
+/// Perceptual hash of a screenshot.
+pub type ScreenshotHash = [u64; 4];
+
+/// Identity of a screenshot, used to tell whether a page changed between captures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenshotFingerprint {
+    /// URL of the page when the screenshot was taken.
+    pub url: String,
+    /// Perceptual hash of the screenshot.
+    pub hash: ScreenshotHash,
+}
+
+impl ScreenshotFingerprint {
+    /// Fingerprint a base64-encoded PNG screenshot.
+    pub fn new(url: &str, screenshot_base64: &str) -> Result<Self> {
+        let png = BASE64.decode(screenshot_base64)?;
+        Ok(Self {
+            url: url.to_string(),
+            hash: perceptual_hash(&png)?,
+        })
+    }
+}
+
+/// Compute the difference hash of an encoded image.
+pub fn perceptual_hash(encoded: &[u8]) -> Result<ScreenshotHash> {
+    let grid = image::load_from_memory(encoded)?
+        .grayscale()
+        .resize_exact(HASH_SIZE + 1, HASH_SIZE, FilterType::Triangle)
+        .to_luma8();
+
+    let mut hash = [0u64; 4];
+    for y in 0..HASH_SIZE {
+        for x in 0..HASH_SIZE {
+            if grid.get_pixel(x, y)[0] > grid.get_pixel(x + 1, y)[0] {
+                let bit = (y * HASH_SIZE + x) as usize;
+                hash[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+    }
+    Ok(hash)
+}
+
+/// Whether an encoded image is (almost) a single flat color.
+pub fn is_blank(encoded: &[u8]) -> Result<bool> {
+    let thumbnail = image::load_from_memory(encoded)?
+        .grayscale()
+        .resize_exact(
+            BLANK_THUMBNAIL_SIZE,
+            BLANK_THUMBNAIL_SIZE,
+            FilterType::Triangle,
+        )
+        .to_luma8();
+
+    let (min, max) = thumbnail
+        .pixels()
+        .fold((u8::MAX, u8::MIN), |(min, max), pixel| {
+            (min.min(pixel[0]), max.max(pixel[0]))
+        });
+    Ok(max.saturating_sub(min) <= BLANK_TOLERANCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgb, RgbImage};
+    use std::io::Cursor;
+
+    fn encode(image: &RgbImage) -> Vec<u8> {
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    fn page(box_x: u32) -> RgbImage {
+        RgbImage::from_fn(320, 180, |x, y| {
+            if (box_x..box_x + 80).contains(&x) && (40..120).contains(&y) {
+                Rgb([20, 20, 20])
+            } else {
+                Rgb([240, 240, 240])
+            }
+        })
+    }
+
+    #[test]
+    fn test_identical_screenshots_match() {
+        let png = encode(&page(40));
+        assert_eq!(
+            perceptual_hash(&png).unwrap(),
+            perceptual_hash(&png).unwrap()
+        );
+
+        let screenshot = BASE64.encode(&png);
+        assert_eq!(
+            ScreenshotFingerprint::new("https://example.com", &screenshot).unwrap(),
+            ScreenshotFingerprint::new("https://example.com", &screenshot).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_changed_screenshots_differ() {
+        let before = perceptual_hash(&encode(&page(40))).unwrap();
+        let after = perceptual_hash(&encode(&page(200))).unwrap();
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn test_blank_screenshots() {
+        let white = RgbImage::from_pixel(320, 180, Rgb([255, 255, 255]));
+        assert!(is_blank(&encode(&white)).unwrap());
+        assert!(!is_blank(&encode(&page(40))).unwrap());
+    }
+}
@@ -0,0 +1,85 @@
+//! Screenshots scaled down to a maximum dimension.
+//!
+//! Vision models resize large images themselves, often to about 1568 pixels
+//! on the longest side, so a larger screenshot only costs context. With
+//! `MCP_MAX_SCREENSHOT_DIMENSION`, screenshots whose width or height exceeds
+//! it are scaled down before they are encoded. Coordinates the agent reads
+//! off a scaled screenshot are then scaled up to page pixels before they are
+//! used, and element positions reported to the agent are scaled down, so the
+//! agent works in screenshot pixels throughout.
+
+use anyhow::Result;
+use image::imageops::FilterType;
+use image::ImageFormat;
+
+/// Smallest maximum dimension accepted; smaller screenshots are unreadable.
+pub const MIN_DIMENSION: u32 = 256;
+
+/// Factor screenshots of a `viewport`-sized page are scaled by to fit
+/// `max_dimension`; 1.0 when they already fit or there is no maximum.
+pub fn scale_factor(viewport: (u32, u32), max_dimension: Option<u32>) -> f64 {
+    let longest = viewport.0.max(viewport.1).max(1);
+    match max_dimension {
+        Some(max) if max > 0 && longest > max => f64::from(max) / f64::from(longest),
+        _ => 1.0,
+    }
+}
+
+/// Scale a PNG screenshot down so neither side exceeds `max_dimension`.
+/// Screenshots that already fit are returned as they are.
+pub fn downscale(png: Vec<u8>, max_dimension: u32) -> Result<Vec<u8>> {
+    let image = image::load_from_memory_with_format(&png, ImageFormat::Png)?;
+    if image.width().max(image.height()) <= max_dimension {
+        return Ok(png);
+    }
+    // Keeps the aspect ratio, fitting the longest side to the maximum
+    let scaled = image.resize(max_dimension, max_dimension, FilterType::Triangle);
+    let mut encoded = Vec::new();
+    scaled.write_to(&mut std::io::Cursor::new(&mut encoded), ImageFormat::Png)?;
+    Ok(encoded)
+}
+
+/// A point of a screenshot scaled by `scale`, in page pixels.
+pub fn to_page(x: i64, y: i64, scale: f64) -> (i64, i64) {
+    if scale == 1.0 {
+        return (x, y);
+    }
+    (
+        (x as f64 / scale).round() as i64,
+        (y as f64 / scale).round() as i64,
+    )
+}
+
+/// A point of the page, in pixels of a screenshot scaled by `scale`.
+pub fn to_screenshot(x: i64, y: i64, scale: f64) -> (i64, i64) {
+    if scale == 1.0 {
+        return (x, y);
+    }
+    (
+        (x as f64 * scale).round() as i64,
+        (y as f64 * scale).round() as i64,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaling() {
+        assert_eq!(scale_factor((1280, 720), None), 1.0);
+        assert_eq!(scale_factor((1280, 720), Some(1568)), 1.0);
+        let scale = scale_factor((1920, 1080), Some(960));
+        assert_eq!(scale, 0.5);
+        assert_eq!(to_page(100, 51, scale), (200, 102));
+        assert_eq!(to_screenshot(200, 101, scale), (100, 51));
+
+        let mut png = Vec::new();
+        image::RgbImage::new(200, 100)
+            .write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        let scaled = image::load_from_memory(&downscale(png.clone(), 50).unwrap()).unwrap();
+        assert_eq!((scaled.width(), scaled.height()), (50, 25));
+        assert_eq!(downscale(png.clone(), 200).unwrap(), png);
+    }
+}
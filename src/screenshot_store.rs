@@ -0,0 +1,295 @@
+//! Stored screenshots with a retention policy.
+//!
+//! Every screenshot a tool returns is kept under an ID, which the response
+//! reports as `screenshot_id`, so it can be fetched again later with
+//! `get_screenshot` or read as the MCP resource `screenshot://<id>` instead of
+//! being carried in the conversation. The store keeps the most recent
+//! screenshots of a session within a count and a size limit (`MCP_SCREENSHOT_RETENTION`,
+//! `MCP_SCREENSHOT_STORE_MAX_MB`); older ones are deleted as new ones arrive.
+//!
+//! Screenshots are files in a directory of the session, removed when the
+//! session ends. Builds with the `s3-screenshots` feature can keep them in an
+//! S3-compatible bucket instead (`MCP_SCREENSHOT_S3_BUCKET`), where they
+//! outlive the session until evicted; a lifecycle rule on the bucket should
+//! expire anything left behind.
+
+use crate::config::Config;
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// URI scheme of screenshot resources.
+pub const URI_PREFIX: &str = "screenshot://";
+
+/// Distinguishes the stores of sessions in one process.
+static NEXT_STORE: AtomicU64 = AtomicU64::new(0);
+
+/// Description of a stored screenshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct StoredScreenshot {
+    /// ID of the screenshot.
+    pub id: String,
+    /// Resource URI of the screenshot.
+    pub uri: String,
+    /// Tool whose response carried the screenshot.
+    pub tool: String,
+    /// URL of the page, if the response named one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Capture time in seconds since the UNIX epoch.
+    pub taken_at: u64,
+    /// Size of the PNG in bytes.
+    pub bytes: u64,
+}
+
+/// Resource URI of a screenshot.
+pub fn uri(id: &str) -> String {
+    format!("{}{}", URI_PREFIX, id)
+}
+
+enum Backend {
+    /// Files in a directory of the session.
+    Directory(PathBuf),
+    /// Objects under a key prefix of the session.
+    #[cfg(feature = "s3-screenshots")]
+    S3 {
+        bucket: crate::s3::S3Bucket,
+        prefix: String,
+    },
+}
+
+/// Screenshots of a session.
+pub struct ScreenshotStore {
+    backend: Backend,
+    max_count: usize,
+    max_bytes: u64,
+    /// Stored screenshots, oldest first.
+    index: Mutex<VecDeque<StoredScreenshot>>,
+    next_id: AtomicU64,
+}
+
+impl ScreenshotStore {
+    /// Create the store of a session, or `None` when storage is disabled.
+    pub fn new(config: &Config) -> anyhow::Result<Option<Self>> {
+        if config.screenshot_retention == 0 {
+            return Ok(None);
+        }
+        let session = format!(
+            "{}-{}",
+            std::process::id(),
+            NEXT_STORE.fetch_add(1, Ordering::Relaxed)
+        );
+        let backend = match config.screenshot_s3_bucket {
+            #[cfg(feature = "s3-screenshots")]
+            Some(ref bucket) => Backend::S3 {
+                bucket: crate::s3::S3Bucket::from_env(
+                    &config.screenshot_s3_endpoint.clone().unwrap_or_else(|| {
+                        format!("https://s3.{}.amazonaws.com", config.screenshot_s3_region)
+                    }),
+                    bucket,
+                    &config.screenshot_s3_region,
+                )?,
+                prefix: format!("{}{}/", config.screenshot_s3_prefix, session),
+            },
+            #[cfg(not(feature = "s3-screenshots"))]
+            Some(_) => {
+                return Err(anyhow::anyhow!(
+                    "MCP_SCREENSHOT_S3_BUCKET requires a build with the s3-screenshots feature"
+                ))
+            }
+            None => Backend::Directory(
+                config
+                    .screenshot_dir
+                    .clone()
+                    .unwrap_or_else(|| {
+                        std::env::temp_dir()
+                            .join("mcp-computer-use")
+                            .join("screenshots")
+                    })
+                    .join(session),
+            ),
+        };
+        Ok(Some(Self::with_backend(
+            backend,
+            config.screenshot_retention,
+            config.screenshot_store_max_mb * 1024 * 1024,
+        )))
+    }
+
+    fn with_backend(backend: Backend, max_count: usize, max_bytes: u64) -> Self {
+        Self {
+            backend,
+            max_count,
+            max_bytes,
+            index: Mutex::new(VecDeque::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Store a PNG screenshot and evict the oldest ones beyond the limits.
+    pub async fn put(
+        &self,
+        png: Vec<u8>,
+        tool: &str,
+        url: Option<String>,
+    ) -> anyhow::Result<StoredScreenshot> {
+        let id = (self.next_id.fetch_add(1, Ordering::Relaxed) + 1).to_string();
+        let screenshot = StoredScreenshot {
+            uri: uri(&id),
+            id,
+            tool: tool.to_string(),
+            url,
+            taken_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            bytes: png.len() as u64,
+        };
+        self.write(&screenshot.id, png).await?;
+
+        let evicted = {
+            let mut index = self.index.lock().unwrap_or_else(|e| e.into_inner());
+            index.push_back(screenshot.clone());
+            let mut total: u64 = index.iter().map(|s| s.bytes).sum();
+            let mut evicted = Vec::new();
+            // The newest screenshot is kept even when it alone exceeds the size limit
+            while index.len() > 1 && (index.len() > self.max_count || total > self.max_bytes) {
+                if let Some(oldest) = index.pop_front() {
+                    total -= oldest.bytes;
+                    evicted.push(oldest.id);
+                }
+            }
+            evicted
+        };
+        for id in evicted {
+            if let Err(e) = self.delete(&id).await {
+                tracing::warn!("Failed to delete screenshot {}: {}", id, e);
+            }
+        }
+        Ok(screenshot)
+    }
+
+    /// A stored screenshot and its PNG, or `None` for unknown or evicted IDs.
+    pub async fn get(&self, id: &str) -> anyhow::Result<Option<(StoredScreenshot, Vec<u8>)>> {
+        let Some(screenshot) = self.find(id) else {
+            return Ok(None);
+        };
+        let png = self.read(id).await?;
+        Ok(Some((screenshot, png)))
+    }
+
+    /// Description of a stored screenshot.
+    pub fn find(&self, id: &str) -> Option<StoredScreenshot> {
+        self.index
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|s| s.id == id)
+            .cloned()
+    }
+
+    /// Stored screenshots, oldest first.
+    pub fn list(&self) -> Vec<StoredScreenshot> {
+        self.index
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    async fn write(&self, id: &str, png: Vec<u8>) -> anyhow::Result<()> {
+        match self.backend {
+            Backend::Directory(ref dir) => {
+                tokio::fs::create_dir_all(dir).await?;
+                tokio::fs::write(dir.join(format!("{}.png", id)), png).await?;
+            }
+            #[cfg(feature = "s3-screenshots")]
+            Backend::S3 {
+                ref bucket,
+                ref prefix,
+            } => bucket.put(&format!("{}{}.png", prefix, id), png).await?,
+        }
+        Ok(())
+    }
+
+    async fn read(&self, id: &str) -> anyhow::Result<Vec<u8>> {
+        match self.backend {
+            Backend::Directory(ref dir) => {
+                Ok(tokio::fs::read(dir.join(format!("{}.png", id))).await?)
+            }
+            #[cfg(feature = "s3-screenshots")]
+            Backend::S3 {
+                ref bucket,
+                ref prefix,
+            } => bucket.get(&format!("{}{}.png", prefix, id)).await,
+        }
+    }
+
+    async fn delete(&self, id: &str) -> anyhow::Result<()> {
+        match self.backend {
+            Backend::Directory(ref dir) => {
+                tokio::fs::remove_file(dir.join(format!("{}.png", id))).await?
+            }
+            #[cfg(feature = "s3-screenshots")]
+            Backend::S3 {
+                ref bucket,
+                ref prefix,
+            } => bucket.delete(&format!("{}{}.png", prefix, id)).await?,
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ScreenshotStore {
+    fn drop(&mut self) {
+        match self.backend {
+            Backend::Directory(ref dir) => {
+                if dir.exists() {
+                    if let Err(e) = std::fs::remove_dir_all(dir) {
+                        tracing::warn!("Failed to remove screenshot directory {:?}: {}", dir, e);
+                    }
+                }
+            }
+            // Left to the bucket's lifecycle rules; dropping cannot wait for requests
+            #[cfg(feature = "s3-screenshots")]
+            Backend::S3 { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_retention() {
+        let dir = std::env::temp_dir().join(format!("mcp-screenshot-test-{}", std::process::id()));
+        let store = ScreenshotStore::with_backend(Backend::Directory(dir.clone()), 3, 10);
+
+        let first = store
+            .put(b"png1".to_vec(), "navigate", Some("https://a.test/".into()))
+            .await
+            .unwrap();
+        assert_eq!(first.uri, "screenshot://1");
+        for _ in 0..2 {
+            store.put(b"png".to_vec(), "click_at", None).await.unwrap();
+        }
+        let (stored, png) = store.get("1").await.unwrap().unwrap();
+        assert_eq!((stored.tool.as_str(), png), ("navigate", b"png1".to_vec()));
+
+        // Over the 10-byte limit: the oldest screenshots go
+        store.put(b"png".to_vec(), "scroll_at", None).await.unwrap();
+        let ids: Vec<String> = store.list().into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec!["2", "3", "4"]);
+        assert!(store.get("1").await.unwrap().is_none());
+        assert!(!dir.join("1.png").exists());
+
+        drop(store);
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,112 @@
+//! Scroll positions remembered across history navigation.
+//!
+//! Going back to a long list should not start it over from the top. With
+//! `MCP_RESTORE_SCROLL` enabled, every state the controllers report records the
+//! scroll position of the page it shows, per tab and URL. When `go_back` or
+//! `go_forward` returns to a page whose remembered position the browser did
+//! not restore on its own (pages rendered by scripts often lose it), the page
+//! is scrolled there again and the restored offset is reported.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Number of pages whose position is remembered per tab.
+const REMEMBERED_PAGES: usize = 50;
+
+/// JavaScript expression evaluating to the document's [`ScrollPosition`].
+pub const POSITION_SCRIPT: &str =
+    "({ x: Math.round(window.scrollX), y: Math.round(window.scrollY) })";
+
+/// Scroll offset of a document, in CSS pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScrollPosition {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl ScrollPosition {
+    /// Whether the document is scrolled at all.
+    pub fn is_origin(&self) -> bool {
+        self.x == 0 && self.y == 0
+    }
+}
+
+/// JavaScript expression scrolling the document to a position, unless it is
+/// already there. Evaluates to the resulting [`ScrollPosition`], which falls
+/// short when the page is not long enough yet.
+pub fn restore_script(position: ScrollPosition) -> String {
+    format!(
+        r#"(function(x, y) {{
+    if (Math.round(window.scrollX) !== x || Math.round(window.scrollY) !== y) {{
+        window.scrollTo({{ left: x, top: y, behavior: 'instant' }});
+    }}
+    return {};
+}})({}, {})"#,
+        POSITION_SCRIPT, position.x, position.y
+    )
+}
+
+/// Last known scroll position of recently shown pages, per tab.
+#[derive(Default)]
+pub struct ScrollMemory {
+    /// `(url, position)` pairs per tab, least recently recorded first.
+    pages: Mutex<HashMap<String, VecDeque<(String, ScrollPosition)>>>,
+}
+
+impl ScrollMemory {
+    /// Remember the position of the page a tab shows.
+    pub fn record(&self, tab: &str, url: &str, position: ScrollPosition) {
+        let mut pages = self.pages.lock().unwrap_or_else(|e| e.into_inner());
+        let entries = pages.entry(tab.to_string()).or_default();
+        entries.retain(|(entry_url, _)| entry_url != url);
+        entries.push_back((url.to_string(), position));
+        if entries.len() > REMEMBERED_PAGES {
+            entries.pop_front();
+        }
+    }
+
+    /// The remembered position of a page in a tab.
+    pub fn saved(&self, tab: &str, url: &str) -> Option<ScrollPosition> {
+        let pages = self.pages.lock().unwrap_or_else(|e| e.into_inner());
+        pages
+            .get(tab)?
+            .iter()
+            .find(|(entry_url, _)| entry_url == url)
+            .map(|(_, position)| *position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scroll_memory() {
+        let memory = ScrollMemory::default();
+        let position = |y| ScrollPosition { x: 0, y };
+        memory.record("t1", "https://a.test/list", position(2400));
+        memory.record("t1", "https://a.test/item/1", position(0));
+        memory.record("t2", "https://a.test/list", position(80));
+        assert_eq!(
+            memory.saved("t1", "https://a.test/list"),
+            Some(position(2400))
+        );
+        assert_eq!(
+            memory.saved("t2", "https://a.test/list"),
+            Some(position(80))
+        );
+
+        memory.record("t1", "https://a.test/list", position(3000));
+        assert_eq!(
+            memory.saved("t1", "https://a.test/list"),
+            Some(position(3000))
+        );
+        assert_eq!(memory.saved("t1", "https://b.test/"), None);
+
+        for i in 0..REMEMBERED_PAGES {
+            memory.record("t1", &format!("https://a.test/item/{}", i + 2), position(0));
+        }
+        assert_eq!(memory.saved("t1", "https://a.test/list"), None);
+    }
+}
@@ -0,0 +1,143 @@
+//! Named search engines.
+//!
+//! Each engine is a URL template with a `{query}` placeholder, such as
+//! `https://duckduckgo.com/?q={query}`. The `search` and `web_search` tools pick
+//! an engine by name, falling back to the configured default. Google, Bing and
+//! DuckDuckGo are built in; more can be added with `MCP_SEARCH_ENGINES`.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Placeholder replaced by the URL-encoded query.
+pub const QUERY_PLACEHOLDER: &str = "{query}";
+
+/// Name of the engine the built-in default points at.
+pub const DEFAULT_ENGINE: &str = "google";
+
+/// A named search engine.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchEngine {
+    /// Name the engine is selected by, in lowercase.
+    pub name: String,
+    /// URL template. Without a `{query}` placeholder it is the engine's home
+    /// page, and queries are typed into its search box.
+    pub template: String,
+}
+
+impl SearchEngine {
+    pub fn new(name: &str, template: &str) -> Self {
+        Self {
+            name: name.trim().to_lowercase(),
+            template: template.trim().to_string(),
+        }
+    }
+
+    /// Whether the template has a `{query}` placeholder.
+    pub fn has_placeholder(&self) -> bool {
+        self.template.contains(QUERY_PLACEHOLDER)
+    }
+
+    /// URL of the result page for a query, or `None` when the template has no
+    /// placeholder.
+    pub fn query_url(&self, query: &str) -> Option<String> {
+        self.has_placeholder().then(|| {
+            self.template
+                .replace(QUERY_PLACEHOLDER, &encode_query(query))
+        })
+    }
+
+    /// URL of the engine's home page: the template without its placeholder's
+    /// path and query string.
+    pub fn home_url(&self) -> String {
+        if !self.has_placeholder() {
+            return self.template.clone();
+        }
+        match reqwest::Url::parse(&self.template.replace(QUERY_PLACEHOLDER, "")) {
+            Ok(url) if url.has_host() => format!("{}/", url.origin().ascii_serialization()),
+            _ => self.template.replace(QUERY_PLACEHOLDER, ""),
+        }
+    }
+}
+
+/// The engines available without configuration.
+pub fn builtin() -> Vec<SearchEngine> {
+    vec![
+        SearchEngine::new("google", "https://www.google.com/search?q={query}"),
+        SearchEngine::new("bing", "https://www.bing.com/search?q={query}"),
+        SearchEngine::new("duckduckgo", "https://duckduckgo.com/?q={query}"),
+    ]
+}
+
+/// Parse a comma-separated list of `name=template` pairs.
+pub fn parse_list(spec: &str) -> Result<Vec<SearchEngine>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (name, template) = entry
+                .split_once('=')
+                .filter(|(name, template)| !name.trim().is_empty() && !template.trim().is_empty())
+                .ok_or_else(|| anyhow::anyhow!("expected name=template, got '{}'", entry))?;
+            Ok(SearchEngine::new(name, template))
+        })
+        .collect()
+}
+
+/// Add engines to a list, replacing engines with the same name.
+pub fn merge(engines: &mut Vec<SearchEngine>, added: Vec<SearchEngine>) {
+    for engine in added {
+        match engines.iter_mut().find(|e| e.name == engine.name) {
+            Some(existing) => *existing = engine,
+            None => engines.push(engine),
+        }
+    }
+}
+
+/// Percent-encode a query for a URL query string, with spaces as `+`.
+fn encode_query(query: &str) -> String {
+    let mut encoded = String::with_capacity(query.len());
+    for byte in query.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_url() {
+        let engine = SearchEngine::new("DuckDuckGo", "https://duckduckgo.com/?q={query}");
+        assert_eq!(engine.name, "duckduckgo");
+        assert_eq!(
+            engine.query_url("rust & c++").as_deref(),
+            Some("https://duckduckgo.com/?q=rust+%26+c%2B%2B")
+        );
+        assert_eq!(engine.home_url(), "https://duckduckgo.com/");
+
+        let home = SearchEngine::new("intranet", "https://search.example.com");
+        assert_eq!(home.query_url("x"), None);
+        assert_eq!(home.home_url(), "https://search.example.com");
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let mut engines = builtin();
+        let added = parse_list(
+            "bing=https://cn.bing.com/search?q={query}, kagi=https://kagi.com/search?q={query}",
+        )
+        .unwrap();
+        merge(&mut engines, added);
+        assert_eq!(engines.len(), 4);
+        assert_eq!(engines[1].template, "https://cn.bing.com/search?q={query}");
+        assert_eq!(engines[3].name, "kagi");
+        assert!(parse_list("kagi").is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Named keyboard shortcut presets.
+//!
+//! `shortcut` takes the name of a common shortcut instead of a key list, so
+//! prompts do not need to spell out modifier combinations that differ per
+//! platform. Editing shortcuts are pressed with the modifier `Primary`
+//! resolves to ([`KeyMapping`]), and redo follows the platform's convention.
+//! Shortcuts handled by the browser itself rather than the page never reach
+//! it from synthetic key events, so those presets perform the action
+//! directly: opening or closing a tab, or zooming the page.
+
+use crate::keys::KeyMapping;
+use crate::zoom_to_fit;
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+/// Zoom levels of Chrome's zoom menu, stepped through by `zoom_in` and
+/// `zoom_out`.
+const ZOOM_LEVELS: &[f64] = &[
+    0.25, 0.33, 0.5, 0.67, 0.75, 0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0, 4.0, 5.0,
+];
+
+/// A shortcut preset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Shortcut {
+    SelectAll,
+    Copy,
+    Cut,
+    Paste,
+    Undo,
+    Redo,
+    /// The page's find shortcut; the browser's find bar is not reachable.
+    Find,
+    NewTab,
+    CloseTab,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+}
+
+/// What a preset does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShortcutAction {
+    /// Press these DOM keys together.
+    Keys(Vec<String>),
+    NewTab,
+    CloseTab,
+    /// Step the page zoom by this many levels; 0 resets it.
+    Zoom(i32),
+}
+
+impl Shortcut {
+    /// The action of the preset for a key mapping.
+    pub fn action(self, mapping: &KeyMapping) -> ShortcutAction {
+        let chord = |keys: &[&str]| {
+            ShortcutAction::Keys(
+                std::iter::once(mapping.primary)
+                    .chain(keys.iter().copied())
+                    .map(String::from)
+                    .collect(),
+            )
+        };
+        match self {
+            Shortcut::SelectAll => chord(&["a"]),
+            Shortcut::Copy => chord(&["c"]),
+            Shortcut::Cut => chord(&["x"]),
+            Shortcut::Paste => chord(&["v"]),
+            Shortcut::Undo => chord(&["z"]),
+            // Command+Shift+Z on macOS, Control+Y elsewhere
+            Shortcut::Redo if mapping.primary == "Meta" => chord(&["Shift", "z"]),
+            Shortcut::Redo => chord(&["y"]),
+            Shortcut::Find => chord(&["f"]),
+            Shortcut::NewTab => ShortcutAction::NewTab,
+            Shortcut::CloseTab => ShortcutAction::CloseTab,
+            Shortcut::ZoomIn => ShortcutAction::Zoom(1),
+            Shortcut::ZoomOut => ShortcutAction::Zoom(-1),
+            Shortcut::ZoomReset => ShortcutAction::Zoom(0),
+        }
+    }
+}
+
+/// JavaScript expression stepping the zoom of the current document by `step`
+/// levels (0 resets it) and returning the new zoom factor. The page is zoomed
+/// with CSS, which lays it out again like browser zoom does. A zoom set by
+/// the agent takes over from zoom-to-fit until it is reset.
+pub(crate) fn zoom_script(step: i32) -> String {
+    format!(
+        r#"(function(levels, step, marker) {{
+    var root = document.documentElement;
+    var current = parseFloat(root.style.zoom) || 1;
+    var next = 1;
+    if (step > 0) {{
+        next = levels.find(function(level) {{ return level > current + 0.001; }}) || levels[levels.length - 1];
+    }} else if (step < 0) {{
+        next = levels.slice().reverse().find(function(level) {{ return level < current - 0.001; }}) || levels[0];
+    }}
+    root.style.zoom = next === 1 ? '' : String(next);
+    delete root.dataset[marker];
+    return next;
+}})({:?}, {}, '{}')"#,
+        ZOOM_LEVELS,
+        step,
+        zoom_to_fit::MARKER
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::Platform;
+
+    #[test]
+    fn test_shortcut_actions() {
+        let mac = KeyMapping::for_platform(Platform::MacOs);
+        let other = KeyMapping::for_platform(Platform::Other);
+        let keys =
+            |keys: &[&str]| ShortcutAction::Keys(keys.iter().map(|k| k.to_string()).collect());
+
+        assert_eq!(Shortcut::Copy.action(&mac), keys(&["Meta", "c"]));
+        assert_eq!(Shortcut::SelectAll.action(&other), keys(&["Control", "a"]));
+        assert_eq!(Shortcut::Redo.action(&mac), keys(&["Meta", "Shift", "z"]));
+        assert_eq!(Shortcut::Redo.action(&other), keys(&["Control", "y"]));
+        assert_eq!(Shortcut::ZoomOut.action(&mac), ShortcutAction::Zoom(-1));
+
+        let shortcut: Shortcut = serde_json::from_str(r#""close_tab""#).unwrap();
+        assert_eq!(shortcut.action(&other), ShortcutAction::CloseTab);
+        assert!(zoom_script(1).contains("[0.25, 0.33"));
+    }
+}
@@ -0,0 +1,167 @@
+//! Change summaries between consecutive states.
+//!
+//! With `MCP_STATE_DELTA` enabled, every state a tool returns carries a short
+//! summary of what changed since the previous state of the same tab: whether
+//! the URL or title changed, how many DOM mutations happened and which console
+//! errors were logged. It tells the model cheaply whether its last action had
+//! any effect, without comparing screenshots.
+//!
+//! A tracking script in the page counts mutations with a `MutationObserver`
+//! and records `console.error` calls and uncaught errors. Reading the counters
+//! resets them. The counters live in the document, so after a navigation they
+//! cover the new page since it loaded.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// What changed since the previous state of a tab.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct StateDelta {
+    /// Whether the URL changed.
+    pub url_changed: bool,
+    /// Whether the document title changed.
+    pub title_changed: bool,
+    /// Current document title.
+    pub title: String,
+    /// DOM mutations since the previous state. Absent when tracking was only
+    /// just installed in the document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dom_mutations: Option<u64>,
+    /// Console errors and uncaught exceptions logged since the previous state.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub new_console_errors: Vec<String>,
+}
+
+/// Page activity read by [`read_script`].
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageActivity {
+    pub title: String,
+    pub mutations: u64,
+    pub errors: Vec<String>,
+    /// Whether the tracking script was installed by this read.
+    pub fresh: bool,
+}
+
+/// Script installing the tracking in a document. Safe to run more than once.
+pub fn track_script() -> String {
+    format!("({})()", TRACK_FUNCTION)
+}
+
+/// JavaScript expression returning the document's [`PageActivity`] and
+/// resetting its counters. Installs the tracking when it is missing.
+pub fn read_script() -> String {
+    format!(
+        r#"(function(install) {{
+    var fresh = !window.__mcpActivity;
+    install();
+    var activity = window.__mcpActivity;
+    var result = {{
+        title: document.title,
+        mutations: activity.mutations,
+        errors: activity.errors.slice(),
+        fresh: fresh
+    }};
+    activity.mutations = 0;
+    activity.errors.length = 0;
+    return result;
+}})({})"#,
+        TRACK_FUNCTION
+    )
+}
+
+/// Previous URL and title of each tab.
+#[derive(Default)]
+pub struct StateDeltaTracker {
+    previous: Mutex<HashMap<String, (String, String)>>,
+}
+
+impl StateDeltaTracker {
+    /// Compare a tab's state with its previous one and remember it.
+    pub fn diff(&self, tab: &str, url: &str, activity: PageActivity) -> StateDelta {
+        let mut previous = self.previous.lock().unwrap_or_else(|e| e.into_inner());
+        let (url_changed, title_changed) = match previous.get(tab) {
+            Some((old_url, old_title)) => (old_url != url, *old_title != activity.title),
+            None => (true, true),
+        };
+        previous.insert(tab.to_string(), (url.to_string(), activity.title.clone()));
+        StateDelta {
+            url_changed,
+            title_changed,
+            title: activity.title,
+            dom_mutations: (!activity.fresh).then_some(activity.mutations),
+            new_console_errors: activity.errors,
+        }
+    }
+}
+
+const TRACK_FUNCTION: &str = r#"
+function() {
+    if (window.__mcpActivity) {
+        return;
+    }
+    var activity = { mutations: 0, errors: [] };
+    Object.defineProperty(window, '__mcpActivity', { value: activity });
+    function recordError(message) {
+        activity.errors.push(String(message).slice(0, 300));
+        if (activity.errors.length > 20) {
+            activity.errors.shift();
+        }
+    }
+    function observe() {
+        new MutationObserver(function(records) {
+            activity.mutations += records.length;
+        }).observe(document.documentElement, {
+            childList: true, subtree: true, attributes: true, characterData: true
+        });
+    }
+    if (document.documentElement) {
+        observe();
+    } else {
+        document.addEventListener('DOMContentLoaded', observe);
+    }
+    var error = console.error;
+    console.error = function() {
+        recordError(Array.prototype.map.call(arguments, function(arg) {
+            return arg instanceof Error ? arg.message : String(arg);
+        }).join(' '));
+        return error.apply(console, arguments);
+    };
+    window.addEventListener('error', function(event) {
+        if (event.message) {
+            recordError('Uncaught ' + event.message);
+        }
+    });
+    window.addEventListener('unhandledrejection', function(event) {
+        var reason = event.reason;
+        recordError('Unhandled rejection: ' + (reason && reason.message ? reason.message : String(reason)));
+    });
+}"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_delta() {
+        let tracker = StateDeltaTracker::default();
+        let activity = |title: &str, mutations, fresh| PageActivity {
+            title: title.to_string(),
+            mutations,
+            errors: Vec::new(),
+            fresh,
+        };
+
+        let first = tracker.diff("t", "https://a.test/", activity("A", 0, true));
+        assert!(first.url_changed && first.title_changed);
+        assert_eq!(first.dom_mutations, None);
+
+        let second = tracker.diff("t", "https://a.test/", activity("A", 12, false));
+        assert!(!second.url_changed && !second.title_changed);
+        assert_eq!(second.dom_mutations, Some(12));
+
+        let third = tracker.diff("t", "https://a.test/", activity("A (1)", 3, false));
+        assert!(third.title_changed);
+    }
+}
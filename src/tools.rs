@@ -2,70 +2,325 @@
 //!
 //! This module defines all the MCP tools that expose browser control capabilities.
 
-use crate::browser::{BrowserController, EnvState, TabInfo};
+use crate::accessibility::{self, AccessibilityAudit, Violation};
+use crate::action_history::{ActionHistory, ActionRecord};
+use crate::action_queue::{action_kind, ActionKind, ActionQueue};
+use crate::approval::{ApprovalGate, ApprovalRequest};
+use crate::browser::{
+    normalize_url, BrowserController, ElementTarget, EnvState, FrameInfo, InsertionMode, KeyStep,
+    PageMetadata, PageTarget, ScrollAmount, TabInfo, DEFAULT_HIGHLIGHT_DURATION_MS,
+    PAGE_CRASHED_CODE, PAGE_CRASHED_MESSAGE,
+};
+use crate::browser_stats::{BrowserStats, PageMetrics};
+use crate::cassette::Cassette;
 use crate::cdp_browser::CdpBrowserController;
-use crate::config::{tool_names, Config, ConnectionMode};
+use crate::checkpoint::{CheckpointSummary, PageCheckpoint};
+use crate::config::{tool_names, Config, ConnectionMode, ScreenshotPolicy};
+use crate::context_strips::ContextStrips;
+use crate::credentials::{self, CredentialFill, CredentialStore, SiteCredentials};
+use crate::dom_snapshot::{DomSnapshot, SnapshotNode};
+use crate::draw_path::{self, PathPoint, PointerType};
+use crate::element_aliases::{ElementAliases, ElementFingerprint};
+use crate::element_locator::{LocatedElement, Locator};
+use crate::emulation::{MediaEmulation, MediaType, VisionDeficiency};
+use crate::evaluate::Evaluation;
+use crate::grounding::{self, GroundingElement};
+use crate::input_controls::ControlValue;
+use crate::keyboard_focus::{self, Activation, TabFocus};
+use crate::keys::KeyMapping;
+use crate::link_check::{self, LinkScope, LinkStatus, PageLinks};
+use crate::media::{MediaCommand, MediaElement, VideoFrame};
+use crate::metrics;
+#[cfg(test)]
+use crate::mock_backend::MockBackend;
+use crate::notifications::CapturedNotification;
+use crate::oauth::{OAuthStep, Provider, StepAction};
+#[cfg(feature = "ocr")]
+use crate::ocr;
+use crate::page_events::{self, EventBatch, EventType, PageEvents};
+use crate::page_watch::{self, PageText, PageWatches, WatchStatus};
+use crate::path_policy::PathPolicy;
+use crate::politeness::Politeness;
+use crate::quarantine::DownloadPolicy;
+use crate::scratch::{RemovedFile, ScratchFile, SessionScratch};
+use crate::screenshot_diff::ScreenshotFingerprint;
+use crate::screenshot_scale;
+use crate::screenshot_store::{self, ScreenshotStore, StoredScreenshot};
+use crate::scroll_memory::ScrollPosition;
+use crate::shortcuts::{Shortcut, ShortcutAction};
+use crate::state_delta::StateDelta;
+use crate::visual_diff::{self, Region};
+use crate::web_search::{self, SearchResult};
+use crate::workspace::{self, SavedCookie, Workspace, WorkspaceSummary};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use rmcp::{
-    handler::server::{router::tool::ToolRouter, wrapper::Parameters},
+    handler::server::{router::tool::ToolRouter, tool::ToolCallContext, wrapper::Parameters},
     model::{
-        CallToolResult, Content, ErrorData as McpError, Implementation, ServerCapabilities,
-        ServerInfo,
+        AnnotateAble, CallToolRequestParam, CallToolResult, Content, ErrorData as McpError,
+        Implementation, ListResourcesResult, ListToolsResult, PaginatedRequestParam, RawContent,
+        RawResource, ReadResourceRequestParam, ReadResourceResult, ResourceContents,
+        ResourceUpdatedNotificationParam, ServerCapabilities, ServerInfo, SubscribeRequestParam,
+        UnsubscribeRequestParam,
     },
-    schemars, tool, tool_handler, tool_router, ServerHandler,
+    schemars,
+    service::RequestContext,
+    tool, tool_router, Peer, RoleServer, ServerHandler,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
-use tracing::{debug, info, warn};
+use tracing::{debug, info, warn, Instrument};
+
+tokio::task_local! {
+    /// Correlation ID of the tool call running in the current task, set by `call_tool`.
+    static REQUEST_ID: String;
+}
+
+/// Generate an ID for a tool call, unique within this process. The process ID
+/// prefix keeps IDs from different server runs apart in shared logs.
+fn next_request_id() -> String {
+    static NEXT_REQUEST: AtomicU64 = AtomicU64::new(1);
+    format!(
+        "{:x}-{}",
+        std::process::id(),
+        NEXT_REQUEST.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Correlation ID of the tool call being handled, if any.
+fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Name of the browser tools act on when no `browser_id` is given.
+const DEFAULT_BROWSER_ID: &str = "default";
+
+/// Maximum number of browsers created with `create_browser` per session.
+const MAX_BROWSERS: usize = 8;
+
+/// Error returned by the CDP target tools in WebDriver mode.
+const TARGETS_REQUIRE_CDP: &str = "list_targets and attach_to_tab require CDP mode (MCP_CONNECTION_MODE=cdp); use list_tabs and switch_tab instead";
+
+/// Error returned by subscribe_events in WebDriver mode.
+const EVENTS_REQUIRE_CDP: &str = "subscribe_events requires CDP mode (MCP_CONNECTION_MODE=cdp)";
+
+/// Default minimum confidence of the words ocr_screenshot returns.
+#[cfg(feature = "ocr")]
+const DEFAULT_OCR_CONFIDENCE: f32 = 40.0;
+
+/// Default number of events poll_events returns.
+const DEFAULT_POLL_LIMIT: usize = 100;
+
+/// Shortest time between two notifications about new events, so a burst of
+/// events, such as a flood of console messages, is reported once.
+const EVENT_NOTIFY_INTERVAL: Duration = Duration::from_millis(500);
 
 /// Unified browser interface that supports both WebDriver and CDP modes.
 pub enum BrowserBackend {
     WebDriver(Arc<BrowserController>),
     Cdp(Arc<CdpBrowserController>),
+    /// A backend whose replies are recorded to a cassette.
+    Recording(Box<BrowserBackend>, Arc<Cassette>),
+    /// Replies replayed from a cassette, without a browser.
+    Replay(Arc<Cassette>),
+    /// Scripted replies for unit tests of the tool layer.
+    #[cfg(test)]
+    Mock(Arc<MockBackend>),
 }
 
 impl BrowserBackend {
-    /// Create a new browser backend based on connection mode.
+    /// Create a new browser backend based on connection mode, recording or
+    /// replaying the configured cassette.
     pub fn new(config: Config) -> Self {
-        match config.connection_mode {
+        let cassette = config.cassette.clone();
+        if let Some(cassette) = cassette.clone().filter(|c| c.is_replay()) {
+            return BrowserBackend::Replay(cassette);
+        }
+        let backend = match config.connection_mode {
             ConnectionMode::WebDriver => {
                 BrowserBackend::WebDriver(Arc::new(BrowserController::new(config)))
             }
             ConnectionMode::Cdp => BrowserBackend::Cdp(Arc::new(CdpBrowserController::new(config))),
+        };
+        match cassette {
+            Some(cassette) => BrowserBackend::Recording(Box::new(backend), cassette),
+            None => backend,
         }
     }
 
     /// Open the browser.
     pub async fn open(&self) -> anyhow::Result<EnvState> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("open"),
             BrowserBackend::WebDriver(ctrl) => ctrl.open().await,
             BrowserBackend::Cdp(ctrl) => ctrl.open().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("open", Box::pin(inner.open()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("open"),
+        }
+    }
+
+    /// Run an action against a specific tab, or the active tab when `tab` is `None`.
+    ///
+    /// In WebDriver mode the session has a single current window, so the tab is
+    /// switched to first and stays active. In CDP mode the action targets the tab
+    /// directly without activating it, so actions in different tabs can run concurrently.
+    pub async fn in_tab<T>(
+        &self,
+        tab: Option<&str>,
+        action: impl Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(_) => action.await,
+            BrowserBackend::WebDriver(ctrl) => {
+                if let Some(tab) = tab {
+                    ctrl.focus_tab(tab).await?;
+                }
+                action.await
+            }
+            BrowserBackend::Cdp(_) => CdpBrowserController::in_tab(tab, action).await,
+            BrowserBackend::Recording(inner, _) => Box::pin(inner.in_tab(tab, action)).await,
+            BrowserBackend::Replay(_) => action.await,
+        }
+    }
+
+    /// Run a read-only action against a specific tab without leaving it active.
+    ///
+    /// CDP reads a tab without activating it anyway. In WebDriver mode the tab
+    /// has to be switched to, so the previously current window is switched back
+    /// to afterwards, leaving the foreground tab as it was.
+    pub async fn peek_tab<T>(
+        &self,
+        tab: Option<&str>,
+        action: impl Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        match (self, tab) {
+            (BrowserBackend::WebDriver(ctrl), Some(tab)) => {
+                let previous = ctrl.active_tab().await;
+                ctrl.focus_tab(tab).await?;
+                let result = action.await;
+                if let Some(previous) = previous {
+                    if let Err(e) = ctrl.focus_tab(&previous).await {
+                        warn!("Failed to return to tab {}: {}", previous, e);
+                    }
+                }
+                result
+            }
+            (BrowserBackend::Recording(inner, _), _) => Box::pin(inner.peek_tab(tab, action)).await,
+            _ => self.in_tab(tab, action).await,
+        }
+    }
+
+    /// Decide how a tool call targeting `tab` is scheduled: its action kind and
+    /// the queue lane it is serialized in.
+    pub async fn schedule(&self, tool: &str, tab: Option<&str>) -> (ActionKind, String) {
+        let kind = action_kind(tool);
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(_) => (kind, String::new()),
+            // One current window per session: every tab shares a lane, and targeting
+            // a tab switches windows, so even queries must wait their turn
+            BrowserBackend::WebDriver(_) => {
+                let kind = if tab.is_some() && kind == ActionKind::Query {
+                    ActionKind::Mutation
+                } else {
+                    kind
+                };
+                (kind, String::new())
+            }
+            BrowserBackend::Cdp(ctrl) => {
+                let lane = match tab {
+                    Some(tab) => tab.to_string(),
+                    None => ctrl.active_tab().await.unwrap_or_default(),
+                };
+                (kind, lane)
+            }
+            BrowserBackend::Recording(inner, _) => Box::pin(inner.schedule(tool, tab)).await,
+            BrowserBackend::Replay(_) => (kind, String::new()),
         }
     }
 
     /// Get current state.
     pub async fn current_state(&self) -> anyhow::Result<EnvState> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("current_state"),
             BrowserBackend::WebDriver(ctrl) => ctrl.current_state().await,
             BrowserBackend::Cdp(ctrl) => ctrl.current_state().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("current_state", Box::pin(inner.current_state()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("current_state"),
+        }
+    }
+
+    /// Capture strips of the content above and below the viewport.
+    pub async fn context_strips(&self) -> anyhow::Result<ContextStrips> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("context_strips"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.context_strips().await,
+            BrowserBackend::Cdp(ctrl) => ctrl.context_strips().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("context_strips", Box::pin(inner.context_strips()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("context_strips"),
         }
     }
 
     /// Click at coordinates.
     pub async fn click_at(&self, x: i64, y: i64) -> anyhow::Result<EnvState> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("click_at"),
             BrowserBackend::WebDriver(ctrl) => ctrl.click_at(x, y).await,
             BrowserBackend::Cdp(ctrl) => ctrl.click_at(x, y).await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("click_at", Box::pin(inner.click_at(x, y)).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("click_at"),
         }
     }
 
     /// Hover at coordinates.
-    pub async fn hover_at(&self, x: i64, y: i64) -> anyhow::Result<EnvState> {
+    pub async fn hover_at(
+        &self,
+        x: i64,
+        y: i64,
+        duration_ms: Option<u64>,
+    ) -> anyhow::Result<EnvState> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("hover_at"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.hover_at(x, y, duration_ms).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.hover_at(x, y, duration_ms).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "hover_at",
+                Box::pin(inner.hover_at(x, y, duration_ms)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("hover_at"),
+        }
+    }
+
+    /// Move the mouse off the hovered element.
+    pub async fn move_mouse_away(&self) -> anyhow::Result<EnvState> {
         match self {
-            BrowserBackend::WebDriver(ctrl) => ctrl.hover_at(x, y).await,
-            BrowserBackend::Cdp(ctrl) => ctrl.hover_at(x, y).await,
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("move_mouse_away"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.move_mouse_away().await,
+            BrowserBackend::Cdp(ctrl) => ctrl.move_mouse_away().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("move_mouse_away", Box::pin(inner.move_mouse_away()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("move_mouse_away"),
         }
     }
 
@@ -76,25 +331,48 @@ impl BrowserBackend {
         y: i64,
         text: &str,
         press_enter: bool,
-        clear_before_typing: bool,
+        mode: InsertionMode,
+        simulate_keystrokes: bool,
     ) -> anyhow::Result<EnvState> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("type_text_at"),
             BrowserBackend::WebDriver(ctrl) => {
-                ctrl.type_text_at(x, y, text, press_enter, clear_before_typing)
+                ctrl.type_text_at(x, y, text, press_enter, mode, simulate_keystrokes)
                     .await
             }
             BrowserBackend::Cdp(ctrl) => {
-                ctrl.type_text_at(x, y, text, press_enter, clear_before_typing)
+                ctrl.type_text_at(x, y, text, press_enter, mode, simulate_keystrokes)
                     .await
             }
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "type_text_at",
+                Box::pin(inner.type_text_at(x, y, text, press_enter, mode, simulate_keystrokes))
+                    .await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("type_text_at"),
         }
     }
 
     /// Scroll the document.
-    pub async fn scroll_document(&self, direction: &str) -> anyhow::Result<EnvState> {
+    pub async fn scroll_document(
+        &self,
+        direction: &str,
+        amount: ScrollAmount,
+        smooth: bool,
+    ) -> anyhow::Result<EnvState> {
         match self {
-            BrowserBackend::WebDriver(ctrl) => ctrl.scroll_document(direction).await,
-            BrowserBackend::Cdp(ctrl) => ctrl.scroll_document(direction).await,
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("scroll_document"),
+            BrowserBackend::WebDriver(ctrl) => {
+                ctrl.scroll_document(direction, amount, smooth).await
+            }
+            BrowserBackend::Cdp(ctrl) => ctrl.scroll_document(direction, amount, smooth).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "scroll_document",
+                Box::pin(inner.scroll_document(direction, amount, smooth)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("scroll_document"),
         }
     }
 
@@ -107,56 +385,323 @@ impl BrowserBackend {
         magnitude: i64,
     ) -> anyhow::Result<EnvState> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("scroll_at"),
             BrowserBackend::WebDriver(ctrl) => ctrl.scroll_at(x, y, direction, magnitude).await,
             BrowserBackend::Cdp(ctrl) => ctrl.scroll_at(x, y, direction, magnitude).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "scroll_at",
+                Box::pin(inner.scroll_at(x, y, direction, magnitude)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("scroll_at"),
+        }
+    }
+
+    /// Scroll the element at coordinates below any pinned header.
+    pub async fn scroll_to_element(
+        &self,
+        x: i64,
+        y: i64,
+        offset: Option<i64>,
+    ) -> anyhow::Result<(i64, EnvState)> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("scroll_to_element"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.scroll_to_element(x, y, offset).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.scroll_to_element(x, y, offset).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "scroll_to_element",
+                Box::pin(inner.scroll_to_element(x, y, offset)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("scroll_to_element"),
+        }
+    }
+
+    /// Select text in an element.
+    pub async fn select_text(
+        &self,
+        target: ElementTarget,
+        start: Option<u32>,
+        end: Option<u32>,
+    ) -> anyhow::Result<(String, EnvState)> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("select_text"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.select_text(target, start, end).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.select_text(target, start, end).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "select_text",
+                Box::pin(inner.select_text(target, start, end)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("select_text"),
+        }
+    }
+
+    /// Set a color or range input.
+    pub async fn set_control_value(
+        &self,
+        target: ElementTarget,
+        value: ControlValue,
+    ) -> anyhow::Result<(String, EnvState)> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("set_control_value"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.set_control_value(target, value).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.set_control_value(target, value).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "set_control_value",
+                Box::pin(inner.set_control_value(target, value)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("set_control_value"),
+        }
+    }
+
+    /// Draw a temporary box around an element.
+    pub async fn highlight_element(
+        &self,
+        target: ElementTarget,
+        duration_ms: u64,
+    ) -> anyhow::Result<(String, EnvState)> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("highlight_element"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.highlight_element(target, duration_ms).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.highlight_element(target, duration_ms).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "highlight_element",
+                Box::pin(inner.highlight_element(target, duration_ms)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("highlight_element"),
+        }
+    }
+
+    /// Fingerprint an element so it can be clicked by name later.
+    pub async fn remember_element(
+        &self,
+        target: ElementTarget,
+    ) -> anyhow::Result<ElementFingerprint> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("remember_element"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.remember_element(target).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.remember_element(target).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "remember_element",
+                Box::pin(inner.remember_element(target)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("remember_element"),
+        }
+    }
+
+    /// Find an element by CSS selector or XPath and scroll it into view.
+    pub async fn locate_element(&self, locator: &Locator) -> anyhow::Result<LocatedElement> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("locate_element"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.locate_element(locator).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.locate_element(locator).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "locate_element",
+                Box::pin(inner.locate_element(locator)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("locate_element"),
+        }
+    }
+
+    /// Find a remembered element again and click it.
+    pub async fn click_named(
+        &self,
+        fingerprint: &ElementFingerprint,
+    ) -> anyhow::Result<((i64, i64), EnvState)> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("click_named"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.click_named(fingerprint).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.click_named(fingerprint).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "click_named",
+                Box::pin(inner.click_named(fingerprint)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("click_named"),
         }
     }
 
     /// Wait 5 seconds.
     pub async fn wait_5_seconds(&self) -> anyhow::Result<EnvState> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("wait_5_seconds"),
             BrowserBackend::WebDriver(ctrl) => ctrl.wait_5_seconds().await,
             BrowserBackend::Cdp(ctrl) => ctrl.wait_5_seconds().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("wait_5_seconds", Box::pin(inner.wait_5_seconds()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("wait_5_seconds"),
         }
     }
 
     /// Go back.
-    pub async fn go_back(&self) -> anyhow::Result<EnvState> {
+    pub async fn go_back(&self) -> anyhow::Result<(EnvState, Option<ScrollPosition>)> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("go_back"),
             BrowserBackend::WebDriver(ctrl) => ctrl.go_back().await,
             BrowserBackend::Cdp(ctrl) => ctrl.go_back().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("go_back", Box::pin(inner.go_back()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("go_back"),
         }
     }
 
     /// Go forward.
-    pub async fn go_forward(&self) -> anyhow::Result<EnvState> {
+    pub async fn go_forward(&self) -> anyhow::Result<(EnvState, Option<ScrollPosition>)> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("go_forward"),
             BrowserBackend::WebDriver(ctrl) => ctrl.go_forward().await,
             BrowserBackend::Cdp(ctrl) => ctrl.go_forward().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("go_forward", Box::pin(inner.go_forward()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("go_forward"),
         }
     }
 
     /// Search.
-    pub async fn search(&self) -> anyhow::Result<EnvState> {
+    pub async fn search(
+        &self,
+        query: Option<&str>,
+        engine: Option<&str>,
+    ) -> anyhow::Result<EnvState> {
         match self {
-            BrowserBackend::WebDriver(ctrl) => ctrl.search().await,
-            BrowserBackend::Cdp(ctrl) => ctrl.search().await,
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("search"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.search(query, engine).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.search(query, engine).await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("search", Box::pin(inner.search(query, engine)).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("search"),
         }
     }
 
     /// Navigate.
     pub async fn navigate(&self, url: &str) -> anyhow::Result<EnvState> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("navigate"),
             BrowserBackend::WebDriver(ctrl) => ctrl.navigate(url).await,
             BrowserBackend::Cdp(ctrl) => ctrl.navigate(url).await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("navigate", Box::pin(inner.navigate(url)).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("navigate"),
+        }
+    }
+
+    /// Get the URL of the current page.
+    pub async fn current_url(&self) -> anyhow::Result<String> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("current_url"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.current_url().await,
+            BrowserBackend::Cdp(ctrl) => ctrl.current_url().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("current_url", Box::pin(inner.current_url()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("current_url"),
+        }
+    }
+
+    /// Search the web with the configured search engine.
+    pub async fn web_search(
+        &self,
+        query: &str,
+        engine: Option<&str>,
+        num_results: usize,
+    ) -> anyhow::Result<Vec<SearchResult>> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("web_search"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.web_search(query, engine, num_results).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.web_search(query, engine, num_results).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "web_search",
+                Box::pin(inner.web_search(query, engine, num_results)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("web_search"),
         }
     }
 
     /// Key combination.
     pub async fn key_combination(&self, keys: Vec<String>) -> anyhow::Result<EnvState> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("key_combination"),
             BrowserBackend::WebDriver(ctrl) => ctrl.key_combination(keys).await,
             BrowserBackend::Cdp(ctrl) => ctrl.key_combination(keys).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "key_combination",
+                Box::pin(inner.key_combination(keys)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("key_combination"),
+        }
+    }
+
+    /// Step the page zoom.
+    pub async fn zoom_page(&self, step: i32) -> anyhow::Result<(f64, EnvState)> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("zoom_page"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.zoom_page(step).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.zoom_page(step).await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("zoom_page", Box::pin(inner.zoom_page(step)).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("zoom_page"),
+        }
+    }
+
+    /// Press a sequence of keys or chords.
+    pub async fn press_keys_sequence(&self, steps: Vec<KeyStep>) -> anyhow::Result<EnvState> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("press_keys_sequence"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.press_keys_sequence(steps).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.press_keys_sequence(steps).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "press_keys_sequence",
+                Box::pin(inner.press_keys_sequence(steps)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("press_keys_sequence"),
+        }
+    }
+
+    /// Tab to an element and activate it.
+    pub async fn tab_to_element(
+        &self,
+        locator: &Locator,
+        backwards: bool,
+        max_presses: u32,
+        activation: Activation,
+    ) -> anyhow::Result<(TabFocus, EnvState)> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("tab_to_element"),
+            BrowserBackend::WebDriver(ctrl) => {
+                ctrl.tab_to_element(locator, backwards, max_presses, activation)
+                    .await
+            }
+            BrowserBackend::Cdp(ctrl) => {
+                ctrl.tab_to_element(locator, backwards, max_presses, activation)
+                    .await
+            }
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "tab_to_element",
+                Box::pin(inner.tab_to_element(locator, backwards, max_presses, activation)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("tab_to_element"),
         }
     }
 
@@ -169,935 +714,6262 @@ impl BrowserBackend {
         destination_y: i64,
     ) -> anyhow::Result<EnvState> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("drag_and_drop"),
             BrowserBackend::WebDriver(ctrl) => {
                 ctrl.drag_and_drop(x, y, destination_x, destination_y).await
             }
             BrowserBackend::Cdp(ctrl) => {
                 ctrl.drag_and_drop(x, y, destination_x, destination_y).await
             }
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "drag_and_drop",
+                Box::pin(inner.drag_and_drop(x, y, destination_x, destination_y)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("drag_and_drop"),
+        }
+    }
+
+    /// Draw a path with native pointer events.
+    pub async fn draw_path(
+        &self,
+        points: &[PathPoint],
+        duration_ms: u64,
+        pointer: PointerType,
+    ) -> anyhow::Result<EnvState> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("draw_path"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.draw_path(points, duration_ms, pointer).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.draw_path(points, duration_ms, pointer).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "draw_path",
+                Box::pin(inner.draw_path(points, duration_ms, pointer)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("draw_path"),
         }
     }
 
-    /// New tab (WebDriver only).
+    /// New tab.
     pub async fn new_tab(&self, url: Option<&str>) -> anyhow::Result<(TabInfo, EnvState)> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("new_tab"),
             BrowserBackend::WebDriver(ctrl) => ctrl.new_tab(url).await,
-            BrowserBackend::Cdp(_) => Err(anyhow::anyhow!(
-                "Tab management is not supported in CDP mode. Use WebDriver mode for tab operations."
-            )),
+            BrowserBackend::Cdp(ctrl) => ctrl.new_tab(url).await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("new_tab", Box::pin(inner.new_tab(url)).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("new_tab"),
         }
     }
 
-    /// Close tab (WebDriver only).
+    /// Close tab.
     pub async fn close_tab(&self, handle: Option<&str>) -> anyhow::Result<EnvState> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("close_tab"),
             BrowserBackend::WebDriver(ctrl) => ctrl.close_tab(handle).await,
-            BrowserBackend::Cdp(_) => Err(anyhow::anyhow!(
-                "Tab management is not supported in CDP mode. Use WebDriver mode for tab operations."
-            )),
+            BrowserBackend::Cdp(ctrl) => ctrl.close_tab(handle).await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("close_tab", Box::pin(inner.close_tab(handle)).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("close_tab"),
         }
     }
 
-    /// Switch tab (WebDriver only).
+    /// Switch tab.
     pub async fn switch_tab(
         &self,
         handle: Option<&str>,
         index: Option<usize>,
     ) -> anyhow::Result<EnvState> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("switch_tab"),
             BrowserBackend::WebDriver(ctrl) => ctrl.switch_tab(handle, index).await,
-            BrowserBackend::Cdp(_) => Err(anyhow::anyhow!(
-                "Tab management is not supported in CDP mode. Use WebDriver mode for tab operations."
-            )),
+            BrowserBackend::Cdp(ctrl) => ctrl.switch_tab(handle, index).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "switch_tab",
+                Box::pin(inner.switch_tab(handle, index)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("switch_tab"),
         }
     }
 
-    /// List tabs (WebDriver only).
+    /// List tabs.
     pub async fn list_tabs(&self) -> anyhow::Result<(Vec<TabInfo>, EnvState)> {
         match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("list_tabs"),
             BrowserBackend::WebDriver(ctrl) => ctrl.list_tabs().await,
-            BrowserBackend::Cdp(_) => Err(anyhow::anyhow!(
-                "Tab management is not supported in CDP mode. Use WebDriver mode for tab operations."
-            )),
+            BrowserBackend::Cdp(ctrl) => ctrl.list_tabs().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("list_tabs", Box::pin(inner.list_tabs()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("list_tabs"),
         }
     }
 
-    /// Close the browser and clean up resources.
-    pub async fn close(&self) -> anyhow::Result<()> {
+    /// Audit the accessibility of the page with axe-core.
+    pub async fn audit_accessibility(
+        &self,
+        axe_source: &str,
+        selector: Option<&str>,
+    ) -> anyhow::Result<AccessibilityAudit> {
         match self {
-            BrowserBackend::WebDriver(ctrl) => ctrl.close().await,
-            BrowserBackend::Cdp(ctrl) => ctrl.close().await,
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("audit_accessibility"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.audit_accessibility(axe_source, selector).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.audit_accessibility(axe_source, selector).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "audit_accessibility",
+                Box::pin(inner.audit_accessibility(axe_source, selector)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("audit_accessibility"),
         }
     }
-}
-
-/// Response type for browser actions that includes screenshot and URL.
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct BrowserStateResponse {
-    /// Current URL of the page.
-    pub url: String,
-    /// Whether the action was successful.
-    pub success: bool,
-    /// Optional message describing the result.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub message: Option<String>,
-}
 
-fn env_state_to_result(state: EnvState, message: Option<&str>) -> Result<CallToolResult, McpError> {
-    let response = BrowserStateResponse {
-        url: state.url,
-        success: true,
-        message: message.map(String::from),
-    };
-    let text = serde_json::to_string_pretty(&response)
-        .or_else(|_| serde_json::to_string(&response))
-        .unwrap_or_else(|e| {
-            tracing::warn!("Failed to serialize response: {}", e);
-            // Fallback: construct minimal valid JSON with safely escaped URL
-            let safe_url =
-                serde_json::to_string(&response.url).unwrap_or_else(|_| "null".to_string());
-            format!(r#"{{"url":{},"success":true}}"#, safe_url)
-        });
-    let text_content = Content::text(text);
-    let image_content = Content::image(state.screenshot, "image/png");
+    /// Collect the links of the page.
+    pub async fn collect_links(&self, selector: Option<&str>) -> anyhow::Result<PageLinks> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("collect_links"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.collect_links(selector).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.collect_links(selector).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "collect_links",
+                Box::pin(inner.collect_links(selector)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("collect_links"),
+        }
+    }
 
-    Ok(CallToolResult::success(vec![text_content, image_content]))
-}
+    /// Change the emulated media settings of the page.
+    pub async fn set_emulated_media(
+        &self,
+        changes: MediaEmulation,
+    ) -> anyhow::Result<(EnvState, MediaEmulation)> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("set_emulated_media"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.set_emulated_media(changes).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.set_emulated_media(changes).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "set_emulated_media",
+                Box::pin(inner.set_emulated_media(changes)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("set_emulated_media"),
+        }
+    }
 
-fn error_to_result(error: &str) -> Result<CallToolResult, McpError> {
-    let response = BrowserStateResponse {
-        url: String::new(),
-        success: false,
-        message: Some(error.to_string()),
-    };
-    // Use serde_json without pretty printing as fallback since it's more reliable
-    let text = serde_json::to_string_pretty(&response)
-        .or_else(|_| serde_json::to_string(&response))
-        .unwrap_or_else(|e| {
-            tracing::warn!("Failed to serialize error response: {}", e);
-            // Construct minimal valid JSON manually
-            format!(
-                r#"{{"success":false,"message":"{}"}}"#,
-                error
-                    .chars()
-                    .filter(|c| c.is_ascii() && *c != '"' && *c != '\\')
-                    .collect::<String>()
-            )
-        });
-    Ok(CallToolResult::error(vec![Content::text(text)]))
-}
+    /// Render the page as seen with a vision deficiency.
+    pub async fn set_vision_deficiency(
+        &self,
+        deficiency: VisionDeficiency,
+    ) -> anyhow::Result<EnvState> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("set_vision_deficiency"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.set_vision_deficiency(deficiency).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.set_vision_deficiency(deficiency).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "set_vision_deficiency",
+                Box::pin(inner.set_vision_deficiency(deficiency)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("set_vision_deficiency"),
+        }
+    }
 
-/// Returns an MCP-level error for disabled tools.
-fn disabled_tool_error(tool_name: &str) -> Result<CallToolResult, McpError> {
-    Err(McpError::invalid_request(
-        format!(
-            "Tool '{}' is disabled via MCP_DISABLED_TOOLS configuration",
-            tool_name
-        ),
-        None,
-    ))
-}
+    /// Open a URL in a new tab, leaving the active tab active.
+    pub async fn open_background_tab(&self, url: &str) -> anyhow::Result<String> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("open_background_tab"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.open_background_tab(url).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.open_background_tab(url).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "open_background_tab",
+                Box::pin(inner.open_background_tab(url)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("open_background_tab"),
+        }
+    }
 
-/// MCP Server handler for browser control.
-#[derive(Clone)]
-pub struct BrowserMcpServer {
-    browser: Arc<BrowserBackend>,
-    config: Arc<Config>,
-    tool_router: ToolRouter<Self>,
-    /// Timestamp of last activity (seconds since UNIX epoch).
-    /// Used for idle timeout tracking.
-    last_activity: Arc<AtomicU64>,
-    /// Handle to the idle timeout monitor task.
-    /// Used to manage the task lifecycle; the task is explicitly cancelled (via `abort`) during shutdown.
-    idle_monitor_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
-    /// Flag to indicate that a browser operation is currently in progress.
-    /// Used to prevent the idle timeout from closing the browser during active operations.
-    operation_in_progress: Arc<AtomicBool>,
-}
+    /// Read the text of a tab, optionally reloading it first, leaving the active
+    /// tab active.
+    pub async fn read_background_tab(
+        &self,
+        tab: &str,
+        selector: Option<&str>,
+        reload: bool,
+    ) -> anyhow::Result<PageText> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("read_background_tab"),
+            BrowserBackend::WebDriver(ctrl) => {
+                ctrl.read_background_tab(tab, selector, reload).await
+            }
+            BrowserBackend::Cdp(ctrl) => ctrl.read_background_tab(tab, selector, reload).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "read_background_tab",
+                Box::pin(inner.read_background_tab(tab, selector, reload)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("read_background_tab"),
+        }
+    }
 
-impl BrowserMcpServer {
-    /// Create a new MCP server with the given configuration.
-    pub fn new(config: Config) -> Self {
-        let config = Arc::new(config);
-        Self::new_with_config(config)
+    /// Close a tab other than the active one.
+    pub async fn close_background_tab(&self, tab: &str) -> anyhow::Result<()> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("close_background_tab"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.close_background_tab(tab).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.close_background_tab(tab).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "close_background_tab",
+                Box::pin(inner.close_background_tab(tab)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("close_background_tab"),
+        }
     }
 
-    /// Create a new MCP server with an Arc-wrapped configuration.
-    /// This avoids cloning the config for each session in HTTP mode.
-    pub fn new_with_config(config: Arc<Config>) -> Self {
-        let browser = Arc::new(BrowserBackend::new((*config).clone()));
-        let last_activity = Arc::new(AtomicU64::new(current_timestamp()));
-        Self {
-            browser,
-            config,
-            tool_router: Self::tool_router(),
-            last_activity,
-            idle_monitor_handle: Arc::new(Mutex::new(None)),
-            operation_in_progress: Arc::new(AtomicBool::new(false)),
+    /// Report the browser's page events to `events`.
+    pub async fn watch_events(&self, events: Arc<PageEvents>) -> anyhow::Result<()> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("watch_events"),
+            BrowserBackend::WebDriver(_) => Err(anyhow::anyhow!(EVENTS_REQUIRE_CDP)),
+            BrowserBackend::Cdp(ctrl) => ctrl.watch_events(events).await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("watch_events", Box::pin(inner.watch_events(events)).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("watch_events"),
         }
     }
 
-    /// Update the last activity timestamp and mark operation as in progress.
-    /// Note: The two atomic stores are not atomic as a unit. A reader could see
-    /// `operation_in_progress=true` but the old `last_activity` timestamp if it reads
-    /// between the two stores. This is acceptable for idle timeout tracking since
-    /// the monitor will simply wait for the next check interval.
-    fn touch(&self) {
-        self.operation_in_progress.store(true, Ordering::Release);
-        self.last_activity
-            .store(current_timestamp(), Ordering::Release);
+    /// How a background reload of a tab is scheduled. Reading another tab
+    /// switches windows in WebDriver mode, so it must run alone there.
+    fn background_kind(&self) -> ActionKind {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(_) => ActionKind::Mutation,
+            BrowserBackend::WebDriver(_) => ActionKind::Exclusive,
+            BrowserBackend::Cdp(_) => ActionKind::Mutation,
+            BrowserBackend::Recording(inner, _) => inner.background_kind(),
+            BrowserBackend::Replay(_) => ActionKind::Mutation,
+        }
     }
 
-    /// Mark the current operation as complete.
-    fn operation_complete(&self) {
-        // Update timestamp first to ensure accurate idle tracking
-        self.last_activity
-            .store(current_timestamp(), Ordering::Release);
-        self.operation_in_progress.store(false, Ordering::Release);
+    /// List the page targets of a browser connected over CDP.
+    pub async fn list_targets(&self) -> anyhow::Result<Vec<PageTarget>> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("list_targets"),
+            BrowserBackend::WebDriver(_) => Err(anyhow::anyhow!(TARGETS_REQUIRE_CDP)),
+            BrowserBackend::Cdp(ctrl) => ctrl.list_targets().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("list_targets", Box::pin(inner.list_targets()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("list_targets"),
+        }
     }
 
-    /// Get the duration since last activity.
-    #[allow(dead_code)]
-    fn idle_duration(&self) -> Duration {
-        let last = self.last_activity.load(Ordering::Acquire);
-        let now = current_timestamp();
-        Duration::from_secs(now.saturating_sub(last))
+    /// Attach to a page target of a browser connected over CDP and make it active.
+    pub async fn attach_to_tab(&self, handle: &str) -> anyhow::Result<EnvState> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("attach_to_tab"),
+            BrowserBackend::WebDriver(_) => Err(anyhow::anyhow!(TARGETS_REQUIRE_CDP)),
+            BrowserBackend::Cdp(ctrl) => ctrl.attach_to_tab(handle).await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("attach_to_tab", Box::pin(inner.attach_to_tab(handle)).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("attach_to_tab"),
+        }
     }
 
-    /// Start the idle timeout monitor if configured.
-    /// This spawns a background task that closes the browser after idle timeout.
-    /// If a monitor is already running, this function does nothing.
-    pub async fn start_idle_monitor(&self) {
-        let idle_timeout = self.config.idle_timeout;
+    /// List frames in the current document.
+    pub async fn list_frames(&self) -> anyhow::Result<(Vec<FrameInfo>, EnvState)> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("list_frames"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.list_frames().await,
+            BrowserBackend::Cdp(ctrl) => ctrl.list_frames().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("list_frames", Box::pin(inner.list_frames()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("list_frames"),
+        }
+    }
 
-        // If idle timeout is zero, don't start the monitor
-        if idle_timeout.is_zero() {
-            debug!("Idle timeout is disabled (set to 0)");
-            return;
+    /// Switch into a frame, or back to the top-level document.
+    pub async fn switch_frame(&self, index: Option<usize>) -> anyhow::Result<EnvState> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("switch_frame"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.switch_frame(index).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.switch_frame(index).await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("switch_frame", Box::pin(inner.switch_frame(index)).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("switch_frame"),
         }
+    }
 
-        // Acquire lock and hold it while checking and spawning to prevent race conditions
-        let mut guard = self.idle_monitor_handle.lock().await;
-        if guard.is_some() {
-            debug!("Idle monitor is already running, skipping start");
-            return;
+    /// Extract page metadata.
+    pub async fn page_metadata(&self) -> anyhow::Result<PageMetadata> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("page_metadata"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.page_metadata().await,
+            BrowserBackend::Cdp(ctrl) => ctrl.page_metadata().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("page_metadata", Box::pin(inner.page_metadata()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("page_metadata"),
         }
+    }
 
-        let browser = Arc::clone(&self.browser);
-        let last_activity = Arc::clone(&self.last_activity);
-        let operation_in_progress = Arc::clone(&self.operation_in_progress);
-        // Check 4 times per timeout period, but at least once per second
-        // to avoid excessive polling for very short timeouts
-        let check_interval = (idle_timeout / 4).max(Duration::from_secs(1));
+    /// Get the notifications recorded in the current page.
+    pub async fn get_notifications(
+        &self,
+        clear: bool,
+    ) -> anyhow::Result<Vec<CapturedNotification>> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("get_notifications"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.get_notifications(clear).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.get_notifications(clear).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "get_notifications",
+                Box::pin(inner.get_notifications(clear)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("get_notifications"),
+        }
+    }
 
-        let handle = tokio::spawn(async move {
-            loop {
-                tokio::time::sleep(check_interval).await;
+    /// Capture the current frame of a video.
+    pub async fn capture_video_frame(&self, selector: Option<&str>) -> anyhow::Result<VideoFrame> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("capture_video_frame"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.capture_video_frame(selector).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.capture_video_frame(selector).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "capture_video_frame",
+                Box::pin(inner.capture_video_frame(selector)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("capture_video_frame"),
+        }
+    }
 
-                // Check if an operation is currently in progress first
-                if operation_in_progress.load(Ordering::Acquire) {
-                    continue;
-                }
+    /// Evaluate a JavaScript expression in the current document.
+    pub async fn evaluate_javascript(&self, expression: &str) -> anyhow::Result<Evaluation> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("evaluate_javascript"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.evaluate_javascript(expression).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.evaluate_javascript(expression).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "evaluate_javascript",
+                Box::pin(inner.evaluate_javascript(expression)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("evaluate_javascript"),
+        }
+    }
 
-                let last = last_activity.load(Ordering::Acquire);
-                let now = current_timestamp();
-                let idle_secs = now.saturating_sub(last);
+    /// List the audio and video elements of the current document.
+    pub async fn get_media(&self) -> anyhow::Result<Vec<MediaElement>> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("get_media"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.get_media().await,
+            BrowserBackend::Cdp(ctrl) => ctrl.get_media().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("get_media", Box::pin(inner.get_media()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("get_media"),
+        }
+    }
 
-                // Check idle time and atomically claim operation_in_progress
-                if idle_secs >= idle_timeout.as_secs() {
-                    // Atomically transition operation_in_progress from false to true.
-                    // If this fails, another operation has started and we should not close.
-                    let claimed = operation_in_progress
-                        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
-                        .is_ok();
+    /// Play, pause, mute or unmute media elements.
+    pub async fn control_media(
+        &self,
+        command: MediaCommand,
+        index: Option<u32>,
+    ) -> anyhow::Result<Vec<MediaElement>> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("control_media"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.control_media(command, index).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.control_media(command, index).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "control_media",
+                Box::pin(inner.control_media(command, index)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("control_media"),
+        }
+    }
 
-                    if !claimed {
-                        continue;
-                    }
+    /// Capture a checkpoint of the current page.
+    pub async fn capture_checkpoint(
+        &self,
+        include_storage: bool,
+    ) -> anyhow::Result<PageCheckpoint> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("capture_checkpoint"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.capture_checkpoint(include_storage).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.capture_checkpoint(include_storage).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "capture_checkpoint",
+                Box::pin(inner.capture_checkpoint(include_storage)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("capture_checkpoint"),
+        }
+    }
 
-                    info!(
-                        "Browser idle for {}s (timeout: {}s), closing browser",
-                        idle_secs,
-                        idle_timeout.as_secs()
-                    );
-                    if let Err(e) = browser.close().await {
-                        warn!("Error closing browser due to idle timeout: {}", e);
-                    }
+    /// Return to a checkpoint.
+    pub async fn restore_checkpoint(
+        &self,
+        checkpoint: &PageCheckpoint,
+    ) -> anyhow::Result<(usize, EnvState)> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("restore_checkpoint"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.restore_checkpoint(checkpoint).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.restore_checkpoint(checkpoint).await,
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "restore_checkpoint",
+                Box::pin(inner.restore_checkpoint(checkpoint)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("restore_checkpoint"),
+        }
+    }
 
-                    // Clear the flag after closing
-                    operation_in_progress.store(false, Ordering::Release);
-                    break;
-                }
+    /// Get all cookies; `None` when the browser cannot report them.
+    pub async fn get_cookies(&self) -> anyhow::Result<Option<Vec<SavedCookie>>> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("get_cookies"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.get_cookies().await,
+            BrowserBackend::Cdp(ctrl) => ctrl.get_cookies().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("get_cookies", Box::pin(inner.get_cookies()).await)
             }
-        });
-
-        *guard = Some(handle);
+            BrowserBackend::Replay(cassette) => cassette.play("get_cookies"),
+        }
     }
 
-    /// Initialize the server, optionally opening the browser if configured.
-    /// Call this after construction if `open_browser_on_start` is enabled.
-    pub async fn init(&self) -> anyhow::Result<()> {
-        if self.config.open_browser_on_start {
-            info!("Opening browser on server start (MCP_OPEN_BROWSER_ON_START=true)");
-            // Note: touch() and start_idle_monitor() are only called if open() succeeds
-            // due to the ? operator returning early on error
-            self.browser.open().await?;
-            self.touch();
-            self.operation_complete();
-            // Start idle monitor only after browser is actually opened
-            self.start_idle_monitor().await;
+    /// Set cookies; false when the browser cannot take them.
+    pub async fn set_cookies(&self, cookies: &[SavedCookie]) -> anyhow::Result<bool> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("set_cookies"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.set_cookies(cookies).await,
+            BrowserBackend::Cdp(ctrl) => ctrl.set_cookies(cookies).await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("set_cookies", Box::pin(inner.set_cookies(cookies)).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("set_cookies"),
         }
-
-        Ok(())
     }
 
-    /// Shutdown the server and close the browser.
-    /// This should be called before the program exits when auto_start is enabled
-    /// to ensure the browser is properly closed.
-    pub async fn shutdown(&self) -> anyhow::Result<()> {
-        info!("Shutting down MCP server, closing browser...");
-
-        // Cancel idle monitor if running
-        let mut guard = self.idle_monitor_handle.lock().await;
-        if let Some(handle) = guard.take() {
-            handle.abort();
+    /// Fill saved credentials into the sign-in form of the current page.
+    pub async fn fill_credentials(
+        &self,
+        credentials: &SiteCredentials,
+        username: Option<&str>,
+        password: &str,
+        submit: bool,
+    ) -> anyhow::Result<(CredentialFill, EnvState)> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("fill_credentials"),
+            BrowserBackend::WebDriver(ctrl) => {
+                ctrl.fill_credentials(credentials, username, password, submit)
+                    .await
+            }
+            BrowserBackend::Cdp(ctrl) => {
+                ctrl.fill_credentials(credentials, username, password, submit)
+                    .await
+            }
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "fill_credentials",
+                Box::pin(inner.fill_credentials(credentials, username, password, submit)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("fill_credentials"),
         }
-        drop(guard);
+    }
 
-        self.browser.close().await
+    /// Run one step of a sign-in on an identity provider page.
+    pub async fn oauth_step(
+        &self,
+        credentials: &SiteCredentials,
+        username: Option<&str>,
+        password: &str,
+        approve_consent: bool,
+    ) -> anyhow::Result<OAuthStep> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("oauth_step"),
+            BrowserBackend::WebDriver(ctrl) => {
+                ctrl.oauth_step(credentials, username, password, approve_consent)
+                    .await
+            }
+            BrowserBackend::Cdp(ctrl) => {
+                ctrl.oauth_step(credentials, username, password, approve_consent)
+                    .await
+            }
+            BrowserBackend::Recording(inner, cassette) => cassette.record_reply(
+                "oauth_step",
+                Box::pin(inner.oauth_step(credentials, username, password, approve_consent)).await,
+            ),
+            BrowserBackend::Replay(cassette) => cassette.play("oauth_step"),
+        }
     }
 
-    /// Get a reference to the browser backend.
-    #[allow(dead_code)]
-    pub fn browser(&self) -> &Arc<BrowserBackend> {
-        &self.browser
+    /// Get page performance metrics, if the browser supports them.
+    pub async fn page_metrics(&self) -> anyhow::Result<Option<PageMetrics>> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("page_metrics"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.page_metrics().await,
+            BrowserBackend::Cdp(ctrl) => ctrl.page_metrics().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("page_metrics", Box::pin(inner.page_metrics()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("page_metrics"),
+        }
     }
-}
 
-/// Get the current timestamp in seconds since UNIX epoch.
-fn current_timestamp() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("System time should be after UNIX epoch")
-        .as_secs()
-}
+    /// Capture a compact DOM snapshot.
+    pub async fn dom_snapshot(&self) -> anyhow::Result<DomSnapshot> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => mock.reply("dom_snapshot"),
+            BrowserBackend::WebDriver(ctrl) => ctrl.dom_snapshot().await,
+            BrowserBackend::Cdp(ctrl) => ctrl.dom_snapshot().await,
+            BrowserBackend::Recording(inner, cassette) => {
+                cassette.record_reply("dom_snapshot", Box::pin(inner.dom_snapshot()).await)
+            }
+            BrowserBackend::Replay(cassette) => cassette.play("dom_snapshot"),
+        }
+    }
 
-// Tool parameter types
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct ClickAtParams {
-    /// X coordinate on the screen.
-    pub x: i64,
-    /// Y coordinate on the screen.
-    pub y: i64,
+    /// Close the browser and clean up resources.
+    pub async fn close(&self) -> anyhow::Result<()> {
+        match self {
+            #[cfg(test)]
+            BrowserBackend::Mock(mock) => {
+                mock.record("close");
+                Ok(())
+            }
+            BrowserBackend::WebDriver(ctrl) => ctrl.close().await,
+            BrowserBackend::Cdp(ctrl) => ctrl.close().await,
+            BrowserBackend::Recording(inner, _) => Box::pin(inner.close()).await,
+            BrowserBackend::Replay(_) => Ok(()),
+        }
+    }
 }
 
+/// Response type for browser actions that includes screenshot and URL.
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct HoverAtParams {
-    /// X coordinate on the screen.
-    pub x: i64,
-    /// Y coordinate on the screen.
-    pub y: i64,
+pub struct BrowserStateResponse {
+    /// Current URL of the page.
+    pub url: String,
+    /// Whether the action was successful.
+    pub success: bool,
+    /// Optional message describing the result.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Set when the screenshot was omitted because the page looks the same as
+    /// in the previous `current_state` call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unchanged: Option<bool>,
+    /// Correlation ID of the failed tool call, matching the `request_id` in the server logs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Machine-readable cause of a failure, e.g. `page_crashed` when the tab
+    /// crashed and was reloaded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    /// Web notifications and permission prompts shown since the previous
+    /// action, when MCP_NOTIFICATIONS_IN_RESPONSE is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<Vec<CapturedNotification>>,
+    /// Warnings about the navigation so far, e.g. a loop between pages or an
+    /// exceeded per-domain navigation budget.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<Vec<String>>,
+    /// What changed since the previous state of the tab, when
+    /// MCP_STATE_DELTA is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta: Option<StateDelta>,
+    /// `false` when a click had no observable effect: no navigation, DOM
+    /// change or focus change. Absent otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effect_detected: Option<bool>,
+    /// Where the time of the call went, for responses carrying a page state.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<DurationBreakdown>,
+    /// `true` when the page asks the user to sign in, e.g. after the session
+    /// expired, when MCP_DETECT_LOGIN is enabled. Absent otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub login_required: Option<bool>,
+    /// Handle of the tab the state is of, when `current_state` was asked for
+    /// a specific tab.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+    /// Title of that tab.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
 }
 
+/// Time a tool call spent in each phase, in milliseconds.
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct TypeTextAtParams {
-    /// X coordinate on the screen.
-    pub x: i64,
-    /// Y coordinate on the screen.
-    pub y: i64,
-    /// Text to type.
-    pub text: String,
-    /// Whether to press Enter after typing. Defaults to false.
-    #[serde(default)]
-    pub press_enter: bool,
-    /// Whether to clear existing content before typing. Defaults to true.
-    #[serde(default = "default_true")]
-    pub clear_before_typing: bool,
+pub struct DurationBreakdown {
+    /// Running the action itself, tab switching included. Filled in once the
+    /// whole call has been timed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<u64>,
+    /// Waiting for the page to be ready and settle before the screenshot.
+    pub settle: u64,
+    /// Capturing and encoding the screenshot.
+    pub screenshot: u64,
 }
 
-fn default_true() -> bool {
-    true
+fn env_state_to_result(state: EnvState, message: Option<&str>) -> Result<CallToolResult, McpError> {
+    tab_state_to_result(state, message, None, None)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct ScrollDocumentParams {
-    /// Direction to scroll: "up", "down", "left", or "right".
-    pub direction: String,
+/// Result carrying a page state of a specific tab, named by its handle and title.
+fn tab_state_to_result(
+    state: EnvState,
+    message: Option<&str>,
+    tab: Option<&str>,
+    title: Option<String>,
+) -> Result<CallToolResult, McpError> {
+    let response = BrowserStateResponse {
+        url: state.url,
+        success: true,
+        message: message.map(String::from),
+        unchanged: None,
+        request_id: None,
+        error_code: None,
+        notifications: (!state.notifications.is_empty()).then_some(state.notifications),
+        warnings: (!state.warnings.is_empty()).then_some(state.warnings),
+        delta: state.delta,
+        effect_detected: state.effect_detected,
+        duration_ms: Some(DurationBreakdown {
+            action: None,
+            settle: state.timings.settle_ms,
+            screenshot: state.timings.screenshot_ms,
+        }),
+        login_required: state.login_required.then_some(true),
+        tab: tab.map(String::from),
+        title,
+    };
+    let text = serde_json::to_string_pretty(&response)
+        .or_else(|_| serde_json::to_string(&response))
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to serialize response: {}", e);
+            // Fallback: construct minimal valid JSON with safely escaped URL
+            let safe_url =
+                serde_json::to_string(&response.url).unwrap_or_else(|_| "null".to_string());
+            format!(r#"{{"url":{},"success":true}}"#, safe_url)
+        });
+    let text_content = Content::text(text);
+    let image_content = Content::image(state.screenshot, "image/png");
+
+    Ok(CallToolResult::success(vec![text_content, image_content]))
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct ScrollAtParams {
-    /// X coordinate on the screen.
-    pub x: i64,
-    /// Y coordinate on the screen.
-    pub y: i64,
-    /// Direction to scroll: "up", "down", "left", or "right".
-    pub direction: String,
-    /// Magnitude of scroll in pixels. Defaults to 800.
-    #[serde(default = "default_magnitude")]
-    pub magnitude: i64,
+/// Append context strips to a state result, each image after a line saying
+/// what it shows. A failed capture is reported without failing the result.
+fn with_context_strips(
+    mut result: CallToolResult,
+    strips: anyhow::Result<ContextStrips>,
+) -> CallToolResult {
+    let strips = match strips {
+        Ok(strips) => strips,
+        Err(e) => {
+            result
+                .content
+                .push(Content::text(format!("Context strips unavailable: {}", e)));
+            return result;
+        }
+    };
+    for (side, strip) in [("above", strips.above), ("below", strips.below)] {
+        match strip {
+            Some(strip) => {
+                result.content.push(Content::text(format!(
+                    "Preview of the {} px {} the viewport, at half scale ({} px of content {} it in total):",
+                    strip.covers, side, strip.hidden, side
+                )));
+                result
+                    .content
+                    .push(Content::image(strip.screenshot, "image/png"));
+            }
+            None => result
+                .content
+                .push(Content::text(format!("No content {} the viewport.", side))),
+        }
+    }
+    result
 }
 
-fn default_magnitude() -> i64 {
-    800
+/// Result for a page that has not changed since the last screenshot; carries no image.
+fn unchanged_to_result(url: String) -> Result<CallToolResult, McpError> {
+    let response = BrowserStateResponse {
+        url,
+        success: true,
+        message: Some("Page unchanged since the previous screenshot".to_string()),
+        unchanged: Some(true),
+        request_id: None,
+        error_code: None,
+        notifications: None,
+        warnings: None,
+        delta: None,
+        effect_detected: None,
+        duration_ms: None,
+        login_required: None,
+        tab: None,
+        title: None,
+    };
+    let text = serde_json::to_string_pretty(&response)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    Ok(CallToolResult::success(vec![Content::text(text)]))
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct NavigateParams {
-    /// URL to navigate to. Will be prefixed with "https://" if no protocol specified.
-    pub url: String,
+fn error_to_result(error: &str) -> Result<CallToolResult, McpError> {
+    let response = BrowserStateResponse {
+        url: String::new(),
+        success: false,
+        message: Some(error.to_string()),
+        unchanged: None,
+        request_id: current_request_id(),
+        error_code: error_code(error).map(String::from),
+        notifications: None,
+        warnings: None,
+        delta: None,
+        effect_detected: None,
+        duration_ms: None,
+        login_required: None,
+        tab: None,
+        title: None,
+    };
+    // Use serde_json without pretty printing as fallback since it's more reliable
+    let text = serde_json::to_string_pretty(&response)
+        .or_else(|_| serde_json::to_string(&response))
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to serialize error response: {}", e);
+            // Construct minimal valid JSON manually
+            format!(
+                r#"{{"success":false,"message":"{}"}}"#,
+                error
+                    .chars()
+                    .filter(|c| c.is_ascii() && *c != '"' && *c != '\\')
+                    .collect::<String>()
+            )
+        });
+    Ok(CallToolResult::error(vec![Content::text(text)]))
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct KeyCombinationParams {
-    /// List of keys to press together. Example: ["Control", "c"] for Ctrl+C.
-    pub keys: Vec<String>,
+/// Error code for a failure message. Errors reach `error_to_result` already
+/// formatted, so the cause is recognized by its message.
+fn error_code(message: &str) -> Option<&'static str> {
+    message
+        .contains(PAGE_CRASHED_MESSAGE)
+        .then_some(PAGE_CRASHED_CODE)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct DragAndDropParams {
-    /// Starting X coordinate.
-    pub x: i64,
-    /// Starting Y coordinate.
-    pub y: i64,
-    /// Destination X coordinate.
-    pub destination_x: i64,
-    /// Destination Y coordinate.
-    pub destination_y: i64,
+/// Longest selected text echoed back by the selection tools, in characters.
+const MAX_SELECTION_PREVIEW_CHARS: usize = 200;
+
+/// Describe a text selection, shortening long selections.
+fn selection_message(text: &str) -> String {
+    let length = text.chars().count();
+    if length == 0 {
+        return "Placed the caret".to_string();
+    }
+    if length > MAX_SELECTION_PREVIEW_CHARS {
+        let preview: String = text.chars().take(MAX_SELECTION_PREVIEW_CHARS).collect();
+        return format!("Selected {} characters: {:?}...", length, preview);
+    }
+    format!("Selected {} characters: {:?}", length, text)
 }
 
-// Tab operation parameter types
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct NewTabParams {
-    /// Optional URL to navigate to in the new tab.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub url: Option<String>,
+/// Returns an MCP-level error for disabled tools.
+fn disabled_tool_error(tool_name: &str) -> Result<CallToolResult, McpError> {
+    Err(McpError::invalid_request(
+        format!(
+            "Tool '{}' is disabled via MCP_DISABLED_TOOLS configuration",
+            tool_name
+        ),
+        None,
+    ))
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct CloseTabParams {
-    /// The window handle of the tab to close. If not provided, closes the current tab.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub handle: Option<String>,
+/// Maximum number of pages complete_oauth_login steps through.
+const MAX_OAUTH_STEPS: usize = 8;
+
+/// Maximum number of levels open_menu_path walks through.
+const MAX_MENU_LEVELS: usize = 10;
+
+/// Time each open_menu_path level waits for its item by default, in
+/// milliseconds.
+const DEFAULT_MENU_LEVEL_TIMEOUT_MS: u64 = 2000;
+
+/// Maximum time each open_menu_path level waits for its item, in
+/// milliseconds.
+const MAX_MENU_LEVEL_TIMEOUT_MS: u64 = 10_000;
+
+/// Interval between looks for a menu item that has not appeared yet.
+const MENU_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Maximum number of checkpoints kept per session.
+const MAX_CHECKPOINTS: usize = 32;
+
+/// MCP Server handler for browser control.
+#[derive(Clone)]
+pub struct BrowserMcpServer {
+    browser: Arc<BrowserBackend>,
+    config: Arc<Config>,
+    tool_router: ToolRouter<Self>,
+    /// Timestamp of last activity (seconds since UNIX epoch).
+    /// Used for idle timeout tracking.
+    last_activity: Arc<AtomicU64>,
+    /// Handle to the idle timeout monitor task.
+    /// Used to manage the task lifecycle; the task is explicitly cancelled (via `abort`) during shutdown.
+    idle_monitor_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    /// Handle to the memory monitor task, which logs warnings when memory thresholds are exceeded.
+    memory_monitor_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    /// Flag to indicate that a browser operation is currently in progress.
+    /// Used to prevent the idle timeout from closing the browser during active operations.
+    operation_in_progress: Arc<AtomicBool>,
+    /// Serializes state-changing actions while letting read-only queries through.
+    action_queue: Arc<ActionQueue>,
+    /// Fingerprint of the last `current_state` screenshot per tab ("" for the
+    /// active tab), used to detect unchanged pages.
+    last_screenshots: Arc<Mutex<HashMap<String, ScreenshotFingerprint>>>,
+    /// Checkpoints saved with `save_checkpoint`, by name.
+    checkpoints: Arc<Mutex<HashMap<String, PageCheckpoint>>>,
+    /// Recent tool calls, returned by `get_action_history`.
+    action_history: Arc<ActionHistory>,
+    /// robots.txt checks and per-host delays for navigations.
+    politeness: Arc<Politeness>,
+    /// Browsers created with `create_browser`, by ID.
+    browsers: Arc<Mutex<HashMap<String, NamedBrowser>>>,
+    /// Pages watched with `watch_page` and the client's resource subscriptions.
+    watches: Arc<PageWatches>,
+    /// Reload tasks of the watched pages, by watch ID.
+    watch_tasks: Arc<Mutex<HashMap<String, WatchTask>>>,
+    /// Elements named with `remember_element`.
+    element_aliases: Arc<ElementAliases>,
+    /// Events collected with `subscribe_events`.
+    events: Arc<PageEvents>,
+    /// Task notifying subscribers of the event resource about new events.
+    event_notifier: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    /// Screenshots returned by tools, for `get_screenshot`; `None` when disabled.
+    screenshots: Option<Arc<ScreenshotStore>>,
+    /// Scratch directory of the session, for downloads and other files.
+    scratch: Arc<SessionScratch>,
+    /// Limits on the files tools read and write.
+    path_policy: Arc<PathPolicy>,
+    /// Limits on the files the browser downloads.
+    download_policy: Arc<DownloadPolicy>,
+    /// Operator approval of sensitive tool calls; `None` when not configured.
+    approval: Option<Arc<ApprovalGate>>,
+    /// Closes the browser once the last clone of this server is dropped.
+    _cleanup: Arc<SessionCleanup>,
 }
 
-/// Parameters for switching to a tab.
-/// Exactly one of `handle` or `index` must be provided.
-#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
-pub struct SwitchTabParams {
-    /// The window handle of the tab to switch to.
-    /// Exactly one of `handle` or `index` must be provided.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub handle: Option<String>,
-    /// The index of the tab to switch to (0-based).
-    /// Exactly one of `handle` or `index` must be provided.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub index: Option<usize>,
+/// Tears down a server's browser when the server goes away.
+///
+/// In HTTP mode every MCP session owns its own server, which rmcp drops when the
+/// session is closed by the client or expires. Without this guard the session's
+/// browser, and any browser process launched for it, would outlive the session.
+struct SessionCleanup {
+    browser: Arc<BrowserBackend>,
+    browsers: Arc<Mutex<HashMap<String, NamedBrowser>>>,
+    watch_tasks: Arc<Mutex<HashMap<String, WatchTask>>>,
+    monitor_handles: [Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>; 3],
+    /// Removed once the browsers are closed, so no download is still writing to it.
+    scratch: Arc<SessionScratch>,
 }
 
-// Custom deserialization to enforce that exactly one of `handle` or `index` is provided.
-impl<'de> serde::Deserialize<'de> for SwitchTabParams {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        #[derive(Deserialize)]
-        struct RawSwitchTabParams {
-            handle: Option<String>,
-            index: Option<usize>,
-        }
+impl Drop for SessionCleanup {
+    fn drop(&mut self) {
+        // Closing is async; without a runtime (process exit) there is nothing to schedule on
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
 
-        let raw = RawSwitchTabParams::deserialize(deserializer)?;
+        let browser = Arc::clone(&self.browser);
+        let browsers = Arc::clone(&self.browsers);
+        let watch_tasks = Arc::clone(&self.watch_tasks);
+        let monitor_handles = self.monitor_handles.clone();
+        let scratch = Arc::clone(&self.scratch);
+        runtime.spawn(async move {
+            debug!("MCP session ended, releasing browser");
+            for handle in monitor_handles {
+                if let Some(handle) = handle.lock().await.take() {
+                    handle.abort();
+                }
+            }
+            for (_, watch) in watch_tasks.lock().await.drain() {
+                watch.task.abort();
+            }
+            // No-op when the browser is already closed, e.g. after an explicit shutdown()
+            if let Err(e) = browser.close().await {
+                warn!("Error closing browser after session ended: {}", e);
+            }
+            for (id, named) in browsers.lock().await.drain() {
+                if let Err(e) = named.close().await {
+                    warn!("Error closing browser '{}' after session ended: {}", id, e);
+                }
+            }
+            drop(scratch);
+        });
+    }
+}
 
-        match (&raw.handle, &raw.index) {
-            (Some(_), Some(_)) => Err(serde::de::Error::custom(
-                "Provide exactly one of 'handle' or 'index', not both",
-            )),
-            (None, None) => Err(serde::de::Error::custom(
-                "Provide either 'handle' or 'index'",
-            )),
-            _ => Ok(SwitchTabParams {
-                handle: raw.handle,
-                index: raw.index,
-            }),
+/// A browser created with `create_browser`. It has its own action queue and
+/// screenshot history, so it does not wait for or interfere with the others.
+#[derive(Clone)]
+struct NamedBrowser {
+    browser: Arc<BrowserBackend>,
+    action_queue: Arc<ActionQueue>,
+    last_screenshots: Arc<Mutex<HashMap<String, ScreenshotFingerprint>>>,
+    /// Profile directory of a browser launched in CDP mode, removed on close.
+    profile_dir: Option<PathBuf>,
+}
+
+impl NamedBrowser {
+    /// Close the browser and remove its profile.
+    async fn close(&self) -> anyhow::Result<()> {
+        let result = self.browser.close().await;
+        if let Some(ref dir) = self.profile_dir {
+            if let Err(e) = std::fs::remove_dir_all(dir) {
+                debug!("Failed to remove browser profile {:?}: {}", dir, e);
+            }
         }
+        result
     }
 }
 
-/// Response type for tab list operation.
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct TabListResponse {
-    /// List of all open tabs.
-    pub tabs: Vec<TabInfo>,
-    /// Whether the operation was successful.
-    pub success: bool,
-    /// Optional message describing the result.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub message: Option<String>,
+/// Background reloads of a page watched with `watch_page`.
+struct WatchTask {
+    browser: Arc<BrowserBackend>,
+    action_queue: Arc<ActionQueue>,
+    tab: String,
+    task: tokio::task::JoinHandle<()>,
 }
 
-/// Response type for new tab operation.
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct NewTabResponse {
-    /// Information about the newly created tab.
-    pub tab: TabInfo,
-    /// Whether the operation was successful.
-    pub success: bool,
-    /// Optional message describing the result.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub message: Option<String>,
+impl WatchTask {
+    /// Stop reloading and close the watched tab.
+    async fn stop(self) -> anyhow::Result<()> {
+        self.task.abort();
+        let _permit = self
+            .action_queue
+            .admit(self.browser.background_kind(), &self.tab)
+            .await;
+        self.browser.close_background_tab(&self.tab).await
+    }
 }
 
-#[tool_router]
-impl BrowserMcpServer {
-    /// Opens the web browser and returns the current state.
+/// Reload a watched page every interval until its watch is removed, notifying
+/// the client when the text changes. Reloads count as activity for the idle
+/// timeout.
+async fn run_watch(
+    browser: Arc<BrowserBackend>,
+    action_queue: Arc<ActionQueue>,
+    politeness: Arc<Politeness>,
+    watches: Arc<PageWatches>,
+    last_activity: Arc<AtomicU64>,
+    watch: WatchStatus,
+    peer: Peer<RoleServer>,
+) {
+    let interval = Duration::from_secs(watch.interval_secs);
+    loop {
+        tokio::time::sleep(interval).await;
+        let read = async {
+            politeness.before_navigation(&watch.url).await?;
+            let _permit = action_queue
+                .admit(browser.background_kind(), &watch.tab)
+                .await;
+            last_activity.store(current_timestamp(), Ordering::Release);
+            browser
+                .read_background_tab(&watch.tab, watch.selector.as_deref(), true)
+                .await
+        }
+        .await;
+        if let Err(ref e) = read {
+            debug!("Failed to reload watched page {}: {}", watch.url, e);
+        }
+        let read = read.map_err(|e| e.to_string());
+        let Some(changed) = watches.record(&watch.id, read, current_timestamp()) else {
+            return;
+        };
+        if changed && watches.is_subscribed(&watch.uri) {
+            debug!("Watched page {} changed", watch.url);
+            let notification = ResourceUpdatedNotificationParam {
+                uri: watch.uri.clone(),
+            };
+            if let Err(e) = peer.notify_resource_updated(notification).await {
+                debug!("Failed to notify about {}: {}", watch.uri, e);
+            }
+        }
+    }
+}
+
+/// Notify the client about new events while it is subscribed to the event
+/// resource.
+async fn run_event_notifier(events: Arc<PageEvents>, peer: Peer<RoleServer>) {
+    loop {
+        events.arrived().await;
+        if events.is_resource_subscribed() {
+            let notification = ResourceUpdatedNotificationParam {
+                uri: page_events::URI.to_string(),
+            };
+            if let Err(e) = peer.notify_resource_updated(notification).await {
+                debug!("Failed to notify about new events: {}", e);
+            }
+        }
+        tokio::time::sleep(EVENT_NOTIFY_INTERVAL).await;
+    }
+}
+
+/// Result of the event tools.
+fn events_to_result(batch: EventBatch) -> Result<CallToolResult, McpError> {
+    let response = EventsResponse {
+        success: true,
+        uri: page_events::URI.to_string(),
+        batch,
+    };
+    let text = serde_json::to_string_pretty(&response)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    Ok(CallToolResult::success(vec![Content::text(text)]))
+}
+
+impl BrowserMcpServer {
+    /// Create a new MCP server with the given configuration.
+    pub fn new(config: Config) -> Self {
+        let config = Arc::new(config);
+        Self::new_with_config(config)
+    }
+
+    /// Create a new MCP server with an Arc-wrapped configuration.
+    /// This avoids cloning the config for each session in HTTP mode.
+    pub fn new_with_config(config: Arc<Config>) -> Self {
+        let scratch = Arc::new(SessionScratch::new(config.scratch_dir.as_deref()));
+        let mut browser_config = (*config).clone();
+        browser_config.download_dir = Some(scratch.downloads_dir());
+        let browser = Arc::new(BrowserBackend::new(browser_config));
+        Self::assemble(config, browser, scratch)
+    }
+
+    /// Create a server whose tools act on a mock backend.
+    #[cfg(test)]
+    fn with_mock(config: Config, mock: Arc<MockBackend>) -> Self {
+        let scratch = Arc::new(SessionScratch::new(config.scratch_dir.as_deref()));
+        Self::assemble(
+            Arc::new(config),
+            Arc::new(BrowserBackend::Mock(mock)),
+            scratch,
+        )
+    }
+
+    /// Create a server acting on `browser`, with `scratch` as its scratch directory.
+    fn assemble(
+        config: Arc<Config>,
+        browser: Arc<BrowserBackend>,
+        scratch: Arc<SessionScratch>,
+    ) -> Self {
+        let path_policy = Arc::new(PathPolicy::new(&config).with_root(scratch.dir()));
+        let download_policy = Arc::new(DownloadPolicy::new(&config));
+        let approval = ApprovalGate::new(&config).map(Arc::new);
+        let last_activity = Arc::new(AtomicU64::new(current_timestamp()));
+        let idle_monitor_handle = Arc::new(Mutex::new(None));
+        let memory_monitor_handle = Arc::new(Mutex::new(None));
+        let action_history = Arc::new(ActionHistory::new(config.action_history_size));
+        let politeness = Arc::new(Politeness::new(
+            config.respect_robots_txt,
+            config.politeness_delay,
+        ));
+        let browsers = Arc::new(Mutex::new(HashMap::new()));
+        let watch_tasks = Arc::new(Mutex::new(HashMap::new()));
+        let event_notifier = Arc::new(Mutex::new(None));
+        #[allow(unused_mut)]
+        let mut tool_router = Self::tool_router();
+        #[cfg(feature = "ocr")]
+        tool_router.merge(Self::ocr_tool_router());
+        let screenshots = ScreenshotStore::new(&config)
+            .unwrap_or_else(|e| {
+                warn!("Screenshot storage disabled: {}", e);
+                None
+            })
+            .map(Arc::new);
+        let cleanup = Arc::new(SessionCleanup {
+            browser: Arc::clone(&browser),
+            browsers: Arc::clone(&browsers),
+            watch_tasks: Arc::clone(&watch_tasks),
+            monitor_handles: [
+                Arc::clone(&idle_monitor_handle),
+                Arc::clone(&memory_monitor_handle),
+                Arc::clone(&event_notifier),
+            ],
+            scratch: Arc::clone(&scratch),
+        });
+        Self {
+            browser,
+            config,
+            tool_router,
+            last_activity,
+            idle_monitor_handle,
+            memory_monitor_handle,
+            operation_in_progress: Arc::new(AtomicBool::new(false)),
+            action_queue: Arc::new(ActionQueue::new()),
+            last_screenshots: Arc::new(Mutex::new(HashMap::new())),
+            checkpoints: Arc::new(Mutex::new(HashMap::new())),
+            action_history,
+            politeness,
+            browsers,
+            watches: Arc::new(PageWatches::default()),
+            element_aliases: Arc::new(ElementAliases::default()),
+            watch_tasks,
+            events: Arc::new(PageEvents::default()),
+            event_notifier,
+            screenshots,
+            scratch,
+            path_policy,
+            download_policy,
+            approval,
+            _cleanup: cleanup,
+        }
+    }
+
+    /// Update the last activity timestamp and mark operation as in progress.
+    /// Note: The two atomic stores are not atomic as a unit. A reader could see
+    /// `operation_in_progress=true` but the old `last_activity` timestamp if it reads
+    /// between the two stores. This is acceptable for idle timeout tracking since
+    /// the monitor will simply wait for the next check interval.
+    fn touch(&self) {
+        self.operation_in_progress.store(true, Ordering::Release);
+        self.last_activity
+            .store(current_timestamp(), Ordering::Release);
+    }
+
+    /// Convert a point of the returned screenshots, which may be scaled
+    /// down, to page pixels.
+    fn page_point(&self, x: i64, y: i64) -> (i64, i64) {
+        screenshot_scale::to_page(x, y, self.config.screenshot_scale())
+    }
+
+    /// Convert a point of the page to pixels of the returned screenshots.
+    fn screenshot_point(&self, x: i64, y: i64) -> (i64, i64) {
+        screenshot_scale::to_screenshot(x, y, self.config.screenshot_scale())
+    }
+
+    /// Convert the bounds of snapshot nodes and their children from page
+    /// pixels to pixels of the returned screenshots.
+    fn screenshot_bounds(&self, nodes: &mut [SnapshotNode]) {
+        for node in nodes {
+            let [x, y, width, height] = node.bounds;
+            let (left, top) = self.screenshot_point(x, y);
+            let (right, bottom) = self.screenshot_point(x + width, y + height);
+            node.bounds = [left, top, right - left, bottom - top];
+            self.screenshot_bounds(&mut node.children);
+        }
+    }
+
+    /// Convert a point target from screenshot to page pixels.
+    fn page_target(&self, target: ElementTarget) -> ElementTarget {
+        match target {
+            ElementTarget::Point(x, y) => {
+                let (x, y) = self.page_point(x, y);
+                ElementTarget::Point(x, y)
+            }
+            target => target,
+        }
+    }
+
+    /// Mark the current operation as complete.
+    fn operation_complete(&self) {
+        // Update timestamp first to ensure accurate idle tracking
+        self.last_activity
+            .store(current_timestamp(), Ordering::Release);
+        self.operation_in_progress.store(false, Ordering::Release);
+    }
+
+    /// Record the fingerprint of a `current_state` screenshot and report whether
+    /// it matches the previous one for the same tab.
+    async fn screenshot_unchanged(&self, key: String, url: &str, screenshot: &str) -> bool {
+        let url = url.to_string();
+        let screenshot = screenshot.to_string();
+        // Decoding and resizing a full screenshot is CPU-bound
+        let fingerprint = match tokio::task::spawn_blocking(move || {
+            ScreenshotFingerprint::new(&url, &screenshot)
+        })
+        .await
+        {
+            Ok(Ok(fingerprint)) => fingerprint,
+            Ok(Err(e)) => {
+                warn!("Failed to fingerprint screenshot: {}", e);
+                self.last_screenshots.lock().await.remove(&key);
+                return false;
+            }
+            Err(e) => {
+                warn!("Screenshot fingerprint task failed: {}", e);
+                return false;
+            }
+        };
+
+        let previous = self
+            .last_screenshots
+            .lock()
+            .await
+            .insert(key, fingerprint.clone());
+        previous.is_some_and(|previous| previous == fingerprint)
+    }
+
+    /// Fill the saved credentials of a site into the current page, opening the
+    /// site's sign-in page first when the page is on another site.
+    async fn fill_saved_credentials(
+        &self,
+        site: &str,
+        submit: bool,
+    ) -> anyhow::Result<(CredentialFill, EnvState)> {
+        let path = self.config.credentials_file.as_deref().ok_or_else(|| {
+            anyhow::anyhow!("No saved credentials: MCP_CREDENTIALS_FILE is not set")
+        })?;
+        let store = CredentialStore::load(path)?;
+        let credentials = store.get(site)?;
+        let username = credentials.username()?;
+        let password = credentials.password().await?;
+
+        let (fill, state) = self
+            .browser
+            .fill_credentials(credentials, username.as_deref(), &password, submit)
+            .await?;
+        match credentials.url {
+            Some(ref url) if !fill.allowed => {
+                info!("Opening the sign-in page of '{}'", site);
+                self.browser.navigate(url).await?;
+                self.browser
+                    .fill_credentials(credentials, username.as_deref(), &password, submit)
+                    .await
+            }
+            _ => Ok((fill, state)),
+        }
+    }
+
+    /// Check a navigation target against robots.txt and wait out its host's
+    /// politeness delay.
+    async fn before_navigation(&self, url: &str) -> anyhow::Result<()> {
+        self.politeness.before_navigation(&normalize_url(url)).await
+    }
+
+    /// [`Self::before_navigation`] for the page a search opens.
+    async fn before_search(&self, query: Option<&str>, engine: Option<&str>) -> anyhow::Result<()> {
+        if !self.politeness.is_enabled() {
+            return Ok(());
+        }
+        let engine = self.config.search_engine(engine)?;
+        let url = query
+            .and_then(|query| engine.query_url(query))
+            .unwrap_or_else(|| engine.home_url());
+        self.before_navigation(&url).await
+    }
+
+    /// Refusal message for a tool blocked by the recipe of the target page's
+    /// site, if any.
+    async fn recipe_block(&self, tool: &str, tab: Option<&str>) -> Option<String> {
+        if !self.config.recipes.may_block(tool) {
+            return None;
+        }
+        let url = self
+            .browser
+            .in_tab(tab, self.browser.current_url())
+            .await
+            .ok()?;
+        let host = credentials::host_of(&url)?;
+        let recipe = self.config.recipes.for_host(&host)?;
+        recipe.blocks(tool).then(|| {
+            format!(
+                "'{}' is blocked on {} by the site recipe '{}'",
+                tool, host, recipe.name
+            )
+        })
+    }
+
+    /// Ask the operator to approve a call the approval rules name. Returns why
+    /// the call may not run, if it may not.
+    async fn approval_block(
+        &self,
+        tool: &str,
+        tab: Option<&str>,
+        arguments: Option<&serde_json::Map<String, serde_json::Value>>,
+        session_id: Option<&str>,
+    ) -> Option<String> {
+        let gate = self.approval.as_ref()?;
+        if !gate.may_require(tool) {
+            return None;
+        }
+        let url = self
+            .browser
+            .in_tab(tab, self.browser.current_url())
+            .await
+            .ok();
+        let host = url.as_deref().and_then(credentials::host_of);
+        if !gate.requires(tool, host.as_deref()) {
+            return None;
+        }
+        info!("Waiting for operator approval of '{}'", tool);
+        let request_id = current_request_id().unwrap_or_default();
+        let denied = gate
+            .ask(&ApprovalRequest {
+                request_id: &request_id,
+                session_id,
+                tool,
+                arguments,
+                url: url.as_deref(),
+            })
+            .await;
+        match denied {
+            Some(ref reason) => warn!("{}", reason),
+            None => info!("Operator approved '{}'", tool),
+        }
+        denied
+    }
+
+    /// Capture every open tab and the browser's cookies. Also returns notes on
+    /// what could not be captured.
+    async fn capture_workspace(&self) -> anyhow::Result<(Workspace, Vec<String>)> {
+        let (tabs, _) = self.browser.list_tabs().await?;
+        let mut notes = Vec::new();
+        let mut checkpoints = Vec::new();
+        let mut active_tab = 0;
+        for tab in &tabs {
+            let capture = self.browser.capture_checkpoint(true);
+            match self.browser.in_tab(Some(&tab.handle), capture).await {
+                Ok(checkpoint) => {
+                    if tab.active {
+                        active_tab = checkpoints.len();
+                    }
+                    checkpoints.push(checkpoint);
+                }
+                Err(e) => notes.push(format!("Skipped tab {}: {}", tab.url, e)),
+            }
+        }
+        // Capturing switches windows in WebDriver mode
+        if let Some(active) = tabs.iter().find(|tab| tab.active) {
+            self.browser
+                .in_tab(Some(&active.handle), async { Ok(()) })
+                .await?;
+        }
+        let cookies = match self.browser.get_cookies().await? {
+            Some(cookies) => cookies,
+            None => {
+                notes.push("Cookies not saved: they require Chrome or Edge".to_string());
+                Vec::new()
+            }
+        };
+        let workspace = Workspace {
+            saved_at: current_timestamp(),
+            tabs: checkpoints,
+            active_tab,
+            cookies,
+        };
+        Ok((workspace, notes))
+    }
+
+    /// Restore a workspace's cookies, then its tabs: the first one in the
+    /// active tab, the others in new tabs. Also returns notes on what could
+    /// not be restored.
+    async fn restore_workspace(
+        &self,
+        workspace: &Workspace,
+    ) -> anyhow::Result<(EnvState, Vec<String>)> {
+        let mut notes = Vec::new();
+        // Before any page loads, so the pages see them
+        if !workspace.cookies.is_empty() && !self.browser.set_cookies(&workspace.cookies).await? {
+            notes.push("Cookies not restored: they require Chrome or Edge".to_string());
+        }
+
+        let (tabs, _) = self.browser.list_tabs().await?;
+        let mut handles = Vec::new();
+        for (index, checkpoint) in workspace.tabs.iter().enumerate() {
+            let handle = match tabs.iter().find(|tab| tab.active) {
+                Some(active) if index == 0 => active.handle.clone(),
+                _ => self.browser.new_tab(None).await?.0.handle,
+            };
+            let restore = self.browser.restore_checkpoint(checkpoint);
+            if let Err(e) = self.browser.in_tab(Some(&handle), restore).await {
+                notes.push(format!("Failed to restore tab {}: {}", checkpoint.url, e));
+            }
+            handles.push(handle);
+        }
+
+        let state = match handles.get(workspace.active_tab) {
+            Some(handle) => self.browser.switch_tab(Some(handle), None).await?,
+            None => self.browser.current_state().await?,
+        };
+        Ok((state, notes))
+    }
+
+    /// A copy of the server whose tools act on the browser with the given ID.
+    async fn with_browser(&self, id: &str) -> Result<Self, String> {
+        if id == DEFAULT_BROWSER_ID {
+            return Ok(self.clone());
+        }
+        let browsers = self.browsers.lock().await;
+        let named = browsers.get(id).ok_or_else(|| {
+            format!(
+                "No browser with ID '{}'; create one with create_browser",
+                id
+            )
+        })?;
+        Ok(Self {
+            browser: Arc::clone(&named.browser),
+            action_queue: Arc::clone(&named.action_queue),
+            last_screenshots: Arc::clone(&named.last_screenshots),
+            ..self.clone()
+        })
+    }
+
+    /// Keep the screenshots of a tool result in the screenshot store and add
+    /// their IDs to the result's JSON.
+    async fn store_screenshots(&self, tool: &str, result: &mut CallToolResult) {
+        let Some(ref store) = self.screenshots else {
+            return;
+        };
+        let response = response_json(result);
+        let url = response
+            .as_ref()
+            .and_then(|response| response.get("url"))
+            .and_then(|url| url.as_str())
+            .map(String::from);
+
+        let mut ids = Vec::new();
+        for content in &result.content {
+            let RawContent::Image(ref image) = content.raw else {
+                continue;
+            };
+            if image.mime_type != "image/png" {
+                continue;
+            }
+            let stored = match BASE64.decode(&image.data) {
+                Ok(png) => store.put(png, tool, url.clone()).await,
+                Err(e) => Err(e.into()),
+            };
+            match stored {
+                Ok(stored) => ids.push(stored.id),
+                Err(e) => warn!("Failed to store screenshot: {}", e),
+            }
+        }
+
+        if ids.is_empty() || response.is_none() {
+            return;
+        }
+        let (name, value) = match ids.as_slice() {
+            [id] => ("screenshot_id", serde_json::json!(id)),
+            _ => ("screenshot_ids", serde_json::json!(ids)),
+        };
+        append_response_field(result, name, value);
+    }
+
+    /// Drop the screenshot of an action's result as `MCP_SCREENSHOT_POLICY`
+    /// says. `include` is the call's `include_screenshot` argument.
+    async fn apply_screenshot_policy(
+        &self,
+        tab: Option<&str>,
+        include: bool,
+        result: &mut CallToolResult,
+    ) {
+        let keep = match self.config.screenshot_policy {
+            ScreenshotPolicy::Always => return,
+            ScreenshotPolicy::Never => false,
+            ScreenshotPolicy::OnDemand => include,
+            ScreenshotPolicy::OnChange => {
+                let url = response_json(result)
+                    .and_then(|response| response.get("url")?.as_str().map(String::from));
+                let screenshot = result
+                    .content
+                    .iter()
+                    .find_map(|content| content.as_image())
+                    .map(|image| image.data.clone());
+                let (Some(url), Some(screenshot)) = (url, screenshot) else {
+                    return;
+                };
+                let key = tab.unwrap_or_default().to_string();
+                let unchanged = self.screenshot_unchanged(key, &url, &screenshot).await;
+                if unchanged {
+                    append_response_field(result, "unchanged", serde_json::json!(true));
+                }
+                !unchanged
+            }
+        };
+        if !keep {
+            result
+                .content
+                .retain(|content| content.as_image().is_none());
+        }
+    }
+
+    /// Open a page in a background tab and start reloading it.
+    async fn start_watch(
+        &self,
+        params: &WatchPageParams,
+        peer: Peer<RoleServer>,
+    ) -> anyhow::Result<WatchStatus> {
+        self.before_navigation(&params.url).await?;
+        let selector = params.selector.as_deref();
+        let tab = self.browser.open_background_tab(&params.url).await?;
+        let first = match self
+            .browser
+            .read_background_tab(&tab, selector, false)
+            .await
+        {
+            Ok(first) => first,
+            Err(e) => {
+                if let Err(e) = self.browser.close_background_tab(&tab).await {
+                    debug!("Failed to close tab {}: {}", tab, e);
+                }
+                return Err(e);
+            }
+        };
+        let watch = self.watches.add(
+            &normalize_url(&params.url),
+            &tab,
+            selector,
+            params.interval,
+            first,
+        );
+        let task = tokio::spawn(run_watch(
+            Arc::clone(&self.browser),
+            Arc::clone(&self.action_queue),
+            Arc::clone(&self.politeness),
+            Arc::clone(&self.watches),
+            Arc::clone(&self.last_activity),
+            watch.clone(),
+            peer,
+        ));
+        self.watch_tasks.lock().await.insert(
+            watch.id.clone(),
+            WatchTask {
+                browser: Arc::clone(&self.browser),
+                action_queue: Arc::clone(&self.action_queue),
+                tab,
+                task,
+            },
+        );
+        Ok(watch)
+    }
+
+    /// Launch a separate browser with a fresh profile and register it under an ID.
+    async fn create_named_browser(&self, id: &str) -> anyhow::Result<EnvState> {
+        if id.is_empty() || id == DEFAULT_BROWSER_ID {
+            return Err(anyhow::anyhow!(
+                "Browser ID must not be empty or '{}'",
+                DEFAULT_BROWSER_ID
+            ));
+        }
+        // Never attach to the browser the default one uses, and keep launched
+        // browsers from sharing a profile
+        let mut config = (*self.config).clone();
+        config.cdp_url = None;
+        config.attached_mode = false;
+        let profile_dir = (config.connection_mode == ConnectionMode::Cdp).then(|| {
+            std::env::temp_dir().join(format!(
+                "mcp-computer-use-{}-{}",
+                std::process::id(),
+                next_request_id()
+            ))
+        });
+        config.profile_dir = profile_dir.clone();
+        config.download_dir = Some(self.scratch.downloads_dir());
+        let named = NamedBrowser {
+            browser: Arc::new(BrowserBackend::new(config)),
+            action_queue: Arc::new(ActionQueue::new()),
+            last_screenshots: Arc::new(Mutex::new(HashMap::new())),
+            profile_dir,
+        };
+
+        // Reserve the ID first, so the launch does not hold up other browsers
+        {
+            let mut browsers = self.browsers.lock().await;
+            if browsers.contains_key(id) {
+                return Err(anyhow::anyhow!("A browser with ID '{}' already exists", id));
+            }
+            if browsers.len() >= MAX_BROWSERS {
+                return Err(anyhow::anyhow!(
+                    "At most {} browsers can be created; destroy one first",
+                    MAX_BROWSERS
+                ));
+            }
+            browsers.insert(id.to_string(), named.clone());
+        }
+        match named.browser.open().await {
+            Ok(state) => Ok(state),
+            Err(e) => {
+                self.browsers.lock().await.remove(id);
+                let _ = named.close().await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Find a visible menu item by its label, waiting up to `timeout` for it
+    /// to appear.
+    async fn wait_for_menu_item(
+        &self,
+        label: &str,
+        timeout: Duration,
+    ) -> anyhow::Result<LocatedElement> {
+        let locator = Locator::Text(label.to_string());
+        let started = Instant::now();
+        loop {
+            match self.browser.locate_element(&locator).await {
+                Ok(element) => return Ok(element),
+                Err(e) if started.elapsed() >= timeout => return Err(e),
+                Err(_) => tokio::time::sleep(MENU_POLL_INTERVAL).await,
+            }
+        }
+    }
+
+    /// Hover through the items of a nested menu by their labels, clicking a
+    /// level whose submenu does not open on hover, and click or hover over
+    /// the last item. Returns a summary and the final state.
+    async fn walk_menu_path(
+        &self,
+        labels: &[String],
+        timeout: Duration,
+        click_last: bool,
+    ) -> anyhow::Result<(String, EnvState)> {
+        let mut item = self
+            .wait_for_menu_item(&labels[0], timeout)
+            .await
+            .map_err(|e| anyhow::anyhow!("Level 1: {}", e))?;
+        for (level, label) in labels.iter().enumerate().skip(1) {
+            let parent = &labels[level - 1];
+            self.browser.hover_at(item.x, item.y, None).await?;
+            item = match self.wait_for_menu_item(label, timeout).await {
+                Ok(next) => next,
+                Err(_) => {
+                    // Menu bars and many dropdowns open on click only
+                    debug!("'{}' did not open on hover, clicking it", parent);
+                    self.browser.click_at(item.x, item.y).await?;
+                    self.wait_for_menu_item(label, timeout).await.map_err(|e| {
+                        anyhow::anyhow!(
+                            "Level {}: {} after hovering over and clicking '{}'",
+                            level + 1,
+                            e,
+                            parent
+                        )
+                    })?
+                }
+            };
+        }
+
+        let state = if click_last {
+            self.browser.click_at(item.x, item.y).await?
+        } else {
+            self.browser.hover_at(item.x, item.y, None).await?
+        };
+        let (x, y) = self.screenshot_point(item.x, item.y);
+        let message = format!(
+            "{} {} at ({}, {})",
+            if click_last {
+                "Clicked"
+            } else {
+                "Hovered over"
+            },
+            labels.join(" > "),
+            x,
+            y
+        );
+        Ok((message, state))
+    }
+
+    /// Walk through an identity provider's sign-in pages until the browser leaves
+    /// the provider or a page needs something the steps cannot provide.
+    /// Returns a summary of the steps taken and the final state.
+    async fn complete_oauth(
+        &self,
+        site: &str,
+        approve_consent: bool,
+    ) -> anyhow::Result<(String, EnvState)> {
+        let path = self.config.credentials_file.as_deref().ok_or_else(|| {
+            anyhow::anyhow!("No saved credentials: MCP_CREDENTIALS_FILE is not set")
+        })?;
+        let store = CredentialStore::load(path)?;
+        let credentials = store.get(site)?;
+        let username = credentials.username()?;
+        let password = credentials.password().await?;
+
+        let mut provider: Option<Provider> = None;
+        let mut steps: Vec<&str> = Vec::new();
+        let mut previous: Option<OAuthStep> = None;
+        let (completed, outcome) = loop {
+            if steps.len() >= MAX_OAUTH_STEPS {
+                break (false, "the provider kept asking for more steps".to_string());
+            }
+            let step = self
+                .browser
+                .oauth_step(credentials, username.as_deref(), &password, approve_consent)
+                .await?;
+            let Some(current) = step.provider else {
+                match provider {
+                    Some(_) => break (true, format!("returned to {}", step.host)),
+                    None => {
+                        return Err(anyhow::anyhow!(
+                            "The page ({}) is not a Google, Microsoft or Okta sign-in page",
+                            step.host
+                        ))
+                    }
+                }
+            };
+            provider = Some(current);
+            if previous.as_ref() == Some(&step) {
+                break (
+                    false,
+                    format!(
+                        "the {} step on {} did not advance; the saved credentials may be wrong",
+                        steps.last().copied().unwrap_or("first"),
+                        step.host
+                    ),
+                );
+            }
+            match step.action {
+                StepAction::Username => steps.push("username"),
+                StepAction::Password => steps.push("password"),
+                StepAction::Account => steps.push("account"),
+                StepAction::Consent => steps.push("consent"),
+                StepAction::ConsentPending => {
+                    break (false, "stopped at the consent screen".to_string())
+                }
+                StepAction::NeedsCredentials => {
+                    break (
+                        false,
+                        format!(
+                            "{} is not one of the domains saved for '{}'",
+                            step.host, site
+                        ),
+                    )
+                }
+                StepAction::None => {
+                    break (
+                        false,
+                        format!(
+                            "{} needs input the tool cannot provide, such as a second factor",
+                            step.host
+                        ),
+                    )
+                }
+            }
+            previous = Some(step);
+        };
+
+        let name = provider.map(Provider::name).unwrap_or("the provider");
+        let steps = match steps.is_empty() {
+            true => "none".to_string(),
+            false => steps.join(", "),
+        };
+        let message = match completed {
+            true => format!("Signed in with {} ({}), {}", name, steps, outcome),
+            false => format!(
+                "Sign-in with {} incomplete after steps: {}; {}",
+                name, steps, outcome
+            ),
+        };
+        Ok((message, self.browser.current_state().await?))
+    }
+
+    /// Get the duration since last activity.
+    #[allow(dead_code)]
+    fn idle_duration(&self) -> Duration {
+        let last = self.last_activity.load(Ordering::Acquire);
+        let now = current_timestamp();
+        Duration::from_secs(now.saturating_sub(last))
+    }
+
+    /// Start the idle timeout monitor if configured.
+    /// This spawns a background task that closes the browser after idle timeout.
+    /// If a monitor is already running, this function does nothing.
+    pub async fn start_idle_monitor(&self) {
+        let idle_timeout = self.config.idle_timeout;
+
+        // If idle timeout is zero, don't start the monitor
+        if idle_timeout.is_zero() {
+            debug!("Idle timeout is disabled (set to 0)");
+            return;
+        }
+
+        // Acquire lock and hold it while checking and spawning to prevent race conditions
+        let mut guard = self.idle_monitor_handle.lock().await;
+        if guard.is_some() {
+            debug!("Idle monitor is already running, skipping start");
+            return;
+        }
+
+        let browser = Arc::clone(&self.browser);
+        let last_activity = Arc::clone(&self.last_activity);
+        let operation_in_progress = Arc::clone(&self.operation_in_progress);
+        // Check 4 times per timeout period, but at least once per second
+        // to avoid excessive polling for very short timeouts
+        let check_interval = (idle_timeout / 4).max(Duration::from_secs(1));
+
+        let handle = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(check_interval).await;
+
+                // Check if an operation is currently in progress first
+                if operation_in_progress.load(Ordering::Acquire) {
+                    continue;
+                }
+
+                let last = last_activity.load(Ordering::Acquire);
+                let now = current_timestamp();
+                let idle_secs = now.saturating_sub(last);
+
+                // Check idle time and atomically claim operation_in_progress
+                if idle_secs >= idle_timeout.as_secs() {
+                    // Atomically transition operation_in_progress from false to true.
+                    // If this fails, another operation has started and we should not close.
+                    let claimed = operation_in_progress
+                        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                        .is_ok();
+
+                    if !claimed {
+                        continue;
+                    }
+
+                    info!(
+                        "Browser idle for {}s (timeout: {}s), closing browser",
+                        idle_secs,
+                        idle_timeout.as_secs()
+                    );
+                    if let Err(e) = browser.close().await {
+                        warn!("Error closing browser due to idle timeout: {}", e);
+                    }
+
+                    // Clear the flag after closing
+                    operation_in_progress.store(false, Ordering::Release);
+                    break;
+                }
+            }
+        });
+
+        *guard = Some(handle);
+    }
+
+    /// Start the memory monitor if configured.
+    /// This spawns a background task that periodically logs a warning for every
+    /// exceeded memory threshold. If a monitor is already running, this function does nothing.
+    pub async fn start_memory_monitor(&self) {
+        let interval = self.config.memory_check_interval;
+
+        if interval.is_zero() {
+            debug!("Memory monitor is disabled (set to 0)");
+            return;
+        }
+
+        let mut guard = self.memory_monitor_handle.lock().await;
+        if guard.is_some() {
+            debug!("Memory monitor is already running, skipping start");
+            return;
+        }
+
+        let browser = Arc::clone(&self.browser);
+        let config = Arc::clone(&self.config);
+
+        let handle = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+
+                let stats = collect_browser_stats(&browser, &config).await;
+                for warning in &stats.warnings {
+                    warn!("{}", warning);
+                }
+            }
+        });
+
+        *guard = Some(handle);
+    }
+
+    /// Initialize the server, optionally opening the browser if configured.
+    /// Call this after construction if `open_browser_on_start` is enabled.
+    pub async fn init(&self) -> anyhow::Result<()> {
+        if self.config.open_browser_on_start {
+            info!("Opening browser on server start (MCP_OPEN_BROWSER_ON_START=true)");
+            // Note: touch() and start_idle_monitor() are only called if open() succeeds
+            // due to the ? operator returning early on error
+            self.browser.open().await?;
+            self.touch();
+            self.operation_complete();
+            // Start idle monitor only after browser is actually opened
+            self.start_idle_monitor().await;
+            self.start_memory_monitor().await;
+        }
+
+        Ok(())
+    }
+
+    /// Shutdown the server and close the browser.
+    /// This should be called before the program exits when auto_start is enabled
+    /// to ensure the browser is properly closed.
+    pub async fn shutdown(&self) -> anyhow::Result<()> {
+        info!("Shutting down MCP server, closing browser...");
+
+        // Cancel idle monitor if running
+        let mut guard = self.idle_monitor_handle.lock().await;
+        if let Some(handle) = guard.take() {
+            handle.abort();
+        }
+        drop(guard);
+
+        // Cancel memory monitor if running
+        let mut guard = self.memory_monitor_handle.lock().await;
+        if let Some(handle) = guard.take() {
+            handle.abort();
+        }
+        drop(guard);
+
+        if let Some(handle) = self.event_notifier.lock().await.take() {
+            handle.abort();
+        }
+
+        for (_, watch) in self.watch_tasks.lock().await.drain() {
+            watch.task.abort();
+        }
+        for (id, named) in self.browsers.lock().await.drain() {
+            if let Err(e) = named.close().await {
+                warn!("Error closing browser '{}': {}", id, e);
+            }
+        }
+        self.browser.close().await
+    }
+
+    /// Get a reference to the browser backend.
+    #[allow(dead_code)]
+    pub fn browser(&self) -> &Arc<BrowserBackend> {
+        &self.browser
+    }
+}
+
+/// Collect browser stats, treating unavailable page metrics as absent.
+async fn collect_browser_stats(browser: &BrowserBackend, config: &Config) -> BrowserStats {
+    let page = match browser.page_metrics().await {
+        Ok(metrics) => metrics,
+        Err(e) => {
+            debug!("Page metrics unavailable: {}", e);
+            None
+        }
+    };
+    BrowserStats::collect(page, config).await
+}
+
+/// Get the current timestamp in seconds since UNIX epoch.
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time should be after UNIX epoch")
+        .as_secs()
+}
+
+// Tool parameter types
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ClickAtParams {
+    /// X coordinate on the screen.
+    pub x: i64,
+    /// Y coordinate on the screen.
+    pub y: i64,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct HoverAtParams {
+    /// X coordinate on the screen.
+    pub x: i64,
+    /// Y coordinate on the screen.
+    pub y: i64,
+    /// Keep hovering for this many milliseconds before the screenshot is taken
+    /// (max 10000), for menus and tooltips that need a sustained hover.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for click_element.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ClickElementParams {
+    /// CSS selector of the element. Use either selector or xpath.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// XPath of the element, e.g. //button[normalize-space()='Save'].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xpath: Option<String>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for hover_element.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct HoverElementParams {
+    /// CSS selector of the element. Use either selector or xpath.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// XPath of the element.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xpath: Option<String>,
+    /// Keep hovering for this many milliseconds before the screenshot is taken
+    /// (max 10000).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for open_menu_path.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct OpenMenuPathParams {
+    /// Visible labels of the menu items from the top level down, e.g.
+    /// ["File", "Export", "PDF"]. Matched against the whole text or
+    /// aria-label of an item, ignoring case.
+    pub labels: Vec<String>,
+    /// How long each level waits for its item to appear, in milliseconds
+    /// (default 2000, max 10000).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level_timeout_ms: Option<u64>,
+    /// Click the last item (default true); false only hovers over it.
+    #[serde(default = "default_true")]
+    pub click_last: bool,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for type_in_element.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct TypeInElementParams {
+    /// CSS selector of the field. Use either selector or xpath.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// XPath of the field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xpath: Option<String>,
+    /// Text to type.
+    pub text: String,
+    /// Whether to press Enter after typing. Defaults to false.
+    #[serde(default)]
+    pub press_enter: bool,
+    /// Whether to clear existing content before typing. Defaults to true.
+    /// Ignored when mode is set.
+    #[serde(default = "default_true")]
+    pub clear_before_typing: bool,
+    /// Where to put the text, as in type_text_at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<InsertionMode>,
+    /// Send a key press for every character, as in type_text_at.
+    #[serde(default)]
+    pub simulate_keystrokes: bool,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct TypeTextAtParams {
+    /// X coordinate on the screen.
+    pub x: i64,
+    /// Y coordinate on the screen.
+    pub y: i64,
+    /// Text to type.
+    pub text: String,
+    /// Whether to press Enter after typing. Defaults to false.
+    #[serde(default)]
+    pub press_enter: bool,
+    /// Whether to clear existing content before typing. Defaults to true.
+    /// Ignored when mode is set.
+    #[serde(default = "default_true")]
+    pub clear_before_typing: bool,
+    /// Where to put the text: "append", "prepend", "replace_selection" or
+    /// "replace_all". Defaults to "replace_all", or "append" when
+    /// clear_before_typing is false.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<InsertionMode>,
+    /// Send a key press for every character, with a short pause in between, so
+    /// autocomplete and other key listeners react as they would to a person
+    /// typing. Slower for long text. Defaults to false.
+    #[serde(default)]
+    pub simulate_keystrokes: bool,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ScrollDocumentParams {
+    /// Direction to scroll: "up", "down", "left", or "right".
+    pub direction: String,
+    /// Distance as a multiple of the viewport height (up/down) or width (left/right),
+    /// e.g. 0.5 for half a page or 3 for three pages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_pages: Option<f64>,
+    /// Distance in pixels. Cannot be combined with amount_pages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pixels: Option<i64>,
+    /// Animate the scroll like a user would. The screenshot is taken once
+    /// scrolling has finished either way.
+    #[serde(default)]
+    pub smooth: bool,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ScrollAtParams {
+    /// X coordinate on the screen.
+    pub x: i64,
+    /// Y coordinate on the screen.
+    pub y: i64,
+    /// Direction to scroll: "up", "down", "left", or "right".
+    pub direction: String,
+    /// Magnitude of scroll in pixels. Defaults to 800.
+    #[serde(default = "default_magnitude")]
+    pub magnitude: i64,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ScrollToElementParams {
+    /// X coordinate of the element on the screen.
+    pub x: i64,
+    /// Y coordinate of the element on the screen.
+    pub y: i64,
+    /// Space in pixels to leave above the element. Defaults to the height of any
+    /// fixed or sticky header, detected automatically.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i64>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SelectTextParams {
+    /// X coordinate of the input or text element on the screen.
+    pub x: i64,
+    /// Y coordinate of the input or text element on the screen.
+    pub y: i64,
+    /// Character offset where the selection starts. Defaults to the start of the text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<u32>,
+    /// Character offset where the selection ends (exclusive). Defaults to the end
+    /// of the text. Use the same value as start to place the caret there.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<u32>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SelectAllInParams {
+    /// CSS selector of the input or element whose text to select.
+    pub selector: String,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct HighlightElementParams {
+    /// CSS selector of the element. Use either selector or x and y.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// X coordinate of the element on the screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<i64>,
+    /// Y coordinate of the element on the screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<i64>,
+    /// How long the highlight stays visible in milliseconds (default 3000, max 60000).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+impl HighlightElementParams {
+    /// The element to highlight: a selector or a point, but not both.
+    fn target(&self) -> Result<ElementTarget, String> {
+        match (&self.selector, self.x, self.y) {
+            (Some(selector), None, None) => Ok(ElementTarget::Selector(selector.clone())),
+            (None, Some(x), Some(y)) => Ok(ElementTarget::Point(x, y)),
+            _ => Err("Provide either selector or both x and y".to_string()),
+        }
+    }
+}
+
+/// Parameters for set_color.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SetColorParams {
+    /// Color to set, as #rrggbb or #rgb.
+    pub color: String,
+    /// CSS selector of the color input. Use either selector or x and y.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// X coordinate of the color input (or its label) on the screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<i64>,
+    /// Y coordinate of the color input (or its label) on the screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<i64>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for set_range.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SetRangeParams {
+    /// Value to set; clamped to the slider's min and max and snapped to its step.
+    pub value: f64,
+    /// CSS selector of the range input. Use either selector or x and y.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// X coordinate of the slider on the screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<i64>,
+    /// Y coordinate of the slider on the screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<i64>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// The input a set_color or set_range call targets: a selector or a point,
+/// but not both.
+fn control_target(
+    selector: &Option<String>,
+    x: Option<i64>,
+    y: Option<i64>,
+) -> Result<ElementTarget, String> {
+    match (selector, x, y) {
+        (Some(selector), None, None) => Ok(ElementTarget::Selector(selector.clone())),
+        (None, Some(x), Some(y)) => Ok(ElementTarget::Point(x, y)),
+        _ => Err("Provide either selector or both x and y".to_string()),
+    }
+}
+
+/// Parameters for remember_element.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct RememberElementParams {
+    /// Name to remember the element under, e.g. "save_button". Replaces an
+    /// element remembered under the same name.
+    pub name: String,
+    /// CSS selector of the element. Use either selector or x and y.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// X coordinate of the element on the screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<i64>,
+    /// Y coordinate of the element on the screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<i64>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+impl RememberElementParams {
+    /// The element to remember: a selector or a point, but not both.
+    fn target(&self) -> Result<ElementTarget, String> {
+        match (&self.selector, self.x, self.y) {
+            (Some(selector), None, None) => Ok(ElementTarget::Selector(selector.clone())),
+            (None, Some(x), Some(y)) => Ok(ElementTarget::Point(x, y)),
+            _ => Err("Provide either selector or both x and y".to_string()),
+        }
+    }
+}
+
+/// Response type for remember_element.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct RememberElementResponse {
+    /// Name the element was remembered under.
+    pub name: String,
+    /// What identifies the element when it is looked up again.
+    pub element: ElementFingerprint,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Parameters for click_named.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ClickNamedParams {
+    /// Name given to the element with remember_element.
+    pub name: String,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+fn default_magnitude() -> i64 {
+    800
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct NavigateParams {
+    /// URL to navigate to. Will be prefixed with "https://" if no protocol specified.
+    pub url: String,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SearchParams {
+    /// Query to search for. Without it, the search engine's home page is opened.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// Name of the search engine to use, e.g. "google", "bing" or "duckduckgo".
+    /// Defaults to the configured default engine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WebSearchParams {
+    /// Search query.
+    pub query: String,
+    /// Name of the search engine to use, e.g. "google", "bing" or "duckduckgo".
+    /// Defaults to the configured default engine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+    /// Maximum number of results to return (default: 10, max: 50).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_results: Option<usize>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Response type for web search operation.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WebSearchResponse {
+    /// The query that was searched.
+    pub query: String,
+    /// Results in the order the search engine listed them.
+    pub results: Vec<SearchResult>,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct KeyCombinationParams {
+    /// List of keys to press together. Example: ["Control", "c"] or ["ctrl+c"] for
+    /// Ctrl+C. "Primary" is Command on macOS and Control elsewhere.
+    pub keys: Vec<String>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for shortcut.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ShortcutParams {
+    /// Preset to perform.
+    pub name: Shortcut,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PressKeysSequenceParams {
+    /// Steps to perform in order. Example: [{"keys": ["Tab"]}, {"keys": ["Tab"]},
+    /// {"keys": ["Enter"], "delay_ms": 500}].
+    pub steps: Vec<KeyStep>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for tab_to_element.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct TabToElementParams {
+    /// Visible text, value, placeholder, label or aria-label of the element,
+    /// ignoring case. Use either text or selector.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// CSS selector of the element, or of an element containing it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Move focus backwards with Shift+Tab.
+    #[serde(default)]
+    pub backwards: bool,
+    /// Most Tab presses before giving up (default 50, max 200).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_presses: Option<u32>,
+    /// Key pressed once the element has focus: auto (default; Space for
+    /// checkboxes, radio buttons and switches, nothing for text fields and
+    /// selects, Enter otherwise), enter, space, or none.
+    #[serde(default)]
+    pub activate: Activation,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DragAndDropParams {
+    /// Starting X coordinate.
+    pub x: i64,
+    /// Starting Y coordinate.
+    pub y: i64,
+    /// Destination X coordinate.
+    pub destination_x: i64,
+    /// Destination Y coordinate.
+    pub destination_y: i64,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for draw_path.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DrawPathParams {
+    /// Points to draw through, e.g. [{"x": 100, "y": 200}, {"x": 140, "y": 180}].
+    /// The pointer is pressed at the first and released at the last.
+    pub points: Vec<PathPoint>,
+    /// Time the whole path takes in milliseconds (default 500, max 10000).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Pointer to draw with: mouse (default) or touch.
+    #[serde(default)]
+    pub pointer: PointerType,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for actions that take no arguments besides the target tab.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct TabParams {
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for current_state.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CurrentStateParams {
+    /// Tab handle to look at (from list_tabs), without making it the active
+    /// tab. The response then names the tab and its title. Defaults to the
+    /// active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+    /// Omit the screenshot and return `unchanged: true` when the page looks the
+    /// same as in the previous current_state call. Useful when polling.
+    #[serde(default)]
+    pub skip_if_unchanged: bool,
+    /// Add thin, half-scale previews of the content just above and below the
+    /// viewport, to tell whether scrolling would reveal anything relevant.
+    #[serde(default)]
+    pub context_strips: bool,
+}
+
+/// Parameters for get_notifications.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetNotificationsParams {
+    /// Remove the returned notifications, so the next call only reports new ones.
+    #[serde(default)]
+    pub clear: bool,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Response type for get_notifications.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct NotificationsResponse {
+    /// Notifications and permission prompts, oldest first.
+    pub notifications: Vec<CapturedNotification>,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Parameters for capture_video_frame.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CaptureVideoFrameParams {
+    /// CSS selector of the video, or of an element containing it. Defaults to
+    /// the first playing video, else the first video.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Response type for capture_video_frame; the frame follows as an image.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct VideoFrameResponse {
+    /// Index of the video among the media elements, as in get_media.
+    pub index: u32,
+    /// Size of the frame image.
+    pub width: u32,
+    pub height: u32,
+    /// Playback position of the frame, in seconds.
+    pub current_time: f64,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Parameters for evaluate_javascript.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct EvaluateJavascriptParams {
+    /// JavaScript expression to evaluate, e.g. `document.querySelectorAll('tr').length`.
+    /// Wrap statements in an immediately invoked function.
+    pub expression: String,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Response type for evaluate_javascript.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct EvaluateJavascriptResponse {
+    /// The value of the expression as JSON. When truncated, the start of its
+    /// JSON text as a string.
+    pub result: serde_json::Value,
+    /// Type of the value: string, number, boolean, object, array, null or undefined.
+    #[serde(rename = "type")]
+    pub result_type: String,
+    /// Whether the result was cut off.
+    pub truncated: bool,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Parameters for get_media.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetMediaParams {
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for control_media.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ControlMediaParams {
+    /// What to do: play, pause, mute or unmute.
+    pub action: MediaCommand,
+    /// Index of the media element (from get_media). Defaults to all of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Response type for get_media and control_media.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct MediaResponse {
+    /// Audio and video elements in document order.
+    pub media: Vec<MediaElement>,
+    /// Whether any element is playing sound the page can hear.
+    pub audible: bool,
+    /// Whether the browser was started muted (MCP_MUTE_AUDIO), in which
+    /// case nothing is heard outside the page either way.
+    pub browser_muted: bool,
+    /// Message describing what was done.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+impl MediaResponse {
+    fn new(media: Vec<MediaElement>, browser_muted: bool, message: Option<String>) -> Self {
+        Self {
+            audible: media.iter().any(MediaElement::audible),
+            media,
+            browser_muted,
+            message,
+            success: true,
+        }
+    }
+}
+
+// Tab operation parameter types
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct NewTabParams {
+    /// Optional URL to navigate to in the new tab.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CloseTabParams {
+    /// The window handle of the tab to close. If not provided, closes the current tab.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handle: Option<String>,
+}
+
+/// Parameters for switching to a tab.
+/// Exactly one of `handle` or `index` must be provided.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct SwitchTabParams {
+    /// The window handle of the tab to switch to.
+    /// Exactly one of `handle` or `index` must be provided.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handle: Option<String>,
+    /// The index of the tab to switch to (0-based).
+    /// Exactly one of `handle` or `index` must be provided.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+}
+
+// Custom deserialization to enforce that exactly one of `handle` or `index` is provided.
+impl<'de> serde::Deserialize<'de> for SwitchTabParams {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawSwitchTabParams {
+            handle: Option<String>,
+            index: Option<usize>,
+        }
+
+        let raw = RawSwitchTabParams::deserialize(deserializer)?;
+
+        match (&raw.handle, &raw.index) {
+            (Some(_), Some(_)) => Err(serde::de::Error::custom(
+                "Provide exactly one of 'handle' or 'index', not both",
+            )),
+            (None, None) => Err(serde::de::Error::custom(
+                "Provide either 'handle' or 'index'",
+            )),
+            _ => Ok(SwitchTabParams {
+                handle: raw.handle,
+                index: raw.index,
+            }),
+        }
+    }
+}
+
+/// Response type for tab list operation.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct TabListResponse {
+    /// List of all open tabs.
+    pub tabs: Vec<TabInfo>,
+    /// Whether the operation was successful.
+    pub success: bool,
+    /// Optional message describing the result.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Parameters for attach_to_tab.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct AttachToTabParams {
+    /// The handle of the page target to attach to, as reported by list_targets.
+    pub handle: String,
+}
+
+/// Response type for target list operation.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct TargetListResponse {
+    /// Page targets of the connected browser.
+    pub targets: Vec<PageTarget>,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+// Frame operation parameter types
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SwitchFrameParams {
+    /// Index of the frame to switch into, as reported by list_frames.
+    /// Omit to return to the top-level document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+}
+
+/// Response type for frame list operation.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct FrameListResponse {
+    /// Frames in the currently selected document.
+    pub frames: Vec<FrameInfo>,
+    /// Whether the operation was successful.
+    pub success: bool,
+    /// Optional message describing the result.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Response type for page metadata operation.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PageMetadataResponse {
+    /// Metadata extracted from the page.
+    pub metadata: PageMetadata,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Response type for browser stats operation.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct BrowserStatsResponse {
+    /// Browser resource usage.
+    pub stats: BrowserStats,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Parameters for create_browser and destroy_browser.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct BrowserIdParams {
+    /// ID of the browser, passed as `browser_id` to other tools to act on it.
+    pub browser_id: String,
+}
+
+/// Parameters for audit_accessibility.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct AuditAccessibilityParams {
+    /// CSS selector of the element to audit. Defaults to the whole document.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Response type for audit_accessibility.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct AccessibilityAuditResponse {
+    /// Rules the page violates, as reported by axe-core.
+    pub violations: Vec<Violation>,
+    /// Number of rules the page passes.
+    pub passes: usize,
+    /// Number of rules that need a human to review.
+    pub incomplete: usize,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Parameters for check_links.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CheckLinksParams {
+    /// Which links to check: "all" (default), "internal" (same origin as the
+    /// page) or "external".
+    #[serde(default)]
+    pub scope: LinkScope,
+    /// CSS selector of the element whose links are checked. Defaults to the
+    /// whole document.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Response type for check_links.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CheckLinksResponse {
+    /// URL of the checked page.
+    pub url: String,
+    /// Number of distinct links in scope.
+    pub found: usize,
+    /// Number of links checked, at most 200.
+    pub checked: usize,
+    /// Number of checked links that failed or answered with an error status.
+    pub broken: usize,
+    /// Checked links in page order.
+    pub links: Vec<LinkStatus>,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Parameters for compare_screenshot.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CompareScreenshotParams {
+    /// ID of the baseline (letters, digits, '-' and '_'). The first comparison
+    /// under an ID stores the current screenshot as its baseline.
+    pub baseline_id: String,
+    /// Percentage of pixels allowed to change before the comparison fails.
+    /// Defaults to 0.1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f64>,
+    /// Replace the baseline with the current screenshot after comparing.
+    #[serde(default)]
+    pub update_baseline: bool,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for ocr_screenshot.
+#[cfg(feature = "ocr")]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct OcrScreenshotParams {
+    /// Tesseract language codes to recognize, e.g. ["eng", "deu"] or
+    /// ["chi_sim"]. Defaults to MCP_OCR_LANGUAGES.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub languages: Option<Vec<String>>,
+    /// Minimum confidence (0-100) of the words returned. Defaults to 40.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_confidence: Option<f32>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Response type for ocr_screenshot.
+#[cfg(feature = "ocr")]
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct OcrScreenshotResponse {
+    /// URL of the page.
+    pub url: String,
+    /// Languages the text was recognized in.
+    pub languages: Vec<String>,
+    /// Recognized lines of text, top to bottom.
+    pub lines: Vec<ocr::OcrLine>,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Response type for compare_screenshot.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CompareScreenshotResponse {
+    /// ID of the baseline.
+    pub baseline_id: String,
+    /// URL of the page.
+    pub url: String,
+    /// Whether no baseline existed and the current screenshot became it.
+    pub created: bool,
+    /// Whether the changed share of pixels is within the threshold.
+    pub matches: bool,
+    /// Number of changed pixels.
+    pub changed_pixels: u64,
+    /// Changed pixels as a percentage of all pixels.
+    pub changed_percent: f64,
+    /// Threshold the comparison used, in percent.
+    pub threshold: f64,
+    /// Whether the screenshot and the baseline differ in size.
+    pub size_changed: bool,
+    /// Changed regions in screenshot pixels, largest first.
+    pub regions: Vec<Region>,
+    /// Whether the baseline was replaced with the current screenshot.
+    pub baseline_updated: bool,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Parameters for get_screenshot.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetScreenshotParams {
+    /// ID of the screenshot, from the `screenshot_id` of an earlier response.
+    pub id: String,
+}
+
+/// Response type for get_screenshot.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetScreenshotResponse {
+    /// The stored screenshot.
+    pub screenshot: StoredScreenshot,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Parameters for set_emulated_media.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SetEmulatedMediaParams {
+    /// CSS media type to render with: "print" or "screen". Unchanged when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<MediaType>,
+    /// Make `prefers-reduced-motion: reduce` match (true) or not (false).
+    /// Unchanged when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reduced_motion: Option<bool>,
+    /// Make `forced-colors: active` match (true) or not (false). Unchanged when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forced_colors: Option<bool>,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for set_vision_deficiency.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SetVisionDeficiencyParams {
+    /// Deficiency to emulate: "deuteranopia", "protanopia", "tritanopia",
+    /// "achromatopsia", "blurred_vision", "reduced_contrast", or "none" to stop.
+    #[serde(rename = "type")]
+    pub deficiency: VisionDeficiency,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for watch_page.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WatchPageParams {
+    /// URL to watch.
+    pub url: String,
+    /// Seconds between reloads (at least 10).
+    pub interval: u64,
+    /// CSS selector of the element to watch. Defaults to the whole page.
+    #[serde(default)]
+    pub selector: Option<String>,
+}
+
+/// Response type for watch_page.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WatchResponse {
+    /// The new watch.
+    pub watch: WatchStatus,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Parameters for unwatch_page.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct UnwatchPageParams {
+    /// ID of the watch, from watch_page.
+    pub id: String,
+}
+
+/// Parameters for subscribe_events.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SubscribeEventsParams {
+    /// Event types to collect: navigation, dialog, download, console, target.
+    /// Replaces the previous subscription; an empty list stops collecting.
+    pub types: Vec<EventType>,
+}
+
+/// Parameters for poll_events.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PollEventsParams {
+    /// Maximum number of events to return, oldest first (default: 100).
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Response type for subscribe_events and poll_events.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct EventsResponse {
+    /// Whether the operation was successful.
+    pub success: bool,
+    /// Resource URI of the event buffer, to subscribe to for notifications.
+    pub uri: String,
+    #[serde(flatten)]
+    pub batch: EventBatch,
+}
+
+/// Parameters for save_checkpoint.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SaveCheckpointParams {
+    /// Name to save the checkpoint under; replaces an existing checkpoint with the same name.
+    pub name: String,
+    /// Also save the page's localStorage and sessionStorage.
+    #[serde(default)]
+    pub include_storage: bool,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for restore_checkpoint.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct RestoreCheckpointParams {
+    /// Name of a checkpoint saved with save_checkpoint.
+    pub name: String,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Response type for save checkpoint operation.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CheckpointResponse {
+    /// The saved checkpoint.
+    pub checkpoint: CheckpointSummary,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Parameters for save_workspace and load_workspace.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WorkspaceParams {
+    /// Name of the workspace: letters, digits, '-' or '_'.
+    pub name: String,
+}
+
+/// Response type for save workspace operation.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WorkspaceResponse {
+    /// The saved workspace.
+    pub workspace: WorkspaceSummary,
+    /// File the workspace was written to.
+    pub path: String,
+    /// Tabs or state that could not be saved.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Response type for list_workspace_files.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WorkspaceFilesResponse {
+    /// The session's scratch directory.
+    pub directory: String,
+    /// Files in the directory, sorted by path.
+    pub files: Vec<ScratchFile>,
+    /// Files just deleted for breaking the operator's file limits.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<RemovedFile>,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Parameters for login_with_saved_credentials.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct LoginParams {
+    /// Name of the site whose saved credentials to use.
+    pub site: String,
+    /// Whether to submit the form after filling it. Default: true.
+    #[serde(default = "default_true")]
+    pub submit: bool,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for complete_oauth_login.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct OAuthLoginParams {
+    /// Name of the site whose saved credentials sign in to the identity provider.
+    pub site: String,
+    /// Whether to accept the consent screen asking to share the account with the app.
+    /// Default: true. When false, the sign-in stops at the consent screen.
+    #[serde(default = "default_true")]
+    pub approve_consent: bool,
+    /// Tab handle to act on (from list_tabs). Defaults to the active tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<String>,
+}
+
+/// Parameters for get_action_history.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ActionHistoryParams {
+    /// Maximum number of most recent actions to return. Returns the whole history when omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// Response type for action history operation.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ActionHistoryResponse {
+    /// Recent actions, oldest first.
+    pub actions: Vec<ActionRecord>,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Response type for DOM snapshot operation.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DomSnapshotResponse {
+    /// The compact DOM snapshot.
+    pub snapshot: DomSnapshot,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Response type for grounding_snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GroundingSnapshotResponse {
+    /// URL of the page.
+    pub url: String,
+    /// Width of the screenshot in pixels.
+    pub image_width: u32,
+    /// Height of the screenshot in pixels.
+    pub image_height: u32,
+    /// Interactable elements visible in the screenshot, in document order.
+    pub elements: Vec<GroundingElement>,
+    /// Whether the operation was successful.
+    pub success: bool,
+}
+
+/// Response type for new tab operation.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct NewTabResponse {
+    /// Information about the newly created tab.
+    pub tab: TabInfo,
+    /// Whether the operation was successful.
+    pub success: bool,
+    /// Optional message describing the result.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[tool_router]
+impl BrowserMcpServer {
+    /// Opens the web browser and returns the current state.
+    #[tool(
+        description = "Opens the web browser. Call this first before any other browser actions."
+    )]
+    async fn open_web_browser(&self) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::OPEN_WEB_BROWSER) {
+            return disabled_tool_error(tool_names::OPEN_WEB_BROWSER);
+        }
+        self.touch();
+        info!("Opening web browser");
+        let result = self.browser.open().await;
+        let tool_result = match &result {
+            Ok(state) => env_state_to_result(state.clone(), Some("Browser opened successfully")),
+            Err(e) => error_to_result(&format!("Failed to open browser: {}", e)),
+        };
+        self.operation_complete();
+
+        // Start idle monitor after operation is complete (only if browser opened successfully)
+        if result.is_ok() {
+            self.start_idle_monitor().await;
+            self.start_memory_monitor().await;
+        }
+
+        tool_result
+    }
+
+    /// Clicks at a specific x, y coordinate on the webpage.
+    #[tool(
+        description = "Clicks at a specific x, y coordinate on the webpage. The coordinates are absolute values scaled to the screen dimensions. The response has effect_detected: false when the click changed nothing (no navigation, DOM change or focus change); try a different target instead of repeating it."
+    )]
+    async fn click_at(
+        &self,
+        Parameters(params): Parameters<ClickAtParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::CLICK_AT) {
+            return disabled_tool_error(tool_names::CLICK_AT);
+        }
+        self.touch();
+        info!("Clicking at ({}, {})", params.x, params.y);
+        let (x, y) = self.page_point(params.x, params.y);
+        let result = match self
+            .browser
+            .in_tab(params.tab.as_deref(), self.browser.click_at(x, y))
+            .await
+        {
+            Ok(state) => {
+                let message = if state.effect_detected == Some(false) {
+                    format!(
+                        "Clicked at ({}, {}), but nothing on the page changed",
+                        params.x, params.y
+                    )
+                } else {
+                    format!("Clicked at ({}, {})", params.x, params.y)
+                };
+                env_state_to_result(state, Some(&message))
+            }
+            Err(e) => error_to_result(&format!("Failed to click: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Hovers at a specific x, y coordinate on the webpage.
+    #[tool(
+        description = "Hovers at a specific x, y coordinate on the webpage. May be used to explore sub-menus that appear on hover. Set duration_ms to keep hovering before the screenshot, and use move_mouse_away to end the hover."
+    )]
+    async fn hover_at(
+        &self,
+        Parameters(params): Parameters<HoverAtParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::HOVER_AT) {
+            return disabled_tool_error(tool_names::HOVER_AT);
+        }
+        self.touch();
+        info!("Hovering at ({}, {})", params.x, params.y);
+        let (x, y) = self.page_point(params.x, params.y);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.hover_at(x, y, params.duration_ms),
+            )
+            .await
+        {
+            Ok(state) => env_state_to_result(
+                state,
+                Some(&format!("Hovered at ({}, {})", params.x, params.y)),
+            ),
+            Err(e) => error_to_result(&format!("Failed to hover: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Ends a hover by moving the mouse away.
+    #[tool(
+        description = "Moves the mouse off the element last hovered with hover_at, so hover menus and tooltips close."
+    )]
+    async fn move_mouse_away(
+        &self,
+        Parameters(params): Parameters<TabParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::MOVE_MOUSE_AWAY) {
+            return disabled_tool_error(tool_names::MOVE_MOUSE_AWAY);
+        }
+        self.touch();
+        info!("Moving mouse away");
+        let result = match self
+            .browser
+            .in_tab(params.tab.as_deref(), self.browser.move_mouse_away())
+            .await
+        {
+            Ok(state) => env_state_to_result(state, Some("Moved mouse away")),
+            Err(e) => error_to_result(&format!("Failed to move mouse away: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Types text at a specific x, y coordinate.
+    #[tool(
+        description = "Types text at a specific x, y coordinate. The system can optionally press ENTER after typing. By default existing content is replaced; mode can instead append, prepend, or replace only the current selection (see select_text). Set simulate_keystrokes when autocomplete or key listeners must see each character."
+    )]
+    async fn type_text_at(
+        &self,
+        Parameters(params): Parameters<TypeTextAtParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::TYPE_TEXT_AT) {
+            return disabled_tool_error(tool_names::TYPE_TEXT_AT);
+        }
+        self.touch();
+        info!("Typing at ({}, {}): {}", params.x, params.y, params.text);
+        let (x, y) = self.page_point(params.x, params.y);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.type_text_at(
+                    x,
+                    y,
+                    &params.text,
+                    params.press_enter,
+                    params.mode.unwrap_or(if params.clear_before_typing {
+                        InsertionMode::ReplaceAll
+                    } else {
+                        InsertionMode::Append
+                    }),
+                    params.simulate_keystrokes,
+                ),
+            )
+            .await
+        {
+            Ok(state) => env_state_to_result(
+                state,
+                Some(&format!(
+                    "Typed '{}' at ({}, {})",
+                    params.text, params.x, params.y
+                )),
+            ),
+            Err(e) => error_to_result(&format!("Failed to type: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Clicks an element found by CSS selector or XPath.
+    #[tool(
+        description = "Clicks the first visible element matching a CSS selector or an XPath, scrolling it into view first. Use instead of click_at when the element is known from the DOM. Reports the element clicked, the point used, and effect_detected: false when nothing changed."
+    )]
+    async fn click_element(
+        &self,
+        Parameters(params): Parameters<ClickElementParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::CLICK_ELEMENT) {
+            return disabled_tool_error(tool_names::CLICK_ELEMENT);
+        }
+        let locator =
+            match Locator::from_params(params.selector.as_deref(), params.xpath.as_deref()) {
+                Ok(locator) => locator,
+                Err(e) => return error_to_result(&e),
+            };
+        self.touch();
+        info!("Clicking element by {}", locator);
+        let clicked = self
+            .browser
+            .in_tab(params.tab.as_deref(), async {
+                let element = self.browser.locate_element(&locator).await?;
+                let state = self.browser.click_at(element.x, element.y).await?;
+                Ok((element, state))
+            })
+            .await;
+        let result = match clicked {
+            Ok((element, state)) => {
+                let (x, y) = self.screenshot_point(element.x, element.y);
+                let mut message = format!("Clicked {} at ({}, {})", element.describe(), x, y);
+                if state.effect_detected == Some(false) {
+                    message.push_str(", but nothing on the page changed");
+                }
+                env_state_to_result(state, Some(&message))
+            }
+            Err(e) => error_to_result(&format!("Failed to click element: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Hovers over an element found by CSS selector or XPath.
+    #[tool(
+        description = "Hovers over the first visible element matching a CSS selector or an XPath, scrolling it into view first, e.g. to open a menu. Set duration_ms to keep hovering before the screenshot."
+    )]
+    async fn hover_element(
+        &self,
+        Parameters(params): Parameters<HoverElementParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::HOVER_ELEMENT) {
+            return disabled_tool_error(tool_names::HOVER_ELEMENT);
+        }
+        let locator =
+            match Locator::from_params(params.selector.as_deref(), params.xpath.as_deref()) {
+                Ok(locator) => locator,
+                Err(e) => return error_to_result(&e),
+            };
+        self.touch();
+        info!("Hovering over element by {}", locator);
+        let hovered = self
+            .browser
+            .in_tab(params.tab.as_deref(), async {
+                let element = self.browser.locate_element(&locator).await?;
+                let state = self
+                    .browser
+                    .hover_at(element.x, element.y, params.duration_ms)
+                    .await?;
+                Ok((element, state))
+            })
+            .await;
+        let result = match hovered {
+            Ok((element, state)) => {
+                let (x, y) = self.screenshot_point(element.x, element.y);
+                env_state_to_result(
+                    state,
+                    Some(&format!(
+                        "Hovered over {} at ({}, {})",
+                        element.describe(),
+                        x,
+                        y
+                    )),
+                )
+            }
+            Err(e) => error_to_result(&format!("Failed to hover over element: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Types text into an element found by CSS selector or XPath.
+    #[tool(
+        description = "Types text into the first visible field matching a CSS selector or an XPath, scrolling it into view first. Takes the same press_enter, clear_before_typing, mode and simulate_keystrokes options as type_text_at."
+    )]
+    async fn type_in_element(
+        &self,
+        Parameters(params): Parameters<TypeInElementParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::TYPE_IN_ELEMENT) {
+            return disabled_tool_error(tool_names::TYPE_IN_ELEMENT);
+        }
+        let locator =
+            match Locator::from_params(params.selector.as_deref(), params.xpath.as_deref()) {
+                Ok(locator) => locator,
+                Err(e) => return error_to_result(&e),
+            };
+        self.touch();
+        info!("Typing into element by {}: {}", locator, params.text);
+        let mode = params.mode.unwrap_or(if params.clear_before_typing {
+            InsertionMode::ReplaceAll
+        } else {
+            InsertionMode::Append
+        });
+        let typed = self
+            .browser
+            .in_tab(params.tab.as_deref(), async {
+                let element = self.browser.locate_element(&locator).await?;
+                let state = self
+                    .browser
+                    .type_text_at(
+                        element.x,
+                        element.y,
+                        &params.text,
+                        params.press_enter,
+                        mode,
+                        params.simulate_keystrokes,
+                    )
+                    .await?;
+                Ok((element, state))
+            })
+            .await;
+        let result = match typed {
+            Ok((element, state)) => env_state_to_result(
+                state,
+                Some(&format!(
+                    "Typed '{}' into {}",
+                    params.text,
+                    element.describe()
+                )),
+            ),
+            Err(e) => error_to_result(&format!("Failed to type into element: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Opens a nested menu and clicks an item by the labels along the way.
+    #[tool(
+        description = "Opens a multi-level menu and clicks an item in one call, given the visible labels from the top level down, e.g. [\"File\", \"Export\", \"PDF\"]. Each level is hovered to open its submenu, or clicked when hovering does not open it, and the next label is waited for up to level_timeout_ms. Labels match the whole text or aria-label of an item, ignoring case. Set click_last to false to only hover over the last item."
+    )]
+    async fn open_menu_path(
+        &self,
+        Parameters(params): Parameters<OpenMenuPathParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::OPEN_MENU_PATH) {
+            return disabled_tool_error(tool_names::OPEN_MENU_PATH);
+        }
+        if params.labels.is_empty() || params.labels.len() > MAX_MENU_LEVELS {
+            return error_to_result(&format!(
+                "Provide 1 to {} labels (got {})",
+                MAX_MENU_LEVELS,
+                params.labels.len()
+            ));
+        }
+        let timeout_ms = params
+            .level_timeout_ms
+            .unwrap_or(DEFAULT_MENU_LEVEL_TIMEOUT_MS);
+        if timeout_ms > MAX_MENU_LEVEL_TIMEOUT_MS {
+            return error_to_result(&format!(
+                "level_timeout_ms must be at most {} (got {})",
+                MAX_MENU_LEVEL_TIMEOUT_MS, timeout_ms
+            ));
+        }
+        self.touch();
+        info!("Opening menu path {}", params.labels.join(" > "));
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.walk_menu_path(
+                    &params.labels,
+                    Duration::from_millis(timeout_ms),
+                    params.click_last,
+                ),
+            )
+            .await
+        {
+            Ok((message, state)) => env_state_to_result(state, Some(&message)),
+            Err(e) => error_to_result(&format!("Failed to open menu path: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Scrolls the entire webpage in the specified direction.
+    #[tool(
+        description = "Scrolls the entire webpage 'up', 'down', 'left' or 'right' based on direction. By default scrolls 0.8 of the viewport height vertically or half its width horizontally; set amount_pages (e.g. 0.5 or 3) or pixels to scroll a different distance. Set smooth to animate the scroll. Waits for scrolling to finish and visible images to load before taking the screenshot."
+    )]
+    async fn scroll_document(
+        &self,
+        Parameters(params): Parameters<ScrollDocumentParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::SCROLL_DOCUMENT) {
+            return disabled_tool_error(tool_names::SCROLL_DOCUMENT);
+        }
+        self.touch();
+        info!("Scrolling document: {}", params.direction);
+        let amount = match ScrollAmount::from_params(params.amount_pages, params.pixels) {
+            Ok(amount) => amount,
+            Err(e) => {
+                self.operation_complete();
+                return error_to_result(&format!("Failed to scroll: {}", e));
+            }
+        };
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser
+                    .scroll_document(&params.direction, amount, params.smooth),
+            )
+            .await
+        {
+            Ok(state) => env_state_to_result(
+                state,
+                Some(&format!(
+                    "Scrolled document {} by {}",
+                    params.direction, amount
+                )),
+            ),
+            Err(e) => error_to_result(&format!("Failed to scroll: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Scrolls at a specific coordinate in the specified direction.
+    #[tool(
+        description = "Scrolls up, down, right, or left at a x, y coordinate by magnitude pixels."
+    )]
+    async fn scroll_at(
+        &self,
+        Parameters(params): Parameters<ScrollAtParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::SCROLL_AT) {
+            return disabled_tool_error(tool_names::SCROLL_AT);
+        }
+        self.touch();
+        info!(
+            "Scrolling at ({}, {}) direction: {} magnitude: {}",
+            params.x, params.y, params.direction, params.magnitude
+        );
+        let (x, y) = self.page_point(params.x, params.y);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser
+                    .scroll_at(x, y, &params.direction, params.magnitude),
+            )
+            .await
+        {
+            Ok(state) => env_state_to_result(
+                state,
+                Some(&format!(
+                    "Scrolled {} at ({}, {}) by {} pixels",
+                    params.direction, params.x, params.y, params.magnitude
+                )),
+            ),
+            Err(e) => error_to_result(&format!("Failed to scroll: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Scrolls an element to the top of the viewport, below any sticky header.
+    #[tool(
+        description = "Scrolls the element at a x, y coordinate to the top of the viewport, leaving room for fixed or sticky headers so the element is not hidden behind them. Use it to reveal the content below an element before clicking there. Optional offset overrides the detected header height."
+    )]
+    async fn scroll_to_element(
+        &self,
+        Parameters(params): Parameters<ScrollToElementParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::SCROLL_TO_ELEMENT) {
+            return disabled_tool_error(tool_names::SCROLL_TO_ELEMENT);
+        }
+        self.touch();
+        info!(
+            "Scrolling element at ({}, {}) into view",
+            params.x, params.y
+        );
+        let (x, y) = self.page_point(params.x, params.y);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.scroll_to_element(x, y, params.offset),
+            )
+            .await
+        {
+            Ok((offset, state)) => env_state_to_result(
+                state,
+                Some(&format!(
+                    "Scrolled element at ({}, {}) to the top of the viewport, {}px below the top edge",
+                    params.x, params.y, offset
+                )),
+            ),
+            Err(e) => error_to_result(&format!("Failed to scroll to element: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Selects a range of text in an input or element, or places the caret.
+    #[tool(
+        description = "Selects text in the input, textarea or text element at a x, y coordinate, from character offset start to end (exclusive). Omit start or end to select from the beginning or to the end. With start equal to end, places the caret there. Typing with type_text_at in mode replace_selection then replaces only the selected text."
+    )]
+    async fn select_text(
+        &self,
+        Parameters(params): Parameters<SelectTextParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::SELECT_TEXT) {
+            return disabled_tool_error(tool_names::SELECT_TEXT);
+        }
+        self.touch();
+        info!(
+            "Selecting text at ({}, {}) from {:?} to {:?}",
+            params.x, params.y, params.start, params.end
+        );
+        let (x, y) = self.page_point(params.x, params.y);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser
+                    .select_text(ElementTarget::Point(x, y), params.start, params.end),
+            )
+            .await
+        {
+            Ok((text, state)) => env_state_to_result(state, Some(&selection_message(&text))),
+            Err(e) => error_to_result(&format!("Failed to select text: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Selects all text in the element matching a CSS selector.
+    #[tool(
+        description = "Selects all text in the first input, textarea or element matching a CSS selector, so it can be replaced by typing or copied."
+    )]
+    async fn select_all_in(
+        &self,
+        Parameters(params): Parameters<SelectAllInParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::SELECT_ALL_IN) {
+            return disabled_tool_error(tool_names::SELECT_ALL_IN);
+        }
+        self.touch();
+        info!("Selecting all text in {}", params.selector);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.select_text(
+                    ElementTarget::Selector(params.selector.clone()),
+                    None,
+                    None,
+                ),
+            )
+            .await
+        {
+            Ok((text, state)) => env_state_to_result(state, Some(&selection_message(&text))),
+            Err(e) => error_to_result(&format!("Failed to select text: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Sets the value of a color input.
+    #[tool(
+        description = "Sets an <input type=color>, found by CSS selector or by x, y coordinate, to a #rrggbb color and fires input and change events. Use it instead of clicking the swatch: the color picker it opens is not part of the page and cannot be operated."
+    )]
+    async fn set_color(
+        &self,
+        Parameters(params): Parameters<SetColorParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::SET_COLOR) {
+            return disabled_tool_error(tool_names::SET_COLOR);
+        }
+        self.touch();
+        let target = match control_target(&params.selector, params.x, params.y) {
+            Ok(target) => self.page_target(target),
+            Err(e) => return error_to_result(&e),
+        };
+        let color = match ControlValue::color(&params.color) {
+            Ok(color) => color,
+            Err(e) => return error_to_result(&e),
+        };
+        info!("Setting color input {:?} to {:?}", target, color);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.set_control_value(target, color),
+            )
+            .await
+        {
+            Ok((value, state)) => {
+                env_state_to_result(state, Some(&format!("Set color input to {}", value)))
+            }
+            Err(e) => error_to_result(&format!("Failed to set color: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Sets the value of a range slider.
+    #[tool(
+        description = "Sets an <input type=range> slider, found by CSS selector or by x, y coordinate, to a value and fires input and change events, instead of dragging its thumb to an approximate position. The value is clamped to the slider's min and max and snapped to its step; the response reports the value it took."
+    )]
+    async fn set_range(
+        &self,
+        Parameters(params): Parameters<SetRangeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::SET_RANGE) {
+            return disabled_tool_error(tool_names::SET_RANGE);
+        }
+        self.touch();
+        let target = match control_target(&params.selector, params.x, params.y) {
+            Ok(target) => self.page_target(target),
+            Err(e) => return error_to_result(&e),
+        };
+        if !params.value.is_finite() {
+            return error_to_result("The value must be a finite number");
+        }
+        info!("Setting range input {:?} to {}", target, params.value);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser
+                    .set_control_value(target, ControlValue::Range(params.value)),
+            )
+            .await
+        {
+            Ok((value, state)) => {
+                env_state_to_result(state, Some(&format!("Set slider to {}", value)))
+            }
+            Err(e) => error_to_result(&format!("Failed to set slider: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Outlines an element so a human can see what the agent is about to act on.
+    #[tool(
+        description = "Draws a temporary outlined box around an element, found by CSS selector or by x, y coordinate, and returns a screenshot showing it. Use it to show a human reviewer which element you intend to interact with before acting. The box does not block clicks and disappears after duration_ms."
+    )]
+    async fn highlight_element(
+        &self,
+        Parameters(params): Parameters<HighlightElementParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::HIGHLIGHT_ELEMENT) {
+            return disabled_tool_error(tool_names::HIGHLIGHT_ELEMENT);
+        }
+        self.touch();
+        let target = match params.target() {
+            Ok(target) => self.page_target(target),
+            Err(e) => return error_to_result(&e),
+        };
+        info!("Highlighting {:?}", target);
+        let duration_ms = params.duration_ms.unwrap_or(DEFAULT_HIGHLIGHT_DURATION_MS);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.highlight_element(target, duration_ms),
+            )
+            .await
+        {
+            Ok((description, state)) => {
+                env_state_to_result(state, Some(&format!("Highlighted {}", description)))
+            }
+            Err(e) => error_to_result(&format!("Failed to highlight element: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Remembers an element under a name for click_named.
+    #[tool(
+        description = "Remembers an element, found by CSS selector or by x, y coordinate, under a name for the rest of the session, so it can be clicked later with click_named without finding it in a screenshot again. A point inside a button or link remembers the whole control. The element is found again by a stable selector, or by its tag and text after the page re-rendered."
+    )]
+    async fn remember_element(
+        &self,
+        Parameters(params): Parameters<RememberElementParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::REMEMBER_ELEMENT) {
+            return disabled_tool_error(tool_names::REMEMBER_ELEMENT);
+        }
+        self.touch();
+        let target = match params.target() {
+            Ok(target) => self.page_target(target),
+            Err(e) => return error_to_result(&e),
+        };
+        info!("Remembering {:?} as '{}'", target, params.name);
+        let result = match self
+            .browser
+            .in_tab(params.tab.as_deref(), self.browser.remember_element(target))
+            .await
+        {
+            Ok(element) => match self.element_aliases.remember(&params.name, element.clone()) {
+                Ok(()) => {
+                    let response = RememberElementResponse {
+                        name: params.name,
+                        element,
+                        success: true,
+                    };
+                    let text = serde_json::to_string_pretty(&response)
+                        .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                    Ok(CallToolResult::success(vec![Content::text(text)]))
+                }
+                Err(e) => error_to_result(&e),
+            },
+            Err(e) => error_to_result(&format!("Failed to remember element: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Clicks an element remembered with remember_element.
+    #[tool(
+        description = "Clicks the element remembered under 'name' with remember_element. The element is looked up again first, so the click lands on it even after the page re-rendered or scrolled; it is scrolled into view if needed. Returns the state after the click."
+    )]
+    async fn click_named(
+        &self,
+        Parameters(params): Parameters<ClickNamedParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::CLICK_NAMED) {
+            return disabled_tool_error(tool_names::CLICK_NAMED);
+        }
+        let element = match self.element_aliases.get(&params.name) {
+            Ok(element) => element,
+            Err(e) => return error_to_result(&e),
+        };
+        self.touch();
+        info!("Clicking '{}' ({})", params.name, element.describe());
+        let result = match self
+            .browser
+            .in_tab(params.tab.as_deref(), self.browser.click_named(&element))
+            .await
+        {
+            Ok(((x, y), state)) => {
+                let (x, y) = self.screenshot_point(x, y);
+                let mut message = format!(
+                    "Clicked '{}' ({}) at ({}, {})",
+                    params.name,
+                    element.describe(),
+                    x,
+                    y
+                );
+                if state.effect_detected == Some(false) {
+                    message.push_str(", but nothing on the page changed");
+                }
+                env_state_to_result(state, Some(&message))
+            }
+            Err(e) => error_to_result(&format!("Failed to click '{}': {}", params.name, e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Waits for 5 seconds to allow unfinished webpage processes to complete.
+    #[tool(description = "Waits for 5 seconds to allow unfinished webpage processes to complete.")]
+    async fn wait_5_seconds(
+        &self,
+        Parameters(params): Parameters<TabParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::WAIT_5_SECONDS) {
+            return disabled_tool_error(tool_names::WAIT_5_SECONDS);
+        }
+        self.touch();
+        info!("Waiting 5 seconds");
+        let result = match self
+            .browser
+            .in_tab(params.tab.as_deref(), self.browser.wait_5_seconds())
+            .await
+        {
+            Ok(state) => env_state_to_result(state, Some("Waited 5 seconds")),
+            Err(e) => error_to_result(&format!("Failed to wait: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Navigates back to the previous webpage in the browser history.
+    #[tool(
+        description = "Navigates back to the previous webpage in the browser history. A page that was scrolled when it was last shown is scrolled back to the same position."
+    )]
+    async fn go_back(
+        &self,
+        Parameters(params): Parameters<TabParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::GO_BACK) {
+            return disabled_tool_error(tool_names::GO_BACK);
+        }
+        self.touch();
+        info!("Going back");
+        let result = match self
+            .browser
+            .in_tab(params.tab.as_deref(), self.browser.go_back())
+            .await
+        {
+            Ok((state, None)) => env_state_to_result(state, Some("Navigated back")),
+            Ok((state, Some(position))) => env_state_to_result(
+                state,
+                Some(&format!(
+                    "Navigated back and restored the scroll position to ({}, {})",
+                    position.x, position.y
+                )),
+            ),
+            Err(e) => error_to_result(&format!("Failed to go back: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Navigates forward to the next webpage in the browser history.
+    #[tool(
+        description = "Navigates forward to the next webpage in the browser history. A page that was scrolled when it was last shown is scrolled back to the same position."
+    )]
+    async fn go_forward(
+        &self,
+        Parameters(params): Parameters<TabParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::GO_FORWARD) {
+            return disabled_tool_error(tool_names::GO_FORWARD);
+        }
+        self.touch();
+        info!("Going forward");
+        let result = match self
+            .browser
+            .in_tab(params.tab.as_deref(), self.browser.go_forward())
+            .await
+        {
+            Ok((state, None)) => env_state_to_result(state, Some("Navigated forward")),
+            Ok((state, Some(position))) => env_state_to_result(
+                state,
+                Some(&format!(
+                    "Navigated forward and restored the scroll position to ({}, {})",
+                    position.x, position.y
+                )),
+            ),
+            Err(e) => error_to_result(&format!("Failed to go forward: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Opens a search engine, optionally with the results for a query.
+    #[tool(
+        description = "Opens a search engine's results for a query, or its home page when no query is given. Optionally names the engine (e.g. google, bing, duckduckgo); defaults to the configured engine."
+    )]
+    async fn search(
+        &self,
+        Parameters(params): Parameters<SearchParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::SEARCH) {
+            return disabled_tool_error(tool_names::SEARCH);
+        }
+        self.touch();
+        info!("Searching for: {:?}", params.query);
+        let result = match self
+            .browser
+            .in_tab(params.tab.as_deref(), async {
+                self.before_search(params.query.as_deref(), params.engine.as_deref())
+                    .await?;
+                self.browser
+                    .search(params.query.as_deref(), params.engine.as_deref())
+                    .await
+            })
+            .await
+        {
+            Ok(state) => {
+                let message = match &params.query {
+                    Some(query) => format!("Searched for {}", query),
+                    None => "Navigated to search engine".to_string(),
+                };
+                env_state_to_result(state, Some(&message))
+            }
+            Err(e) => error_to_result(&format!("Failed to search: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Navigates directly to a specified URL.
+    #[tool(
+        description = "Navigates directly to a specified URL. URLs without a protocol will be prefixed with 'https://'."
+    )]
+    async fn navigate(
+        &self,
+        Parameters(params): Parameters<NavigateParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::NAVIGATE) {
+            return disabled_tool_error(tool_names::NAVIGATE);
+        }
+        self.touch();
+        info!("Navigating to: {}", params.url);
+        let result = match self
+            .browser
+            .in_tab(params.tab.as_deref(), async {
+                self.before_navigation(&params.url).await?;
+                self.browser.navigate(&params.url).await
+            })
+            .await
+        {
+            Ok(state) => env_state_to_result(state, Some(&format!("Navigated to {}", params.url))),
+            Err(e) => error_to_result(&format!("Failed to navigate: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Presses keyboard keys and combinations.
+    #[tool(
+        description = "Presses keyboard keys and combinations, such as ['Control', 'c'] or ['Enter']. Supports modifiers like Control, Shift, Alt, Meta/Command, combination strings like 'ctrl+c', and 'Primary' for Command on macOS and Control elsewhere."
+    )]
+    async fn key_combination(
+        &self,
+        Parameters(params): Parameters<KeyCombinationParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::KEY_COMBINATION) {
+            return disabled_tool_error(tool_names::KEY_COMBINATION);
+        }
+        self.touch();
+        info!("Pressing key combination: {:?}", params.keys);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.key_combination(params.keys.clone()),
+            )
+            .await
+        {
+            Ok(state) => {
+                env_state_to_result(state, Some(&format!("Pressed keys: {:?}", params.keys)))
+            }
+            Err(e) => error_to_result(&format!("Failed to press keys: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Performs a named keyboard shortcut.
+    #[tool(
+        description = "Performs a common keyboard shortcut by name, with the right modifiers for the platform: select_all, copy, cut, paste, undo, redo, find, new_tab, close_tab, zoom_in, zoom_out or zoom_reset. new_tab and close_tab open or close a tab, and the zoom presets zoom the page, since the browser does not receive those shortcuts from simulated keys."
+    )]
+    async fn shortcut(
+        &self,
+        Parameters(params): Parameters<ShortcutParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::SHORTCUT) {
+            return disabled_tool_error(tool_names::SHORTCUT);
+        }
+        let action = params.name.action(&KeyMapping::from_config(&self.config));
+        // Presets acting on tabs must not get around disabling the tab tools
+        match action {
+            ShortcutAction::NewTab if self.config.is_tool_disabled(tool_names::NEW_TAB) => {
+                return disabled_tool_error(tool_names::NEW_TAB);
+            }
+            ShortcutAction::CloseTab if self.config.is_tool_disabled(tool_names::CLOSE_TAB) => {
+                return disabled_tool_error(tool_names::CLOSE_TAB);
+            }
+            _ => {}
+        }
+        self.touch();
+        info!("Performing shortcut {:?}: {:?}", params.name, action);
+        let tab = params.tab.as_deref();
+        let result = match action {
+            ShortcutAction::Keys(keys) => {
+                let message = format!("Pressed {}", keys.join("+"));
+                match self
+                    .browser
+                    .in_tab(tab, self.browser.key_combination(keys))
+                    .await
+                {
+                    Ok(state) => env_state_to_result(state, Some(&message)),
+                    Err(e) => error_to_result(&format!("Failed to press shortcut: {}", e)),
+                }
+            }
+            ShortcutAction::NewTab => match self.browser.new_tab(None).await {
+                Ok((tab_info, state)) => {
+                    env_state_to_result(state, Some(&format!("Opened new tab {}", tab_info.handle)))
+                }
+                Err(e) => error_to_result(&format!("Failed to create new tab: {}", e)),
+            },
+            ShortcutAction::CloseTab => match self.browser.close_tab(tab).await {
+                Ok(state) => env_state_to_result(state, Some("Tab closed successfully")),
+                Err(e) => error_to_result(&format!("Failed to close tab: {}", e)),
+            },
+            ShortcutAction::Zoom(step) => {
+                match self.browser.in_tab(tab, self.browser.zoom_page(step)).await {
+                    Ok((zoom, state)) => env_state_to_result(
+                        state,
+                        Some(&format!("Zoomed page to {:.0}%", zoom * 100.0)),
+                    ),
+                    Err(e) => error_to_result(&format!("Failed to zoom page: {}", e)),
+                }
+            }
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Presses a sequence of keys and combinations in one call.
+    #[tool(
+        description = "Presses a sequence of keys or key combinations in order, such as Tab, Tab, Enter, for keyboard navigation of forms and menus. Each step has a keys list like key_combination and an optional delay_ms to wait after it (max 5000). Returns a single screenshot after the last step."
+    )]
+    async fn press_keys_sequence(
+        &self,
+        Parameters(params): Parameters<PressKeysSequenceParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self
+            .config
+            .is_tool_disabled(tool_names::PRESS_KEYS_SEQUENCE)
+        {
+            return disabled_tool_error(tool_names::PRESS_KEYS_SEQUENCE);
+        }
+        self.touch();
+        let step_count = params.steps.len();
+        info!("Pressing key sequence of {} steps", step_count);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.press_keys_sequence(params.steps),
+            )
+            .await
+        {
+            Ok(state) => env_state_to_result(
+                state,
+                Some(&format!("Pressed key sequence of {} steps", step_count)),
+            ),
+            Err(e) => error_to_result(&format!("Failed to press key sequence: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Moves focus to an element with Tab and activates it.
+    #[tool(
+        description = "Presses Tab (or Shift+Tab with backwards) until the element with the given text or matching selector has focus, checked after every press, then presses Enter on it, Space on checkboxes, radio buttons and switches, or nothing on text fields (set activate to enter, space or none to choose). A fallback for elements that clicks keep missing, are covered by overlays, or only react to the keyboard. Fails when focus goes around the page without reaching the element."
+    )]
+    async fn tab_to_element(
+        &self,
+        Parameters(params): Parameters<TabToElementParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::TAB_TO_ELEMENT) {
+            return disabled_tool_error(tool_names::TAB_TO_ELEMENT);
+        }
+        let locator = match (params.text, params.selector) {
+            (Some(text), None) if !text.trim().is_empty() => Locator::Text(text),
+            (None, Some(selector)) if !selector.trim().is_empty() => Locator::Css(selector),
+            _ => return error_to_result("Provide either text or selector"),
+        };
+        let max_presses = params
+            .max_presses
+            .unwrap_or(keyboard_focus::DEFAULT_MAX_PRESSES);
+        if max_presses > keyboard_focus::MAX_PRESSES {
+            return error_to_result(&format!(
+                "max_presses must be at most {} (got {})",
+                keyboard_focus::MAX_PRESSES,
+                max_presses
+            ));
+        }
+        self.touch();
+        info!("Tabbing to the element with {}", locator);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.tab_to_element(
+                    &locator,
+                    params.backwards,
+                    max_presses,
+                    params.activate,
+                ),
+            )
+            .await
+        {
+            Ok((focus, state)) => {
+                let mut message = format!(
+                    "Focused {} after {} {} {}",
+                    focus.describe(),
+                    focus.presses,
+                    if params.backwards { "Shift+Tab" } else { "Tab" },
+                    if focus.presses == 1 {
+                        "press"
+                    } else {
+                        "presses"
+                    }
+                );
+                match focus.activated {
+                    Some(Activation::Space) => message.push_str(" and pressed Space"),
+                    Some(_) => message.push_str(" and pressed Enter"),
+                    None => {}
+                }
+                env_state_to_result(state, Some(&message))
+            }
+            Err(e) => error_to_result(&format!("Failed to tab to element: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Draws a path through points with native pointer events.
+    #[tool(
+        description = "Draws on a canvas, signature pad or whiteboard: presses the mouse (or a finger, with pointer: touch) at the first of points, moves through the others at a steady speed over duration_ms, and releases at the last. Points are x, y screenshot coordinates; intermediate points are filled in every few pixels, so a few points per stroke suffice for straight lines and more are needed for curves. Each call draws one stroke. Chromium only."
+    )]
+    async fn draw_path(
+        &self,
+        Parameters(params): Parameters<DrawPathParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::DRAW_PATH) {
+            return disabled_tool_error(tool_names::DRAW_PATH);
+        }
+        self.touch();
+        let duration_ms = params.duration_ms.unwrap_or(draw_path::DEFAULT_DURATION_MS);
+        info!(
+            "Drawing a path through {} points over {}ms",
+            params.points.len(),
+            duration_ms
+        );
+        let points: Vec<PathPoint> = params
+            .points
+            .iter()
+            .map(|point| {
+                let (x, y) = self.page_point(point.x, point.y);
+                PathPoint { x, y }
+            })
+            .collect();
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.draw_path(&points, duration_ms, params.pointer),
+            )
+            .await
+        {
+            Ok(state) => env_state_to_result(
+                state,
+                Some(&format!(
+                    "Drew a path through {} points over {}ms",
+                    params.points.len(),
+                    duration_ms
+                )),
+            ),
+            Err(e) => error_to_result(&format!("Failed to draw path: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Drag and drop an element from one position to another.
+    #[tool(
+        description = "Drag and drop an element from a x, y coordinate to a destination_x, destination_y coordinate."
+    )]
+    async fn drag_and_drop(
+        &self,
+        Parameters(params): Parameters<DragAndDropParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::DRAG_AND_DROP) {
+            return disabled_tool_error(tool_names::DRAG_AND_DROP);
+        }
+        self.touch();
+        info!(
+            "Drag and drop from ({}, {}) to ({}, {})",
+            params.x, params.y, params.destination_x, params.destination_y
+        );
+        let (x, y) = self.page_point(params.x, params.y);
+        let (destination_x, destination_y) =
+            self.page_point(params.destination_x, params.destination_y);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser
+                    .drag_and_drop(x, y, destination_x, destination_y),
+            )
+            .await
+        {
+            Ok(state) => env_state_to_result(
+                state,
+                Some(&format!(
+                    "Dragged from ({}, {}) to ({}, {})",
+                    params.x, params.y, params.destination_x, params.destination_y
+                )),
+            ),
+            Err(e) => error_to_result(&format!("Failed to drag and drop: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Returns the current state of the webpage.
+    #[tool(
+        description = "Returns the current state of the webpage including a screenshot and the current URL. With tab, returns the state of that tab with its title while the active tab stays in front. With skip_if_unchanged, the screenshot is omitted and unchanged: true is returned when the page looks the same as in the previous call. With context_strips, half-scale previews of the content just above and below the viewport follow the screenshot, with how much content lies beyond each edge."
+    )]
+    async fn current_state(
+        &self,
+        Parameters(params): Parameters<CurrentStateParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::CURRENT_STATE) {
+            return disabled_tool_error(tool_names::CURRENT_STATE);
+        }
+        self.touch();
+        info!("Getting current state");
+        let tab = params.tab.as_deref();
+        let peeked = self
+            .browser
+            .peek_tab(tab, async {
+                let state = self.browser.current_state().await?;
+                // Names the tab being peeked at, which is not on screen
+                let title = match tab {
+                    Some(_) => self.browser.page_metadata().await.ok().map(|m| m.title),
+                    None => None,
+                };
+                let strips = match params.context_strips {
+                    true => Some(self.browser.context_strips().await),
+                    false => None,
+                };
+                Ok((state, title, strips))
+            })
+            .await;
+        let result = match peeked {
+            Ok((state, title, strips)) => {
+                let key = params.tab.clone().unwrap_or_default();
+                // New notifications are always reported, even on an unchanged page
+                if params.skip_if_unchanged
+                    && self
+                        .screenshot_unchanged(key, &state.url, &state.screenshot)
+                        .await
+                    && state.notifications.is_empty()
+                {
+                    unchanged_to_result(state.url)
+                } else {
+                    let result =
+                        tab_state_to_result(state, Some("Current state retrieved"), tab, title);
+                    match strips {
+                        Some(strips) => result.map(|result| with_context_strips(result, strips)),
+                        None => result,
+                    }
+                }
+            }
+            Err(e) => error_to_result(&format!("Failed to get current state: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    // ========== Tab Management Tools ==========
+
+    /// Creates a new browser tab.
+    #[tool(
+        description = "Creates a new browser tab. Optionally navigates to a URL in the new tab. Returns information about the new tab and a screenshot."
+    )]
+    async fn new_tab(
+        &self,
+        Parameters(params): Parameters<NewTabParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::NEW_TAB) {
+            return disabled_tool_error(tool_names::NEW_TAB);
+        }
+        self.touch();
+        info!("Creating new tab with URL: {:?}", params.url);
+        let opened = async {
+            if let Some(ref url) = params.url {
+                self.before_navigation(url).await?;
+            }
+            self.browser.new_tab(params.url.as_deref()).await
+        };
+        let result = match opened.await {
+            Ok((tab_info, state)) => {
+                let response = NewTabResponse {
+                    tab: tab_info,
+                    success: true,
+                    message: Some("New tab created successfully".to_string()),
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                let text_content = Content::text(text);
+                let image_content = Content::image(state.screenshot, "image/png");
+                Ok(CallToolResult::success(vec![text_content, image_content]))
+            }
+            Err(e) => error_to_result(&format!("Failed to create new tab: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Closes a browser tab.
+    #[tool(description = "Closes a browser tab. If no handle is provided, closes the current tab.")]
+    async fn close_tab(
+        &self,
+        Parameters(params): Parameters<CloseTabParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::CLOSE_TAB) {
+            return disabled_tool_error(tool_names::CLOSE_TAB);
+        }
+        self.touch();
+        info!("Closing tab: {:?}", params.handle);
+        let result = match self.browser.close_tab(params.handle.as_deref()).await {
+            Ok(state) => env_state_to_result(state, Some("Tab closed successfully")),
+            Err(e) => error_to_result(&format!("Failed to close tab: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Switches to a different browser tab.
+    #[tool(
+        description = "Switches to a different browser tab by handle or index. Provide exactly one of 'handle' (window handle string) or 'index' (0-based tab index)."
+    )]
+    async fn switch_tab(
+        &self,
+        Parameters(params): Parameters<SwitchTabParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::SWITCH_TAB) {
+            return disabled_tool_error(tool_names::SWITCH_TAB);
+        }
+        self.touch();
+        info!(
+            "Switching to tab: handle={:?}, index={:?}",
+            params.handle, params.index
+        );
+        let result = match self
+            .browser
+            .switch_tab(params.handle.as_deref(), params.index)
+            .await
+        {
+            Ok(state) => env_state_to_result(state, Some("Switched to tab")),
+            Err(e) => error_to_result(&format!("Failed to switch tab: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Lists all open browser tabs.
+    #[tool(
+        description = "Lists all open browser tabs with their handles, URLs, titles, and active status. Also returns a screenshot of the current tab."
+    )]
+    async fn list_tabs(&self) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::LIST_TABS) {
+            return disabled_tool_error(tool_names::LIST_TABS);
+        }
+        self.touch();
+        info!("Listing all tabs");
+        let result = match self.browser.list_tabs().await {
+            Ok((tabs, state)) => {
+                let response = TabListResponse {
+                    tabs,
+                    success: true,
+                    message: Some("Tabs listed successfully".to_string()),
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true,"tabs":[]}"#.to_string());
+                let text_content = Content::text(text);
+                let image_content = Content::image(state.screenshot, "image/png");
+                Ok(CallToolResult::success(vec![text_content, image_content]))
+            }
+            Err(e) => error_to_result(&format!("Failed to list tabs: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Searches the web and returns the parsed results.
+    #[tool(
+        description = "Searches the web with the configured or named search engine and returns the results as JSON (title, url, snippet for each), without a screenshot. Navigates the tab to the search engine. Use navigate to open a result."
+    )]
+    async fn web_search(
+        &self,
+        Parameters(params): Parameters<WebSearchParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::WEB_SEARCH) {
+            return disabled_tool_error(tool_names::WEB_SEARCH);
+        }
+        self.touch();
+        info!("Searching the web for: {}", params.query);
+        let num_results = params
+            .num_results
+            .unwrap_or(web_search::DEFAULT_NUM_RESULTS)
+            .clamp(1, web_search::MAX_NUM_RESULTS);
+        let result = match self
+            .browser
+            .in_tab(params.tab.as_deref(), async {
+                self.before_search(Some(&params.query), params.engine.as_deref())
+                    .await?;
+                self.browser
+                    .web_search(&params.query, params.engine.as_deref(), num_results)
+                    .await
+            })
+            .await
+        {
+            Ok(results) => {
+                let response = WebSearchResponse {
+                    query: params.query,
+                    results,
+                    success: true,
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true,"results":[]}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => error_to_result(&format!("Failed to search the web: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Lists the page targets of a browser connected over CDP.
+    #[tool(
+        description = "Lists every tab of the browser connected over CDP, including tabs opened by the user before the server connected, with their handles, URLs, titles, and which one is active. Use attach_to_tab to point actions at one of them. Requires CDP mode."
+    )]
+    async fn list_targets(&self) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::LIST_TARGETS) {
+            return disabled_tool_error(tool_names::LIST_TARGETS);
+        }
+        self.touch();
+        info!("Listing page targets");
+        let result = match self.browser.list_targets().await {
+            Ok(targets) => {
+                let response = TargetListResponse {
+                    targets,
+                    success: true,
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true,"targets":[]}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => error_to_result(&format!("Failed to list targets: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Attaches to a tab of a browser connected over CDP.
+    #[tool(
+        description = "Attaches to a tab of the browser connected over CDP by its handle from list_targets and makes it the active tab, so later actions target it. Works for tabs the user opened before the server connected. Requires CDP mode."
+    )]
+    async fn attach_to_tab(
+        &self,
+        Parameters(params): Parameters<AttachToTabParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::ATTACH_TO_TAB) {
+            return disabled_tool_error(tool_names::ATTACH_TO_TAB);
+        }
+        self.touch();
+        info!("Attaching to tab: {}", params.handle);
+        let result = match self.browser.attach_to_tab(&params.handle).await {
+            Ok(state) => env_state_to_result(state, Some("Attached to tab")),
+            Err(e) => error_to_result(&format!("Failed to attach to tab: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    // ========== Page Inspection Tools ==========
+
+    /// Returns a compact snapshot of the visible DOM.
+    #[tool(
+        description = "Returns a compact tree of the visible DOM nodes with their text, key attributes, bounding boxes ([x, y, width, height] in screenshot coordinates), clickability, and whether they are in the viewport. A cheaper text alternative to screenshots for locating elements. Requires a Chromium-based browser."
+    )]
+    async fn dom_snapshot(
+        &self,
+        Parameters(params): Parameters<TabParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::DOM_SNAPSHOT) {
+            return disabled_tool_error(tool_names::DOM_SNAPSHOT);
+        }
+        self.touch();
+        info!("Capturing DOM snapshot");
+        let result = match self
+            .browser
+            .in_tab(params.tab.as_deref(), self.browser.dom_snapshot())
+            .await
+        {
+            Ok(mut snapshot) => {
+                self.screenshot_bounds(&mut snapshot.nodes);
+                let response = DomSnapshotResponse {
+                    snapshot,
+                    success: true,
+                };
+                // Compact JSON keeps large snapshots cheap to consume
+                let text = serde_json::to_string(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => error_to_result(&format!("Failed to capture DOM snapshot: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Returns a screenshot with the boxes of its interactable elements.
+    #[tool(
+        description = "Returns a screenshot of the page together with a JSON list of the interactable elements visible in it (buttons, links, fields, other clickable elements), each with an id, role, label, tag, 'bbox' as [left, top, right, bottom] in screenshot pixels and the 'center' to click. Suited to exporting grounding examples for training or evaluating computer-use models (Chromium only)."
+    )]
+    async fn grounding_snapshot(
+        &self,
+        Parameters(params): Parameters<TabParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::GROUNDING_SNAPSHOT) {
+            return disabled_tool_error(tool_names::GROUNDING_SNAPSHOT);
+        }
+        self.touch();
+        info!("Capturing grounding snapshot");
+        let captured = self
+            .browser
+            .in_tab(params.tab.as_deref(), async {
+                let snapshot = self.browser.dom_snapshot().await?;
+                let state = self.browser.current_state().await?;
+                let png = BASE64.decode(&state.screenshot)?;
+                let (width, height) = image::ImageReader::new(std::io::Cursor::new(png))
+                    .with_guessed_format()?
+                    .into_dimensions()?;
+                Ok::<_, anyhow::Error>((snapshot, state, width, height))
+            })
+            .await;
+        let result = match captured {
+            Ok((snapshot, state, image_width, image_height)) => {
+                let viewport = (self.config.screen_width, self.config.screen_height);
+                let mut elements = grounding::elements(&snapshot, viewport);
+                for element in &mut elements {
+                    let [left, top, right, bottom] = element.bbox;
+                    let (left, top) = self.screenshot_point(left, top);
+                    let (right, bottom) = self.screenshot_point(right, bottom);
+                    element.bbox = [left, top, right, bottom];
+                    let (x, y) = self.screenshot_point(element.center[0], element.center[1]);
+                    element.center = [x, y];
+                }
+                let response = GroundingSnapshotResponse {
+                    url: state.url,
+                    image_width,
+                    image_height,
+                    elements,
+                    success: true,
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                Ok(CallToolResult::success(vec![
+                    Content::text(text),
+                    Content::image(state.screenshot, "image/png"),
+                ]))
+            }
+            Err(e) => error_to_result(&format!("Failed to capture grounding snapshot: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Returns metadata describing the current page.
+    #[tool(
+        description = "Returns metadata for the current page: title, meta description, canonical URL, OpenGraph tags (og:title, og:image, ...), and favicon URL. Useful for summarizing or bookmarking pages."
+    )]
+    async fn get_page_metadata(
+        &self,
+        Parameters(params): Parameters<TabParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::GET_PAGE_METADATA) {
+            return disabled_tool_error(tool_names::GET_PAGE_METADATA);
+        }
+        self.touch();
+        info!("Getting page metadata");
+        let result = match self
+            .browser
+            .in_tab(params.tab.as_deref(), self.browser.page_metadata())
+            .await
+        {
+            Ok(metadata) => {
+                let response = PageMetadataResponse {
+                    metadata,
+                    success: true,
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => error_to_result(&format!("Failed to get page metadata: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Returns the web notifications shown by the page.
+    #[tool(
+        description = "Returns web notifications (title, body) and permission prompts (notifications, geolocation, camera, microphone) the current page has shown since it loaded, oldest first. Use it to confirm actions whose only feedback is a notification, e.g. 'Message sent'. With clear: true, returned entries are removed."
+    )]
+    async fn get_notifications(
+        &self,
+        Parameters(params): Parameters<GetNotificationsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::GET_NOTIFICATIONS) {
+            return disabled_tool_error(tool_names::GET_NOTIFICATIONS);
+        }
+        self.touch();
+        info!("Getting notifications");
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.get_notifications(params.clear),
+            )
+            .await
+        {
+            Ok(notifications) => {
+                let response = NotificationsResponse {
+                    notifications,
+                    success: true,
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => error_to_result(&format!("Failed to get notifications: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Captures the current frame of a video element.
+    #[tool(
+        description = "Returns the frame a <video> element currently shows as a PNG image, at the video's own resolution (scaled down to at most 1920 pixels), without the controls, captions, ads or overlays a screenshot shows on top of it. Targets the video matching selector, else the first playing video. Videos from another origin without CORS cannot be read. Pause the video first with control_media to capture a specific moment."
+    )]
+    async fn capture_video_frame(
+        &self,
+        Parameters(params): Parameters<CaptureVideoFrameParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self
+            .config
+            .is_tool_disabled(tool_names::CAPTURE_VIDEO_FRAME)
+        {
+            return disabled_tool_error(tool_names::CAPTURE_VIDEO_FRAME);
+        }
+        self.touch();
+        info!("Capturing video frame of {:?}", params.selector);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.capture_video_frame(params.selector.as_deref()),
+            )
+            .await
+        {
+            Ok(frame) => {
+                let response = VideoFrameResponse {
+                    index: frame.index,
+                    width: frame.width,
+                    height: frame.height,
+                    current_time: frame.current_time,
+                    success: true,
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                Ok(CallToolResult::success(vec![
+                    Content::text(text),
+                    Content::image(frame.image, "image/png"),
+                ]))
+            }
+            Err(e) => error_to_result(&format!("Failed to capture video frame: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Evaluates a JavaScript expression in the page.
+    #[tool(
+        description = "Evaluates a JavaScript expression in the current page (or selected frame) and returns its value as JSON. Promises are awaited for up to 10 seconds. DOM elements are returned as descriptions such as '<div#app>'; return the properties you need instead, e.g. el.textContent. Results longer than 50,000 characters are truncated. Prefer the dedicated tools for actions: changes made here bypass the page's input handling."
+    )]
+    async fn evaluate_javascript(
+        &self,
+        Parameters(params): Parameters<EvaluateJavascriptParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self
+            .config
+            .is_tool_disabled(tool_names::EVALUATE_JAVASCRIPT)
+        {
+            return disabled_tool_error(tool_names::EVALUATE_JAVASCRIPT);
+        }
+        self.touch();
+        info!(
+            "Evaluating JavaScript ({} characters)",
+            params.expression.len()
+        );
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.evaluate_javascript(&params.expression),
+            )
+            .await
+        {
+            Ok(evaluation) => {
+                let response = EvaluateJavascriptResponse {
+                    result: evaluation.value,
+                    result_type: evaluation.value_type,
+                    truncated: evaluation.truncated,
+                    success: true,
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => error_to_result(&format!("Failed to evaluate JavaScript: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Lists the audio and video elements of the page.
+    #[tool(
+        description = "Lists the audio and video elements of the current page: index, kind, source, whether each is playing and muted, playback position and duration. audible is true when anything plays unmuted. The browser itself is usually started muted (browser_muted), so pages never play sound out loud; use control_media to stop a video that distracts from the page or to start one."
+    )]
+    async fn get_media(
+        &self,
+        Parameters(params): Parameters<GetMediaParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::GET_MEDIA) {
+            return disabled_tool_error(tool_names::GET_MEDIA);
+        }
+        self.touch();
+        info!("Listing media elements");
+        let result = match self
+            .browser
+            .in_tab(params.tab.as_deref(), self.browser.get_media())
+            .await
+        {
+            Ok(media) => {
+                let response = MediaResponse::new(media, self.config.mute_audio, None);
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => error_to_result(&format!("Failed to list media: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Plays, pauses, mutes or unmutes media elements.
+    #[tool(
+        description = "Plays, pauses, mutes or unmutes the audio or video element at index (from get_media), or every media element of the page when index is omitted. Returns the media state half a second later; an element that is still not playing after play was blocked by the page or the browser's autoplay policy, and clicking its play button may work instead."
+    )]
+    async fn control_media(
+        &self,
+        Parameters(params): Parameters<ControlMediaParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::CONTROL_MEDIA) {
+            return disabled_tool_error(tool_names::CONTROL_MEDIA);
+        }
+        self.touch();
+        info!("Media {:?} on {:?}", params.action, params.index);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.control_media(params.action, params.index),
+            )
+            .await
+        {
+            Ok(media) => {
+                let target = match params.index {
+                    Some(index) => format!("media element {}", index),
+                    None => format!("{} media elements", media.len()),
+                };
+                let message = format!("{} {}", params.action, target);
+                let response = MediaResponse::new(media, self.config.mute_audio, Some(message));
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => error_to_result(&format!("Failed to control media: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Returns browser memory usage.
+    #[tool(
+        description = "Returns browser resource usage: process count and resident memory of the browser processes started by this server, JavaScript heap size, DOM node and document counts, plus warnings for any exceeded memory thresholds."
+    )]
+    async fn get_browser_stats(&self) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::GET_BROWSER_STATS) {
+            return disabled_tool_error(tool_names::GET_BROWSER_STATS);
+        }
+        self.touch();
+        info!("Getting browser stats");
+        let stats = collect_browser_stats(&self.browser, &self.config).await;
+        for warning in &stats.warnings {
+            warn!("{}", warning);
+        }
+        let response = BrowserStatsResponse {
+            stats,
+            success: true,
+        };
+        let text = serde_json::to_string_pretty(&response)
+            .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+        self.operation_complete();
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    /// Returns the history of recent actions.
+    #[tool(
+        description = "Returns the most recent actions performed in this session, oldest first: tool name, arguments (long text shortened), whether it succeeded, and the resulting page URL. Use it to recall what was already tried."
+    )]
+    async fn get_action_history(
+        &self,
+        Parameters(params): Parameters<ActionHistoryParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::GET_ACTION_HISTORY) {
+            return disabled_tool_error(tool_names::GET_ACTION_HISTORY);
+        }
+        info!("Getting action history");
+        let response = ActionHistoryResponse {
+            actions: self.action_history.recent(params.limit),
+            success: true,
+        };
+        let text = serde_json::to_string_pretty(&response)
+            .unwrap_or_else(|_| r#"{"success":true,"actions":[]}"#.to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    /// Audits the page for accessibility problems with axe-core.
+    #[tool(
+        description = "Runs the axe-core accessibility rules on the page, or on the element matching 'selector', and returns the violations as JSON: rule ID, impact (minor, moderate, serious, critical), description, help URL, and the offending elements with their CSS selectors. Also reports how many rules passed and how many need manual review."
+    )]
+    async fn audit_accessibility(
+        &self,
+        Parameters(params): Parameters<AuditAccessibilityParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self
+            .config
+            .is_tool_disabled(tool_names::AUDIT_ACCESSIBILITY)
+        {
+            return disabled_tool_error(tool_names::AUDIT_ACCESSIBILITY);
+        }
+        self.touch();
+        info!("Auditing accessibility");
+        let audit = async {
+            let source = accessibility::axe_source(self.config.axe_core_path.as_deref()).await?;
+            self.browser
+                .in_tab(
+                    params.tab.as_deref(),
+                    self.browser
+                        .audit_accessibility(source, params.selector.as_deref()),
+                )
+                .await
+        };
+        let result = match audit.await {
+            Ok(audit) => {
+                let response = AccessibilityAuditResponse {
+                    violations: audit.violations,
+                    passes: audit.passes,
+                    incomplete: audit.incomplete,
+                    success: true,
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true,"violations":[]}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => error_to_result(&format!("Failed to audit accessibility: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Checks the links on the page for broken targets.
     #[tool(
-        description = "Opens the web browser. Call this first before any other browser actions."
+        description = "Collects the HTTP(S) links on the page, or inside the element matching 'selector', and sends a HEAD request to each (falling back to GET when HEAD is not supported), a few at a time. Returns each link's status code, final URL after redirects, or error, and how many are broken. 'scope' limits the check to 'internal' (same origin) or 'external' links. Requests carry no browser cookies, links to loopback, link-local and private addresses are refused, and at most 200 links are checked per call."
     )]
-    async fn open_web_browser(&self) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::OPEN_WEB_BROWSER) {
-            return disabled_tool_error(tool_names::OPEN_WEB_BROWSER);
+    async fn check_links(
+        &self,
+        Parameters(params): Parameters<CheckLinksParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::CHECK_LINKS) {
+            return disabled_tool_error(tool_names::CHECK_LINKS);
         }
         self.touch();
-        info!("Opening web browser");
-        let result = self.browser.open().await;
-        let tool_result = match &result {
-            Ok(state) => env_state_to_result(state.clone(), Some("Browser opened successfully")),
-            Err(e) => error_to_result(&format!("Failed to open browser: {}", e)),
+        info!("Checking links (scope: {:?})", params.scope);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.collect_links(params.selector.as_deref()),
+            )
+            .await
+        {
+            Ok(page) => {
+                let (links, found) = link_check::select_links(&page, params.scope);
+                let links = link_check::check(
+                    links,
+                    &self.politeness,
+                    self.config.check_links_allow_private,
+                )
+                .await;
+                let response = CheckLinksResponse {
+                    url: page.url,
+                    found,
+                    checked: links.len(),
+                    broken: links.iter().filter(|link| !link.ok).count(),
+                    links,
+                    success: true,
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true,"links":[]}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => error_to_result(&format!("Failed to collect links: {}", e)),
         };
         self.operation_complete();
-
-        // Start idle monitor after operation is complete (only if browser opened successfully)
-        if result.is_ok() {
-            self.start_idle_monitor().await;
-        }
-
-        tool_result
+        result
     }
 
-    /// Clicks at a specific x, y coordinate on the webpage.
+    /// Compares the current screenshot with a stored baseline.
     #[tool(
-        description = "Clicks at a specific x, y coordinate on the webpage. The coordinates are absolute values scaled to the screen dimensions."
+        description = "Visual regression check: diffs the current screenshot pixel by pixel against the baseline stored under 'baseline_id' and reports the percentage of changed pixels, whether it is within 'threshold' (percent, default 0.1), and the changed regions as boxes. When pixels changed, also returns a diff image with the changes in red. The first call with a new ID stores the current screenshot as the baseline; set 'update_baseline' to replace it after comparing."
     )]
-    async fn click_at(
+    async fn compare_screenshot(
         &self,
-        Parameters(params): Parameters<ClickAtParams>,
+        Parameters(params): Parameters<CompareScreenshotParams>,
     ) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::CLICK_AT) {
-            return disabled_tool_error(tool_names::CLICK_AT);
+        if self.config.is_tool_disabled(tool_names::COMPARE_SCREENSHOT) {
+            return disabled_tool_error(tool_names::COMPARE_SCREENSHOT);
         }
+        let dir = self.config.effective_baselines_dir();
+        let baseline =
+            match visual_diff::load_baseline(&dir, &params.baseline_id, &self.path_policy) {
+                Ok(baseline) => baseline,
+                Err(e) => return error_to_result(&format!("Failed to load baseline: {}", e)),
+            };
+        let threshold = params
+            .threshold
+            .unwrap_or(visual_diff::DEFAULT_THRESHOLD_PERCENT);
         self.touch();
-        info!("Clicking at ({}, {})", params.x, params.y);
-        let result = match self.browser.click_at(params.x, params.y).await {
-            Ok(state) => env_state_to_result(
-                state,
-                Some(&format!("Clicked at ({}, {})", params.x, params.y)),
-            ),
-            Err(e) => error_to_result(&format!("Failed to click: {}", e)),
+        info!(
+            "Comparing screenshot with baseline '{}'",
+            params.baseline_id
+        );
+        let compared = async {
+            let state = self
+                .browser
+                .in_tab(params.tab.as_deref(), self.browser.current_state())
+                .await?;
+            let png = BASE64.decode(&state.screenshot)?;
+            let diff = baseline
+                .as_deref()
+                .map(|baseline| visual_diff::compare(baseline, &png))
+                .transpose()?;
+            if baseline.is_none() || params.update_baseline {
+                visual_diff::save_baseline(&dir, &params.baseline_id, &png, &self.path_policy)?;
+            }
+            Ok::<_, anyhow::Error>((state.url, diff))
+        };
+        let result = match compared.await {
+            Ok((url, diff)) => {
+                let mut response = CompareScreenshotResponse {
+                    baseline_id: params.baseline_id.clone(),
+                    url,
+                    created: diff.is_none(),
+                    matches: true,
+                    changed_pixels: 0,
+                    changed_percent: 0.0,
+                    threshold,
+                    size_changed: false,
+                    regions: Vec::new(),
+                    baseline_updated: diff.is_some() && params.update_baseline,
+                    success: true,
+                };
+                let mut diff_image = None;
+                if let Some(diff) = diff {
+                    response.matches = !diff.size_changed && diff.changed_percent <= threshold;
+                    response.changed_pixels = diff.changed_pixels;
+                    response.changed_percent = diff.changed_percent;
+                    response.size_changed = diff.size_changed;
+                    response.regions = diff.regions;
+                    if diff.changed_pixels > 0 {
+                        diff_image = Some(BASE64.encode(&diff.diff_png));
+                    }
+                }
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                let mut content = vec![Content::text(text)];
+                if let Some(diff_image) = diff_image {
+                    content.push(Content::image(diff_image, "image/png"));
+                }
+                Ok(CallToolResult::success(content))
+            }
+            Err(e) => error_to_result(&format!("Failed to compare screenshot: {}", e)),
         };
         self.operation_complete();
         result
     }
 
-    /// Hovers at a specific x, y coordinate on the webpage.
+    /// Returns a screenshot stored from an earlier response.
     #[tool(
-        description = "Hovers at a specific x, y coordinate on the webpage. May be used to explore sub-menus that appear on hover."
+        description = "Returns a screenshot from an earlier tool response by the 'screenshot_id' that response reported. Only the most recent screenshots of the session are kept; older IDs are no longer available. Stored screenshots are also readable as 'screenshot://<id>' resources."
     )]
-    async fn hover_at(
+    async fn get_screenshot(
         &self,
-        Parameters(params): Parameters<HoverAtParams>,
+        Parameters(params): Parameters<GetScreenshotParams>,
     ) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::HOVER_AT) {
-            return disabled_tool_error(tool_names::HOVER_AT);
+        if self.config.is_tool_disabled(tool_names::GET_SCREENSHOT) {
+            return disabled_tool_error(tool_names::GET_SCREENSHOT);
+        }
+        let Some(ref store) = self.screenshots else {
+            return error_to_result(
+                "Screenshot storage is disabled; set MCP_SCREENSHOT_RETENTION above 0",
+            );
+        };
+        info!("Getting screenshot {}", params.id);
+        match store.get(&params.id).await {
+            Ok(Some((screenshot, png))) => {
+                let response = GetScreenshotResponse {
+                    screenshot,
+                    success: true,
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                Ok(CallToolResult::success(vec![
+                    Content::text(text),
+                    Content::image(BASE64.encode(&png), "image/png"),
+                ]))
+            }
+            Ok(None) => error_to_result(&format!(
+                "No screenshot with ID '{}'; it may have been evicted",
+                params.id
+            )),
+            Err(e) => error_to_result(&format!("Failed to read screenshot: {}", e)),
+        }
+    }
+
+    // ========== Emulation Tools ==========
+
+    /// Switches the CSS media type and media features the page is rendered with.
+    #[tool(
+        description = "Changes how the page's CSS media queries match and returns a screenshot. 'media' renders with the 'print' or 'screen' media type, to check print stylesheets before printing. 'reduced_motion' and 'forced_colors' emulate the prefers-reduced-motion: reduce and forced-colors: active user settings, to check that the page respects them. Omitted settings keep their current value. Applies to the tab until changed, across navigations. Requires a Chromium-based browser."
+    )]
+    async fn set_emulated_media(
+        &self,
+        Parameters(params): Parameters<SetEmulatedMediaParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::SET_EMULATED_MEDIA) {
+            return disabled_tool_error(tool_names::SET_EMULATED_MEDIA);
         }
         self.touch();
-        info!("Hovering at ({}, {})", params.x, params.y);
-        let result = match self.browser.hover_at(params.x, params.y).await {
-            Ok(state) => env_state_to_result(
+        let changes = MediaEmulation {
+            media: params.media,
+            reduced_motion: params.reduced_motion,
+            forced_colors: params.forced_colors,
+        };
+        info!("Emulating media: {:?}", changes);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.set_emulated_media(changes),
+            )
+            .await
+        {
+            Ok((state, settings)) => env_state_to_result(
                 state,
-                Some(&format!("Hovered at ({}, {})", params.x, params.y)),
+                Some(&format!("Rendering with {}", settings.describe())),
             ),
-            Err(e) => error_to_result(&format!("Failed to hover: {}", e)),
+            Err(e) => error_to_result(&format!("Failed to emulate media: {}", e)),
         };
         self.operation_complete();
         result
     }
 
-    /// Types text at a specific x, y coordinate.
+    /// Renders the page as seen with a vision deficiency.
     #[tool(
-        description = "Types text at a specific x, y coordinate. The system can optionally press ENTER after typing and clear existing content before typing."
+        description = "Renders the page as a user with a vision deficiency would see it and returns a screenshot, to check color contrast and color-coded information. 'type' is 'deuteranopia', 'protanopia' or 'tritanopia' (color blindness), 'achromatopsia' (no color vision), 'blurred_vision', 'reduced_contrast', or 'none' to return to normal. Applies to the tab until changed. Requires a Chromium-based browser."
     )]
-    async fn type_text_at(
+    async fn set_vision_deficiency(
         &self,
-        Parameters(params): Parameters<TypeTextAtParams>,
+        Parameters(params): Parameters<SetVisionDeficiencyParams>,
     ) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::TYPE_TEXT_AT) {
-            return disabled_tool_error(tool_names::TYPE_TEXT_AT);
+        if self
+            .config
+            .is_tool_disabled(tool_names::SET_VISION_DEFICIENCY)
+        {
+            return disabled_tool_error(tool_names::SET_VISION_DEFICIENCY);
         }
         self.touch();
-        info!("Typing at ({}, {}): {}", params.x, params.y, params.text);
+        info!("Emulating vision deficiency: {:?}", params.deficiency);
+        let message = match params.deficiency {
+            VisionDeficiency::None => "Rendering with normal vision".to_string(),
+            deficiency => format!("Rendering as seen with {}", deficiency.cdp_value()),
+        };
         let result = match self
             .browser
-            .type_text_at(
-                params.x,
-                params.y,
-                &params.text,
-                params.press_enter,
-                params.clear_before_typing,
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.set_vision_deficiency(params.deficiency),
             )
             .await
         {
-            Ok(state) => env_state_to_result(
-                state,
-                Some(&format!(
-                    "Typed '{}' at ({}, {})",
-                    params.text, params.x, params.y
-                )),
-            ),
-            Err(e) => error_to_result(&format!("Failed to type: {}", e)),
+            Ok(state) => env_state_to_result(state, Some(&message)),
+            Err(e) => error_to_result(&format!("Failed to emulate vision deficiency: {}", e)),
         };
         self.operation_complete();
         result
     }
 
-    /// Scrolls the entire webpage in the specified direction.
+    // ========== Checkpoint Tools ==========
+
+    /// Saves a checkpoint of the current page.
     #[tool(
-        description = "Scrolls the entire webpage 'up', 'down', 'left' or 'right' based on direction."
+        description = "Saves a named checkpoint of the current page: URL, scroll position and form field values (passwords and file inputs excluded), and optionally localStorage/sessionStorage. Use restore_checkpoint to return to it after a misstep."
     )]
-    async fn scroll_document(
+    async fn save_checkpoint(
         &self,
-        Parameters(params): Parameters<ScrollDocumentParams>,
+        Parameters(params): Parameters<SaveCheckpointParams>,
     ) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::SCROLL_DOCUMENT) {
-            return disabled_tool_error(tool_names::SCROLL_DOCUMENT);
+        if self.config.is_tool_disabled(tool_names::SAVE_CHECKPOINT) {
+            return disabled_tool_error(tool_names::SAVE_CHECKPOINT);
         }
         self.touch();
-        info!("Scrolling document: {}", params.direction);
-        let result = match self.browser.scroll_document(&params.direction).await {
-            Ok(state) => env_state_to_result(
-                state,
-                Some(&format!("Scrolled document {}", params.direction)),
-            ),
-            Err(e) => error_to_result(&format!("Failed to scroll: {}", e)),
+        info!("Saving checkpoint '{}'", params.name);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.capture_checkpoint(params.include_storage),
+            )
+            .await
+        {
+            Ok(checkpoint) => {
+                let mut checkpoints = self.checkpoints.lock().await;
+                if checkpoints.len() >= MAX_CHECKPOINTS && !checkpoints.contains_key(&params.name) {
+                    error_to_result(&format!(
+                        "Too many checkpoints (limit {}); reuse an existing name",
+                        MAX_CHECKPOINTS
+                    ))
+                } else {
+                    let response = CheckpointResponse {
+                        checkpoint: checkpoint.summary(&params.name),
+                        success: true,
+                    };
+                    checkpoints.insert(params.name, checkpoint);
+                    let text = serde_json::to_string_pretty(&response)
+                        .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                    Ok(CallToolResult::success(vec![Content::text(text)]))
+                }
+            }
+            Err(e) => error_to_result(&format!("Failed to save checkpoint: {}", e)),
         };
         self.operation_complete();
         result
     }
 
-    /// Scrolls at a specific coordinate in the specified direction.
+    /// Returns to a saved checkpoint.
     #[tool(
-        description = "Scrolls up, down, right, or left at a x, y coordinate by magnitude pixels."
+        description = "Returns to a checkpoint saved with save_checkpoint: loads its URL, restores saved storage, form field values and scroll position. Returns a screenshot of the restored page."
     )]
-    async fn scroll_at(
+    async fn restore_checkpoint(
         &self,
-        Parameters(params): Parameters<ScrollAtParams>,
+        Parameters(params): Parameters<RestoreCheckpointParams>,
     ) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::SCROLL_AT) {
-            return disabled_tool_error(tool_names::SCROLL_AT);
+        if self.config.is_tool_disabled(tool_names::RESTORE_CHECKPOINT) {
+            return disabled_tool_error(tool_names::RESTORE_CHECKPOINT);
         }
+        let Some(checkpoint) = self.checkpoints.lock().await.get(&params.name).cloned() else {
+            return error_to_result(&format!("No checkpoint named '{}'", params.name));
+        };
         self.touch();
-        info!(
-            "Scrolling at ({}, {}) direction: {} magnitude: {}",
-            params.x, params.y, params.direction, params.magnitude
-        );
+        info!("Restoring checkpoint '{}'", params.name);
         let result = match self
             .browser
-            .scroll_at(params.x, params.y, &params.direction, params.magnitude)
+            .in_tab(
+                params.tab.as_deref(),
+                self.browser.restore_checkpoint(&checkpoint),
+            )
             .await
         {
-            Ok(state) => env_state_to_result(
+            Ok((restored, state)) => env_state_to_result(
                 state,
                 Some(&format!(
-                    "Scrolled {} at ({}, {}) by {} pixels",
-                    params.direction, params.x, params.y, params.magnitude
+                    "Restored checkpoint '{}' ({} of {} fields)",
+                    params.name,
+                    restored,
+                    checkpoint.fields.len()
                 )),
             ),
-            Err(e) => error_to_result(&format!("Failed to scroll: {}", e)),
+            Err(e) => error_to_result(&format!("Failed to restore checkpoint: {}", e)),
         };
         self.operation_complete();
         result
     }
 
-    /// Waits for 5 seconds to allow unfinished webpage processes to complete.
-    #[tool(description = "Waits for 5 seconds to allow unfinished webpage processes to complete.")]
-    async fn wait_5_seconds(&self) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::WAIT_5_SECONDS) {
-            return disabled_tool_error(tool_names::WAIT_5_SECONDS);
+    // ========== Workspace Tools ==========
+
+    /// Saves the open tabs and cookies to disk.
+    #[tool(
+        description = "Saves the open tabs (URL, scroll position, form values except passwords, localStorage and sessionStorage) and the browser's cookies to disk under a name, replacing a workspace with the same name. Use load_workspace to resume from it, even after a restart."
+    )]
+    async fn save_workspace(
+        &self,
+        Parameters(params): Parameters<WorkspaceParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::SAVE_WORKSPACE) {
+            return disabled_tool_error(tool_names::SAVE_WORKSPACE);
+        }
+        if let Err(e) = workspace::validate_name(&params.name) {
+            return error_to_result(&e.to_string());
         }
         self.touch();
-        info!("Waiting 5 seconds");
-        let result = match self.browser.wait_5_seconds().await {
-            Ok(state) => env_state_to_result(state, Some("Waited 5 seconds")),
-            Err(e) => error_to_result(&format!("Failed to wait: {}", e)),
+        info!("Saving workspace '{}'", params.name);
+        let dir = self.config.effective_workspaces_dir();
+        let saved = match self.capture_workspace().await {
+            Ok((captured, notes)) => {
+                workspace::save(&dir, &params.name, &captured, &self.path_policy)
+                    .map(|path| (captured.summary(&params.name), path, notes))
+            }
+            Err(e) => Err(e),
+        };
+        let result = match saved {
+            Ok((summary, path, notes)) => {
+                let response = WorkspaceResponse {
+                    workspace: summary,
+                    path: path.display().to_string(),
+                    notes,
+                    success: true,
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => error_to_result(&format!("Failed to save workspace: {}", e)),
         };
         self.operation_complete();
         result
     }
 
-    /// Navigates back to the previous webpage in the browser history.
-    #[tool(description = "Navigates back to the previous webpage in the browser history.")]
-    async fn go_back(&self) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::GO_BACK) {
-            return disabled_tool_error(tool_names::GO_BACK);
+    /// Reopens the tabs of a saved workspace.
+    #[tool(
+        description = "Reopens the tabs saved with save_workspace under a name and restores their cookies, storage, form values and scroll positions. The first saved tab replaces the active tab; the others open as new tabs. Open the browser first. Returns a screenshot of the tab that was active when the workspace was saved."
+    )]
+    async fn load_workspace(
+        &self,
+        Parameters(params): Parameters<WorkspaceParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::LOAD_WORKSPACE) {
+            return disabled_tool_error(tool_names::LOAD_WORKSPACE);
         }
+        let dir = self.config.effective_workspaces_dir();
+        let saved = match workspace::load(&dir, &params.name, &self.path_policy) {
+            Ok(saved) => saved,
+            Err(e) => return error_to_result(&format!("Failed to load workspace: {}", e)),
+        };
         self.touch();
-        info!("Going back");
-        let result = match self.browser.go_back().await {
-            Ok(state) => env_state_to_result(state, Some("Navigated back")),
-            Err(e) => error_to_result(&format!("Failed to go back: {}", e)),
+        info!("Loading workspace '{}'", params.name);
+        let result = match self.restore_workspace(&saved).await {
+            Ok((state, notes)) => {
+                let mut message = format!(
+                    "Loaded workspace '{}' ({} tabs, {} cookies)",
+                    params.name,
+                    saved.tabs.len(),
+                    saved.cookies.len()
+                );
+                for note in notes {
+                    message.push_str(". ");
+                    message.push_str(&note);
+                }
+                env_state_to_result(state, Some(&message))
+            }
+            Err(e) => error_to_result(&format!("Failed to load workspace: {}", e)),
         };
         self.operation_complete();
         result
     }
 
-    /// Navigates forward to the next webpage in the browser history.
-    #[tool(description = "Navigates forward to the next webpage in the browser history.")]
-    async fn go_forward(&self) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::GO_FORWARD) {
-            return disabled_tool_error(tool_names::GO_FORWARD);
+    /// Lists the files in the session's scratch directory.
+    #[tool(
+        description = "Lists the files in this session's scratch directory, where the browser saves downloads (in downloads/). Returns each file's path relative to the directory, size, modification time, and whether it is a download still in progress. Downloads are quarantined: executables, content types the operator does not allow and files over the size limit are deleted and reported as removed; the rest are made non-executable. The directory and its files are deleted when the session ends."
+    )]
+    async fn list_workspace_files(&self) -> Result<CallToolResult, McpError> {
+        if self
+            .config
+            .is_tool_disabled(tool_names::LIST_WORKSPACE_FILES)
+        {
+            return disabled_tool_error(tool_names::LIST_WORKSPACE_FILES);
         }
         self.touch();
-        info!("Going forward");
-        let result = match self.browser.go_forward().await {
-            Ok(state) => env_state_to_result(state, Some("Navigated forward")),
-            Err(e) => error_to_result(&format!("Failed to go forward: {}", e)),
+        info!("Listing workspace files");
+        let listed = self
+            .scratch
+            .sweep(&self.path_policy, &self.download_policy)
+            .and_then(|removed| Ok((self.scratch.list()?, removed)));
+        let result = match listed {
+            Ok((files, removed)) => {
+                let response = WorkspaceFilesResponse {
+                    directory: self.scratch.dir().display().to_string(),
+                    files,
+                    removed,
+                    success: true,
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => error_to_result(&format!("Failed to list workspace files: {}", e)),
         };
         self.operation_complete();
         result
     }
 
-    /// Directly jumps to a search engine home page.
+    // ========== Credential Tools ==========
+
+    /// Signs in with credentials saved by the operator.
     #[tool(
-        description = "Directly jumps to a search engine home page. Used when you need to start with a search."
+        description = "Signs in to a site with credentials the operator saved for it, by site name. Fills the username and password fields of the current page and submits the form; opens the site's sign-in page first when the page is on another site. The credentials are never shown to you. For sign-ins that ask for the username and password on separate pages, call again on the password page. Returns a screenshot of the resulting page."
     )]
-    async fn search(&self) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::SEARCH) {
-            return disabled_tool_error(tool_names::SEARCH);
+    async fn login_with_saved_credentials(
+        &self,
+        Parameters(params): Parameters<LoginParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self
+            .config
+            .is_tool_disabled(tool_names::LOGIN_WITH_SAVED_CREDENTIALS)
+        {
+            return disabled_tool_error(tool_names::LOGIN_WITH_SAVED_CREDENTIALS);
+        }
+        self.touch();
+        info!("Signing in with saved credentials for '{}'", params.site);
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.fill_saved_credentials(&params.site, params.submit),
+            )
+            .await
+        {
+            Ok((fill, _)) if !fill.allowed => error_to_result(&format!(
+                "The page on '{}' is not a secure page on one of the domains saved for '{}'",
+                fill.host, params.site
+            )),
+            Ok((fill, _)) if fill.refused.is_some() => error_to_result(&format!(
+                "Saved credentials for '{}' were not filled: {}",
+                params.site,
+                fill.refused.unwrap_or_default()
+            )),
+            Ok((fill, _)) if !fill.username && !fill.password => {
+                error_to_result(&format!("No sign-in fields found on {}", fill.host))
+            }
+            Ok((fill, state)) => {
+                let filled = match (fill.username, fill.password) {
+                    (true, true) => "username and password",
+                    (true, false) => "username",
+                    _ => "password",
+                };
+                let message = if fill.submitted {
+                    format!(
+                        "Filled {} for '{}' and submitted the form",
+                        filled, params.site
+                    )
+                } else {
+                    format!("Filled {} for '{}'", filled, params.site)
+                };
+                env_state_to_result(state, Some(&message))
+            }
+            Err(e) => error_to_result(&format!("Failed to sign in: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Completes a sign-in on an identity provider.
+    #[tool(
+        description = "Completes a sign-in on a Google, Microsoft or Okta page in one call: picks the account, fills the username and password saved by the operator for the given site, accepts 'stay signed in' and the app's consent screen, and stops once the browser returns to the app. Call it after clicking 'Sign in with ...'. Stops early on pages it cannot handle, such as a second factor or CAPTCHA, and reports the steps taken. The credentials are never shown to you. Returns a screenshot of the final page."
+    )]
+    async fn complete_oauth_login(
+        &self,
+        Parameters(params): Parameters<OAuthLoginParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self
+            .config
+            .is_tool_disabled(tool_names::COMPLETE_OAUTH_LOGIN)
+        {
+            return disabled_tool_error(tool_names::COMPLETE_OAUTH_LOGIN);
         }
         self.touch();
-        info!("Navigating to search engine");
-        let result = match self.browser.search().await {
-            Ok(state) => env_state_to_result(state, Some("Navigated to search engine")),
-            Err(e) => error_to_result(&format!("Failed to navigate to search: {}", e)),
+        info!(
+            "Completing sign-in with saved credentials for '{}'",
+            params.site
+        );
+        let result = match self
+            .browser
+            .in_tab(
+                params.tab.as_deref(),
+                self.complete_oauth(&params.site, params.approve_consent),
+            )
+            .await
+        {
+            Ok((message, state)) => env_state_to_result(state, Some(&message)),
+            Err(e) => error_to_result(&format!("Failed to complete sign-in: {}", e)),
         };
         self.operation_complete();
         result
     }
 
-    /// Navigates directly to a specified URL.
+    // ========== Browser Instance Tools ==========
+
+    /// Creates a separate browser instance.
     #[tool(
-        description = "Navigates directly to a specified URL. URLs without a protocol will be prefixed with 'https://'."
+        description = "Launches a separate browser with a fresh profile (no cookies or logins) under the given browser_id, e.g. to compare a signed-in and a signed-out view of a site. Pass the same browser_id to any other tool to act on this browser; tools without browser_id act on the default browser. Returns a screenshot of the new browser."
     )]
-    async fn navigate(
+    async fn create_browser(
         &self,
-        Parameters(params): Parameters<NavigateParams>,
+        Parameters(params): Parameters<BrowserIdParams>,
     ) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::NAVIGATE) {
-            return disabled_tool_error(tool_names::NAVIGATE);
+        if self.config.is_tool_disabled(tool_names::CREATE_BROWSER) {
+            return disabled_tool_error(tool_names::CREATE_BROWSER);
         }
         self.touch();
-        info!("Navigating to: {}", params.url);
-        let result = match self.browser.navigate(&params.url).await {
-            Ok(state) => env_state_to_result(state, Some(&format!("Navigated to {}", params.url))),
-            Err(e) => error_to_result(&format!("Failed to navigate: {}", e)),
+        info!("Creating browser '{}'", params.browser_id);
+        let result = match self.create_named_browser(&params.browser_id).await {
+            Ok(state) => env_state_to_result(
+                state,
+                Some(&format!("Created browser '{}'", params.browser_id)),
+            ),
+            Err(e) => error_to_result(&format!("Failed to create browser: {}", e)),
         };
         self.operation_complete();
         result
     }
 
-    /// Presses keyboard keys and combinations.
+    /// Closes a browser created with create_browser.
+    #[tool(description = "Closes a browser created with create_browser and deletes its profile.")]
+    async fn destroy_browser(
+        &self,
+        Parameters(params): Parameters<BrowserIdParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::DESTROY_BROWSER) {
+            return disabled_tool_error(tool_names::DESTROY_BROWSER);
+        }
+        let Some(named) = self.browsers.lock().await.remove(&params.browser_id) else {
+            return error_to_result(&format!("No browser with ID '{}'", params.browser_id));
+        };
+        self.touch();
+        info!("Destroying browser '{}'", params.browser_id);
+        self.watch_tasks.lock().await.retain(|id, watch| {
+            let in_browser = Arc::ptr_eq(&watch.browser, &named.browser);
+            if in_browser {
+                watch.task.abort();
+                self.watches.remove(id);
+            }
+            !in_browser
+        });
+        let result = match named.close().await {
+            Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Destroyed browser '{}'",
+                params.browser_id
+            ))])),
+            Err(e) => error_to_result(&format!("Failed to close browser: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    // ========== Page Watch Tools ==========
+
+    /// Watches a page for changes in the background.
     #[tool(
-        description = "Presses keyboard keys and combinations, such as ['Control', 'c'] or ['Enter']. Supports modifiers like Control, Shift, Alt, Meta/Command."
+        description = "Opens a URL in a background tab and reloads it every 'interval' seconds (at least 10), watching the text of the page, or of the element matching 'selector', for changes. The active tab stays active. Each watch is an MCP resource (watch://<id>) holding its status and latest text; subscribe to it to be notified when the text changes instead of polling. Stop watching with unwatch_page."
     )]
-    async fn key_combination(
+    async fn watch_page(
+        &self,
+        Parameters(params): Parameters<WatchPageParams>,
+        peer: Peer<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::WATCH_PAGE) {
+            return disabled_tool_error(tool_names::WATCH_PAGE);
+        }
+        if params.interval < page_watch::MIN_INTERVAL_SECS {
+            return error_to_result(&format!(
+                "interval must be at least {} seconds",
+                page_watch::MIN_INTERVAL_SECS
+            ));
+        }
+        if self.watches.is_full() {
+            return error_to_result(&format!(
+                "At most {} pages can be watched; stop one with unwatch_page first",
+                page_watch::MAX_WATCHES
+            ));
+        }
+        self.touch();
+        info!("Watching {} every {}s", params.url, params.interval);
+        let result = match self.start_watch(&params, peer.clone()).await {
+            Ok(watch) => {
+                if let Err(e) = peer.notify_resource_list_changed().await {
+                    debug!("Failed to notify about new watch: {}", e);
+                }
+                let response = WatchResponse {
+                    watch,
+                    success: true,
+                };
+                let text = serde_json::to_string_pretty(&response)
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => error_to_result(&format!("Failed to watch page: {}", e)),
+        };
+        self.operation_complete();
+        result
+    }
+
+    /// Stops watching a page.
+    #[tool(description = "Stops a watch started with watch_page and closes its background tab.")]
+    async fn unwatch_page(
         &self,
-        Parameters(params): Parameters<KeyCombinationParams>,
+        Parameters(params): Parameters<UnwatchPageParams>,
+        peer: Peer<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::KEY_COMBINATION) {
-            return disabled_tool_error(tool_names::KEY_COMBINATION);
+        if self.config.is_tool_disabled(tool_names::UNWATCH_PAGE) {
+            return disabled_tool_error(tool_names::UNWATCH_PAGE);
         }
+        let Some(watch) = self.watch_tasks.lock().await.remove(&params.id) else {
+            return error_to_result(&format!("No watch with ID '{}'", params.id));
+        };
+        self.watches.remove(&params.id);
         self.touch();
-        info!("Pressing key combination: {:?}", params.keys);
-        let result = match self.browser.key_combination(params.keys.clone()).await {
-            Ok(state) => {
-                env_state_to_result(state, Some(&format!("Pressed keys: {:?}", params.keys)))
-            }
-            Err(e) => error_to_result(&format!("Failed to press keys: {}", e)),
+        info!("Stopping watch {}", params.id);
+        if let Err(e) = peer.notify_resource_list_changed().await {
+            debug!("Failed to notify about removed watch: {}", e);
+        }
+        let result = match watch.stop().await {
+            Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Stopped watch {}",
+                params.id
+            ))])),
+            Err(e) => error_to_result(&format!(
+                "Stopped watch {} but failed to close its tab: {}",
+                params.id, e
+            )),
         };
         self.operation_complete();
         result
     }
 
-    /// Drag and drop an element from one position to another.
+    // ========== Event Tools ==========
+
+    /// Collects page events in the background.
     #[tool(
-        description = "Drag and drop an element from a x, y coordinate to a destination_x, destination_y coordinate."
+        description = "Collects page events of the given types in the background: 'navigation' (a tab navigated), 'dialog' (an alert, confirm or prompt opened), 'download' (a download started), 'console' (console messages) and 'target' (tabs opened or closed). Replaces the previous subscription; an empty list stops collecting. Take the collected events with poll_events, or subscribe to the resource events://page to be notified when events arrive. Requires CDP mode."
     )]
-    async fn drag_and_drop(
+    async fn subscribe_events(
         &self,
-        Parameters(params): Parameters<DragAndDropParams>,
+        Parameters(params): Parameters<SubscribeEventsParams>,
+        peer: Peer<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::DRAG_AND_DROP) {
-            return disabled_tool_error(tool_names::DRAG_AND_DROP);
+        if self.config.is_tool_disabled(tool_names::SUBSCRIBE_EVENTS) {
+            return disabled_tool_error(tool_names::SUBSCRIBE_EVENTS);
         }
         self.touch();
-        info!(
-            "Drag and drop from ({}, {}) to ({}, {})",
-            params.x, params.y, params.destination_x, params.destination_y
-        );
-        let result = match self
-            .browser
-            .drag_and_drop(
-                params.x,
-                params.y,
-                params.destination_x,
-                params.destination_y,
-            )
-            .await
-        {
-            Ok(state) => env_state_to_result(
-                state,
-                Some(&format!(
-                    "Dragged from ({}, {}) to ({}, {})",
-                    params.x, params.y, params.destination_x, params.destination_y
-                )),
-            ),
-            Err(e) => error_to_result(&format!("Failed to drag and drop: {}", e)),
-        };
+        info!("Subscribing to events: {:?}", params.types);
+        if !params.types.is_empty() {
+            if let Err(e) = self.browser.watch_events(Arc::clone(&self.events)).await {
+                self.operation_complete();
+                return error_to_result(&format!("Failed to subscribe to events: {}", e));
+            }
+        }
+        let was_active = self.events.is_active();
+        let types = self.events.subscribe(&params.types);
+        let mut notifier = self.event_notifier.lock().await;
+        if types.is_empty() {
+            if let Some(task) = notifier.take() {
+                task.abort();
+            }
+        } else if notifier.is_none() {
+            *notifier = Some(tokio::spawn(run_event_notifier(
+                Arc::clone(&self.events),
+                peer.clone(),
+            )));
+        }
+        drop(notifier);
+        if was_active == types.is_empty() {
+            if let Err(e) = peer.notify_resource_list_changed().await {
+                debug!("Failed to notify about the event resource: {}", e);
+            }
+        }
+        let mut batch = self.events.peek();
+        batch.events.clear();
+        let result = events_to_result(batch);
         self.operation_complete();
         result
     }
 
-    /// Returns the current state of the webpage.
+    /// Returns collected page events.
     #[tool(
-        description = "Returns the current state of the webpage including a screenshot and the current URL."
+        description = "Returns and removes the oldest page events collected since subscribe_events, up to 'limit' (default 100). Also reports how many events were dropped because the buffer of 500 events was full, and how many are still buffered."
     )]
-    async fn current_state(&self) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::CURRENT_STATE) {
-            return disabled_tool_error(tool_names::CURRENT_STATE);
+    async fn poll_events(
+        &self,
+        Parameters(params): Parameters<PollEventsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::POLL_EVENTS) {
+            return disabled_tool_error(tool_names::POLL_EVENTS);
+        }
+        if !self.events.is_active() {
+            return error_to_result("No events are being collected; call subscribe_events first");
         }
         self.touch();
-        info!("Getting current state");
-        let result = match self.browser.current_state().await {
-            Ok(state) => env_state_to_result(state, Some("Current state retrieved")),
-            Err(e) => error_to_result(&format!("Failed to get current state: {}", e)),
-        };
+        let batch = self.events.take(params.limit.unwrap_or(DEFAULT_POLL_LIMIT));
+        debug!("Polled {} events", batch.events.len());
+        let result = events_to_result(batch);
         self.operation_complete();
         result
     }
 
-    // ========== Tab Management Tools ==========
+    // ========== Frame Tools ==========
 
-    /// Creates a new browser tab.
+    /// Lists the frames in the current document.
     #[tool(
-        description = "Creates a new browser tab. Optionally navigates to a URL in the new tab. Returns information about the new tab and a screenshot."
+        description = "Lists the iframes in the current document with their index, name, id, source URL, position and size on the screenshot, and whether they are cross-origin. Also returns a screenshot."
     )]
-    async fn new_tab(
-        &self,
-        Parameters(params): Parameters<NewTabParams>,
-    ) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::NEW_TAB) {
-            return disabled_tool_error(tool_names::NEW_TAB);
+    async fn list_frames(&self) -> Result<CallToolResult, McpError> {
+        if self.config.is_tool_disabled(tool_names::LIST_FRAMES) {
+            return disabled_tool_error(tool_names::LIST_FRAMES);
         }
         self.touch();
-        info!("Creating new tab with URL: {:?}", params.url);
-        let result = match self.browser.new_tab(params.url.as_deref()).await {
-            Ok((tab_info, state)) => {
-                let response = NewTabResponse {
-                    tab: tab_info,
+        info!("Listing frames");
+        let result = match self.browser.list_frames().await {
+            Ok((mut frames, state)) => {
+                for frame in &mut frames {
+                    (frame.x, frame.y) = self.screenshot_point(frame.x, frame.y);
+                    (frame.width, frame.height) = self.screenshot_point(frame.width, frame.height);
+                }
+                let response = FrameListResponse {
+                    frames,
                     success: true,
-                    message: Some("New tab created successfully".to_string()),
+                    message: Some("Frames listed successfully".to_string()),
                 };
                 let text = serde_json::to_string_pretty(&response)
-                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                    .unwrap_or_else(|_| r#"{"success":true,"frames":[]}"#.to_string());
                 let text_content = Content::text(text);
                 let image_content = Content::image(state.screenshot, "image/png");
                 Ok(CallToolResult::success(vec![text_content, image_content]))
             }
-            Err(e) => error_to_result(&format!("Failed to create new tab: {}", e)),
+            Err(e) => error_to_result(&format!("Failed to list frames: {}", e)),
         };
         self.operation_complete();
         result
     }
 
-    /// Closes a browser tab.
-    #[tool(description = "Closes a browser tab. If no handle is provided, closes the current tab.")]
-    async fn close_tab(
+    /// Switches actions into a frame or back to the top-level document.
+    #[tool(
+        description = "Switches subsequent actions into an iframe by its index from list_frames, so that element and script actions run inside it; coordinate clicks and typing reach any frame without it. In CDP mode, cross-origin iframes that run in a separate process cannot be selected; use WebDriver mode for those. Frames are indexed relative to the currently selected document, so nested frames are entered one level at a time. Omit 'index' to return to the top-level document. Coordinates keep referring to the screenshot. Navigation and tab changes return to the top-level document."
+    )]
+    async fn switch_frame(
         &self,
-        Parameters(params): Parameters<CloseTabParams>,
+        Parameters(params): Parameters<SwitchFrameParams>,
     ) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::CLOSE_TAB) {
-            return disabled_tool_error(tool_names::CLOSE_TAB);
+        if self.config.is_tool_disabled(tool_names::SWITCH_FRAME) {
+            return disabled_tool_error(tool_names::SWITCH_FRAME);
         }
         self.touch();
-        info!("Closing tab: {:?}", params.handle);
-        let result = match self.browser.close_tab(params.handle.as_deref()).await {
-            Ok(state) => env_state_to_result(state, Some("Tab closed successfully")),
-            Err(e) => error_to_result(&format!("Failed to close tab: {}", e)),
+        info!("Switching to frame: {:?}", params.index);
+        let message = match params.index {
+            Some(index) => format!("Switched to frame {}", index),
+            None => "Switched to top-level document".to_string(),
+        };
+        let result = match self.browser.switch_frame(params.index).await {
+            Ok(state) => env_state_to_result(state, Some(&message)),
+            Err(e) => error_to_result(&format!("Failed to switch frame: {}", e)),
         };
         self.operation_complete();
         result
     }
+}
 
-    /// Switches to a different browser tab.
+/// Tools built with the `ocr` feature, merged into the router by `new`.
+#[cfg(feature = "ocr")]
+#[tool_router(router = ocr_tool_router)]
+impl BrowserMcpServer {
+    /// Recognizes text in a screenshot of the page.
     #[tool(
-        description = "Switches to a different browser tab by handle or index. Provide exactly one of 'handle' (window handle string) or 'index' (0-based tab index)."
+        description = "Recognizes the text in a screenshot of the page with OCR and returns each line of text with its bounding box (x, y, width, height in screenshot coordinates, usable with click_at) and confidence. Use it on pages drawn in a canvas or WebGL, such as dashboards, games and PDF viewers, where dom_snapshot finds no text. 'languages' takes Tesseract codes, e.g. ['eng', 'jpn']."
     )]
-    async fn switch_tab(
+    async fn ocr_screenshot(
         &self,
-        Parameters(params): Parameters<SwitchTabParams>,
+        Parameters(params): Parameters<OcrScreenshotParams>,
     ) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::SWITCH_TAB) {
-            return disabled_tool_error(tool_names::SWITCH_TAB);
+        if self.config.is_tool_disabled(tool_names::OCR_SCREENSHOT) {
+            return disabled_tool_error(tool_names::OCR_SCREENSHOT);
         }
+        let languages = params
+            .languages
+            .filter(|languages| !languages.is_empty())
+            .unwrap_or_else(|| self.config.ocr_languages.clone());
         self.touch();
-        info!(
-            "Switching to tab: handle={:?}, index={:?}",
-            params.handle, params.index
-        );
-        let result = match self
-            .browser
-            .switch_tab(params.handle.as_deref(), params.index)
-            .await
-        {
-            Ok(state) => env_state_to_result(state, Some("Switched to tab")),
-            Err(e) => error_to_result(&format!("Failed to switch tab: {}", e)),
+        info!("Recognizing text in {}", languages.join("+"));
+        let recognized = async {
+            let state = self
+                .browser
+                .in_tab(params.tab.as_deref(), self.browser.current_state())
+                .await?;
+            let png = BASE64.decode(&state.screenshot)?;
+            let lines = ocr::recognize(
+                &self.config.tesseract_path,
+                &png,
+                &languages,
+                params.min_confidence.unwrap_or(DEFAULT_OCR_CONFIDENCE),
+            )
+            .await?;
+            Ok::<_, anyhow::Error>((state.url, lines))
         };
-        self.operation_complete();
-        result
-    }
-
-    /// Lists all open browser tabs.
-    #[tool(
-        description = "Lists all open browser tabs with their handles, URLs, titles, and active status. Also returns a screenshot of the current tab."
-    )]
-    async fn list_tabs(&self) -> Result<CallToolResult, McpError> {
-        if self.config.is_tool_disabled(tool_names::LIST_TABS) {
-            return disabled_tool_error(tool_names::LIST_TABS);
-        }
-        self.touch();
-        info!("Listing all tabs");
-        let result = match self.browser.list_tabs().await {
-            Ok((tabs, state)) => {
-                let response = TabListResponse {
-                    tabs,
+        let result = match recognized.await {
+            Ok((url, lines)) => {
+                let response = OcrScreenshotResponse {
+                    url,
+                    languages,
+                    lines,
                     success: true,
-                    message: Some("Tabs listed successfully".to_string()),
                 };
                 let text = serde_json::to_string_pretty(&response)
-                    .unwrap_or_else(|_| r#"{"success":true,"tabs":[]}"#.to_string());
-                let text_content = Content::text(text);
-                let image_content = Content::image(state.screenshot, "image/png");
-                Ok(CallToolResult::success(vec![text_content, image_content]))
+                    .unwrap_or_else(|_| r#"{"success":true}"#.to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
             }
-            Err(e) => error_to_result(&format!("Failed to list tabs: {}", e)),
+            Err(e) => error_to_result(&format!("Failed to recognize text: {}", e)),
         };
         self.operation_complete();
         result
     }
 }
 
-#[tool_handler]
+/// Record metrics for a completed tool call.
+fn record_tool_metrics(tool: &str, result: &Result<CallToolResult, McpError>, duration: Duration) {
+    let success = matches!(result, Ok(r) if r.is_error != Some(true));
+    metrics::record_tool_call(tool, success, duration);
+
+    if let Ok(result) = result {
+        for content in &result.content {
+            if let RawContent::Image(image) = &content.raw {
+                // Decoded size of the base64 payload
+                metrics::record_screenshot_bytes((image.data.len() as u64 / 4) * 3);
+            }
+        }
+    }
+}
+
+/// Whether a tool manages browser instances rather than acting in one.
+fn is_browser_instance_tool(tool: &str) -> bool {
+    tool == tool_names::CREATE_BROWSER || tool == tool_names::DESTROY_BROWSER
+}
+
+/// Whether a tool exists to return a screenshot, so the screenshot policy
+/// does not apply to it.
+fn is_screenshot_tool(tool: &str) -> bool {
+    matches!(
+        tool,
+        tool_names::CURRENT_STATE
+            | tool_names::GET_SCREENSHOT
+            | tool_names::COMPARE_SCREENSHOT
+            | tool_names::GROUNDING_SNAPSHOT
+    )
+}
+
+/// Add the optional `include_screenshot` argument of the `on_demand`
+/// screenshot policy, handled by `call_tool`, to a tool's input schema.
+fn add_include_screenshot_param(schema: &mut rmcp::model::JsonObject) {
+    let properties = schema
+        .entry("properties")
+        .or_insert_with(|| serde_json::json!({}));
+    if let Some(properties) = properties.as_object_mut() {
+        properties.insert(
+            "include_screenshot".to_string(),
+            serde_json::json!({
+                "type": "boolean",
+                "description": "Attach a screenshot of the page to the response. Defaults to false; current_state always returns one."
+            }),
+        );
+    }
+}
+
+/// The JSON object a tool result carries as its first content, if any.
+fn response_json(result: &CallToolResult) -> Option<serde_json::Value> {
+    result
+        .content
+        .first()
+        .and_then(|content| match content.raw {
+            RawContent::Text(ref text) => serde_json::from_str::<serde_json::Value>(&text.text)
+                .ok()
+                .filter(|value| value.as_object().is_some_and(|object| !object.is_empty())),
+            _ => None,
+        })
+}
+
+/// Complete the `duration_ms` breakdown of a tool result with the time of the
+/// action itself: whatever part of `run_time` the page did not spend settling
+/// or being captured.
+fn add_action_duration(result: &mut CallToolResult, run_time: Duration) {
+    update_response_json(result, |response| {
+        let Some(mut duration) = response
+            .get("duration_ms")
+            .and_then(|value| serde_json::from_value::<DurationBreakdown>(value.clone()).ok())
+        else {
+            return;
+        };
+        duration.action = Some(
+            (run_time.as_millis() as u64).saturating_sub(duration.settle + duration.screenshot),
+        );
+        if let Ok(value) = serde_json::to_value(duration) {
+            response.insert("duration_ms".to_string(), value);
+        }
+    });
+}
+
+/// Change the JSON object a tool result carries as its first content and
+/// write it back. Fields keep their order, so the text only changes where
+/// `update` changed the object.
+fn update_response_json(
+    result: &mut CallToolResult,
+    update: impl FnOnce(&mut serde_json::Map<String, serde_json::Value>),
+) {
+    let Some(RawContent::Text(ref mut text)) =
+        result.content.first_mut().map(|content| &mut content.raw)
+    else {
+        return;
+    };
+    let Ok(serde_json::Value::Object(mut response)) = serde_json::from_str(&text.text) else {
+        return;
+    };
+    update(&mut response);
+    if let Ok(updated) = serde_json::to_string_pretty(&response) {
+        text.text = updated;
+    }
+}
+
+/// Add a field after the others to the JSON object of a tool result.
+fn append_response_field(result: &mut CallToolResult, name: &str, value: serde_json::Value) {
+    update_response_json(result, |response| {
+        response.insert(name.to_string(), value);
+    });
+}
+
+/// Add the optional `browser_id` argument, handled by `call_tool`, to a tool's
+/// input schema.
+fn add_browser_id_param(schema: &mut rmcp::model::JsonObject) {
+    let properties = schema
+        .entry("properties")
+        .or_insert_with(|| serde_json::json!({}));
+    if let Some(properties) = properties.as_object_mut() {
+        properties.insert(
+            "browser_id".to_string(),
+            serde_json::json!({
+                "type": "string",
+                "description": "Browser to act on, from create_browser. Defaults to the default browser."
+            }),
+        );
+    }
+}
+
+/// MCP session of a request, available in HTTP mode from the `Mcp-Session-Id` header.
+fn session_id(context: &RequestContext<RoleServer>) -> Option<String> {
+    #[cfg(feature = "http-server")]
+    {
+        context
+            .extensions
+            .get::<axum::http::request::Parts>()
+            .and_then(|parts| parts.headers.get("mcp-session-id"))
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    }
+    #[cfg(not(feature = "http-server"))]
+    {
+        let _ = context;
+        None
+    }
+}
+
 impl ServerHandler for BrowserMcpServer {
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        // Only label metrics with known tool names to keep cardinality bounded
+        let tool = self
+            .tool_router
+            .has_route(&request.name)
+            .then(|| request.name.to_string());
+        let started = Instant::now();
+        let request_id = next_request_id();
+        let span = tracing::info_span!(
+            "tool_call",
+            request_id = %request_id,
+            tool = %request.name,
+            session_id = tracing::field::Empty,
+        );
+        let session = session_id(&context);
+        if let Some(ref session_id) = session {
+            span.record("session_id", session_id.as_str());
+        }
+
+        let call = async {
+            // Route the call to the browser named by browser_id; the tools
+            // themselves never see the argument
+            let mut request = request;
+            let browser_id = match request.arguments.as_mut() {
+                Some(args) if !is_browser_instance_tool(&request.name) => args.remove("browser_id"),
+                _ => None,
+            };
+            let server = match browser_id {
+                None => None,
+                Some(serde_json::Value::String(id)) => match self.with_browser(&id).await {
+                    Ok(server) => Some(server),
+                    Err(message) => return error_to_result(&message),
+                },
+                Some(_) => return error_to_result("browser_id must be a string"),
+            };
+            let server = server.as_ref().unwrap_or(self);
+            let include_screenshot = match request.arguments.as_mut() {
+                Some(args)
+                    if self.config.screenshot_policy == ScreenshotPolicy::OnDemand
+                        && !is_screenshot_tool(&request.name) =>
+                {
+                    args.remove("include_screenshot")
+                        .and_then(|include| include.as_bool())
+                        .unwrap_or(false)
+                }
+                _ => false,
+            };
+
+            // Queue wait counts towards the tool duration, as the client experiences it
+            let tab = request
+                .arguments
+                .as_ref()
+                .and_then(|args| args.get("tab"))
+                .and_then(|tab| tab.as_str());
+            let tab_key = tab.map(String::from);
+            let (kind, lane) = server.browser.schedule(&request.name, tab).await;
+            let permit = server.action_queue.admit(kind, &lane).await;
+            let mut blocked = server.recipe_block(&request.name, tab).await;
+            // Waits for the operator with the permit held, so the page they
+            // approve an action on is the page it runs on
+            if blocked.is_none() {
+                blocked = server
+                    .approval_block(
+                        &request.name,
+                        tab,
+                        request.arguments.as_ref(),
+                        session.as_deref(),
+                    )
+                    .await;
+            }
+
+            let arguments = request.arguments.clone();
+            let run_started = Instant::now();
+            let mut result = match blocked {
+                Some(message) => error_to_result(&message),
+                None => {
+                    let tcc = ToolCallContext::new(server, request, context);
+                    server.tool_router.call(tcc).await
+                }
+            };
+            let run_time = run_started.elapsed();
+            // The browser is done with; let the next action in the lane start
+            // while the screenshots are stored and the call is recorded
+            drop(permit);
+
+            let duration = started.elapsed();
+            let success = matches!(result, Ok(ref r) if r.is_error != Some(true));
+            info!(
+                duration_ms = duration.as_millis() as u64,
+                success, "Tool call finished"
+            );
+            if let Err(ref mut error) = result {
+                error.data.get_or_insert_with(
+                    || serde_json::json!({ "request_id": current_request_id() }),
+                );
+            }
+            // Any tool may have started a download
+            if let Err(e) = self
+                .scratch
+                .sweep_if_changed(&self.path_policy, &self.download_policy)
+            {
+                debug!("Failed to check the scratch directory: {}", e);
+            }
+            if let (Some(ref tool), Ok(ref mut result)) = (&tool, &mut result) {
+                if result.is_error != Some(true) && tool != tool_names::GET_SCREENSHOT {
+                    self.store_screenshots(tool, result).await;
+                }
+                add_action_duration(result, run_time);
+                // Dropped screenshots stay in the store for get_screenshot
+                if result.is_error != Some(true) && !is_screenshot_tool(tool) {
+                    server
+                        .apply_screenshot_policy(tab_key.as_deref(), include_screenshot, result)
+                        .await;
+                }
+            }
+            if let Some(tool) = tool {
+                record_tool_metrics(&tool, &result, duration);
+                if let Ok(ref result) = result {
+                    if tool != tool_names::GET_ACTION_HISTORY {
+                        self.action_history
+                            .record(&tool, arguments.as_ref(), result);
+                    }
+                }
+            }
+            result
+        };
+        REQUEST_ID.scope(request_id, call.instrument(span)).await
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        let mut tools = self.tool_router.list_all();
+        if !self.config.is_tool_disabled(tool_names::CREATE_BROWSER) {
+            for tool in &mut tools {
+                if !is_browser_instance_tool(&tool.name) {
+                    add_browser_id_param(Arc::make_mut(&mut tool.input_schema));
+                }
+            }
+        }
+        if self.config.screenshot_policy == ScreenshotPolicy::OnDemand {
+            for tool in &mut tools {
+                if !is_screenshot_tool(&tool.name) {
+                    add_include_screenshot_param(Arc::make_mut(&mut tool.input_schema));
+                }
+            }
+        }
+        Ok(ListToolsResult::with_all_items(tools))
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let mut resources: Vec<_> = self
+            .watches
+            .list()
+            .into_iter()
+            .map(|watch| {
+                let mut resource = RawResource::new(watch.uri, format!("watch-{}", watch.id));
+                resource.title = Some(format!("Watch of {}", watch.url));
+                resource.description = Some(format!(
+                    "Status and latest text of {}, reloaded every {} seconds",
+                    watch.url, watch.interval_secs
+                ));
+                resource.mime_type = Some("application/json".to_string());
+                resource.no_annotation()
+            })
+            .collect();
+        if self.events.is_active() {
+            let mut resource = RawResource::new(page_events::URI, "page-events");
+            resource.title = Some("Page events".to_string());
+            resource.description =
+                Some("Page events collected since subscribe_events and not yet polled".to_string());
+            resource.mime_type = Some("application/json".to_string());
+            resources.push(resource.no_annotation());
+        }
+        let screenshots = self.screenshots.as_ref().map(|store| store.list());
+        for screenshot in screenshots.unwrap_or_default() {
+            let mut resource =
+                RawResource::new(screenshot.uri, format!("screenshot-{}", screenshot.id));
+            resource.title = Some(format!("Screenshot {}", screenshot.id));
+            resource.description = Some(match screenshot.url {
+                Some(url) => format!("Screenshot of {} from {}", url, screenshot.tool),
+                None => format!("Screenshot from {}", screenshot.tool),
+            });
+            resource.mime_type = Some("image/png".to_string());
+            resource.size = u32::try_from(screenshot.bytes).ok();
+            resources.push(resource.no_annotation());
+        }
+        Ok(ListResourcesResult::with_all_items(resources))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        if let Some(id) = request.uri.strip_prefix(screenshot_store::URI_PREFIX) {
+            let stored = match self.screenshots {
+                Some(ref store) => store
+                    .get(id)
+                    .await
+                    .map_err(|e| McpError::internal_error(e.to_string(), None))?,
+                None => None,
+            };
+            let (_, png) = stored.ok_or_else(|| {
+                McpError::resource_not_found(format!("No resource {}", request.uri), None)
+            })?;
+            return Ok(ReadResourceResult {
+                contents: vec![ResourceContents::BlobResourceContents {
+                    uri: request.uri,
+                    mime_type: Some("image/png".to_string()),
+                    blob: BASE64.encode(&png),
+                    meta: None,
+                }],
+            });
+        }
+        if request.uri == page_events::URI && self.events.is_active() {
+            let text = serde_json::to_string_pretty(&self.events.peek())
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            return Ok(ReadResourceResult {
+                contents: vec![ResourceContents::TextResourceContents {
+                    uri: request.uri,
+                    mime_type: Some("application/json".to_string()),
+                    text,
+                    meta: None,
+                }],
+            });
+        }
+        let watch = request
+            .uri
+            .strip_prefix(page_watch::URI_PREFIX)
+            .and_then(|id| self.watches.get(id))
+            .ok_or_else(|| {
+                McpError::resource_not_found(format!("No resource {}", request.uri), None)
+            })?;
+        let text = serde_json::to_string_pretty(&watch)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri: request.uri,
+                mime_type: Some("application/json".to_string()),
+                text,
+                meta: None,
+            }],
+        })
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        if request.uri == page_events::URI {
+            self.events.set_resource_subscribed(true);
+            Ok(())
+        } else if self.watches.subscribe(&request.uri) {
+            Ok(())
+        } else {
+            Err(McpError::resource_not_found(
+                format!("No resource {}", request.uri),
+                None,
+            ))
+        }
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        if request.uri == page_events::URI {
+            self.events.set_resource_subscribed(false);
+        }
+        self.watches.unsubscribe(&request.uri);
+        Ok(())
+    }
+
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             instructions: Some(
@@ -1105,7 +6977,12 @@ impl ServerHandler for BrowserMcpServer {
                 Call 'open_web_browser' first to start the browser, then use other tools to interact with web pages."
                     .to_string(),
             ),
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_resources_list_changed()
+                .build(),
             server_info: Implementation {
                 name: "mcp-computer-use".to_string(),
                 version: env!("CARGO_PKG_VERSION").to_string(),
@@ -1117,3 +6994,185 @@ impl ServerHandler for BrowserMcpServer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_backend;
+
+    fn server(config: Config) -> (BrowserMcpServer, Arc<MockBackend>) {
+        let mock = Arc::new(MockBackend::new());
+        (BrowserMcpServer::with_mock(config, Arc::clone(&mock)), mock)
+    }
+
+    fn response(result: &CallToolResult) -> serde_json::Value {
+        let text = result.content[0].as_text().expect("No text content");
+        serde_json::from_str(&text.text).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_disabled_tool() {
+        let config = Config {
+            disabled_tools: [tool_names::CLICK_AT.to_string()].into(),
+            ..Config::default()
+        };
+        let (server, mock) = server(config);
+        let click = ClickAtParams {
+            x: 10,
+            y: 20,
+            tab: None,
+        };
+        let error = server.click_at(Parameters(click)).await.unwrap_err();
+        assert!(error.message.contains("disabled via MCP_DISABLED_TOOLS"));
+        assert!(mock.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_action_responses() {
+        let (server, mock) = server(Config::default());
+        let click = || {
+            Parameters(ClickAtParams {
+                x: 10,
+                y: 20,
+                tab: None,
+            })
+        };
+
+        let mut state = mock_backend::state("https://a.test/");
+        state.effect_detected = Some(false);
+        state.timings = crate::browser::StateTimings {
+            settle_ms: 300,
+            screenshot_ms: 50,
+        };
+        state.login_required = true;
+        mock.script("click_at", Ok(state));
+        let mut result = server.click_at(click()).await.unwrap();
+        assert_ne!(result.is_error, Some(true));
+        add_action_duration(&mut result, Duration::from_millis(500));
+        let json = response(&result);
+        assert_eq!(
+            json["duration_ms"],
+            serde_json::json!({ "action": 150, "settle": 300, "screenshot": 50 })
+        );
+        assert_eq!(json["url"], "https://a.test/");
+        assert_eq!(
+            json["message"],
+            "Clicked at (10, 20), but nothing on the page changed"
+        );
+        assert_eq!(json["effect_detected"], false);
+        assert_eq!(json["login_required"], true);
+        assert!(result.content[1].as_image().is_some());
+
+        // Backend errors become error results with a code the model can act on
+        mock.script::<EnvState>(
+            "click_at",
+            Err(anyhow::anyhow!(
+                "{}; the tab was reloaded",
+                PAGE_CRASHED_MESSAGE
+            )),
+        );
+        let result = server.click_at(click()).await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+        let json = response(&result);
+        assert_eq!(json["success"], false);
+        assert_eq!(json["error_code"], PAGE_CRASHED_CODE);
+        assert!(json["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Failed to click: Page crashed"));
+        assert_eq!(mock.calls(), vec!["click_at", "click_at"]);
+    }
+
+    #[tokio::test]
+    async fn test_screenshot_policy() {
+        let config = Config {
+            screenshot_policy: ScreenshotPolicy::OnChange,
+            ..Config::default()
+        };
+        let (on_change, mock) = server(config);
+        let click = || {
+            Parameters(ClickAtParams {
+                x: 10,
+                y: 20,
+                tab: None,
+            })
+        };
+
+        for _ in 0..2 {
+            mock.script("click_at", Ok(mock_backend::state("https://a.test/")));
+        }
+        let mut first = on_change.click_at(click()).await.unwrap();
+        on_change
+            .apply_screenshot_policy(None, false, &mut first)
+            .await;
+        assert!(first.content[1].as_image().is_some());
+        let mut second = on_change.click_at(click()).await.unwrap();
+        on_change
+            .apply_screenshot_policy(None, false, &mut second)
+            .await;
+        assert_eq!(second.content.len(), 1);
+        assert_eq!(response(&second)["unchanged"], true);
+
+        let config = Config {
+            screenshot_policy: ScreenshotPolicy::OnDemand,
+            ..Config::default()
+        };
+        let (on_demand, mock) = server(config);
+        for _ in 0..2 {
+            mock.script("click_at", Ok(mock_backend::state("https://a.test/")));
+        }
+        let mut omitted = on_demand.click_at(click()).await.unwrap();
+        on_demand
+            .apply_screenshot_policy(None, false, &mut omitted)
+            .await;
+        assert_eq!(omitted.content.len(), 1);
+        let mut included = on_demand.click_at(click()).await.unwrap();
+        on_demand
+            .apply_screenshot_policy(None, true, &mut included)
+            .await;
+        assert!(included.content[1].as_image().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_dom_snapshot_scaled() {
+        let config = Config {
+            screen_width: 1920,
+            screen_height: 1080,
+            max_screenshot_dimension: Some(960),
+            ..Config::default()
+        };
+        let (server, mock) = server(config);
+        let node = |bounds, children| SnapshotNode {
+            tag: "div".to_string(),
+            bounds,
+            text: None,
+            attributes: Default::default(),
+            clickable: false,
+            in_viewport: true,
+            children,
+        };
+        mock.script(
+            "dom_snapshot",
+            Ok(DomSnapshot {
+                url: "https://a.test/".to_string(),
+                nodes: vec![node(
+                    [100, 50, 40, 20],
+                    vec![node([200, 100, 10, 10], vec![])],
+                )],
+                node_count: 2,
+                truncated: false,
+            }),
+        );
+        let result = server
+            .dom_snapshot(Parameters(TabParams { tab: None }))
+            .await
+            .unwrap();
+        let json = response(&result);
+        let outer = &json["snapshot"]["nodes"][0];
+        assert_eq!(outer["bounds"], serde_json::json!([50, 25, 20, 10]));
+        assert_eq!(
+            outer["children"][0]["bounds"],
+            serde_json::json!([100, 50, 5, 5])
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Virtual display management for headful browsers on display-less Linux hosts.
+//!
+//! Some sites behave differently in headless browsers. Running the browser
+//! headful on a server or in CI needs an X display, so when enabled the server
+//! starts an Xvfb display of the configured screen size and starts the
+//! browsers and drivers it launches on it.
+
+use crate::config::Config;
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// First display number tried, above those a desktop session normally uses.
+const FIRST_DISPLAY: u32 = 99;
+
+/// Number of display numbers tried before giving up.
+const DISPLAY_ATTEMPTS: u32 = 100;
+
+/// Maximum time to wait for Xvfb to accept connections.
+const XVFB_READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between readiness checks.
+const XVFB_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Whether a virtual display should be started: it is enabled, the browser runs
+/// headful, and the host is Linux without a display of its own.
+pub fn is_needed(config: &Config) -> bool {
+    let has_display = ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .any(|var| std::env::var(var).is_ok_and(|value| !value.is_empty()));
+    config.virtual_display && !config.headless && cfg!(target_os = "linux") && !has_display
+}
+
+/// An Xvfb server owned by this process, stopped when dropped.
+pub struct VirtualDisplay {
+    process: Child,
+    display: String,
+}
+
+impl VirtualDisplay {
+    /// Start Xvfb on the first free display number with a screen of the
+    /// configured size, and wait until it accepts connections.
+    pub fn start(config: &Config) -> Result<Self> {
+        let xvfb = which::which("Xvfb").context(
+            "MCP_VIRTUAL_DISPLAY is enabled but Xvfb was not found in PATH. \
+             Install it (e.g., the xvfb package) or run the browser headless.",
+        )?;
+        let number = first_free_display(display_in_use)
+            .ok_or_else(|| anyhow::anyhow!("No free X display number for Xvfb"))?;
+        let name = format!(":{}", number);
+
+        info!(
+            "Starting Xvfb on display {} ({}x{})",
+            name, config.screen_width, config.screen_height
+        );
+        let process = Command::new(&xvfb)
+            .arg(&name)
+            .arg("-screen")
+            .arg("0")
+            .arg(format!(
+                "{}x{}x24",
+                config.screen_width, config.screen_height
+            ))
+            .arg("-nolisten")
+            .arg("tcp")
+            .stdout(Stdio::null())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Failed to start Xvfb from {:?}", xvfb))?;
+
+        let mut virtual_display = Self {
+            process,
+            display: name,
+        };
+        virtual_display.wait_until_ready(number)?;
+        info!("Xvfb ready on display {}", virtual_display.display);
+        Ok(virtual_display)
+    }
+
+    /// The X display name, for the `DISPLAY` environment variable.
+    pub fn display(&self) -> &str {
+        &self.display
+    }
+
+    /// Wait for the display socket to appear, failing if Xvfb exits first.
+    fn wait_until_ready(&mut self, number: u32) -> Result<()> {
+        let socket = format!("/tmp/.X11-unix/X{}", number);
+        let deadline = Instant::now() + XVFB_READY_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Some(status) = self.process.try_wait()? {
+                return Err(anyhow::anyhow!(
+                    "Xvfb exited during startup with {}",
+                    status
+                ));
+            }
+            if Path::new(&socket).exists() {
+                return Ok(());
+            }
+            std::thread::sleep(XVFB_POLL_INTERVAL);
+        }
+        Err(anyhow::anyhow!(
+            "Xvfb did not become ready within {} seconds",
+            XVFB_READY_TIMEOUT.as_secs()
+        ))
+    }
+
+    /// Stop the Xvfb server.
+    pub fn stop(&mut self) {
+        if let Ok(Some(_)) = self.process.try_wait() {
+            return;
+        }
+        debug!("Stopping Xvfb on display {}", self.display);
+        if let Err(e) = self.process.kill() {
+            warn!("Failed to stop Xvfb: {}", e);
+        }
+        let _ = self.process.wait();
+    }
+}
+
+impl Drop for VirtualDisplay {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Whether an X server already holds the display number.
+fn display_in_use(number: u32) -> bool {
+    Path::new(&format!("/tmp/.X{}-lock", number)).exists()
+        || Path::new(&format!("/tmp/.X11-unix/X{}", number)).exists()
+}
+
+/// The first display number from [`FIRST_DISPLAY`] that is not in use.
+fn first_free_display(in_use: impl Fn(u32) -> bool) -> Option<u32> {
+    (FIRST_DISPLAY..FIRST_DISPLAY + DISPLAY_ATTEMPTS).find(|&number| !in_use(number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_free_display() {
+        assert_eq!(first_free_display(|_| false), Some(FIRST_DISPLAY));
+        assert_eq!(
+            first_free_display(|n| n < FIRST_DISPLAY + 2),
+            Some(FIRST_DISPLAY + 2)
+        );
+        assert_eq!(first_free_display(|_| true), None);
+    }
+}
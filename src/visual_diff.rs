@@ -0,0 +1,306 @@
+//! Visual regression checks against stored baselines.
+//!
+//! `compare_screenshot` diffs the current screenshot pixel by pixel against a
+//! baseline PNG stored under an ID in `MCP_BASELINES_DIR`. The first comparison
+//! under an ID records the baseline. A pixel counts as changed when any color
+//! channel differs by more than a small tolerance, so anti-aliasing noise does
+//! not fail a check. Changed pixels are grouped into boxes on a coarse grid,
+//! and a diff image shows them in red over a faded copy of the current
+//! screenshot.
+
+use crate::path_policy::PathPolicy;
+use anyhow::Context;
+use image::{ImageFormat, Rgba, RgbaImage};
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// Default share of pixels, in percent, allowed to change before a
+/// comparison fails.
+pub const DEFAULT_THRESHOLD_PERCENT: f64 = 0.1;
+
+/// Largest per-channel difference (0-255) of pixels that count as unchanged.
+const PIXEL_TOLERANCE: u8 = 16;
+
+/// Side of the grid cells changed pixels are grouped by, in pixels.
+const REGION_CELL: u32 = 16;
+
+/// Maximum number of changed regions reported, largest first.
+const MAX_REGIONS: usize = 20;
+
+/// Maximum length of a baseline ID.
+const MAX_ID_LEN: usize = 64;
+
+/// A changed rectangle, in screenshot pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    fn area(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+}
+
+/// Result of comparing a screenshot with its baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VisualDiff {
+    /// Number of changed pixels, including pixels outside the smaller image
+    /// when the sizes differ.
+    pub changed_pixels: u64,
+    /// Changed pixels as a percentage of all pixels.
+    pub changed_percent: f64,
+    /// Whether the screenshot and the baseline differ in size.
+    pub size_changed: bool,
+    /// Changed regions, largest first.
+    pub regions: Vec<Region>,
+    /// PNG highlighting the changed pixels.
+    pub diff_png: Vec<u8>,
+}
+
+/// Compare two encoded images.
+pub fn compare(baseline: &[u8], current: &[u8]) -> anyhow::Result<VisualDiff> {
+    let baseline = image::load_from_memory(baseline)
+        .context("Invalid baseline image")?
+        .to_rgba8();
+    let current = image::load_from_memory(current)
+        .context("Invalid screenshot")?
+        .to_rgba8();
+
+    let width = baseline.width().max(current.width());
+    let height = baseline.height().max(current.height());
+    let cols = width.div_ceil(REGION_CELL);
+    let rows = height.div_ceil(REGION_CELL);
+    let mut cells = vec![false; (cols * rows) as usize];
+    let mut diff = RgbaImage::new(width, height);
+    let mut changed_pixels = 0u64;
+
+    for y in 0..height {
+        for x in 0..width {
+            let before = baseline.get_pixel_checked(x, y);
+            let after = current.get_pixel_checked(x, y);
+            let changed = match (before, after) {
+                (Some(before), Some(after)) => pixel_changed(before, after),
+                _ => true,
+            };
+            let pixel = if changed {
+                changed_pixels += 1;
+                cells[((y / REGION_CELL) * cols + x / REGION_CELL) as usize] = true;
+                Rgba([255, 0, 0, 255])
+            } else {
+                faded(
+                    after
+                        .or(before)
+                        .copied()
+                        .unwrap_or(Rgba([255, 255, 255, 255])),
+                )
+            };
+            diff.put_pixel(x, y, pixel);
+        }
+    }
+
+    let mut diff_png = Vec::new();
+    diff.write_to(&mut Cursor::new(&mut diff_png), ImageFormat::Png)?;
+
+    let total = width as u64 * height as u64;
+    Ok(VisualDiff {
+        changed_pixels,
+        changed_percent: if total == 0 {
+            0.0
+        } else {
+            changed_pixels as f64 * 100.0 / total as f64
+        },
+        size_changed: baseline.dimensions() != current.dimensions(),
+        regions: regions(&cells, cols, rows, width, height),
+        diff_png,
+    })
+}
+
+fn pixel_changed(a: &Rgba<u8>, b: &Rgba<u8>) -> bool {
+    a.0.iter()
+        .zip(b.0.iter())
+        .any(|(a, b)| a.abs_diff(*b) > PIXEL_TOLERANCE)
+}
+
+/// A pixel washed out towards white, as background of the diff image.
+fn faded(pixel: Rgba<u8>) -> Rgba<u8> {
+    let [r, g, b, _] = pixel.0;
+    let gray = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000;
+    let washed = (255 - (255 - gray) / 4) as u8;
+    Rgba([washed, washed, washed, 255])
+}
+
+/// Bounding boxes of groups of adjacent changed cells, largest first.
+fn regions(cells: &[bool], cols: u32, rows: u32, width: u32, height: u32) -> Vec<Region> {
+    let mut seen = vec![false; cells.len()];
+    let mut regions = Vec::new();
+    for start in 0..cells.len() {
+        if !cells[start] || seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let (mut min_col, mut min_row, mut max_col, mut max_row) = (cols, rows, 0, 0);
+        while let Some(index) = stack.pop() {
+            let (col, row) = (index as u32 % cols, index as u32 / cols);
+            min_col = min_col.min(col);
+            min_row = min_row.min(row);
+            max_col = max_col.max(col);
+            max_row = max_row.max(row);
+            // Eight neighbours, so diagonal strokes stay one region
+            for dy in -1i64..=1 {
+                for dx in -1i64..=1 {
+                    let (c, r) = (col as i64 + dx, row as i64 + dy);
+                    if c < 0 || r < 0 || c >= cols as i64 || r >= rows as i64 {
+                        continue;
+                    }
+                    let neighbour = (r * cols as i64 + c) as usize;
+                    if cells[neighbour] && !seen[neighbour] {
+                        seen[neighbour] = true;
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+        let x = min_col * REGION_CELL;
+        let y = min_row * REGION_CELL;
+        regions.push(Region {
+            x,
+            y,
+            width: ((max_col + 1) * REGION_CELL).min(width) - x,
+            height: ((max_row + 1) * REGION_CELL).min(height) - y,
+        });
+    }
+    regions.sort_by_key(|region| std::cmp::Reverse(region.area()));
+    regions.truncate(MAX_REGIONS);
+    regions
+}
+
+/// Check a baseline ID, which becomes a file name.
+pub fn validate_id(id: &str) -> anyhow::Result<()> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Invalid baseline ID '{}': use 1-{} letters, digits, '-' or '_'",
+            id,
+            MAX_ID_LEN
+        ));
+    }
+    Ok(())
+}
+
+fn baseline_path(dir: &Path, id: &str) -> anyhow::Result<PathBuf> {
+    validate_id(id)?;
+    Ok(dir.join(format!("{}.png", id)))
+}
+
+/// Read a baseline from `dir`, if one is stored under the ID.
+pub fn load_baseline(dir: &Path, id: &str, policy: &PathPolicy) -> anyhow::Result<Option<Vec<u8>>> {
+    let path = baseline_path(dir, id)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    policy.check_read(&path)?;
+    std::fs::read(&path)
+        .map(Some)
+        .with_context(|| format!("Failed to read {:?}", path))
+}
+
+/// Store a PNG as the baseline under an ID, replacing any previous one.
+pub fn save_baseline(
+    dir: &Path,
+    id: &str,
+    png: &[u8],
+    policy: &PathPolicy,
+) -> anyhow::Result<PathBuf> {
+    let path = baseline_path(dir, id)?;
+    policy.check_write(&path, png.len() as u64)?;
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create baselines directory {:?}", dir))?;
+    std::fs::write(&path, png).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn encode(image: &RgbImage) -> Vec<u8> {
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    fn page(boxes: &[(u32, u32)]) -> RgbImage {
+        RgbImage::from_fn(320, 180, |x, y| {
+            let inside = boxes
+                .iter()
+                .any(|&(bx, by)| (bx..bx + 40).contains(&x) && (by..by + 20).contains(&y));
+            if inside {
+                Rgb([20, 20, 20])
+            } else {
+                Rgb([240, 240, 240])
+            }
+        })
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = encode(&page(&[(40, 40)]));
+        let same = compare(&baseline, &baseline).unwrap();
+        assert_eq!(same.changed_pixels, 0);
+        assert!(same.regions.is_empty());
+
+        // Noise below the tolerance is ignored
+        let mut noisy = page(&[(40, 40)]);
+        noisy
+            .pixels_mut()
+            .for_each(|pixel| pixel[0] = pixel[0].saturating_add(8));
+        let noisy = encode(&noisy);
+        assert_eq!(compare(&baseline, &noisy).unwrap().changed_pixels, 0);
+
+        let moved = encode(&page(&[(40, 40), (200, 120)]));
+        let diff = compare(&baseline, &moved).unwrap();
+        assert_eq!(diff.changed_pixels, 40 * 20);
+        assert!(!diff.size_changed);
+        assert_eq!(
+            diff.regions,
+            vec![Region {
+                x: 192,
+                y: 112,
+                width: 48,
+                height: 32
+            }]
+        );
+        assert!(image::load_from_memory(&diff.diff_png).is_ok());
+
+        let larger = encode(&RgbImage::from_pixel(320, 200, Rgb([240, 240, 240])));
+        assert!(compare(&baseline, &larger).unwrap().size_changed);
+    }
+
+    #[test]
+    fn test_baselines() {
+        let dir = std::env::temp_dir().join(format!("mcp-baseline-test-{}", std::process::id()));
+        let policy = PathPolicy::default();
+        assert_eq!(load_baseline(&dir, "home", &policy).unwrap(), None);
+        save_baseline(&dir, "home", b"png", &policy).unwrap();
+        assert_eq!(
+            load_baseline(&dir, "home", &policy).unwrap().as_deref(),
+            Some(&b"png"[..])
+        );
+        assert!(save_baseline(&dir, "../escape", b"png", &policy).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
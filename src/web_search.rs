@@ -0,0 +1,172 @@
+//! Structured web search.
+//!
+//! `web_search` opens a search engine's results for the query (see
+//! [`crate::search_engine`]) and reads the result list back as structured data,
+//! so the model gets titles, URLs and snippets without driving the search UI.
+//! Engines configured with a home page instead of a URL template get the query
+//! typed into their search box. Result pages are parsed with selectors for
+//! Google, Bing and DuckDuckGo, falling back to headings inside external links
+//! for other engines.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+/// Default number of results returned.
+pub const DEFAULT_NUM_RESULTS: usize = 10;
+
+/// Maximum number of results returned.
+pub const MAX_NUM_RESULTS: usize = 50;
+
+/// Number of times the result page is read while it loads.
+pub const RESULT_ATTEMPTS: u32 = 5;
+
+/// Interval between reads of the result page (in milliseconds).
+pub const RESULT_POLL_INTERVAL_MS: u64 = 1000;
+
+/// One search result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SearchResult {
+    /// Title of the result.
+    pub title: String,
+    /// URL the result links to.
+    pub url: String,
+    /// Text shown under the title, shortened to 300 characters.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub snippet: String,
+}
+
+/// JavaScript expression typing the query into the page's search box and
+/// submitting it. Evaluates to `false` when the page has no search box.
+/// `search_box` is the selector from the site's recipe, if it has one.
+pub fn submit_script(query: &str, search_box: Option<&str>) -> anyhow::Result<String> {
+    Ok(format!(
+        "{}({}, {})",
+        SUBMIT_FUNCTION,
+        serde_json::to_string(query)?,
+        serde_json::to_string(&search_box)?
+    ))
+}
+
+/// JavaScript expression returning up to `limit` results from a result page,
+/// as [`SearchResult`]s.
+pub fn results_script(limit: usize) -> String {
+    format!("{}({})", RESULTS_FUNCTION, limit)
+}
+
+/// Parse the result of [`results_script`].
+pub fn parse_results(value: &serde_json::Value) -> anyhow::Result<Vec<SearchResult>> {
+    if value.is_null() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_value(value.clone())?)
+}
+
+const SUBMIT_FUNCTION: &str = r#"
+(function(query, selector) {
+    var box = (selector && document.querySelector(selector)) || document.querySelector(
+        'textarea[name=q], input[name=q], input[type=search], input[name=p], input[name=query]');
+    if (!box) {
+        return false;
+    }
+    var setter = Object.getOwnPropertyDescriptor(Object.getPrototypeOf(box), 'value').set;
+    box.focus();
+    setter.call(box, query);
+    box.dispatchEvent(new Event('input', { bubbles: true }));
+    var form = box.form;
+    // Submit after returning, so the navigation cannot swallow the result
+    setTimeout(function() {
+        if (form && form.requestSubmit) {
+            form.requestSubmit();
+        } else if (form) {
+            form.submit();
+        } else {
+            ['keydown', 'keypress', 'keyup'].forEach(function(type) {
+                box.dispatchEvent(new KeyboardEvent(type, {
+                    key: 'Enter', code: 'Enter', keyCode: 13, which: 13, bubbles: true
+                }));
+            });
+        }
+    }, 0);
+    return true;
+})"#;
+
+const RESULTS_FUNCTION: &str = r#"
+(function(limit) {
+    var engines = [
+        // Google
+        { result: '#search .MjjYud, #search .g', title: 'h3', link: 'a[href]',
+          snippet: '.VwiC3b, [data-sncf], .IsZvec' },
+        // Bing
+        { result: 'li.b_algo', title: 'h2', link: 'h2 a[href]',
+          snippet: '.b_caption p, .b_lineclamp2, .b_algoSlug' },
+        // DuckDuckGo, including its HTML version
+        { result: 'article[data-testid=result], .result', title: 'h2',
+          link: 'a[data-testid=result-title-a], a.result__a, h2 a[href]',
+          snippet: '[data-result=snippet], .result__snippet' }
+    ];
+    function clean(text) {
+        return (text || '').replace(/\s+/g, ' ').trim();
+    }
+    // Unwrap the engines' click-tracking redirects
+    function target(href) {
+        try {
+            var url = new URL(href, location.href);
+            var wrapped = (url.pathname === '/url' && url.searchParams.get('q')) ||
+                url.searchParams.get('uddg');
+            return wrapped ? new URL(wrapped) : url;
+        } catch (e) {
+            return null;
+        }
+    }
+    var seen = {};
+    var results = [];
+    function add(title, href, snippet) {
+        var url = href && target(href);
+        if (!title || !url || !/^https?:$/.test(url.protocol) || url.hostname === location.hostname) {
+            return;
+        }
+        if (seen[url.href]) {
+            return;
+        }
+        seen[url.href] = true;
+        results.push({ title: title, url: url.href, snippet: clean(snippet).slice(0, 300) });
+    }
+    for (var i = 0; i < engines.length && !results.length; i++) {
+        var engine = engines[i];
+        document.querySelectorAll(engine.result).forEach(function(el) {
+            var title = el.querySelector(engine.title);
+            var link = el.querySelector(engine.link);
+            var snippet = el.querySelector(engine.snippet);
+            add(clean(title && title.textContent), link && link.href, snippet && snippet.textContent);
+        });
+    }
+    if (!results.length) {
+        document.querySelectorAll('a[href] h3, a[href] h2').forEach(function(heading) {
+            var link = heading.closest('a');
+            var title = clean(heading.textContent);
+            var container = link.parentElement && link.parentElement.parentElement;
+            var snippet = container ? clean(container.textContent).replace(title, '') : '';
+            add(title, link.href, snippet);
+        });
+    }
+    return results.slice(0, limit);
+})"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_scripts() {
+        let script = submit_script("rust \"async\" traits", None).unwrap();
+        assert!(script.ends_with(r#"})("rust \"async\" traits", null)"#));
+        assert!(results_script(5).ends_with("})(5)"));
+
+        let value = serde_json::json!([
+            { "title": "The Rust Book", "url": "https://doc.rust-lang.org/book/", "snippet": "" }
+        ]);
+        let results = parse_results(&value).unwrap();
+        assert_eq!(results[0].title, "The Rust Book");
+        assert!(parse_results(&serde_json::Value::Null).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+//! Named workspaces persisted to disk.
+//!
+//! A workspace records the open tabs of the browser (each as a
+//! [`PageCheckpoint`] with its web storage) together with the browser's
+//! cookies, so a long-running task can pick up where it left off after the
+//! server or the machine restarts. Workspaces are JSON files in
+//! `MCP_WORKSPACES_DIR`, one per name.
+//!
+//! Cookies are read and written over CDP and are only saved with Chromium-based
+//! browsers. The files hold session cookies and storage in plain text, so they
+//! are created readable only by their owner.
+
+use crate::checkpoint::PageCheckpoint;
+use crate::path_policy::PathPolicy;
+use anyhow::Context;
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Maximum length of a workspace name.
+const MAX_NAME_LEN: usize = 64;
+
+/// A saved set of tabs and cookies.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Workspace {
+    /// Save time in seconds since the UNIX epoch.
+    pub saved_at: u64,
+    /// Open tabs, in tab order.
+    pub tabs: Vec<PageCheckpoint>,
+    /// Index in `tabs` of the tab that was active.
+    pub active_tab: usize,
+    /// Browser cookies.
+    #[serde(default)]
+    pub cookies: Vec<SavedCookie>,
+}
+
+/// A cookie, with the field names of the CDP `Network.CookieParam` type so it
+/// can be passed back to `setCookies` as is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedCookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    /// Expiry in seconds since the UNIX epoch; absent for session cookies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<f64>,
+    #[serde(default)]
+    pub http_only: bool,
+    #[serde(default)]
+    pub secure: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<String>,
+}
+
+/// Summary of a workspace returned to the model. Cookies and storage are not
+/// echoed back.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WorkspaceSummary {
+    /// Name of the workspace.
+    pub name: String,
+    /// URLs of the saved tabs, in tab order.
+    pub tabs: Vec<String>,
+    /// Index of the tab that was active.
+    pub active_tab: usize,
+    /// Number of saved cookies.
+    pub cookie_count: usize,
+}
+
+impl Workspace {
+    /// Describe the workspace under the given name.
+    pub fn summary(&self, name: &str) -> WorkspaceSummary {
+        WorkspaceSummary {
+            name: name.to_string(),
+            tabs: self.tabs.iter().map(|tab| tab.url.clone()).collect(),
+            active_tab: self.active_tab,
+            cookie_count: self.cookies.len(),
+        }
+    }
+}
+
+/// Read cookies from a CDP `getCookies`/`getAllCookies` result, or from its
+/// `cookies` array. Session cookies lose their placeholder expiry.
+pub fn parse_cookies(value: &serde_json::Value) -> anyhow::Result<Vec<SavedCookie>> {
+    let cookies = value.get("cookies").unwrap_or(value);
+    let raw: Vec<serde_json::Value> = serde_json::from_value(cookies.clone())?;
+    raw.into_iter()
+        .map(|cookie| {
+            let session = cookie.get("session").and_then(|s| s.as_bool());
+            let mut cookie: SavedCookie = serde_json::from_value(cookie)?;
+            if session == Some(true) || cookie.expires.is_some_and(|expires| expires <= 0.0) {
+                cookie.expires = None;
+            }
+            Ok(cookie)
+        })
+        .collect()
+}
+
+/// Check a workspace name, which becomes a file name.
+pub fn validate_name(name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Invalid workspace name '{}': use 1-{} letters, digits, '-' or '_'",
+            name,
+            MAX_NAME_LEN
+        ));
+    }
+    Ok(())
+}
+
+fn workspace_path(dir: &Path, name: &str) -> anyhow::Result<PathBuf> {
+    validate_name(name)?;
+    Ok(dir.join(format!("{}.json", name)))
+}
+
+/// Write a workspace to `dir`, replacing one with the same name.
+pub fn save(
+    dir: &Path,
+    name: &str,
+    workspace: &Workspace,
+    policy: &PathPolicy,
+) -> anyhow::Result<PathBuf> {
+    let path = workspace_path(dir, name)?;
+    let content = serde_json::to_string_pretty(workspace)?;
+    policy.check_write(&path, content.len() as u64)?;
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create workspaces directory {:?}", dir))?;
+    // Write next to the target and rename, so a crash never leaves half a file
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, content).with_context(|| format!("Failed to write {:?}", tmp))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+    }
+    std::fs::rename(&tmp, &path).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(path)
+}
+
+/// Read a workspace from `dir`.
+pub fn load(dir: &Path, name: &str, policy: &PathPolicy) -> anyhow::Result<Workspace> {
+    let path = workspace_path(dir, name)?;
+    if !path.exists() {
+        return Err(anyhow::anyhow!("No workspace named '{}'", name));
+    }
+    policy.check_read(&path)?;
+    let content =
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid workspace file {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cookies() {
+        let result = serde_json::json!({
+            "cookies": [
+                {
+                    "name": "sid", "value": "abc", "domain": ".example.com", "path": "/",
+                    "expires": -1, "size": 6, "httpOnly": true, "secure": true,
+                    "session": true, "sameSite": "Lax", "priority": "Medium"
+                },
+                {
+                    "name": "pref", "value": "dark", "domain": "example.com", "path": "/",
+                    "expires": 1900000000.5, "httpOnly": false, "secure": false, "session": false
+                }
+            ]
+        });
+        let cookies = parse_cookies(&result).unwrap();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].expires, None);
+        assert!(cookies[0].http_only);
+        assert_eq!(cookies[0].same_site.as_deref(), Some("Lax"));
+        assert_eq!(cookies[1].expires, Some(1900000000.5));
+
+        let param = serde_json::to_value(&cookies[0]).unwrap();
+        assert_eq!(param["httpOnly"], true);
+        assert!(param.get("expires").is_none());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("mcp-workspace-test-{}", std::process::id()));
+        let workspace = Workspace {
+            saved_at: 1,
+            tabs: Vec::new(),
+            active_tab: 0,
+            cookies: Vec::new(),
+        };
+        let policy = PathPolicy::default();
+        save(&dir, "research_1", &workspace, &policy).unwrap();
+        assert_eq!(load(&dir, "research_1", &policy).unwrap(), workspace);
+        assert!(load(&dir, "missing", &policy).is_err());
+        assert!(save(&dir, "../escape", &workspace, &policy).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
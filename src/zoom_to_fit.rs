@@ -0,0 +1,57 @@
+//! Zooming wide pages out so their full width fits the viewport.
+//!
+//! Layouts wider than the configured viewport push their right-hand side,
+//! often a toolbar, a cart or a panel of settings, out of every screenshot,
+//! and the agent has no reason to scroll sideways to look for it. With
+//! `MCP_ZOOM_TO_FIT` enabled, the top-level document is zoomed out before
+//! each capture until its content width fits, down to a minimum zoom that
+//! keeps text legible. The zoom is CSS zoom, which lays the page out again,
+//! so screenshot coordinates still land on what they show. A page the agent
+//! zoomed itself with `shortcut` is left alone.
+
+/// Data attribute marking a zoom set by this module, as a dataset key.
+pub const MARKER: &str = "mcpZoomToFit";
+
+/// Smallest zoom that can be configured, in percent.
+pub const MIN_ZOOM_PERCENT: u32 = 25;
+
+/// JavaScript expression zooming the document out so its content fits the
+/// viewport width, never below `min_percent`. Returns the zoom factor in
+/// effect, or `null` when the agent zoomed the page itself. A fitted page is
+/// only zoomed further out when its content grew wider.
+pub(crate) fn fit_script(min_percent: u32) -> String {
+    format!(
+        r#"(function(minZoom, marker) {{
+    var root = document.documentElement;
+    var fitted = root.dataset[marker] !== undefined;
+    if (root.style.zoom && !fitted) {{
+        return null;
+    }}
+    var current = parseFloat(root.style.zoom) || 1;
+    var content = Math.max(root.scrollWidth, document.body ? document.body.scrollWidth : 0);
+    var viewport = root.clientWidth;
+    if (!viewport || content <= viewport + 1 || current <= minZoom) {{
+        return current;
+    }}
+    var zoom = Math.max(minZoom, Math.floor(current * viewport / content * 100) / 100);
+    root.style.zoom = String(zoom);
+    root.dataset[marker] = '';
+    return zoom;
+}})({}, '{}')"#,
+        f64::from(min_percent.clamp(MIN_ZOOM_PERCENT, 100)) / 100.0,
+        MARKER
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_script() {
+        assert!(fit_script(50).ends_with("})(0.5, 'mcpZoomToFit')"));
+        // Out of range minimums are clamped
+        assert!(fit_script(5).contains("})(0.25, "));
+        assert!(fit_script(250).contains("})(1, "));
+    }
+}
@@ -0,0 +1,383 @@
+//! Fixture web site the integration tests drive the browser through.
+//!
+//! Every page places the elements the tests interact with at fixed positions,
+//! so coordinate-based tools can hit them without a screenshot, and reports
+//! what happened on the page in its title, which `get_page_metadata` reads
+//! back.
+
+use axum::extract::Query;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+/// A running fixture site, stopped when dropped.
+pub struct FixtureSite {
+    addr: SocketAddr,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl FixtureSite {
+    /// Serve the fixture site on a free local port.
+    pub async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind the fixture site");
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, router()).await.unwrap();
+        });
+        Self { addr, server }
+    }
+
+    /// URL of a path on the site, e.g. `url("/form")`.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+}
+
+impl Drop for FixtureSite {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+fn router() -> Router {
+    Router::new()
+        .route("/", get(|| page("Fixture", INDEX)))
+        .route("/form", get(|| page("name: ", FORM)))
+        .route("/submitted", get(submitted))
+        .route("/login", get(|| page("Sign in", LOGIN)))
+        .route("/welcome", get(welcome))
+        .route("/frames", get(|| page("frames", FRAMES)))
+        .route("/frames/inner", get(|| page("inner", FRAME_INNER)))
+        .route("/dialog", get(|| page("dialog: closed", DIALOG)))
+        .route("/downloads", get(|| page("downloads", DOWNLOADS)))
+        .route(
+            "/files/report.csv",
+            get(|| attachment("report.csv", REPORT_CSV)),
+        )
+        .route(
+            "/files/install.sh",
+            get(|| attachment("install.sh", INSTALL_SH)),
+        )
+        .route("/scroll", get(|| page("items: 0", INFINITE_SCROLL)))
+        .route("/pointer", get(|| page("pointer", POINTER)))
+        .route("/text", get(|| page("selected: ", TEXT)))
+        .route("/links", get(|| page("links", LINKS)))
+        .route("/notify", get(|| page("notify", NOTIFY)))
+        .route("/media", get(|| page("media", MEDIA)))
+        .route(
+            "/controls",
+            get(|| page("tint: #000000, volume: 4", CONTROLS)),
+        )
+        .route("/menu", get(|| page("menu", MENU)))
+        .route("/search", get(search))
+        .route("/ok", get(|| page("ok", "<p>Fine.</p>")))
+}
+
+/// An HTML page with the fixture's styles.
+async fn page(title: &str, body: &str) -> Html<String> {
+    Html(format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="description" content="Fixture site for integration tests">
+<title>{}</title>
+<style>
+body {{ margin: 0; font: 16px sans-serif; }}
+.at {{ position: absolute; box-sizing: border-box; margin: 0; }}
+</style>
+</head>
+<body>
+{}
+</body>
+</html>"#,
+        title, body
+    ))
+}
+
+/// A file served as a download.
+async fn attachment(name: &str, content: &'static str) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", name),
+            ),
+        ],
+        content,
+    )
+        .into_response()
+}
+
+async fn submitted(Query(query): Query<HashMap<String, String>>) -> Html<String> {
+    let name = query.get("name").map(String::as_str).unwrap_or_default();
+    page(
+        &format!("submitted: {}", html_escape(name)),
+        "<p>Thanks!</p>",
+    )
+    .await
+}
+
+async fn welcome(Query(query): Query<HashMap<String, String>>) -> Response {
+    match (query.get("username"), query.get("password")) {
+        (Some(username), Some(password)) if password == "correct horse" => page(
+            &format!("welcome: {}", html_escape(username)),
+            "<p>Signed in.</p>",
+        )
+        .await
+        .into_response(),
+        _ => (StatusCode::UNAUTHORIZED, "Wrong password").into_response(),
+    }
+}
+
+/// Result page in the markup search engines use, linking to the fixture under
+/// another host name so the links count as external results.
+async fn search(headers: HeaderMap, Query(query): Query<HashMap<String, String>>) -> Html<String> {
+    let q = html_escape(query.get("q").map(String::as_str).unwrap_or_default());
+    let port = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .and_then(|host| host.rsplit(':').next())
+        .unwrap_or("80")
+        .to_string();
+    let results: String = ["form", "links"]
+        .iter()
+        .map(|path| {
+            format!(
+                r#"<div class="g"><a href="http://localhost:{port}/{path}"><h3>{q} {path}</h3></a><span>About the {path} page.</span></div>"#
+            )
+        })
+        .collect();
+    page(&format!("search: {}", q), &results).await
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const INDEX: &str = r#"
+<h1>Fixture</h1>
+<ul>
+<li><a href="/form">Form</a></li>
+<li><a href="/frames">Frames</a></li>
+<li><a href="/dialog">Dialog</a></li>
+<li><a href="/downloads">Downloads</a></li>
+<li><a href="/scroll">Infinite scroll</a></li>
+</ul>
+"#;
+
+/// Name field at (20, 20)-(320, 50), submit button at (20, 80)-(120, 110).
+const FORM: &str = r#"
+<form action="/submitted" method="get">
+<input class="at" id="name" name="name" style="left: 20px; top: 20px; width: 300px; height: 30px">
+<button class="at" type="submit" style="left: 20px; top: 80px; width: 100px; height: 30px">Send</button>
+</form>
+<script>
+document.getElementById('name').addEventListener('input', function(e) {
+    document.title = 'name: ' + e.target.value;
+});
+</script>
+"#;
+
+const LOGIN: &str = r#"
+<form action="/welcome" method="get">
+<label>Username <input name="username" autocomplete="username"></label>
+<label>Password <input name="password" type="password" autocomplete="current-password"></label>
+<button type="submit">Sign in</button>
+</form>
+"#;
+
+/// Iframe at (0, 0)-(400, 300); its button at (20, 20)-(120, 50) of the frame.
+const FRAMES: &str = r#"
+<iframe class="at" name="inner" src="/frames/inner" style="left: 0; top: 0; width: 400px; height: 300px; border: 0"></iframe>
+"#;
+
+const FRAME_INNER: &str = r#"
+<button class="at" style="left: 20px; top: 20px; width: 100px; height: 30px"
+    onclick="parent.document.title = 'frame: clicked'">Inside</button>
+"#;
+
+/// Open button at (20, 20)-(120, 50); the modal dialog's confirm button at
+/// (120, 120)-(220, 150).
+const DIALOG: &str = r#"
+<button class="at" id="open" style="left: 20px; top: 20px; width: 100px; height: 30px">Open</button>
+<dialog id="dialog" class="at" style="left: 100px; top: 100px; width: 300px; height: 120px; padding: 0">
+<form method="dialog">
+<button class="at" value="confirmed" style="left: 20px; top: 20px; width: 100px; height: 30px">Confirm</button>
+<button class="at" value="cancelled" style="left: 140px; top: 20px; width: 100px; height: 30px">Cancel</button>
+</form>
+</dialog>
+<script>
+var dialog = document.getElementById('dialog');
+document.getElementById('open').addEventListener('click', function() {
+    dialog.showModal();
+    document.title = 'dialog: open';
+});
+dialog.addEventListener('close', function() {
+    document.title = 'dialog: ' + dialog.returnValue;
+});
+</script>
+"#;
+
+/// Links to a harmless file at (20, 20)-(220, 50) and a script at
+/// (20, 80)-(220, 110).
+const DOWNLOADS: &str = r#"
+<a class="at" href="/files/report.csv" style="left: 20px; top: 20px; width: 200px; height: 30px; display: block">report.csv</a>
+<a class="at" href="/files/install.sh" style="left: 20px; top: 80px; width: 200px; height: 30px; display: block">install.sh</a>
+"#;
+
+const REPORT_CSV: &str = "region,sales\nnorth,12\nsouth,7\n";
+
+const INSTALL_SH: &str = "#!/bin/sh\necho installed\n";
+
+/// Appends twenty items whenever the page is scrolled near its end.
+const INFINITE_SCROLL: &str = r#"
+<div id="items"></div>
+<script>
+var items = document.getElementById('items');
+function more() {
+    for (var i = 0; i < 20; i++) {
+        var item = document.createElement('p');
+        item.style.height = '80px';
+        item.textContent = 'Item ' + (items.children.length + 1);
+        items.appendChild(item);
+    }
+    document.title = 'items: ' + items.children.length;
+}
+more();
+window.addEventListener('scroll', function() {
+    if (window.scrollY + window.innerHeight > document.body.scrollHeight - 200) {
+        more();
+    }
+});
+</script>
+"#;
+
+/// Hover target at (20, 20)-(220, 70), drag source at (20, 120)-(120, 220),
+/// drop target at (300, 120)-(450, 270), a scrollable panel at
+/// (500, 20)-(800, 220), and a drawing pad at (20, 290)-(220, 370).
+const POINTER: &str = r#"
+<div class="at" id="hover" style="left: 20px; top: 20px; width: 200px; height: 50px; background: #ddd">Hover me</div>
+<div class="at" id="source" style="left: 20px; top: 120px; width: 100px; height: 100px; background: #9cf">Drag me</div>
+<div class="at" id="target" style="left: 300px; top: 120px; width: 150px; height: 150px; background: #fc9">Drop here</div>
+<canvas class="at" id="pad" width="200" height="80" style="left: 20px; top: 290px; background: #eee"></canvas>
+<div class="at" id="panel" style="left: 500px; top: 20px; width: 300px; height: 200px; overflow: auto">
+<div style="height: 2000px">Panel</div>
+</div>
+<script>
+var hover = document.getElementById('hover');
+hover.addEventListener('mouseenter', function() { document.title = 'hover: in'; });
+hover.addEventListener('mouseleave', function() { document.title = 'hover: out'; });
+var dragging = false;
+document.getElementById('source').addEventListener('mousedown', function() { dragging = true; });
+document.addEventListener('mouseup', function(e) {
+    if (dragging && document.getElementById('target').contains(e.target)) {
+        document.title = 'dropped';
+    }
+    dragging = false;
+});
+var moves = null;
+var pad = document.getElementById('pad');
+pad.addEventListener('pointerdown', function() { moves = 0; });
+pad.addEventListener('pointermove', function() { if (moves !== null) { moves++; } });
+pad.addEventListener('pointerup', function() { document.title = 'stroke: ' + moves; moves = null; });
+document.getElementById('panel').addEventListener('scroll', function(e) {
+    document.title = 'panel: ' + Math.round(e.target.scrollTop);
+});
+</script>
+"#;
+
+/// Paragraph at (20, 20)-(620, 50).
+const TEXT: &str = r#"
+<p class="at" id="para" style="left: 20px; top: 20px; width: 600px; height: 30px">The quick brown fox jumps over the lazy dog</p>
+<script>
+document.addEventListener('selectionchange', function() {
+    document.title = 'selected: ' + document.getSelection().toString();
+});
+</script>
+"#;
+
+const LINKS: &str = r#"
+<a href="/ok">Working</a>
+<a href="/missing">Broken</a>
+"#;
+
+const NOTIFY: &str = r#"
+<script>
+new Notification('Order placed', { body: 'Your order is on its way' });
+</script>
+"#;
+
+/// A color input and a slider from 0 to 10 in steps of 2, reporting their
+/// values in the title.
+const CONTROLS: &str = r#"
+<input id="tint" type="color" class="at" style="left: 20px; top: 20px; width: 60px; height: 30px">
+<input id="volume" type="range" min="0" max="10" step="2" value="4" class="at" style="left: 20px; top: 60px; width: 180px; height: 20px">
+<script>
+const report = () => document.title = `tint: ${tint.value}, volume: ${volume.value}`;
+tint.addEventListener('input', report);
+volume.addEventListener('change', report);
+</script>
+"#;
+
+/// A menu bar whose File menu opens on click, with an Export submenu that
+/// opens on hover; its items report the format in the title.
+const MENU: &str = r#"
+<style>
+.menu { display: none; position: absolute; list-style: none; margin: 0; padding: 0; background: #eee; width: 120px }
+.menu li { position: relative; height: 30px }
+#export:hover > .menu { display: block; left: 120px; top: 0 }
+</style>
+<div class="at" style="left: 20px; top: 20px">
+<button id="file">File</button>
+<ul class="menu" id="file-menu">
+<li>New</li>
+<li id="export">Export
+<ul class="menu">
+<li onclick="document.title = 'exported: PDF'">PDF</li>
+<li onclick="document.title = 'exported: PNG'">PNG</li>
+</ul>
+</li>
+</ul>
+</div>
+<script>
+file.addEventListener('click', () => {
+    const menu = document.getElementById('file-menu');
+    menu.style.display = menu.style.display === 'block' ? 'none' : 'block';
+});
+</script>
+"#;
+
+/// A looping, muted track of one second of silence, generated as a WAV file,
+/// and a video of a red canvas.
+const MEDIA: &str = r#"
+<audio id="track" autoplay muted loop></audio>
+<video id="clip" autoplay muted></video>
+<script>
+const canvas = Object.assign(document.createElement('canvas'), { width: 64, height: 48 });
+const context = canvas.getContext('2d');
+context.fillStyle = 'red';
+setInterval(() => context.fillRect(0, 0, 64, 48), 100);
+document.getElementById('clip').srcObject = canvas.captureStream();
+
+const rate = 8000;
+const view = new DataView(new ArrayBuffer(44 + rate));
+const text = (at, s) => [...s].forEach((c, i) => view.setUint8(at + i, c.charCodeAt(0)));
+text(0, 'RIFF'); view.setUint32(4, 36 + rate, true); text(8, 'WAVEfmt ');
+view.setUint32(16, 16, true); view.setUint16(20, 1, true); view.setUint16(22, 1, true);
+view.setUint32(24, rate, true); view.setUint32(28, rate, true);
+view.setUint16(32, 1, true); view.setUint16(34, 8, true);
+text(36, 'data'); view.setUint32(40, rate, true);
+for (let i = 0; i < rate; i++) view.setUint8(44 + i, 128);
+document.getElementById('track').src = URL.createObjectURL(new Blob([view], { type: 'audio/wav' }));
+</script>
+"#;
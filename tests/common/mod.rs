@@ -0,0 +1,191 @@
+//! Harness running the server binary against the fixture site.
+//!
+//! Each [`TestServer`] starts its own server process over stdio with a
+//! headless Chrome in CDP mode, on its own CDP port and with its own
+//! directories, so tests can run in parallel. Chrome is found the way the
+//! server always finds it; set `MCP_BROWSER_PATH` to pick a binary.
+
+pub mod fixture;
+
+use rmcp::model::{CallToolRequestParam, CallToolResult};
+use rmcp::service::RunningService;
+use rmcp::{RoleClient, ServiceExt};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+pub use fixture::FixtureSite;
+
+/// Distinguishes the directories of servers in one test process.
+static NEXT_SERVER: AtomicU64 = AtomicU64::new(0);
+
+/// A server process and the MCP client connected to it.
+pub struct TestServer {
+    /// Taken when the server is stopped.
+    client: Option<RunningService<RoleClient, ()>>,
+    child: tokio::process::Child,
+    dir: PathBuf,
+}
+
+impl TestServer {
+    /// Start a server whose browser opens on `site`'s index page, with extra
+    /// environment variables.
+    pub async fn start(site: &FixtureSite, env: &[(&str, &str)]) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "mcp-integration-{}-{}",
+            std::process::id(),
+            NEXT_SERVER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut command = tokio::process::Command::new(env!("CARGO_BIN_EXE_mcp-computer-use"));
+        command
+            .env("MCP_TRANSPORT", "stdio")
+            .env("MCP_CONNECTION_MODE", "cdp")
+            .env("MCP_AUTO_START", "true")
+            .env("MCP_HEADLESS", "true")
+            .env("MCP_CDP_PORT", free_port().to_string())
+            .env("MCP_INITIAL_URL", site.url("/"))
+            .env("MCP_SEARCH_ENGINE_URL", site.url("/search?q={query}"))
+            .env("MCP_IDLE_TIMEOUT", "0")
+            .env("MCP_MEMORY_CHECK_INTERVAL", "0")
+            .env("MCP_SCRATCH_DIR", dir.join("scratch"))
+            .env("MCP_WORKSPACES_DIR", dir.join("workspaces"))
+            .env("MCP_BASELINES_DIR", dir.join("baselines"))
+            .env("MCP_SCREENSHOT_DIR", dir.join("screenshots"))
+            .envs(env.iter().copied())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true);
+        let mut child = command.spawn().expect("Failed to start the server");
+        let transport = (child.stdout.take().unwrap(), child.stdin.take().unwrap());
+        let client = ().serve(transport).await.expect("Failed to connect to the server");
+        Self {
+            client: Some(client),
+            child,
+            dir,
+        }
+    }
+
+    /// Names of the tools the server offers.
+    pub async fn list_tools(&self) -> Vec<String> {
+        self.client
+            .as_ref()
+            .expect("The server was stopped")
+            .list_all_tools()
+            .await
+            .expect("Listing tools failed")
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect()
+    }
+
+    /// Call a tool and return its result.
+    pub async fn call_raw(&self, tool: &str, arguments: Value) -> CallToolResult {
+        let arguments = match arguments {
+            Value::Object(map) => Some(map),
+            Value::Null => None,
+            other => panic!("Arguments must be an object, not {}", other),
+        };
+        self.client
+            .as_ref()
+            .expect("The server was stopped")
+            .call_tool(CallToolRequestParam {
+                name: tool.to_string().into(),
+                arguments,
+            })
+            .await
+            .unwrap_or_else(|e| panic!("Calling {} failed: {}", tool, e))
+    }
+
+    /// Call a tool and return the JSON response it carries, whether or not it
+    /// succeeded.
+    pub async fn call(&self, tool: &str, arguments: Value) -> Value {
+        response(&self.call_raw(tool, arguments).await)
+    }
+
+    /// Call a tool that must succeed and return its JSON response.
+    pub async fn call_ok(&self, tool: &str, arguments: Value) -> Value {
+        let response = self.call(tool, arguments).await;
+        assert_eq!(
+            response["success"],
+            Value::Bool(true),
+            "{} failed: {}",
+            tool,
+            response
+        );
+        response
+    }
+
+    /// Title of the active page, where the fixture reports what happened.
+    pub async fn title(&self) -> String {
+        let response = self
+            .call_ok("get_page_metadata", serde_json::json!({}))
+            .await;
+        response["metadata"]["title"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// Wait up to ten seconds for the page title to become `expected`.
+    pub async fn wait_for_title(&self, expected: &str) {
+        let mut title = String::new();
+        for _ in 0..20 {
+            title = self.title().await;
+            if title == expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        panic!("Expected the title {:?}, found {:?}", expected, title);
+    }
+
+    /// Stop the server, letting it close its browser.
+    pub async fn stop(mut self) {
+        if let Some(client) = self.client.take() {
+            let _ = client.cancel().await;
+        }
+        if tokio::time::timeout(Duration::from_secs(20), self.child.wait())
+            .await
+            .is_err()
+        {
+            let _ = self.child.kill().await;
+        }
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// The JSON text of a tool result.
+pub fn response(result: &CallToolResult) -> Value {
+    let text = result
+        .content
+        .first()
+        .and_then(|content| content.as_text())
+        .map(|text| text.text.as_str())
+        .unwrap_or_default();
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+/// Whether a tool result carries a screenshot.
+pub fn has_screenshot(result: &CallToolResult) -> bool {
+    result
+        .content
+        .iter()
+        .any(|content| content.as_image().is_some())
+}
+
+/// A local port nothing listens on.
+fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .expect("Failed to find a free port")
+}
@@ -0,0 +1,1059 @@
+//! End-to-end tests of every tool against the fixture site.
+//!
+//! The tests launch headless Chrome and are ignored by default; run them with
+//! `cargo test --test end_to_end -- --ignored`. `all_tools_are_covered` needs
+//! no browser and runs with the rest of the test suite, failing when a tool is
+//! added without a test here.
+
+mod common;
+
+use common::{has_screenshot, FixtureSite, TestServer};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Tools exercised by the tests in this file.
+const COVERED_TOOLS: &[&str] = &[
+    "open_web_browser",
+    "click_at",
+    "hover_at",
+    "move_mouse_away",
+    "type_text_at",
+    "click_element",
+    "hover_element",
+    "type_in_element",
+    "open_menu_path",
+    "scroll_document",
+    "scroll_at",
+    "scroll_to_element",
+    "select_text",
+    "select_all_in",
+    "set_color",
+    "set_range",
+    "highlight_element",
+    "remember_element",
+    "click_named",
+    "wait_5_seconds",
+    "go_back",
+    "go_forward",
+    "search",
+    "navigate",
+    "web_search",
+    "key_combination",
+    "shortcut",
+    "press_keys_sequence",
+    "tab_to_element",
+    "drag_and_drop",
+    "draw_path",
+    "current_state",
+    "new_tab",
+    "close_tab",
+    "switch_tab",
+    "list_tabs",
+    "list_targets",
+    "attach_to_tab",
+    "list_frames",
+    "switch_frame",
+    "dom_snapshot",
+    "grounding_snapshot",
+    "get_page_metadata",
+    "get_notifications",
+    "evaluate_javascript",
+    "get_media",
+    "control_media",
+    "capture_video_frame",
+    "get_browser_stats",
+    "get_action_history",
+    "audit_accessibility",
+    "check_links",
+    "compare_screenshot",
+    "get_screenshot",
+    "login_with_saved_credentials",
+    "complete_oauth_login",
+    "save_checkpoint",
+    "restore_checkpoint",
+    "save_workspace",
+    "load_workspace",
+    "list_workspace_files",
+    "create_browser",
+    "destroy_browser",
+    "set_emulated_media",
+    "set_vision_deficiency",
+    "watch_page",
+    "unwatch_page",
+    "subscribe_events",
+    "poll_events",
+    "ocr_screenshot",
+];
+
+/// Stand-in for axe-core reporting one violation, so audits need no download.
+const FAKE_AXE: &str = r#"window.axe = { run: function() { return Promise.resolve({
+    violations: [{ id: 'image-alt', impact: 'critical', help: 'Images must have alternate text',
+        helpUrl: 'https://example.com/image-alt',
+        nodes: [{ target: ['img'], html: '<img src="x.png">', failureSummary: 'Add an alt attribute' }] }],
+    passes: [{}, {}, {}],
+    incomplete: []
+}); } };"#;
+
+/// Start the fixture site and a server with its browser open.
+async fn open(env: &[(&str, &str)]) -> (FixtureSite, TestServer) {
+    let site = FixtureSite::start().await;
+    let server = TestServer::start(&site, env).await;
+    let result = server.call_raw("open_web_browser", json!({})).await;
+    assert!(
+        has_screenshot(&result),
+        "open_web_browser returned no screenshot"
+    );
+    (site, server)
+}
+
+/// Write a file the server reads, in a directory of this test process.
+fn write_file(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mcp-integration-files-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+fn url_of(response: &Value) -> &str {
+    response["url"].as_str().unwrap_or_default()
+}
+
+#[tokio::test]
+async fn all_tools_are_covered() {
+    let site = FixtureSite::start().await;
+    let server = TestServer::start(&site, &[]).await;
+    let mut missing: Vec<String> = server
+        .list_tools()
+        .await
+        .into_iter()
+        .filter(|tool| !COVERED_TOOLS.contains(&tool.as_str()))
+        .collect();
+    missing.sort();
+    assert!(
+        missing.is_empty(),
+        "Tools without an end-to-end test: {:?}",
+        missing
+    );
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn navigation() {
+    let (site, server) = open(&[]).await;
+
+    let response = server
+        .call_ok("navigate", json!({ "url": site.url("/form") }))
+        .await;
+    assert_eq!(url_of(&response), site.url("/form"));
+    let response = server.call_ok("go_back", json!({})).await;
+    assert_eq!(url_of(&response), site.url("/"));
+    let response = server.call_ok("go_forward", json!({})).await;
+    assert_eq!(url_of(&response), site.url("/form"));
+
+    let result = server.call_raw("current_state", json!({})).await;
+    assert!(has_screenshot(&result));
+    let response = server
+        .call_ok("current_state", json!({ "skip_if_unchanged": true }))
+        .await;
+    assert_eq!(response["unchanged"], json!(true));
+
+    server.call_ok("wait_5_seconds", json!({})).await;
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn form_and_keyboard() {
+    let (site, server) = open(&[]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/form") }))
+        .await;
+
+    server
+        .call_ok("click_at", json!({ "x": 170, "y": 35 }))
+        .await;
+    server
+        .call_ok("type_text_at", json!({ "x": 170, "y": 35, "text": "Ada" }))
+        .await;
+    server.wait_for_title("name: Ada").await;
+
+    server
+        .call_ok("key_combination", json!({ "keys": ["Primary", "a"] }))
+        .await;
+    server
+        .call_ok(
+            "press_keys_sequence",
+            json!({ "steps": [{ "keys": ["Backspace"] }] }),
+        )
+        .await;
+    server.wait_for_title("name: ").await;
+
+    server
+        .call_ok(
+            "type_text_at",
+            json!({ "x": 170, "y": 35, "text": "Grace", "press_enter": true }),
+        )
+        .await;
+    server.wait_for_title("submitted: Grace").await;
+
+    // Keyboard only: focus the field without typing, then submit with Enter
+    server
+        .call_ok("navigate", json!({ "url": site.url("/form") }))
+        .await;
+    let response = server
+        .call_ok("tab_to_element", json!({ "selector": "#name" }))
+        .await;
+    assert_eq!(
+        response["message"],
+        json!("Focused input after 1 Tab press")
+    );
+    let missing = server
+        .call(
+            "tab_to_element",
+            json!({ "text": "Cancel", "max_presses": 5 }),
+        )
+        .await;
+    assert_eq!(missing["success"], false);
+    server
+        .call_ok("tab_to_element", json!({ "text": "send" }))
+        .await;
+    server.wait_for_title("submitted: ").await;
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn selectors() {
+    let (site, server) = open(&[]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/form") }))
+        .await;
+
+    server
+        .call_ok(
+            "type_in_element",
+            json!({ "selector": "#name", "text": "Ada" }),
+        )
+        .await;
+    server.wait_for_title("name: Ada").await;
+    server
+        .call_ok(
+            "hover_element",
+            json!({ "xpath": "//button[normalize-space()='Send']" }),
+        )
+        .await;
+    let missing = server
+        .call("click_element", json!({ "selector": "#missing" }))
+        .await;
+    assert_eq!(missing["success"], false);
+
+    let response = server
+        .call_ok(
+            "click_element",
+            json!({ "xpath": "//button[normalize-space()='Send']" }),
+        )
+        .await;
+    assert!(response["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Clicked button \"Send\""));
+    server.wait_for_title("submitted: Ada").await;
+
+    // Typed values never show up in descriptions of the element
+    server
+        .call_ok("navigate", json!({ "url": site.url("/login") }))
+        .await;
+    server
+        .call_ok(
+            "type_in_element",
+            json!({ "selector": "input[type=password]", "text": "correct horse" }),
+        )
+        .await;
+    let response = server
+        .call_ok(
+            "click_element",
+            json!({ "selector": "input[type=password]" }),
+        )
+        .await;
+    assert!(!response.to_string().contains("correct horse"));
+    server
+        .call_ok(
+            "click_element",
+            json!({ "selector": "input[name=username]" }),
+        )
+        .await;
+    let response = server
+        .call_ok(
+            "tab_to_element",
+            json!({ "selector": "input[type=password]" }),
+        )
+        .await;
+    assert_eq!(
+        response["message"],
+        json!("Focused input after 1 Tab press")
+    );
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn menu_path() {
+    let (site, server) = open(&[]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/menu") }))
+        .await;
+
+    let missing = server
+        .call(
+            "open_menu_path",
+            json!({ "labels": ["File", "Print"], "level_timeout_ms": 300 }),
+        )
+        .await;
+    assert_eq!(missing["success"], false);
+
+    // File opens on click, Export on hover
+    let response = server
+        .call_ok(
+            "open_menu_path",
+            json!({ "labels": ["file", "Export", "PDF"], "level_timeout_ms": 500 }),
+        )
+        .await;
+    assert!(response["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Clicked file > Export > PDF"));
+    server.wait_for_title("exported: PDF").await;
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn color_and_range_inputs() {
+    let (site, server) = open(&[]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/controls") }))
+        .await;
+
+    let response = server
+        .call_ok("set_color", json!({ "selector": "#tint", "color": "#0AF" }))
+        .await;
+    assert_eq!(response["message"], json!("Set color input to #00aaff"));
+    server.wait_for_title("tint: #00aaff, volume: 4").await;
+    // Snapped to the step of 2 and clamped to the maximum
+    let response = server
+        .call_ok("set_range", json!({ "x": 110, "y": 70, "value": 7.2 }))
+        .await;
+    assert_eq!(response["message"], json!("Set slider to 8"));
+    server
+        .call_ok("set_range", json!({ "selector": "#volume", "value": 99 }))
+        .await;
+    server.wait_for_title("tint: #00aaff, volume: 10").await;
+
+    let response = server
+        .call("set_range", json!({ "selector": "#tint", "value": 1 }))
+        .await;
+    assert_eq!(response["success"], false);
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn shortcuts() {
+    let (site, server) = open(&[]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/form") }))
+        .await;
+    server
+        .call_ok("type_text_at", json!({ "x": 170, "y": 35, "text": "Ada" }))
+        .await;
+    server.wait_for_title("name: Ada").await;
+
+    server
+        .call_ok("shortcut", json!({ "name": "select_all" }))
+        .await;
+    server
+        .call_ok("key_combination", json!({ "keys": ["Backspace"] }))
+        .await;
+    server.wait_for_title("name: ").await;
+
+    let zoomed = server
+        .call_ok("shortcut", json!({ "name": "zoom_in" }))
+        .await;
+    assert_eq!(zoomed["message"], "Zoomed page to 110%");
+    let reset = server
+        .call_ok("shortcut", json!({ "name": "zoom_reset" }))
+        .await;
+    assert_eq!(reset["message"], "Zoomed page to 100%");
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn element_aliases() {
+    let (site, server) = open(&[]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/form") }))
+        .await;
+
+    let remembered = server
+        .call_ok(
+            "remember_element",
+            json!({ "name": "send", "x": 70, "y": 95 }),
+        )
+        .await;
+    assert_eq!(remembered["element"]["tag"], "button");
+
+    // The page is rendered again; the alias still finds the button
+    server
+        .call_ok("navigate", json!({ "url": site.url("/form") }))
+        .await;
+    server
+        .call_ok("type_text_at", json!({ "x": 170, "y": 35, "text": "Lin" }))
+        .await;
+    server
+        .call_ok("click_named", json!({ "name": "send" }))
+        .await;
+    server.wait_for_title("submitted: Lin").await;
+
+    let missing = server
+        .call("click_named", json!({ "name": "cancel" }))
+        .await;
+    assert_eq!(missing["success"], false);
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn pointer() {
+    let (site, server) = open(&[]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/pointer") }))
+        .await;
+
+    server
+        .call_ok("hover_at", json!({ "x": 120, "y": 45 }))
+        .await;
+    server.wait_for_title("hover: in").await;
+    server.call_ok("move_mouse_away", json!({})).await;
+    server.wait_for_title("hover: out").await;
+
+    server
+        .call_ok(
+            "drag_and_drop",
+            json!({ "x": 70, "y": 170, "destination_x": 375, "destination_y": 195 }),
+        )
+        .await;
+    server.wait_for_title("dropped").await;
+
+    // A stroke across the pad, turning a corner
+    server
+        .call_ok(
+            "draw_path",
+            json!({ "points": [
+                { "x": 40, "y": 310 }, { "x": 140, "y": 310 }, { "x": 140, "y": 350 }
+            ] }),
+        )
+        .await;
+    let title = server.title().await;
+    let moves: u32 = title
+        .strip_prefix("stroke: ")
+        .and_then(|moves| moves.parse().ok())
+        .unwrap_or_else(|| panic!("No stroke was drawn: {}", title));
+    assert!(moves >= 20, "Only {} moves were drawn", moves);
+
+    server
+        .call_ok(
+            "scroll_at",
+            json!({ "x": 650, "y": 120, "direction": "down", "magnitude": 300 }),
+        )
+        .await;
+    let title = server.title().await;
+    assert!(
+        title.starts_with("panel: ") && title != "panel: 0",
+        "The panel did not scroll: {}",
+        title
+    );
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn text_selection() {
+    let (site, server) = open(&[]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/text") }))
+        .await;
+
+    server
+        .call_ok("select_all_in", json!({ "selector": "#para" }))
+        .await;
+    server
+        .wait_for_title("selected: The quick brown fox jumps over the lazy dog")
+        .await;
+    server
+        .call_ok(
+            "select_text",
+            json!({ "x": 200, "y": 35, "start": 4, "end": 9 }),
+        )
+        .await;
+    server.wait_for_title("selected: quick").await;
+
+    let result = server
+        .call_raw(
+            "highlight_element",
+            json!({ "selector": "#para", "duration_ms": 500 }),
+        )
+        .await;
+    assert!(has_screenshot(&result));
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn frames_and_dialogs() {
+    let (site, server) = open(&[]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/frames") }))
+        .await;
+
+    let response = server.call_ok("list_frames", json!({})).await;
+    assert_eq!(response["frames"][0]["name"], json!("inner"));
+    server.call_ok("switch_frame", json!({ "index": 0 })).await;
+    server
+        .call_ok("click_at", json!({ "x": 70, "y": 35 }))
+        .await;
+    server.call_ok("switch_frame", json!({})).await;
+    server.wait_for_title("frame: clicked").await;
+
+    server
+        .call_ok("navigate", json!({ "url": site.url("/dialog") }))
+        .await;
+    server
+        .call_ok("click_at", json!({ "x": 70, "y": 35 }))
+        .await;
+    server.wait_for_title("dialog: open").await;
+    server
+        .call_ok("click_at", json!({ "x": 170, "y": 135 }))
+        .await;
+    server.wait_for_title("dialog: confirmed").await;
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn infinite_scroll() {
+    let (site, server) = open(&[]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/scroll") }))
+        .await;
+    server.wait_for_title("items: 20").await;
+
+    // At the top of the page only the strip below has content
+    let result = server
+        .call_raw("current_state", json!({ "context_strips": true }))
+        .await;
+    let images = result
+        .content
+        .iter()
+        .filter(|content| content.as_image().is_some())
+        .count();
+    assert_eq!(images, 2);
+    assert!(result.content.iter().any(|content| content
+        .as_text()
+        .is_some_and(|text| text.text == "No content above the viewport.")));
+
+    for _ in 0..3 {
+        server
+            .call_ok(
+                "scroll_document",
+                json!({ "direction": "down", "amount_pages": 3 }),
+            )
+            .await;
+    }
+    let items: usize = server
+        .title()
+        .await
+        .trim_start_matches("items: ")
+        .parse()
+        .unwrap();
+    assert!(items > 20, "No items were loaded while scrolling");
+
+    server
+        .call_ok("scroll_to_element", json!({ "x": 100, "y": 400 }))
+        .await;
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn tabs() {
+    let (site, server) = open(&[]).await;
+
+    server
+        .call_ok("new_tab", json!({ "url": site.url("/form") }))
+        .await;
+    let response = server.call_ok("list_tabs", json!({})).await;
+    let tabs = response["tabs"].as_array().unwrap();
+    assert_eq!(tabs.len(), 2);
+    let form_tab = tabs
+        .iter()
+        .find(|tab| tab["url"] == json!(site.url("/form")))
+        .expect("The new tab is not listed");
+    assert_eq!(form_tab["active"], json!(true));
+
+    let response = server.call_ok("switch_tab", json!({ "index": 0 })).await;
+    assert_eq!(url_of(&response), site.url("/"));
+
+    // Peeking at the background tab leaves the first tab active
+    let response = server
+        .call_ok("current_state", json!({ "tab": form_tab["handle"] }))
+        .await;
+    assert_eq!(url_of(&response), site.url("/form"));
+    assert_eq!(response["title"], "name: ");
+    assert_eq!(response["tab"], form_tab["handle"]);
+    let response = server.call_ok("current_state", json!({})).await;
+    assert_eq!(url_of(&response), site.url("/"));
+
+    let response = server.call_ok("list_targets", json!({})).await;
+    let target = response["targets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|target| target["url"] == json!(site.url("/form")))
+        .expect("The form tab is not a target");
+    let response = server
+        .call_ok("attach_to_tab", json!({ "handle": target["handle"] }))
+        .await;
+    assert_eq!(url_of(&response), site.url("/form"));
+
+    server
+        .call_ok("close_tab", json!({ "handle": form_tab["handle"] }))
+        .await;
+    let response = server.call_ok("list_tabs", json!({})).await;
+    assert_eq!(response["tabs"].as_array().unwrap().len(), 1);
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn downloads() {
+    let (site, server) = open(&[]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/downloads") }))
+        .await;
+
+    server
+        .call_ok("click_at", json!({ "x": 120, "y": 95 }))
+        .await;
+    server
+        .call_ok("click_at", json!({ "x": 120, "y": 35 }))
+        .await;
+    let mut listed = Value::Null;
+    for _ in 0..20 {
+        listed = server.call_ok("list_workspace_files", json!({})).await;
+        for removed in listed["removed"].as_array().into_iter().flatten() {
+            assert_eq!(removed["path"], json!("downloads/install.sh"));
+            assert!(removed["reason"]
+                .as_str()
+                .unwrap_or_default()
+                .contains("executable"));
+        }
+        let files = listed["files"].as_array().unwrap();
+        let finished = files.iter().all(|file| file["partial"] != json!(true));
+        if finished
+            && files
+                .iter()
+                .any(|f| f["path"] == json!("downloads/report.csv"))
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    let files = listed["files"].as_array().unwrap();
+    let report = files
+        .iter()
+        .find(|file| file["path"] == json!("downloads/report.csv"))
+        .expect("report.csv was not downloaded");
+    assert_eq!(report["content_type"], json!("text/plain"));
+    assert!(
+        !files
+            .iter()
+            .any(|file| file["path"] == json!("downloads/install.sh")),
+        "The script was not quarantined"
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let path =
+            PathBuf::from(listed["directory"].as_str().unwrap()).join("downloads/report.csv");
+        let mode = std::fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn search() {
+    let (site, server) = open(&[]).await;
+
+    let response = server
+        .call_ok("search", json!({ "query": "widgets" }))
+        .await;
+    assert_eq!(url_of(&response), site.url("/search?q=widgets"));
+
+    let response = server
+        .call_ok(
+            "web_search",
+            json!({ "query": "gadgets", "num_results": 5 }),
+        )
+        .await;
+    let results = response["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["title"], json!("gadgets form"));
+    assert!(results[0]["url"]
+        .as_str()
+        .unwrap()
+        .starts_with("http://localhost:"));
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn page_inspection() {
+    let axe = write_file("axe.min.js", FAKE_AXE);
+    let (site, server) = open(&[
+        ("MCP_AXE_CORE_PATH", axe.to_str().unwrap()),
+        ("MCP_CHECK_LINKS_ALLOW_PRIVATE", "true"),
+    ])
+    .await;
+
+    let response = server.call_ok("get_page_metadata", json!({})).await;
+    assert_eq!(
+        response["metadata"]["description"],
+        json!("Fixture site for integration tests")
+    );
+    let snapshot = server.call("dom_snapshot", json!({})).await;
+    assert!(snapshot.to_string().contains("Infinite scroll"));
+    let response = server.call_ok("grounding_snapshot", json!({})).await;
+    let elements = response["elements"].as_array().unwrap();
+    assert!(elements
+        .iter()
+        .any(|element| element["role"] == "link" && element["label"] == "Infinite scroll"));
+
+    let response = server.call_ok("audit_accessibility", json!({})).await;
+    assert_eq!(response["violations"][0]["rule"], json!("image-alt"));
+    assert_eq!(response["passes"], json!(3));
+
+    server
+        .call_ok("navigate", json!({ "url": site.url("/links") }))
+        .await;
+    let response = server.call_ok("check_links", json!({})).await;
+    assert_eq!(response["checked"], json!(2));
+    assert_eq!(response["broken"], json!(1));
+    let response = server
+        .call_ok(
+            "evaluate_javascript",
+            json!({ "expression": "Promise.resolve({ links: document.links.length, body: document.body })" }),
+        )
+        .await;
+    assert_eq!(response["result"], json!({ "links": 2, "body": "<body>" }));
+    let response = server
+        .call(
+            "evaluate_javascript",
+            json!({ "expression": "missing.value" }),
+        )
+        .await;
+    assert_eq!(response["success"], false);
+
+    server
+        .call_ok("navigate", json!({ "url": site.url("/notify") }))
+        .await;
+    let response = server
+        .call_ok("get_notifications", json!({ "clear": true }))
+        .await;
+    assert_eq!(response["notifications"][0]["title"], json!("Order placed"));
+    let response = server.call_ok("get_notifications", json!({})).await;
+    assert_eq!(response["notifications"], json!([]));
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn media() {
+    let (site, server) = open(&[]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/media") }))
+        .await;
+
+    let response = server.call_ok("get_media", json!({})).await;
+    assert_eq!(response["browser_muted"], json!(true));
+    assert_eq!(response["media"][0]["kind"], json!("audio"));
+    assert_eq!(response["media"][0]["muted"], json!(true));
+
+    let response = server
+        .call_ok("control_media", json!({ "action": "pause" }))
+        .await;
+    assert_eq!(response["media"][0]["playing"], json!(false));
+    // Muted media may play without a user gesture
+    let response = server
+        .call_ok("control_media", json!({ "action": "play", "index": 0 }))
+        .await;
+    assert_eq!(response["media"][0]["playing"], json!(true));
+    assert_eq!(response["audible"], json!(false));
+
+    let response = server
+        .call("control_media", json!({ "action": "pause", "index": 3 }))
+        .await;
+    assert_eq!(response["success"], false);
+
+    let response = server
+        .call_ok("capture_video_frame", json!({ "selector": "#clip" }))
+        .await;
+    assert_eq!(response["index"], json!(1));
+    assert_eq!(
+        (response["width"].clone(), response["height"].clone()),
+        (json!(64), json!(48))
+    );
+    let response = server
+        .call("capture_video_frame", json!({ "selector": "#track" }))
+        .await;
+    assert_eq!(response["success"], false);
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn fake_media_devices() {
+    let (site, server) = open(&[("MCP_FAKE_MEDIA_DEVICES", "true")]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/ok") }))
+        .await;
+
+    // Granted without a prompt, from the fake camera
+    let response = server
+        .call_ok(
+            "evaluate_javascript",
+            json!({ "expression": "navigator.mediaDevices.getUserMedia({ video: true }).then(s => s.getVideoTracks()[0].readyState)" }),
+        )
+        .await;
+    assert_eq!(response["result"], json!("live"));
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn screenshots_and_emulation() {
+    let (_site, server) = open(&[]).await;
+
+    let response = server.call_ok("current_state", json!({})).await;
+    let id = response["screenshot_id"]
+        .as_str()
+        .expect("The screenshot was not stored")
+        .to_string();
+    let result = server.call_raw("get_screenshot", json!({ "id": id })).await;
+    assert!(has_screenshot(&result));
+
+    let response = server
+        .call_ok("compare_screenshot", json!({ "baseline_id": "index" }))
+        .await;
+    assert_eq!(response["created"], json!(true));
+    let response = server
+        .call_ok("compare_screenshot", json!({ "baseline_id": "index" }))
+        .await;
+    assert_eq!(response["matches"], json!(true));
+
+    server
+        .call_ok(
+            "set_emulated_media",
+            json!({ "media": "print", "reduced_motion": true }),
+        )
+        .await;
+    server
+        .call_ok(
+            "set_vision_deficiency",
+            json!({ "deficiency": "deuteranopia" }),
+        )
+        .await;
+    server
+        .call_ok("set_vision_deficiency", json!({ "deficiency": "none" }))
+        .await;
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn session_state() {
+    let (site, server) = open(&[]).await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/form") }))
+        .await;
+    server
+        .call_ok("type_text_at", json!({ "x": 170, "y": 35, "text": "Ada" }))
+        .await;
+
+    server
+        .call_ok("save_checkpoint", json!({ "name": "filled" }))
+        .await;
+    server
+        .call_ok("save_workspace", json!({ "name": "e2e" }))
+        .await;
+    server
+        .call_ok("navigate", json!({ "url": site.url("/ok") }))
+        .await;
+    let response = server
+        .call_ok("restore_checkpoint", json!({ "name": "filled" }))
+        .await;
+    assert_eq!(url_of(&response), site.url("/form"));
+
+    server
+        .call_ok("navigate", json!({ "url": site.url("/ok") }))
+        .await;
+    server
+        .call_ok("load_workspace", json!({ "name": "e2e" }))
+        .await;
+    let response = server.call_ok("list_tabs", json!({})).await;
+    assert!(response["tabs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|tab| tab["url"] == json!(site.url("/form"))));
+
+    let response = server
+        .call_ok("get_action_history", json!({ "limit": 3 }))
+        .await;
+    let actions = response["actions"].as_array().unwrap();
+    assert_eq!(actions.len(), 3);
+    assert_eq!(actions[2]["tool"], json!("list_tabs"));
+    server.call_ok("get_browser_stats", json!({})).await;
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn saved_credentials() {
+    let site = FixtureSite::start().await;
+    let credentials = write_file(
+        "credentials.toml",
+        &format!(
+            "[sites.fixture]\nurl = \"{}\"\nusername = \"alice\"\npassword = \"correct horse\"\nallow_http = true\n",
+            site.url("/login")
+        ),
+    );
+    let server = TestServer::start(
+        &site,
+        &[("MCP_CREDENTIALS_FILE", credentials.to_str().unwrap())],
+    )
+    .await;
+    server.call_ok("open_web_browser", json!({})).await;
+
+    server
+        .call_ok("login_with_saved_credentials", json!({ "site": "fixture" }))
+        .await;
+    server.wait_for_title("welcome: alice").await;
+
+    let response = server
+        .call("complete_oauth_login", json!({ "site": "fixture" }))
+        .await;
+    assert_eq!(response["success"], json!(false));
+    assert!(response["message"]
+        .as_str()
+        .unwrap_or_default()
+        .contains("not a Google, Microsoft or Okta sign-in page"));
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn browser_instances() {
+    let (site, server) = open(&[]).await;
+
+    server
+        .call_ok("create_browser", json!({ "browser_id": "second" }))
+        .await;
+    let response = server
+        .call_ok(
+            "navigate",
+            json!({ "url": site.url("/form"), "browser_id": "second" }),
+        )
+        .await;
+    assert_eq!(url_of(&response), site.url("/form"));
+    let response = server.call_ok("current_state", json!({})).await;
+    assert_eq!(url_of(&response), site.url("/"));
+
+    server
+        .call_ok("destroy_browser", json!({ "browser_id": "second" }))
+        .await;
+    let response = server
+        .call("current_state", json!({ "browser_id": "second" }))
+        .await;
+    assert_eq!(response["success"], json!(false));
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn page_watches() {
+    let (site, server) = open(&[]).await;
+
+    let response = server
+        .call_ok(
+            "watch_page",
+            json!({ "url": site.url("/ok"), "interval": 10 }),
+        )
+        .await;
+    let id = response["watch"]["id"].as_str().unwrap().to_string();
+    let response = server.call_ok("current_state", json!({})).await;
+    assert_eq!(url_of(&response), site.url("/"));
+    server.call_ok("unwatch_page", json!({ "id": id })).await;
+    server.stop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome"]
+async fn page_events() {
+    let (site, server) = open(&[]).await;
+
+    let response = server
+        .call_ok(
+            "subscribe_events",
+            json!({ "types": ["navigation", "console"] }),
+        )
+        .await;
+    assert_eq!(response["types"], json!(["navigation", "console"]));
+    server
+        .call_ok("navigate", json!({ "url": site.url("/form") }))
+        .await;
+    let response = server.call_ok("poll_events", json!({})).await;
+    let events = response["events"].as_array().unwrap();
+    assert!(events
+        .iter()
+        .any(|event| event["type"] == "navigation" && event["url"] == site.url("/form")));
+    let response = server.call_ok("poll_events", json!({})).await;
+    assert_eq!(response["remaining"], json!(0));
+    server.stop().await;
+}
+
+#[cfg(feature = "ocr")]
+#[tokio::test]
+#[ignore = "needs Chrome and Tesseract"]
+async fn text_recognition() {
+    let (site, server) = open(&[]).await;
+
+    server
+        .call_ok("navigate", json!({ "url": site.url("/form") }))
+        .await;
+    let response = server
+        .call_ok("ocr_screenshot", json!({ "languages": ["eng"] }))
+        .await;
+    assert_eq!(response["languages"], json!(["eng"]));
+    assert!(!response["lines"].as_array().unwrap().is_empty());
+    let response = server
+        .call("ocr_screenshot", json!({ "languages": ["--psm"] }))
+        .await;
+    assert_eq!(response["success"], json!(false));
+    server.stop().await;
+}